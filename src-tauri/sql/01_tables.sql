@@ -65,16 +65,16 @@ CREATE TABLE IF NOT EXISTS audit_logs (
 );
 
 -- AI模型使用统计表
+-- model_id 是 models.json 中的模型 id（不再引用 ai_models 表）
 CREATE TABLE IF NOT EXISTS ai_model_usage_stats (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
-    model_id TEXT NOT NULL,
+    model_id TEXT NOT NULL UNIQUE,
     request_count INTEGER DEFAULT 0,
     total_tokens INTEGER DEFAULT 0,
     total_cost REAL DEFAULT 0.0,
     last_used_at DATETIME,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-    FOREIGN KEY (model_id) REFERENCES ai_models(id) ON DELETE CASCADE
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
 );
 
 -- ===========================
@@ -152,6 +152,8 @@ CREATE TABLE IF NOT EXISTS messages (
     cache_read_tokens INTEGER,
     cache_write_tokens INTEGER,
 
+    cost REAL NOT NULL DEFAULT 0,
+
     created_at INTEGER NOT NULL,
     finished_at INTEGER,
     duration_ms INTEGER
@@ -178,11 +180,34 @@ CREATE TABLE IF NOT EXISTS runs (
     root_node_id INTEGER REFERENCES agent_nodes(id) ON DELETE SET NULL,
     status TEXT NOT NULL DEFAULT 'queued' CHECK (status IN ('queued', 'running', 'completed', 'error', 'cancelled')),
     summary TEXT,
+    total_cost REAL NOT NULL DEFAULT 0,
     created_at INTEGER NOT NULL,
     started_at INTEGER,
     finished_at INTEGER
 );
 
+-- One row per LLM call (agent turns, compaction, subtask summaries, tools, MCP sampling).
+-- Rows outlive the session/run/message they are attributed to so spend reports stay complete.
+CREATE TABLE IF NOT EXISTS llm_usage (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    workspace_path TEXT,
+    session_id INTEGER REFERENCES sessions(id) ON DELETE SET NULL,
+    run_id INTEGER REFERENCES runs(id) ON DELETE SET NULL,
+    message_id INTEGER REFERENCES messages(id) ON DELETE SET NULL,
+
+    model_id TEXT NOT NULL,
+    provider_id TEXT,
+    purpose TEXT NOT NULL,
+
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_write_tokens INTEGER NOT NULL DEFAULT 0,
+    cost REAL NOT NULL DEFAULT 0,
+
+    created_at INTEGER NOT NULL
+);
+
 CREATE TABLE IF NOT EXISTS agent_nodes (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
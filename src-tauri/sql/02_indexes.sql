@@ -30,6 +30,10 @@ CREATE INDEX IF NOT EXISTS idx_messages_session ON messages(session_id);
 CREATE INDEX IF NOT EXISTS idx_messages_session_role ON messages(session_id, role);
 CREATE INDEX IF NOT EXISTS idx_messages_parent ON messages(parent_message_id);
 
+CREATE INDEX IF NOT EXISTS idx_llm_usage_workspace ON llm_usage(workspace_path, created_at);
+CREATE INDEX IF NOT EXISTS idx_llm_usage_model ON llm_usage(model_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_llm_usage_session ON llm_usage(session_id);
+
 CREATE INDEX IF NOT EXISTS idx_tool_executions_message ON tool_executions(message_id);
 CREATE INDEX IF NOT EXISTS idx_tool_executions_session ON tool_executions(session_id);
 CREATE INDEX IF NOT EXISTS idx_tool_executions_tool ON tool_executions(tool_name);
@@ -0,0 +1,283 @@
+//! Expansion of `@path` file references and `` !`cmd` `` shell interpolation in command
+//! templates.
+//!
+//! Only the template is expanded, before the user's arguments are substituted, so nothing the
+//! user typed is ever run or read.
+//!
+//! Both are gated by the settings permission rules: a shell snippet only runs when a `shell`
+//! rule explicitly allows it (there is nobody to confirm an `ask` while a prompt is being
+//! rendered), and a file is only included when `read` isn't denied and the file is inside the
+//! workspace or explicitly allowed.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use regex::Regex;
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+use super::loader::CommandConfigLoader;
+use super::types::{CommandConfig, CommandRenderResult};
+use crate::agent::common::truncate_chars;
+use crate::agent::permissions::{PermissionChecker, PermissionDecision, ToolAction};
+use crate::agent::tools::builtin::file_utils::normalize_path;
+use crate::agent::tools::registry::bash_param_variants;
+
+const SHELL_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_SHELL_OUTPUT_CHARS: usize = 20_000;
+const MAX_FILE_CHARS: usize = 50_000;
+const MAX_INCLUDED_FILES: usize = 20;
+
+static SHELL_SNIPPET: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!`([^`\n]+)`").expect("valid shell snippet regex"));
+
+static FILE_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|\s)@([^\s`]+)").expect("valid file reference regex"));
+
+/// Expand the command's template, then substitute `input` into it verbatim
+pub async fn render_command(
+    cfg: &CommandConfig,
+    input: &str,
+    workspace_root: &Path,
+    permissions: &PermissionChecker,
+) -> CommandRenderResult {
+    let expanded = CommandConfig {
+        template: expand_command_prompt(&cfg.template, workspace_root, permissions).await,
+        ..cfg.clone()
+    };
+    CommandConfigLoader::render(&expanded, input)
+}
+
+/// Run permitted `` !`cmd` `` snippets in place and append the contents of `@path` files.
+pub async fn expand_command_prompt(
+    prompt: &str,
+    workspace_root: &Path,
+    permissions: &PermissionChecker,
+) -> String {
+    let prompt = expand_shell_snippets(prompt, workspace_root, permissions).await;
+    append_file_references(prompt, workspace_root, permissions).await
+}
+
+async fn expand_shell_snippets(
+    prompt: &str,
+    workspace_root: &Path,
+    permissions: &PermissionChecker,
+) -> String {
+    let mut out = String::with_capacity(prompt.len());
+    let mut last = 0;
+
+    for caps in SHELL_SNIPPET.captures_iter(prompt) {
+        let whole = caps.get(0).expect("match");
+        let command = caps[1].trim();
+        out.push_str(&prompt[last..whole.start()]);
+        last = whole.end();
+
+        let action = ToolAction::new(
+            "shell",
+            workspace_root.to_path_buf(),
+            bash_param_variants(command),
+        );
+        let decision = permissions.check(&action);
+        if decision != PermissionDecision::Allow {
+            debug!(
+                "Command shell snippet `{}` not run: {:?}",
+                command, decision
+            );
+            out.push_str(&format!(
+                "[`{command}` was not run: add a shell allow rule to permit it]"
+            ));
+            continue;
+        }
+
+        match run_shell_snippet(command, workspace_root).await {
+            Ok(output) => out.push_str(output.trim_end()),
+            Err(err) => {
+                warn!("Command shell snippet `{}` failed: {}", command, err);
+                out.push_str(&format!("[`{command}` failed: {err}]"));
+            }
+        }
+    }
+
+    out.push_str(&prompt[last..]);
+    out
+}
+
+async fn run_shell_snippet(command: &str, cwd: &Path) -> Result<String, String> {
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+    cmd.current_dir(cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let output = tokio::time::timeout(SHELL_TIMEOUT, cmd.output())
+        .await
+        .map_err(|_| format!("timed out after {}s", SHELL_TIMEOUT.as_secs()))?
+        .map_err(|e| e.to_string())?;
+
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&stderr);
+    }
+    if !output.status.success() {
+        let code = output
+            .status
+            .code()
+            .map_or_else(|| "signal".to_string(), |c| c.to_string());
+        text.push_str(&format!("\n(exit code {code})"));
+    }
+    Ok(truncate_chars(&text, MAX_SHELL_OUTPUT_CHARS))
+}
+
+async fn append_file_references(
+    prompt: String,
+    workspace_root: &Path,
+    permissions: &PermissionChecker,
+) -> String {
+    let mut seen: Vec<PathBuf> = Vec::new();
+    let mut blocks = Vec::new();
+
+    for caps in FILE_REFERENCE.captures_iter(&prompt) {
+        if seen.len() >= MAX_INCLUDED_FILES {
+            break;
+        }
+        let reference = caps[1].trim_end_matches(['.', ',', ';', ':', ')', '!', '?']);
+        let Some(path) = resolve_reference(reference, workspace_root) else {
+            continue;
+        };
+        if seen.contains(&path) || !path.is_file() {
+            continue;
+        }
+
+        let action = ToolAction::new(
+            "read",
+            workspace_root.to_path_buf(),
+            vec![path.to_string_lossy().to_string()],
+        );
+        let readable = match permissions.check(&action) {
+            PermissionDecision::Allow => true,
+            PermissionDecision::Ask => path.starts_with(workspace_root),
+            PermissionDecision::Deny => false,
+        };
+        if !readable {
+            debug!("Command file reference '{}' not included", path.display());
+            continue;
+        }
+
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => {
+                blocks.push(format!(
+                    "<file path=\"{reference}\">\n{}\n</file>",
+                    truncate_chars(content.trim_end(), MAX_FILE_CHARS)
+                ));
+                seen.push(path);
+            }
+            Err(err) => warn!(
+                "Failed to read command file reference '{}': {}",
+                path.display(),
+                err
+            ),
+        }
+    }
+
+    if blocks.is_empty() {
+        prompt
+    } else {
+        format!("{prompt}\n\n{}", blocks.join("\n\n"))
+    }
+}
+
+fn resolve_reference(reference: &str, workspace_root: &Path) -> Option<PathBuf> {
+    if reference.is_empty() {
+        return None;
+    }
+    let path = if let Some(rest) = reference.strip_prefix("~/") {
+        dirs::home_dir()?.join(rest)
+    } else {
+        workspace_root.join(reference)
+    };
+    Some(normalize_path(&path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::types::PermissionRules;
+
+    fn checker(allow: &[&str], deny: &[&str]) -> PermissionChecker {
+        PermissionChecker::new(&PermissionRules {
+            allow: allow.iter().map(|s| s.to_string()).collect(),
+            deny: deny.iter().map(|s| s.to_string()).collect(),
+            ask: Vec::new(),
+        })
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn shell_snippets_require_allow_rule() {
+        let dir = tempfile::tempdir().unwrap();
+        let prompt = "Branch: !`echo main`\nLog: !`echo secret`";
+
+        let expanded =
+            expand_command_prompt(prompt, dir.path(), &checker(&["Bash(echo main)"], &[])).await;
+        assert!(expanded.starts_with("Branch: main\n"));
+        assert!(expanded.contains("[`echo secret` was not run"));
+    }
+
+    #[tokio::test]
+    async fn file_references_respect_workspace_and_deny_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("CHANGELOG.md"), "## 1.2.0").unwrap();
+        std::fs::write(dir.path().join(".env"), "TOKEN=x").unwrap();
+        let prompt = "Summarize @CHANGELOG.md, @.env and @../outside.txt";
+
+        let expanded =
+            expand_command_prompt(prompt, dir.path(), &checker(&[], &["Read(**/.env)"])).await;
+        assert!(expanded.contains("<file path=\"CHANGELOG.md\">\n## 1.2.0\n</file>"));
+        assert!(!expanded.contains("TOKEN=x"));
+        assert!(!expanded.contains("<file path=\"../outside.txt\">"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn arguments_are_never_expanded() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".env"), "TOKEN=x").unwrap();
+        let cfg = CommandConfig {
+            name: "review".to_string(),
+            description: None,
+            agent: None,
+            model: None,
+            subtask: false,
+            template: "On !`echo main`: review $ARGUMENTS".to_string(),
+            source_path: None,
+        };
+        // Files in the workspace are readable without a rule
+        let permissions = checker(&["Bash(echo *)"], &[]);
+
+        let rendered =
+            render_command(&cfg, "!`echo pwned` and @.env", dir.path(), &permissions).await;
+        assert_eq!(rendered.prompt, "On main: review !`echo pwned` and @.env");
+
+        let appended = CommandConfig {
+            template: "Review the diff.".to_string(),
+            ..cfg
+        };
+        let rendered = render_command(&appended, "@.env", dir.path(), &permissions).await;
+        assert_eq!(rendered.prompt, "Review the diff.\n\n@.env");
+    }
+}
@@ -1,7 +1,21 @@
+//! Slash command loader
+//!
+//! Built-in commands are compiled in from prompts/commands/*.md; user commands are loaded from
+//! `~/.opencodex/commands/*.md` and `<workspace>/.opencodex/commands/*.md`. Later sources
+//! override earlier ones with the same name (builtin < global < workspace).
+
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
 
+use regex::{Captures, Regex};
+use tokio::fs;
+use tracing::warn;
+
 use super::types::{CommandConfig, CommandRenderResult, CommandSummary};
+use crate::agent::agents::frontmatter::{parse_frontmatter, split_frontmatter};
+use crate::agent::core::context::TaskExecutionRequest;
+use crate::agent::error::{AgentError, AgentResult};
 
 /// Built-in command templates, compiled into the binary.
 static BUILTIN_COMMANDS: LazyLock<HashMap<String, CommandConfig>> = LazyLock::new(|| {
@@ -42,25 +56,172 @@ static BUILTIN_COMMANDS: LazyLock<HashMap<String, CommandConfig>> = LazyLock::ne
                 model: None,
                 subtask: false,
                 template: template.trim().to_string(),
+                source_path: None,
             },
         );
     }
     m
 });
 
+/// `{{input}}`, `$ARGUMENTS`, a positional `$1` to `$9`, or an escaped `$$`. Other `$<digits>`
+/// such as `$0` or `$100` are matched only to be left alone.
+static ARGUMENT_PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{input\}\}|\$\$|\$(ARGUMENTS|\d+)").expect("valid argument regex")
+});
+
 pub struct CommandConfigLoader;
 
 impl CommandConfigLoader {
-    pub fn get(command_id: &str) -> Option<&'static CommandConfig> {
-        BUILTIN_COMMANDS.get(command_id)
+    /// Global user commands directory (`~/.opencodex/commands`)
+    pub fn global_commands_dir() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".opencodex").join("commands"))
+    }
+
+    /// Load builtin, global and workspace commands. Unreadable or malformed files are
+    /// skipped with a warning so one bad command doesn't hide the rest.
+    pub async fn load_for_workspace(workspace_root: &Path) -> HashMap<String, CommandConfig> {
+        let mut commands = BUILTIN_COMMANDS.clone();
+
+        let dirs = [
+            Self::global_commands_dir(),
+            Some(workspace_root.join(".opencodex").join("commands")),
+        ];
+        for dir in dirs.into_iter().flatten() {
+            for config in Self::load_dir(&dir).await {
+                commands.insert(config.name.clone(), config);
+            }
+        }
+
+        commands
+    }
+
+    async fn load_dir(dir: &Path) -> Vec<CommandConfig> {
+        let mut entries = match fs::read_dir(dir).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+            Err(err) => {
+                warn!(
+                    "Failed to read command directory '{}': {}",
+                    dir.display(),
+                    err
+                );
+                return Vec::new();
+            }
+        };
+
+        let mut configs = Vec::new();
+        loop {
+            let entry = match entries.next_entry().await {
+                Ok(Some(entry)) => entry,
+                Ok(None) => break,
+                Err(err) => {
+                    warn!(
+                        "Failed to read command directory '{}': {}",
+                        dir.display(),
+                        err
+                    );
+                    break;
+                }
+            };
+
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("md") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+
+            let content = match fs::read_to_string(&path).await {
+                Ok(content) => content,
+                Err(err) => {
+                    warn!("Failed to read command '{}': {}", path.display(), err);
+                    continue;
+                }
+            };
+
+            match Self::parse_command_content(
+                name,
+                &content,
+                Some(path.to_string_lossy().to_string()),
+            ) {
+                Ok(config) => configs.push(config),
+                Err(err) => warn!("Failed to parse command '{}': {}", path.display(), err),
+            }
+        }
+
+        configs
     }
 
-    pub fn all() -> &'static HashMap<String, CommandConfig> {
-        &BUILTIN_COMMANDS
+    /// Parse a command md file. Frontmatter is optional; the body is the template.
+    /// `model` picks the model for the command's turn by model id or name; `subtask: true`
+    /// runs the command as a child task, with `agent` naming its profile.
+    fn parse_command_content(
+        name: &str,
+        content: &str,
+        source_path: Option<String>,
+    ) -> AgentResult<CommandConfig> {
+        let (front, body) = split_frontmatter(content);
+        let fields = front.map(parse_frontmatter).unwrap_or_default().fields;
+        let field = |key: &str| fields.get(key).filter(|v| !v.is_empty()).cloned();
+
+        let subtask = match field("subtask") {
+            Some(raw) => match raw.trim().to_ascii_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                other => {
+                    return Err(AgentError::Parse(format!(
+                        "Invalid subtask value '{other}', expected true or false"
+                    )))
+                }
+            },
+            None => false,
+        };
+
+        let template = body.trim().to_string();
+        if template.is_empty() {
+            return Err(AgentError::Parse("Command template is empty".to_string()));
+        }
+
+        Ok(CommandConfig {
+            name: name.to_string(),
+            description: field("description"),
+            agent: field("agent"),
+            model: field("model"),
+            subtask,
+            template,
+            source_path,
+        })
     }
 
+    /// Substitute the user's input into the template.
+    ///
+    /// `{{input}}` and `$ARGUMENTS` receive the whole input; `$1` to `$9` receive shell-style
+    /// split positional arguments (missing ones become empty), and `$$` is a literal `$`.
+    /// When the template has no placeholder at all, non-empty input is appended so it isn't
+    /// silently dropped.
     pub fn render(cfg: &CommandConfig, input: &str) -> CommandRenderResult {
-        let prompt = cfg.template.replace("{{input}}", input);
+        let args = split_arguments(input);
+        let mut substituted = false;
+        // Single pass, so placeholder-like text inside the input is left alone
+        let rendered = ARGUMENT_PLACEHOLDER.replace_all(&cfg.template, |caps: &Captures| {
+            let value = match caps.get(1).map(|m| m.as_str()) {
+                None if &caps[0] == "$$" => return "$".to_string(),
+                None | Some("ARGUMENTS") => input.to_string(),
+                Some(digits) => match digits.parse::<usize>() {
+                    Ok(i @ 1..=9) => args.get(i - 1).cloned().unwrap_or_default(),
+                    _ => return caps[0].to_string(),
+                },
+            };
+            substituted = true;
+            value
+        });
+        let prompt = if substituted || input.trim().is_empty() {
+            rendered.into_owned()
+        } else {
+            format!("{}\n\n{}", rendered, input.trim())
+        };
+
         CommandRenderResult {
             name: cfg.name.clone(),
             agent: cfg.agent.clone(),
@@ -70,6 +231,27 @@ impl CommandConfigLoader {
         }
     }
 
+    /// Child task a `subtask` command runs its rendered `prompt` as, under its `agent`
+    /// profile or `general`; `None` for commands that run in the current turn.
+    pub fn subtask_request(cfg: &CommandConfig, prompt: &str) -> Option<TaskExecutionRequest> {
+        if !cfg.subtask {
+            return None;
+        }
+        Some(TaskExecutionRequest {
+            description: format!("/{}", cfg.name),
+            prompt: prompt.to_string(),
+            profile: cfg
+                .agent
+                .clone()
+                .filter(|agent| !agent.trim().is_empty())
+                .unwrap_or_else(|| "general".to_string()),
+            session_id: None,
+            call_id: None,
+            model_id: None,
+            use_worktree: false,
+        })
+    }
+
     pub fn summarize(cfg: &CommandConfig) -> CommandSummary {
         CommandSummary {
             name: cfg.name.clone(),
@@ -80,3 +262,115 @@ impl CommandConfigLoader {
         }
     }
 }
+
+/// Split input like a shell would, falling back to whitespace for unbalanced quotes.
+fn split_arguments(input: &str) -> Vec<String> {
+    shell_words::split(input)
+        .unwrap_or_else(|_| input.split_whitespace().map(str::to_string).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(template: &str) -> CommandConfig {
+        CommandConfig {
+            name: "test".to_string(),
+            description: None,
+            agent: None,
+            model: None,
+            subtask: false,
+            template: template.to_string(),
+            source_path: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_frontmatter_fields() {
+        let content = "---\ndescription: Cut a release\nagent: plan\nmodel: gpt-5\nsubtask: true\n---\nRelease $1\n";
+        let cfg = CommandConfigLoader::parse_command_content("release", content, None).unwrap();
+        assert_eq!(cfg.name, "release");
+        assert_eq!(cfg.description.as_deref(), Some("Cut a release"));
+        assert_eq!(cfg.agent.as_deref(), Some("plan"));
+        assert_eq!(cfg.model.as_deref(), Some("gpt-5"));
+        assert!(cfg.subtask);
+        assert_eq!(cfg.template, "Release $1");
+
+        // A subtask command runs as a child task under its agent profile
+        let rendered = CommandConfigLoader::render(&cfg, "v1.2.0");
+        let request = CommandConfigLoader::subtask_request(&cfg, &rendered.prompt).unwrap();
+        assert_eq!(request.profile, "plan");
+        assert_eq!(request.prompt, "Release v1.2.0");
+        assert_eq!(request.description, "/release");
+        assert!(request.session_id.is_none());
+
+        let plain =
+            CommandConfigLoader::parse_command_content("plain", "Just do it", None).unwrap();
+        assert!(plain.agent.is_none() && plain.model.is_none() && !plain.subtask);
+        assert!(CommandConfigLoader::subtask_request(&plain, "Just do it").is_none());
+
+        assert!(CommandConfigLoader::parse_command_content(
+            "bad",
+            "---\nsubtask: yes\n---\nDo it",
+            None
+        )
+        .is_err());
+
+        assert!(CommandConfigLoader::parse_command_content(
+            "empty",
+            "---\nagent: plan\n---\n",
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_render_positional_arguments() {
+        let cfg = command("Tag $1 from $2 ($ARGUMENTS) $3");
+        let rendered = CommandConfigLoader::render(&cfg, r#"v1.2.0 "release branch""#);
+        assert_eq!(
+            rendered.prompt,
+            r#"Tag v1.2.0 from release branch (v1.2.0 "release branch") "#
+        );
+
+        let legacy = CommandConfigLoader::render(&command("Review: {{input}} $1"), "cost $1");
+        assert_eq!(legacy.prompt, "Review: cost $1 cost");
+
+        let appended = CommandConfigLoader::render(&command("Review the diff."), "focus on auth");
+        assert_eq!(appended.prompt, "Review the diff.\n\nfocus on auth");
+    }
+
+    #[test]
+    fn test_render_keeps_literal_dollars() {
+        let cfg = command("Budget: costs $100, run `echo $0` for $1; escape $$2");
+        let rendered = CommandConfigLoader::render(&cfg, "Q3");
+        assert_eq!(
+            rendered.prompt,
+            "Budget: costs $100, run `echo $0` for Q3; escape $2"
+        );
+
+        // Only literal dollars, so the input is still appended
+        let literal = CommandConfigLoader::render(&command("Costs $100 or $$5"), "cheaper");
+        assert_eq!(literal.prompt, "Costs $100 or $5\n\ncheaper");
+    }
+
+    #[tokio::test]
+    async fn test_workspace_commands_override_builtins() {
+        let dir = tempfile::tempdir().unwrap();
+        let commands_dir = dir.path().join(".opencodex").join("commands");
+        std::fs::create_dir_all(&commands_dir).unwrap();
+        std::fs::write(
+            commands_dir.join("code-review.md"),
+            "---\ndescription: Team review\n---\nReview $ARGUMENTS",
+        )
+        .unwrap();
+        std::fs::write(commands_dir.join("notes.txt"), "ignored").unwrap();
+
+        let commands = CommandConfigLoader::load_for_workspace(dir.path()).await;
+        let review = &commands["code-review"];
+        assert_eq!(review.description.as_deref(), Some("Team review"));
+        assert!(review.source_path.is_some());
+        assert!(commands.contains_key("plan-mode"));
+        assert!(!commands.contains_key("notes"));
+    }
+}
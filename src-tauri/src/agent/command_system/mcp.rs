@@ -0,0 +1,249 @@
+//! MCP prompts exposed as slash commands (`/mcp__<server>__<prompt>`)
+
+use serde_json::{Map, Value};
+
+use super::types::{CommandRenderResult, CommandSummary};
+use crate::agent::mcp::types::{McpGetPromptResult, McpPrompt};
+use crate::agent::mcp::{McpError, McpRegistry, McpResult};
+
+pub const MCP_COMMAND_PREFIX: &str = "mcp__";
+
+pub fn mcp_command_name(server: &str, prompt: &str) -> String {
+    format!("{MCP_COMMAND_PREFIX}{server}__{prompt}")
+}
+
+/// Whether `name` has the `mcp__<server>__<prompt>` shape. Server and prompt names may
+/// contain `__` themselves, so which is which is only known from [`find_mcp_prompt`].
+pub fn is_mcp_command_name(name: &str) -> bool {
+    name.strip_prefix(MCP_COMMAND_PREFIX)
+        .and_then(|rest| rest.split_once("__"))
+        .is_some_and(|(server, prompt)| !server.is_empty() && !prompt.is_empty())
+}
+
+/// The `(server, prompt)` among `prompts` whose command name is `name`
+pub fn find_mcp_prompt<'a>(
+    prompts: &'a [(String, McpPrompt)],
+    name: &str,
+) -> Option<&'a (String, McpPrompt)> {
+    prompts
+        .iter()
+        .find(|(server, prompt)| mcp_command_name(server, &prompt.name) == name)
+}
+
+pub fn list_mcp_commands(registry: &McpRegistry, workspace_key: &str) -> Vec<CommandSummary> {
+    registry
+        .get_prompts_for_workspace(workspace_key)
+        .into_iter()
+        .map(|(server, prompt)| CommandSummary {
+            name: mcp_command_name(&server, &prompt.name),
+            description: prompt.description.clone(),
+            agent: None,
+            model: None,
+            subtask: false,
+        })
+        .collect()
+}
+
+/// Render an MCP prompt command. Returns `Ok(None)` when `name` is not an MCP command.
+pub async fn render_mcp_command(
+    registry: &McpRegistry,
+    workspace_key: &str,
+    name: &str,
+    input: &str,
+) -> McpResult<Option<CommandRenderResult>> {
+    if !is_mcp_command_name(name) {
+        return Ok(None);
+    }
+
+    let prompts = registry.get_prompts_for_workspace(workspace_key);
+    let (server, prompt) = find_mcp_prompt(&prompts, name)
+        .ok_or_else(|| McpError::Protocol(format!("Unknown MCP prompt command: {name}")))?;
+    let client = registry
+        .get_client(workspace_key, server)
+        .ok_or(McpError::NotConnected)?;
+
+    let arguments = map_prompt_arguments(prompt, input)?;
+    let result = client.get_prompt(&prompt.name, arguments).await?;
+
+    Ok(Some(CommandRenderResult {
+        name: name.to_string(),
+        agent: None,
+        model: None,
+        subtask: false,
+        prompt: prompt_result_to_text(&result),
+    }))
+}
+
+/// Map free-form command input onto the prompt's declared arguments: words are
+/// assigned positionally and the last argument receives the remainder.
+fn map_prompt_arguments(prompt: &McpPrompt, input: &str) -> McpResult<Map<String, Value>> {
+    let mut arguments = Map::new();
+    let input = input.trim();
+    if prompt.arguments.is_empty() || input.is_empty() {
+        return check_required(prompt, arguments);
+    }
+
+    let words = shell_words::split(input)
+        .unwrap_or_else(|_| input.split_whitespace().map(str::to_string).collect());
+    let last = prompt.arguments.len() - 1;
+    for (index, arg) in prompt.arguments.iter().enumerate() {
+        let value = if index == last {
+            words.get(index..).map(|rest| rest.join(" "))
+        } else {
+            words.get(index).cloned()
+        };
+        if let Some(value) = value.filter(|v| !v.is_empty()) {
+            arguments.insert(arg.name.clone(), Value::String(value));
+        }
+    }
+
+    check_required(prompt, arguments)
+}
+
+fn check_required(
+    prompt: &McpPrompt,
+    arguments: Map<String, Value>,
+) -> McpResult<Map<String, Value>> {
+    let missing: Vec<&str> = prompt
+        .arguments
+        .iter()
+        .filter(|arg| arg.required && !arguments.contains_key(&arg.name))
+        .map(|arg| arg.name.as_str())
+        .collect();
+    if missing.is_empty() {
+        Ok(arguments)
+    } else {
+        Err(McpError::Protocol(format!(
+            "Missing required arguments for MCP prompt '{}': {}",
+            prompt.name,
+            missing.join(", ")
+        )))
+    }
+}
+
+/// Flatten prompt messages into a single user prompt
+fn prompt_result_to_text(result: &McpGetPromptResult) -> String {
+    result
+        .messages
+        .iter()
+        .filter_map(|message| {
+            let content = &message.content;
+            let text = match content.get("type").and_then(Value::as_str) {
+                Some("text") => content.get("text").and_then(Value::as_str),
+                Some("resource") => content
+                    .get("resource")
+                    .and_then(|r| r.get("text"))
+                    .and_then(Value::as_str),
+                _ => None,
+            }?;
+            if message.role == "assistant" {
+                Some(format!("[assistant]\n{text}"))
+            } else {
+                Some(text.to_string())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::mcp::types::{McpPromptArgument, McpPromptMessage};
+    use serde_json::json;
+
+    fn prompt(args: &[(&str, bool)]) -> McpPrompt {
+        named_prompt("review", args)
+    }
+
+    fn named_prompt(name: &str, args: &[(&str, bool)]) -> McpPrompt {
+        McpPrompt {
+            name: name.to_string(),
+            description: None,
+            arguments: args
+                .iter()
+                .map(|(name, required)| McpPromptArgument {
+                    name: name.to_string(),
+                    description: None,
+                    required: *required,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_command_name() {
+        assert!(is_mcp_command_name("mcp__docs__release_notes"));
+        assert!(!is_mcp_command_name("code-review"));
+        assert!(!is_mcp_command_name("mcp__docs"));
+        assert_eq!(
+            mcp_command_name("docs", "release_notes"),
+            "mcp__docs__release_notes"
+        );
+    }
+
+    #[test]
+    fn test_find_prompt_with_underscored_names() {
+        let prompts = vec![
+            ("my__srv".to_string(), named_prompt("notes", &[])),
+            ("docs".to_string(), named_prompt("release__notes", &[])),
+        ];
+        let find = |name| {
+            find_mcp_prompt(&prompts, name)
+                .map(|(server, prompt)| (server.as_str(), prompt.name.as_str()))
+        };
+
+        assert_eq!(find("mcp__my__srv__notes"), Some(("my__srv", "notes")));
+        assert_eq!(
+            find("mcp__docs__release__notes"),
+            Some(("docs", "release__notes"))
+        );
+        assert_eq!(find("mcp__my__notes"), None);
+    }
+
+    #[test]
+    fn test_map_arguments_positionally() {
+        let p = prompt(&[("branch", true), ("focus", false)]);
+        let args = map_prompt_arguments(&p, "main error handling paths").unwrap();
+        assert_eq!(args["branch"], "main");
+        assert_eq!(args["focus"], "error handling paths");
+
+        let args = map_prompt_arguments(&p, "\"release/1.2\"").unwrap();
+        assert_eq!(args["branch"], "release/1.2");
+        assert!(!args.contains_key("focus"));
+    }
+
+    #[test]
+    fn test_missing_required_argument() {
+        let p = prompt(&[("branch", true)]);
+        assert!(map_prompt_arguments(&p, "  ").is_err());
+    }
+
+    #[test]
+    fn test_prompt_result_to_text() {
+        let result = McpGetPromptResult {
+            description: None,
+            messages: vec![
+                McpPromptMessage {
+                    role: "user".to_string(),
+                    content: json!({ "type": "text", "text": "Review this diff" }),
+                },
+                McpPromptMessage {
+                    role: "user".to_string(),
+                    content: json!({
+                        "type": "resource",
+                        "resource": { "uri": "file:///a", "text": "diff --git" }
+                    }),
+                },
+                McpPromptMessage {
+                    role: "user".to_string(),
+                    content: json!({ "type": "image", "data": "..." }),
+                },
+            ],
+        };
+        assert_eq!(
+            prompt_result_to_text(&result),
+            "Review this diff\n\ndiff --git"
+        );
+    }
+}
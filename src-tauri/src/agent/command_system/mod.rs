@@ -1,5 +1,9 @@
+pub mod expand;
 pub mod loader;
+pub mod mcp;
 pub mod types;
 
+pub use expand::{expand_command_prompt, render_command};
 pub use loader::CommandConfigLoader;
+pub use mcp::{is_mcp_command_name, list_mcp_commands, render_mcp_command};
 pub use types::{CommandConfig, CommandRenderResult, CommandSummary};
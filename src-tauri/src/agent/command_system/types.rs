@@ -9,6 +9,9 @@ pub struct CommandConfig {
     pub model: Option<String>,
     pub subtask: bool,
     pub template: String,
+    /// Markdown file the command was loaded from; `None` for built-in commands
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
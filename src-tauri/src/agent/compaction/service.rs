@@ -6,10 +6,10 @@ use crate::agent::common::llm_text::extract_text_from_llm_message;
 use crate::agent::error::AgentResult;
 use crate::agent::persistence::AgentPersistence;
 use crate::agent::prompt::BuiltinPrompts;
-use crate::agent::utils::count_message_param_tokens;
 use crate::agent::types::{Block, Message, MessageRole, MessageStatus, TextBlock};
+use crate::agent::utils::count_message_param_tokens;
 use crate::llm::anthropic_types::{
-    CreateMessageRequest, MessageContent, MessageParam, SystemPrompt,
+    CreateMessageRequest, MessageContent, MessageParam, SystemPrompt, Usage,
 };
 use crate::llm::service::LLMService;
 use crate::storage::DatabaseManager;
@@ -36,6 +36,8 @@ pub struct SummaryCompletion {
     pub status: MessageStatus,
     pub finished_at: DateTime<Utc>,
     pub duration_ms: i64,
+    /// Tokens spent on the summary call, for cost accounting by the caller
+    pub usage: Usage,
 }
 
 pub struct CompactionService {
@@ -185,6 +187,7 @@ impl CompactionService {
         summary_message.finished_at = Some(finished_at);
         summary_message.duration_ms = Some(duration_ms);
         summary_message.role = MessageRole::Assistant;
+        summary_message.token_usage = Some(resp.usage.into());
 
         self.persistence.messages().update(&summary_message).await?;
 
@@ -193,6 +196,7 @@ impl CompactionService {
             status: MessageStatus::Completed,
             finished_at,
             duration_ms,
+            usage: resp.usage,
         })
     }
 }
@@ -62,6 +62,8 @@ pub struct FileContextTracker {
     stale: RwLock<HashSet<String>>,
     recently_modified: RwLock<HashSet<String>>,
     recently_agent_edits: RwLock<HashSet<String>>,
+    /// Files read or edited by the agent or the user; mentions don't count
+    worked_on: RwLock<HashSet<String>>,
 
     /// Track file modification times when files are read
     file_mtimes: RwLock<HashMap<String, SystemTime>>,
@@ -79,6 +81,7 @@ impl FileContextTracker {
             stale: RwLock::new(HashSet::new()),
             recently_modified: RwLock::new(HashSet::new()),
             recently_agent_edits: RwLock::new(HashSet::new()),
+            worked_on: RwLock::new(HashSet::new()),
             file_mtimes: RwLock::new(HashMap::new()),
         }
     }
@@ -135,6 +138,9 @@ impl FileContextTracker {
                 .await
                 .insert(normalized_path.clone());
         }
+        if !matches!(record.source, FileRecordSource::FileMentioned) {
+            self.worked_on.write().await.insert(normalized_path.clone());
+        }
 
         Ok(TrackedFileRecord {
             relative_path: normalized_path,
@@ -176,6 +182,17 @@ impl FileContextTracker {
         guard.drain().collect()
     }
 
+    /// Absolute paths of the files read or edited in this session
+    pub async fn read_or_edited_files(&self) -> Vec<PathBuf> {
+        let base = self.workspace_base_path();
+        self.worked_on
+            .read()
+            .await
+            .iter()
+            .map(|path| base.join(path))
+            .collect()
+    }
+
     /// Record the modification time of a file when it's read
     pub async fn record_file_mtime(&self, path: impl AsRef<Path>) -> AgentResult<()> {
         let normalized = self.normalized_path(path.as_ref());
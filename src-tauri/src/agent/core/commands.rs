@@ -3,13 +3,20 @@
  */
 
 use crate::agent::agents::AgentConfigLoader;
-use crate::agent::command_system::{CommandConfigLoader, CommandRenderResult, CommandSummary};
+use crate::agent::command_system::{
+    list_mcp_commands, render_command, render_mcp_command, CommandConfigLoader,
+    CommandRenderResult, CommandSummary,
+};
 use crate::agent::core::executor::{ExecuteTaskParams, TaskExecutor, TaskSummary};
-use crate::agent::persistence::repositories::CreateMessageParams;
+use crate::agent::permissions::PermissionChecker;
+use crate::agent::persistence::repositories::{CreateMessageParams, SpendQuery};
+use crate::agent::persistence::{SpendBucket, SpendGroup, SpendPeriod};
 use crate::agent::skill::SkillSummary;
 use crate::agent::tools::registry::ToolConfirmationDecision;
 use crate::agent::types::{AgentSwitchBlock, Block, MessageRole, MessageStatus, TaskEvent};
 use crate::agent::workspace_changes::{ChangeKind, PendingChange, WorkspaceChangeJournal};
+use crate::agent::worktree;
+use crate::git::{BranchDiff, MergeOutcome, MergeStrategy};
 use crate::utils::{EmptyData, TauriApiResult};
 use crate::{api_error, api_success};
 use serde::Deserialize;
@@ -65,6 +72,9 @@ pub async fn agent_execute_task(
                 crate::agent::error::TaskExecutorError::TooManyActiveTasksGlobal { .. } => {
                     Ok(api_error!("agent.too_many_active_tasks_global"))
                 }
+                crate::agent::error::TaskExecutorError::HookBlocked { reason, .. } => {
+                    Ok(api_error!("agent.prompt_blocked_by_hook", "reason" => reason))
+                }
                 _ => Ok(api_error!("agent.execute_failed")),
             }
         }
@@ -190,6 +200,9 @@ pub async fn agent_cancel_task(
 pub struct ToolConfirmationParams {
     pub request_id: String,
     pub decision: ToolConfirmationDecision,
+    /// Form values, for confirmations that ask the user for input
+    #[serde(default)]
+    pub content: Option<serde_json::Value>,
 }
 
 /// Return tool confirmation result
@@ -217,7 +230,7 @@ pub async fn agent_tool_confirm(
 
     let ok = ctx
         .tool_registry()
-        .resolve_confirmation(&ctx, &params.request_id, params.decision)
+        .resolve_confirmation(&ctx, &params.request_id, params.decision, params.content)
         .await;
 
     if ok {
@@ -243,22 +256,129 @@ pub async fn agent_list_tasks(
     }
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendParams {
+    pub period: SpendPeriod,
+    pub group_by: SpendGroup,
+    /// Inclusive unix timestamp (seconds)
+    pub since: i64,
+    /// Exclusive unix timestamp (seconds), defaults to now
+    pub until: Option<i64>,
+    pub workspace_path: Option<String>,
+}
+
+/// Daily or weekly LLM spend per workspace or per model
+#[tauri::command]
+pub async fn agent_get_spend(
+    state: State<'_, TaskExecutorState>,
+    params: SpendParams,
+) -> TauriApiResult<Vec<SpendBucket>> {
+    let until = params
+        .until
+        .unwrap_or_else(|| chrono::Utc::now().timestamp() + 1);
+    let query = SpendQuery {
+        period: params.period,
+        group_by: params.group_by,
+        since: params.since,
+        until,
+        workspace_path: params.workspace_path.as_deref(),
+    };
+    match state
+        .executor
+        .agent_persistence()
+        .usage()
+        .spend(query)
+        .await
+    {
+        Ok(buckets) => Ok(api_success!(buckets)),
+        Err(e) => {
+            tracing::error!("❌ Load spend failed: {}", e);
+            Ok(api_error!("agent.spend_failed"))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeParams {
+    pub session_id: i64,
+    #[serde(default)]
+    pub strategy: MergeStrategy,
+}
+
+/// Changes a `use_worktree` task made, relative to the checkout it was started from
+#[tauri::command]
+pub async fn agent_worktree_diff(
+    state: State<'_, TaskExecutorState>,
+    params: WorktreeParams,
+) -> TauriApiResult<BranchDiff> {
+    match worktree::diff(&state.executor.agent_persistence(), params.session_id).await {
+        Ok(diff) => Ok(api_success!(diff)),
+        Err(e) => {
+            tracing::error!("❌ Load worktree diff failed: {}", e);
+            Ok(api_error!("agent.worktree_diff_failed"))
+        }
+    }
+}
+
+/// Merge a `use_worktree` task back; conflicts are reported in the outcome, not as errors
+#[tauri::command]
+pub async fn agent_worktree_merge(
+    state: State<'_, TaskExecutorState>,
+    params: WorktreeParams,
+) -> TauriApiResult<MergeOutcome> {
+    match worktree::merge(
+        &state.executor.agent_persistence(),
+        params.session_id,
+        params.strategy,
+    )
+    .await
+    {
+        Ok(outcome) => Ok(api_success!(outcome)),
+        Err(e) => {
+            tracing::error!("❌ Merge worktree failed: {}", e);
+            Ok(api_error!("agent.worktree_merge_failed"))
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn agent_worktree_discard(
+    state: State<'_, TaskExecutorState>,
+    params: WorktreeParams,
+) -> TauriApiResult<EmptyData> {
+    match worktree::discard(&state.executor.agent_persistence(), params.session_id).await {
+        Ok(()) => Ok(api_success!()),
+        Err(e) => {
+            tracing::error!("❌ Discard worktree failed: {}", e);
+            Ok(api_error!("agent.worktree_discard_failed"))
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ListCommandsParams {
     pub workspace_path: String,
 }
 
-/// List built-in commands
+/// List built-in and user commands plus prompts of connected MCP servers
 #[tauri::command]
 pub async fn agent_list_commands(
-    _state: State<'_, TaskExecutorState>,
-    _params: ListCommandsParams,
+    state: State<'_, TaskExecutorState>,
+    params: ListCommandsParams,
 ) -> TauriApiResult<Vec<CommandSummary>> {
-    let mut out = CommandConfigLoader::all()
+    let workspace_root = std::path::PathBuf::from(&params.workspace_path);
+    let mut out = CommandConfigLoader::load_for_workspace(&workspace_root)
+        .await
         .values()
         .map(CommandConfigLoader::summarize)
         .collect::<Vec<_>>();
+    out.extend(list_mcp_commands(
+        &state.executor.mcp_registry(),
+        &params.workspace_path,
+    ));
     out.sort_by(|a, b| a.name.cmp(&b.name));
     Ok(api_success!(out))
 }
@@ -271,20 +391,50 @@ pub struct RenderCommandParams {
     pub input: String,
 }
 
-/// Render built-in command template (only does `{{input}}` replacement)
+/// Render a command: templates get permission-gated `@path` and shell expansion, then argument
+/// substitution; MCP prompt commands are fetched from the server via `prompts/get`
 #[tauri::command]
 pub async fn agent_render_command(
-    _state: State<'_, TaskExecutorState>,
+    state: State<'_, TaskExecutorState>,
     params: RenderCommandParams,
 ) -> TauriApiResult<CommandRenderResult> {
-    let Some(cfg) = CommandConfigLoader::get(&params.name) else {
-        return Ok(api_error!("agent.command_not_found"));
-    };
+    let workspace_root = std::path::PathBuf::from(&params.workspace_path);
+    let commands = CommandConfigLoader::load_for_workspace(&workspace_root).await;
+    if let Some(cfg) = commands.get(&params.name) {
+        let permissions = match state
+            .executor
+            .settings_manager()
+            .get_effective_settings(Some(workspace_root.clone()))
+            .await
+        {
+            Ok(effective) => PermissionChecker::new(&effective.permissions),
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to load effective settings for command render: {}",
+                    e
+                );
+                return Ok(api_error!("agent.command_render_failed"));
+            }
+        };
+        let rendered = render_command(cfg, &params.input, &workspace_root, &permissions).await;
+        return Ok(api_success!(rendered));
+    }
 
-    Ok(api_success!(CommandConfigLoader::render(
-        cfg,
-        &params.input
-    )))
+    match render_mcp_command(
+        &state.executor.mcp_registry(),
+        &params.workspace_path,
+        &params.name,
+        &params.input,
+    )
+    .await
+    {
+        Ok(Some(rendered)) => Ok(api_success!(rendered)),
+        Ok(None) => Ok(api_error!("agent.command_not_found")),
+        Err(e) => {
+            tracing::warn!("Failed to render MCP command '{}': {}", params.name, e);
+            Ok(api_error!("agent.command_render_failed"))
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+
+/// Share of a budget after which the model is reminded to wrap up.
+const WARNING_RATIO: f64 = 0.8;
+
+/// Token and cost budget shared by a run's root task and every `task`-spawned child.
+#[derive(Debug)]
+pub struct RunBudget {
+    max_tokens: Option<u64>,
+    max_cost: Option<f64>,
+    used: parking_lot::Mutex<(u64, f64)>,
+}
+
+/// Spend of a run against its limits
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetUsage {
+    pub used_tokens: u64,
+    pub max_tokens: Option<u64>,
+    /// USD
+    pub used_cost: f64,
+    pub max_cost: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetState {
+    Within,
+    Approaching(BudgetUsage),
+    Exceeded(BudgetUsage),
+}
+
+impl RunBudget {
+    /// `max_tokens == 0` and a non-positive `max_cost` mean no limit.
+    pub fn new(max_tokens: u64, max_cost: Option<f64>) -> Self {
+        Self {
+            max_tokens: (max_tokens > 0).then_some(max_tokens),
+            max_cost: max_cost.filter(|cost| *cost > 0.0),
+            used: parking_lot::Mutex::new((0, 0.0)),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(0, None)
+    }
+
+    pub fn add(&self, tokens: u64, cost: f64) {
+        let mut used = self.used.lock();
+        used.0 = used.0.saturating_add(tokens);
+        used.1 += cost;
+    }
+
+    pub fn usage(&self) -> BudgetUsage {
+        let (used_tokens, used_cost) = *self.used.lock();
+        BudgetUsage {
+            used_tokens,
+            max_tokens: self.max_tokens,
+            used_cost,
+            max_cost: self.max_cost,
+        }
+    }
+
+    pub fn state(&self) -> BudgetState {
+        let usage = self.usage();
+        let ratio = usage.ratio();
+        if ratio >= 1.0 {
+            BudgetState::Exceeded(usage)
+        } else if ratio >= WARNING_RATIO {
+            BudgetState::Approaching(usage)
+        } else {
+            BudgetState::Within
+        }
+    }
+}
+
+impl BudgetUsage {
+    /// Largest share of any configured limit that has been used
+    pub fn ratio(&self) -> f64 {
+        let tokens = self
+            .max_tokens
+            .map(|max| self.used_tokens as f64 / max as f64)
+            .unwrap_or(0.0);
+        let cost = self.max_cost.map(|max| self.used_cost / max).unwrap_or(0.0);
+        tokens.max(cost)
+    }
+
+    /// Human-readable spend, e.g. `182000/200000 tokens, $1.20/$2.00`
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(max) = self.max_tokens {
+            parts.push(format!("{}/{} tokens", self.used_tokens, max));
+        }
+        if let Some(max) = self.max_cost {
+            parts.push(format!("${:.2}/${:.2}", self.used_cost, max));
+        }
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::anthropic_types::Usage;
+    use crate::llm::cost::fresh_tokens;
+    use crate::settings::types::AgentConfig;
+
+    #[test]
+    fn zero_limits_never_trigger() {
+        let budget = RunBudget::new(0, Some(0.0));
+        budget.add(u64::MAX / 2, 1_000.0);
+        assert_eq!(budget.state(), BudgetState::Within);
+    }
+
+    /// One orchestrate iteration over a cached prompt that grows by 2k tokens each time
+    fn iteration_usage(iteration: u32) -> Usage {
+        Usage {
+            input_tokens: 1_500,
+            output_tokens: 600,
+            cache_creation_input_tokens: Some(400),
+            cache_read_input_tokens: Some(20_000 + iteration * 2_000),
+        }
+    }
+
+    fn default_budget() -> RunBudget {
+        let config = AgentConfig::default();
+        RunBudget::new(config.max_token_budget, config.max_cost_budget)
+    }
+
+    #[test]
+    fn normal_run_stays_within_defaults() {
+        let budget = default_budget();
+        for iteration in 0..30 {
+            budget.add(fresh_tokens(&iteration_usage(iteration), false), 0.05);
+        }
+
+        assert_eq!(budget.state(), BudgetState::Within);
+        assert_eq!(budget.usage().used_tokens, 75_000);
+    }
+
+    #[test]
+    fn runaway_loop_trips_defaults() {
+        let budget = default_budget();
+        let mut iterations = 0;
+        while !matches!(budget.state(), BudgetState::Exceeded(_)) {
+            assert!(iterations < 200, "default budget never tripped");
+            budget.add(fresh_tokens(&iteration_usage(iterations), false), 0.05);
+            iterations += 1;
+        }
+
+        assert_eq!(iterations, 80);
+        assert_eq!(budget.usage().used_tokens, 200_000);
+    }
+
+    #[test]
+    fn warns_then_stops_on_either_limit() {
+        let budget = RunBudget::new(1_000, Some(1.0));
+        budget.add(500, 0.1);
+        assert_eq!(budget.state(), BudgetState::Within);
+
+        budget.add(300, 0.1);
+        assert!(matches!(budget.state(), BudgetState::Approaching(_)));
+
+        let cost_bound = RunBudget::new(1_000, Some(1.0));
+        cost_bound.add(10, 1.0);
+        match cost_bound.state() {
+            BudgetState::Exceeded(usage) => {
+                assert_eq!(usage.describe(), "10/1000 tokens, $1.00/$1.00")
+            }
+            other => panic!("expected exceeded, got {other:?}"),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod budget;
 pub mod chain;
 pub mod states;
 
@@ -12,6 +13,7 @@ use tauri::ipc::Channel;
 use tokio::sync::Notify;
 use tokio::sync::RwLock;
 
+use self::budget::RunBudget;
 use self::chain::Chain;
 use self::states::{ExecutionState, TaskStates};
 use crate::agent::config::{AgentConfig, TaskExecutionConfig};
@@ -19,7 +21,8 @@ use crate::agent::context::FileContextTracker;
 use crate::agent::core::executor::ImageAttachment;
 use crate::agent::core::status::AgentTaskStatus;
 use crate::agent::error::{TaskExecutorError, TaskExecutorResult};
-use crate::agent::persistence::repositories::CreateMessageParams;
+use crate::agent::persistence::models::UsagePurpose;
+use crate::agent::persistence::repositories::{CreateMessageParams, RecordUsageParams};
 use crate::agent::persistence::AgentPersistence;
 use crate::agent::react::runtime::ReactRuntime;
 use crate::agent::react::types::ReactRuntimeConfig;
@@ -33,7 +36,7 @@ use crate::agent::types::{
 use crate::agent::workspace_changes::WorkspaceChangeJournal;
 use crate::checkpoint::CheckpointService;
 use crate::llm::anthropic_types::{
-    ContentBlock, MessageContent, MessageParam, MessageRole as AnthropicRole, SystemPrompt,
+    ContentBlock, MessageContent, MessageParam, MessageRole as AnthropicRole, SystemPrompt, Usage,
 };
 use crate::storage::DatabaseManager;
 use tokio_util::sync::CancellationToken;
@@ -92,6 +95,8 @@ pub struct TaskContextInit {
     pub updates_run_status: bool,
     pub emit_task_events: bool,
     pub progress_channel: Option<Channel<TaskEvent>>,
+    /// Shared with every child execution of the run
+    pub budget: Arc<RunBudget>,
     pub deps: TaskContextDeps,
 }
 
@@ -115,6 +120,7 @@ pub struct TaskContext {
     active_checkpoint: Arc<RwLock<Option<ActiveCheckpoint>>>,
     workspace_changes: Arc<WorkspaceChangeJournal>,
     workspace_key: Arc<str>,
+    budget: Arc<RunBudget>,
 
     pub(crate) states: TaskStates,
 
@@ -137,6 +143,7 @@ impl TaskContext {
             updates_run_status,
             emit_task_events,
             progress_channel,
+            budget,
             deps,
         } = init;
         let agent_config = AgentConfig::default();
@@ -197,6 +204,7 @@ impl TaskContext {
             active_checkpoint: Arc::new(RwLock::new(None)),
             workspace_changes: deps.workspace_changes,
             workspace_key,
+            budget,
             states,
             pause_status: AtomicU8::new(0),
             pause_notify: Arc::new(Notify::new()),
@@ -299,6 +307,10 @@ impl TaskContext {
         Arc::clone(&self.tool_registry)
     }
 
+    pub fn run_budget(&self) -> Arc<RunBudget> {
+        Arc::clone(&self.budget)
+    }
+
     pub fn task_execution_runner(&self) -> &dyn TaskExecutionRunner {
         self.task_execution_runner.as_ref()
     }
@@ -316,7 +328,7 @@ impl TaskContext {
                 AgentTaskStatus::Running => "running",
                 AgentTaskStatus::Completed => "completed",
                 AgentTaskStatus::Error => "error",
-                AgentTaskStatus::Cancelled => "cancelled",
+                AgentTaskStatus::Cancelled | AgentTaskStatus::BudgetExceeded => "cancelled",
             }
         };
 
@@ -333,7 +345,9 @@ impl TaskContext {
             AgentTaskStatus::Running => crate::agent::persistence::RunStatus::Running,
             AgentTaskStatus::Completed => crate::agent::persistence::RunStatus::Completed,
             AgentTaskStatus::Error => crate::agent::persistence::RunStatus::Error,
-            AgentTaskStatus::Cancelled => crate::agent::persistence::RunStatus::Cancelled,
+            AgentTaskStatus::Cancelled | AgentTaskStatus::BudgetExceeded => {
+                crate::agent::persistence::RunStatus::Cancelled
+            }
         };
 
         self.agent_persistence()
@@ -403,7 +417,10 @@ impl TaskContext {
         };
         if matches!(
             status,
-            AgentTaskStatus::Cancelled | AgentTaskStatus::Completed | AgentTaskStatus::Error
+            AgentTaskStatus::Cancelled
+                | AgentTaskStatus::Completed
+                | AgentTaskStatus::Error
+                | AgentTaskStatus::BudgetExceeded
         ) {
             return true;
         }
@@ -918,6 +935,78 @@ impl TaskContext {
         })
     }
 
+    pub async fn assistant_message_id(&self) -> Option<i64> {
+        self.states
+            .messages
+            .lock()
+            .await
+            .assistant_message
+            .as_ref()
+            .map(|message| message.id)
+    }
+
+    /// Price an LLM call made for this task, charge it to the run budget and attribute it to
+    /// `message_id`, the session, the run and the model's usage stats.
+    ///
+    /// Usage on the current assistant message is also accumulated so it is reported when
+    /// the message finishes. Accounting failures are logged and never fail the task.
+    pub async fn record_llm_usage(
+        &self,
+        model_id: &str,
+        purpose: UsagePurpose,
+        message_id: Option<i64>,
+        usage: &Usage,
+    ) {
+        let pricing = crate::llm::cost::model_pricing(&self.repositories(), model_id).await;
+        let cost = pricing
+            .as_ref()
+            .map(|pricing| pricing.cost_of(usage))
+            .unwrap_or(0.0);
+        let token_usage = TokenUsage::from(*usage);
+        // Without a configured model the provider is unknown; OpenAI style semantics can
+        // only undercount Anthropic cache reads, never double charge them
+        let fresh_tokens = match &pricing {
+            Some(pricing) => pricing.fresh_tokens(usage),
+            None => crate::llm::cost::fresh_tokens(usage, true),
+        };
+        self.budget.add(fresh_tokens, cost);
+
+        if let Some(message_id) = message_id {
+            let mut messages = self.states.messages.lock().await;
+            if let Some(message) = messages
+                .assistant_message
+                .as_mut()
+                .filter(|message| message.id == message_id)
+            {
+                message.token_usage = Some(match message.token_usage.take() {
+                    Some(total) => add_token_usage(total, &token_usage),
+                    None => token_usage.clone(),
+                });
+            }
+        }
+
+        let result = self
+            .agent_persistence()
+            .usage()
+            .record(RecordUsageParams {
+                workspace_path: Some(&self.workspace_key),
+                session_id: Some(self.session_id),
+                run_id: Some(self.run_id),
+                message_id,
+                model_id,
+                provider_id: pricing.as_ref().map(|pricing| pricing.provider.as_str()),
+                purpose,
+                usage: &token_usage,
+                cost,
+            })
+            .await;
+        if let Err(err) = result {
+            warn!(task_id = %self.task_id, "Failed to record LLM usage: {}", err);
+        }
+    }
+
+    /// Finish the assistant message. Without explicit `token_usage`, the usage accumulated
+    /// by [`Self::record_llm_usage`] is reported.
     pub async fn finish_assistant_message(
         &self,
         status: MessageStatus,
@@ -946,6 +1035,7 @@ impl TaskContext {
         message.status = status.clone();
         message.finished_at = Some(finished_at);
         message.duration_ms = Some(duration_ms);
+        let token_usage = token_usage.or_else(|| message.token_usage.clone());
         message.token_usage = token_usage.clone();
         message.context_usage = context_usage.clone();
 
@@ -1189,7 +1279,7 @@ fn map_status(status: &AgentTaskStatus) -> TaskStatus {
         AgentTaskStatus::Paused => TaskStatus::Paused,
         AgentTaskStatus::Completed => TaskStatus::Done,
         AgentTaskStatus::Error => TaskStatus::Error,
-        AgentTaskStatus::Cancelled => TaskStatus::Aborted,
+        AgentTaskStatus::Cancelled | AgentTaskStatus::BudgetExceeded => TaskStatus::Aborted,
     }
 }
 
@@ -1258,6 +1348,22 @@ fn find_block_index(blocks: &[Block], block_id: &str) -> Option<usize> {
     })
 }
 
+fn add_token_usage(total: TokenUsage, usage: &TokenUsage) -> TokenUsage {
+    fn add(a: Option<i64>, b: Option<i64>) -> Option<i64> {
+        match (a, b) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+        }
+    }
+
+    TokenUsage {
+        input_tokens: total.input_tokens + usage.input_tokens,
+        output_tokens: total.output_tokens + usage.output_tokens,
+        cache_read_tokens: add(total.cache_read_tokens, usage.cache_read_tokens),
+        cache_write_tokens: add(total.cache_write_tokens, usage.cache_write_tokens),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentToolCallResult {
     pub call_id: String,
@@ -12,12 +12,16 @@ use std::sync::Arc;
 use tauri::ipc::Channel;
 
 use crate::agent::agents::AgentConfigLoader;
-use crate::agent::command_system::CommandConfigLoader;
+use crate::agent::command_system::{
+    is_mcp_command_name, render_command, render_mcp_command, CommandConfigLoader,
+};
 use crate::agent::common::truncate_chars;
 use crate::agent::config::TaskExecutionConfig;
+use crate::agent::core::context::budget::RunBudget;
 use crate::agent::core::context::TaskContext;
 use crate::agent::core::executor::{ExecuteTaskParams, TaskExecutor};
 use crate::agent::error::{TaskExecutorError, TaskExecutorResult};
+use crate::agent::permissions::PermissionChecker;
 use crate::agent::persistence::{AgentNodeRole, CreateAgentNodeParams, CreateRunParams, RunStatus};
 use crate::agent::types::TaskEvent;
 
@@ -35,6 +39,46 @@ impl TaskExecutor {
         self.create_new_context(params, progress_channel).await
     }
 
+    /// MCP servers are (re)started by the task lifecycle; commands rendered before that
+    /// need the workspace's clients, so connect them here if this is the first turn.
+    async fn ensure_mcp_workspace_servers(&self, workspace_root: &std::path::Path) {
+        let workspace_key = workspace_root.to_string_lossy();
+        if self.mcp_registry().has_workspace(&workspace_key) {
+            return;
+        }
+
+        let effective = match self
+            .settings_manager()
+            .get_effective_settings(Some(workspace_root.to_path_buf()))
+            .await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!("Failed to load effective settings for MCP commands: {}", e);
+                return;
+            }
+        };
+        let workspace_settings = match self
+            .settings_manager()
+            .get_workspace_settings(workspace_root)
+            .await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!("Failed to load workspace settings for MCP commands: {}", e);
+                None
+            }
+        };
+
+        if let Err(err) = self
+            .mcp_registry()
+            .init_workspace_servers(workspace_root, &effective, workspace_settings.as_ref())
+            .await
+        {
+            tracing::warn!("Failed to initialize MCP workspace servers: {}", err);
+        }
+    }
+
     async fn finish_running_task_for_session(&self, session_id: i64) -> TaskExecutorResult<()> {
         let mut to_cancel = Vec::new();
         for entry in self.active_tasks().iter() {
@@ -149,12 +193,20 @@ impl TaskExecutor {
             Some(manager)
         };
 
+        let command_permissions = PermissionChecker::new(&effective.permissions);
+        let budget = Arc::new(RunBudget::new(
+            effective.agent.max_token_budget,
+            effective.agent.max_cost_budget,
+        ));
         let tool_registry = crate::agent::tools::create_tool_registry(
             "agent",
             effective.permissions,
+            effective.hooks,
+            effective.agent.sandbox,
             agent_tool_filter,
             self.tool_confirmations(),
             Vec::new(),
+            &workspace_root,
             self.vector_search_engine(),
             Some(self.lsp_manager()),
             skill_manager,
@@ -169,19 +221,42 @@ impl TaskExecutor {
             truncate_chars(&raw_user_prompt, 400)
         );
 
-        // Process command_id if present (render built-in command template with raw user input)
+        // Process command_id if present (render the built-in or user command template with raw user input)
         let user_prompt = if let Some(cmd_id) = params.command_id.as_deref() {
-            if let Some(cmd_config) = CommandConfigLoader::get(cmd_id) {
-                let rendered = CommandConfigLoader::render(cmd_config, &raw_user_prompt);
-                if params.agent_type.is_none() {
+            let commands = CommandConfigLoader::load_for_workspace(&workspace_root).await;
+            if let Some(cmd_config) = commands.get(cmd_id) {
+                let rendered = render_command(
+                    cmd_config,
+                    &raw_user_prompt,
+                    &workspace_root,
+                    &command_permissions,
+                )
+                .await;
+                // A subtask command's agent is the child's profile; this turn keeps its own
+                if params.agent_type.is_none() && !cmd_config.subtask {
                     if let Some(command_agent) =
                         rendered.agent.as_ref().filter(|v| !v.trim().is_empty())
                     {
                         agent_type = command_agent.clone();
                     }
                 }
-                tracing::info!("Rendered built-in command '{}' template", cmd_id);
+                tracing::info!("Rendered command '{}' template", cmd_id);
                 rendered.prompt
+            } else if is_mcp_command_name(cmd_id) {
+                self.ensure_mcp_workspace_servers(&workspace_root).await;
+                match render_mcp_command(&self.mcp_registry(), &cwd, cmd_id, &raw_user_prompt).await
+                {
+                    Ok(Some(rendered)) => {
+                        tracing::info!("Rendered MCP prompt command '{}'", cmd_id);
+                        rendered.prompt
+                    }
+                    Ok(None) => raw_user_prompt.clone(),
+                    Err(e) => {
+                        return Err(TaskExecutorError::ConfigurationError(format!(
+                            "Failed to render MCP prompt '{cmd_id}': {e}"
+                        )));
+                    }
+                }
             } else {
                 tracing::warn!("Command '{}' not found, using raw prompt", cmd_id);
                 raw_user_prompt.clone()
@@ -237,6 +312,7 @@ impl TaskExecutor {
             updates_run_status: true,
             emit_task_events: true,
             progress_channel,
+            budget,
             deps: crate::agent::core::context::TaskContextDeps {
                 tool_registry,
                 repositories: Arc::clone(&self.database()),
@@ -9,19 +9,23 @@ use tokio::task;
 use tracing::{error, warn};
 use uuid::Uuid;
 
+use crate::agent::command_system::{CommandConfig, CommandConfigLoader};
 use crate::agent::common::truncate_chars;
-use crate::agent::core::context::TaskContext;
+use crate::agent::core::context::{TaskContext, TaskExecutionRequest};
 use crate::agent::core::executor::{ExecuteTaskParams, TaskExecutor};
 use crate::agent::core::status::AgentTaskStatus;
 use crate::agent::error::{TaskExecutorError, TaskExecutorResult};
+use crate::agent::hooks::{HookEvent, HookInput, HookRunner};
 use crate::agent::persistence::repositories::CreateMessageParams;
-use crate::agent::tools::RunnableTool;
+use crate::agent::persistence::UsagePurpose;
+use crate::agent::tools::builtin::task::run_delegated_task;
 use crate::agent::tools::ToolAvailabilityContext;
 use crate::agent::tools::{ToolResultContent, ToolResultStatus};
 use crate::agent::types::{
     AgentSwitchBlock, Block, ErrorBlock, Message, MessageRole, MessageStatus, SubtaskBlock,
     SubtaskStatus, TaskEvent, ToolBlock, ToolOutput, ToolStatus,
 };
+use crate::storage::repositories::AIModels;
 use crate::workspace::WorkspaceService;
 use crate::{agent::common::llm_text::extract_text_from_llm_message, llm::service::LLMService};
 
@@ -89,12 +93,23 @@ impl TaskExecutor {
         params: ExecuteTaskParams,
         progress_channel: Channel<TaskEvent>,
     ) -> TaskExecutorResult<Arc<TaskContext>> {
+        let is_new_session = params.session_id <= 0;
         // Normalize parameters: validate workspace is set and create session if needed
-        let params = self.normalize_task_params(params).await?;
+        let mut params = self.normalize_task_params(params).await?;
+        let command = self.load_command(&params).await;
+        if let Some(command) = &command {
+            self.apply_command_model(command, &mut params).await;
+        }
+        self.run_prompt_hooks(&mut params, is_new_session).await?;
 
         let ctx = self
             .build_or_restore_context(&params, Some(progress_channel))
             .await?;
+        let command_subtask = command.as_ref().and_then(|command| {
+            let mut request = CommandConfigLoader::subtask_request(command, &ctx.user_prompt)?;
+            request.model_id = command.model.as_ref().map(|_| params.model_id.clone());
+            Some(request)
+        });
 
         // Clear the agent edit set from the previous task to avoid "diagnosing old files" behavior.
         ctx.file_tracker().take_recent_agent_edits().await;
@@ -181,6 +196,16 @@ impl TaskExecutor {
                 warn!("Failed to initialize MCP workspace servers: {}", err);
             }
 
+            if let Some(request) = command_subtask {
+                if let Err(e) = executor
+                    .run_command_subtask(ctx_for_spawn, &model_id, request)
+                    .await
+                {
+                    error!("Command subtask failed: {}", e);
+                }
+                return;
+            }
+
             // If prior subtasks were cancelled mid-flight, do NOT dump partial output into the
             // parent prompt. Backfill *real* summaries once per block using the LLM.
             if let Err(e) = executor
@@ -211,6 +236,7 @@ impl TaskExecutor {
 
             let availability_ctx = ToolAvailabilityContext {
                 has_vector_index: executor.vector_search_engine().is_some(),
+                has_keyword_index: crate::vector_db::has_keyword_index(&workspace_root),
             };
             for tool in executor
                 .mcp_registry()
@@ -219,12 +245,7 @@ impl TaskExecutor {
                 let name = tool.name().to_string();
                 if let Err(err) = ctx_for_spawn
                     .tool_registry()
-                    .register(
-                        &name,
-                        Arc::new(tool) as Arc<dyn RunnableTool>,
-                        false,
-                        &availability_ctx,
-                    )
+                    .register(&name, tool, false, &availability_ctx)
                     .await
                 {
                     warn!("Failed to register MCP tool '{}': {}", name, err);
@@ -323,9 +344,11 @@ impl TaskExecutor {
         model_id: String,
     ) -> TaskExecutorResult<()> {
         const MAX_SYNTAX_REPAIR_ROUNDS: usize = 2;
+        const MAX_STOP_HOOK_ROUNDS: usize = 3;
 
         let mut drop_guard = RunTaskLoopDropGuard::new(self.clone(), Arc::clone(&ctx));
         let mut repair_round = 0usize;
+        let mut stop_hook_round = 0usize;
 
         loop {
             // Directly call ReactOrchestrator, passing self as ReactHandler
@@ -341,6 +364,14 @@ impl TaskExecutor {
                         .run_syntax_diagnostics_and_maybe_request_fix(&ctx, repair_round)
                         .await?;
 
+                    if syntax_ok && stop_hook_round < MAX_STOP_HOOK_ROUNDS {
+                        if let Some(reason) = run_stop_hooks(&ctx, stop_hook_round > 0).await {
+                            stop_hook_round += 1;
+                            ctx.add_user_message(reason).await?;
+                            continue;
+                        }
+                    }
+
                     if syntax_ok {
                         ctx.set_status(AgentTaskStatus::Completed).await?;
                         let context_usage = ctx.calculate_context_usage(&model_id).await;
@@ -445,6 +476,40 @@ impl TaskExecutor {
                         break;
                     }
 
+                    // Running out of budget is an expected stop, not a failure: keep what the
+                    // assistant produced so far and tell the client why the run ended.
+                    if let TaskExecutorError::BudgetExceeded(reason) = &e {
+                        warn!("Task stopped: run budget exceeded ({})", reason);
+                        ctx.set_status(AgentTaskStatus::BudgetExceeded).await?;
+                        let context_usage = ctx.calculate_context_usage(&model_id).await;
+                        if let Err(err) = ctx
+                            .finish_assistant_message(
+                                crate::agent::types::MessageStatus::Completed,
+                                None,
+                                context_usage,
+                            )
+                            .await
+                        {
+                            warn!("Failed to finish assistant message over budget: {}", err);
+                        }
+                        if ctx.emits_task_events() {
+                            let usage = ctx.run_budget().usage();
+                            if let Err(err) = ctx
+                                .emit_event(TaskEvent::TaskBudgetExceeded {
+                                    task_id: ctx.task_id.to_string(),
+                                    used_tokens: usage.used_tokens,
+                                    max_tokens: usage.max_tokens,
+                                    used_cost: usage.used_cost,
+                                    max_cost: usage.max_cost,
+                                })
+                                .await
+                            {
+                                warn!("Failed to emit budget exceeded event: {}", err);
+                            }
+                        }
+                        break;
+                    }
+
                     error!("Task failed: {}", e);
                     ctx.set_status(AgentTaskStatus::Error).await?;
 
@@ -647,6 +712,150 @@ impl TaskExecutor {
         Ok(())
     }
 
+    /// Run SessionStart (first turn of a new session) and UserPromptSubmit hooks. Context they
+    /// print is injected as system reminders; a blocking UserPromptSubmit hook rejects the prompt.
+    async fn run_prompt_hooks(
+        &self,
+        params: &mut ExecuteTaskParams,
+        is_new_session: bool,
+    ) -> TaskExecutorResult<()> {
+        let workspace_root = tokio::fs::canonicalize(&params.workspace_path)
+            .await
+            .unwrap_or_else(|_| std::path::PathBuf::from(&params.workspace_path));
+        let effective = self
+            .settings_manager()
+            .get_effective_settings(Some(workspace_root.clone()))
+            .await
+            .map_err(|e| TaskExecutorError::ConfigurationError(e.to_string()))?;
+        if effective.hooks.is_empty() {
+            return Ok(());
+        }
+
+        let hooks = HookRunner::new(effective.hooks);
+        let cwd = workspace_root.to_string_lossy().to_string();
+
+        if is_new_session {
+            let input = HookInput::new(HookEvent::SessionStart, params.session_id, cwd.as_str());
+            let outcome = hooks.run(input).await;
+            params.system_reminders.extend(outcome.context_text());
+        }
+
+        let input = HookInput::new(HookEvent::UserPromptSubmit, params.session_id, cwd)
+            .with_prompt(&params.user_prompt);
+        let outcome = hooks.run(input).await;
+        if let Some(reason) = outcome.blocked {
+            return Err(TaskExecutorError::HookBlocked {
+                event: HookEvent::UserPromptSubmit.as_str().to_string(),
+                reason,
+            });
+        }
+        params.system_reminders.extend(outcome.context_text());
+
+        Ok(())
+    }
+
+    /// Normalize task parameters:
+    /// The user or workspace command the prompt invokes, if any
+    async fn load_command(&self, params: &ExecuteTaskParams) -> Option<CommandConfig> {
+        let cmd_id = params.command_id.as_deref()?;
+        let workspace_root = std::path::Path::new(&params.workspace_path);
+        CommandConfigLoader::load_for_workspace(workspace_root)
+            .await
+            .remove(cmd_id)
+    }
+
+    /// A command's `model` frontmatter picks the model for its turn, by model id or name
+    async fn apply_command_model(&self, command: &CommandConfig, params: &mut ExecuteTaskParams) {
+        let cmd_id = command.name.as_str();
+        let Some(model) = command.model.as_deref() else {
+            return;
+        };
+
+        let models = match AIModels::new(self.database().as_ref()).find_all().await {
+            Ok(models) => models,
+            Err(err) => {
+                warn!("Failed to load models for command '{}': {}", cmd_id, err);
+                return;
+            }
+        };
+        let found = models
+            .iter()
+            .find(|m| m.id == model)
+            .or_else(|| models.iter().find(|m| m.model == model));
+        match found {
+            Some(found) => params.model_id = found.id.clone(),
+            None => warn!(
+                "Command '{}' asks for unknown model '{}', using the selected model",
+                cmd_id, model
+            ),
+        }
+    }
+
+    /// Run a `subtask` command as a child of `ctx` the way the `task` tool does, and end the
+    /// turn with the child's result instead of a model reply.
+    async fn run_command_subtask(
+        &self,
+        ctx: Arc<TaskContext>,
+        model_id: &str,
+        request: TaskExecutionRequest,
+    ) -> TaskExecutorResult<()> {
+        let mut drop_guard = RunTaskLoopDropGuard::new(self.clone(), Arc::clone(&ctx));
+
+        match run_delegated_task(&ctx, request).await {
+            Ok(response) if matches!(response.status, SubtaskStatus::Cancelled) => {
+                ctx.set_status(AgentTaskStatus::Cancelled).await?;
+                ctx.cancel_assistant_message().await?;
+                if ctx.emits_task_events() {
+                    ctx.emit_event(TaskEvent::TaskCancelled {
+                        task_id: ctx.task_id.to_string(),
+                    })
+                    .await?;
+                }
+            }
+            // A child that failed is reported in its subtask block; the turn itself completed
+            Ok(_) => {
+                ctx.set_status(AgentTaskStatus::Completed).await?;
+                let context_usage = ctx.calculate_context_usage(model_id).await;
+                ctx.finish_assistant_message(MessageStatus::Completed, None, context_usage)
+                    .await?;
+                if ctx.emits_task_events() {
+                    ctx.emit_event(TaskEvent::TaskCompleted {
+                        task_id: ctx.task_id.to_string(),
+                    })
+                    .await?;
+                }
+            }
+            Err(e) => {
+                error!("Command subtask failed: {}", e);
+                ctx.set_status(AgentTaskStatus::Error).await?;
+                let error_block = ErrorBlock {
+                    code: "task.execution_error".to_string(),
+                    message: e.to_string(),
+                    details: None,
+                };
+                if let Err(err) = ctx.fail_assistant_message(error_block.clone()).await {
+                    warn!("Failed to persist command subtask error message: {}", err);
+                }
+                if ctx.emits_task_events() {
+                    ctx.emit_event(TaskEvent::TaskError {
+                        task_id: ctx.task_id.to_string(),
+                        error: error_block,
+                    })
+                    .await?;
+                }
+            }
+        }
+
+        ctx.abort();
+        ctx.tool_registry()
+            .cancel_pending_confirmations_for_task(&ctx, ctx.task_id.as_ref())
+            .await;
+        self.active_tasks().remove(ctx.task_id.as_ref());
+        drop_guard.disarm();
+
+        Ok(())
+    }
+
     /// Normalize task parameters:
     /// - Validate workspace_path is not empty (required)
     /// - Create new session when session_id = 0
@@ -846,6 +1055,13 @@ impl TaskExecutor {
                 .call(request)
                 .await
                 .map_err(|e| TaskExecutorError::LLMCallFailed(e.to_string()))?;
+            ctx.record_llm_usage(
+                model_id,
+                UsagePurpose::SubtaskSummary,
+                Some(parent_msg.id),
+                &resp.usage,
+            )
+            .await;
             let mut summary = extract_text_from_llm_message(&resp);
             summary = summary.trim().to_string();
             if summary.is_empty() {
@@ -924,6 +1140,18 @@ fn truncate_transcript(transcript: String, max_chars: usize) -> String {
     format!("{head}\n\n…\n\n{tail}").trim().to_string()
 }
 
+/// Stop hooks run when the agent is about to finish a user turn. A blocking hook keeps the
+/// agent going: its reason is sent back as the next user message.
+async fn run_stop_hooks(ctx: &TaskContext, stop_hook_active: bool) -> Option<String> {
+    // Subtasks report back to their parent; only the user-facing turn is "stopping".
+    if !ctx.emits_task_events() {
+        return None;
+    }
+    let hooks = ctx.tool_registry().hooks().cloned()?;
+    let input = HookInput::for_task(HookEvent::Stop, ctx).with_stop_hook_active(stop_hook_active);
+    hooks.run(input).await.blocked
+}
+
 fn tool_result_preview_text(result: &crate::agent::tools::ToolResult) -> String {
     result
         .content
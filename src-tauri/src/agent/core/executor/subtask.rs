@@ -9,7 +9,6 @@ use crate::agent::core::context::{
 use crate::agent::error::{TaskExecutorError, TaskExecutorResult};
 use crate::agent::permissions::PermissionDecision;
 use crate::agent::persistence::{AgentNodeRole, CreateAgentNodeParams, RunStatus};
-use crate::agent::tools::RunnableTool;
 use crate::agent::types::{Block, MessageRole, SubtaskStatus};
 use crate::git::service as git_service;
 use crate::storage::repositories::AIModels;
@@ -223,10 +222,7 @@ pub async fn run_subtask(
 
     let mcp_tools = executor
         .mcp_registry()
-        .get_tools_for_workspace(parent.cwd.as_ref())
-        .into_iter()
-        .map(|t| Arc::new(t) as Arc<dyn RunnableTool>)
-        .collect::<Vec<_>>();
+        .get_tools_for_workspace(parent.cwd.as_ref());
 
     // Tool access is controlled entirely by each agent's frontmatter configuration.
     // Level-1 (general) agents may spawn Level-2 functional agents via task permissions.
@@ -251,9 +247,12 @@ pub async fn run_subtask(
     let tool_registry = crate::agent::tools::create_tool_registry(
         "agent",
         effective.permissions,
+        effective.hooks,
+        effective.agent.sandbox,
         Some(merged_tool_filter),
         executor.tool_confirmations(),
         mcp_tools,
+        &workspace_root,
         executor.vector_search_engine(),
         Some(executor.lsp_manager()),
         skill_manager,
@@ -275,8 +274,8 @@ pub async fn run_subtask(
                 parent.cwd.to_string()
             }
         };
-        let branch = format!("opencodex/task-{child_session_id}");
-        let wt_dir = format!("{repo_root}/.git/opencodex-worktrees/{child_session_id}");
+        let branch = crate::agent::worktree::branch_name(child_session_id);
+        let wt_dir = crate::agent::worktree::worktree_dir(&repo_root, child_session_id);
         match crate::git::service::GitService::worktree_add(&repo_root, &branch, &wt_dir).await {
             Ok(wt_path) => {
                 if let Err(err) = executor
@@ -351,6 +350,7 @@ pub async fn run_subtask(
         // Mixed-view design: stream child agent tool/message events on the same channel, but
         // persist them to the child session. The UI merges sessions into one timeline.
         progress_channel,
+        budget: parent.run_budget(),
         deps: TaskContextDeps {
             tool_registry: Arc::clone(&tool_registry),
             repositories: executor.database(),
@@ -413,7 +413,17 @@ pub async fn run_subtask(
     executor.active_tasks().remove(&task_key);
     executor.decrement_active_child_executions_for_parent(parent.task_id.as_ref());
 
+    let budget_exceeded = matches!(
+        ctx.status().await,
+        crate::agent::core::status::AgentTaskStatus::BudgetExceeded
+    );
     let (status, runtime_error) = match run_result {
+        Ok(()) if budget_exceeded => (
+            SubtaskStatus::Error,
+            Some(TaskExecutorError::BudgetExceeded(
+                ctx.run_budget().usage().describe(),
+            )),
+        ),
         Ok(()) => (SubtaskStatus::Completed, None),
         Err(TaskExecutorError::TaskInterrupted) | Err(TaskExecutorError::TaskCancelled(_)) => {
             (SubtaskStatus::Cancelled, None)
@@ -9,6 +9,9 @@ pub enum AgentTaskStatus {
     Completed,
     Error,
     Cancelled,
+    /// Stopped because the run's token or cost budget ran out
+    #[serde(rename = "budget_exceeded")]
+    BudgetExceeded,
 }
 
 impl AgentTaskStatus {
@@ -20,6 +23,7 @@ impl AgentTaskStatus {
             Self::Completed => "completed",
             Self::Error => "error",
             Self::Cancelled => "cancelled",
+            Self::BudgetExceeded => "budget_exceeded",
         }
     }
 }
@@ -40,6 +40,8 @@ pub enum AgentError {
     InvalidSkillFormat(String),
     #[error("Skill not found: {0}")]
     SkillNotFound(String),
+    #[error("Git error: {0}")]
+    Git(String),
 }
 
 pub type AgentResult<T> = Result<T, AgentError>;
@@ -50,6 +52,12 @@ impl From<xmltree::ParseError> for AgentError {
     }
 }
 
+impl From<crate::git::GitError> for AgentError {
+    fn from(err: crate::git::GitError) -> Self {
+        AgentError::Git(err.message)
+    }
+}
+
 impl From<xmltree::Error> for AgentError {
     fn from(err: xmltree::Error) -> Self {
         AgentError::XmlSerialize(err.to_string())
@@ -121,6 +129,12 @@ pub enum TaskExecutorError {
     #[error("Invalid task state transition: {from} -> {to}")]
     InvalidStateTransition { from: String, to: String },
 
+    #[error("Blocked by {event} hook: {reason}")]
+    HookBlocked { event: String, reason: String },
+
+    #[error("Run budget exceeded: {0}")]
+    BudgetExceeded(String),
+
     #[error("Internal task executor error: {0}")]
     InternalError(String),
 }
@@ -147,6 +161,8 @@ impl TaskExecutorError {
             TaskExecutorError::TooManyActiveSubtasksGlobal { .. } => false,
             TaskExecutorError::TooManyActiveSubtasksPerParent { .. } => false,
             TaskExecutorError::InvalidStateTransition { .. } => false,
+            TaskExecutorError::HookBlocked { .. } => false,
+            TaskExecutorError::BudgetExceeded(_) => false,
             TaskExecutorError::InternalError(_) => false,
         }
     }
@@ -172,6 +188,8 @@ impl TaskExecutorError {
             TaskExecutorError::TooManyActiveSubtasksGlobal { .. } => ErrorSeverity::Warning,
             TaskExecutorError::TooManyActiveSubtasksPerParent { .. } => ErrorSeverity::Warning,
             TaskExecutorError::InvalidStateTransition { .. } => ErrorSeverity::Error,
+            TaskExecutorError::HookBlocked { .. } => ErrorSeverity::Info,
+            TaskExecutorError::BudgetExceeded(_) => ErrorSeverity::Warning,
             TaskExecutorError::InternalError(_) => ErrorSeverity::Critical,
         }
     }
@@ -297,7 +315,8 @@ impl From<AgentError> for TaskExecutorError {
             | AgentError::Parse(e)
             | AgentError::Internal(e)
             | AgentError::InvalidSkillFormat(e)
-            | AgentError::SkillNotFound(e) => TaskExecutorError::InternalError(e),
+            | AgentError::SkillNotFound(e)
+            | AgentError::Git(e) => TaskExecutorError::InternalError(e),
         }
     }
 }
@@ -318,7 +337,8 @@ impl From<AgentError> for ToolExecutorError {
             | AgentError::Parse(e)
             | AgentError::Internal(e)
             | AgentError::InvalidSkillFormat(e)
-            | AgentError::SkillNotFound(e) => ToolExecutorError::InternalError(e),
+            | AgentError::SkillNotFound(e)
+            | AgentError::Git(e) => ToolExecutorError::InternalError(e),
         }
     }
 }
@@ -0,0 +1,5 @@
+pub mod runner;
+pub mod types;
+
+pub use runner::HookRunner;
+pub use types::{HookDecision, HookEvent, HookInput, HookOutcome, HookOutput};
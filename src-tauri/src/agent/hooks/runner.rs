@@ -0,0 +1,289 @@
+/*!
+ * Runs user-configured hook commands
+ *
+ * Protocol: the event payload is written to stdin as JSON. Exit code 0 succeeds and stdout is
+ * either a `HookOutput` JSON object or plain text that becomes additional context. Exit code 2
+ * blocks, with stderr as the reason. Any other failure is logged and ignored.
+ */
+
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+use super::types::{HookDecision, HookEvent, HookInput, HookOutcome, HookOutput};
+use crate::agent::permissions::matches_simple_glob;
+use crate::settings::types::{HookConfig, HooksConfig};
+
+const DEFAULT_HOOK_TIMEOUT: Duration = Duration::from_secs(60);
+const BLOCKING_EXIT_CODE: i32 = 2;
+
+#[derive(Debug, Clone, Default)]
+pub struct HookRunner {
+    config: HooksConfig,
+}
+
+impl HookRunner {
+    pub fn new(config: HooksConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn has_hooks(&self, event: HookEvent) -> bool {
+        !self.hooks_for(event).is_empty()
+    }
+
+    fn hooks_for(&self, event: HookEvent) -> &[HookConfig] {
+        match event {
+            HookEvent::PreToolUse => &self.config.pre_tool_use,
+            HookEvent::PostToolUse => &self.config.post_tool_use,
+            HookEvent::UserPromptSubmit => &self.config.user_prompt_submit,
+            HookEvent::SessionStart => &self.config.session_start,
+            HookEvent::Stop => &self.config.stop,
+        }
+    }
+
+    /// Run every hook registered for the input's event, in order. The first blocking hook
+    /// stops the chain; argument rewrites are visible to the hooks that follow.
+    pub async fn run(&self, mut input: HookInput) -> HookOutcome {
+        let event = input.hook_event_name;
+        let mut outcome = HookOutcome::default();
+
+        for hook in self.hooks_for(event) {
+            if event.is_tool_event()
+                && !matches_tool(hook.matcher.as_deref(), input.tool_name.as_deref())
+            {
+                continue;
+            }
+
+            let output = match run_hook_command(hook, &input).await {
+                Ok(output) => output,
+                Err(err) => {
+                    warn!("{} hook `{}` failed: {}", event.as_str(), hook.command, err);
+                    continue;
+                }
+            };
+
+            if let Some(context) = output.additional_context.filter(|c| !c.trim().is_empty()) {
+                outcome.additional_context.push(context.trim().to_string());
+            }
+
+            if event == HookEvent::PreToolUse {
+                if let Some(updated) = output.updated_input {
+                    input.tool_input = Some(updated.clone());
+                    outcome.updated_input = Some(updated);
+                }
+            }
+
+            if output.decision == Some(HookDecision::Block) {
+                let reason = output
+                    .reason
+                    .filter(|r| !r.trim().is_empty())
+                    .unwrap_or_else(|| format!("blocked by hook `{}`", hook.command));
+                outcome.blocked = Some(reason);
+                break;
+            }
+        }
+
+        outcome
+    }
+}
+
+fn matches_tool(matcher: Option<&str>, tool_name: Option<&str>) -> bool {
+    let matcher = matcher.map(str::trim).unwrap_or_default();
+    if matcher.is_empty() || matcher == "*" {
+        return true;
+    }
+    let Some(tool_name) = tool_name else {
+        return false;
+    };
+    matcher
+        .split('|')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .any(|pattern| matches_simple_glob(pattern, tool_name))
+}
+
+fn shell_command(command: &str) -> Command {
+    if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    }
+}
+
+async fn run_hook_command(hook: &HookConfig, input: &HookInput) -> Result<HookOutput, String> {
+    let payload = serde_json::to_vec(input).map_err(|e| e.to_string())?;
+    let timeout = hook
+        .timeout
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_HOOK_TIMEOUT);
+
+    let mut cmd = shell_command(&hook.command);
+    if Path::new(&input.cwd).is_dir() {
+        cmd.current_dir(&input.cwd);
+    }
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = cmd.spawn().map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Written concurrently so a hook that prints before reading can't deadlock us.
+        // Hooks are free to ignore stdin, so a broken pipe is not an error.
+        tokio::spawn(async move {
+            let _ = stdin.write_all(&payload).await;
+        });
+    }
+
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| format!("timed out after {}s", timeout.as_secs()))?
+        .map_err(|e| e.to_string())?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    debug!(
+        "{} hook `{}` exited with {:?}",
+        input.hook_event_name.as_str(),
+        hook.command,
+        output.status.code()
+    );
+    parse_hook_output(output.status.code(), &stdout, &stderr)
+}
+
+fn parse_hook_output(
+    exit_code: Option<i32>,
+    stdout: &str,
+    stderr: &str,
+) -> Result<HookOutput, String> {
+    match exit_code {
+        Some(0) => {
+            let stdout = stdout.trim();
+            if stdout.starts_with('{') {
+                serde_json::from_str(stdout).map_err(|e| format!("invalid hook output: {e}"))
+            } else if stdout.is_empty() {
+                Ok(HookOutput::default())
+            } else {
+                Ok(HookOutput {
+                    additional_context: Some(stdout.to_string()),
+                    ..Default::default()
+                })
+            }
+        }
+        Some(BLOCKING_EXIT_CODE) => Ok(HookOutput {
+            decision: Some(HookDecision::Block),
+            reason: Some(stderr.trim().to_string()),
+            ..Default::default()
+        }),
+        Some(code) => Err(format!("exit code {code}: {}", stderr.trim())),
+        None => Err("terminated by signal".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn hook(matcher: Option<&str>, command: &str) -> HookConfig {
+        HookConfig {
+            matcher: matcher.map(str::to_string),
+            command: command.to_string(),
+            timeout: Some(5),
+        }
+    }
+
+    #[test]
+    fn test_matches_tool() {
+        assert!(matches_tool(None, Some("write_file")));
+        assert!(matches_tool(Some("*"), Some("shell")));
+        assert!(matches_tool(
+            Some("edit_file | write_file"),
+            Some("write_file")
+        ));
+        assert!(matches_tool(Some("mcp__*"), Some("mcp__github__search")));
+        assert!(!matches_tool(Some("edit_file"), Some("write_file")));
+        assert!(!matches_tool(Some("edit_file"), None));
+    }
+
+    #[test]
+    fn test_parse_hook_output() {
+        let out = parse_hook_output(Some(0), "", "").unwrap();
+        assert!(out.decision.is_none() && out.additional_context.is_none());
+
+        let out = parse_hook_output(Some(0), "formatted 2 files\n", "").unwrap();
+        assert_eq!(out.additional_context.as_deref(), Some("formatted 2 files"));
+
+        let out = parse_hook_output(
+            Some(0),
+            r#"{"decision":"block","reason":"nope","updatedInput":{"path":"a"}}"#,
+            "",
+        )
+        .unwrap();
+        assert_eq!(out.decision, Some(HookDecision::Block));
+        assert_eq!(out.reason.as_deref(), Some("nope"));
+        assert_eq!(out.updated_input, Some(json!({"path": "a"})));
+
+        let out = parse_hook_output(Some(2), "", "generated file\n").unwrap();
+        assert_eq!(out.decision, Some(HookDecision::Block));
+        assert_eq!(out.reason.as_deref(), Some("generated file"));
+
+        assert!(parse_hook_output(Some(1), "", "boom").is_err());
+        assert!(parse_hook_output(Some(0), "{not json", "").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_pre_tool_use_blocks_and_rewrites() {
+        let runner = HookRunner::new(HooksConfig {
+            pre_tool_use: vec![
+                hook(
+                    Some("write_file"),
+                    r#"echo '{"updatedInput":{"path":"src/lib.rs"},"additionalContext":"checked"}'"#,
+                ),
+                hook(
+                    Some("write_file"),
+                    r#"grep -q '"path":"src/lib.rs"' && echo 'generated' >&2 && exit 2"#,
+                ),
+                hook(None, "echo unreachable"),
+            ],
+            ..Default::default()
+        });
+
+        let input = HookInput::new(HookEvent::PreToolUse, 1, "/")
+            .with_tool("write_file", json!({"path": "gen/out.rs"}));
+        let outcome = runner.run(input).await;
+
+        assert_eq!(outcome.updated_input, Some(json!({"path": "src/lib.rs"})));
+        assert_eq!(outcome.blocked.as_deref(), Some("generated"));
+        assert_eq!(outcome.context_text().as_deref(), Some("checked"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failing_hook_is_ignored() {
+        let runner = HookRunner::new(HooksConfig {
+            post_tool_use: vec![
+                hook(Some("edit_file"), "exit 1"),
+                hook(Some("shell"), "echo skipped"),
+                hook(Some("edit_file"), "cat > /dev/null; echo done"),
+            ],
+            ..Default::default()
+        });
+
+        let input =
+            HookInput::new(HookEvent::PostToolUse, 1, "/").with_tool("edit_file", json!({}));
+        let outcome = runner.run(input).await;
+
+        assert!(outcome.blocked.is_none());
+        assert_eq!(outcome.additional_context, vec!["done".to_string()]);
+    }
+}
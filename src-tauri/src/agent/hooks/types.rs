@@ -0,0 +1,140 @@
+/*!
+ * Hook event payloads and results
+ */
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::agent::core::context::TaskContext;
+
+/// Lifecycle points users can attach hooks to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HookEvent {
+    PreToolUse,
+    PostToolUse,
+    UserPromptSubmit,
+    SessionStart,
+    Stop,
+}
+
+impl HookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::PreToolUse => "PreToolUse",
+            HookEvent::PostToolUse => "PostToolUse",
+            HookEvent::UserPromptSubmit => "UserPromptSubmit",
+            HookEvent::SessionStart => "SessionStart",
+            HookEvent::Stop => "Stop",
+        }
+    }
+
+    /// Tool events honour the hook's `matcher`; the others ignore it.
+    pub fn is_tool_event(&self) -> bool {
+        matches!(self, HookEvent::PreToolUse | HookEvent::PostToolUse)
+    }
+}
+
+/// JSON written to the hook command's stdin.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookInput {
+    pub hook_event_name: HookEvent,
+    pub session_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    pub cwd: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_input: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_response: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_hook_active: Option<bool>,
+}
+
+impl HookInput {
+    pub fn new(event: HookEvent, session_id: i64, cwd: impl Into<String>) -> Self {
+        Self {
+            hook_event_name: event,
+            session_id,
+            task_id: None,
+            cwd: cwd.into(),
+            tool_name: None,
+            tool_input: None,
+            tool_response: None,
+            prompt: None,
+            stop_hook_active: None,
+        }
+    }
+
+    pub fn for_task(event: HookEvent, context: &TaskContext) -> Self {
+        let mut input = Self::new(event, context.session_id, context.cwd.as_ref());
+        input.task_id = Some(context.task_id.to_string());
+        input
+    }
+
+    pub fn with_tool(mut self, name: &str, input: Value) -> Self {
+        self.tool_name = Some(name.to_string());
+        self.tool_input = Some(input);
+        self
+    }
+
+    pub fn with_tool_response(mut self, response: Value) -> Self {
+        self.tool_response = Some(response);
+        self
+    }
+
+    pub fn with_prompt(mut self, prompt: &str) -> Self {
+        self.prompt = Some(prompt.to_string());
+        self
+    }
+
+    pub fn with_stop_hook_active(mut self, active: bool) -> Self {
+        self.stop_hook_active = Some(active);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HookDecision {
+    Allow,
+    Block,
+}
+
+/// JSON a hook may print on stdout (exit code 0).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookOutput {
+    #[serde(default)]
+    pub decision: Option<HookDecision>,
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Replacement tool arguments (PreToolUse only)
+    #[serde(default)]
+    pub updated_input: Option<Value>,
+    #[serde(default)]
+    pub additional_context: Option<String>,
+}
+
+/// Combined result of every hook that ran for one event.
+#[derive(Debug, Clone, Default)]
+pub struct HookOutcome {
+    /// Reason given by the first hook that blocked
+    pub blocked: Option<String>,
+    pub updated_input: Option<Value>,
+    pub additional_context: Vec<String>,
+}
+
+impl HookOutcome {
+    pub fn context_text(&self) -> Option<String> {
+        if self.additional_context.is_empty() {
+            None
+        } else {
+            Some(self.additional_context.join("\n\n"))
+        }
+    }
+}
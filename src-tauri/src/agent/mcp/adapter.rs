@@ -1,15 +1,19 @@
 use async_trait::async_trait;
 use serde_json::Value;
 use std::sync::Arc;
+use tokio::sync::mpsc;
 
 use crate::agent::core::context::TaskContext;
 use crate::agent::error::ToolExecutorResult;
 use crate::agent::mcp::client::McpClient;
-use crate::agent::mcp::types::McpToolDefinition;
+use crate::agent::mcp::error::McpError;
+use crate::agent::mcp::server_requests::handle_server_request;
+use crate::agent::mcp::types::{McpCallEvent, McpToolDefinition};
 use crate::agent::tools::{
     RunnableTool, ToolCategory, ToolMetadata, ToolPriority, ToolResult, ToolResultContent,
     ToolResultStatus,
 };
+use crate::agent::types::TaskEvent;
 
 pub struct McpToolAdapter {
     client: Arc<McpClient>,
@@ -26,6 +30,44 @@ impl McpToolAdapter {
             qualified_name,
         }
     }
+
+    /// Surface server progress and log notifications of the running call to the
+    /// frontend, and answer the server's sampling/elicitation requests
+    async fn handle_call_event(&self, ctx: &TaskContext, event: McpCallEvent) {
+        let task_id = ctx.task_id.to_string();
+        let server = self.client.name().to_string();
+        let event = match event {
+            McpCallEvent::Request(request) => {
+                let response =
+                    handle_server_request(ctx, &server, &self.qualified_name, request).await;
+                if let Some(response) = response {
+                    if let Err(err) = self.client.respond(response).await {
+                        tracing::warn!(target: "mcp", server = %server, "Failed to answer MCP server request: {}", err);
+                    }
+                }
+                return;
+            }
+            McpCallEvent::Progress(progress) => TaskEvent::McpProgress {
+                task_id,
+                tool_name: self.qualified_name.clone(),
+                server,
+                progress: progress.progress,
+                total: progress.total,
+                message: progress.message,
+            },
+            McpCallEvent::Log(log) => TaskEvent::McpLog {
+                task_id,
+                tool_name: self.qualified_name.clone(),
+                server,
+                level: log.level,
+                logger: log.logger,
+                data: log.data,
+            },
+        };
+        if let Err(err) = ctx.emit_event(event).await {
+            tracing::warn!(target: "mcp", "Failed to emit MCP tool event: {}", err);
+        }
+    }
 }
 
 #[async_trait]
@@ -47,8 +89,25 @@ impl RunnableTool for McpToolAdapter {
             .with_tags(vec!["mcp".into(), self.client.name().into()])
     }
 
-    async fn run(&self, _ctx: &TaskContext, args: Value) -> ToolExecutorResult<ToolResult> {
-        let res = self.client.call_tool(&self.tool_def.name, args).await;
+    async fn run(&self, ctx: &TaskContext, args: Value) -> ToolExecutorResult<ToolResult> {
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        let cancel = ctx.create_stream_cancel_token();
+        let call = self
+            .client
+            .call_tool(&self.tool_def.name, args, events_tx, &cancel);
+        tokio::pin!(call);
+
+        let res = loop {
+            tokio::select! {
+                res = &mut call => break res,
+                Some(event) = events_rx.recv() => self.handle_call_event(ctx, event).await,
+            }
+        };
+        // Notifications read just before the response may still be queued
+        while let Ok(event) = events_rx.try_recv() {
+            self.handle_call_event(ctx, event).await;
+        }
+
         match res {
             Ok(call) => {
                 let serialized_content = match serde_json::to_string(&call.content) {
@@ -76,6 +135,25 @@ impl RunnableTool for McpToolAdapter {
                     })
                 }
             }
+            Err(McpError::Cancelled) => Ok(ToolResult {
+                content: vec![ToolResultContent::Error(
+                    "MCP tool call cancelled".to_string(),
+                )],
+                status: ToolResultStatus::Cancelled,
+                cancel_reason: Some("aborted".to_string()),
+                execution_time_ms: None,
+                ext_info: None,
+            }),
+            Err(e) if e.is_retryable() => Ok(ToolResult {
+                content: vec![ToolResultContent::Error(format!(
+                    "MCP server '{}' is unavailable and being restarted; retry this call shortly ({e})",
+                    self.client.name()
+                ))],
+                status: ToolResultStatus::Error,
+                cancel_reason: None,
+                execution_time_ms: None,
+                ext_info: Some(serde_json::json!({ "retryable": true })),
+            }),
             Err(e) => Ok(ToolResult {
                 content: vec![ToolResultContent::Error(format!("mcp call failed: {e}"))],
                 status: ToolResultStatus::Error,
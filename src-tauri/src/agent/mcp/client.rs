@@ -1,27 +1,102 @@
-use std::path::Path;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
 
+use parking_lot::RwLock;
 use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
 use crate::agent::mcp::error::{McpError, McpResult};
+use crate::agent::mcp::notifications::{ClientWork, ListChanged, NotificationRouter};
+use crate::agent::mcp::oauth::{McpAuthorizer, McpOAuth};
 use crate::agent::mcp::protocol::jsonrpc::{JsonRpcRequest, JsonRpcResponse};
 use crate::agent::mcp::transport::sse::SseTransport;
 use crate::agent::mcp::transport::stdio::{
     ensure_abs_workspace, expand_args, expand_env_map, StdioTransport,
 };
-use crate::agent::mcp::types::{McpCallResult, McpToolDefinition, ServerInfo};
+use crate::agent::mcp::transport::streamable_http::StreamableHttpTransport;
+use crate::agent::mcp::transport::RequestTimeout;
+use crate::agent::mcp::types::{
+    McpCallEvent, McpCallResult, McpGetPromptResult, McpPrompt, McpResource, McpResourceContents,
+    McpToolDefinition, ServerCapabilities, ServerInfo,
+};
 use crate::settings::types::McpServerConfig;
 
+#[derive(Clone)]
 enum Transport {
     Stdio(Arc<StdioTransport>),
     Sse(Arc<SseTransport>),
+    StreamableHttp(Arc<StreamableHttpTransport>),
+}
+
+impl Transport {
+    fn alloc_id(&self) -> i64 {
+        match self {
+            Transport::Stdio(t) => t.alloc_id(),
+            Transport::Sse(t) => t.alloc_id(),
+            Transport::StreamableHttp(t) => t.alloc_id(),
+        }
+    }
+
+    async fn request(
+        &self,
+        req: JsonRpcRequest,
+        timeout: &RequestTimeout,
+    ) -> McpResult<JsonRpcResponse> {
+        match self {
+            Transport::Stdio(t) => t.request(req, timeout).await,
+            Transport::Sse(t) => t.request(req, timeout).await,
+            Transport::StreamableHttp(t) => t.request(req, timeout).await,
+        }
+    }
+
+    async fn notify(&self, req: JsonRpcRequest) -> McpResult<()> {
+        match self {
+            Transport::Stdio(t) => t.notify(req).await,
+            Transport::Sse(t) => t.notify(req).await,
+            Transport::StreamableHttp(t) => t.notify(req).await,
+        }
+    }
+
+    async fn respond(&self, response: JsonRpcResponse) -> McpResult<()> {
+        match self {
+            Transport::Stdio(t) => t.respond(response).await,
+            Transport::Sse(t) => t.respond(response).await,
+            Transport::StreamableHttp(t) => t.respond(response).await,
+        }
+    }
+
+    async fn close(&self) -> McpResult<()> {
+        match self {
+            Transport::Stdio(t) => t.close().await,
+            Transport::Sse(t) => t.close().await,
+            Transport::StreamableHttp(t) => t.close().await,
+        }
+    }
+
+    fn is_connected(&self) -> bool {
+        match self {
+            Transport::Stdio(t) => t.is_connected(),
+            Transport::Sse(t) => t.is_connected(),
+            Transport::StreamableHttp(t) => t.is_connected(),
+        }
+    }
 }
 
 pub struct McpClient {
     name: String,
-    transport: Transport,
-    server_info: Option<ServerInfo>,
-    tools: Vec<McpToolDefinition>,
+    config: McpServerConfig,
+    workspace_root: PathBuf,
+    oauth: Option<Arc<McpOAuth>>,
+    /// `None` while the server is down and waiting to be reconnected
+    transport: RwLock<Option<Transport>>,
+    server_info: RwLock<Option<ServerInfo>>,
+    capabilities: RwLock<ServerCapabilities>,
+    tools: RwLock<Vec<McpToolDefinition>>,
+    resources: RwLock<Vec<McpResource>>,
+    prompts: RwLock<Vec<McpPrompt>>,
+    notifications: Arc<NotificationRouter>,
 }
 
 impl McpClient {
@@ -29,8 +104,62 @@ impl McpClient {
         name: String,
         config: &McpServerConfig,
         workspace_root: &Path,
-    ) -> McpResult<Self> {
-        let transport = match config {
+        oauth: Option<&Arc<McpOAuth>>,
+    ) -> McpResult<Arc<Self>> {
+        let (notifications, work) = NotificationRouter::new(name.clone());
+        let client = Self {
+            name,
+            config: config.clone(),
+            workspace_root: workspace_root.to_path_buf(),
+            oauth: oauth.cloned(),
+            transport: RwLock::new(None),
+            server_info: RwLock::new(None),
+            capabilities: RwLock::new(ServerCapabilities::default()),
+            tools: RwLock::new(Vec::new()),
+            resources: RwLock::new(Vec::new()),
+            prompts: RwLock::new(Vec::new()),
+            notifications,
+        };
+        client.connect().await?;
+
+        // Messages received during the handshake stay queued in the channel
+        let client = Arc::new(client);
+        spawn_work_loop(Arc::downgrade(&client), work);
+        Ok(client)
+    }
+
+    /// Start the transport, run the handshake and load the server's lists
+    async fn connect(&self) -> McpResult<()> {
+        let transport = self.open_transport().await?;
+        if let Err(err) = self.initialize(&transport).await {
+            if let Err(close_err) = transport.close().await {
+                tracing::debug!(target: "mcp", server = %self.name, "Failed to close MCP transport: {}", close_err);
+            }
+            return Err(err);
+        }
+        *self.transport.write() = Some(transport);
+
+        self.refresh_tools().await?;
+
+        // Resources and prompts are optional; a failure here should not drop the tools
+        let capabilities = self.capabilities.read().clone();
+        if capabilities.resources.is_some() {
+            if let Err(err) = self.refresh_resources().await {
+                tracing::warn!(target: "mcp", server = %self.name, "Failed to list MCP resources: {}", err);
+            }
+        }
+        if capabilities.prompts.is_some() {
+            if let Err(err) = self.refresh_prompts().await {
+                tracing::warn!(target: "mcp", server = %self.name, "Failed to list MCP prompts: {}", err);
+            }
+        }
+        Ok(())
+    }
+
+    async fn open_transport(&self) -> McpResult<Transport> {
+        let message_handler = self.notifications.handler();
+        let oauth = self.oauth.as_ref();
+        let transport = match &self.config {
             McpServerConfig::Stdio {
                 command,
                 args,
@@ -40,10 +169,17 @@ impl McpClient {
                 if *disabled {
                     return Err(McpError::Disabled);
                 }
-                let workspace_root = ensure_abs_workspace(workspace_root)?;
+                let workspace_root = ensure_abs_workspace(&self.workspace_root)?;
                 let args = expand_args(args, &workspace_root);
                 let env = expand_env_map(env, &workspace_root);
-                let t = StdioTransport::spawn(command, &args, &env, Some(&workspace_root)).await?;
+                let t = StdioTransport::spawn(
+                    command,
+                    &args,
+                    &env,
+                    Some(&workspace_root),
+                    message_handler,
+                )
+                .await?;
                 Transport::Stdio(Arc::new(t))
             }
             McpServerConfig::Sse {
@@ -54,43 +190,105 @@ impl McpClient {
                 if *disabled {
                     return Err(McpError::Disabled);
                 }
-                let t = SseTransport::new(url, headers).await?;
+                let auth = authorizer(oauth, url, headers);
+                let t = SseTransport::new(url, headers, message_handler, auth).await?;
                 Transport::Sse(Arc::new(t))
             }
-            McpServerConfig::StreamableHttp { disabled, .. } => {
+            McpServerConfig::StreamableHttp {
+                url,
+                headers,
+                disabled,
+            } => {
                 if *disabled {
                     return Err(McpError::Disabled);
                 }
-                return Err(McpError::UnsupportedTransport);
+                let auth = authorizer(oauth, url, headers);
+                let t = StreamableHttpTransport::new(url, headers, message_handler, auth).await?;
+                Transport::StreamableHttp(Arc::new(t))
             }
         };
+        Ok(transport)
+    }
 
-        let mut client = Self {
-            name,
-            transport,
-            server_info: None,
-            tools: Vec::new(),
+    /// Drop the current transport. Until `reconnect` succeeds, requests fail
+    /// with `McpError::Reconnecting`.
+    pub async fn disconnect(&self) {
+        let Some(transport) = self.transport.write().take() else {
+            return;
         };
+        if let Err(err) = transport.close().await {
+            tracing::debug!(target: "mcp", server = %self.name, "Failed to close MCP transport: {}", err);
+        }
+    }
 
-        client.initialize().await?;
-        client.refresh_tools().await?;
+    /// Restart the server in place; tools and adapters holding this client keep working
+    pub async fn reconnect(&self) -> McpResult<()> {
+        self.disconnect().await;
+        self.connect().await
+    }
 
-        Ok(client)
+    /// Whether the transport is up (child process alive, event stream open)
+    pub fn is_connected(&self) -> bool {
+        self.transport
+            .read()
+            .as_ref()
+            .is_some_and(Transport::is_connected)
+    }
+
+    /// Remote servers have no process to watch; a `ping` round trip shows they still answer
+    pub fn is_remote(&self) -> bool {
+        !matches!(self.config, McpServerConfig::Stdio { .. })
+    }
+
+    pub async fn ping(&self) -> McpResult<()> {
+        let resp = self
+            .request(JsonRpcRequest::new_request(0, "ping", None))
+            .await?;
+        parse_json_result(resp, "ping").map(|_| ())
     }
 
     pub fn name(&self) -> &str {
         &self.name
     }
 
-    pub fn tools(&self) -> &[McpToolDefinition] {
-        &self.tools
+    pub fn tools(&self) -> Vec<McpToolDefinition> {
+        self.tools.read().clone()
+    }
+
+    pub fn resources(&self) -> Vec<McpResource> {
+        self.resources.read().clone()
     }
 
-    pub async fn call_tool(&self, name: &str, arguments: Value) -> McpResult<McpCallResult> {
+    pub fn prompts(&self) -> Vec<McpPrompt> {
+        self.prompts.read().clone()
+    }
+
+    pub async fn read_resource(&self, uri: &str) -> McpResult<Vec<McpResourceContents>> {
+        let resp = self
+            .request(JsonRpcRequest::new_request(
+                0,
+                "resources/read",
+                Some(json!({ "uri": uri })),
+            ))
+            .await?;
+
+        let result = parse_json_result(resp, "resources/read")?;
+        let contents = result
+            .get("contents")
+            .cloned()
+            .ok_or_else(|| McpError::Protocol("resources/read missing contents".into()))?;
+        Ok(serde_json::from_value(contents)?)
+    }
+
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: serde_json::Map<String, Value>,
+    ) -> McpResult<McpGetPromptResult> {
         let resp = self
             .request(JsonRpcRequest::new_request(
                 0,
-                "tools/call",
+                "prompts/get",
                 Some(json!({
                     "name": name,
                     "arguments": arguments
@@ -98,66 +296,224 @@ impl McpClient {
             ))
             .await?;
 
-        parse_result(resp, "tools/call")
+        let result = parse_json_result(resp, "prompts/get")?;
+        Ok(serde_json::from_value(result)?)
     }
 
-    async fn request(&self, req: JsonRpcRequest) -> McpResult<JsonRpcResponse> {
-        match &self.transport {
-            Transport::Stdio(t) => t.request(req).await,
-            Transport::Sse(t) => t.request(req).await,
+    /// Call a tool. Progress and log notifications received while the call is
+    /// in flight are sent to `events`; cancelling `cancel` sends
+    /// `notifications/cancelled` to the server and returns `McpError::Cancelled`.
+    ///
+    /// The call times out after a minute without progress. Sampling and elicitation
+    /// requests sent to `events` pause that timeout until they are answered with
+    /// `respond`, since the server can't finish the call before then.
+    pub async fn call_tool(
+        &self,
+        name: &str,
+        arguments: Value,
+        events: mpsc::UnboundedSender<McpCallEvent>,
+        cancel: &CancellationToken,
+    ) -> McpResult<McpCallResult> {
+        self.call_tool_within(name, arguments, events, cancel, RequestTimeout::default())
+            .await
+    }
+
+    async fn call_tool_within(
+        &self,
+        name: &str,
+        arguments: Value,
+        events: mpsc::UnboundedSender<McpCallEvent>,
+        cancel: &CancellationToken,
+        timeout: RequestTimeout,
+    ) -> McpResult<McpCallResult> {
+        let transport = self.transport()?;
+        let id = transport.alloc_id();
+        let request = JsonRpcRequest::new_request(
+            id,
+            "tools/call",
+            Some(json!({
+                "name": name,
+                "arguments": arguments,
+                "_meta": { "progressToken": id }
+            })),
+        );
+
+        self.notifications
+            .register_call(id, events, timeout.clone());
+        let resp = tokio::select! {
+            resp = transport.request(request, &timeout) => resp,
+            _ = cancel.cancelled() => {
+                self.cancel_request(id, "Task cancelled by user").await;
+                Err(McpError::Cancelled)
+            }
+        };
+        self.notifications.finish_call(id);
+
+        parse_result(resp?, "tools/call")
+    }
+
+    async fn cancel_request(&self, id: i64, reason: &str) {
+        let notification = JsonRpcRequest::new_notification(
+            "notifications/cancelled",
+            Some(json!({ "requestId": id, "reason": reason })),
+        );
+        if let Err(err) = self.notify(notification).await {
+            tracing::warn!(target: "mcp", server = %self.name, "Failed to send MCP cancellation: {}", err);
         }
     }
 
+    /// Answer a request the server sent us
+    pub async fn respond(&self, response: JsonRpcResponse) -> McpResult<()> {
+        self.notifications.answered(&response.id);
+        self.transport()?.respond(response).await
+    }
+
+    fn transport(&self) -> McpResult<Transport> {
+        self.transport
+            .read()
+            .clone()
+            .ok_or_else(|| McpError::Reconnecting(self.name.clone()))
+    }
+
+    async fn request(&self, req: JsonRpcRequest) -> McpResult<JsonRpcResponse> {
+        self.transport()?
+            .request(req, &RequestTimeout::default())
+            .await
+    }
+
     async fn notify(&self, req: JsonRpcRequest) -> McpResult<()> {
-        match &self.transport {
-            Transport::Stdio(t) => t.notify(req).await,
-            Transport::Sse(t) => t.notify(req).await,
-        }
+        self.transport()?.notify(req).await
     }
 
-    async fn initialize(&mut self) -> McpResult<()> {
-        let resp = self
-            .request(JsonRpcRequest::new_request(
-                0,
-                "initialize",
-                Some(json!({
-                    "protocolVersion": "2025-11-25",
-                    "clientInfo": { "name": "OpenCodex", "version": env!("CARGO_PKG_VERSION") },
-                    "capabilities": {}
-                })),
-            ))
+    async fn initialize(&self, transport: &Transport) -> McpResult<()> {
+        let resp = transport
+            .request(
+                JsonRpcRequest::new_request(
+                    0,
+                    "initialize",
+                    Some(json!({
+                        "protocolVersion": "2025-11-25",
+                        "clientInfo": { "name": "OpenCodex", "version": env!("CARGO_PKG_VERSION") },
+                        "capabilities": {
+                            "sampling": {},
+                            "elicitation": {}
+                        }
+                    })),
+                ),
+                &RequestTimeout::default(),
+            )
             .await?;
 
         let result = parse_json_result(resp, "initialize")?;
         if let Some(info) = result.get("serverInfo") {
             let server_info: ServerInfo = serde_json::from_value(info.clone())?;
-            self.server_info = Some(server_info);
+            *self.server_info.write() = Some(server_info);
+        }
+        if let Some(capabilities) = result.get("capabilities") {
+            *self.capabilities.write() = serde_json::from_value(capabilities.clone())?;
         }
 
-        if let Err(err) = self
-            .notify(JsonRpcRequest::new_notification("initialized", None))
+        if let Err(err) = transport
+            .notify(JsonRpcRequest::new_notification(
+                "notifications/initialized",
+                None,
+            ))
             .await
         {
             tracing::warn!("Failed to send MCP initialized notification: {}", err);
         }
 
+        if let Transport::StreamableHttp(t) = transport {
+            t.start_listen_stream();
+        }
+
         Ok(())
     }
 
-    async fn refresh_tools(&mut self) -> McpResult<()> {
-        let resp = self
-            .request(JsonRpcRequest::new_request(0, "tools/list", None))
-            .await?;
+    async fn refresh_tools(&self) -> McpResult<()> {
+        let tools = self.list_all("tools/list", "tools").await?;
+        *self.tools.write() = tools;
+        Ok(())
+    }
 
-        let result = parse_json_result(resp, "tools/list")?;
-        let tools_val = result
-            .get("tools")
-            .ok_or_else(|| McpError::Protocol("tools/list missing tools".into()))?;
+    async fn refresh_resources(&self) -> McpResult<()> {
+        let resources = self.list_all("resources/list", "resources").await?;
+        *self.resources.write() = resources;
+        Ok(())
+    }
 
-        let tools: Vec<McpToolDefinition> = serde_json::from_value(tools_val.clone())?;
-        self.tools = tools;
+    async fn refresh_prompts(&self) -> McpResult<()> {
+        let prompts = self.list_all("prompts/list", "prompts").await?;
+        *self.prompts.write() = prompts;
         Ok(())
     }
+
+    /// Run a paginated `*/list` request, following `nextCursor` until exhausted
+    async fn list_all<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        key: &str,
+    ) -> McpResult<Vec<T>> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let params = cursor.as_ref().map(|c| json!({ "cursor": c }));
+            let resp = self
+                .request(JsonRpcRequest::new_request(0, method, params))
+                .await?;
+
+            let result = parse_json_result(resp, method)?;
+            let page = result
+                .get(key)
+                .cloned()
+                .ok_or_else(|| McpError::Protocol(format!("{method} missing {key}")))?;
+            items.extend(serde_json::from_value::<Vec<T>>(page)?);
+
+            cursor = result
+                .get("nextCursor")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            if cursor.is_none() {
+                return Ok(items);
+            }
+        }
+    }
+}
+
+/// Re-fetch lists the server reports as changed and send the router's replies,
+/// until the client is dropped
+fn spawn_work_loop(client: Weak<McpClient>, mut work: mpsc::UnboundedReceiver<ClientWork>) {
+    tokio::spawn(async move {
+        while let Some(item) = work.recv().await {
+            let Some(client) = client.upgrade() else {
+                return;
+            };
+            let change = match item {
+                ClientWork::Refresh(change) => change,
+                ClientWork::Reply(response) => {
+                    if let Err(err) = client.respond(response).await {
+                        tracing::warn!(target: "mcp", server = %client.name, "Failed to answer MCP server request: {}", err);
+                    }
+                    continue;
+                }
+            };
+            let result = match change {
+                ListChanged::Tools => client.refresh_tools().await,
+                ListChanged::Resources => client.refresh_resources().await,
+                ListChanged::Prompts => client.refresh_prompts().await,
+            };
+            if let Err(err) = result {
+                tracing::warn!(
+                    target: "mcp",
+                    server = %client.name,
+                    "Failed to refresh MCP {:?} list: {}",
+                    change,
+                    err
+                );
+            }
+        }
+    });
 }
 
 fn parse_json_result(resp: JsonRpcResponse, method: &str) -> McpResult<Value> {
@@ -176,3 +532,226 @@ fn parse_result(resp: JsonRpcResponse, method: &str) -> McpResult<McpCallResult>
     let call: McpCallResult = serde_json::from_value(result)?;
     Ok(call)
 }
+
+/// OAuth only applies when the config doesn't already supply credentials
+fn authorizer(
+    oauth: Option<&Arc<McpOAuth>>,
+    url: &str,
+    headers: &HashMap<String, String>,
+) -> Option<Arc<McpAuthorizer>> {
+    if headers
+        .keys()
+        .any(|name| name.eq_ignore_ascii_case("authorization"))
+    {
+        return None;
+    }
+    oauth.map(|oauth| oauth.authorizer(url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::mcp::transport::test_utils::{
+        http_response, json_reply, spawn_server, sse_event, sse_head, MockReply,
+    };
+    use std::collections::HashMap;
+    use std::sync::Mutex as StdMutex;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_call_tool_progress_and_cancel() {
+        // Keeps the tools/call stream open until the test ends
+        let open_stream = Arc::new(StdMutex::new(None));
+        let stream = Arc::clone(&open_stream);
+        let (url, requests) = spawn_server(move |req| match req.rpc_method() {
+            "initialize" => json_reply(&req.rpc_id(), json!({ "capabilities": {} }), &[]),
+            "tools/list" => json_reply(
+                &req.rpc_id(),
+                json!({ "tools": [{ "name": "slow", "inputSchema": { "type": "object" } }] }),
+                &[],
+            ),
+            "tools/call" => {
+                let token = req.body["params"]["_meta"]["progressToken"].clone();
+                let (tx, rx) = mpsc::unbounded_channel();
+                let _ = tx.send(sse_event(
+                    None,
+                    None,
+                    &json!({
+                        "jsonrpc": "2.0",
+                        "method": "notifications/progress",
+                        "params": { "progressToken": token, "progress": 1, "total": 3 }
+                    })
+                    .to_string(),
+                ));
+                *stream.lock().unwrap() = Some(tx);
+                MockReply::Stream(sse_head(), rx)
+            }
+            _ => MockReply::Full(http_response("202 Accepted", &[], "")),
+        })
+        .await;
+
+        let config = McpServerConfig::StreamableHttp {
+            url: format!("{url}/mcp"),
+            headers: HashMap::new(),
+            disabled: false,
+        };
+        let client = McpClient::new("slow-server".to_string(), &config, Path::new("/"), None)
+            .await
+            .unwrap();
+        assert_eq!(client.tools().len(), 1);
+
+        let (events_tx, mut events) = mpsc::unbounded_channel();
+        let cancel = CancellationToken::new();
+        let call = client.call_tool("slow", json!({}), events_tx, &cancel);
+        let cancel_on_progress = async {
+            let event = events.recv().await.unwrap();
+            cancel.cancel();
+            event
+        };
+        let (result, event) = tokio::join!(call, cancel_on_progress);
+
+        assert!(matches!(result, Err(McpError::Cancelled)));
+        match event {
+            McpCallEvent::Progress(progress) => assert_eq!(progress.total, Some(3.0)),
+            other => panic!("unexpected event: {other:?}"),
+        }
+
+        let requests = requests.lock().unwrap();
+        let init = requests
+            .iter()
+            .find(|r| r.rpc_method() == "initialize")
+            .unwrap();
+        assert_eq!(
+            init.body["params"]["capabilities"],
+            json!({ "sampling": {}, "elicitation": {} })
+        );
+        let call_id = requests
+            .iter()
+            .find(|r| r.rpc_method() == "tools/call")
+            .unwrap()
+            .rpc_id();
+        let cancelled = requests
+            .iter()
+            .find(|r| r.rpc_method() == "notifications/cancelled")
+            .unwrap();
+        assert_eq!(cancelled.body["params"]["requestId"], call_id);
+    }
+
+    #[tokio::test]
+    async fn test_sampling_request_pauses_call_timeout() {
+        // tools/call id and stream, kept until the server gets its sampling answer
+        let open_call = Arc::new(StdMutex::new(None));
+        let call = Arc::clone(&open_call);
+        let (url, _) = spawn_server(move |req| match req.rpc_method() {
+            "initialize" => json_reply(&req.rpc_id(), json!({ "capabilities": {} }), &[]),
+            "tools/list" => json_reply(&req.rpc_id(), json!({ "tools": [] }), &[]),
+            "tools/call" => {
+                let (tx, rx) = mpsc::unbounded_channel();
+                let _ = tx.send(sse_event(
+                    None,
+                    None,
+                    &json!({
+                        "jsonrpc": "2.0",
+                        "id": "sample-1",
+                        "method": "sampling/createMessage",
+                        "params": { "messages": [] }
+                    })
+                    .to_string(),
+                ));
+                *call.lock().unwrap() = Some((req.rpc_id(), tx));
+                MockReply::Stream(sse_head(), rx)
+            }
+            _ => {
+                // The call finishes shortly after the sampling answer arrives
+                if req.body["id"] == "sample-1" {
+                    if let Some((call_id, tx)) = call.lock().unwrap().take() {
+                        tokio::spawn(async move {
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            let _ = tx.send(sse_event(
+                                None,
+                                None,
+                                &json!({ "jsonrpc": "2.0", "id": call_id, "result": { "content": [] } })
+                                    .to_string(),
+                            ));
+                        });
+                    }
+                }
+                MockReply::Full(http_response("202 Accepted", &[], ""))
+            }
+        })
+        .await;
+
+        let config = McpServerConfig::StreamableHttp {
+            url: format!("{url}/mcp"),
+            headers: HashMap::new(),
+            disabled: false,
+        };
+        let client = McpClient::new("sampler".to_string(), &config, Path::new("/"), None)
+            .await
+            .unwrap();
+
+        // Handled like the tool adapter does: the call isn't polled while the user decides
+        let (events_tx, mut events) = mpsc::unbounded_channel();
+        let cancel = CancellationToken::new();
+        let timeout = RequestTimeout::new(Duration::from_millis(100));
+        let call = client.call_tool_within("sample", json!({}), events_tx, &cancel, timeout);
+        tokio::pin!(call);
+        let result = loop {
+            tokio::select! {
+                res = &mut call => break res,
+                Some(event) = events.recv() => {
+                    let McpCallEvent::Request(request) = event else {
+                        continue;
+                    };
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                    let answer = json!({ "role": "assistant", "content": { "type": "text", "text": "ok" } });
+                    client
+                        .respond(JsonRpcResponse::success(request.id.unwrap(), answer))
+                        .await
+                        .unwrap();
+                }
+            }
+        };
+
+        assert!(!result.unwrap().is_error);
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_in_place() {
+        let (url, requests) = spawn_server(|req| match req.rpc_method() {
+            "initialize" => json_reply(&req.rpc_id(), json!({ "capabilities": {} }), &[]),
+            "tools/list" => json_reply(&req.rpc_id(), json!({ "tools": [] }), &[]),
+            _ => MockReply::Full(http_response("202 Accepted", &[], "")),
+        })
+        .await;
+        let config = McpServerConfig::StreamableHttp {
+            url: format!("{url}/mcp"),
+            headers: HashMap::new(),
+            disabled: false,
+        };
+        let client = McpClient::new("flaky".to_string(), &config, Path::new("/"), None)
+            .await
+            .unwrap();
+        assert!(client.is_connected());
+
+        client.disconnect().await;
+        assert!(!client.is_connected());
+        let (events_tx, _events) = mpsc::unbounded_channel();
+        let err = client
+            .call_tool("any", json!({}), events_tx, &CancellationToken::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(&err, McpError::Reconnecting(name) if name == "flaky"));
+        assert!(err.is_retryable());
+
+        client.reconnect().await.unwrap();
+        assert!(client.is_connected());
+        let initializes = requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.rpc_method() == "initialize")
+            .count();
+        assert_eq!(initializes, 2);
+    }
+}
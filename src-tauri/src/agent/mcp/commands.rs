@@ -5,10 +5,11 @@ use tauri::State;
 
 use crate::agent::mcp::registry::McpRegistry;
 use crate::agent::mcp::types::{McpServerStatus, McpTestResult};
-use crate::api_success;
+use crate::llm::oauth::OAuthFlowInfo;
 use crate::settings::types::McpServerConfig;
 use crate::settings::SettingsManager;
-use crate::utils::TauriApiResult;
+use crate::utils::{EmptyData, TauriApiResult};
+use crate::{api_error, api_success};
 
 /// Get MCP server status list (requires workspace to be meaningful)
 #[tauri::command]
@@ -97,25 +98,33 @@ pub async fn test_mcp_server(
     name: String,
     config: McpServerConfig,
     workspace: Option<String>,
+    registry: State<'_, Arc<McpRegistry>>,
 ) -> TauriApiResult<McpTestResult> {
     let workspace_root = match workspace {
         Some(path) => PathBuf::from(path),
         None => std::env::temp_dir(),
     };
 
-    let result =
-        match crate::agent::mcp::client::McpClient::new(name, &config, &workspace_root).await {
-            Ok(client) => McpTestResult {
-                success: true,
-                tools_count: client.tools().len(),
-                error: None,
-            },
-            Err(e) => McpTestResult {
-                success: false,
-                tools_count: 0,
-                error: Some(e.to_string()),
-            },
-        };
+    let oauth = registry.oauth();
+    let result = match crate::agent::mcp::client::McpClient::new(
+        name,
+        &config,
+        &workspace_root,
+        Some(oauth),
+    )
+    .await
+    {
+        Ok(client) => McpTestResult {
+            success: true,
+            tools_count: client.tools().len(),
+            error: None,
+        },
+        Err(e) => McpTestResult {
+            success: false,
+            tools_count: 0,
+            error: Some(e.to_string()),
+        },
+    };
 
     Ok(api_success!(result))
 }
@@ -185,3 +194,65 @@ pub async fn reload_mcp_servers(
         registry.get_servers_status(Some(workspace_key.as_str()))
     ))
 }
+
+/// Start OAuth authorization for a remote MCP server. The frontend opens
+/// `authorizeUrl` and then calls `finish_mcp_oauth` with the flow id.
+#[tauri::command]
+pub async fn start_mcp_oauth(
+    workspace: Option<String>,
+    name: String,
+    registry: State<'_, Arc<McpRegistry>>,
+    settings_mgr: State<'_, Arc<SettingsManager>>,
+) -> TauriApiResult<OAuthFlowInfo> {
+    let workspace_root = workspace.map(PathBuf::from);
+    let effective = match settings_mgr.get_effective_settings(workspace_root).await {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::warn!(target: "mcp", error = %e, "Failed to load settings for MCP OAuth");
+            return Ok(api_error!("mcp.authorization_failed", "error" => e.to_string()));
+        }
+    };
+
+    let url = match effective.mcp_servers.get(&name) {
+        Some(McpServerConfig::Sse { url, .. } | McpServerConfig::StreamableHttp { url, .. }) => {
+            url.clone()
+        }
+        Some(McpServerConfig::Stdio { .. }) => return Ok(api_error!("mcp.oauth_unsupported")),
+        None => return Ok(api_error!("mcp.server_not_found", "name" => name)),
+    };
+
+    match registry.oauth().start_authorization(&url).await {
+        Ok(flow) => Ok(api_success!(flow)),
+        Err(e) => {
+            tracing::warn!(target: "mcp", server = %name, error = %e, "Failed to start MCP OAuth");
+            Ok(api_error!("mcp.authorization_failed", "error" => e.to_string()))
+        }
+    }
+}
+
+/// Wait for the browser callback of an MCP OAuth flow, store the tokens and
+/// reconnect the workspace's servers
+#[tauri::command]
+pub async fn finish_mcp_oauth(
+    workspace: Option<String>,
+    flow_id: String,
+    registry: State<'_, Arc<McpRegistry>>,
+    settings_mgr: State<'_, Arc<SettingsManager>>,
+) -> TauriApiResult<Vec<McpServerStatus>> {
+    if let Err(e) = registry.oauth().finish_authorization(&flow_id).await {
+        tracing::warn!(target: "mcp", error = %e, "MCP OAuth flow failed");
+        return Ok(api_error!("mcp.authorization_failed", "error" => e.to_string()));
+    }
+
+    reload_mcp_servers(workspace, registry, settings_mgr).await
+}
+
+/// Abandon an MCP OAuth flow (dialog closed before the callback arrived)
+#[tauri::command]
+pub async fn cancel_mcp_oauth(
+    flow_id: String,
+    registry: State<'_, Arc<McpRegistry>>,
+) -> TauriApiResult<EmptyData> {
+    registry.oauth().cancel_authorization(&flow_id).await;
+    Ok(api_success!())
+}
@@ -42,8 +42,31 @@ pub enum McpError {
     #[error("Workspace root is not absolute: {0}")]
     WorkspaceNotAbsolute(PathBuf),
 
-    #[error("Unsupported transport type (only stdio is supported)")]
-    UnsupportedTransport,
+    #[error("MCP session expired")]
+    SessionExpired,
+
+    #[error("MCP request cancelled")]
+    Cancelled,
+
+    #[error("MCP server requires authorization: {0}")]
+    AuthorizationRequired(String),
+
+    #[error("MCP authorization failed: {0}")]
+    OAuth(String),
+
+    #[error("MCP server '{0}' is reconnecting, retry shortly")]
+    Reconnecting(String),
+}
+
+impl McpError {
+    /// Failures caused by the server going away; the supervisor restarts it, so
+    /// the same call is expected to succeed later
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            McpError::Reconnecting(_) | McpError::Closed | McpError::NotConnected
+        )
+    }
 }
 
 pub type McpResult<T> = Result<T, McpError>;
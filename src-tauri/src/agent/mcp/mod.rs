@@ -2,8 +2,12 @@ pub mod adapter;
 pub mod client;
 pub mod commands;
 pub mod error;
+pub mod notifications;
+pub mod oauth;
 pub mod protocol;
 pub mod registry;
+pub mod resource_tool;
+pub mod server_requests;
 pub mod transport;
 pub mod types;
 
@@ -11,4 +15,5 @@ pub use adapter::McpToolAdapter;
 pub use client::McpClient;
 pub use error::{McpError, McpResult};
 pub use registry::McpRegistry;
+pub use resource_tool::McpResourceTool;
 pub use types::{McpServerStatus, McpTestResult};
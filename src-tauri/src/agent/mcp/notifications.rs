@@ -0,0 +1,345 @@
+//! Routing of MCP server notifications and requests
+//!
+//! Progress and log notifications are delivered synchronously from the transport
+//! reader to the tool calls in flight, so they can never overtake the response of
+//! the call they belong to. List changes are queued for the client to re-fetch.
+//!
+//! Sampling and elicitation requests need a task (its model, its confirmation UI),
+//! so they are handed to the most recent in-flight call, whose timeout stays paused
+//! until the request is answered. Anything the router can answer by itself (pings,
+//! unsupported methods) is queued as a reply.
+
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::agent::mcp::protocol::jsonrpc::{
+    JsonRpcId, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, INVALID_REQUEST,
+    METHOD_NOT_FOUND,
+};
+use crate::agent::mcp::transport::{
+    RequestTimeout, ServerMessage, ServerMessageHandler, TimeoutHold,
+};
+use crate::agent::mcp::types::{McpCallEvent, McpLogParams, McpProgressParams};
+
+/// Server-side list that changed and must be fetched again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListChanged {
+    Tools,
+    Resources,
+    Prompts,
+}
+
+/// Work the router hands back to the client
+#[derive(Debug, Clone)]
+pub enum ClientWork {
+    Refresh(ListChanged),
+    Reply(JsonRpcResponse),
+}
+
+/// A tool call in flight
+struct CallRoute {
+    events: mpsc::UnboundedSender<McpCallEvent>,
+    timeout: RequestTimeout,
+}
+
+pub struct NotificationRouter {
+    server: String,
+    /// In-flight tool calls keyed by request id (also used as progress token)
+    calls: DashMap<i64, CallRoute>,
+    /// Unanswered server requests, with the call they were handed to
+    server_requests: DashMap<JsonRpcId, (i64, TimeoutHold)>,
+    work: mpsc::UnboundedSender<ClientWork>,
+}
+
+impl NotificationRouter {
+    pub fn new(server: String) -> (Arc<Self>, mpsc::UnboundedReceiver<ClientWork>) {
+        let (work, rx) = mpsc::unbounded_channel();
+        let router = Arc::new(Self {
+            server,
+            calls: DashMap::new(),
+            server_requests: DashMap::new(),
+            work,
+        });
+        (router, rx)
+    }
+
+    pub fn handler(self: &Arc<Self>) -> ServerMessageHandler {
+        let router = Arc::clone(self);
+        Arc::new(move |message| match message {
+            ServerMessage::Notification(notification) => router.dispatch(notification),
+            ServerMessage::Request(request) => router.dispatch_request(request),
+        })
+    }
+
+    /// Route messages for the call `request_id` to `events`. Its progress restarts
+    /// `timeout`, and its server requests pause it until answered.
+    pub fn register_call(
+        &self,
+        request_id: i64,
+        events: mpsc::UnboundedSender<McpCallEvent>,
+        timeout: RequestTimeout,
+    ) {
+        self.calls.insert(request_id, CallRoute { events, timeout });
+    }
+
+    pub fn finish_call(&self, request_id: i64) {
+        self.calls.remove(&request_id);
+        self.server_requests
+            .retain(|_, (call, _)| *call != request_id);
+    }
+
+    /// The client answered the server request `id`
+    pub fn answered(&self, id: &JsonRpcId) {
+        self.server_requests.remove(id);
+    }
+
+    fn dispatch(&self, notification: JsonRpcNotification) {
+        let params = notification.params.unwrap_or(Value::Null);
+        match notification.method.as_str() {
+            "notifications/tools/list_changed" => self.queue_refresh(ListChanged::Tools),
+            "notifications/resources/list_changed" => self.queue_refresh(ListChanged::Resources),
+            "notifications/prompts/list_changed" => self.queue_refresh(ListChanged::Prompts),
+            "notifications/progress" => {
+                let progress: McpProgressParams = match serde_json::from_value(params) {
+                    Ok(progress) => progress,
+                    Err(err) => {
+                        tracing::warn!(target: "mcp", server = %self.server, "Invalid MCP progress notification: {}", err);
+                        return;
+                    }
+                };
+                let Some(token) = progress.progress_token.as_i64() else {
+                    return;
+                };
+                if let Some(call) = self.calls.get(&token) {
+                    call.timeout.touch();
+                    let _ = call.events.send(McpCallEvent::Progress(progress));
+                }
+            }
+            "notifications/message" => {
+                let log: McpLogParams = match serde_json::from_value(params) {
+                    Ok(log) => log,
+                    Err(err) => {
+                        tracing::warn!(target: "mcp", server = %self.server, "Invalid MCP log notification: {}", err);
+                        return;
+                    }
+                };
+                tracing::debug!(
+                    target: "mcp",
+                    server = %self.server,
+                    level = %log.level,
+                    logger = log.logger.as_deref().unwrap_or(""),
+                    "{}",
+                    log.data
+                );
+                // Log entries are not tied to a request; every in-flight call sees them
+                for call in self.calls.iter() {
+                    let _ = call.events.send(McpCallEvent::Log(log.clone()));
+                }
+            }
+            method => {
+                tracing::debug!(target: "mcp", server = %self.server, method = %method, "Unhandled MCP notification");
+            }
+        }
+    }
+
+    fn dispatch_request(&self, request: JsonRpcRequest) {
+        let Some(id) = request.id.clone() else {
+            return;
+        };
+        match request.method.as_str() {
+            "ping" => self.queue_work(ClientWork::Reply(JsonRpcResponse::success(
+                id,
+                Value::Object(Default::default()),
+            ))),
+            "sampling/createMessage" | "elicitation/create" => {
+                let method = request.method.clone();
+                // The server cannot tell us which call it acts for; the newest one is the
+                // best guess and, for servers that don't run calls concurrently, exact
+                let call = self.calls.iter().max_by_key(|call| *call.key());
+                let delivered = call.is_some_and(|call| {
+                    let hold = call.timeout.hold();
+                    let sent = call.events.send(McpCallEvent::Request(request)).is_ok();
+                    if sent {
+                        self.server_requests.insert(id.clone(), (*call.key(), hold));
+                    }
+                    sent
+                });
+                if !delivered {
+                    self.queue_work(ClientWork::Reply(JsonRpcResponse::error(
+                        id,
+                        INVALID_REQUEST,
+                        format!("{method} is only supported while a tool call is running"),
+                    )));
+                }
+            }
+            method => {
+                tracing::debug!(target: "mcp", server = %self.server, method = %method, "Unsupported MCP server request");
+                self.queue_work(ClientWork::Reply(JsonRpcResponse::error(
+                    id,
+                    METHOD_NOT_FOUND,
+                    format!("Method not found: {method}"),
+                )));
+            }
+        }
+    }
+
+    fn queue_refresh(&self, list: ListChanged) {
+        self.queue_work(ClientWork::Refresh(list));
+    }
+
+    fn queue_work(&self, work: ClientWork) {
+        if self.work.send(work).is_err() {
+            tracing::debug!(target: "mcp", server = %self.server, "MCP client gone, dropping server message");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn notification(method: &str, params: Value) -> ServerMessage {
+        ServerMessage::Notification(JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params: Some(params),
+        })
+    }
+
+    fn request(id: i64, method: &str, params: Value) -> ServerMessage {
+        ServerMessage::Request(JsonRpcRequest::new_request(id, method, Some(params)))
+    }
+
+    fn reply(work: ClientWork) -> JsonRpcResponse {
+        match work {
+            ClientWork::Reply(response) => response,
+            other => panic!("unexpected work: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_progress_routed_by_token() {
+        let (router, _changes) = NotificationRouter::new("docs".to_string());
+        let (first_tx, mut first) = mpsc::unbounded_channel();
+        let (second_tx, mut second) = mpsc::unbounded_channel();
+        router.register_call(1, first_tx, RequestTimeout::default());
+        router.register_call(2, second_tx, RequestTimeout::default());
+
+        let handler = router.handler();
+        handler(notification(
+            "notifications/progress",
+            json!({ "progressToken": 2, "progress": 5, "total": 10, "message": "indexing" }),
+        ));
+
+        assert!(first.try_recv().is_err());
+        match second.try_recv().unwrap() {
+            McpCallEvent::Progress(progress) => {
+                assert_eq!(progress.progress, 5.0);
+                assert_eq!(progress.total, Some(10.0));
+                assert_eq!(progress.message.as_deref(), Some("indexing"));
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+
+        router.finish_call(2);
+        handler(notification(
+            "notifications/progress",
+            json!({ "progressToken": 2, "progress": 6 }),
+        ));
+        assert!(second.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_log_sent_to_all_calls() {
+        let (router, _changes) = NotificationRouter::new("docs".to_string());
+        let (first_tx, mut first) = mpsc::unbounded_channel();
+        let (second_tx, mut second) = mpsc::unbounded_channel();
+        router.register_call(1, first_tx, RequestTimeout::default());
+        router.register_call(2, second_tx, RequestTimeout::default());
+
+        router.handler()(notification(
+            "notifications/message",
+            json!({ "level": "warning", "logger": "db", "data": "slow query" }),
+        ));
+
+        for rx in [&mut first, &mut second] {
+            match rx.try_recv().unwrap() {
+                McpCallEvent::Log(log) => {
+                    assert_eq!(log.level, "warning");
+                    assert_eq!(log.data, "slow query");
+                }
+                other => panic!("unexpected event: {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_list_changed_queued() {
+        let (router, mut changes) = NotificationRouter::new("docs".to_string());
+        let handler = router.handler();
+        handler(notification(
+            "notifications/tools/list_changed",
+            Value::Null,
+        ));
+        handler(notification(
+            "notifications/prompts/list_changed",
+            Value::Null,
+        ));
+
+        for expected in [ListChanged::Tools, ListChanged::Prompts] {
+            match changes.try_recv().unwrap() {
+                ClientWork::Refresh(list) => assert_eq!(list, expected),
+                other => panic!("unexpected work: {other:?}"),
+            }
+        }
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_sampling_request_sent_to_latest_call() {
+        let (router, mut work) = NotificationRouter::new("docs".to_string());
+        let (first_tx, mut first) = mpsc::unbounded_channel();
+        let (second_tx, mut second) = mpsc::unbounded_channel();
+        router.register_call(1, first_tx, RequestTimeout::default());
+        router.register_call(2, second_tx, RequestTimeout::default());
+
+        router.handler()(request(
+            7,
+            "sampling/createMessage",
+            json!({ "messages": [] }),
+        ));
+
+        assert!(first.try_recv().is_err());
+        match second.try_recv().unwrap() {
+            McpCallEvent::Request(request) => assert_eq!(request.method, "sampling/createMessage"),
+            other => panic!("unexpected event: {other:?}"),
+        }
+        assert!(work.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_requests_answered_by_router() {
+        let (router, mut work) = NotificationRouter::new("docs".to_string());
+        let handler = router.handler();
+
+        handler(request(1, "ping", json!({})));
+        let pong = reply(work.try_recv().unwrap());
+        assert_eq!(pong.result, Some(json!({})));
+
+        handler(request(
+            2,
+            "elicitation/create",
+            json!({ "message": "Name?" }),
+        ));
+        let no_call = reply(work.try_recv().unwrap());
+        assert_eq!(no_call.error.unwrap().code, INVALID_REQUEST);
+
+        handler(request(3, "roots/list", json!({})));
+        let unknown = reply(work.try_recv().unwrap());
+        assert_eq!(unknown.error.unwrap().code, METHOD_NOT_FOUND);
+    }
+}
@@ -0,0 +1,783 @@
+//! OAuth 2.1 authorization for remote MCP servers
+//!
+//! HTTP transports attach the bearer token of their server's `McpAuthorizer` and
+//! hand it every 401. Stored tokens are refreshed automatically; without usable
+//! tokens the server is reported as needing authorization and the user finishes
+//! the browser flow from settings (`start_authorization` / `finish_authorization`),
+//! which runs on the shared `OAuthManager` callback server.
+//!
+//! Endpoints come from protected-resource metadata (RFC 9728) and authorization
+//! server metadata (RFC 8414); the client registers itself dynamically (RFC 7591).
+//! Credentials are kept encrypted in app preferences, keyed by server URL.
+
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use dashmap::DashMap;
+use reqwest::header::WWW_AUTHENTICATE;
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::agent::mcp::error::{McpError, McpResult};
+use crate::llm::oauth::server::OAuthCallbackServer;
+use crate::llm::oauth::{OAuthError, OAuthFlowInfo, OAuthManager, PkceCodes};
+use crate::storage::repositories::AppPreferences;
+use crate::storage::DatabaseManager;
+
+const CLIENT_NAME: &str = "OpenCodex";
+/// Refresh access tokens this long before they expire
+const REFRESH_MARGIN_SECS: i64 = 60;
+
+/// Registered client and tokens for one MCP server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpCredentials {
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    pub token_endpoint: String,
+    /// Canonical server URL, sent as the RFC 8707 `resource` parameter
+    pub resource: String,
+    #[serde(default)]
+    pub access_token: Option<String>,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+}
+
+impl McpCredentials {
+    fn is_expiring(&self) -> bool {
+        self.expires_at
+            .is_some_and(|at| at - chrono::Utc::now().timestamp() < REFRESH_MARGIN_SECS)
+    }
+
+    fn apply(&mut self, grant: TokenGrant) {
+        self.access_token = Some(grant.access_token);
+        // Servers that don't rotate refresh tokens omit them from refresh responses
+        if grant.refresh_token.is_some() {
+            self.refresh_token = grant.refresh_token;
+        }
+        self.expires_at = grant
+            .expires_in
+            .map(|secs| chrono::Utc::now().timestamp() + secs);
+    }
+}
+
+/// Endpoints of the authorization server protecting an MCP server
+#[derive(Debug, Clone)]
+struct AuthServerEndpoints {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    registration_endpoint: Option<String>,
+    scope: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProtectedResourceMetadata {
+    #[serde(default)]
+    authorization_servers: Vec<String>,
+    #[serde(default)]
+    scopes_supported: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthServerMetadata {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    #[serde(default)]
+    registration_endpoint: Option<String>,
+    #[serde(default)]
+    code_challenge_methods_supported: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClientRegistration {
+    client_id: String,
+    #[serde(default)]
+    client_secret: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenGrant {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<i64>,
+}
+
+struct PendingAuthorization {
+    server_url: String,
+    redirect_uri: String,
+    credentials: McpCredentials,
+}
+
+/// Credential store and authorization flows for all MCP servers
+pub struct McpOAuth {
+    db: Arc<DatabaseManager>,
+    manager: Arc<OAuthManager>,
+    http: Client,
+    /// Last `WWW-Authenticate` challenge per server URL, used to start discovery
+    challenges: DashMap<String, String>,
+    /// Browser flows waiting for their callback, by flow id
+    pending: DashMap<String, PendingAuthorization>,
+}
+
+impl McpOAuth {
+    pub fn new(db: Arc<DatabaseManager>, manager: Arc<OAuthManager>) -> Self {
+        Self {
+            db,
+            manager,
+            http: Client::new(),
+            challenges: DashMap::new(),
+            pending: DashMap::new(),
+        }
+    }
+
+    pub fn authorizer(self: &Arc<Self>, server_url: &str) -> Arc<McpAuthorizer> {
+        Arc::new(McpAuthorizer {
+            oauth: Arc::clone(self),
+            server_url: server_url.to_string(),
+            state: tokio::sync::Mutex::new(AuthorizerState::default()),
+        })
+    }
+
+    /// Discover the authorization server, register a client and return the URL the
+    /// user has to open. Complete with `finish_authorization`.
+    pub async fn start_authorization(&self, server_url: &str) -> McpResult<OAuthFlowInfo> {
+        let challenge = self
+            .challenges
+            .get(server_url)
+            .map(|challenge| challenge.clone());
+        let endpoints = discover(&self.http, server_url, challenge.as_deref()).await?;
+        let redirect_uri = OAuthCallbackServer::callback_url();
+        let registration = register_client(&self.http, &endpoints, &redirect_uri).await?;
+
+        let credentials = McpCredentials {
+            client_id: registration.client_id,
+            client_secret: registration.client_secret,
+            token_endpoint: endpoints.token_endpoint.clone(),
+            resource: canonical_resource(server_url)?,
+            access_token: None,
+            refresh_token: None,
+            expires_at: None,
+        };
+
+        let flow = self
+            .manager
+            .start_pkce_flow("mcp", |pkce, state| {
+                authorize_url(&endpoints, &credentials, &redirect_uri, pkce, state)
+                    .map_err(|err| OAuthError::Other(err.to_string()))
+            })
+            .await
+            .map_err(|err| McpError::OAuth(err.to_string()))?;
+
+        self.pending.insert(
+            flow.flow_id.clone(),
+            PendingAuthorization {
+                server_url: server_url.to_string(),
+                redirect_uri,
+                credentials,
+            },
+        );
+        tracing::info!(target: "mcp", server = %server_url, "MCP OAuth flow started");
+        Ok(flow)
+    }
+
+    /// Wait for the browser callback of `flow_id`, exchange the code and store the tokens
+    pub async fn finish_authorization(&self, flow_id: &str) -> McpResult<()> {
+        let (_, pending) = self
+            .pending
+            .remove(flow_id)
+            .ok_or_else(|| McpError::OAuth(format!("Unknown authorization flow: {flow_id}")))?;
+        let (code, pkce) = self
+            .manager
+            .wait_for_code(flow_id)
+            .await
+            .map_err(|err| McpError::OAuth(err.to_string()))?;
+
+        let mut credentials = pending.credentials;
+        let grant = exchange_code(
+            &self.http,
+            &credentials,
+            &code,
+            &pkce,
+            &pending.redirect_uri,
+        )
+        .await?;
+        credentials.apply(grant);
+
+        self.save(&pending.server_url, &credentials).await?;
+        self.challenges.remove(&pending.server_url);
+        tracing::info!(target: "mcp", server = %pending.server_url, "MCP server authorized");
+        Ok(())
+    }
+
+    pub async fn cancel_authorization(&self, flow_id: &str) {
+        self.pending.remove(flow_id);
+        if let Err(err) = self.manager.cancel_flow(flow_id).await {
+            tracing::warn!(target: "mcp", "Failed to cancel MCP OAuth flow: {}", err);
+        }
+    }
+
+    async fn refresh(&self, server_url: &str, credentials: &mut McpCredentials) -> McpResult<()> {
+        let grant = refresh_tokens(&self.http, credentials).await?;
+        credentials.apply(grant);
+        self.save(server_url, credentials).await
+    }
+
+    async fn load(&self, server_url: &str) -> Option<McpCredentials> {
+        let key = credentials_preference_key(server_url);
+        let stored = match AppPreferences::new(&self.db).get(&key).await {
+            Ok(Some(stored)) => stored,
+            Ok(None) => return None,
+            Err(err) => {
+                tracing::warn!(target: "mcp", server = %server_url, "Failed to load MCP credentials: {}", err);
+                return None;
+            }
+        };
+
+        let decoded = async {
+            let encrypted = STANDARD.decode(stored.trim()).map_err(|e| e.to_string())?;
+            let json = self
+                .db
+                .decrypt_data(&encrypted)
+                .await
+                .map_err(|e| e.to_string())?;
+            serde_json::from_str::<McpCredentials>(&json).map_err(|e| e.to_string())
+        };
+        match decoded.await {
+            Ok(credentials) => Some(credentials),
+            Err(err) => {
+                tracing::warn!(target: "mcp", server = %server_url, "Discarding unreadable MCP credentials: {}", err);
+                None
+            }
+        }
+    }
+
+    async fn save(&self, server_url: &str, credentials: &McpCredentials) -> McpResult<()> {
+        let json = serde_json::to_string(credentials)?;
+        let encrypted = self
+            .db
+            .encrypt_data(&json)
+            .await
+            .map_err(|err| McpError::OAuth(format!("Failed to encrypt credentials: {err}")))?;
+        AppPreferences::new(&self.db)
+            .set(
+                &credentials_preference_key(server_url),
+                Some(&STANDARD.encode(encrypted)),
+            )
+            .await
+            .map_err(|err| McpError::OAuth(format!("Failed to store credentials: {err}")))
+    }
+}
+
+#[derive(Default)]
+struct AuthorizerState {
+    loaded: bool,
+    credentials: Option<McpCredentials>,
+}
+
+/// Bearer tokens for one MCP server, shared by the requests of its transport
+pub struct McpAuthorizer {
+    oauth: Arc<McpOAuth>,
+    server_url: String,
+    state: tokio::sync::Mutex<AuthorizerState>,
+}
+
+impl McpAuthorizer {
+    /// Current access token, refreshed first when it is about to expire
+    pub async fn access_token(&self) -> Option<String> {
+        let mut state = self.state.lock().await;
+        self.ensure_loaded(&mut state).await;
+        let credentials = state.credentials.as_mut()?;
+
+        if credentials.is_expiring() && credentials.refresh_token.is_some() {
+            if let Err(err) = self.oauth.refresh(&self.server_url, credentials).await {
+                tracing::warn!(target: "mcp", server = %self.server_url, "Failed to refresh MCP access token: {}", err);
+            }
+        }
+        credentials.access_token.clone()
+    }
+
+    /// The server rejected our token (or we had none). Succeeds when a refreshed
+    /// token is available for a retry; otherwise the user has to authorize.
+    pub async fn handle_unauthorized(&self, challenge: Option<String>) -> McpResult<()> {
+        if let Some(challenge) = challenge {
+            self.oauth
+                .challenges
+                .insert(self.server_url.clone(), challenge);
+        }
+
+        let mut state = self.state.lock().await;
+        self.ensure_loaded(&mut state).await;
+        if let Some(credentials) = state.credentials.as_mut() {
+            if credentials.refresh_token.is_some() {
+                match self.oauth.refresh(&self.server_url, credentials).await {
+                    Ok(()) => return Ok(()),
+                    Err(err) => {
+                        tracing::warn!(target: "mcp", server = %self.server_url, "MCP token refresh rejected: {}", err);
+                    }
+                }
+            }
+        }
+
+        Err(McpError::AuthorizationRequired(self.server_url.clone()))
+    }
+
+    async fn ensure_loaded(&self, state: &mut AuthorizerState) {
+        if !state.loaded {
+            state.credentials = self.oauth.load(&self.server_url).await;
+            state.loaded = true;
+        }
+    }
+}
+
+/// Send the request produced by `build` with the server's bearer token. On a 401
+/// the authorizer gets one chance to provide a new token before the retry.
+pub async fn send_authorized(
+    auth: Option<&McpAuthorizer>,
+    build: impl Fn() -> RequestBuilder,
+    on_error: impl Fn(reqwest::Error) -> McpError,
+) -> McpResult<Response> {
+    let Some(auth) = auth else {
+        return build().send().await.map_err(on_error);
+    };
+
+    let mut retried = false;
+    loop {
+        let mut builder = build();
+        if let Some(token) = auth.access_token().await {
+            builder = builder.bearer_auth(token);
+        }
+        let response = builder.send().await.map_err(&on_error)?;
+        if response.status() != StatusCode::UNAUTHORIZED || retried {
+            return Ok(response);
+        }
+
+        let challenge = response
+            .headers()
+            .get(WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        auth.handle_unauthorized(challenge).await?;
+        retried = true;
+    }
+}
+
+fn credentials_preference_key(server_url: &str) -> String {
+    let digest = blake3::hash(server_url.as_bytes());
+    format!("agent.mcp.oauth.{}", digest.to_hex())
+}
+
+async fn discover(
+    http: &Client,
+    server_url: &str,
+    challenge: Option<&str>,
+) -> McpResult<AuthServerEndpoints> {
+    let metadata_urls = match challenge.and_then(|c| challenge_param(c, "resource_metadata")) {
+        Some(url) => vec![url],
+        None => protected_resource_metadata_urls(server_url)?,
+    };
+    let mut resource_metadata: Option<ProtectedResourceMetadata> = None;
+    for url in metadata_urls {
+        if let Some(metadata) = fetch_json(http, &url).await {
+            resource_metadata = Some(metadata);
+            break;
+        }
+    }
+
+    // Servers without protected-resource metadata act as their own authorization server
+    let issuer = match resource_metadata
+        .as_ref()
+        .and_then(|metadata| metadata.authorization_servers.first())
+    {
+        Some(issuer) => issuer.clone(),
+        None => parse_url(server_url)?.origin().ascii_serialization(),
+    };
+
+    let mut server_metadata: Option<AuthServerMetadata> = None;
+    for url in authorization_server_metadata_urls(&issuer)? {
+        if let Some(metadata) = fetch_json(http, &url).await {
+            server_metadata = Some(metadata);
+            break;
+        }
+    }
+    let metadata = server_metadata.ok_or_else(|| {
+        McpError::OAuth(format!(
+            "No authorization server metadata found for {issuer}"
+        ))
+    })?;
+
+    if let Some(methods) = &metadata.code_challenge_methods_supported {
+        if !methods.iter().any(|method| method == "S256") {
+            return Err(McpError::OAuth(format!(
+                "Authorization server {issuer} does not support PKCE (S256)"
+            )));
+        }
+    }
+
+    let scope = challenge
+        .and_then(|c| challenge_param(c, "scope"))
+        .or_else(|| {
+            resource_metadata
+                .filter(|metadata| !metadata.scopes_supported.is_empty())
+                .map(|metadata| metadata.scopes_supported.join(" "))
+        });
+
+    Ok(AuthServerEndpoints {
+        authorization_endpoint: metadata.authorization_endpoint,
+        token_endpoint: metadata.token_endpoint,
+        registration_endpoint: metadata.registration_endpoint,
+        scope,
+    })
+}
+
+async fn fetch_json<T: DeserializeOwned>(http: &Client, url: &str) -> Option<T> {
+    let response = match http.get(url).send().await {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => {
+            tracing::debug!(target: "mcp", "OAuth metadata {} answered {}", url, response.status());
+            return None;
+        }
+        Err(err) => {
+            tracing::debug!(target: "mcp", "OAuth metadata {} failed: {}", url, err);
+            return None;
+        }
+    };
+    match response.json().await {
+        Ok(value) => Some(value),
+        Err(err) => {
+            tracing::debug!(target: "mcp", "Invalid OAuth metadata at {}: {}", url, err);
+            None
+        }
+    }
+}
+
+async fn register_client(
+    http: &Client,
+    endpoints: &AuthServerEndpoints,
+    redirect_uri: &str,
+) -> McpResult<ClientRegistration> {
+    let endpoint = endpoints.registration_endpoint.as_deref().ok_or_else(|| {
+        McpError::OAuth("Authorization server does not support dynamic client registration".into())
+    })?;
+
+    let response = http
+        .post(endpoint)
+        .json(&json!({
+            "client_name": CLIENT_NAME,
+            "redirect_uris": [redirect_uri],
+            "grant_types": ["authorization_code", "refresh_token"],
+            "response_types": ["code"],
+            "token_endpoint_auth_method": "none"
+        }))
+        .send()
+        .await
+        .map_err(|e| McpError::OAuth(format!("Client registration failed: {e}")))?;
+
+    let response = ensure_success(response, "Client registration").await?;
+    response
+        .json()
+        .await
+        .map_err(|e| McpError::OAuth(format!("Invalid client registration response: {e}")))
+}
+
+fn authorize_url(
+    endpoints: &AuthServerEndpoints,
+    credentials: &McpCredentials,
+    redirect_uri: &str,
+    pkce: &PkceCodes,
+    state: &str,
+) -> McpResult<String> {
+    let mut url = parse_url(&endpoints.authorization_endpoint)?;
+    {
+        let mut query = url.query_pairs_mut();
+        query
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &credentials.client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("code_challenge", &pkce.challenge)
+            .append_pair("code_challenge_method", "S256")
+            .append_pair("state", state)
+            .append_pair("resource", &credentials.resource);
+        if let Some(scope) = &endpoints.scope {
+            query.append_pair("scope", scope);
+        }
+    }
+    Ok(url.to_string())
+}
+
+async fn exchange_code(
+    http: &Client,
+    credentials: &McpCredentials,
+    code: &str,
+    pkce: &PkceCodes,
+    redirect_uri: &str,
+) -> McpResult<TokenGrant> {
+    request_tokens(
+        http,
+        credentials,
+        vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("code_verifier", &pkce.verifier),
+        ],
+        "Token exchange",
+    )
+    .await
+}
+
+async fn refresh_tokens(http: &Client, credentials: &McpCredentials) -> McpResult<TokenGrant> {
+    let refresh_token = credentials
+        .refresh_token
+        .as_deref()
+        .ok_or_else(|| McpError::OAuth("No refresh token".into()))?;
+    request_tokens(
+        http,
+        credentials,
+        vec![
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ],
+        "Token refresh",
+    )
+    .await
+}
+
+async fn request_tokens(
+    http: &Client,
+    credentials: &McpCredentials,
+    mut params: Vec<(&str, &str)>,
+    action: &str,
+) -> McpResult<TokenGrant> {
+    params.push(("client_id", &credentials.client_id));
+    params.push(("resource", &credentials.resource));
+    if let Some(secret) = credentials.client_secret.as_deref() {
+        params.push(("client_secret", secret));
+    }
+
+    let response = http
+        .post(&credentials.token_endpoint)
+        .form(&params)
+        .send()
+        .await
+        .map_err(|e| McpError::OAuth(format!("{action} failed: {e}")))?;
+
+    let response = ensure_success(response, action).await?;
+    response
+        .json()
+        .await
+        .map_err(|e| McpError::OAuth(format!("Invalid {action} response: {e}")))
+}
+
+async fn ensure_success(response: Response, action: &str) -> McpResult<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_else(|err| {
+        tracing::warn!(target: "mcp", "Failed to read {} error body: {}", action, err);
+        format!("<failed to read response body: {err}>")
+    });
+    Err(McpError::OAuth(format!(
+        "{action} failed with status {status}: {body}"
+    )))
+}
+
+fn parse_url(url: &str) -> McpResult<Url> {
+    Url::parse(url).map_err(|e| McpError::InvalidConfig(format!("Invalid url '{url}': {e}")))
+}
+
+fn canonical_resource(server_url: &str) -> McpResult<String> {
+    let mut url = parse_url(server_url)?;
+    url.set_fragment(None);
+    Ok(url.to_string())
+}
+
+/// Value of an auth-param in a `WWW-Authenticate` challenge (quoted or token form)
+fn challenge_param(challenge: &str, name: &str) -> Option<String> {
+    let mut rest = challenge;
+    while let Some(pos) = rest.find(name) {
+        let preceded_by_separator = rest[..pos]
+            .chars()
+            .last()
+            .is_none_or(|c| c == ' ' || c == ',');
+        let after = &rest[pos + name.len()..];
+        if preceded_by_separator {
+            if let Some(value) = after.trim_start().strip_prefix('=') {
+                let value = value.trim_start();
+                let value = match value.strip_prefix('"') {
+                    Some(quoted) => quoted.split('"').next().unwrap_or_default(),
+                    None => value.split([',', ' ']).next().unwrap_or_default(),
+                };
+                return Some(value.to_string());
+            }
+        }
+        rest = after;
+    }
+    None
+}
+
+/// RFC 9728 locations: path-specific first, then the origin root
+fn protected_resource_metadata_urls(server_url: &str) -> McpResult<Vec<String>> {
+    let url = parse_url(server_url)?;
+    let origin = url.origin().ascii_serialization();
+    let path = url.path().trim_end_matches('/');
+
+    let mut urls = Vec::new();
+    if !path.is_empty() {
+        urls.push(format!(
+            "{origin}/.well-known/oauth-protected-resource{path}"
+        ));
+    }
+    urls.push(format!("{origin}/.well-known/oauth-protected-resource"));
+    Ok(urls)
+}
+
+/// RFC 8414 and OpenID Connect discovery locations for an issuer
+fn authorization_server_metadata_urls(issuer: &str) -> McpResult<Vec<String>> {
+    let url = parse_url(issuer)?;
+    let origin = url.origin().ascii_serialization();
+    let path = url.path().trim_end_matches('/');
+
+    Ok(if path.is_empty() {
+        vec![
+            format!("{origin}/.well-known/oauth-authorization-server"),
+            format!("{origin}/.well-known/openid-configuration"),
+        ]
+    } else {
+        vec![
+            format!("{origin}/.well-known/oauth-authorization-server{path}"),
+            format!("{origin}/.well-known/openid-configuration{path}"),
+            format!("{origin}{path}/.well-known/openid-configuration"),
+        ]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::mcp::transport::test_utils::{http_response, spawn_server, MockReply};
+
+    fn json_response(body: serde_json::Value) -> MockReply {
+        MockReply::Full(http_response(
+            "200 OK",
+            &[("Content-Type", "application/json")],
+            &body.to_string(),
+        ))
+    }
+
+    #[test]
+    fn test_challenge_param() {
+        let challenge = r#"Bearer error="invalid_token", resource_metadata="https://mcp.example.com/.well-known/oauth-protected-resource", scope=files:read"#;
+        assert_eq!(
+            challenge_param(challenge, "resource_metadata").as_deref(),
+            Some("https://mcp.example.com/.well-known/oauth-protected-resource")
+        );
+        assert_eq!(
+            challenge_param(challenge, "scope").as_deref(),
+            Some("files:read")
+        );
+        assert_eq!(challenge_param(challenge, "realm"), None);
+    }
+
+    #[test]
+    fn test_well_known_urls() {
+        assert_eq!(
+            protected_resource_metadata_urls("https://mcp.example.com/v1/mcp").unwrap(),
+            vec![
+                "https://mcp.example.com/.well-known/oauth-protected-resource/v1/mcp",
+                "https://mcp.example.com/.well-known/oauth-protected-resource",
+            ]
+        );
+        assert_eq!(
+            authorization_server_metadata_urls("https://auth.example.com/tenant").unwrap(),
+            vec![
+                "https://auth.example.com/.well-known/oauth-authorization-server/tenant",
+                "https://auth.example.com/.well-known/openid-configuration/tenant",
+                "https://auth.example.com/tenant/.well-known/openid-configuration",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_discovery_registration_and_refresh() {
+        let base = Arc::new(std::sync::OnceLock::<String>::new());
+        let issuer = Arc::clone(&base);
+        let (url, requests) = spawn_server(move |req| {
+            let issuer = issuer.get().unwrap();
+            match req.path.as_str() {
+                "/.well-known/oauth-protected-resource/mcp" => json_response(json!({
+                    "resource": format!("{issuer}/mcp"),
+                    "authorization_servers": [issuer],
+                    "scopes_supported": ["tools", "resources"]
+                })),
+                "/.well-known/oauth-authorization-server" => json_response(json!({
+                    "issuer": issuer,
+                    "authorization_endpoint": format!("{issuer}/authorize"),
+                    "token_endpoint": format!("{issuer}/token"),
+                    "registration_endpoint": format!("{issuer}/register"),
+                    "code_challenge_methods_supported": ["S256"]
+                })),
+                "/register" => json_response(json!({ "client_id": "client-1" })),
+                "/token" => json_response(json!({ "access_token": "fresh", "expires_in": 3600 })),
+                _ => MockReply::Full(http_response("404 Not Found", &[], "")),
+            }
+        })
+        .await;
+        base.set(url.clone()).unwrap();
+        let http = Client::new();
+        let server_url = format!("{url}/mcp");
+
+        let endpoints = discover(&http, &server_url, None).await.unwrap();
+        assert_eq!(endpoints.token_endpoint, format!("{url}/token"));
+        assert_eq!(endpoints.scope.as_deref(), Some("tools resources"));
+
+        let registration = register_client(&http, &endpoints, "http://localhost/cb")
+            .await
+            .unwrap();
+        assert_eq!(registration.client_id, "client-1");
+
+        let mut credentials = McpCredentials {
+            client_id: registration.client_id,
+            client_secret: None,
+            token_endpoint: endpoints.token_endpoint.clone(),
+            resource: canonical_resource(&server_url).unwrap(),
+            access_token: Some("stale".into()),
+            refresh_token: Some("refresh-1".into()),
+            expires_at: Some(0),
+        };
+        assert!(credentials.is_expiring());
+
+        let pkce = PkceCodes {
+            verifier: "verifier".into(),
+            challenge: "challenge".into(),
+        };
+        let authorize =
+            authorize_url(&endpoints, &credentials, "http://localhost/cb", &pkce, "s1").unwrap();
+        assert!(authorize.starts_with(&format!("{url}/authorize?response_type=code")));
+        assert!(authorize.contains("code_challenge_method=S256"));
+        assert!(authorize.contains("scope=tools+resources"));
+
+        let grant = refresh_tokens(&http, &credentials).await.unwrap();
+        credentials.apply(grant);
+        assert_eq!(credentials.access_token.as_deref(), Some("fresh"));
+        // Not rotated by the server, so the old one stays usable
+        assert_eq!(credentials.refresh_token.as_deref(), Some("refresh-1"));
+        assert!(!credentials.is_expiring());
+
+        let requests = requests.lock().unwrap();
+        let registration = requests.iter().find(|r| r.path == "/register").unwrap();
+        assert_eq!(registration.body["token_endpoint_auth_method"], "none");
+        let refresh = requests.iter().find(|r| r.path == "/token").unwrap();
+        assert_eq!(
+            refresh.header("content-type"),
+            Some("application/x-www-form-urlencoded")
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum JsonRpcId {
     Number(i64),
@@ -11,6 +11,7 @@ pub enum JsonRpcId {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcRequest {
     pub jsonrpc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<JsonRpcId>,
     pub method: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -27,6 +28,14 @@ impl JsonRpcRequest {
         }
     }
 
+    /// Id chosen by the caller. Requests built with id `0` let the transport assign one.
+    pub fn assigned_id(&self) -> Option<i64> {
+        match self.id {
+            Some(JsonRpcId::Number(id)) if id > 0 => Some(id),
+            _ => None,
+        }
+    }
+
     pub fn new_notification(method: impl Into<String>, params: Option<Value>) -> Self {
         Self {
             jsonrpc: "2.0".to_string(),
@@ -37,6 +46,11 @@ impl JsonRpcRequest {
     }
 }
 
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcErrorObject {
     pub code: i64,
@@ -49,8 +63,62 @@ pub struct JsonRpcErrorObject {
 pub struct JsonRpcResponse {
     pub jsonrpc: String,
     pub id: JsonRpcId,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub result: Option<Value>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub error: Option<JsonRpcErrorObject>,
 }
+
+impl JsonRpcResponse {
+    pub fn success(id: JsonRpcId, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    pub fn error(id: JsonRpcId, code: i64, message: impl Into<String>) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(JsonRpcErrorObject {
+                code,
+                message: message.into(),
+                data: None,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default)]
+    pub params: Option<Value>,
+}
+
+/// A message received from an MCP server
+#[derive(Debug, Clone)]
+pub enum JsonRpcMessage {
+    Response(JsonRpcResponse),
+    Notification(JsonRpcNotification),
+    Request(JsonRpcRequest),
+}
+
+impl JsonRpcMessage {
+    pub fn from_value(val: Value) -> serde_json::Result<Self> {
+        let has_id = val.get("id").is_some_and(|id| !id.is_null());
+        if val.get("method").is_none() {
+            return serde_json::from_value(val).map(Self::Response);
+        }
+        if has_id {
+            serde_json::from_value(val).map(Self::Request)
+        } else {
+            serde_json::from_value(val).map(Self::Notification)
+        }
+    }
+}
@@ -1,35 +1,116 @@
 use dashmap::DashMap;
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
 
 use crate::agent::mcp::adapter::McpToolAdapter;
 use crate::agent::mcp::client::McpClient;
 use crate::agent::mcp::error::{McpError, McpResult};
+use crate::agent::mcp::oauth::McpOAuth;
+use crate::agent::mcp::resource_tool::McpResourceTool;
 use crate::agent::mcp::types::{
-    McpConnectionStatus, McpServerSource, McpServerStatus, McpToolInfo,
+    McpConnectionStatus, McpPrompt, McpServerSource, McpServerStatus, McpServerStatusEvent,
+    McpToolInfo,
 };
+use crate::agent::tools::RunnableTool;
 use crate::settings::types::{EffectiveSettings, McpServerConfig, Settings};
 
+/// How often the supervisor checks connected servers and due restarts
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Remote servers have no process to watch, so they are pinged at this interval
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+const RESTART_BASE_DELAY: Duration = Duration::from_secs(1);
+const RESTART_MAX_DELAY: Duration = Duration::from_secs(60);
+/// Consecutive failed restarts before the server is left in `Error`
+const MAX_RESTART_ATTEMPTS: u32 = 8;
+const STATUS_CHANNEL_CAPACITY: usize = 64;
+
 /// Stores a single client and its metadata
 struct ClientEntry {
     source: McpServerSource,
     status: McpConnectionStatus,
     error: Option<String>,
     client: Option<Arc<McpClient>>,
+    /// Config of a server whose first connect failed, kept so the supervisor can retry it
+    pending: Option<McpServerConfig>,
+    restart_attempts: u32,
+    next_restart: Option<Instant>,
+    last_ping: Instant,
+}
+
+impl ClientEntry {
+    fn new(
+        source: McpServerSource,
+        status: McpConnectionStatus,
+        error: Option<String>,
+        client: Option<Arc<McpClient>>,
+    ) -> Self {
+        Self {
+            source,
+            status,
+            error,
+            client,
+            pending: None,
+            restart_attempts: 0,
+            next_restart: None,
+            last_ping: Instant::now(),
+        }
+    }
+
+    /// A server that could not be started; it is retried with the same backoff as a crash
+    fn failed(source: McpServerSource, error: String, config: McpServerConfig) -> Self {
+        let mut entry = Self::new(source, McpConnectionStatus::Reconnecting, Some(error), None);
+        entry.pending = Some(config);
+        entry.next_restart = Some(Instant::now() + restart_delay(0));
+        entry
+    }
 }
 
 struct WorkspaceMcpState {
     servers: BTreeMap<String, ClientEntry>,
+    /// Stops the workspace's health supervisor when the state is replaced or removed
+    supervisor: CancellationToken,
 }
 
-#[derive(Default)]
+impl Drop for WorkspaceMcpState {
+    fn drop(&mut self) {
+        self.supervisor.cancel();
+    }
+}
+
+type Workspaces = DashMap<Arc<str>, WorkspaceMcpState>;
+
 pub struct McpRegistry {
     /// Workspace-specific MCP servers
-    workspaces: DashMap<Arc<str>, WorkspaceMcpState>,
+    workspaces: Arc<Workspaces>,
+    oauth: Arc<McpOAuth>,
+    status_tx: broadcast::Sender<McpServerStatusEvent>,
 }
 
 impl McpRegistry {
+    pub fn new(oauth: Arc<McpOAuth>) -> Self {
+        let (status_tx, _) = broadcast::channel(STATUS_CHANNEL_CAPACITY);
+        Self {
+            workspaces: Arc::new(DashMap::new()),
+            oauth,
+            status_tx,
+        }
+    }
+
+    /// Status changes made by the health supervisor (crashes, restarts, giving up)
+    pub fn subscribe_status(&self) -> broadcast::Receiver<McpServerStatusEvent> {
+        self.status_tx.subscribe()
+    }
+
+    /// OAuth credentials and authorization flows of remote servers
+    pub fn oauth(&self) -> &Arc<McpOAuth> {
+        &self.oauth
+    }
+
     async fn canonicalize_workspace_root(&self, workspace_root: &Path) -> PathBuf {
         match tokio::fs::canonicalize(workspace_root).await {
             Ok(path) => path,
@@ -90,89 +171,133 @@ impl McpRegistry {
                 McpServerSource::Global
             };
 
-            match McpClient::new(name.clone(), config, &workspace_root).await {
+            match McpClient::new(name.clone(), config, &workspace_root, Some(&self.oauth)).await {
                 Ok(client) => {
                     servers.insert(
                         name.clone(),
-                        ClientEntry {
+                        ClientEntry::new(
                             source,
-                            status: McpConnectionStatus::Connected,
-                            error: None,
-                            client: Some(Arc::new(client)),
-                        },
+                            McpConnectionStatus::Connected,
+                            None,
+                            Some(client),
+                        ),
                     );
                 }
                 Err(McpError::Disabled) => continue,
-                Err(McpError::UnsupportedTransport) => {
+                Err(e @ McpError::AuthorizationRequired(_)) => {
+                    tracing::info!(target: "mcp", server = %name, "MCP server requires authorization");
                     servers.insert(
                         name.clone(),
-                        ClientEntry {
+                        ClientEntry::new(
                             source,
-                            status: McpConnectionStatus::Error,
-                            error: Some(
-                                "Unsupported transport type (only stdio is supported)".to_string(),
-                            ),
-                            client: None,
-                        },
+                            McpConnectionStatus::NeedsAuth,
+                            Some(e.to_string()),
+                            None,
+                        ),
                     );
                 }
                 Err(e) => {
                     tracing::warn!(target: "mcp", server = %name, error = %e, "Failed to init MCP server");
                     servers.insert(
                         name.clone(),
-                        ClientEntry {
-                            source,
-                            status: McpConnectionStatus::Error,
-                            error: Some(e.to_string()),
-                            client: None,
-                        },
+                        ClientEntry::failed(source, e.to_string(), config.clone()),
                     );
                 }
             }
         }
 
         let workspace_key = Self::workspace_key(&workspace_root);
-        self.workspaces
-            .insert(workspace_key, WorkspaceMcpState { servers });
+        let supervisor = CancellationToken::new();
+        spawn_supervisor(
+            Arc::downgrade(&self.workspaces),
+            Arc::clone(&workspace_key),
+            Arc::clone(&self.oauth),
+            self.status_tx.clone(),
+            supervisor.clone(),
+        );
+        self.workspaces.insert(
+            workspace_key,
+            WorkspaceMcpState {
+                servers,
+                supervisor,
+            },
+        );
         Ok(())
     }
 
-    /// Get all available tools for workspace (workspace_key = canonical workspace path string)
-    pub fn get_tools_for_workspace(&self, workspace_key: &str) -> Vec<McpToolAdapter> {
-        let mut out = Vec::new();
+    /// Whether servers of this workspace have been initialized
+    pub fn has_workspace(&self, workspace_key: &str) -> bool {
         let normalized = Self::normalize_workspace_key(workspace_key);
+        self.workspaces.contains_key(normalized.as_str())
+            || self.workspaces.contains_key(workspace_key)
+    }
 
-        if let Some(workspace) = self.workspaces.get(normalized.as_str()) {
-            for entry in workspace.value().servers.values() {
-                let Some(client) = entry.client.as_ref() else {
-                    continue;
-                };
-                out.extend(
-                    client
-                        .tools()
-                        .iter()
-                        .cloned()
-                        .map(|tool| McpToolAdapter::new(Arc::clone(client), tool)),
-                );
-            }
-        } else if let Some(workspace) = self.workspaces.get(workspace_key) {
-            for entry in workspace.value().servers.values() {
-                let Some(client) = entry.client.as_ref() else {
-                    continue;
-                };
-                out.extend(
-                    client
-                        .tools()
-                        .iter()
-                        .cloned()
-                        .map(|tool| McpToolAdapter::new(Arc::clone(client), tool)),
-                );
-            }
+    /// Connected clients of a workspace (workspace_key = canonical workspace path string)
+    fn clients_for_workspace(&self, workspace_key: &str) -> Vec<Arc<McpClient>> {
+        let normalized = Self::normalize_workspace_key(workspace_key);
+        let workspace = match self.workspaces.get(normalized.as_str()) {
+            Some(workspace) => workspace,
+            None => match self.workspaces.get(workspace_key) {
+                Some(workspace) => workspace,
+                None => return Vec::new(),
+            },
+        };
+
+        workspace
+            .value()
+            .servers
+            .values()
+            .filter_map(|entry| entry.client.clone())
+            .collect()
+    }
+
+    /// Get all available tools for workspace, including the resource reader
+    /// when any server exposes resources
+    pub fn get_tools_for_workspace(&self, workspace_key: &str) -> Vec<Arc<dyn RunnableTool>> {
+        let clients = self.clients_for_workspace(workspace_key);
+
+        let mut out: Vec<Arc<dyn RunnableTool>> = clients
+            .iter()
+            .flat_map(|client| {
+                client
+                    .tools()
+                    .into_iter()
+                    .map(|tool| {
+                        Arc::new(McpToolAdapter::new(Arc::clone(client), tool))
+                            as Arc<dyn RunnableTool>
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        if let Some(resource_tool) = McpResourceTool::new(clients) {
+            out.push(Arc::new(resource_tool));
         }
 
         out
     }
 
+    /// Prompts of all connected servers, as `(server name, prompt)` pairs
+    pub fn get_prompts_for_workspace(&self, workspace_key: &str) -> Vec<(String, McpPrompt)> {
+        self.clients_for_workspace(workspace_key)
+            .iter()
+            .flat_map(|client| {
+                client
+                    .prompts()
+                    .into_iter()
+                    .map(|prompt| (client.name().to_string(), prompt))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Look up a connected client by server name
+    pub fn get_client(&self, workspace_key: &str, server: &str) -> Option<Arc<McpClient>> {
+        self.clients_for_workspace(workspace_key)
+            .into_iter()
+            .find(|client| client.name() == server)
+    }
+
     /// Get all server statuses (for frontend display)
     pub fn get_servers_status(&self, workspace_key: Option<&str>) -> Vec<McpServerStatus> {
         let mut statuses = Vec::new();
@@ -236,3 +361,297 @@ fn is_disabled(config: &McpServerConfig) -> bool {
         McpServerConfig::StreamableHttp { disabled, .. } => *disabled,
     }
 }
+
+/// What the supervisor does with a server on this tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SupervisorAction {
+    Check { ping: bool },
+    Restart,
+}
+
+fn next_action(entry: &ClientEntry, remote: bool, now: Instant) -> Option<SupervisorAction> {
+    match entry.status {
+        McpConnectionStatus::Connected => Some(SupervisorAction::Check {
+            ping: remote && now.duration_since(entry.last_ping) >= PING_INTERVAL,
+        }),
+        McpConnectionStatus::Reconnecting if entry.next_restart.is_none_or(|at| now >= at) => {
+            Some(SupervisorAction::Restart)
+        }
+        _ => None,
+    }
+}
+
+/// Exponential backoff between restarts, capped at `RESTART_MAX_DELAY`
+fn restart_delay(attempts: u32) -> Duration {
+    RESTART_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempts))
+        .min(RESTART_MAX_DELAY)
+}
+
+/// Update an entry with the outcome of a health check or restart
+fn apply_outcome(
+    entry: &mut ClientEntry,
+    action: SupervisorAction,
+    outcome: McpResult<()>,
+    now: Instant,
+) {
+    match (action, outcome) {
+        (SupervisorAction::Check { ping }, Ok(())) => {
+            if ping {
+                entry.last_ping = now;
+            }
+        }
+        (SupervisorAction::Restart, Ok(())) => {
+            entry.status = McpConnectionStatus::Connected;
+            entry.error = None;
+            entry.restart_attempts = 0;
+            entry.next_restart = None;
+            entry.last_ping = now;
+        }
+        (_, Err(err @ McpError::AuthorizationRequired(_))) => {
+            entry.status = McpConnectionStatus::NeedsAuth;
+            entry.error = Some(err.to_string());
+            entry.next_restart = None;
+        }
+        (SupervisorAction::Check { .. }, Err(err)) => {
+            entry.status = McpConnectionStatus::Reconnecting;
+            entry.error = Some(err.to_string());
+            entry.restart_attempts = 0;
+            entry.next_restart = Some(now + restart_delay(0));
+        }
+        (SupervisorAction::Restart, Err(err)) => {
+            entry.restart_attempts += 1;
+            entry.error = Some(err.to_string());
+            if entry.restart_attempts >= MAX_RESTART_ATTEMPTS {
+                entry.status = McpConnectionStatus::Error;
+                entry.next_restart = None;
+            } else {
+                entry.next_restart = Some(now + restart_delay(entry.restart_attempts));
+            }
+        }
+    }
+}
+
+async fn check_health(client: &McpClient, ping: bool) -> McpResult<()> {
+    if !client.is_connected() {
+        return Err(McpError::Closed);
+    }
+    if ping {
+        tokio::time::timeout(PING_TIMEOUT, client.ping())
+            .await
+            .map_err(|_| McpError::Timeout)??;
+    }
+    Ok(())
+}
+
+/// What the supervisor acts on: a running client, or the config of a server that never started
+enum SupervisedServer {
+    Client(Arc<McpClient>),
+    Pending(McpServerConfig),
+}
+
+/// Watch a workspace's servers: restart crashed ones and retry those that failed to
+/// start, with exponential backoff, and publish every status change
+fn spawn_supervisor(
+    workspaces: Weak<Workspaces>,
+    workspace_key: Arc<str>,
+    oauth: Arc<McpOAuth>,
+    status_tx: broadcast::Sender<McpServerStatusEvent>,
+    cancel: CancellationToken,
+) {
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => return,
+                _ = tokio::time::sleep(HEALTH_CHECK_INTERVAL) => {}
+            }
+            let Some(workspaces) = workspaces.upgrade() else {
+                return;
+            };
+            supervise_workspace(&workspaces, &workspace_key, &oauth, &status_tx, &cancel).await;
+        }
+    });
+}
+
+async fn supervise_workspace(
+    workspaces: &Workspaces,
+    workspace_key: &Arc<str>,
+    oauth: &Arc<McpOAuth>,
+    status_tx: &broadcast::Sender<McpServerStatusEvent>,
+    cancel: &CancellationToken,
+) {
+    let now = Instant::now();
+    // Collect work first; the map must not stay locked across the awaits below
+    let due: Vec<(String, SupervisedServer, SupervisorAction)> = {
+        let Some(workspace) = workspaces.get(workspace_key) else {
+            return;
+        };
+        workspace
+            .servers
+            .iter()
+            .filter_map(|(name, entry)| {
+                let (server, remote) = match (&entry.client, &entry.pending) {
+                    (Some(client), _) => {
+                        (SupervisedServer::Client(client.clone()), client.is_remote())
+                    }
+                    (None, Some(config)) => (SupervisedServer::Pending(config.clone()), false),
+                    (None, None) => return None,
+                };
+                let action = next_action(entry, remote, now)?;
+                Some((name.clone(), server, action))
+            })
+            .collect()
+    };
+
+    for (name, server, action) in due {
+        let mut started = None;
+        let outcome = match (server, action) {
+            (SupervisedServer::Pending(config), _) => {
+                let workspace_root = Path::new(workspace_key.as_ref());
+                match McpClient::new(name.clone(), &config, workspace_root, Some(oauth)).await {
+                    Ok(client) => {
+                        tracing::info!(target: "mcp", server = %name, "MCP server started");
+                        started = Some(client);
+                        Ok(())
+                    }
+                    Err(err) => {
+                        tracing::warn!(target: "mcp", server = %name, "MCP server start failed: {}", err);
+                        Err(err)
+                    }
+                }
+            }
+            (SupervisedServer::Client(client), SupervisorAction::Check { ping }) => {
+                let outcome = check_health(&client, ping).await;
+                if outcome.is_err() {
+                    tracing::warn!(target: "mcp", server = %name, "MCP server went away, restarting");
+                    client.disconnect().await;
+                }
+                outcome
+            }
+            (SupervisedServer::Client(client), SupervisorAction::Restart) => {
+                let outcome = client.reconnect().await;
+                match &outcome {
+                    Ok(()) => {
+                        tracing::info!(target: "mcp", server = %name, "MCP server reconnected")
+                    }
+                    Err(err) => {
+                        tracing::warn!(target: "mcp", server = %name, "MCP server restart failed: {}", err)
+                    }
+                }
+                outcome
+            }
+        };
+
+        if cancel.is_cancelled() {
+            return;
+        }
+        let Some(mut workspace) = workspaces.get_mut(workspace_key) else {
+            return;
+        };
+        let Some(entry) = workspace.servers.get_mut(&name) else {
+            continue;
+        };
+        if let Some(client) = started {
+            entry.client = Some(client);
+            entry.pending = None;
+        }
+        let previous = entry.status;
+        apply_outcome(entry, action, outcome, Instant::now());
+        if entry.status != previous {
+            // No subscribers is fine (e.g. headless runs)
+            let _ = status_tx.send(McpServerStatusEvent {
+                workspace: workspace_key.to_string(),
+                name,
+                status: entry.status,
+                error: entry.error.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(status: McpConnectionStatus) -> ClientEntry {
+        ClientEntry::new(McpServerSource::Global, status, None, None)
+    }
+
+    #[test]
+    fn test_restart_delay_backs_off_exponentially() {
+        assert_eq!(restart_delay(0), Duration::from_secs(1));
+        assert_eq!(restart_delay(3), Duration::from_secs(8));
+        assert_eq!(restart_delay(6), RESTART_MAX_DELAY);
+        assert_eq!(restart_delay(40), RESTART_MAX_DELAY);
+    }
+
+    #[test]
+    fn test_crash_restart_and_give_up() {
+        let mut server = entry(McpConnectionStatus::Connected);
+        let now = server.last_ping;
+        assert_eq!(
+            next_action(&server, false, now),
+            Some(SupervisorAction::Check { ping: false })
+        );
+        assert_eq!(
+            next_action(&server, true, now + PING_INTERVAL),
+            Some(SupervisorAction::Check { ping: true })
+        );
+
+        let check = SupervisorAction::Check { ping: false };
+        apply_outcome(&mut server, check, Err(McpError::Closed), now);
+        assert_eq!(server.status, McpConnectionStatus::Reconnecting);
+        assert_eq!(next_action(&server, false, now), None);
+        let due = now + restart_delay(0);
+        assert_eq!(
+            next_action(&server, false, due),
+            Some(SupervisorAction::Restart)
+        );
+
+        let restart = SupervisorAction::Restart;
+        apply_outcome(&mut server, restart, Err(McpError::Timeout), due);
+        assert_eq!(server.status, McpConnectionStatus::Reconnecting);
+        assert_eq!(server.next_restart, Some(due + restart_delay(1)));
+
+        apply_outcome(&mut server, restart, Ok(()), due);
+        assert_eq!(server.status, McpConnectionStatus::Connected);
+        assert_eq!(server.restart_attempts, 0);
+        assert!(server.error.is_none());
+
+        let mut failing = entry(McpConnectionStatus::Reconnecting);
+        for _ in 0..MAX_RESTART_ATTEMPTS {
+            apply_outcome(&mut failing, restart, Err(McpError::Timeout), now);
+        }
+        assert_eq!(failing.status, McpConnectionStatus::Error);
+        assert_eq!(next_action(&failing, false, now), None);
+
+        let config: McpServerConfig = serde_json::from_value(serde_json::json!({
+            "type": "stdio",
+            "command": "slow-server"
+        }))
+        .unwrap();
+        let mut never_started =
+            ClientEntry::failed(McpServerSource::Workspace, "spawn failed".into(), config);
+        assert_eq!(next_action(&never_started, false, now), None);
+        let first_retry = never_started.next_restart.unwrap();
+        assert_eq!(
+            next_action(&never_started, false, first_retry),
+            Some(SupervisorAction::Restart)
+        );
+        apply_outcome(
+            &mut never_started,
+            restart,
+            Err(McpError::Timeout),
+            first_retry,
+        );
+        assert_eq!(
+            never_started.next_restart,
+            Some(first_retry + restart_delay(1))
+        );
+
+        let mut remote = entry(McpConnectionStatus::Connected);
+        let unauthorized = Err(McpError::AuthorizationRequired("https://mcp".into()));
+        apply_outcome(&mut remote, check, unauthorized, now);
+        assert_eq!(remote.status, McpConnectionStatus::NeedsAuth);
+    }
+}
@@ -0,0 +1,196 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::agent::core::context::TaskContext;
+use crate::agent::error::{ToolExecutorError, ToolExecutorResult};
+use crate::agent::mcp::client::McpClient;
+use crate::agent::tools::{
+    RunnableTool, ToolCategory, ToolMetadata, ToolPriority, ToolResult, ToolResultContent,
+    ToolResultStatus,
+};
+
+const TOOL_NAME: &str = "read_mcp_resource";
+/// Resources listed inline in the tool description; the rest are reachable by listing
+const MAX_LISTED_RESOURCES: usize = 50;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReadMcpResourceArgs {
+    server: Option<String>,
+    uri: Option<String>,
+}
+
+/// Exposes `resources/list` and `resources/read` of all connected MCP servers as one tool
+pub struct McpResourceTool {
+    clients: Vec<Arc<McpClient>>,
+    description: String,
+}
+
+impl McpResourceTool {
+    /// Returns `None` when no connected server exposes resources
+    pub fn new(clients: Vec<Arc<McpClient>>) -> Option<Self> {
+        let clients: Vec<_> = clients
+            .into_iter()
+            .filter(|c| !c.resources().is_empty())
+            .collect();
+        if clients.is_empty() {
+            return None;
+        }
+
+        let mut description = String::from(
+            r#"Reads a resource (documentation, files, records) exposed by a connected MCP server.
+
+Usage:
+- Without uri: lists available resources (optionally only those of `server`)
+- With uri: reads the resource and returns its contents
+- Pass `server` when the same uri could come from several servers
+
+Available resources:"#,
+        );
+        let listed: Vec<_> = clients
+            .iter()
+            .flat_map(|c| c.resources().into_iter().map(move |r| (c.name(), r)))
+            .collect();
+        for (server, resource) in listed.iter().take(MAX_LISTED_RESOURCES) {
+            description.push_str(&format!(
+                "\n- [{server}] {} ({})",
+                resource.uri, resource.name
+            ));
+        }
+        if listed.len() > MAX_LISTED_RESOURCES {
+            description.push_str("\n- ... (call without uri to list all)");
+        }
+
+        Some(Self {
+            clients,
+            description,
+        })
+    }
+
+    fn list(&self, server: Option<&str>) -> String {
+        let mut lines = Vec::new();
+        for client in self
+            .clients
+            .iter()
+            .filter(|c| server.is_none_or(|s| c.name() == s))
+        {
+            for resource in client.resources() {
+                let mut line = format!("[{}] {} - {}", client.name(), resource.uri, resource.name);
+                if let Some(description) = resource.description.as_deref() {
+                    line.push_str(&format!(": {description}"));
+                }
+                lines.push(line);
+            }
+        }
+        if lines.is_empty() {
+            "No MCP resources available.".to_string()
+        } else {
+            lines.join("\n")
+        }
+    }
+
+    fn resolve_client(&self, server: Option<&str>, uri: &str) -> Option<&Arc<McpClient>> {
+        if let Some(server) = server {
+            return self.clients.iter().find(|c| c.name() == server);
+        }
+        self.clients
+            .iter()
+            .find(|c| c.resources().iter().any(|r| r.uri == uri))
+            .or_else(|| self.clients.first().filter(|_| self.clients.len() == 1))
+    }
+}
+
+#[async_trait]
+impl RunnableTool for McpResourceTool {
+    fn name(&self) -> &str {
+        TOOL_NAME
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "server": {
+                    "type": "string",
+                    "description": "MCP server name. Optional when the uri is unambiguous."
+                },
+                "uri": {
+                    "type": "string",
+                    "description": "Resource URI to read. Omit to list available resources."
+                }
+            }
+        })
+    }
+
+    fn metadata(&self) -> ToolMetadata {
+        ToolMetadata::new(ToolCategory::FileRead, ToolPriority::Standard)
+            .with_tags(vec!["mcp".into(), "resource".into()])
+    }
+
+    async fn run(&self, _ctx: &TaskContext, args: Value) -> ToolExecutorResult<ToolResult> {
+        let args: ReadMcpResourceArgs = serde_json::from_value(args)?;
+        let server = args.server.as_deref().filter(|s| !s.trim().is_empty());
+
+        let Some(uri) = args.uri.as_deref().filter(|u| !u.trim().is_empty()) else {
+            return Ok(ToolResult {
+                content: vec![ToolResultContent::Success(self.list(server))],
+                status: ToolResultStatus::Success,
+                cancel_reason: None,
+                execution_time_ms: None,
+                ext_info: None,
+            });
+        };
+
+        let client = self.resolve_client(server, uri).ok_or_else(|| {
+            ToolExecutorError::InvalidArguments {
+                tool_name: TOOL_NAME.to_string(),
+                error: format!("No MCP server found for resource '{uri}'"),
+            }
+        })?;
+
+        match client.read_resource(uri).await {
+            Ok(contents) => {
+                let text = contents
+                    .iter()
+                    .map(|c| match (&c.text, &c.blob) {
+                        (Some(text), _) => text.clone(),
+                        (None, Some(blob)) => format!(
+                            "<binary content: {}, {} base64 bytes>",
+                            c.mime_type.as_deref().unwrap_or("application/octet-stream"),
+                            blob.len()
+                        ),
+                        (None, None) => String::new(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+
+                Ok(ToolResult {
+                    content: vec![ToolResultContent::Success(text)],
+                    status: ToolResultStatus::Success,
+                    cancel_reason: None,
+                    execution_time_ms: None,
+                    ext_info: Some(json!({
+                        "server": client.name(),
+                        "uri": uri,
+                        "contents": contents.len(),
+                    })),
+                })
+            }
+            Err(e) => Ok(ToolResult {
+                content: vec![ToolResultContent::Error(format!(
+                    "mcp resource read failed: {e}"
+                ))],
+                status: ToolResultStatus::Error,
+                cancel_reason: None,
+                execution_time_ms: None,
+                ext_info: None,
+            }),
+        }
+    }
+}
@@ -0,0 +1,360 @@
+//! Requests an MCP server sends back while one of its tools is running
+//!
+//! `sampling/createMessage` lets the server borrow the session's LLM and
+//! `elicitation/create` asks the user for structured input. Both go through the
+//! confirmation dialog of the task that issued the tool call.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::agent::common::llm_text::extract_text_from_llm_message;
+use crate::agent::common::text::truncate_chars;
+use crate::agent::core::context::TaskContext;
+use crate::agent::mcp::protocol::jsonrpc::{
+    JsonRpcErrorObject, JsonRpcRequest, JsonRpcResponse, INTERNAL_ERROR, INVALID_PARAMS,
+    METHOD_NOT_FOUND,
+};
+use crate::agent::persistence::UsagePurpose;
+use crate::agent::tools::registry::ToolConfirmationDecision;
+use crate::llm::anthropic_types::{
+    ContentBlock, CreateMessageRequest, ImageSource, Message, MessageContent, MessageParam,
+    MessageRole, StopReason, SystemPrompt,
+};
+use crate::llm::service::LLMService;
+
+/// Permission under which "always allow" for sampling is remembered (pattern = server name)
+const SAMPLING_PERMISSION: &str = "mcp_sampling";
+/// Error code the MCP spec uses when the user rejects a sampling request
+const USER_REJECTED: i64 = -1;
+/// Used when the server leaves `maxTokens` out
+const DEFAULT_SAMPLING_MAX_TOKENS: u32 = 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateMessageParams {
+    messages: Vec<SamplingMessage>,
+    #[serde(default)]
+    system_prompt: Option<String>,
+    #[serde(default)]
+    max_tokens: Option<u32>,
+    #[serde(default)]
+    temperature: Option<f64>,
+    #[serde(default)]
+    stop_sequences: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SamplingMessage {
+    role: MessageRole,
+    content: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ElicitParams {
+    message: String,
+    #[serde(default)]
+    requested_schema: Value,
+}
+
+/// Answer a server request forwarded to the tool call `tool_name` of `server`.
+/// Returns `None` for messages without an id, which need no answer.
+pub async fn handle_server_request(
+    ctx: &TaskContext,
+    server: &str,
+    tool_name: &str,
+    request: JsonRpcRequest,
+) -> Option<JsonRpcResponse> {
+    let id = request.id?;
+    let params = request.params.unwrap_or(Value::Null);
+    let result = match request.method.as_str() {
+        "sampling/createMessage" => create_message(ctx, server, tool_name, params).await,
+        "elicitation/create" => elicit(ctx, server, tool_name, params).await,
+        method => Err(rpc_error(
+            METHOD_NOT_FOUND,
+            format!("Method not found: {method}"),
+        )),
+    };
+
+    Some(match result {
+        Ok(result) => JsonRpcResponse::success(id, result),
+        Err(err) => JsonRpcResponse::error(id, err.code, err.message),
+    })
+}
+
+async fn create_message(
+    ctx: &TaskContext,
+    server: &str,
+    tool_name: &str,
+    params: Value,
+) -> Result<Value, JsonRpcErrorObject> {
+    let params: CreateMessageParams = serde_json::from_value(params)
+        .map_err(|err| rpc_error(INVALID_PARAMS, format!("Invalid sampling request: {err}")))?;
+    let model = session_model(ctx)
+        .await
+        .map_err(|err| rpc_error(INTERNAL_ERROR, err))?;
+    let request = sampling_request(params, model).map_err(|err| rpc_error(INVALID_PARAMS, err))?;
+
+    let summary = sampling_summary(server, &request);
+    let decision = ctx
+        .tool_registry()
+        .confirm_action(ctx, tool_name, &summary, SAMPLING_PERMISSION, server)
+        .await
+        .map_err(|err| rpc_error(USER_REJECTED, err.to_string()))?;
+    if matches!(decision, ToolConfirmationDecision::Deny) {
+        return Err(rpc_error(USER_REJECTED, "User rejected sampling request"));
+    }
+
+    let model_id = request.model.clone();
+    let cancel = ctx.create_stream_cancel_token();
+    let llm = LLMService::new(ctx.repositories());
+    let message = tokio::select! {
+        res = llm.call(request) => res
+            .map_err(|err| rpc_error(INTERNAL_ERROR, format!("LLM call failed: {err}")))?,
+        _ = cancel.cancelled() => {
+            return Err(rpc_error(USER_REJECTED, "Task cancelled"));
+        }
+    };
+    let message_id = ctx.assistant_message_id().await;
+    ctx.record_llm_usage(
+        &model_id,
+        UsagePurpose::McpSampling,
+        message_id,
+        &message.usage,
+    )
+    .await;
+
+    Ok(sampling_result(&message))
+}
+
+async fn elicit(
+    ctx: &TaskContext,
+    server: &str,
+    tool_name: &str,
+    params: Value,
+) -> Result<Value, JsonRpcErrorObject> {
+    let params: ElicitParams = serde_json::from_value(params).map_err(|err| {
+        rpc_error(
+            INVALID_PARAMS,
+            format!("Invalid elicitation request: {err}"),
+        )
+    })?;
+
+    let summary = format!("{server}: {}", params.message);
+    let result = match ctx
+        .tool_registry()
+        .request_user_input(ctx, tool_name, &summary, params.requested_schema)
+        .await
+    {
+        Ok(Some(content)) => json!({ "action": "accept", "content": content }),
+        Ok(None) => json!({ "action": "decline" }),
+        Err(err) => {
+            tracing::debug!(target: "mcp", server = %server, "MCP elicitation cancelled: {}", err);
+            json!({ "action": "cancel" })
+        }
+    };
+    Ok(result)
+}
+
+async fn session_model(ctx: &TaskContext) -> Result<String, String> {
+    let session = ctx
+        .agent_persistence()
+        .sessions()
+        .get(ctx.session_id)
+        .await
+        .map_err(|e| format!("session lookup: {e}"))?
+        .ok_or("session not found")?;
+    session
+        .model_id
+        .ok_or_else(|| "no model_id on session".to_string())
+}
+
+fn sampling_request(
+    params: CreateMessageParams,
+    model: String,
+) -> Result<CreateMessageRequest, String> {
+    let messages = params
+        .messages
+        .into_iter()
+        .map(|message| {
+            Ok(MessageParam {
+                role: message.role,
+                content: sampling_content(message.content)?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(CreateMessageRequest {
+        model,
+        max_tokens: params.max_tokens.unwrap_or(DEFAULT_SAMPLING_MAX_TOKENS),
+        system: params.system_prompt.map(SystemPrompt::Text),
+        developer_context: None,
+        messages,
+        tools: None,
+        stream: false,
+        temperature: params.temperature,
+        top_p: None,
+        top_k: None,
+        metadata: None,
+        stop_sequences: params.stop_sequences,
+        thinking: None,
+    })
+}
+
+/// MCP message content is a single block or an array of blocks
+fn sampling_content(content: Value) -> Result<MessageContent, String> {
+    let items = match content {
+        Value::Array(items) => items,
+        item => vec![item],
+    };
+    let mut blocks = items
+        .into_iter()
+        .map(sampling_block)
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if let [ContentBlock::Text { text, .. }] = blocks.as_mut_slice() {
+        return Ok(MessageContent::Text(std::mem::take(text)));
+    }
+    Ok(MessageContent::Blocks(blocks))
+}
+
+fn sampling_block(item: Value) -> Result<ContentBlock, String> {
+    let field = |key: &str| item.get(key).and_then(Value::as_str).map(str::to_string);
+    match item.get("type").and_then(Value::as_str) {
+        Some("text") => Ok(ContentBlock::Text {
+            text: field("text").unwrap_or_default(),
+            cache_control: None,
+        }),
+        Some("image") => {
+            let (Some(data), Some(media_type)) = (field("data"), field("mimeType")) else {
+                return Err("Image content requires data and mimeType".to_string());
+            };
+            Ok(ContentBlock::Image {
+                source: ImageSource::Base64 { media_type, data },
+                cache_control: None,
+            })
+        }
+        other => Err(format!(
+            "Unsupported sampling content type: {}",
+            other.unwrap_or("<missing>")
+        )),
+    }
+}
+
+/// Shown in the confirmation dialog: the server and the last prompt it wants answered
+fn sampling_summary(server: &str, request: &CreateMessageRequest) -> String {
+    let prompt = request
+        .messages
+        .last()
+        .and_then(|message| match &message.content {
+            MessageContent::Text(text) => Some(text.as_str()),
+            MessageContent::Blocks(blocks) => blocks.iter().find_map(|block| match block {
+                ContentBlock::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            }),
+        })
+        .unwrap_or_default();
+    format!(
+        "{server} requests an LLM completion: {}",
+        truncate_chars(prompt, 200)
+    )
+}
+
+fn sampling_result(message: &Message) -> Value {
+    let stop_reason = match message.stop_reason {
+        Some(StopReason::MaxTokens) => "maxTokens",
+        Some(StopReason::StopSequence) => "stopSequence",
+        _ => "endTurn",
+    };
+    json!({
+        "role": "assistant",
+        "content": { "type": "text", "text": extract_text_from_llm_message(message) },
+        "model": message.model,
+        "stopReason": stop_reason
+    })
+}
+
+fn rpc_error(code: i64, message: impl Into<String>) -> JsonRpcErrorObject {
+    JsonRpcErrorObject {
+        code,
+        message: message.into(),
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(value: Value) -> CreateMessageParams {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_sampling_request_from_params() {
+        let request = sampling_request(
+            params(json!({
+                "messages": [
+                    { "role": "user", "content": { "type": "text", "text": "Summarize" } },
+                    { "role": "assistant", "content": [
+                        { "type": "text", "text": "Which part?" },
+                        { "type": "image", "data": "aGk=", "mimeType": "image/png" }
+                    ] }
+                ],
+                "systemPrompt": "Be brief",
+                "maxTokens": 300,
+                "temperature": 0.2,
+                "stopSequences": ["END"]
+            })),
+            "model-a".to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(request.model, "model-a");
+        assert_eq!(request.max_tokens, 300);
+        assert_eq!(request.system, Some(SystemPrompt::Text("Be brief".into())));
+        assert_eq!(request.temperature, Some(0.2));
+        assert_eq!(request.stop_sequences, Some(vec!["END".to_string()]));
+        assert!(matches!(
+            &request.messages[0].content,
+            MessageContent::Text(text) if text == "Summarize"
+        ));
+        match &request.messages[1].content {
+            MessageContent::Blocks(blocks) => {
+                assert_eq!(blocks.len(), 2);
+                assert!(matches!(
+                    &blocks[1],
+                    ContentBlock::Image { source: ImageSource::Base64 { media_type, .. }, .. }
+                        if media_type == "image/png"
+                ));
+            }
+            other => panic!("unexpected content: {other:?}"),
+        }
+        assert_eq!(
+            sampling_summary("docs", &request),
+            "docs requests an LLM completion: Which part?"
+        );
+    }
+
+    #[test]
+    fn test_sampling_request_defaults_and_unsupported_content() {
+        let request = sampling_request(
+            params(json!({
+                "messages": [{ "role": "user", "content": { "type": "text", "text": "hi" } }]
+            })),
+            "model-a".to_string(),
+        )
+        .unwrap();
+        assert_eq!(request.max_tokens, DEFAULT_SAMPLING_MAX_TOKENS);
+        assert!(request.system.is_none());
+
+        let err = sampling_request(
+            params(json!({
+                "messages": [{ "role": "user", "content": { "type": "audio", "data": "", "mimeType": "audio/wav" } }]
+            })),
+            "model-a".to_string(),
+        )
+        .unwrap_err();
+        assert!(err.contains("audio"));
+    }
+}
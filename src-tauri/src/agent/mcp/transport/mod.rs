@@ -1,2 +1,140 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+use crate::agent::mcp::error::{McpError, McpResult};
+use crate::agent::mcp::protocol::jsonrpc::{JsonRpcNotification, JsonRpcRequest};
+
 pub mod sse;
 pub mod stdio;
+pub mod streamable_http;
+#[cfg(test)]
+pub(crate) mod test_utils;
+
+/// How long a request may go without a response or any sign of progress
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Message initiated by the server
+#[derive(Debug, Clone)]
+pub enum ServerMessage {
+    Notification(JsonRpcNotification),
+    Request(JsonRpcRequest),
+}
+
+/// Invoked by transports for every server-initiated message, in the order messages are
+/// read, so notifications sent before a response are handled before that response resolves.
+/// Requests are answered through the transport's `respond`.
+pub type ServerMessageHandler = Arc<dyn Fn(ServerMessage) + Send + Sync>;
+
+/// Response timeout of one request.
+///
+/// Every `touch` (a progress notification) restarts it, and it is paused while a
+/// `hold` is alive, i.e. while the server waits on our answer to one of its own
+/// requests (sampling, elicitation) and so cannot finish the call.
+#[derive(Debug, Clone)]
+pub struct RequestTimeout {
+    limit: Duration,
+    /// Number of live holds; every change restarts the timer
+    holds: Arc<watch::Sender<usize>>,
+}
+
+/// Keeps a `RequestTimeout` paused until dropped
+#[derive(Debug)]
+pub struct TimeoutHold {
+    holds: Arc<watch::Sender<usize>>,
+}
+
+impl Default for RequestTimeout {
+    fn default() -> Self {
+        Self::new(REQUEST_TIMEOUT)
+    }
+}
+
+impl RequestTimeout {
+    pub fn new(limit: Duration) -> Self {
+        Self {
+            limit,
+            holds: Arc::new(watch::Sender::new(0)),
+        }
+    }
+
+    /// Restart the timer
+    pub fn touch(&self) {
+        self.holds.send_modify(|_| {});
+    }
+
+    pub fn hold(&self) -> TimeoutHold {
+        self.holds.send_modify(|holds| *holds += 1);
+        TimeoutHold {
+            holds: Arc::clone(&self.holds),
+        }
+    }
+
+    /// Run `future`, failing with `McpError::Timeout` once the limit passes without
+    /// the future completing, a touch, or a hold
+    pub async fn run<F: Future>(&self, future: F) -> McpResult<F::Output> {
+        let mut changes = self.holds.subscribe();
+        let mut deadline = Instant::now() + self.limit;
+        tokio::pin!(future);
+        loop {
+            // Changes made while this future wasn't polled count as activity too
+            if changes.has_changed().unwrap_or(false) {
+                deadline = Instant::now() + self.limit;
+            }
+            let held = *changes.borrow_and_update() > 0;
+            tokio::select! {
+                biased;
+                output = &mut future => return Ok(output),
+                _ = changes.changed() => deadline = Instant::now() + self.limit,
+                _ = tokio::time::sleep_until(deadline), if !held => return Err(McpError::Timeout),
+            }
+        }
+    }
+}
+
+impl Drop for TimeoutHold {
+    fn drop(&mut self) {
+        self.holds.send_modify(|holds| *holds -= 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: Duration = Duration::from_millis(100);
+
+    #[tokio::test]
+    async fn test_timeout_restarts_on_touch() {
+        let timeout = RequestTimeout::new(LIMIT);
+        let idle = std::future::pending::<()>();
+        assert!(matches!(timeout.run(idle).await, Err(McpError::Timeout)));
+
+        let progress = timeout.clone();
+        let reporting = async {
+            for _ in 0..4 {
+                tokio::time::sleep(LIMIT / 2).await;
+                progress.touch();
+            }
+        };
+        assert!(timeout.run(reporting).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_paused_while_held() {
+        let timeout = RequestTimeout::new(LIMIT);
+        let hold = timeout.hold();
+        let waiting = async {
+            tokio::time::sleep(LIMIT * 3).await;
+            drop(hold);
+            tokio::time::sleep(LIMIT / 2).await;
+        };
+        assert!(timeout.run(waiting).await.is_ok());
+
+        let idle = std::future::pending::<()>();
+        assert!(matches!(timeout.run(idle).await, Err(McpError::Timeout)));
+    }
+}
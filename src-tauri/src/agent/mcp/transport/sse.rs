@@ -1,23 +1,145 @@
-use crate::agent::mcp::error::{McpError, McpResult};
-use crate::agent::mcp::protocol::jsonrpc::{JsonRpcId, JsonRpcRequest, JsonRpcResponse};
-use reqwest::Client;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
 
-pub struct SseTransport {
+use dashmap::DashMap;
+use eventsource_stream::Eventsource;
+use futures::StreamExt;
+use parking_lot::RwLock;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT};
+use reqwest::{Client, StatusCode, Url};
+use serde_json::Value;
+use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
+
+use super::{RequestTimeout, ServerMessage, ServerMessageHandler};
+use crate::agent::mcp::error::{McpError, McpResult};
+use crate::agent::mcp::oauth::{send_authorized, McpAuthorizer};
+use crate::agent::mcp::protocol::jsonrpc::{
+    JsonRpcId, JsonRpcMessage, JsonRpcRequest, JsonRpcResponse,
+};
+
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(2);
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+
+type PendingMap = DashMap<i64, oneshot::Sender<McpResult<JsonRpcResponse>>>;
+
+/// State shared between the transport handle and the event stream task
+struct SseShared {
     client: Client,
-    url: String,
-    request_id: Arc<Mutex<u64>>,
+    url: Url,
+    /// POST endpoint announced by the server's `endpoint` event
+    endpoint: RwLock<Option<Url>>,
+    pending: PendingMap,
+    messages: ServerMessageHandler,
+    connected: AtomicBool,
+    auth: Option<Arc<McpAuthorizer>>,
+}
+
+impl SseShared {
+    fn endpoint(&self) -> McpResult<Url> {
+        self.endpoint.read().clone().ok_or(McpError::NotConnected)
+    }
+
+    async fn post(&self, body: &impl serde::Serialize) -> McpResult<()> {
+        let endpoint = self.endpoint()?;
+        let response = send_authorized(
+            self.auth.as_deref(),
+            || self.client.post(endpoint.clone()).json(body),
+            |e| McpError::Protocol(format!("SSE request failed: {e}")),
+        )
+        .await?;
+
+        self.check_status(response.status(), "request")
+    }
+
+    fn check_status(&self, status: StatusCode, action: &str) -> McpResult<()> {
+        if status.is_success() {
+            return Ok(());
+        }
+        if status == StatusCode::UNAUTHORIZED {
+            return Err(McpError::AuthorizationRequired(self.url.to_string()));
+        }
+        Err(McpError::Protocol(format!(
+            "SSE {action} failed with status: {status}"
+        )))
+    }
+
+    fn fail_pending(&self) {
+        let ids: Vec<i64> = self.pending.iter().map(|entry| *entry.key()).collect();
+        for id in ids {
+            if let Some((_, tx)) = self.pending.remove(&id) {
+                if tx.send(Err(McpError::Closed)).is_err() {
+                    tracing::warn!(
+                        target: "mcp",
+                        "Failed to notify pending MCP SSE request {} about close",
+                        id
+                    );
+                }
+            }
+        }
+    }
+
+    fn handle_message(&self, data: &str) {
+        let val: Value = match serde_json::from_str(data) {
+            Ok(val) => val,
+            Err(err) => {
+                tracing::warn!(target: "mcp", "Invalid JSON in MCP SSE stream: {}", err);
+                return;
+            }
+        };
+
+        match JsonRpcMessage::from_value(val) {
+            Ok(JsonRpcMessage::Response(resp)) => {
+                let JsonRpcId::Number(id) = resp.id else {
+                    return;
+                };
+                if let Some((_, tx)) = self.pending.remove(&id) {
+                    if tx.send(Ok(resp)).is_err() {
+                        tracing::warn!(
+                            target: "mcp",
+                            "Failed to deliver MCP SSE response for request {}",
+                            id
+                        );
+                    }
+                }
+            }
+            Ok(JsonRpcMessage::Notification(notification)) => {
+                (self.messages)(ServerMessage::Notification(notification))
+            }
+            Ok(JsonRpcMessage::Request(request)) => {
+                (self.messages)(ServerMessage::Request(request))
+            }
+            Err(err) => {
+                tracing::warn!(target: "mcp", "Invalid MCP message in SSE stream: {}", err);
+            }
+        }
+    }
+}
+
+/// Legacy MCP HTTP+SSE transport (protocol 2024-11-05): a long-lived GET event
+/// stream carries server messages, client messages are POSTed to the endpoint it announces
+pub struct SseTransport {
+    shared: Arc<SseShared>,
+    next_id: AtomicI64,
+    cancel: CancellationToken,
 }
 
 impl SseTransport {
-    pub async fn new(url: &str, headers: &HashMap<String, String>) -> McpResult<Self> {
-        let mut default_headers = reqwest::header::HeaderMap::new();
+    pub async fn new(
+        url: &str,
+        headers: &HashMap<String, String>,
+        messages: ServerMessageHandler,
+        auth: Option<Arc<McpAuthorizer>>,
+    ) -> McpResult<Self> {
+        let mut default_headers = HeaderMap::new();
         for (k, v) in headers {
             if let (Ok(name), Ok(value)) = (
-                reqwest::header::HeaderName::from_bytes(k.as_bytes()),
-                reqwest::header::HeaderValue::from_str(v),
+                HeaderName::from_bytes(k.as_bytes()),
+                HeaderValue::from_str(v),
             ) {
                 default_headers.insert(name, value);
             }
@@ -28,56 +150,336 @@ impl SseTransport {
             .build()
             .map_err(|e| McpError::Protocol(format!("Failed to create HTTP client: {e}")))?;
 
-        Ok(Self {
-            client,
-            url: url.to_string(),
-            request_id: Arc::new(Mutex::new(1)),
-        })
+        let url = Url::parse(url)
+            .map_err(|e| McpError::InvalidConfig(format!("Invalid SSE url '{url}': {e}")))?;
+
+        let transport = Self {
+            shared: Arc::new(SseShared {
+                client,
+                url,
+                endpoint: RwLock::new(None),
+                pending: DashMap::new(),
+                messages,
+                connected: AtomicBool::new(true),
+                auth,
+            }),
+            next_id: AtomicI64::new(1),
+            cancel: CancellationToken::new(),
+        };
+
+        let (endpoint_tx, endpoint_rx) = oneshot::channel();
+        transport.start_event_loop(endpoint_tx);
+
+        match tokio::time::timeout(ENDPOINT_TIMEOUT, endpoint_rx).await {
+            Ok(Ok(Ok(()))) => Ok(transport),
+            Ok(Ok(Err(err))) => Err(err),
+            Ok(Err(_)) => Err(McpError::Closed),
+            Err(_) => Err(McpError::Timeout),
+        }
     }
 
-    pub async fn request(&self, mut req: JsonRpcRequest) -> McpResult<JsonRpcResponse> {
-        let mut id = self.request_id.lock().await;
-        req.id = Some(JsonRpcId::Number(*id as i64));
-        *id += 1;
-        drop(id);
+    fn start_event_loop(&self, endpoint_tx: oneshot::Sender<McpResult<()>>) {
+        let shared = Arc::clone(&self.shared);
+        let cancel = self.cancel.clone();
 
-        let response = self
-            .client
-            .post(&self.url)
-            .json(&req)
-            .send()
-            .await
-            .map_err(|e| McpError::Protocol(format!("SSE request failed: {e}")))?;
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = cancel.cancelled() => {}
+                _ = run_event_loop(&shared, endpoint_tx) => {}
+            }
+            shared.connected.store(false, Ordering::SeqCst);
+            shared.fail_pending();
+        });
+    }
+
+    pub fn alloc_id(&self) -> i64 {
+        self.next_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    pub async fn request(
+        &self,
+        mut req: JsonRpcRequest,
+        timeout: &RequestTimeout,
+    ) -> McpResult<JsonRpcResponse> {
+        if !self.is_connected() {
+            return Err(McpError::NotConnected);
+        }
+
+        let id = req.assigned_id().unwrap_or_else(|| self.alloc_id());
+        req.id = Some(JsonRpcId::Number(id));
+
+        let (tx, rx) = oneshot::channel();
+        self.shared.pending.insert(id, tx);
+
+        // The POST is only acknowledged; the response arrives on the event stream
+        if let Err(err) = self.shared.post(&req).await {
+            self.shared.pending.remove(&id);
+            return Err(err);
+        }
+
+        let res = match timeout.run(rx).await {
+            Ok(res) => res,
+            Err(err) => {
+                self.shared.pending.remove(&id);
+                return Err(err);
+            }
+        };
+
+        match res {
+            Ok(inner) => inner,
+            Err(_) => Err(McpError::Closed),
+        }
+    }
+
+    pub async fn notify(&self, req: JsonRpcRequest) -> McpResult<()> {
+        if !self.is_connected() {
+            return Err(McpError::NotConnected);
+        }
+        self.shared.post(&req).await
+    }
+
+    /// Answer a request initiated by the server
+    pub async fn respond(&self, response: JsonRpcResponse) -> McpResult<()> {
+        if !self.is_connected() {
+            return Err(McpError::NotConnected);
+        }
+        self.shared.post(&response).await
+    }
+
+    pub async fn close(&self) -> McpResult<()> {
+        self.shared.connected.store(false, Ordering::SeqCst);
+        self.cancel.cancel();
+        Ok(())
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.shared.connected.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for SseTransport {
+    fn drop(&mut self) {
+        self.shared.connected.store(false, Ordering::SeqCst);
+        self.cancel.cancel();
+    }
+}
+
+/// Keep the event stream open, reconnecting with `Last-Event-ID` until the
+/// retry budget is exhausted. The first failure before an `endpoint` event is
+/// reported through `endpoint_tx` instead of retried.
+async fn run_event_loop(shared: &SseShared, endpoint_tx: oneshot::Sender<McpResult<()>>) {
+    let mut endpoint_tx = Some(endpoint_tx);
+    let mut last_event_id: Option<String> = None;
+    let mut retry_delay = DEFAULT_RECONNECT_DELAY;
+    let mut attempts: u32 = 0;
+
+    loop {
+        let opened = send_authorized(
+            shared.auth.as_deref(),
+            || {
+                let mut request = shared
+                    .client
+                    .get(shared.url.clone())
+                    .header(ACCEPT, "text/event-stream");
+                if let Some(event_id) = last_event_id.as_deref() {
+                    request = request.header(LAST_EVENT_ID_HEADER, event_id);
+                }
+                request
+            },
+            |e| McpError::Protocol(format!("SSE stream failed: {e}")),
+        )
+        .await
+        .and_then(|response| {
+            shared.check_status(response.status(), "stream")?;
+            Ok(response)
+        });
+
+        match opened {
+            Ok(response) => {
+                let mut events = response.bytes_stream().eventsource();
+                while let Some(event) = events.next().await {
+                    let event = match event {
+                        Ok(event) => event,
+                        Err(err) => {
+                            tracing::debug!(target: "mcp", "MCP SSE stream interrupted: {}", err);
+                            break;
+                        }
+                    };
+                    attempts = 0;
 
-        if !response.status().is_success() {
-            return Err(McpError::Protocol(format!(
-                "SSE request failed with status: {}",
-                response.status()
+                    if !event.id.is_empty() {
+                        last_event_id = Some(event.id.clone());
+                    }
+                    if let Some(retry) = event.retry {
+                        retry_delay = retry;
+                    }
+
+                    match event.event.as_str() {
+                        "endpoint" => match shared.url.join(event.data.trim()) {
+                            Ok(endpoint) => {
+                                *shared.endpoint.write() = Some(endpoint);
+                                if let Some(tx) = endpoint_tx.take() {
+                                    let _ = tx.send(Ok(()));
+                                }
+                            }
+                            Err(err) => {
+                                tracing::warn!(target: "mcp", "Invalid MCP SSE endpoint: {}", err);
+                            }
+                        },
+                        "" | "message" => shared.handle_message(&event.data),
+                        other => {
+                            tracing::debug!(target: "mcp", "Ignoring MCP SSE event '{}'", other);
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                if let Some(tx) = endpoint_tx.take() {
+                    let _ = tx.send(Err(err));
+                    return;
+                }
+                tracing::warn!(target: "mcp", "MCP SSE reconnect failed: {}", err);
+                // Retrying cannot help until the user authorizes again
+                if matches!(err, McpError::AuthorizationRequired(_)) {
+                    return;
+                }
+            }
+        }
+
+        if let Some(tx) = endpoint_tx.take() {
+            let _ = tx.send(Err(McpError::Protocol(
+                "SSE stream closed before endpoint event".into(),
             )));
+            return;
+        }
+
+        attempts += 1;
+        if attempts > MAX_RECONNECT_ATTEMPTS {
+            tracing::warn!(target: "mcp", "MCP SSE stream gave up after {} reconnects", MAX_RECONNECT_ATTEMPTS);
+            return;
         }
+        tokio::time::sleep(retry_delay * attempts).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::mcp::transport::test_utils::{
+        http_response, message_channel, spawn_server, sse_event, sse_head, MockReply, MockRequest,
+    };
+    use serde_json::json;
+    use std::sync::Mutex as StdMutex;
+    use tokio::sync::mpsc;
+
+    type StreamSender = Arc<StdMutex<Option<mpsc::UnboundedSender<String>>>>;
+
+    /// GET opens a stream that announces `/messages`; POSTed requests are
+    /// answered on that stream with a notification followed by the response
+    fn handler(stream: StreamSender, event_id: &'static str) -> impl Fn(&MockRequest) -> MockReply {
+        move |req| {
+            if req.method == "GET" {
+                let (tx, rx) = mpsc::unbounded_channel();
+                let _ = tx.send(sse_event(
+                    Some("endpoint"),
+                    Some(event_id),
+                    "/messages?session=1",
+                ));
+                *stream.lock().unwrap() = Some(tx);
+                return MockReply::Stream(sse_head(), rx);
+            }
+
+            if let Some(tx) = stream.lock().unwrap().as_ref() {
+                let _ = tx.send(sse_event(
+                    Some("message"),
+                    None,
+                    &json!({ "jsonrpc": "2.0", "method": "notifications/message", "params": { "level": "info" } }).to_string(),
+                ));
+                let _ = tx.send(sse_event(
+                    Some("message"),
+                    None,
+                    &json!({ "jsonrpc": "2.0", "id": req.rpc_id(), "result": { "echo": req.rpc_method() } }).to_string(),
+                ));
+            }
+            MockReply::Full(http_response("202 Accepted", &[], ""))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_event_and_response_correlation() {
+        let stream: StreamSender = Arc::new(StdMutex::new(None));
+        let (url, requests) = spawn_server(handler(Arc::clone(&stream), "1")).await;
+
+        let (handler, mut rx) = message_channel();
+        let transport = SseTransport::new(&format!("{url}/sse"), &HashMap::new(), handler, None)
+            .await
+            .unwrap();
 
-        let resp: JsonRpcResponse = response
-            .json()
+        let resp = transport
+            .request(
+                JsonRpcRequest::new_request(0, "tools/list", None),
+                &RequestTimeout::default(),
+            )
             .await
-            .map_err(|e| McpError::Protocol(format!("Failed to parse SSE response: {e}")))?;
+            .unwrap();
+        assert_eq!(resp.result.unwrap()["echo"], "tools/list");
+
+        let Some(ServerMessage::Notification(notification)) = rx.recv().await else {
+            panic!("expected a notification");
+        };
+        assert_eq!(notification.method, "notifications/message");
 
-        Ok(resp)
+        let requests = requests.lock().unwrap();
+        let post = requests.iter().find(|r| r.method == "POST").unwrap();
+        assert_eq!(post.path, "/messages?session=1");
     }
 
-    pub async fn notify(&self, req: JsonRpcRequest) -> McpResult<()> {
-        let response = self
-            .client
-            .post(&self.url)
-            .json(&req)
-            .send()
+    #[tokio::test]
+    async fn test_reconnects_with_last_event_id() {
+        let stream: StreamSender = Arc::new(StdMutex::new(None));
+        let (url, requests) = spawn_server(handler(Arc::clone(&stream), "evt-7")).await;
+
+        let (handler, _rx) = message_channel();
+        let transport = SseTransport::new(&format!("{url}/sse"), &HashMap::new(), handler, None)
             .await
-            .map_err(|e| McpError::Protocol(format!("SSE notify failed: {e}")))?;
-        if !response.status().is_success() {
-            return Err(McpError::Protocol(format!(
-                "SSE notify failed with status: {}",
-                response.status()
-            )));
+            .unwrap();
+
+        // Ask the client to reconnect quickly, then drop the stream
+        if let Some(tx) = stream.lock().unwrap().take() {
+            let _ = tx.send("retry: 10\n\n".to_string());
         }
-        Ok(())
+
+        let mut reconnected = false;
+        for _ in 0..100 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let gets = requests
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|r| r.method == "GET")
+                .count();
+            if gets >= 2 && stream.lock().unwrap().is_some() {
+                reconnected = true;
+                break;
+            }
+        }
+        assert!(reconnected);
+
+        let resp = transport
+            .request(
+                JsonRpcRequest::new_request(0, "ping", None),
+                &RequestTimeout::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.result.unwrap()["echo"], "ping");
+
+        let requests = requests.lock().unwrap();
+        let resume = requests
+            .iter()
+            .filter(|r| r.method == "GET")
+            .nth(1)
+            .unwrap();
+        assert_eq!(resume.header(LAST_EVENT_ID_HEADER), Some("evt-7"));
     }
 }
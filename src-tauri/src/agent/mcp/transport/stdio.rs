@@ -8,8 +8,11 @@ use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
 use tokio::sync::{oneshot, Mutex};
 
+use super::{RequestTimeout, ServerMessage, ServerMessageHandler};
 use crate::agent::mcp::error::{McpError, McpResult};
-use crate::agent::mcp::protocol::jsonrpc::{JsonRpcId, JsonRpcRequest, JsonRpcResponse};
+use crate::agent::mcp::protocol::jsonrpc::{
+    JsonRpcId, JsonRpcMessage, JsonRpcRequest, JsonRpcResponse,
+};
 
 pub struct StdioTransport {
     child: Mutex<Option<Child>>,
@@ -25,6 +28,7 @@ impl StdioTransport {
         args: &[String],
         env: &HashMap<String, String>,
         cwd: Option<&Path>,
+        messages: ServerMessageHandler,
     ) -> McpResult<Self> {
         let mut cmd = Command::new(command);
         cmd.args(args);
@@ -63,13 +67,17 @@ impl StdioTransport {
             connected: Arc::new(AtomicBool::new(true)),
         };
 
-        transport.start_stdout_loop(stdout);
+        transport.start_stdout_loop(stdout, messages);
         transport.start_stderr_loop(stderr);
 
         Ok(transport)
     }
 
-    fn start_stdout_loop(&self, stdout: tokio::process::ChildStdout) {
+    fn start_stdout_loop(
+        &self,
+        stdout: tokio::process::ChildStdout,
+        messages: ServerMessageHandler,
+    ) {
         let pending = Arc::clone(&self.pending);
         let connected = Arc::clone(&self.connected);
 
@@ -86,9 +94,8 @@ impl StdioTransport {
                     continue;
                 };
 
-                let response: Result<JsonRpcResponse, _> = serde_json::from_value(val.clone());
-                match response {
-                    Ok(resp) => {
+                match JsonRpcMessage::from_value(val) {
+                    Ok(JsonRpcMessage::Response(resp)) => {
                         if let JsonRpcId::Number(id) = &resp.id {
                             let id = *id;
                             if let Some((_, tx)) = pending.remove(&id) {
@@ -102,8 +109,14 @@ impl StdioTransport {
                             }
                         }
                     }
+                    Ok(JsonRpcMessage::Notification(notification)) => {
+                        messages(ServerMessage::Notification(notification))
+                    }
+                    Ok(JsonRpcMessage::Request(request)) => {
+                        messages(ServerMessage::Request(request))
+                    }
                     Err(_) => {
-                        // notifications or unknown messages are ignored for now
+                        // unknown messages are ignored
                     }
                 }
             }
@@ -135,7 +148,7 @@ impl StdioTransport {
         });
     }
 
-    fn alloc_id(&self) -> i64 {
+    pub fn alloc_id(&self) -> i64 {
         self.next_id.fetch_add(1, Ordering::SeqCst)
     }
 
@@ -151,12 +164,16 @@ impl StdioTransport {
         Ok(())
     }
 
-    pub async fn request(&self, mut request: JsonRpcRequest) -> McpResult<JsonRpcResponse> {
+    pub async fn request(
+        &self,
+        mut request: JsonRpcRequest,
+        timeout: &RequestTimeout,
+    ) -> McpResult<JsonRpcResponse> {
         if !self.is_connected() {
             return Err(McpError::NotConnected);
         }
 
-        let id = self.alloc_id();
+        let id = request.assigned_id().unwrap_or_else(|| self.alloc_id());
         request.id = Some(JsonRpcId::Number(id));
 
         let (tx, rx) = oneshot::channel();
@@ -165,9 +182,7 @@ impl StdioTransport {
         let json = serde_json::to_string(&request)?;
         self.write_line(&json).await?;
 
-        let res = tokio::time::timeout(std::time::Duration::from_secs(60), rx)
-            .await
-            .map_err(|_| McpError::Timeout)?;
+        let res = timeout.run(rx).await?;
 
         match res {
             Ok(inner) => inner,
@@ -184,6 +199,15 @@ impl StdioTransport {
         Ok(())
     }
 
+    /// Answer a request initiated by the server
+    pub async fn respond(&self, response: JsonRpcResponse) -> McpResult<()> {
+        if !self.is_connected() {
+            return Err(McpError::NotConnected);
+        }
+        let json = serde_json::to_string(&response)?;
+        self.write_line(&json).await
+    }
+
     pub async fn close(&self) -> McpResult<()> {
         self.connected.store(false, Ordering::SeqCst);
 
@@ -0,0 +1,631 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use eventsource_stream::Eventsource;
+use futures::StreamExt;
+use parking_lot::RwLock;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_TYPE};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use super::{RequestTimeout, ServerMessage, ServerMessageHandler};
+use crate::agent::mcp::error::{McpError, McpResult};
+use crate::agent::mcp::oauth::{send_authorized, McpAuthorizer};
+use crate::agent::mcp::protocol::jsonrpc::{
+    JsonRpcId, JsonRpcMessage, JsonRpcRequest, JsonRpcResponse,
+};
+
+const SESSION_ID_HEADER: &str = "mcp-session-id";
+const PROTOCOL_VERSION_HEADER: &str = "mcp-protocol-version";
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+const MAX_RESUME_ATTEMPTS: usize = 3;
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// State shared between request calls and the background listen stream
+struct HttpShared {
+    client: Client,
+    url: String,
+    session_id: RwLock<Option<String>>,
+    protocol_version: RwLock<Option<String>>,
+    messages: ServerMessageHandler,
+    auth: Option<Arc<McpAuthorizer>>,
+}
+
+impl HttpShared {
+    fn with_session_headers(&self, mut builder: RequestBuilder) -> RequestBuilder {
+        if let Some(session_id) = self.session_id.read().as_deref() {
+            builder = builder.header(SESSION_ID_HEADER, session_id);
+        }
+        if let Some(version) = self.protocol_version.read().as_deref() {
+            builder = builder.header(PROTOCOL_VERSION_HEADER, version);
+        }
+        builder
+    }
+
+    fn capture_session_id(&self, response: &Response) {
+        let Some(value) = response.headers().get(SESSION_ID_HEADER) else {
+            return;
+        };
+        match value.to_str() {
+            Ok(session_id) => *self.session_id.write() = Some(session_id.to_string()),
+            Err(err) => {
+                tracing::warn!(target: "mcp", "Ignoring non-ASCII MCP session id: {}", err);
+            }
+        }
+    }
+
+    /// Map HTTP status to MCP errors. A 404 on a request that carried a
+    /// session id means the server dropped the session.
+    fn check_status(&self, response: &Response, action: &str) -> McpResult<()> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        if status == StatusCode::UNAUTHORIZED {
+            return Err(McpError::AuthorizationRequired(self.url.clone()));
+        }
+        if status == StatusCode::NOT_FOUND && self.session_id.read().is_some() {
+            self.session_id.write().take();
+            return Err(McpError::SessionExpired);
+        }
+        Err(McpError::Protocol(format!(
+            "Streamable HTTP {action} failed with status: {status}"
+        )))
+    }
+
+    async fn post(&self, body: &impl serde::Serialize) -> McpResult<Response> {
+        let response = send_authorized(
+            self.auth.as_deref(),
+            || {
+                let builder = self
+                    .client
+                    .post(&self.url)
+                    .header(ACCEPT, "application/json, text/event-stream")
+                    .json(body);
+                self.with_session_headers(builder)
+            },
+            |e| McpError::Protocol(format!("Streamable HTTP request failed: {e}")),
+        )
+        .await?;
+
+        self.check_status(&response, "POST")?;
+        self.capture_session_id(&response);
+        Ok(response)
+    }
+
+    /// Open a GET event stream. Returns `None` when the server does not offer one (405).
+    async fn open_stream(&self, last_event_id: Option<&str>) -> McpResult<Option<Response>> {
+        let response = send_authorized(
+            self.auth.as_deref(),
+            || {
+                let mut builder = self
+                    .client
+                    .get(&self.url)
+                    .header(ACCEPT, "text/event-stream");
+                if let Some(event_id) = last_event_id {
+                    builder = builder.header(LAST_EVENT_ID_HEADER, event_id);
+                }
+                self.with_session_headers(builder)
+            },
+            |e| McpError::Protocol(format!("Streamable HTTP GET failed: {e}")),
+        )
+        .await?;
+
+        if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+            return Ok(None);
+        }
+        self.check_status(&response, "GET")?;
+        Ok(Some(response))
+    }
+}
+
+/// MCP Streamable HTTP transport (single endpoint, POST for client messages,
+/// JSON or SSE responses, optional GET stream for server-initiated messages)
+pub struct StreamableHttpTransport {
+    shared: Arc<HttpShared>,
+    next_id: AtomicI64,
+    connected: Arc<AtomicBool>,
+    listener: CancellationToken,
+}
+
+impl StreamableHttpTransport {
+    pub async fn new(
+        url: &str,
+        headers: &HashMap<String, String>,
+        messages: ServerMessageHandler,
+        auth: Option<Arc<McpAuthorizer>>,
+    ) -> McpResult<Self> {
+        let mut default_headers = HeaderMap::new();
+        for (k, v) in headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(k.as_bytes()),
+                HeaderValue::from_str(v),
+            ) {
+                default_headers.insert(name, value);
+            }
+        }
+
+        let client = Client::builder()
+            .default_headers(default_headers)
+            .build()
+            .map_err(|e| McpError::Protocol(format!("Failed to create HTTP client: {e}")))?;
+
+        Ok(Self {
+            shared: Arc::new(HttpShared {
+                client,
+                url: url.to_string(),
+                session_id: RwLock::new(None),
+                protocol_version: RwLock::new(None),
+                messages,
+                auth,
+            }),
+            next_id: AtomicI64::new(1),
+            connected: Arc::new(AtomicBool::new(true)),
+            listener: CancellationToken::new(),
+        })
+    }
+
+    pub fn alloc_id(&self) -> i64 {
+        self.next_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    pub fn session_id(&self) -> Option<String> {
+        self.shared.session_id.read().clone()
+    }
+
+    pub async fn request(
+        &self,
+        mut request: JsonRpcRequest,
+        timeout: &RequestTimeout,
+    ) -> McpResult<JsonRpcResponse> {
+        if !self.is_connected() {
+            return Err(McpError::NotConnected);
+        }
+
+        let id = request.assigned_id().unwrap_or_else(|| self.alloc_id());
+        request.id = Some(JsonRpcId::Number(id));
+
+        let response = timeout.run(self.send_request(&request, id)).await??;
+
+        if request.method == "initialize" {
+            let version = response
+                .result
+                .as_ref()
+                .and_then(|r| r.get("protocolVersion"))
+                .and_then(Value::as_str);
+            if let Some(version) = version {
+                *self.shared.protocol_version.write() = Some(version.to_string());
+            }
+        }
+
+        Ok(response)
+    }
+
+    pub async fn notify(&self, request: JsonRpcRequest) -> McpResult<()> {
+        if !self.is_connected() {
+            return Err(McpError::NotConnected);
+        }
+        // Servers answer notifications with 202 Accepted and no body
+        self.shared.post(&request).await?;
+        Ok(())
+    }
+
+    /// Answer a request initiated by the server (acknowledged with 202 Accepted)
+    pub async fn respond(&self, response: JsonRpcResponse) -> McpResult<()> {
+        if !self.is_connected() {
+            return Err(McpError::NotConnected);
+        }
+        self.shared.post(&response).await?;
+        Ok(())
+    }
+
+    async fn send_request(&self, request: &JsonRpcRequest, id: i64) -> McpResult<JsonRpcResponse> {
+        let response = self.shared.post(request).await?;
+
+        if is_event_stream(&response) {
+            return self.read_stream_response(response, id).await;
+        }
+
+        response
+            .json::<JsonRpcResponse>()
+            .await
+            .map_err(|e| McpError::Protocol(format!("Failed to parse MCP HTTP response: {e}")))
+    }
+
+    /// Read an SSE response until the reply for `id` arrives. If the server closes
+    /// the stream early, resume it via GET with `Last-Event-ID`.
+    async fn read_stream_response(
+        &self,
+        mut response: Response,
+        id: i64,
+    ) -> McpResult<JsonRpcResponse> {
+        let mut last_event_id: Option<String> = None;
+        let mut attempts = 0;
+
+        loop {
+            if let Some(resp) =
+                drain_stream(&self.shared, response, Some(id), &mut last_event_id).await
+            {
+                return Ok(resp);
+            }
+
+            let Some(event_id) = last_event_id.clone() else {
+                return Err(McpError::Closed);
+            };
+            attempts += 1;
+            if attempts > MAX_RESUME_ATTEMPTS {
+                return Err(McpError::Closed);
+            }
+
+            tracing::debug!(target: "mcp", "Resuming MCP stream after event {}", event_id);
+            response = self
+                .shared
+                .open_stream(Some(&event_id))
+                .await?
+                .ok_or(McpError::Closed)?;
+        }
+    }
+
+    /// Open the optional GET stream used by servers to push notifications and requests.
+    pub fn start_listen_stream(&self) {
+        let shared = Arc::clone(&self.shared);
+        let connected = Arc::clone(&self.connected);
+        let cancel = self.listener.clone();
+
+        tokio::spawn(async move {
+            let mut last_event_id: Option<String> = None;
+
+            while connected.load(Ordering::SeqCst) {
+                let opened = tokio::select! {
+                    _ = cancel.cancelled() => return,
+                    opened = shared.open_stream(last_event_id.as_deref()) => opened,
+                };
+
+                match opened {
+                    Ok(Some(response)) => {
+                        tokio::select! {
+                            _ = cancel.cancelled() => return,
+                            _ = drain_stream(&shared, response, None, &mut last_event_id) => {}
+                        }
+                    }
+                    Ok(None) => {
+                        tracing::debug!(target: "mcp", "MCP server does not offer a GET stream");
+                        return;
+                    }
+                    Err(err) => {
+                        tracing::warn!(target: "mcp", "MCP listen stream failed: {}", err);
+                    }
+                }
+
+                tokio::select! {
+                    _ = cancel.cancelled() => return,
+                    _ = tokio::time::sleep(DEFAULT_RECONNECT_DELAY) => {}
+                }
+            }
+        });
+    }
+
+    pub async fn close(&self) -> McpResult<()> {
+        self.connected.store(false, Ordering::SeqCst);
+        self.listener.cancel();
+
+        let Some(session_id) = self.shared.session_id.write().take() else {
+            return Ok(());
+        };
+
+        // Explicit session termination; servers may answer 405 if unsupported
+        let result = send_authorized(
+            self.shared.auth.as_deref(),
+            || {
+                self.shared
+                    .client
+                    .delete(&self.shared.url)
+                    .header(SESSION_ID_HEADER, &session_id)
+            },
+            |e| McpError::Protocol(format!("Streamable HTTP DELETE failed: {e}")),
+        )
+        .await;
+        if let Err(err) = result {
+            tracing::warn!(target: "mcp", "Failed to terminate MCP HTTP session: {}", err);
+        }
+
+        Ok(())
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for StreamableHttpTransport {
+    fn drop(&mut self) {
+        self.connected.store(false, Ordering::SeqCst);
+        self.listener.cancel();
+    }
+}
+
+fn is_event_stream(response: &Response) -> bool {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"))
+}
+
+/// Consume SSE events, tracking the last event id. Returns the response
+/// matching `expected_id` when found; server-initiated messages are forwarded to the client.
+async fn drain_stream(
+    shared: &HttpShared,
+    response: Response,
+    expected_id: Option<i64>,
+    last_event_id: &mut Option<String>,
+) -> Option<JsonRpcResponse> {
+    let mut events = response.bytes_stream().eventsource();
+
+    while let Some(event) = events.next().await {
+        let event = match event {
+            Ok(event) => event,
+            Err(err) => {
+                tracing::debug!(target: "mcp", "MCP event stream interrupted: {}", err);
+                return None;
+            }
+        };
+
+        if !event.id.is_empty() {
+            *last_event_id = Some(event.id.clone());
+        }
+        // Priming events carry an id but no data
+        if event.data.trim().is_empty() {
+            continue;
+        }
+
+        let val: Value = match serde_json::from_str(&event.data) {
+            Ok(val) => val,
+            Err(err) => {
+                tracing::warn!(target: "mcp", "Invalid JSON in MCP event stream: {}", err);
+                continue;
+            }
+        };
+
+        match JsonRpcMessage::from_value(val) {
+            Ok(JsonRpcMessage::Response(resp)) => match (&resp.id, expected_id) {
+                (JsonRpcId::Number(id), Some(expected)) if *id == expected => return Some(resp),
+                _ => {
+                    tracing::debug!(target: "mcp", "Dropping unexpected MCP response {:?}", resp.id);
+                }
+            },
+            Ok(JsonRpcMessage::Notification(notification)) => {
+                (shared.messages)(ServerMessage::Notification(notification))
+            }
+            Ok(JsonRpcMessage::Request(request)) => {
+                (shared.messages)(ServerMessage::Request(request))
+            }
+            Err(err) => {
+                tracing::warn!(target: "mcp", "Invalid MCP message in event stream: {}", err);
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::mcp::transport::test_utils::{
+        http_response, json_reply, message_channel, spawn_server, sse_reply, MockReply,
+    };
+    use std::sync::Mutex as StdMutex;
+    use tokio::sync::mpsc;
+
+    async fn connect(base_url: &str) -> StreamableHttpTransport {
+        connect_with_messages(base_url).await.0
+    }
+
+    async fn connect_with_messages(
+        base_url: &str,
+    ) -> (
+        StreamableHttpTransport,
+        mpsc::UnboundedReceiver<ServerMessage>,
+    ) {
+        let (handler, rx) = message_channel();
+        let transport = StreamableHttpTransport::new(
+            &format!("{base_url}/mcp"),
+            &HashMap::new(),
+            handler,
+            None,
+        )
+        .await
+        .unwrap();
+        (transport, rx)
+    }
+
+    #[tokio::test]
+    async fn test_json_response_and_session_header() {
+        let (url, requests) = spawn_server(|req| {
+            let id = req.rpc_id();
+            match req.rpc_method() {
+                "initialize" => json_reply(
+                    &id,
+                    serde_json::json!({ "protocolVersion": "2025-06-18" }),
+                    &[("Mcp-Session-Id", "session-1")],
+                ),
+                _ => json_reply(&id, serde_json::json!({ "tools": [] }), &[]),
+            }
+        })
+        .await;
+
+        let transport = connect(&url).await;
+        let init = transport
+            .request(
+                JsonRpcRequest::new_request(0, "initialize", None),
+                &RequestTimeout::default(),
+            )
+            .await
+            .unwrap();
+        assert!(init.result.is_some());
+        assert_eq!(transport.session_id().as_deref(), Some("session-1"));
+
+        let list = transport
+            .request(
+                JsonRpcRequest::new_request(0, "tools/list", None),
+                &RequestTimeout::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(list.result.unwrap()["tools"], serde_json::json!([]));
+
+        let requests = requests.lock().unwrap();
+        let second = &requests[1];
+        assert_eq!(second.method, "POST");
+        assert_eq!(second.header(SESSION_ID_HEADER), Some("session-1"));
+        assert_eq!(second.header(PROTOCOL_VERSION_HEADER), Some("2025-06-18"));
+    }
+
+    #[tokio::test]
+    async fn test_sse_response_forwards_notifications() {
+        let (url, _) = spawn_server(|req| {
+            let id = req.rpc_id();
+            sse_reply(&[
+                (
+                    None,
+                    serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "method": "notifications/progress",
+                        "params": { "progress": 1 }
+                    }),
+                ),
+                (
+                    None,
+                    serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": { "ok": true } }),
+                ),
+            ])
+        })
+        .await;
+
+        let (transport, mut messages) = connect_with_messages(&url).await;
+        let resp = transport
+            .request(
+                JsonRpcRequest::new_request(0, "tools/call", None),
+                &RequestTimeout::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.result.unwrap()["ok"], true);
+
+        let Ok(ServerMessage::Notification(notification)) = messages.try_recv() else {
+            panic!("expected a notification");
+        };
+        assert_eq!(notification.method, "notifications/progress");
+        assert_eq!(notification.params.unwrap()["progress"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_resumes_stream_with_last_event_id() {
+        let pending_id = Arc::new(StdMutex::new(Value::Null));
+        let pending = Arc::clone(&pending_id);
+        let (url, requests) = spawn_server(move |req| {
+            if req.method == "POST" {
+                *pending.lock().unwrap() = req.rpc_id();
+                // Priming event, then the server drops the connection
+                return sse_reply(&[(Some("evt-1"), Value::Null)]);
+            }
+            let id = pending.lock().unwrap().clone();
+            sse_reply(&[(
+                Some("evt-2"),
+                serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": { "resumed": true } }),
+            )])
+        })
+        .await;
+
+        let transport = connect(&url).await;
+        let resp = transport
+            .request(
+                JsonRpcRequest::new_request(0, "tools/call", None),
+                &RequestTimeout::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.result.unwrap()["resumed"], true);
+
+        let requests = requests.lock().unwrap();
+        let resume = requests.iter().find(|r| r.method == "GET").unwrap();
+        assert_eq!(resume.header(LAST_EVENT_ID_HEADER), Some("evt-1"));
+    }
+
+    #[tokio::test]
+    async fn test_notification_accepted() {
+        let (url, requests) =
+            spawn_server(|_| MockReply::Full(http_response("202 Accepted", &[], ""))).await;
+
+        let transport = connect(&url).await;
+        transport
+            .notify(JsonRpcRequest::new_notification(
+                "notifications/initialized",
+                None,
+            ))
+            .await
+            .unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].body.get("id").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_expired_session() {
+        let (url, _) = spawn_server(|req| match req.rpc_method() {
+            "initialize" => json_reply(
+                &req.rpc_id(),
+                serde_json::json!({}),
+                &[("Mcp-Session-Id", "gone")],
+            ),
+            _ => MockReply::Full(http_response("404 Not Found", &[], "")),
+        })
+        .await;
+
+        let transport = connect(&url).await;
+        transport
+            .request(
+                JsonRpcRequest::new_request(0, "initialize", None),
+                &RequestTimeout::default(),
+            )
+            .await
+            .unwrap();
+
+        let err = transport
+            .request(
+                JsonRpcRequest::new_request(0, "tools/list", None),
+                &RequestTimeout::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, McpError::SessionExpired));
+        assert!(transport.session_id().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_requires_authorization() {
+        let (url, _) = spawn_server(|_| {
+            MockReply::Full(http_response(
+                "401 Unauthorized",
+                &[("WWW-Authenticate", "Bearer")],
+                "",
+            ))
+        })
+        .await;
+
+        let transport = connect(&url).await;
+        let err = transport
+            .request(
+                JsonRpcRequest::new_request(0, "initialize", None),
+                &RequestTimeout::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, McpError::AuthorizationRequired(server) if server.ends_with("/mcp")));
+    }
+}
@@ -0,0 +1,203 @@
+//! Test utilities module - minimal HTTP server for exercising MCP HTTP transports
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+use super::{ServerMessage, ServerMessageHandler};
+
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: Value,
+}
+
+impl MockRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    pub fn rpc_id(&self) -> Value {
+        self.body.get("id").cloned().unwrap_or(Value::Null)
+    }
+
+    pub fn rpc_method(&self) -> &str {
+        self.body
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+    }
+}
+
+/// Reply for a mock request: either a complete response, or a response head
+/// followed by body chunks pushed through a channel until it is dropped
+pub enum MockReply {
+    Full(String),
+    Stream(String, mpsc::UnboundedReceiver<String>),
+}
+
+pub type Requests = Arc<Mutex<Vec<MockRequest>>>;
+
+/// Server message handler that forwards into a channel the test can inspect
+pub fn message_channel() -> (ServerMessageHandler, mpsc::UnboundedReceiver<ServerMessage>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let handler: ServerMessageHandler = Arc::new(move |message| {
+        let _ = tx.send(message);
+    });
+    (handler, rx)
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<MockRequest> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+        .collect();
+
+    let content_length: usize = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    while buf.len() < header_end + 4 + content_length {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    let body = serde_json::from_slice(&buf[header_end + 4..]).unwrap_or(Value::Null);
+    Some(MockRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+/// Start a server on a random local port. Returns its base URL (`http://127.0.0.1:<port>`)
+/// and the log of received requests.
+pub async fn spawn_server<F>(handler: F) -> (String, Requests)
+where
+    F: Fn(&MockRequest) -> MockReply + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let requests: Requests = Arc::new(Mutex::new(Vec::new()));
+    let handler = Arc::new(handler);
+
+    let recorded = Arc::clone(&requests);
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let handler = Arc::clone(&handler);
+            let recorded = Arc::clone(&recorded);
+            tokio::spawn(async move {
+                let Some(req) = read_request(&mut stream).await else {
+                    return;
+                };
+                recorded.lock().unwrap().push(req.clone());
+                match handler(&req) {
+                    MockReply::Full(reply) => {
+                        let _ = stream.write_all(reply.as_bytes()).await;
+                    }
+                    MockReply::Stream(head, mut chunks) => {
+                        if stream.write_all(head.as_bytes()).await.is_err() {
+                            return;
+                        }
+                        while let Some(chunk) = chunks.recv().await {
+                            if stream.write_all(chunk.as_bytes()).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+
+    (base_url, requests)
+}
+
+pub fn http_response(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+    let mut out = format!(
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        body.len()
+    );
+    for (k, v) in headers {
+        out.push_str(&format!("{k}: {v}\r\n"));
+    }
+    out.push_str("\r\n");
+    out.push_str(body);
+    out
+}
+
+pub fn json_reply(id: &Value, result: Value, headers: &[(&str, &str)]) -> MockReply {
+    let body = serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": result });
+    let mut all = vec![("Content-Type", "application/json")];
+    all.extend_from_slice(headers);
+    MockReply::Full(http_response("200 OK", &all, &body.to_string()))
+}
+
+/// Head of an open-ended `text/event-stream` response (body ends when the connection closes)
+pub fn sse_head() -> String {
+    "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n"
+        .to_string()
+}
+
+/// Encode one SSE event with a single-line data field
+pub fn sse_event(event: Option<&str>, id: Option<&str>, data: &str) -> String {
+    let mut out = String::new();
+    if let Some(event) = event {
+        out.push_str(&format!("event: {event}\n"));
+    }
+    if let Some(id) = id {
+        out.push_str(&format!("id: {id}\n"));
+    }
+    out.push_str(&format!("data: {data}\n\n"));
+    out
+}
+
+/// Complete SSE response containing the given `(id, data)` events.
+/// `Value::Null` data produces an empty data line.
+pub fn sse_reply(events: &[(Option<&str>, Value)]) -> MockReply {
+    let body: String = events
+        .iter()
+        .map(|(id, data)| {
+            let data = if data.is_null() {
+                String::new()
+            } else {
+                data.to_string()
+            };
+            sse_event(None, *id, &data)
+        })
+        .collect();
+    MockReply::Full(http_response(
+        "200 OK",
+        &[("Content-Type", "text/event-stream")],
+        &body,
+    ))
+}
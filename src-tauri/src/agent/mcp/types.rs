@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::agent::mcp::protocol::jsonrpc::JsonRpcRequest;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ServerInfo {
@@ -27,6 +29,112 @@ pub struct McpCallResult {
     pub is_error: bool,
 }
 
+/// Capabilities advertised by the server in its `initialize` result
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerCapabilities {
+    #[serde(default)]
+    pub tools: Option<Value>,
+    #[serde(default)]
+    pub resources: Option<Value>,
+    #[serde(default)]
+    pub prompts: Option<Value>,
+    #[serde(default)]
+    pub logging: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResource {
+    pub uri: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+}
+
+/// One entry of a `resources/read` result (either `text` or base64 `blob` is set)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResourceContents {
+    pub uri: String,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub blob: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpPromptArgument {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpPrompt {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<McpPromptArgument>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpPromptMessage {
+    pub role: String,
+    pub content: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpGetPromptResult {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub messages: Vec<McpPromptMessage>,
+}
+
+/// Params of `notifications/progress`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpProgressParams {
+    pub progress_token: Value,
+    pub progress: f64,
+    #[serde(default)]
+    pub total: Option<f64>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// Params of `notifications/message` (server log entry)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpLogParams {
+    pub level: String,
+    #[serde(default)]
+    pub logger: Option<String>,
+    #[serde(default)]
+    pub data: Value,
+}
+
+/// Server messages relayed to an in-flight tool call
+#[derive(Debug, Clone)]
+pub enum McpCallEvent {
+    Progress(McpProgressParams),
+    Log(McpLogParams),
+    /// `sampling/createMessage` or `elicitation/create`, answered by the call's task
+    Request(JsonRpcRequest),
+}
+
 /// MCP server source
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -42,6 +150,21 @@ pub enum McpConnectionStatus {
     Connected,
     Disconnected,
     Error,
+    /// Remote server rejected us and the user has to complete OAuth
+    #[serde(rename = "needs_auth")]
+    NeedsAuth,
+    /// Transport died; the supervisor is restarting the server
+    Reconnecting,
+}
+
+/// Emitted when the health supervisor changes a server's status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerStatusEvent {
+    pub workspace: String,
+    pub name: String,
+    pub status: McpConnectionStatus,
+    pub error: Option<String>,
 }
 
 /// Tool brief information (for frontend display)
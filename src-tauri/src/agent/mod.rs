@@ -11,6 +11,7 @@ pub mod common; // Common utilities and templates
 pub mod compaction; // Context engineering: Prune/Compact/checkpoint loading
 pub mod context; // Session context tracker and summarizer
 pub mod core; // Executor core (executor only, no tool-related)
+pub mod hooks; // settings.json lifecycle hooks (user shell commands)
 pub mod mcp; // MCP adapter
 pub mod permissions; // settings.json permissions (allow/deny/ask)
 pub mod persistence; // Persistence and repository abstraction
@@ -21,6 +22,7 @@ pub mod terminal; // Agent terminal subsystem
 pub mod tools; // Tool interface and built-in tools
 pub mod utils; // Utility functions
 pub mod workspace_changes; // Workspace change ledger (user/external change injection)
+pub mod worktree; // Merge-back and cleanup of subtask git worktrees
 pub use config::*;
 pub use error::*;
 pub use types::*;
@@ -4,7 +4,7 @@ use crate::storage::database::DatabaseManager;
 
 use super::repositories::{
     AgentNodeRepository, MessageRepository, RunRepository, SessionRepository,
-    ToolExecutionRepository, WorkspaceRepository,
+    ToolExecutionRepository, UsageRepository, WorkspaceRepository,
 };
 
 /// Facade that wires all persistence repositories together for the agent backend.
@@ -17,6 +17,7 @@ pub struct AgentPersistence {
     agent_nodes: AgentNodeRepository,
     messages: MessageRepository,
     tool_executions: ToolExecutionRepository,
+    usage: UsageRepository,
 }
 
 impl AgentPersistence {
@@ -28,6 +29,7 @@ impl AgentPersistence {
             runs: RunRepository::new(Arc::clone(&database)),
             agent_nodes: AgentNodeRepository::new(Arc::clone(&database)),
             messages: MessageRepository::new(Arc::clone(&database)),
+            usage: UsageRepository::new(Arc::clone(&database)),
             database,
         }
     }
@@ -59,4 +61,8 @@ impl AgentPersistence {
     pub fn tool_executions(&self) -> &ToolExecutionRepository {
         &self.tool_executions
     }
+
+    pub fn usage(&self) -> &UsageRepository {
+        &self.usage
+    }
 }
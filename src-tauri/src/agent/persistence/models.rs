@@ -178,6 +178,8 @@ pub struct Run {
     pub root_node_id: Option<i64>,
     pub status: RunStatus,
     pub summary: Option<String>,
+    /// USD spent on LLM calls in this run, including subtasks
+    pub total_cost: f64,
     pub created_at: DateTime<Utc>,
     pub started_at: Option<DateTime<Utc>>,
     pub finished_at: Option<DateTime<Utc>>,
@@ -280,6 +282,75 @@ pub(crate) fn build_tool_execution(row: &sqlx::sqlite::SqliteRow) -> AgentResult
     })
 }
 
+/// What an LLM call was made for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UsagePurpose {
+    /// A turn of the agent loop, including subtask agents
+    Agent,
+    Compaction,
+    SubtaskSummary,
+    WebFetch,
+    McpSampling,
+}
+
+impl UsagePurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Agent => "agent",
+            Self::Compaction => "compaction",
+            Self::SubtaskSummary => "subtask_summary",
+            Self::WebFetch => "web_fetch",
+            Self::McpSampling => "mcp_sampling",
+        }
+    }
+}
+
+/// Time bucket for spend reports; buckets are aligned to UTC, weeks start on Monday
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SpendPeriod {
+    Day,
+    Week,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SpendGroup {
+    Workspace,
+    Model,
+}
+
+/// Spend of one workspace or model within one period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendBucket {
+    /// Unix timestamp (seconds) of the start of the period
+    pub period_start: i64,
+    /// Workspace path or model id, depending on the grouping
+    pub key: String,
+    pub request_count: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cache_write_tokens: i64,
+    /// USD
+    pub cost: f64,
+}
+
+pub(crate) fn build_spend_bucket(row: &sqlx::sqlite::SqliteRow) -> AgentResult<SpendBucket> {
+    Ok(SpendBucket {
+        period_start: row.try_get("period_start")?,
+        key: row.try_get("key")?,
+        request_count: row.try_get("request_count")?,
+        input_tokens: row.try_get("input_tokens")?,
+        output_tokens: row.try_get("output_tokens")?,
+        cache_read_tokens: row.try_get("cache_read_tokens")?,
+        cache_write_tokens: row.try_get("cache_write_tokens")?,
+        cost: row.try_get("cost")?,
+    })
+}
+
 pub(crate) fn build_run(row: &sqlx::sqlite::SqliteRow) -> AgentResult<Run> {
     Ok(Run {
         id: row.try_get("id")?,
@@ -288,6 +359,7 @@ pub(crate) fn build_run(row: &sqlx::sqlite::SqliteRow) -> AgentResult<Run> {
         root_node_id: row.try_get("root_node_id")?,
         status: RunStatus::from_str(row.try_get::<String, _>("status")?.as_str())?,
         summary: row.try_get("summary")?,
+        total_cost: row.try_get("total_cost")?,
         created_at: timestamp_to_datetime(row.try_get::<i64, _>("created_at")?),
         started_at: opt_timestamp_to_datetime(row.try_get("started_at")?),
         finished_at: opt_timestamp_to_datetime(row.try_get("finished_at")?),
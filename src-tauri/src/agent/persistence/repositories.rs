@@ -8,8 +8,9 @@ use crate::agent::types::{Block, Message, MessageRole, MessageStatus, TokenUsage
 use crate::storage::database::DatabaseManager;
 
 use super::models::{
-    build_agent_node, build_run, build_session, build_tool_execution, build_workspace, AgentNode,
-    AgentNodeRole, Run, RunStatus, Session, ToolExecution, Workspace,
+    build_agent_node, build_run, build_session, build_spend_bucket, build_tool_execution,
+    build_workspace, AgentNode, AgentNodeRole, Run, RunStatus, Session, SpendBucket, SpendGroup,
+    SpendPeriod, ToolExecution, UsagePurpose, Workspace,
 };
 use super::{
     bool_to_sql, now_timestamp, opt_datetime_to_timestamp, opt_timestamp_to_datetime,
@@ -376,6 +377,16 @@ impl SessionRepository {
         Ok(())
     }
 
+    pub async fn clear_worktree_path(&self, id: i64) -> AgentResult<()> {
+        let ts = now_timestamp();
+        sqlx::query("UPDATE sessions SET worktree_path = NULL, updated_at = ? WHERE id = ?")
+            .bind(ts)
+            .bind(id)
+            .execute(self.pool())
+            .await?;
+        Ok(())
+    }
+
     pub async fn update_model_selection(
         &self,
         id: i64,
@@ -771,6 +782,156 @@ impl ToolExecutionRepository {
     }
 }
 
+#[derive(Debug)]
+pub struct UsageRepository {
+    database: Arc<DatabaseManager>,
+}
+
+pub struct RecordUsageParams<'a> {
+    pub workspace_path: Option<&'a str>,
+    pub session_id: Option<i64>,
+    pub run_id: Option<i64>,
+    pub message_id: Option<i64>,
+    pub model_id: &'a str,
+    pub provider_id: Option<&'a str>,
+    pub purpose: UsagePurpose,
+    pub usage: &'a TokenUsage,
+    /// USD
+    pub cost: f64,
+}
+
+pub struct SpendQuery<'a> {
+    pub period: SpendPeriod,
+    pub group_by: SpendGroup,
+    /// Inclusive unix timestamp (seconds)
+    pub since: i64,
+    /// Exclusive unix timestamp (seconds)
+    pub until: i64,
+    pub workspace_path: Option<&'a str>,
+}
+
+impl UsageRepository {
+    pub fn new(database: Arc<DatabaseManager>) -> Self {
+        Self { database }
+    }
+
+    fn pool(&self) -> &sqlx::SqlitePool {
+        self.database.pool()
+    }
+
+    /// Store one LLM call and add its cost to the message, session, run and model totals.
+    pub async fn record(&self, params: RecordUsageParams<'_>) -> AgentResult<()> {
+        let usage = params.usage;
+        let cache_read = usage.cache_read_tokens.unwrap_or(0);
+        let cache_write = usage.cache_write_tokens.unwrap_or(0);
+        let total_tokens = usage.input_tokens + usage.output_tokens;
+
+        let mut tx = self.pool().begin().await?;
+        sqlx::query(
+            "INSERT INTO llm_usage (
+                workspace_path, session_id, run_id, message_id, model_id, provider_id, purpose,
+                input_tokens, output_tokens, cache_read_tokens, cache_write_tokens, cost, created_at
+             ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(params.workspace_path)
+        .bind(params.session_id)
+        .bind(params.run_id)
+        .bind(params.message_id)
+        .bind(params.model_id)
+        .bind(params.provider_id)
+        .bind(params.purpose.as_str())
+        .bind(usage.input_tokens)
+        .bind(usage.output_tokens)
+        .bind(cache_read)
+        .bind(cache_write)
+        .bind(params.cost)
+        .bind(now_timestamp())
+        .execute(&mut *tx)
+        .await?;
+
+        if let Some(message_id) = params.message_id {
+            sqlx::query("UPDATE messages SET cost = cost + ? WHERE id = ?")
+                .bind(params.cost)
+                .bind(message_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        if let Some(session_id) = params.session_id {
+            sqlx::query(
+                "UPDATE sessions
+                 SET total_cost = total_cost + ?, total_tokens = total_tokens + ?
+                 WHERE id = ?",
+            )
+            .bind(params.cost)
+            .bind(total_tokens)
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        if let Some(run_id) = params.run_id {
+            sqlx::query("UPDATE runs SET total_cost = total_cost + ? WHERE id = ?")
+                .bind(params.cost)
+                .bind(run_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query(
+            "INSERT INTO ai_model_usage_stats (model_id, request_count, total_tokens, total_cost, last_used_at)
+             VALUES (?, 1, ?, ?, CURRENT_TIMESTAMP)
+             ON CONFLICT(model_id) DO UPDATE SET
+                 request_count = request_count + 1,
+                 total_tokens = total_tokens + excluded.total_tokens,
+                 total_cost = total_cost + excluded.total_cost,
+                 last_used_at = excluded.last_used_at,
+                 updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(params.model_id)
+        .bind(total_tokens)
+        .bind(params.cost)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Spend per day or week, grouped by workspace or model, oldest period first.
+    pub async fn spend(&self, query: SpendQuery<'_>) -> AgentResult<Vec<SpendBucket>> {
+        // 1970-01-01 was a Thursday; shifting by four days aligns weeks to Monday
+        let period_start = match query.period {
+            SpendPeriod::Day => "(created_at / 86400) * 86400",
+            SpendPeriod::Week => "((created_at - 345600) / 604800) * 604800 + 345600",
+        };
+        let key = match query.group_by {
+            SpendGroup::Workspace => "COALESCE(workspace_path, '')",
+            SpendGroup::Model => "model_id",
+        };
+        let sql = format!(
+            "SELECT {period_start} AS period_start, {key} AS key,
+                    COUNT(*) AS request_count,
+                    SUM(input_tokens) AS input_tokens,
+                    SUM(output_tokens) AS output_tokens,
+                    SUM(cache_read_tokens) AS cache_read_tokens,
+                    SUM(cache_write_tokens) AS cache_write_tokens,
+                    SUM(cost) AS cost
+             FROM llm_usage
+             WHERE created_at >= ? AND created_at < ?
+               AND (? IS NULL OR workspace_path = ?)
+             GROUP BY period_start, key
+             ORDER BY period_start ASC, cost DESC"
+        );
+
+        let rows = sqlx::query(&sql)
+            .bind(query.since)
+            .bind(query.until)
+            .bind(query.workspace_path)
+            .bind(query.workspace_path)
+            .fetch_all(self.pool())
+            .await?;
+        rows.iter().map(build_spend_bucket).collect()
+    }
+}
+
 fn role_as_str(role: &MessageRole) -> &'static str {
     match role {
         MessageRole::User => "user",
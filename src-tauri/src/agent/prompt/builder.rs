@@ -201,6 +201,15 @@ impl PromptBuilder {
         vars.insert("count".to_string(), count.to_string());
         Self::render_template(template, &vars)
     }
+
+    /// Get run budget reminder and fill variables
+    pub fn get_budget_warning(&self, percent: u32, usage: &str) -> String {
+        let template = BuiltinPrompts::reminder_budget_warning();
+        let mut vars = HashMap::new();
+        vars.insert("percent".to_string(), percent.to_string());
+        vars.insert("usage".to_string(), usage.to_string());
+        Self::render_template(template, &vars)
+    }
 }
 
 /// Remove frontmatter, return only body content
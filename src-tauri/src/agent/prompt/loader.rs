@@ -67,6 +67,10 @@ impl BuiltinPrompts {
         include_str!("../../../prompts/reminders/duplicate_tools.md")
     }
 
+    pub fn reminder_budget_warning() -> &'static str {
+        include_str!("../../../prompts/reminders/budget_warning.md")
+    }
+
     pub fn reminder_max_steps() -> &'static str {
         include_str!("../../../prompts/reminders/max_steps.md")
     }
@@ -185,6 +189,7 @@ impl PromptLoader {
             ("reminders", "loop_warning") => Some(BuiltinPrompts::reminder_loop_warning()),
             ("reminders", "duplicate_tools") => Some(BuiltinPrompts::reminder_duplicate_tools()),
             ("reminders", "max_steps") => Some(BuiltinPrompts::reminder_max_steps()),
+            ("reminders", "budget_warning") => Some(BuiltinPrompts::reminder_budget_warning()),
             ("system", "env") => Some(BuiltinPrompts::system_env()),
             ("system", "compaction") => Some(BuiltinPrompts::system_compaction()),
             ("system", "subtask_summary_user") => {
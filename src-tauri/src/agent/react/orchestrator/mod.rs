@@ -23,18 +23,19 @@ use crate::agent::agents::{visible_task_profiles, AgentConfigLoader};
 use crate::agent::compaction::{
     CompactionConfig, CompactionService, CompactionTrigger, SessionMessageLoader,
 };
+use crate::agent::core::context::budget::BudgetState;
 use crate::agent::core::context::TaskContext;
 use crate::agent::core::iteration_outcome::IterationOutcome;
 use crate::agent::core::utils::should_render_tool_block;
 use crate::agent::error::{TaskExecutorError, TaskExecutorResult};
-use crate::agent::persistence::AgentPersistence;
+use crate::agent::persistence::{AgentPersistence, UsagePurpose};
 use crate::agent::prompt::PromptBuilder;
 use crate::agent::state::iteration::{IterationContext, IterationSnapshot};
 use crate::agent::terminal::AgentTerminalManager;
 use crate::agent::tools::ToolDescriptionContext;
 use crate::agent::types::{Block, TextBlock, ThinkingBlock, ToolBlock, ToolStatus};
 use crate::llm::anthropic_types::{
-    ContentBlock, ContentBlockStart, ContentDelta, StreamEvent, SystemPrompt,
+    ContentBlock, ContentBlockStart, ContentDelta, StreamEvent, SystemPrompt, Usage,
 };
 use crate::storage::DatabaseManager;
 
@@ -93,14 +94,32 @@ impl ReactOrchestrator {
             // Clear transient system reminders (e.g. loop warnings) each iteration; they are
             // meant to influence the *next* step only, not permanently replace the base prompt.
             context.set_system_prompt_overlay(None).await?;
+            let mut overlays = Vec::new();
             if let Some(manager) = AgentTerminalManager::global() {
-                if let Some(overlay) = manager.build_prompt_overlay(context.session_id) {
-                    if let Err(err) = context
-                        .set_system_prompt_overlay(Some(SystemPrompt::Text(overlay)))
-                        .await
-                    {
-                        warn!("Failed to apply terminal prompt overlay: {}", err);
-                    }
+                overlays.extend(manager.build_prompt_overlay(context.session_id));
+            }
+
+            // The budget is shared with subtasks, so check it before every LLM call
+            match context.run_budget().state() {
+                BudgetState::Within => {}
+                BudgetState::Approaching(usage) => {
+                    let percent = (usage.ratio() * 100.0).floor() as u32;
+                    let warning =
+                        PromptBuilder::new(None).get_budget_warning(percent, &usage.describe());
+                    overlays.push(format!(
+                        "<system-reminder type=\"budget\">\n{warning}\n</system-reminder>"
+                    ));
+                }
+                BudgetState::Exceeded(usage) => {
+                    return Err(TaskExecutorError::BudgetExceeded(usage.describe()));
+                }
+            }
+            if !overlays.is_empty() {
+                if let Err(err) = context
+                    .set_system_prompt_overlay(Some(SystemPrompt::Text(overlays.join("\n\n"))))
+                    .await
+                {
+                    warn!("Failed to apply prompt overlay: {}", err);
                 }
             }
 
@@ -334,334 +353,351 @@ impl ReactOrchestrator {
             let mut thinking_created = false;
             let mut text_created = false;
             let mut stop_reason: Option<crate::llm::anthropic_types::StopReason> = None;
+            let mut usage: Option<Usage> = None;
 
             // ===== Phase 3: Process Anthropic StreamEvent =====
-            while let Some(item) = stream.next().await {
-                if context.is_aborted() {
-                    return Err(TaskExecutorError::TaskInterrupted);
-                }
-                context.check_aborted_async(true).await?;
-
-                match item {
-                    Ok(StreamEvent::MessageStart { .. }) => {}
-                    Ok(StreamEvent::ContentBlockStart {
-                        index,
-                        content_block,
-                    }) => match content_block {
-                        ContentBlockStart::Text { text } => {
-                            current_blocks.insert(index, BlockAccumulator::Text(text));
-                        }
-                        ContentBlockStart::ToolUse { id, name } => {
-                            let should_render = should_render_tool_block(context, &name).await;
-                            tool_block_visibility.insert(id.clone(), should_render);
-                            if should_render {
-                                let now = Utc::now();
-                                tool_block_started_at.insert(id.clone(), now);
-                                context
-                                    .assistant_append_block(Block::Tool(ToolBlock {
-                                        id: id.clone(),
-                                        call_id: id.clone(),
-                                        name: name.clone(),
-                                        status: ToolStatus::Pending,
-                                        input: Value::Object(serde_json::Map::new()),
-                                        output: None,
-                                        compacted_at: None,
-                                        started_at: now,
-                                        finished_at: None,
-                                        duration_ms: None,
-                                    }))
-                                    .await?;
-                            }
-                            current_blocks.insert(
-                                index,
-                                BlockAccumulator::ToolUse {
-                                    id,
-                                    name,
-                                    input_json: String::new(),
-                                    last_ui_update: Instant::now(),
-                                    last_ui_len: 0,
-                                },
-                            );
+            // Usage is recorded however the stream ends: aborted and failed calls are billed too
+            let streamed: TaskExecutorResult<()> = async {
+                while let Some(item) = stream.next().await {
+                    if context.is_aborted() {
+                        return Err(TaskExecutorError::TaskInterrupted);
+                    }
+                    context.check_aborted_async(true).await?;
+
+                    match item {
+                        Ok(StreamEvent::MessageStart { message }) => {
+                            usage = Some(merge_stream_usage(usage, message.usage));
                         }
-                        ContentBlockStart::Thinking { thinking, metadata } => {
-                            if !thinking.is_empty() {
-                                iter_ctx.append_thinking(&thinking);
+                        Ok(StreamEvent::ContentBlockStart {
+                            index,
+                            content_block,
+                        }) => match content_block {
+                            ContentBlockStart::Text { text } => {
+                                current_blocks.insert(index, BlockAccumulator::Text(text));
                             }
-                            current_blocks
-                                .insert(index, BlockAccumulator::Thinking { thinking, metadata });
-                        }
-                        ContentBlockStart::Unknown => {}
-                    },
-                    Ok(StreamEvent::ContentBlockDelta { index, delta }) => {
-                        if let Some(block) = current_blocks.get_mut(&index) {
-                            match delta {
-                                ContentDelta::Text { text } => {
-                                    if let BlockAccumulator::Text(s) = block {
-                                        s.push_str(&text);
-                                        let id = text_stream_id
-                                            .get_or_insert_with(|| Uuid::new_v4().to_string())
-                                            .clone();
-                                        let block = Block::Text(TextBlock {
+                            ContentBlockStart::ToolUse { id, name } => {
+                                let should_render = should_render_tool_block(context, &name).await;
+                                tool_block_visibility.insert(id.clone(), should_render);
+                                if should_render {
+                                    let now = Utc::now();
+                                    tool_block_started_at.insert(id.clone(), now);
+                                    context
+                                        .assistant_append_block(Block::Tool(ToolBlock {
                                             id: id.clone(),
-                                            content: s.clone(),
-                                            is_streaming: true,
-                                        });
-                                        if text_created {
-                                            context.assistant_update_block(&id, block).await?;
-                                        } else {
-                                            context.assistant_append_block(block).await?;
-                                            text_created = true;
-                                        }
-                                        iter_ctx.append_output(&text);
-                                    }
+                                            call_id: id.clone(),
+                                            name: name.clone(),
+                                            status: ToolStatus::Pending,
+                                            input: Value::Object(serde_json::Map::new()),
+                                            output: None,
+                                            compacted_at: None,
+                                            started_at: now,
+                                            finished_at: None,
+                                            duration_ms: None,
+                                        }))
+                                        .await?;
                                 }
-                                ContentDelta::InputJson { partial_json } => {
-                                    if let BlockAccumulator::ToolUse {
+                                current_blocks.insert(
+                                    index,
+                                    BlockAccumulator::ToolUse {
                                         id,
                                         name,
-                                        input_json,
-                                        last_ui_update,
-                                        last_ui_len,
-                                    } = block
-                                    {
-                                        input_json.push_str(&partial_json);
-
-                                        if !tool_block_visibility.get(id).copied().unwrap_or(false)
-                                        {
-                                            continue;
+                                        input_json: String::new(),
+                                        last_ui_update: Instant::now(),
+                                        last_ui_len: 0,
+                                    },
+                                );
+                            }
+                            ContentBlockStart::Thinking { thinking, metadata } => {
+                                if !thinking.is_empty() {
+                                    iter_ctx.append_thinking(&thinking);
+                                }
+                                current_blocks
+                                    .insert(index, BlockAccumulator::Thinking { thinking, metadata });
+                            }
+                            ContentBlockStart::Unknown => {}
+                        },
+                        Ok(StreamEvent::ContentBlockDelta { index, delta }) => {
+                            if let Some(block) = current_blocks.get_mut(&index) {
+                                match delta {
+                                    ContentDelta::Text { text } => {
+                                        if let BlockAccumulator::Text(s) = block {
+                                            s.push_str(&text);
+                                            let id = text_stream_id
+                                                .get_or_insert_with(|| Uuid::new_v4().to_string())
+                                                .clone();
+                                            let block = Block::Text(TextBlock {
+                                                id: id.clone(),
+                                                content: s.clone(),
+                                                is_streaming: true,
+                                            });
+                                            if text_created {
+                                                context.assistant_update_block(&id, block).await?;
+                                            } else {
+                                                context.assistant_append_block(block).await?;
+                                                text_created = true;
+                                            }
+                                            iter_ctx.append_output(&text);
                                         }
+                                    }
+                                    ContentDelta::InputJson { partial_json } => {
+                                        if let BlockAccumulator::ToolUse {
+                                            id,
+                                            name,
+                                            input_json,
+                                            last_ui_update,
+                                            last_ui_len,
+                                        } = block
+                                        {
+                                            input_json.push_str(&partial_json);
 
-                                        const MIN_UI_UPDATE_INTERVAL: Duration =
-                                            Duration::from_millis(750);
-                                        const MIN_BYTES_DELTA_FOR_UPDATE: usize = 2048;
+                                            if !tool_block_visibility.get(id).copied().unwrap_or(false)
+                                            {
+                                                continue;
+                                            }
 
-                                        let now = Instant::now();
-                                        let bytes = input_json.len();
-                                        let bytes_delta = bytes.saturating_sub(*last_ui_len);
-                                        if bytes_delta < MIN_BYTES_DELTA_FOR_UPDATE
-                                            && now.duration_since(*last_ui_update)
-                                                < MIN_UI_UPDATE_INTERVAL
-                                        {
-                                            continue;
-                                        }
+                                            const MIN_UI_UPDATE_INTERVAL: Duration =
+                                                Duration::from_millis(750);
+                                            const MIN_BYTES_DELTA_FOR_UPDATE: usize = 2048;
 
-                                        *last_ui_update = now;
-                                        *last_ui_len = bytes;
+                                            let now = Instant::now();
+                                            let bytes = input_json.len();
+                                            let bytes_delta = bytes.saturating_sub(*last_ui_len);
+                                            if bytes_delta < MIN_BYTES_DELTA_FOR_UPDATE
+                                                && now.duration_since(*last_ui_update)
+                                                    < MIN_UI_UPDATE_INTERVAL
+                                            {
+                                                continue;
+                                            }
 
-                                        let started_at = match tool_block_started_at.get(id) {
-                                            Some(started_at) => *started_at,
-                                            None => {
+                                            *last_ui_update = now;
+                                            *last_ui_len = bytes;
+
+                                            let started_at = match tool_block_started_at.get(id) {
+                                                Some(started_at) => *started_at,
+                                                None => {
+                                                    warn!(
+                                                        tool_use_id = %id,
+                                                        tool_name = %name,
+                                                        "Missing tool start timestamp for streaming update"
+                                                    );
+                                                    Utc::now()
+                                                }
+                                            };
+                                            if let Err(err) = context
+                                                .assistant_upsert_block(Block::Tool(ToolBlock {
+                                                    id: id.clone(),
+                                                    call_id: id.clone(),
+                                                    name: name.clone(),
+                                                    status: ToolStatus::Pending,
+                                                    input: serde_json::json!({
+                                                        "__streaming": true,
+                                                        "__inputBytes": bytes,
+                                                    }),
+                                                    output: None,
+                                                    compacted_at: None,
+                                                    started_at,
+                                                    finished_at: None,
+                                                    duration_ms: None,
+                                                }))
+                                                .await
+                                            {
                                                 warn!(
-                                                    tool_use_id = %id,
-                                                    tool_name = %name,
-                                                    "Missing tool start timestamp for streaming update"
+                                                    "Failed to stream pending tool block '{}' to UI: {}",
+                                                    id, err
                                                 );
-                                                Utc::now()
                                             }
-                                        };
-                                        if let Err(err) = context
-                                            .assistant_upsert_block(Block::Tool(ToolBlock {
-                                                id: id.clone(),
-                                                call_id: id.clone(),
-                                                name: name.clone(),
-                                                status: ToolStatus::Pending,
-                                                input: serde_json::json!({
-                                                    "__streaming": true,
-                                                    "__inputBytes": bytes,
-                                                }),
-                                                output: None,
-                                                compacted_at: None,
-                                                started_at,
-                                                finished_at: None,
-                                                duration_ms: None,
-                                            }))
-                                            .await
-                                        {
-                                            warn!(
-                                                "Failed to stream pending tool block '{}' to UI: {}",
-                                                id, err
-                                            );
-                                        }
-                                    }
-                                }
-                                ContentDelta::Thinking { thinking: delta } => {
-                                    if let BlockAccumulator::Thinking { thinking, .. } = block {
-                                        thinking.push_str(&delta);
-                                        let id = thinking_stream_id
-                                            .get_or_insert_with(|| Uuid::new_v4().to_string());
-                                        let block = Block::Thinking(ThinkingBlock {
-                                            id: id.clone(),
-                                            content: thinking.clone(),
-                                            is_streaming: true,
-                                            metadata: None,
-                                        });
-                                        if thinking_created {
-                                            context.assistant_update_block(id, block).await?;
-                                        } else {
-                                            context.assistant_append_block(block).await?;
-                                            thinking_created = true;
                                         }
-                                        iter_ctx.append_thinking(&delta);
-                                    }
-                                }
-                                ContentDelta::Signature { signature: sig } => {
-                                    if let BlockAccumulator::Thinking { metadata, .. } = block {
-                                        let m = metadata.get_or_insert_with(Default::default);
-                                        m.signature = Some(sig);
                                     }
-                                }
-                                ContentDelta::Unknown => {}
-                            }
-                        }
-                    }
-                    Ok(StreamEvent::ContentBlockStop { index }) => {
-                        if let Some(block) = current_blocks.remove(&index) {
-                            match block {
-                                BlockAccumulator::Text(text) => {
-                                    if text_created {
-                                        if let Some(id) = &text_stream_id {
-                                            let block = Block::Text(TextBlock {
+                                    ContentDelta::Thinking { thinking: delta } => {
+                                        if let BlockAccumulator::Thinking { thinking, .. } = block {
+                                            thinking.push_str(&delta);
+                                            let id = thinking_stream_id
+                                                .get_or_insert_with(|| Uuid::new_v4().to_string());
+                                            let block = Block::Thinking(ThinkingBlock {
                                                 id: id.clone(),
-                                                content: text.clone(),
-                                                is_streaming: false,
+                                                content: thinking.clone(),
+                                                is_streaming: true,
+                                                metadata: None,
                                             });
-                                            if let Err(err) =
-                                                context.assistant_update_block(id, block).await
-                                            {
-                                                warn!(
-                                                    "Failed to finalize text block '{}' in UI: {}",
-                                                    id, err
-                                                );
+                                            if thinking_created {
+                                                context.assistant_update_block(id, block).await?;
+                                            } else {
+                                                context.assistant_append_block(block).await?;
+                                                thinking_created = true;
                                             }
+                                            iter_ctx.append_thinking(&delta);
                                         }
                                     }
-                                    if !text.is_empty() {
-                                        text_content.push(text);
-                                    }
-                                }
-                                BlockAccumulator::ToolUse {
-                                    id,
-                                    name,
-                                    input_json,
-                                    ..
-                                } => {
-                                    // Some OpenAI-compatible models (e.g., GLM) may send
-                                    // duplicate arguments via both `tool_calls` and
-                                    // `function_call`, resulting in concatenated JSON like
-                                    // `{...}{...}`. We defensively parse only the first
-                                    // valid JSON object using a streaming deserializer.
-                                    let input: Value = {
-                                        let mut de =
-                                            serde_json::Deserializer::from_str(&input_json)
-                                                .into_iter::<Value>();
-                                        de.next()
-                                            .ok_or_else(|| {
-                                                TaskExecutorError::InternalError(
-                                                    "Empty tool input JSON from stream".to_string(),
-                                                )
-                                            })?
-                                            .map_err(|err| {
-                                                TaskExecutorError::InternalError(format!(
-                                                    "Invalid tool input JSON from stream: {err}"
-                                                ))
-                                            })?
-                                    };
-                                    tool_use_blocks.push(ContentBlock::ToolUse {
-                                        id: id.clone(),
-                                        name: name.clone(),
-                                        input: input.clone(),
-                                    });
-
-                                    if tool_block_visibility.get(&id).copied().unwrap_or(false) {
-                                        let now = Utc::now();
-                                        let started_at =
-                                            tool_block_started_at.get(&id).cloned().unwrap_or(now);
-                                        context
-                                            .assistant_upsert_block(Block::Tool(ToolBlock {
-                                                id: id.clone(),
-                                                call_id: id.clone(),
-                                                name: name.clone(),
-                                                status: ToolStatus::Pending,
-                                                input: input.clone(),
-                                                output: None,
-                                                compacted_at: None,
-                                                started_at,
-                                                finished_at: None,
-                                                duration_ms: None,
-                                            }))
-                                            .await?;
+                                    ContentDelta::Signature { signature: sig } => {
+                                        if let BlockAccumulator::Thinking { metadata, .. } = block {
+                                            let m = metadata.get_or_insert_with(Default::default);
+                                            m.signature = Some(sig);
+                                        }
                                     }
-
-                                    context.states.react_runtime.write().await.record_action(
-                                        react_iteration_index,
-                                        name.clone(),
-                                        input.clone(),
-                                    );
-                                    iter_ctx.add_tool_call(id.clone(), name.clone(), input.clone());
-                                    pending_tool_calls.push((id, name, input));
+                                    ContentDelta::Unknown => {}
                                 }
-                                BlockAccumulator::Thinking { thinking, metadata } => {
-                                    // Update UI with final thinking content
-                                    if thinking_created {
-                                        if let Some(id) = &thinking_stream_id {
-                                            let block = Block::Thinking(ThinkingBlock {
-                                                id: id.clone(),
-                                                content: thinking.clone(),
-                                                is_streaming: false,
-                                                metadata: metadata.clone(),
-                                            });
-                                            if let Err(err) =
-                                                context.assistant_update_block(id, block).await
-                                            {
-                                                warn!(
-                                                    "Failed to finalize thinking block '{}' in UI: {}",
-                                                    id, err
-                                                );
+                            }
+                        }
+                        Ok(StreamEvent::ContentBlockStop { index }) => {
+                            if let Some(block) = current_blocks.remove(&index) {
+                                match block {
+                                    BlockAccumulator::Text(text) => {
+                                        if text_created {
+                                            if let Some(id) = &text_stream_id {
+                                                let block = Block::Text(TextBlock {
+                                                    id: id.clone(),
+                                                    content: text.clone(),
+                                                    is_streaming: false,
+                                                });
+                                                if let Err(err) =
+                                                    context.assistant_update_block(id, block).await
+                                                {
+                                                    warn!(
+                                                        "Failed to finalize text block '{}' in UI: {}",
+                                                        id, err
+                                                    );
+                                                }
                                             }
                                         }
+                                        if !text.is_empty() {
+                                            text_content.push(text);
+                                        }
                                     }
+                                    BlockAccumulator::ToolUse {
+                                        id,
+                                        name,
+                                        input_json,
+                                        ..
+                                    } => {
+                                        // Some OpenAI-compatible models (e.g., GLM) may send
+                                        // duplicate arguments via both `tool_calls` and
+                                        // `function_call`, resulting in concatenated JSON like
+                                        // `{...}{...}`. We defensively parse only the first
+                                        // valid JSON object using a streaming deserializer.
+                                        let input: Value = {
+                                            let mut de =
+                                                serde_json::Deserializer::from_str(&input_json)
+                                                    .into_iter::<Value>();
+                                            de.next()
+                                                .ok_or_else(|| {
+                                                    TaskExecutorError::InternalError(
+                                                        "Empty tool input JSON from stream".to_string(),
+                                                    )
+                                                })?
+                                                .map_err(|err| {
+                                                    TaskExecutorError::InternalError(format!(
+                                                        "Invalid tool input JSON from stream: {err}"
+                                                    ))
+                                                })?
+                                        };
+                                        tool_use_blocks.push(ContentBlock::ToolUse {
+                                            id: id.clone(),
+                                            name: name.clone(),
+                                            input: input.clone(),
+                                        });
 
-                                    let signature =
-                                        metadata.as_ref().and_then(|m| m.signature.clone());
+                                        if tool_block_visibility.get(&id).copied().unwrap_or(false) {
+                                            let now = Utc::now();
+                                            let started_at =
+                                                tool_block_started_at.get(&id).cloned().unwrap_or(now);
+                                            context
+                                                .assistant_upsert_block(Block::Tool(ToolBlock {
+                                                    id: id.clone(),
+                                                    call_id: id.clone(),
+                                                    name: name.clone(),
+                                                    status: ToolStatus::Pending,
+                                                    input: input.clone(),
+                                                    output: None,
+                                                    compacted_at: None,
+                                                    started_at,
+                                                    finished_at: None,
+                                                    duration_ms: None,
+                                                }))
+                                                .await?;
+                                        }
 
-                                    if let Some(ref meta) = metadata {
-                                        tracing::debug!(
-                                            "Reasoning metadata: item_id={:?}, provider={:?}",
-                                            meta.item_id,
-                                            meta.provider
+                                        context.states.react_runtime.write().await.record_action(
+                                            react_iteration_index,
+                                            name.clone(),
+                                            input.clone(),
                                         );
+                                        iter_ctx.add_tool_call(id.clone(), name.clone(), input.clone());
+                                        pending_tool_calls.push((id, name, input));
                                     }
+                                    BlockAccumulator::Thinking { thinking, metadata } => {
+                                        // Update UI with final thinking content
+                                        if thinking_created {
+                                            if let Some(id) = &thinking_stream_id {
+                                                let block = Block::Thinking(ThinkingBlock {
+                                                    id: id.clone(),
+                                                    content: thinking.clone(),
+                                                    is_streaming: false,
+                                                    metadata: metadata.clone(),
+                                                });
+                                                if let Err(err) =
+                                                    context.assistant_update_block(id, block).await
+                                                {
+                                                    warn!(
+                                                        "Failed to finalize thinking block '{}' in UI: {}",
+                                                        id, err
+                                                    );
+                                                }
+                                            }
+                                        }
+
+                                        let signature =
+                                            metadata.as_ref().and_then(|m| m.signature.clone());
 
-                                    tool_use_blocks.push(ContentBlock::Thinking {
-                                        thinking,
-                                        signature,
-                                        reasoning_metadata: metadata,
-                                    });
+                                        if let Some(ref meta) = metadata {
+                                            tracing::debug!(
+                                                "Reasoning metadata: item_id={:?}, provider={:?}",
+                                                meta.item_id,
+                                                meta.provider
+                                            );
+                                        }
+
+                                        tool_use_blocks.push(ContentBlock::Thinking {
+                                            thinking,
+                                            signature,
+                                            reasoning_metadata: metadata,
+                                        });
+                                    }
                                 }
                             }
                         }
-                    }
-                    Ok(StreamEvent::MessageDelta {
-                        delta,
-                        usage: _usage,
-                    }) => {
-                        if let Some(reason) = delta.stop_reason {
-                            stop_reason = Some(reason);
+                        Ok(StreamEvent::MessageDelta {
+                            delta,
+                            usage: delta_usage,
+                        }) => {
+                            usage = Some(merge_stream_usage(usage, delta_usage));
+                            if let Some(reason) = delta.stop_reason {
+                                stop_reason = Some(reason);
+                            }
+                        }
+                        Ok(StreamEvent::MessageStop) => {
+                            break;
+                        }
+                        Ok(StreamEvent::Ping) => {}
+                        Ok(StreamEvent::Error { error }) => {
+                            return Err(TaskExecutorError::InternalError(error.message));
+                        }
+                        Ok(StreamEvent::Unknown) => {}
+                        Err(e) => {
+                            return Err(TaskExecutorError::InternalError(e.to_string()));
                         }
-                    }
-                    Ok(StreamEvent::MessageStop) => {
-                        break;
-                    }
-                    Ok(StreamEvent::Ping) => {}
-                    Ok(StreamEvent::Error { error }) => {
-                        return Err(TaskExecutorError::InternalError(error.message));
-                    }
-                    Ok(StreamEvent::Unknown) => {}
-                    Err(e) => {
-                        return Err(TaskExecutorError::InternalError(e.to_string()));
                     }
                 }
+                Ok(())
             }
+            .await;
+
+            if let Some(usage) = usage {
+                let message_id = context.assistant_message_id().await;
+                context
+                    .record_llm_usage(model_id, UsagePurpose::Agent, message_id, &usage)
+                    .await;
+            }
+            streamed?;
 
             if context.is_aborted() {
                 return Err(TaskExecutorError::TaskInterrupted);
@@ -962,6 +998,14 @@ impl ReactOrchestrator {
             .complete_summary_job(job, model_id)
             .await
             .map_err(|e| TaskExecutorError::InternalError(e.to_string()))?;
+        context
+            .record_llm_usage(
+                model_id,
+                UsagePurpose::Compaction,
+                Some(completed.message_id),
+                &completed.usage,
+            )
+            .await;
 
         let context_usage = context.calculate_context_usage(model_id).await;
         context
@@ -971,7 +1015,7 @@ impl ReactOrchestrator {
                 status: completed.status,
                 finished_at: completed.finished_at,
                 duration_ms: completed.duration_ms,
-                token_usage: None,
+                token_usage: Some(completed.usage.into()),
                 context_usage,
             })
             .await?;
@@ -1001,6 +1045,24 @@ async fn load_allowed_task_profiles(cwd: &str, agent_type: &str) -> Vec<String>
     visible_task_profiles(caller, &configs)
 }
 
+/// Providers report usage on `message_start` and/or cumulatively on `message_delta`, so
+/// keep the largest value seen for each counter.
+fn merge_stream_usage(current: Option<Usage>, update: Usage) -> Usage {
+    let Some(current) = current else {
+        return update;
+    };
+    Usage {
+        input_tokens: current.input_tokens.max(update.input_tokens),
+        output_tokens: current.output_tokens.max(update.output_tokens),
+        cache_creation_input_tokens: current
+            .cache_creation_input_tokens
+            .max(update.cache_creation_input_tokens),
+        cache_read_input_tokens: current
+            .cache_read_input_tokens
+            .max(update.cache_read_input_tokens),
+    }
+}
+
 fn contains_fabricated_tool_output(text: &str, tool_names: &HashSet<String>) -> bool {
     if tool_names.is_empty() {
         return false;
@@ -2,6 +2,8 @@
 
 use std::time::Duration;
 
+use super::sandbox::SandboxPolicy;
+
 /// Shell executor configuration
 #[derive(Debug, Clone)]
 pub struct ShellExecutorConfig {
@@ -17,6 +19,8 @@ pub struct ShellExecutorConfig {
     pub max_command_length: usize,
     /// Maximum timeout duration
     pub max_timeout: Duration,
+    /// Run commands under an OS-level sandbox
+    pub sandbox: Option<SandboxPolicy>,
 }
 
 impl Default for ShellExecutorConfig {
@@ -28,6 +32,7 @@ impl Default for ShellExecutorConfig {
             completed_retention: Duration::from_secs(300), // 5 minutes
             max_command_length: 10 * 1024,                 // 10KB
             max_timeout: Duration::from_secs(600),         // 10 minutes
+            sandbox: None,
         }
     }
 }
@@ -20,6 +20,9 @@ pub enum ShellError {
     #[error("Too many background commands (max: {0})")]
     TooManyBackgroundCommands(usize),
 
+    #[error("Sandbox unavailable: {0}")]
+    SandboxUnavailable(String),
+
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
 
@@ -10,6 +10,7 @@ use tokio::time::timeout;
 
 use super::config::ShellExecutorConfig;
 use super::error::ShellError;
+use super::sandbox::sandboxed_command;
 use super::types::*;
 
 /// Agent Shell executor
@@ -106,15 +107,21 @@ impl AgentShellExecutor {
         running_cmd.status = CommandStatus::Running { pid: None };
 
         // Build command
-        let mut cmd = if cfg!(target_os = "windows") {
-            let mut c = Command::new("cmd");
-            c.arg("/C").arg(command);
-            c
+        let (program, flag) = if cfg!(target_os = "windows") {
+            ("cmd".to_string(), "/C")
         } else {
-            let shell = self.resolve_unix_shell()?;
-            let mut c = Command::new(shell);
-            c.arg("-lc").arg(command);
-            c
+            (self.resolve_unix_shell()?, "-lc")
+        };
+        let (mut cmd, sandbox) = match self.config.sandbox.as_ref() {
+            Some(policy) => {
+                let (cmd, backend) = sandboxed_command(&program, &[flag, command], policy)?;
+                (cmd, Some(backend))
+            }
+            None => {
+                let mut c = Command::new(program);
+                c.arg(flag).arg(command);
+                (c, None)
+            }
         };
 
         if !cwd.trim().is_empty() {
@@ -182,6 +189,7 @@ impl AgentShellExecutor {
                     duration_ms,
                     cwd: cwd.to_string(),
                     output_truncated: running_cmd.output_buffer.is_overflowed(),
+                    sandbox,
                 })
             }
             Ok(Err(e)) => {
@@ -191,6 +199,14 @@ impl AgentShellExecutor {
                 Err(ShellError::IoError(e))
             }
             Err(_) => {
+                // Sandboxed commands lead their own process group; take down the whole tree.
+                #[cfg(unix)]
+                if let (Some(_), Some(pid)) = (sandbox, running_cmd.pid) {
+                    // SAFETY: signalling a process group we created; failure is harmless.
+                    unsafe {
+                        libc::kill(-(pid as i32), libc::SIGKILL);
+                    }
+                }
                 running_cmd.status = CommandStatus::TimedOut { duration_ms };
                 Err(ShellError::Timeout(duration_ms))
             }
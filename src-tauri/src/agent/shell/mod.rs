@@ -5,15 +5,18 @@
 //! - Background execution
 //! - Timeout control
 //! - Process management
+//! - Optional OS-level sandboxing (Linux)
 
 mod buffer;
 mod config;
 mod error;
 mod executor;
+mod sandbox;
 mod types;
 
 pub use buffer::OutputRingBuffer;
 pub use config::ShellExecutorConfig;
 pub use error::ShellError;
 pub use executor::AgentShellExecutor;
+pub use sandbox::{looks_like_denial, SandboxBackend, SandboxPolicy};
 pub use types::*;
@@ -0,0 +1,487 @@
+//! OS-level sandbox for agent shell commands
+//!
+//! Linux only. Writes are confined to the workspace, temp dirs and configured paths, network
+//! is cut off unless allowed, and every command gets its own process group. Cutting the network
+//! takes bubblewrap (a network namespace); with network allowed, Landlock is used when the
+//! kernel supports it and bubblewrap otherwise.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tokio::process::Command;
+
+use super::error::ShellError;
+use crate::settings::types::SandboxConfig;
+
+/// Mechanism used to enforce a [`SandboxPolicy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SandboxBackend {
+    Landlock,
+    Bubblewrap,
+}
+
+impl SandboxBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SandboxBackend::Landlock => "landlock",
+            SandboxBackend::Bubblewrap => "bubblewrap",
+        }
+    }
+}
+
+/// What a sandboxed command may do
+#[derive(Debug, Clone)]
+pub struct SandboxPolicy {
+    /// Directories (or files) the command may write beneath
+    pub writable_paths: Vec<PathBuf>,
+    pub allow_network: bool,
+}
+
+impl SandboxPolicy {
+    pub fn new(workspace: &Path, config: &SandboxConfig) -> Self {
+        let mut writable_paths = vec![
+            workspace.to_path_buf(),
+            std::env::temp_dir(),
+            PathBuf::from("/tmp"),
+            PathBuf::from("/var/tmp"),
+            // /dev/null, /dev/tty and friends
+            PathBuf::from("/dev"),
+        ];
+        writable_paths.extend(
+            config
+                .writable_paths
+                .iter()
+                .filter(|p| !p.trim().is_empty())
+                .map(|p| resolve_path(p.trim(), workspace)),
+        );
+
+        let mut seen = std::collections::HashSet::new();
+        writable_paths.retain(|p| seen.insert(p.clone()));
+
+        Self {
+            writable_paths,
+            allow_network: config.allow_network,
+        }
+    }
+
+    /// One-line summary shown to the model when a command fails inside the sandbox
+    pub fn describe(&self) -> String {
+        let paths = self
+            .writable_paths
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let network = if self.allow_network {
+            "allowed"
+        } else {
+            "disabled"
+        };
+        format!("writable paths: {paths}; network: {network}")
+    }
+}
+
+fn resolve_path(raw: &str, workspace: &Path) -> PathBuf {
+    if raw == "~" {
+        if let Some(home) = dirs::home_dir() {
+            return home;
+        }
+    }
+    if let Some(rest) = raw.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    let path = PathBuf::from(raw);
+    if path.is_absolute() {
+        path
+    } else {
+        workspace.join(path)
+    }
+}
+
+/// Build `program args...` so it runs under `policy`. The returned command already has its
+/// own process group; callers only add cwd and stdio.
+pub fn sandboxed_command(
+    program: &str,
+    args: &[&str],
+    policy: &SandboxPolicy,
+) -> Result<(Command, SandboxBackend), ShellError> {
+    #[cfg(target_os = "linux")]
+    {
+        let backend = select_backend(policy)?;
+        let mut cmd = match backend {
+            SandboxBackend::Landlock => {
+                let ruleset = linux::LandlockRuleset::new(policy).map_err(|e| {
+                    ShellError::SandboxUnavailable(format!("failed to build Landlock ruleset: {e}"))
+                })?;
+                let mut cmd = Command::new(program);
+                cmd.args(args);
+                // SAFETY: restrict_self only issues prctl/landlock syscalls on an fd created
+                // above; it neither allocates nor takes locks.
+                unsafe {
+                    cmd.pre_exec(move || ruleset.restrict_self());
+                }
+                cmd
+            }
+            SandboxBackend::Bubblewrap => {
+                let mut cmd = Command::new("bwrap");
+                cmd.args(bubblewrap_args(policy))
+                    .arg("--")
+                    .arg(program)
+                    .args(args);
+                cmd
+            }
+        };
+        cmd.process_group(0).kill_on_drop(true);
+        Ok((cmd, backend))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (program, args, policy);
+        Err(ShellError::SandboxUnavailable(
+            "OS-level sandboxing is only supported on Linux".into(),
+        ))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn select_backend(policy: &SandboxPolicy) -> Result<SandboxBackend, ShellError> {
+    let has_bwrap = find_in_path("bwrap").is_some();
+    // Landlock's network rules only cover TCP bind/connect; UDP (and with it DNS) would stay
+    // open, so only a network namespace really cuts the network.
+    if !policy.allow_network {
+        return if has_bwrap {
+            Ok(SandboxBackend::Bubblewrap)
+        } else {
+            Err(ShellError::SandboxUnavailable(
+                "network isolation needs bubblewrap (bwrap); install it or allow network access"
+                    .into(),
+            ))
+        };
+    }
+    if linux::landlock_abi_version() >= 1 {
+        return Ok(SandboxBackend::Landlock);
+    }
+    if has_bwrap {
+        return Ok(SandboxBackend::Bubblewrap);
+    }
+    Err(ShellError::SandboxUnavailable(
+        "the kernel does not support Landlock and bubblewrap (bwrap) is not installed".into(),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn bubblewrap_args(policy: &SandboxPolicy) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "--die-with-parent",
+        "--ro-bind",
+        "/",
+        "/",
+        "--dev-bind",
+        "/dev",
+        "/dev",
+        "--proc",
+        "/proc",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+
+    for path in &policy.writable_paths {
+        if path == Path::new("/dev") || !path.exists() {
+            continue;
+        }
+        let path = path.display().to_string();
+        args.extend(["--bind".to_string(), path.clone(), path]);
+    }
+    if !policy.allow_network {
+        args.push("--unshare-net".to_string());
+    }
+    args
+}
+
+#[cfg(target_os = "linux")]
+fn find_in_path(command: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(command))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Heuristic: does this failed command's output look like the sandbox stopped it?
+pub fn looks_like_denial(output: &str, policy: &SandboxPolicy) -> bool {
+    const FS_MARKERS: &[&str] = &["Permission denied", "Read-only file system"];
+    const NET_MARKERS: &[&str] = &[
+        "Network is unreachable",
+        "Temporary failure in name resolution",
+        "Could not resolve host",
+        "Name or service not known",
+        "getaddrinfo",
+        "Connection refused",
+    ];
+
+    FS_MARKERS.iter().any(|m| output.contains(m))
+        || (!policy.allow_network && NET_MARKERS.iter().any(|m| output.contains(m)))
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+
+    use super::SandboxPolicy;
+
+    const CREATE_RULESET_VERSION: libc::c_uint = 1 << 0;
+    const RULE_PATH_BENEATH: libc::c_int = 1;
+
+    const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+    const ACCESS_FS_REMOVE_DIR: u64 = 1 << 4;
+    const ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+    const ACCESS_FS_MAKE_CHAR: u64 = 1 << 6;
+    const ACCESS_FS_MAKE_DIR: u64 = 1 << 7;
+    const ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+    const ACCESS_FS_MAKE_SOCK: u64 = 1 << 9;
+    const ACCESS_FS_MAKE_FIFO: u64 = 1 << 10;
+    const ACCESS_FS_MAKE_BLOCK: u64 = 1 << 11;
+    const ACCESS_FS_MAKE_SYM: u64 = 1 << 12;
+    const ACCESS_FS_REFER: u64 = 1 << 13; // ABI v2
+    const ACCESS_FS_TRUNCATE: u64 = 1 << 14; // ABI v3
+
+    /// Rights that make sense on a regular file (the rest only apply to directories)
+    const FILE_ACCESS: u64 = ACCESS_FS_WRITE_FILE | ACCESS_FS_TRUNCATE;
+
+    /// The ABI v1 layout; later fields (network) are left out, which every kernel accepts
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    pub fn landlock_abi_version() -> i32 {
+        // SAFETY: querying the ABI version takes no attribute pointer.
+        let version = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<RulesetAttr>(),
+                0usize,
+                CREATE_RULESET_VERSION,
+            )
+        };
+        version.max(0) as i32
+    }
+
+    fn write_access(abi: i32) -> u64 {
+        let mut access = ACCESS_FS_WRITE_FILE
+            | ACCESS_FS_REMOVE_DIR
+            | ACCESS_FS_REMOVE_FILE
+            | ACCESS_FS_MAKE_CHAR
+            | ACCESS_FS_MAKE_DIR
+            | ACCESS_FS_MAKE_REG
+            | ACCESS_FS_MAKE_SOCK
+            | ACCESS_FS_MAKE_FIFO
+            | ACCESS_FS_MAKE_BLOCK
+            | ACCESS_FS_MAKE_SYM;
+        if abi >= 2 {
+            access |= ACCESS_FS_REFER;
+        }
+        if abi >= 3 {
+            access |= ACCESS_FS_TRUNCATE;
+        }
+        access
+    }
+
+    /// A Landlock ruleset built in the parent and enforced in the child right before exec.
+    /// Reads, execution and network stay unrestricted; only writes are handled, so any write
+    /// not explicitly allowed below is denied.
+    pub struct LandlockRuleset {
+        fd: OwnedFd,
+    }
+
+    impl LandlockRuleset {
+        pub fn new(policy: &SandboxPolicy) -> io::Result<Self> {
+            let abi = landlock_abi_version();
+            if abi < 1 {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Landlock is not available",
+                ));
+            }
+
+            let fs_access = write_access(abi);
+            let attr = RulesetAttr {
+                handled_access_fs: fs_access,
+            };
+
+            // SAFETY: attr outlives the call and the size passed is its own.
+            let fd = unsafe {
+                libc::syscall(
+                    libc::SYS_landlock_create_ruleset,
+                    &attr as *const RulesetAttr,
+                    std::mem::size_of::<RulesetAttr>(),
+                    0u32,
+                )
+            };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: the syscall returned a fresh (O_CLOEXEC) file descriptor that we own.
+            let fd = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+
+            for path in &policy.writable_paths {
+                let Ok(metadata) = std::fs::metadata(path) else {
+                    continue;
+                };
+                let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+                    continue;
+                };
+                // SAFETY: c_path is a valid NUL-terminated string.
+                let parent = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+                if parent < 0 {
+                    continue;
+                }
+                // SAFETY: open returned a descriptor that nothing else owns.
+                let parent = unsafe { OwnedFd::from_raw_fd(parent) };
+
+                let allowed_access = if metadata.is_dir() {
+                    fs_access
+                } else {
+                    fs_access & FILE_ACCESS
+                };
+                let rule = PathBeneathAttr {
+                    allowed_access,
+                    parent_fd: parent.as_raw_fd(),
+                };
+                // SAFETY: both fds are open and rule outlives the call.
+                let rc = unsafe {
+                    libc::syscall(
+                        libc::SYS_landlock_add_rule,
+                        fd.as_raw_fd(),
+                        RULE_PATH_BENEATH,
+                        &rule as *const PathBeneathAttr,
+                        0u32,
+                    )
+                };
+                if rc < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+
+            Ok(Self { fd })
+        }
+
+        /// Runs in the forked child: only async-signal-safe syscalls allowed here.
+        pub fn restrict_self(&self) -> io::Result<()> {
+            // SAFETY: plain syscalls with no pointers beyond the ruleset fd.
+            unsafe {
+                if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                if libc::syscall(libc::SYS_landlock_restrict_self, self.fd.as_raw_fd(), 0u32) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(workspace: &Path, allow_network: bool) -> SandboxPolicy {
+        SandboxPolicy::new(
+            workspace,
+            &SandboxConfig {
+                enabled: true,
+                allow_network,
+                writable_paths: vec!["cache".into(), "/opt/shared".into()],
+            },
+        )
+    }
+
+    #[test]
+    fn test_policy_paths() {
+        let policy = policy(Path::new("/work/project"), false);
+        assert_eq!(policy.writable_paths[0], PathBuf::from("/work/project"));
+        assert!(policy
+            .writable_paths
+            .contains(&PathBuf::from("/work/project/cache")));
+        assert!(policy
+            .writable_paths
+            .contains(&PathBuf::from("/opt/shared")));
+        assert!(policy.describe().ends_with("network: disabled"));
+    }
+
+    #[test]
+    fn test_looks_like_denial() {
+        let offline = policy(Path::new("/work"), false);
+        let online = policy(Path::new("/work"), true);
+        assert!(looks_like_denial(
+            "touch: cannot touch '/etc/x': Permission denied",
+            &offline
+        ));
+        assert!(looks_like_denial(
+            "curl: (6) Could not resolve host: example.com",
+            &offline
+        ));
+        assert!(!looks_like_denial(
+            "curl: (6) Could not resolve host: example.com",
+            &online
+        ));
+        assert!(!looks_like_denial(
+            "error[E0308]: mismatched types",
+            &offline
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_network_isolation_uses_a_network_namespace() {
+        let offline = policy(Path::new("/work"), false);
+        match select_backend(&offline) {
+            Ok(backend) => assert_eq!(backend, SandboxBackend::Bubblewrap),
+            Err(ShellError::SandboxUnavailable(reason)) => assert!(reason.contains("bwrap")),
+            Err(other) => panic!("unexpected error: {other}"),
+        }
+        assert!(bubblewrap_args(&offline).contains(&"--unshare-net".to_string()));
+        assert!(!bubblewrap_args(&policy(Path::new("/work"), true))
+            .contains(&"--unshare-net".to_string()));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_writes_outside_workspace_are_denied() {
+        let workspace = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let mut policy = SandboxPolicy::new(workspace.path(), &SandboxConfig::default());
+        // The temp directories are writable by default; keep `outside` off limits
+        policy
+            .writable_paths
+            .retain(|p| !outside.path().starts_with(p));
+        if select_backend(&policy).is_err() {
+            // No Landlock and no bubblewrap on this machine
+            return;
+        }
+
+        let script = format!("touch ok && touch {}/blocked", outside.path().display());
+        let (mut cmd, _) = sandboxed_command("sh", &["-c", &script], &policy).unwrap();
+        let output = cmd.current_dir(workspace.path()).output().await.unwrap();
+
+        assert!(!output.status.success());
+        assert!(workspace.path().join("ok").exists());
+        assert!(!outside.path().join("blocked").exists());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(looks_like_denial(&stderr, &policy), "{stderr}");
+    }
+}
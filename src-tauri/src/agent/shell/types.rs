@@ -5,7 +5,7 @@ use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::time::Instant;
 
-use super::OutputRingBuffer;
+use super::{OutputRingBuffer, SandboxBackend};
 
 /// Command ID type
 pub type CommandId = u64;
@@ -137,4 +137,7 @@ pub struct ShellExecutionResult {
     pub cwd: String,
     /// Whether output was truncated
     pub output_truncated: bool,
+    /// Sandbox the command ran under, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<SandboxBackend>,
 }
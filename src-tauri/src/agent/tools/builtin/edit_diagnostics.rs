@@ -0,0 +1,226 @@
+// Diagnostics feedback for the edit tools.
+// Captures the errors of a file before an edit and reports only the ones the edit
+// introduced. Uses a running language server when there is one, tree-sitter otherwise.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use lsp_types::{Diagnostic, DiagnosticSeverity};
+
+use crate::code_intel::tree_sitter_diagnostics::diagnose_syntax;
+use crate::lsp::LspManager;
+use crate::vector_db::core::Language;
+
+use super::file_utils::is_probably_binary;
+
+/// New problems listed in a tool result; the rest are counted
+const MAX_REPORTED: usize = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Problem {
+    /// 1-based
+    line: u32,
+    /// 1-based
+    column: u32,
+    message: String,
+    source: Option<String>,
+}
+
+/// Errors of a file before an edit
+pub struct DiagnosticsBaseline(Baseline);
+
+enum Baseline {
+    Lsp(Vec<Problem>),
+    Syntax(Vec<Problem>),
+    Unavailable,
+}
+
+#[derive(Clone, Default)]
+pub struct EditDiagnostics {
+    lsp: Option<Arc<LspManager>>,
+}
+
+impl EditDiagnostics {
+    pub fn new(lsp: Option<Arc<LspManager>>) -> Self {
+        Self { lsp }
+    }
+
+    pub async fn baseline(&self, workspace: &str, path: &Path) -> DiagnosticsBaseline {
+        if let Some(lsp) = &self.lsp {
+            if let Some(diagnostics) = lsp.baseline_diagnostics(workspace, path).await {
+                return DiagnosticsBaseline(Baseline::Lsp(lsp_errors(&diagnostics)));
+            }
+        }
+        DiagnosticsBaseline(match syntax_errors(path).await {
+            Some(problems) => Baseline::Syntax(problems),
+            None => Baseline::Unavailable,
+        })
+    }
+
+    /// Report errors the edit introduced, or `None` when there are none or the file
+    /// can't be checked.
+    pub async fn regressions(
+        &self,
+        workspace: &str,
+        path: &Path,
+        baseline: DiagnosticsBaseline,
+    ) -> Option<String> {
+        let (source, before, after) = match baseline.0 {
+            Baseline::Lsp(before) => {
+                let lsp = self.lsp.as_ref()?;
+                let after = lsp.diagnostics_after_edit(workspace, path).await?;
+                ("language server", before, lsp_errors(&after))
+            }
+            Baseline::Syntax(before) => ("syntax check", before, syntax_errors(path).await?),
+            Baseline::Unavailable => return None,
+        };
+
+        let introduced = new_problems(&before, after);
+        if introduced.is_empty() {
+            return None;
+        }
+        Some(format_report(path, source, &introduced))
+    }
+}
+
+fn lsp_errors(diagnostics: &[Diagnostic]) -> Vec<Problem> {
+    diagnostics
+        .iter()
+        // Servers may omit the severity; treat that as an error like editors do
+        .filter(|diagnostic| {
+            diagnostic
+                .severity
+                .is_none_or(|severity| severity == DiagnosticSeverity::ERROR)
+        })
+        .map(|diagnostic| Problem {
+            line: diagnostic.range.start.line + 1,
+            column: diagnostic.range.start.character + 1,
+            message: diagnostic.message.clone(),
+            source: diagnostic.source.clone(),
+        })
+        .collect()
+}
+
+async fn syntax_errors(path: &Path) -> Option<Vec<Problem>> {
+    let language = Language::from_path(path)?;
+    if is_probably_binary(path) {
+        return None;
+    }
+    let content = match tokio::fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Some(Vec::new()),
+        Err(_) => return None,
+    };
+    let diagnostics = diagnose_syntax(path, &content, language).ok()?;
+    Some(
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| Problem {
+                line: diagnostic.range.start.line as u32,
+                column: diagnostic.range.start.column as u32,
+                message: diagnostic.message,
+                source: Some(diagnostic.source.to_string()),
+            })
+            .collect(),
+    )
+}
+
+/// Problems in `after` that were not already present. Edits shift positions, so
+/// problems are matched by message and source, counting duplicates.
+fn new_problems(before: &[Problem], after: Vec<Problem>) -> Vec<Problem> {
+    let mut existing: HashMap<(&str, Option<&str>), usize> = HashMap::new();
+    for problem in before {
+        *existing
+            .entry((problem.message.as_str(), problem.source.as_deref()))
+            .or_default() += 1;
+    }
+    after
+        .into_iter()
+        .filter(|problem| {
+            match existing.get_mut(&(problem.message.as_str(), problem.source.as_deref())) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    false
+                }
+                _ => true,
+            }
+        })
+        .collect()
+}
+
+fn format_report(path: &Path, source: &str, problems: &[Problem]) -> String {
+    let display_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| path.display().to_string());
+
+    let mut out = format!(
+        "\n\nThis edit introduced {} new error(s) ({source}). Fix them before moving on:\n<file_diagnostics>\n",
+        problems.len()
+    );
+    for problem in problems.iter().take(MAX_REPORTED) {
+        out.push_str(&format!(
+            "{}:{}:{} {}",
+            display_name, problem.line, problem.column, problem.message
+        ));
+        if let Some(source) = &problem.source {
+            out.push_str(&format!(" [{source}]"));
+        }
+        out.push('\n');
+    }
+    if problems.len() > MAX_REPORTED {
+        out.push_str(&format!("... and {} more\n", problems.len() - MAX_REPORTED));
+    }
+    out.push_str("</file_diagnostics>");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem(line: u32, message: &str) -> Problem {
+        Problem {
+            line,
+            column: 1,
+            message: message.to_string(),
+            source: Some("rustc".to_string()),
+        }
+    }
+
+    #[test]
+    fn reports_only_problems_the_edit_added() {
+        let before = vec![problem(3, "unused import"), problem(9, "mismatched types")];
+        let after = vec![
+            problem(4, "unused import"),
+            problem(10, "mismatched types"),
+            problem(12, "mismatched types"),
+            problem(20, "cannot find value `x`"),
+        ];
+
+        let introduced = new_problems(&before, after);
+        let lines: Vec<u32> = introduced.iter().map(|problem| problem.line).collect();
+        assert_eq!(lines, vec![12, 20]);
+    }
+
+    #[test]
+    fn lsp_errors_skip_warnings() {
+        let diagnostic = |severity, message: &str| Diagnostic {
+            severity,
+            message: message.to_string(),
+            ..Default::default()
+        };
+        let problems = lsp_errors(&[
+            diagnostic(Some(DiagnosticSeverity::WARNING), "unused"),
+            diagnostic(Some(DiagnosticSeverity::ERROR), "broken"),
+            diagnostic(None, "unknown severity"),
+        ]);
+        let messages: Vec<&str> = problems
+            .iter()
+            .map(|problem| problem.message.as_str())
+            .collect();
+        assert_eq!(messages, vec!["broken", "unknown severity"]);
+    }
+}
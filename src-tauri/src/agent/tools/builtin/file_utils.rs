@@ -60,6 +60,24 @@ pub(crate) mod lenient {
             Val::Null => Ok(None),
         }
     }
+
+    pub fn deserialize_opt_f32<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<f32>, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Val {
+            Num(f32),
+            Str(String),
+            Null,
+        }
+        match Val::deserialize(deserializer)? {
+            Val::Num(n) => Ok(Some(n)),
+            Val::Str(s) if s.is_empty() => Ok(None),
+            Val::Str(s) => s.parse().map(Some).map_err(serde::de::Error::custom),
+            Val::Null => Ok(None),
+        }
+    }
 }
 
 /// List of extensions treated as binary to mirror front-end safeguards.
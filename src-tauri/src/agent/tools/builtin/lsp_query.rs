@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -10,7 +11,10 @@ use crate::agent::tools::{
     RunnableTool, ToolCategory, ToolMetadata, ToolPriority, ToolResult, ToolResultContent,
     ToolResultStatus,
 };
-use crate::lsp::LspManager;
+use crate::lsp::edit::{apply_workspace_edit, LspEditError};
+use crate::lsp::{LspManager, LspPosition, LspRange, LspWorkspaceEdit};
+
+use super::unified_edit::{snapshot_before_edit, track_edit};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -24,15 +28,65 @@ struct LspQueryArgs {
     line: Option<u32>,
     #[serde(default)]
     character: Option<u32>,
+    #[serde(default, alias = "end_line")]
+    end_line: Option<u32>,
+    #[serde(default, alias = "end_character")]
+    end_character: Option<u32>,
+    #[serde(default, alias = "new_name")]
+    new_name: Option<String>,
+    /// Title of the code action to apply
+    #[serde(default)]
+    apply: Option<String>,
 }
 
 pub struct LspQueryTool {
     manager: Arc<LspManager>,
+    allow_edits: bool,
 }
 
 impl LspQueryTool {
-    pub fn new(manager: Arc<LspManager>) -> Self {
-        Self { manager }
+    /// `allow_edits` gates rename, format and applying code actions (off in chat mode).
+    pub fn new(manager: Arc<LspManager>, allow_edits: bool) -> Self {
+        Self {
+            manager,
+            allow_edits,
+        }
+    }
+
+    /// Write a workspace edit through the same checkpoint path as the edit tools so it
+    /// can be rolled back.
+    async fn apply_edit(
+        &self,
+        context: &TaskContext,
+        edit: &LspWorkspaceEdit,
+    ) -> ToolExecutorResult<serde_json::Value> {
+        if !self.allow_edits {
+            return Err(ToolExecutorError::InvalidArguments {
+                tool_name: "lsp_query".to_string(),
+                error: "editing actions are not available in this mode".to_string(),
+            });
+        }
+        edit.ensure_within(Path::new(context.cwd.as_ref()))
+            .await
+            .map_err(map_edit_error)?;
+
+        for path in edit.touched_paths() {
+            let path = Path::new(&path);
+            if path.is_dir() {
+                continue;
+            }
+            context.note_agent_write_intent(path).await;
+            snapshot_before_edit(context, self.name(), path).await?;
+        }
+        let changed = apply_workspace_edit(edit).await.map_err(map_edit_error)?;
+        for path in &changed {
+            let path = Path::new(path);
+            if path.is_file() {
+                track_edit(context, path).await?;
+            }
+        }
+
+        Ok(json!({ "changedFiles": changed, "edit": edit }))
     }
 }
 
@@ -43,7 +97,16 @@ impl RunnableTool for LspQueryTool {
     }
 
     fn description(&self) -> &str {
-        "Query language servers for document symbols, workspace symbols, hover, definitions, references, diagnostics, or status. Use this when tree-sitter/search is not enough and you need IDE-grade semantic results."
+        r#"Query language servers for IDE-grade semantic results and refactorings. Use this when tree-sitter/search is not enough.
+
+Read-only actions: status, document_symbols, workspace_symbols, hover, definition, type_definition, implementation, references, signature_help, diagnostics, code_actions (lists the available fixes and refactorings for a position or range).
+
+Editing actions write files directly and can be rolled back like other edits:
+- rename: rename the symbol at `line`/`character` to `new_name` across the workspace.
+- format: format the file, or only `line`/`character`..`end_line`/`end_character` when given.
+- code_actions with `apply`: apply the action whose title matches `apply` exactly. List the actions first to get the titles.
+
+Positions are 0-based; characters count UTF-16 code units."#
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -52,12 +115,16 @@ impl RunnableTool for LspQueryTool {
             "properties": {
                 "action": {
                     "type": "string",
-                    "enum": ["status", "document_symbols", "workspace_symbols", "hover", "definition", "references", "diagnostics"]
+                    "enum": ["status", "document_symbols", "workspace_symbols", "hover", "definition", "type_definition", "implementation", "references", "signature_help", "diagnostics", "code_actions", "rename", "format"]
                 },
                 "path": { "type": "string", "description": "Absolute or workspace-relative file path when the action targets a file." },
                 "query": { "type": "string", "description": "Required for workspace_symbols." },
-                "line": { "type": "integer", "description": "0-based line for hover/definition/references." },
-                "character": { "type": "integer", "description": "0-based UTF-16 character for hover/definition/references." }
+                "line": { "type": "integer", "description": "0-based line of the position, or of the range start for code_actions/format." },
+                "character": { "type": "integer", "description": "0-based UTF-16 character of the position or range start." },
+                "end_line": { "type": "integer", "description": "Range end line for code_actions/format. Defaults to the start." },
+                "end_character": { "type": "integer", "description": "Range end character for code_actions/format." },
+                "new_name": { "type": "string", "description": "Required for rename." },
+                "apply": { "type": "string", "description": "code_actions only: title of the action to apply." }
             },
             "required": ["action"]
         })
@@ -123,6 +190,112 @@ impl RunnableTool for LspQueryTool {
                         .map_err(map_lsp_error)?,
                 )?
             }
+            "type_definition" => {
+                let path = require_path(&args)?;
+                let (line, character) = require_position(&args)?;
+                serde_json::to_value(
+                    self.manager
+                        .type_definition(workspace, &path, line, character)
+                        .await
+                        .map_err(map_lsp_error)?,
+                )?
+            }
+            "implementation" => {
+                let path = require_path(&args)?;
+                let (line, character) = require_position(&args)?;
+                serde_json::to_value(
+                    self.manager
+                        .implementation(workspace, &path, line, character)
+                        .await
+                        .map_err(map_lsp_error)?,
+                )?
+            }
+            "signature_help" => {
+                let path = require_path(&args)?;
+                let (line, character) = require_position(&args)?;
+                serde_json::to_value(
+                    self.manager
+                        .signature_help(workspace, &path, line, character)
+                        .await
+                        .map_err(map_lsp_error)?,
+                )?
+            }
+            "rename" => {
+                let path = require_path(&args)?;
+                let (line, character) = require_position(&args)?;
+                let new_name = args
+                    .new_name
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .ok_or_else(|| ToolExecutorError::InvalidArguments {
+                        tool_name: "lsp_query".to_string(),
+                        error: "new_name is required for rename".to_string(),
+                    })?;
+                let edit = self
+                    .manager
+                    .rename(workspace, &path, line, character, new_name.to_string())
+                    .await
+                    .map_err(map_lsp_error)?;
+                if edit.is_empty() {
+                    return Err(ToolExecutorError::ExecutionFailed {
+                        tool_name: "lsp_query".to_string(),
+                        error: "the language server returned no edits for this rename".to_string(),
+                    });
+                }
+                self.apply_edit(context, &edit).await?
+            }
+            "format" => {
+                let path = require_path(&args)?;
+                let range = match args.line {
+                    Some(_) => Some(require_range(&args)?),
+                    None => None,
+                };
+                let edit = self
+                    .manager
+                    .formatting(workspace, &path, range)
+                    .await
+                    .map_err(map_lsp_error)?;
+                self.apply_edit(context, &edit).await?
+            }
+            "code_actions" => {
+                let path = require_path(&args)?;
+                let range = require_range(&args)?;
+                match args.apply.as_deref() {
+                    None => serde_json::to_value(
+                        self.manager
+                            .code_actions(workspace, &path, range)
+                            .await
+                            .map_err(map_lsp_error)?,
+                    )?,
+                    Some(title) => {
+                        let action = self
+                            .manager
+                            .code_action(workspace, &path, range, title)
+                            .await
+                            .map_err(map_lsp_error)?
+                            .ok_or_else(|| ToolExecutorError::InvalidArguments {
+                                tool_name: "lsp_query".to_string(),
+                                error: format!("no code action titled '{title}' at this range"),
+                            })?;
+                        if let Some(reason) = &action.disabled_reason {
+                            return Err(ToolExecutorError::ExecutionFailed {
+                                tool_name: "lsp_query".to_string(),
+                                error: format!("code action is disabled: {reason}"),
+                            });
+                        }
+                        let edit =
+                            action
+                                .edit
+                                .ok_or_else(|| ToolExecutorError::ExecutionFailed {
+                                    tool_name: "lsp_query".to_string(),
+                                    error: "code action has no edit; it only runs a server command"
+                                        .to_string(),
+                                })?;
+                        self.apply_edit(context, &edit).await?
+                    }
+                }
+            }
             "diagnostics" => serde_json::to_value(
                 self.manager
                     .diagnostics(workspace, args.path.as_deref())
@@ -195,6 +368,29 @@ fn require_position(args: &LspQueryArgs) -> ToolExecutorResult<(u32, u32)> {
     Ok((line, character))
 }
 
+fn require_range(args: &LspQueryArgs) -> ToolExecutorResult<LspRange> {
+    let (line, character) = require_position(args)?;
+    let end_line = args.end_line.unwrap_or(line);
+    let end_character = match args.end_line {
+        Some(_) => args.end_character.unwrap_or(0),
+        None => args.end_character.unwrap_or(character),
+    };
+    Ok(LspRange {
+        start: LspPosition { line, character },
+        end: LspPosition {
+            line: end_line,
+            character: end_character,
+        },
+    })
+}
+
+fn map_edit_error(err: LspEditError) -> ToolExecutorError {
+    ToolExecutorError::ExecutionFailed {
+        tool_name: "lsp_query".to_string(),
+        error: err.to_string(),
+    }
+}
+
 fn map_lsp_error(err: crate::lsp::manager::LspManagerError) -> ToolExecutorError {
     ToolExecutorError::ExecutionFailed {
         tool_name: "lsp_query".to_string(),
@@ -1,5 +1,6 @@
 pub(crate) mod file_utils;
 
+pub mod edit_diagnostics;
 pub mod glob;
 pub mod grep;
 pub mod list_files;
@@ -15,8 +16,10 @@ pub mod todo;
 pub mod unified_edit;
 pub mod web_fetch;
 pub mod web_search;
+pub mod worktree;
 pub mod write_file;
 
+pub use edit_diagnostics::EditDiagnostics;
 pub use glob::GlobTool;
 pub use grep::GrepTool;
 pub use list_files::ListFilesTool;
@@ -32,4 +35,5 @@ pub use todo::TodoWriteTool;
 pub use unified_edit::UnifiedEditTool;
 pub use web_fetch::WebFetchTool;
 pub use web_search::WebSearchTool;
+pub use worktree::WorktreeTool;
 pub use write_file::WriteFileTool;
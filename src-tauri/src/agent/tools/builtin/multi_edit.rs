@@ -12,6 +12,7 @@ use crate::agent::core::context::TaskContext;
 use crate::agent::error::ToolExecutorResult;
 use crate::agent::tools::{RunnableTool, ToolCategory, ToolMetadata, ToolPriority, ToolResult};
 
+use super::edit_diagnostics::EditDiagnostics;
 use super::file_utils::ensure_absolute;
 use super::unified_edit::{
     error_result, load_file_text, replace, snapshot_before_edit, success_result, track_edit,
@@ -36,7 +37,9 @@ struct EditOp {
     replace_all: bool,
 }
 
-pub struct MultiEditTool;
+pub struct MultiEditTool {
+    diagnostics: EditDiagnostics,
+}
 
 impl Default for MultiEditTool {
     fn default() -> Self {
@@ -46,7 +49,12 @@ impl Default for MultiEditTool {
 
 impl MultiEditTool {
     pub fn new() -> Self {
-        Self
+        Self::with_diagnostics(EditDiagnostics::default())
+    }
+
+    /// Report errors introduced by an edit in the tool result.
+    pub fn with_diagnostics(diagnostics: EditDiagnostics) -> Self {
+        Self { diagnostics }
     }
 }
 
@@ -180,6 +188,7 @@ IMPORTANT:
         };
 
         // All edits succeeded — now write to disk
+        let baseline = self.diagnostics.baseline(&context.cwd, &path).await;
         context.note_agent_write_intent(path.as_path()).await;
         snapshot_before_edit(context, self.name(), path.as_path()).await?;
 
@@ -192,6 +201,11 @@ IMPORTANT:
         }
 
         track_edit(context, &path).await?;
+        let new_errors = self
+            .diagnostics
+            .regressions(&context.cwd, &path, baseline)
+            .await
+            .unwrap_or_default();
 
         let edit_summaries: Vec<serde_json::Value> = args
             .edits
@@ -207,7 +221,7 @@ IMPORTANT:
 
         Ok(success_result(
             format!(
-                "multi_edit_file applied {} edits\nfile={}",
+                "multi_edit_file applied {} edits\nfile={}{new_errors}",
                 args.edits.len(),
                 path.display()
             ),
@@ -16,7 +16,7 @@ use crate::agent::tools::{
     RunnableTool, ToolAvailabilityContext, ToolCategory, ToolMetadata, ToolPriority, ToolResult,
     ToolResultContent, ToolResultStatus,
 };
-use crate::vector_db::search::SemanticSearchEngine;
+use crate::vector_db::search::{HybridSearchEngine, SearchOptions, SemanticSearchEngine};
 use std::sync::Arc;
 
 const DEFAULT_MAX_RESULTS: usize = 10;
@@ -30,6 +30,9 @@ struct SemanticSearchArgs {
     #[serde(default, deserialize_with = "lenient::deserialize_opt_usize")]
     max_results: Option<usize>,
     path: Option<String>,
+    #[serde(default, deserialize_with = "lenient::deserialize_opt_f32")]
+    diversity: Option<f32>,
+    rerank: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -43,33 +46,35 @@ pub struct SemanticResultEntry {
     pub language: String,
 }
 
+/// Searches the workspace index. Without an embedding model only the keyword index is
+/// used.
 pub struct SemanticSearchTool {
-    search_engine: Arc<SemanticSearchEngine>,
+    search_engine: Option<Arc<SemanticSearchEngine>>,
 }
 
 impl SemanticSearchTool {
-    pub fn new(search_engine: Arc<SemanticSearchEngine>) -> Self {
+    pub fn new(search_engine: Option<Arc<SemanticSearchEngine>>) -> Self {
         Self { search_engine }
     }
 
-    async fn vector_search(
+    async fn index_search(
         &self,
         path: &Path,
         query: &str,
-        max_results: usize,
+        search_options: SearchOptions,
     ) -> Result<Vec<SemanticResultEntry>, String> {
-        let search_options = crate::vector_db::search::SearchOptions {
-            top_k: max_results,
-            threshold: 0.3,
-            include_snippet: true,
-            filter_languages: vec![],
-        };
-
-        let results = self
-            .search_engine
-            .search_in_workspace(path, query, search_options)
-            .await
-            .map_err(|e| format!("Vector search failed: {e}"))?;
+        let max_results = search_options.top_k;
+        let results = match &self.search_engine {
+            Some(engine) => {
+                engine
+                    .search_in_workspace(path, query, search_options)
+                    .await
+            }
+            None => {
+                HybridSearchEngine::search_without_embeddings(path, query, &search_options).await
+            }
+        }
+        .map_err(|e| format!("Index search failed: {e}"))?;
 
         let mut entries: Vec<SemanticResultEntry> = Vec::new();
         for r in results.into_iter().take(max_results) {
@@ -100,7 +105,7 @@ impl RunnableTool for SemanticSearchTool {
     }
 
     fn is_available(&self, ctx: &ToolAvailabilityContext) -> bool {
-        ctx.has_vector_index
+        ctx.has_vector_index || ctx.has_keyword_index
     }
 
     fn description(&self) -> &str {
@@ -108,7 +113,9 @@ impl RunnableTool for SemanticSearchTool {
 
 Usage:
 - Describe the functionality you're looking for in natural language
-- Returns relevant code snippets ranked by semantic similarity
+- Returns relevant code snippets ranked by semantic similarity combined with keyword relevance
+- Adjacent matches in a file come back as one snippet; results are spread across files, and files you recently read or edited rank higher
+- Identifiers in the query also match their parts: "getUserById" finds code mentioning user ids
 - Best for conceptual questions: "how does X work", "where is Y handled"
 - Use grep instead for exact pattern matching (symbol names, strings, error messages)
 
@@ -146,6 +153,16 @@ Examples:
                 "path": {
                     "type": "string",
                     "description": "Directory to search (default: workspace root)"
+                },
+                "diversity": {
+                    "type": "number",
+                    "minimum": 0,
+                    "maximum": 1,
+                    "description": "How strongly to spread results across files: 0 ranks by relevance only (default: 0.3)"
+                },
+                "rerank": {
+                    "type": "boolean",
+                    "description": "Rescore the best matches with the configured reranker, if any (default: true)"
                 }
             },
             "required": ["query"]
@@ -185,6 +202,19 @@ Examples:
             Err(result) => return Ok(result),
         };
 
+        let mut search_options = SearchOptions {
+            top_k: max_results,
+            rerank: args.rerank.unwrap_or(true),
+            boost_files: context.file_tracker().read_or_edited_files().await,
+            ..SearchOptions::default()
+        };
+        if let Some(diversity) = args.diversity {
+            if !(0.0..=1.0).contains(&diversity) {
+                return Ok(validation_error("Diversity must be between 0 and 1"));
+            }
+            search_options.diversity = diversity;
+        }
+
         if !search_path.exists() {
             return Ok(tool_error(format!(
                 "Path does not exist: {}",
@@ -193,7 +223,7 @@ Examples:
         }
 
         let started = Instant::now();
-        let result = self.vector_search(&search_path, query, max_results).await;
+        let result = self.index_search(&search_path, query, search_options).await;
         let elapsed_ms = started.elapsed().as_millis() as u64;
 
         match result {
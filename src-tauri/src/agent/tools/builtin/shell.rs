@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
@@ -8,11 +9,15 @@ use super::file_utils::lenient;
 use crate::agent::common::TruncationPolicy;
 use crate::agent::core::context::TaskContext;
 use crate::agent::error::ToolExecutorResult;
+use crate::agent::shell::{
+    looks_like_denial, AgentShellExecutor, SandboxPolicy, ShellError, ShellExecutorConfig,
+};
 use crate::agent::terminal::{AgentTerminalManager, TerminalExecutionMode, TerminalStatus};
 use crate::agent::tools::{
     RunnableTool, ToolCategory, ToolMetadata, ToolPriority, ToolResult, ToolResultContent,
     ToolResultStatus,
 };
+use crate::settings::types::SandboxConfig;
 
 /// Default timeout (milliseconds)
 const DEFAULT_TIMEOUT_MS: u64 = 120_000;
@@ -77,7 +82,11 @@ struct ShellArgs {
     timeout_ms: Option<u64>,
 }
 
-pub struct ShellTool;
+pub struct ShellTool {
+    /// When set, blocking commands run through `AgentShellExecutor` under an OS sandbox
+    /// instead of a visible agent terminal.
+    sandbox: Option<SandboxConfig>,
+}
 
 impl Default for ShellTool {
     fn default() -> Self {
@@ -87,7 +96,90 @@ impl Default for ShellTool {
 
 impl ShellTool {
     pub fn new() -> Self {
-        Self
+        Self { sandbox: None }
+    }
+
+    pub fn with_sandbox(config: SandboxConfig) -> Self {
+        Self {
+            sandbox: config.enabled.then_some(config),
+        }
+    }
+
+    async fn run_sandboxed(
+        &self,
+        config: &SandboxConfig,
+        context: &TaskContext,
+        args: &ShellArgs,
+        cwd: &str,
+        timeout: Duration,
+    ) -> ToolResult {
+        if args.background.unwrap_or(false) {
+            return tool_error(
+                "Background commands are unavailable while the shell sandbox is enabled. Run the command in blocking mode with a suitable timeoutMs.",
+                &args.command,
+                cwd,
+            );
+        }
+
+        let policy = SandboxPolicy::new(Path::new(context.cwd.as_ref()), config);
+        let executor = AgentShellExecutor::with_config(ShellExecutorConfig {
+            sandbox: Some(policy.clone()),
+            ..Default::default()
+        });
+
+        let result = match executor.execute(&args.command, cwd, Some(timeout)).await {
+            Ok(result) => result,
+            Err(ShellError::SandboxUnavailable(reason)) => {
+                // Fail closed: the user asked for a sandbox, so never fall back to running bare.
+                return tool_error(
+                    format!("Shell sandbox is enabled but unavailable: {reason}. Ask the user to fix the sandbox or disable agent.sandbox in settings."),
+                    &args.command,
+                    cwd,
+                );
+            }
+            Err(err) => return tool_error(err.to_string(), &args.command, cwd),
+        };
+
+        let is_success = result.exit_code == Some(0);
+        let denied = !is_success && looks_like_denial(&result.output, &policy);
+        let truncated =
+            crate::agent::common::truncate_middle(&result.output, TruncationPolicy::shell_output());
+        let mut output = truncated.text;
+        if denied {
+            let backend = result.sandbox.map(|b| b.as_str()).unwrap_or("sandbox");
+            output.push_str(&format!(
+                "\n\n[sandbox] This command ran in an OS sandbox ({backend}) and appears to have been blocked by it ({}). Stay within these limits, or ask the user to adjust agent.sandbox in settings.",
+                policy.describe()
+            ));
+        }
+
+        ToolResult {
+            content: vec![if is_success {
+                ToolResultContent::Success(output)
+            } else {
+                ToolResultContent::Error(output)
+            }],
+            status: if is_success {
+                ToolResultStatus::Success
+            } else {
+                ToolResultStatus::Error
+            },
+            cancel_reason: None,
+            execution_time_ms: Some(result.duration_ms),
+            ext_info: Some(json!({
+                "command": args.command,
+                "cwd": cwd,
+                "exitCode": result.exit_code,
+                "isBackground": false,
+                "status": result.status,
+                "truncated": truncated.was_truncated,
+                "originalLines": truncated.info.as_ref().map(|i| i.lines),
+                "sandbox": {
+                    "backend": result.sandbox,
+                    "denied": denied,
+                },
+            })),
+        }
     }
 }
 
@@ -155,16 +247,6 @@ Git safety: Never commit/push/amend without explicit user request."#
         args: serde_json::Value,
     ) -> ToolExecutorResult<ToolResult> {
         let args: ShellArgs = serde_json::from_value(args)?;
-        let manager = match AgentTerminalManager::global() {
-            Some(manager) => manager,
-            None => {
-                return Ok(tool_error(
-                    "Agent terminal manager is not initialized.",
-                    &args.command,
-                    context.cwd.as_ref(),
-                ));
-            }
-        };
 
         // Git safety check
         if let Err(validation_error) = validate_git_command(&args.command) {
@@ -189,6 +271,23 @@ Git safety: Never commit/push/amend without explicit user request."#
             .map(Duration::from_millis)
             .unwrap_or(Duration::from_millis(DEFAULT_TIMEOUT_MS));
 
+        if let Some(config) = self.sandbox.as_ref() {
+            return Ok(self
+                .run_sandboxed(config, context, &args, cwd, timeout_duration)
+                .await);
+        }
+
+        let manager = match AgentTerminalManager::global() {
+            Some(manager) => manager,
+            None => {
+                return Ok(tool_error(
+                    "Agent terminal manager is not initialized.",
+                    &args.command,
+                    context.cwd.as_ref(),
+                ));
+            }
+        };
+
         let is_background = args.background.unwrap_or(false);
         let mode = if is_background {
             TerminalExecutionMode::Background
@@ -3,7 +3,7 @@ use std::time::Duration;
 use async_trait::async_trait;
 use serde_json::{json, Value};
 
-use crate::agent::core::context::{TaskContext, TaskExecutionRequest};
+use crate::agent::core::context::{TaskContext, TaskExecutionRequest, TaskExecutionResponse};
 use crate::agent::error::{ToolExecutorError, ToolExecutorResult};
 use crate::agent::tools::metadata::{ExecutionMode, ToolCategory, ToolMetadata, ToolPriority};
 use crate::agent::tools::{
@@ -85,123 +85,21 @@ impl RunnableTool for TaskTool {
         let call_id = args
             .get("call_id")
             .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+            .map(|s| s.to_string());
 
-        let persistence = context.agent_persistence();
-        let parent_session = persistence
-            .sessions()
-            .get(context.session_id)
-            .await
-            .map_err(|e| ToolExecutorError::ExecutionFailed {
-                tool_name: "task".to_string(),
-                error: e.to_string(),
-            })?
-            .ok_or_else(|| ToolExecutorError::ExecutionFailed {
-                tool_name: "task".to_string(),
-                error: format!("parent session {} not found", context.session_id),
-            })?;
-
-        let child_session_id = match session_id {
-            Some(id) => id,
-            None => {
-                let created = persistence
-                    .sessions()
-                    .create(
-                        &parent_session.workspace_path,
-                        Some(&description),
-                        &profile,
-                        Some(parent_session.id),
-                        Some(&call_id),
-                        parent_session.model_id.as_deref(),
-                        parent_session.provider_id.as_deref(),
-                    )
-                    .await
-                    .map_err(|e| ToolExecutorError::ExecutionFailed {
-                        tool_name: "task".to_string(),
-                        error: e.to_string(),
-                    })?;
-                created.id
-            }
-        };
-
-        context
-            .assistant_append_block(Block::Subtask(SubtaskBlock {
-                id: call_id.clone(),
-                child_session_id,
-                agent_type: profile.clone(),
-                description: description.clone(),
-                status: SubtaskStatus::Running,
-                summary: None,
-            }))
-            .await
-            .map_err(|e| ToolExecutorError::ExecutionFailed {
-                tool_name: "task".to_string(),
-                error: e.to_string(),
-            })?;
-
-        let request = TaskExecutionRequest {
-            description: description.clone(),
-            prompt: prompt.clone(),
-            profile: profile.clone(),
-            session_id: Some(child_session_id),
-            call_id: Some(call_id.clone()),
-            model_id: model_id.clone(),
-            use_worktree,
-        };
-
-        let response = match context
-            .task_execution_runner()
-            .run_task_execution(context, request)
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
-                if let Err(update_err) = context
-                    .assistant_update_block(
-                        &call_id,
-                        Block::Subtask(SubtaskBlock {
-                            id: call_id.clone(),
-                            child_session_id,
-                            agent_type: profile.clone(),
-                            description: description.clone(),
-                            status: SubtaskStatus::Error,
-                            summary: Some(e.to_string()),
-                        }),
-                    )
-                    .await
-                {
-                    tracing::warn!(
-                        "Failed to update task block '{}' after task execution error: {}",
-                        call_id,
-                        update_err
-                    );
-                }
-
-                return Err(ToolExecutorError::ExecutionFailed {
-                    tool_name: "task".to_string(),
-                    error: e.to_string(),
-                });
-            }
-        };
-
-        context
-            .assistant_update_block(
-                &call_id,
-                Block::Subtask(SubtaskBlock {
-                    id: call_id.clone(),
-                    child_session_id: response.session_id,
-                    agent_type: profile.clone(),
-                    description: description.clone(),
-                    status: response.status.clone(),
-                    summary: response.summary.clone(),
-                }),
-            )
-            .await
-            .map_err(|e| ToolExecutorError::ExecutionFailed {
-                tool_name: "task".to_string(),
-                error: e.to_string(),
-            })?;
+        let response = run_delegated_task(
+            context,
+            TaskExecutionRequest {
+                description,
+                prompt,
+                profile: profile.clone(),
+                session_id,
+                call_id,
+                model_id,
+                use_worktree,
+            },
+        )
+        .await?;
 
         let response_status = response.status.clone();
         let response_status_label = format!("{response_status:?}");
@@ -250,6 +148,18 @@ impl RunnableTool for TaskTool {
             ),
         };
 
+        let content = match worktree_note(context, use_worktree, response.session_id).await {
+            Some(note) => match content {
+                ToolResultContent::Success(text) => {
+                    ToolResultContent::Success(format!("{text}\n\n{note}"))
+                }
+                ToolResultContent::Error(text) => {
+                    ToolResultContent::Error(format!("{text}\n\n{note}"))
+                }
+            },
+            None => content,
+        };
+
         Ok(ToolResult {
             content: vec![content],
             status,
@@ -263,6 +173,143 @@ impl RunnableTool for TaskTool {
     }
 }
 
+/// Run `request` as a child of `context`, shown as a subtask block in the current assistant
+/// message. Creates the child session when the request names none.
+pub(crate) async fn run_delegated_task(
+    context: &TaskContext,
+    mut request: TaskExecutionRequest,
+) -> ToolExecutorResult<TaskExecutionResponse> {
+    let call_id = request
+        .call_id
+        .get_or_insert_with(|| uuid::Uuid::new_v4().to_string())
+        .clone();
+
+    let persistence = context.agent_persistence();
+    let parent_session = persistence
+        .sessions()
+        .get(context.session_id)
+        .await
+        .map_err(|e| ToolExecutorError::ExecutionFailed {
+            tool_name: "task".to_string(),
+            error: e.to_string(),
+        })?
+        .ok_or_else(|| ToolExecutorError::ExecutionFailed {
+            tool_name: "task".to_string(),
+            error: format!("parent session {} not found", context.session_id),
+        })?;
+
+    let child_session_id = match request.session_id {
+        Some(id) => id,
+        None => {
+            let created = persistence
+                .sessions()
+                .create(
+                    &parent_session.workspace_path,
+                    Some(&request.description),
+                    &request.profile,
+                    Some(parent_session.id),
+                    Some(&call_id),
+                    parent_session.model_id.as_deref(),
+                    parent_session.provider_id.as_deref(),
+                )
+                .await
+                .map_err(|e| ToolExecutorError::ExecutionFailed {
+                    tool_name: "task".to_string(),
+                    error: e.to_string(),
+                })?;
+            created.id
+        }
+    };
+    request.session_id = Some(child_session_id);
+
+    let block = |child_session_id, status, summary| {
+        Block::Subtask(SubtaskBlock {
+            id: call_id.clone(),
+            child_session_id,
+            agent_type: request.profile.clone(),
+            description: request.description.clone(),
+            status,
+            summary,
+        })
+    };
+
+    context
+        .assistant_append_block(block(child_session_id, SubtaskStatus::Running, None))
+        .await
+        .map_err(|e| ToolExecutorError::ExecutionFailed {
+            tool_name: "task".to_string(),
+            error: e.to_string(),
+        })?;
+
+    let response = match context
+        .task_execution_runner()
+        .run_task_execution(context, request.clone())
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            if let Err(update_err) = context
+                .assistant_update_block(
+                    &call_id,
+                    block(child_session_id, SubtaskStatus::Error, Some(e.to_string())),
+                )
+                .await
+            {
+                tracing::warn!(
+                    "Failed to update task block '{}' after task execution error: {}",
+                    call_id,
+                    update_err
+                );
+            }
+
+            return Err(ToolExecutorError::ExecutionFailed {
+                tool_name: "task".to_string(),
+                error: e.to_string(),
+            });
+        }
+    };
+
+    context
+        .assistant_update_block(
+            &call_id,
+            block(
+                response.session_id,
+                response.status.clone(),
+                response.summary.clone(),
+            ),
+        )
+        .await
+        .map_err(|e| ToolExecutorError::ExecutionFailed {
+            tool_name: "task".to_string(),
+            error: e.to_string(),
+        })?;
+
+    Ok(response)
+}
+
+/// Tells the parent how to bring back the work of a child that ran in its own worktree.
+async fn worktree_note(
+    context: &TaskContext,
+    use_worktree: bool,
+    child_session_id: i64,
+) -> Option<String> {
+    if !use_worktree {
+        return None;
+    }
+    let session = context
+        .agent_persistence()
+        .sessions()
+        .get(child_session_id)
+        .await
+        .ok()
+        .flatten()?;
+    session.worktree_path.as_ref()?;
+    Some(format!(
+        "The changes are on branch `{}` (session_id {child_session_id}), not in your checkout. Review them with `worktree` action `diff`, then `merge` or `discard`.",
+        crate::agent::worktree::branch_name(child_session_id)
+    ))
+}
+
 fn required_string_arg(args: &Value, key: &str) -> ToolExecutorResult<String> {
     let value = args
         .get(key)
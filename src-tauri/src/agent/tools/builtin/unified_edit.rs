@@ -20,6 +20,7 @@ use crate::agent::tools::{
     ToolResultStatus,
 };
 
+use super::edit_diagnostics::EditDiagnostics;
 use super::file_utils::{ensure_absolute, is_probably_binary};
 
 // ============================================================================
@@ -535,7 +536,9 @@ fn levenshtein_distance(a: &str, b: &str) -> usize {
 // Tool implementation
 // ============================================================================
 
-pub struct UnifiedEditTool;
+pub struct UnifiedEditTool {
+    diagnostics: EditDiagnostics,
+}
 
 impl Default for UnifiedEditTool {
     fn default() -> Self {
@@ -545,7 +548,12 @@ impl Default for UnifiedEditTool {
 
 impl UnifiedEditTool {
     pub fn new() -> Self {
-        Self
+        Self::with_diagnostics(EditDiagnostics::default())
+    }
+
+    /// Report errors introduced by an edit in the tool result.
+    pub fn with_diagnostics(diagnostics: EditDiagnostics) -> Self {
+        Self { diagnostics }
     }
 }
 
@@ -663,6 +671,7 @@ Parameters:
             updated
         };
 
+        let baseline = self.diagnostics.baseline(&context.cwd, &path).await;
         context.note_agent_write_intent(path.as_path()).await;
         snapshot_before_edit(context, self.name(), path.as_path()).await?;
 
@@ -675,9 +684,14 @@ Parameters:
         }
 
         track_edit(context, &path).await?;
+        let new_errors = self
+            .diagnostics
+            .regressions(&context.cwd, &path, baseline)
+            .await
+            .unwrap_or_default();
 
         Ok(success_result(
-            format!("edit_file applied\nfile={}", path.display()),
+            format!("edit_file applied\nfile={}{new_errors}", path.display()),
             json!({
                 "file": path.display().to_string(),
                 "old": args.old_text,
@@ -21,6 +21,7 @@ use url::Url;
 use crate::agent::common::llm_text::extract_text_from_llm_message;
 use crate::agent::core::context::TaskContext;
 use crate::agent::error::{ToolExecutorError, ToolExecutorResult};
+use crate::agent::persistence::UsagePurpose;
 use crate::agent::tools::{
     BackoffStrategy, RateLimitConfig, RunnableTool, ToolCategory, ToolMetadata, ToolPriority,
     ToolResult, ToolResultContent, ToolResultStatus,
@@ -347,7 +348,7 @@ async fn summarize_with_llm(
     );
 
     let request = CreateMessageRequest {
-        model: model_id,
+        model: model_id.clone(),
         max_tokens: 2048,
         system: None,
         developer_context: None,
@@ -370,6 +371,10 @@ async fn summarize_with_llm(
         .call(request)
         .await
         .map_err(|e| format!("LLM call failed: {e}"))?;
+    let message_id = context.assistant_message_id().await;
+    context
+        .record_llm_usage(&model_id, UsagePurpose::WebFetch, message_id, &resp.usage)
+        .await;
 
     Ok(extract_text_from_llm_message(&resp))
 }
@@ -0,0 +1,210 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::agent::common::{truncate_middle, TruncationPolicy};
+use crate::agent::core::context::TaskContext;
+use crate::agent::error::{ToolExecutorError, ToolExecutorResult};
+use crate::agent::tools::{
+    RunnableTool, ToolCategory, ToolMetadata, ToolPriority, ToolResult, ToolResultContent,
+    ToolResultStatus,
+};
+use crate::agent::worktree;
+
+use super::unified_edit::{snapshot_before_edit, track_edit};
+use crate::git::{BranchDiff, MergeOutcome, MergeStrategy};
+
+/// Patches beyond this are cut in the middle; the file list is always complete.
+const MAX_PATCH_CHARS: usize = 60_000;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WorktreeAction {
+    Diff,
+    Merge,
+    Discard,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorktreeArgs {
+    action: WorktreeAction,
+    session_id: i64,
+    #[serde(default)]
+    strategy: Option<String>,
+}
+
+#[derive(Default)]
+pub struct WorktreeTool;
+
+impl WorktreeTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl RunnableTool for WorktreeTool {
+    fn name(&self) -> &str {
+        "worktree"
+    }
+
+    fn description(&self) -> &str {
+        r#"Review and integrate the work of a finished `task` that ran with `use_worktree: true`.
+
+Usage:
+- `session_id` is the child session id reported by `task`.
+- `diff`: show the files and patch the child changed, including uncommitted work.
+- `merge`: commit the child's pending work and merge its branch into your checkout. `strategy` is "merge" (default, keeps a merge commit) or "cherry_pick". The files it changes are snapshotted first, so the merge can be rolled back like an edit. On conflicts the merge is aborted, your checkout is left untouched and the conflicting files are listed.
+- `discard`: delete the child's worktree and force-delete its branch (`git branch -D`). Everything the child did is lost, including work it committed. Review it with `diff` first.
+- Both `merge` and `discard` remove the worktree and its branch afterwards.
+- Merge children one at a time and re-check the result before merging the next."#
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": { "type": "string", "enum": ["diff", "merge", "discard"] },
+                "session_id": { "type": "number", "description": "Child session id of the task" },
+                "strategy": { "type": "string", "enum": ["merge", "cherry_pick"], "description": "How `merge` brings the branch in. Default: merge" }
+            },
+            "required": ["action", "session_id"]
+        })
+    }
+
+    fn metadata(&self) -> ToolMetadata {
+        ToolMetadata::new(ToolCategory::FileWrite, ToolPriority::Standard)
+            .with_tags(vec!["git".into(), "orchestration".into()])
+            .with_summary_key_arg("action")
+    }
+
+    async fn run(
+        &self,
+        context: &TaskContext,
+        args: serde_json::Value,
+    ) -> ToolExecutorResult<ToolResult> {
+        let args: WorktreeArgs = serde_json::from_value(args)?;
+        let strategy = match args.strategy.as_deref() {
+            None | Some("merge") => MergeStrategy::Merge,
+            Some("cherry_pick") => MergeStrategy::CherryPick,
+            Some(other) => {
+                return Err(ToolExecutorError::InvalidArguments {
+                    tool_name: "worktree".to_string(),
+                    error: format!("Invalid strategy '{other}'. Expected merge or cherry_pick"),
+                })
+            }
+        };
+
+        let persistence = context.agent_persistence();
+        let child = persistence
+            .sessions()
+            .get(args.session_id)
+            .await?
+            .filter(|session| session.parent_id == Some(context.session_id));
+        if child.is_none() {
+            return Ok(error_result(format!(
+                "Session {} is not a task started by this session",
+                args.session_id
+            )));
+        }
+
+        // The merge writes into the caller's checkout outside the edit tools, so snapshot
+        // what it touches the same way they do
+        let merge_paths = match args.action {
+            WorktreeAction::Merge => {
+                match worktree::merge_paths(&persistence, args.session_id).await {
+                    Ok(paths) => paths,
+                    Err(err) => return Ok(error_result(err.to_string())),
+                }
+            }
+            _ => Vec::new(),
+        };
+        for path in &merge_paths {
+            context.note_agent_write_intent(path).await;
+            snapshot_before_edit(context, self.name(), path).await?;
+        }
+
+        let result = match args.action {
+            WorktreeAction::Diff => worktree::diff(&persistence, args.session_id)
+                .await
+                .map(|diff| format_diff(&diff)),
+            WorktreeAction::Merge => {
+                match worktree::merge(&persistence, args.session_id, strategy).await {
+                    Ok(outcome) => {
+                        if outcome.merged {
+                            for path in merge_paths.iter().filter(|path| path.is_file()) {
+                                track_edit(context, path).await?;
+                            }
+                        }
+                        Ok(format_merge(args.session_id, &outcome))
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+            WorktreeAction::Discard => worktree::discard(&persistence, args.session_id)
+                .await
+                .map(|()| format!("Discarded the worktree of session {}", args.session_id)),
+        };
+
+        Ok(match result {
+            Ok(text) => ToolResult {
+                content: vec![ToolResultContent::Success(text)],
+                status: ToolResultStatus::Success,
+                cancel_reason: None,
+                execution_time_ms: None,
+                ext_info: Some(json!({ "session_id": args.session_id })),
+            },
+            Err(err) => error_result(err.to_string()),
+        })
+    }
+}
+
+fn error_result(message: String) -> ToolResult {
+    ToolResult {
+        content: vec![ToolResultContent::Error(message)],
+        status: ToolResultStatus::Error,
+        cancel_reason: None,
+        execution_time_ms: None,
+        ext_info: None,
+    }
+}
+
+fn format_diff(diff: &BranchDiff) -> String {
+    if diff.files.is_empty() {
+        return format!("Branch {} has no changes", diff.branch);
+    }
+
+    let mut out = format!(
+        "Branch {} changes {} file(s):\n",
+        diff.branch,
+        diff.files.len()
+    );
+    for file in &diff.files {
+        let stats = match (file.additions, file.deletions) {
+            (Some(add), Some(del)) => format!("+{add} -{del}"),
+            _ => "binary".to_string(),
+        };
+        out.push_str(&format!("- {:?} {} ({stats})\n", file.status, file.path));
+    }
+    out.push('\n');
+    out.push_str(&truncate_middle(&diff.patch, TruncationPolicy::Chars(MAX_PATCH_CHARS)).text);
+    out
+}
+
+fn format_merge(session_id: i64, outcome: &MergeOutcome) -> String {
+    if !outcome.merged {
+        return format!(
+            "Merge aborted because of conflicts in:\n{}\n\nYour checkout is unchanged and the worktree of session {session_id} is kept. Resolve the overlap (for example by editing these files yourself) before merging again, or discard it.",
+            outcome
+                .conflicts
+                .iter()
+                .map(|path| format!("- {path}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+    match &outcome.commit {
+        Some(commit) => format!("Merged session {session_id} at {commit}; worktree removed"),
+        None => format!("Session {session_id} had no changes to merge; worktree removed"),
+    }
+}
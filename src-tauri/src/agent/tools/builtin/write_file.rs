@@ -14,6 +14,7 @@ use crate::agent::tools::{
     ToolResultStatus,
 };
 
+use super::edit_diagnostics::EditDiagnostics;
 use super::file_utils::{ensure_absolute, is_probably_binary};
 
 #[derive(Debug, Deserialize)]
@@ -23,7 +24,9 @@ struct WriteFileArgs {
     content: String,
 }
 
-pub struct WriteFileTool;
+pub struct WriteFileTool {
+    diagnostics: EditDiagnostics,
+}
 
 impl Default for WriteFileTool {
     fn default() -> Self {
@@ -33,7 +36,12 @@ impl Default for WriteFileTool {
 
 impl WriteFileTool {
     pub fn new() -> Self {
-        Self
+        Self::with_diagnostics(EditDiagnostics::default())
+    }
+
+    /// Report errors introduced by an edit in the tool result.
+    pub fn with_diagnostics(diagnostics: EditDiagnostics) -> Self {
+        Self { diagnostics }
     }
 }
 
@@ -125,6 +133,7 @@ Usage:
             }
         }
 
+        let baseline = self.diagnostics.baseline(&context.cwd, &path).await;
         context.note_agent_write_intent(path.as_path()).await;
         snapshot_before_edit(context, self.name(), path.as_path()).await?;
 
@@ -143,10 +152,15 @@ Usage:
                 FileRecordSource::AgentEdited,
             ))
             .await?;
+        let new_errors = self
+            .diagnostics
+            .regressions(&context.cwd, &path, baseline)
+            .await
+            .unwrap_or_default();
 
         Ok(ToolResult {
             content: vec![ToolResultContent::Success(format!(
-                "write_file applied\nfile={}",
+                "write_file applied\nfile={}{new_errors}",
                 path.display()
             ))],
             status: ToolResultStatus::Success,
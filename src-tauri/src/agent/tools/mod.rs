@@ -24,18 +24,22 @@ pub use registry::{ToolExecutionStats, ToolRegistry};
 pub use builtin::{
     GlobTool, GrepTool, ListFilesTool, LspQueryTool, MultiEditTool, ReadFileTool, ReadTerminalTool,
     SemanticSearchTool, ShellTool, SyntaxDiagnosticsTool, TaskTool, TodoWriteTool, UnifiedEditTool,
-    WebFetchTool, WebSearchTool, WriteFileTool,
+    WebFetchTool, WebSearchTool, WorktreeTool, WriteFileTool,
 };
 
 use std::sync::Arc;
 use tracing::error;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn create_tool_registry(
     chat_mode: &str,
     permission_rules: crate::settings::types::PermissionRules,
+    hooks: crate::settings::types::HooksConfig,
+    sandbox: crate::settings::types::SandboxConfig,
     agent_tool_filter: Option<crate::agent::permissions::ToolFilter>,
     confirmations: Arc<ToolConfirmationManager>,
     extra_tools: Vec<Arc<dyn RunnableTool>>,
+    workspace_root: &std::path::Path,
     vector_search_engine: Option<Arc<crate::vector_db::search::SemanticSearchEngine>>,
     lsp_manager: Option<Arc<crate::lsp::LspManager>>,
     skill_manager: Option<Arc<crate::agent::skill::SkillManager>>,
@@ -44,21 +48,25 @@ pub async fn create_tool_registry(
         &permission_rules,
     ));
     let agent_filter = agent_tool_filter.map(Arc::new);
+    let hooks = (!hooks.is_empty()).then(|| Arc::new(crate::agent::hooks::HookRunner::new(hooks)));
     let registry = Arc::new(ToolRegistry::new(
         Some(checker),
         agent_filter,
+        hooks,
         confirmations,
     ));
     let is_chat = chat_mode == "chat";
 
     let availability_ctx = ToolAvailabilityContext {
         has_vector_index: vector_search_engine.is_some(),
+        has_keyword_index: crate::vector_db::has_keyword_index(workspace_root),
     };
 
     register_builtin_tools(
         &registry,
         is_chat,
         &availability_ctx,
+        sandbox,
         vector_search_engine,
         lsp_manager,
         skill_manager,
@@ -77,6 +85,7 @@ async fn register_builtin_tools(
     registry: &ToolRegistry,
     is_chat_mode: bool,
     availability_ctx: &ToolAvailabilityContext,
+    sandbox: crate::settings::types::SandboxConfig,
     vector_search_engine: Option<Arc<crate::vector_db::search::SemanticSearchEngine>>,
     lsp_manager: Option<Arc<crate::lsp::LspManager>>,
     skill_manager: Option<Arc<crate::agent::skill::SkillManager>>,
@@ -92,6 +101,15 @@ async fn register_builtin_tools(
     )
     .await;
 
+    register_tool(
+        registry,
+        "worktree",
+        Arc::new(WorktreeTool::new()),
+        is_chat_mode,
+        availability_ctx,
+    )
+    .await;
+
     register_tool(
         registry,
         "todowrite",
@@ -127,10 +145,11 @@ async fn register_builtin_tools(
         availability_ctx,
     )
     .await;
+    let edit_diagnostics = builtin::EditDiagnostics::new(lsp_manager.clone());
     register_tool(
         registry,
         "write_file",
-        Arc::new(WriteFileTool::new()),
+        Arc::new(WriteFileTool::with_diagnostics(edit_diagnostics.clone())),
         is_chat_mode,
         availability_ctx,
     )
@@ -138,7 +157,7 @@ async fn register_builtin_tools(
     register_tool(
         registry,
         "edit_file",
-        Arc::new(UnifiedEditTool::new()),
+        Arc::new(UnifiedEditTool::with_diagnostics(edit_diagnostics.clone())),
         is_chat_mode,
         availability_ctx,
     )
@@ -146,7 +165,7 @@ async fn register_builtin_tools(
     register_tool(
         registry,
         "multi_edit_file",
-        Arc::new(MultiEditTool::new()),
+        Arc::new(MultiEditTool::with_diagnostics(edit_diagnostics)),
         is_chat_mode,
         availability_ctx,
     )
@@ -163,7 +182,7 @@ async fn register_builtin_tools(
     register_tool(
         registry,
         "shell",
-        Arc::new(ShellTool::new()),
+        Arc::new(ShellTool::with_sandbox(sandbox)),
         is_chat_mode,
         availability_ctx,
     )
@@ -186,21 +205,19 @@ async fn register_builtin_tools(
         availability_ctx,
     )
     .await;
-    if let Some(engine) = vector_search_engine {
-        register_tool(
-            registry,
-            "semantic_search",
-            Arc::new(SemanticSearchTool::new(engine)),
-            is_chat_mode,
-            availability_ctx,
-        )
-        .await;
-    }
+    register_tool(
+        registry,
+        "semantic_search",
+        Arc::new(SemanticSearchTool::new(vector_search_engine)),
+        is_chat_mode,
+        availability_ctx,
+    )
+    .await;
     if let Some(manager) = lsp_manager {
         register_tool(
             registry,
             "lsp_query",
-            Arc::new(LspQueryTool::new(manager)),
+            Arc::new(LspQueryTool::new(manager, !is_chat_mode)),
             is_chat_mode,
             availability_ctx,
         )
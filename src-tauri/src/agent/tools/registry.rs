@@ -17,6 +17,7 @@ use super::r#trait::{
 use crate::agent::common::truncate_chars;
 use crate::agent::core::context::TaskContext;
 use crate::agent::error::{ToolExecutorError, ToolExecutorResult};
+use crate::agent::hooks::{HookEvent, HookInput, HookRunner};
 use crate::agent::tools::builtin::file_utils::{ensure_absolute, normalize_path};
 use crate::agent::types::TaskEvent;
 use crate::agent::{
@@ -89,6 +90,8 @@ pub struct ToolRegistry {
     /// Agent tool filter: whitelist/blacklist for tool visibility.
     /// Separate from settings_permissions (which controls allow/deny/ask confirmation).
     agent_tool_filter: Option<Arc<ToolFilter>>,
+    /// User hooks from settings.json (PreToolUse / PostToolUse are run here).
+    hooks: Option<Arc<HookRunner>>,
     confirmations: Arc<ToolConfirmationManager>,
 }
 
@@ -109,6 +112,13 @@ struct PendingConfirmation {
     summary: String,
     permission: String,
     always_patterns: Vec<String>,
+    /// Present when the user is asked to fill in a form rather than just approve
+    form: Option<PendingForm>,
+}
+
+struct PendingForm {
+    schema: serde_json::Value,
+    content_tx: tokio::sync::oneshot::Sender<serde_json::Value>,
 }
 
 #[derive(Debug, Default)]
@@ -138,6 +148,7 @@ impl ToolRegistry {
     pub fn new(
         settings_permissions: Option<Arc<PermissionChecker>>,
         agent_tool_filter: Option<Arc<ToolFilter>>,
+        hooks: Option<Arc<HookRunner>>,
         confirmations: Arc<ToolConfirmationManager>,
     ) -> Self {
         Self {
@@ -145,21 +156,44 @@ impl ToolRegistry {
             entries: DashMap::new(),
             settings_permissions,
             agent_tool_filter,
+            hooks,
             confirmations,
         }
     }
 
+    pub fn hooks(&self) -> Option<&Arc<HookRunner>> {
+        self.hooks.as_ref()
+    }
+
+    fn hooks_for(&self, event: HookEvent) -> Option<&Arc<HookRunner>> {
+        self.hooks.as_ref().filter(|hooks| hooks.has_hooks(event))
+    }
+
     pub async fn resolve_confirmation(
         &self,
         context: &TaskContext,
         request_id: &str,
         decision: ToolConfirmationDecision,
+        content: Option<serde_json::Value>,
     ) -> bool {
         let removed = self.confirmations.pending_confirmations.remove(request_id);
-        let Some((_, pending)) = removed else {
+        let Some((_, mut pending)) = removed else {
             return false;
         };
 
+        // Forms are answered on their own: nothing to remember or cascade
+        if let Some(form) = pending.form.take() {
+            if !matches!(decision, ToolConfirmationDecision::Deny) {
+                let _ = form
+                    .content_tx
+                    .send(content.unwrap_or_else(|| serde_json::json!({})));
+            }
+            let ok = pending.tx.send(decision).is_ok();
+            self.finish_confirmation_and_pump_next(context, request_id)
+                .await;
+            return ok;
+        }
+
         let workspace = pending.workspace_path.clone();
         let task_id = pending.task_id.clone();
         let permission = pending.permission.clone();
@@ -236,7 +270,7 @@ impl ToolRegistry {
         for entry in self.confirmations.pending_confirmations.iter() {
             let id = entry.key().clone();
             let p = entry.value();
-            if p.task_id != task_id {
+            if p.task_id != task_id || p.form.is_some() {
                 continue;
             }
             if p.workspace_path != workspace_path {
@@ -358,6 +392,7 @@ impl ToolRegistry {
                 workspace_path: pending.workspace_path.clone(),
                 tool_name: pending.tool_name.clone(),
                 summary: pending.summary.clone(),
+                form: pending.form.as_ref().map(|form| form.schema.clone()),
             })
             .await
             .map_err(|err| ToolExecutorError::ExecutionFailed {
@@ -445,7 +480,7 @@ impl ToolRegistry {
         &self,
         tool_name: &str,
         context: &TaskContext,
-        args: serde_json::Value,
+        mut args: serde_json::Value,
     ) -> ToolResult {
         let start = Instant::now();
 
@@ -483,6 +518,30 @@ impl ToolRegistry {
             }
         };
 
+        // PreToolUse hooks run first so permission checks see any rewritten arguments.
+        let mut hook_context = Vec::new();
+        if let Some(hooks) = self.hooks_for(HookEvent::PreToolUse) {
+            let input = HookInput::for_task(HookEvent::PreToolUse, context)
+                .with_tool(&resolved, args.clone());
+            let outcome = hooks.run(input).await;
+            if let Some(reason) = outcome.blocked {
+                return self
+                    .make_error_result(
+                        &resolved,
+                        format!("Blocked by PreToolUse hook: {reason}"),
+                        Some("source=hooks".to_string()),
+                        ToolResultStatus::Error,
+                        Some("denied".to_string()),
+                        start,
+                    )
+                    .await;
+            }
+            if let Some(updated) = outcome.updated_input {
+                args = updated;
+            }
+            hook_context = outcome.additional_context;
+        }
+
         let action = build_tool_action(&resolved, &metadata, context, &args);
         let (settings_decision, settings_matched) =
             if let Some(checker) = self.settings_permissions.as_ref() {
@@ -583,6 +642,7 @@ impl ToolRegistry {
         }
 
         let timeout = metadata.effective_timeout();
+        let post_hook_args = self.hooks_for(HookEvent::PostToolUse).map(|_| args.clone());
 
         let timeout_result = tokio::time::timeout(
             timeout,
@@ -590,7 +650,7 @@ impl ToolRegistry {
         )
         .await;
 
-        match timeout_result {
+        let mut result = match timeout_result {
             Ok(result) => result,
             Err(_) => {
                 let elapsed = start.elapsed().as_millis() as u64;
@@ -610,7 +670,23 @@ impl ToolRegistry {
                     ext_info: None,
                 }
             }
+        };
+
+        if let (Some(hooks), Some(args)) = (self.hooks.as_ref(), post_hook_args) {
+            let response = serde_json::to_value(&result).unwrap_or(serde_json::Value::Null);
+            let input = HookInput::for_task(HookEvent::PostToolUse, context)
+                .with_tool(&resolved, args)
+                .with_tool_response(response);
+            let outcome = hooks.run(input).await;
+            hook_context.extend(outcome.additional_context);
+            // The tool already ran; a blocking PostToolUse hook just gets its reason to the model.
+            if let Some(reason) = outcome.blocked {
+                hook_context.push(format!("PostToolUse hook: {reason}"));
+            }
         }
+
+        append_hook_context(&mut result, hook_context);
+        result
     }
 
     fn effective_permission_decision(
@@ -718,6 +794,7 @@ impl ToolRegistry {
                         &format!("external directory access required: {summary}"),
                         "external_directory",
                         &ext,
+                        None,
                     )
                     .await
                 {
@@ -766,6 +843,7 @@ impl ToolRegistry {
                 &summary,
                 &permission,
                 &always_patterns,
+                None,
             )
             .await
         {
@@ -802,6 +880,61 @@ impl ToolRegistry {
         }
     }
 
+    /// Ask the user to approve an action that does not go through `execute_tool`,
+    /// such as an MCP server requesting an LLM completion. "Always allow" is
+    /// remembered per workspace for `permission` + `pattern`.
+    pub async fn confirm_action(
+        &self,
+        context: &TaskContext,
+        tool_name: &str,
+        summary: &str,
+        permission: &str,
+        pattern: &str,
+    ) -> ToolExecutorResult<ToolConfirmationDecision> {
+        let workspace = context.session().workspace.to_string_lossy().to_string();
+        let patterns = vec![pattern.to_string()];
+        let db = context.session().repositories();
+        if is_preapproved(db.as_ref(), &workspace, permission, &patterns).await {
+            return Ok(ToolConfirmationDecision::AllowAlways);
+        }
+
+        self.request_tool_confirmation(
+            context, &workspace, tool_name, summary, permission, &patterns, None,
+        )
+        .await
+    }
+
+    /// Ask the user to fill in a form described by a JSON schema, through the
+    /// confirmation dialog. Returns `None` when the user declines.
+    pub async fn request_user_input(
+        &self,
+        context: &TaskContext,
+        tool_name: &str,
+        summary: &str,
+        schema: serde_json::Value,
+    ) -> ToolExecutorResult<Option<serde_json::Value>> {
+        let workspace = context.session().workspace.to_string_lossy().to_string();
+        let (content_tx, mut content_rx) = tokio::sync::oneshot::channel();
+        let decision = self
+            .request_tool_confirmation(
+                context,
+                &workspace,
+                tool_name,
+                summary,
+                "user_input",
+                &[],
+                Some(PendingForm { schema, content_tx }),
+            )
+            .await?;
+
+        if matches!(decision, ToolConfirmationDecision::Deny) {
+            return Ok(None);
+        }
+        // The content is sent before the decision, so it is already there
+        Ok(content_rx.try_recv().ok())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn request_tool_confirmation(
         &self,
         context: &TaskContext,
@@ -810,6 +943,7 @@ impl ToolRegistry {
         summary: &str,
         permission: &str,
         always_patterns: &[String],
+        form: Option<PendingForm>,
     ) -> ToolExecutorResult<ToolConfirmationDecision> {
         let request_id = Uuid::new_v4().to_string();
         let (tx, rx) = tokio::sync::oneshot::channel();
@@ -823,6 +957,7 @@ impl ToolRegistry {
                 summary: summary.to_string(),
                 permission: permission.to_string(),
                 always_patterns: always_patterns.to_vec(),
+                form,
             },
         );
 
@@ -1056,6 +1191,18 @@ impl ToolRegistry {
     }
 }
 
+/// Hook output is appended to the tool result so the model sees it alongside the output.
+fn append_hook_context(result: &mut ToolResult, context: Vec<String>) {
+    if context.is_empty() {
+        return;
+    }
+    let text = format!("<hook-context>\n{}\n</hook-context>", context.join("\n\n"));
+    result.content.push(match result.status {
+        ToolResultStatus::Success => ToolResultContent::Success(text),
+        _ => ToolResultContent::Error(text),
+    });
+}
+
 fn build_tool_action_for_prompt(tool_name: &str, workspace_root: PathBuf) -> ToolAction {
     if tool_name.starts_with("mcp__") {
         return ToolAction::new(tool_name, workspace_root, vec![]);
@@ -1138,6 +1285,12 @@ fn build_tool_action(
             workspace_root,
             single_arg_variants(args, "query"),
         ),
+        // Rename, format and applied code actions write files like edit_file
+        "lsp_query" if lsp_query_edits(args) => ToolAction::new(
+            "edit",
+            workspace_root,
+            path_variants(args, metadata, context),
+        ),
         "read_terminal" => ToolAction::new("terminal", workspace_root, vec![]),
         "syntax_diagnostics" => ToolAction::new("syntax_diagnostics", workspace_root, vec![]),
         "todowrite" => ToolAction::new("todowrite", workspace_root, vec![]),
@@ -1170,6 +1323,14 @@ fn path_variants(
     }
 }
 
+fn lsp_query_edits(args: &serde_json::Value) -> bool {
+    match args.get("action").and_then(|value| value.as_str()) {
+        Some("rename" | "format") => true,
+        Some("code_actions") => trimmed_string_arg(args, "apply").is_some(),
+        _ => false,
+    }
+}
+
 fn trimmed_string_arg(args: &serde_json::Value, key: &str) -> Option<String> {
     args.get(key)
         .and_then(|value| value.as_str())
@@ -1193,7 +1354,7 @@ fn tool_path_arg(args: &serde_json::Value, metadata: &ToolMetadata) -> Option<St
     })
 }
 
-fn bash_param_variants(command: &str) -> Vec<String> {
+pub(crate) fn bash_param_variants(command: &str) -> Vec<String> {
     let cmd = command.trim();
     if cmd.is_empty() {
         return vec![];
@@ -1453,7 +1614,7 @@ async fn cascade_approvals(
     for entry in pending.iter() {
         let id = entry.key().clone();
         let p = entry.value();
-        if p.workspace_path != workspace_path {
+        if p.workspace_path != workspace_path || p.form.is_some() {
             continue;
         }
         let ok = p
@@ -1481,7 +1642,7 @@ async fn cascade_approvals(
     }
 }
 
-fn summarize_tool_call(
+pub(crate) fn summarize_tool_call(
     tool_name: &str,
     metadata: &ToolMetadata,
     args: &serde_json::Value,
@@ -1506,7 +1667,7 @@ fn summarize_tool_call(
 
 impl Default for ToolRegistry {
     fn default() -> Self {
-        Self::new(None, None, Arc::new(ToolConfirmationManager::new()))
+        Self::new(None, None, None, Arc::new(ToolConfirmationManager::new()))
     }
 }
 
@@ -22,6 +22,8 @@ pub struct ToolDescriptionContext {
 #[derive(Debug, Clone, Default)]
 pub struct ToolAvailabilityContext {
     pub has_vector_index: bool,
+    /// The workspace has a keyword index, which works without an embedding model
+    pub has_keyword_index: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +49,17 @@ pub struct TokenUsage {
     pub cache_write_tokens: Option<i64>,
 }
 
+impl From<crate::llm::anthropic_types::Usage> for TokenUsage {
+    fn from(usage: crate::llm::anthropic_types::Usage) -> Self {
+        Self {
+            input_tokens: i64::from(usage.input_tokens),
+            output_tokens: i64::from(usage.output_tokens),
+            cache_read_tokens: usage.cache_read_input_tokens.map(i64::from),
+            cache_write_tokens: usage.cache_creation_input_tokens.map(i64::from),
+        }
+    }
+}
+
 /// Context usage information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -234,6 +245,9 @@ pub enum TaskEvent {
         workspace_path: String,
         tool_name: String,
         summary: String,
+        /// JSON schema of the form to fill in, when the server asks for input
+        #[serde(skip_serializing_if = "Option::is_none")]
+        form: Option<Value>,
     },
 
     /// LLM request is being retried (connection/rate-limit/server error)
@@ -246,4 +260,37 @@ pub enum TaskEvent {
         error_message: String,
         retry_in_ms: u64,
     },
+
+    /// The run used up its token or cost budget and was stopped; terminal like `TaskCompleted`
+    #[serde(rename_all = "camelCase")]
+    TaskBudgetExceeded {
+        task_id: String,
+        used_tokens: u64,
+        max_tokens: Option<u64>,
+        /// USD
+        used_cost: f64,
+        max_cost: Option<f64>,
+    },
+
+    /// Progress reported by an MCP server for a running tool call
+    #[serde(rename_all = "camelCase")]
+    McpProgress {
+        task_id: String,
+        tool_name: String,
+        server: String,
+        progress: f64,
+        total: Option<f64>,
+        message: Option<String>,
+    },
+
+    /// Log entry sent by an MCP server while one of its tools was running
+    #[serde(rename_all = "camelCase")]
+    McpLog {
+        task_id: String,
+        tool_name: String,
+        server: String,
+        level: String,
+        logger: Option<String>,
+        data: Value,
+    },
 }
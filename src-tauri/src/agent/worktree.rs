@@ -0,0 +1,170 @@
+//! Git worktrees of `task` children started with `use_worktree`
+//!
+//! A child works on branch `opencodex/task-<session id>` checked out under
+//! `<repo>/.git/opencodex-worktrees/<session id>`. Once it finishes, its changes are reviewed
+//! against the parent's checkout and either merged back or discarded; both remove the
+//! worktree and its branch.
+
+use std::path::{Path, PathBuf};
+
+use crate::agent::error::{AgentError, AgentResult};
+use crate::agent::persistence::{AgentPersistence, Session, SessionStatus};
+use crate::git::service::find_repo_root;
+use crate::git::{BranchDiff, GitError, GitService, MergeOutcome, MergeStrategy};
+
+pub fn branch_name(session_id: i64) -> String {
+    format!("opencodex/task-{session_id}")
+}
+
+pub fn worktree_dir(repo_root: &str, session_id: i64) -> String {
+    format!("{repo_root}/.git/opencodex-worktrees/{session_id}")
+}
+
+/// Worktree of a child session and the checkout it merges back into
+struct SubtaskWorktree {
+    session: Session,
+    path: String,
+    branch: String,
+    target: String,
+}
+
+async fn resolve(persistence: &AgentPersistence, session_id: i64) -> AgentResult<SubtaskWorktree> {
+    let session = load_session(persistence, session_id).await?;
+    let path = session
+        .worktree_path
+        .clone()
+        .ok_or_else(|| AgentError::Internal(format!("Session {session_id} has no worktree")))?;
+    if session.status == SessionStatus::Running {
+        return Err(AgentError::Internal(format!(
+            "Session {session_id} is still running"
+        )));
+    }
+
+    // The child was started in the parent's cwd: the nearest ancestor worktree, or the
+    // workspace itself.
+    let mut target = session.workspace_path.clone();
+    let mut parent_id = session.parent_id;
+    while let Some(id) = parent_id {
+        let parent = load_session(persistence, id).await?;
+        if let Some(parent_worktree) = parent.worktree_path {
+            target = parent_worktree;
+            break;
+        }
+        parent_id = parent.parent_id;
+    }
+
+    Ok(SubtaskWorktree {
+        branch: branch_name(session.id),
+        session,
+        path,
+        target,
+    })
+}
+
+async fn load_session(persistence: &AgentPersistence, session_id: i64) -> AgentResult<Session> {
+    persistence
+        .sessions()
+        .get(session_id)
+        .await?
+        .ok_or_else(|| AgentError::Internal(format!("Session {session_id} not found")))
+}
+
+/// Changes of the child worktree, committed or not, since it forked from the target.
+pub async fn diff(persistence: &AgentPersistence, session_id: i64) -> AgentResult<BranchDiff> {
+    let worktree = resolve(persistence, session_id).await?;
+    let base = GitService::head_commit(&worktree.target).await?;
+    Ok(GitService::branch_diff(&worktree.path, &base).await?)
+}
+
+/// Files of the target checkout that merging the child would write, including the old side
+/// of renames.
+pub async fn merge_paths(
+    persistence: &AgentPersistence,
+    session_id: i64,
+) -> AgentResult<Vec<PathBuf>> {
+    let worktree = resolve(persistence, session_id).await?;
+    let base = GitService::head_commit(&worktree.target).await?;
+    let diff = GitService::branch_diff(&worktree.path, &base).await?;
+    let target = Path::new(&worktree.target);
+    Ok(diff
+        .files
+        .iter()
+        .flat_map(|file| std::iter::once(&file.path).chain(file.old_path.as_ref()))
+        .map(|path| target.join(path))
+        .collect())
+}
+
+/// Commit pending work in the child worktree and merge its branch into the target.
+///
+/// The worktree is removed after a successful merge; on conflicts nothing changes and the
+/// conflicting paths are returned.
+pub async fn merge(
+    persistence: &AgentPersistence,
+    session_id: i64,
+    strategy: MergeStrategy,
+) -> AgentResult<MergeOutcome> {
+    let worktree = resolve(persistence, session_id).await?;
+    let title = worktree
+        .session
+        .title
+        .clone()
+        .unwrap_or_else(|| format!("task {session_id}"));
+
+    GitService::commit_all(&worktree.path, &title).await?;
+    let message = format!("Merge {}: {}", worktree.branch, title);
+    let outcome =
+        GitService::merge_branch(&worktree.target, &worktree.branch, strategy, &message).await?;
+
+    if outcome.merged {
+        remove(persistence, &worktree).await?;
+    }
+    Ok(outcome)
+}
+
+/// Throw away the child's changes along with its worktree and branch.
+pub async fn discard(persistence: &AgentPersistence, session_id: i64) -> AgentResult<()> {
+    let worktree = resolve(persistence, session_id).await?;
+    remove(persistence, &worktree).await
+}
+
+async fn remove(persistence: &AgentPersistence, worktree: &SubtaskWorktree) -> AgentResult<()> {
+    remove_worktree(&worktree.target, &worktree.path, &worktree.branch).await?;
+    persistence
+        .sessions()
+        .clear_worktree_path(worktree.session.id)
+        .await
+}
+
+async fn remove_worktree(repo_path: &str, path: &str, branch: &str) -> Result<(), GitError> {
+    if let Err(err) = GitService::worktree_remove(repo_path, path, true).await {
+        // Already deleted by hand: only git's bookkeeping is left
+        if Path::new(path).exists() {
+            return Err(err);
+        }
+        GitService::worktree_prune(repo_path).await?;
+    }
+    GitService::delete_branch(repo_path, branch).await
+}
+
+/// Best-effort removal of the worktree of a session that is being deleted.
+pub async fn remove_for_deleted_session(session: &Session) {
+    let Some(path) = session.worktree_path.as_deref() else {
+        return;
+    };
+    let Some(repo_root) = find_repo_root(&session.workspace_path).await else {
+        tracing::warn!(
+            session_id = session.id,
+            worktree = %path,
+            "Workspace is no longer a git repository, leaving worktree in place"
+        );
+        return;
+    };
+    if let Err(err) = remove_worktree(&repo_root, path, &branch_name(session.id)).await {
+        tracing::warn!(
+            session_id = session.id,
+            worktree = %path,
+            "Failed to remove worktree of deleted session: {}",
+            err.message
+        );
+    }
+}
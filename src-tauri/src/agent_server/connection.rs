@@ -0,0 +1,1000 @@
+//! One client connection: newline-delimited JSON-RPC in both directions
+//!
+//! Requests from the client are handled concurrently so `session/cancel` and permission
+//! responses can arrive while a `session/prompt` is still running. Requests we send to the
+//! client (`session/request_permission`) are matched to their responses by id.
+//!
+//! The connection drives the agent through [`AgentBackend`], which [`TaskExecutor`] implements.
+
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, warn};
+
+use crate::agent::core::context::TaskContext;
+use crate::agent::core::executor::{ExecuteTaskParams, TaskExecutor};
+use crate::agent::mcp::protocol::jsonrpc::{
+    JsonRpcId, JsonRpcMessage, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse,
+};
+use crate::agent::tools::registry::ToolConfirmationDecision;
+use crate::agent::tools::ToolRegistry;
+use crate::agent::types::{Message, TaskEvent};
+use crate::workspace::{SessionRecord, WorkspaceService};
+
+use super::protocol::*;
+use super::ServerOptions;
+
+/// Messages replayed by `session/load`
+const HISTORY_REPLAY_LIMIT: i64 = 500;
+
+/// What a connection needs from the agent runtime
+#[async_trait]
+pub(super) trait AgentBackend: Send + Sync + 'static {
+    async fn create_session(&self, cwd: &str) -> Result<SessionRecord, String>;
+    async fn get_session(&self, id: i64) -> Result<Option<SessionRecord>, String>;
+    /// Model and agent the session last ran with
+    async fn session_defaults(&self, id: i64) -> Result<(Option<String>, Option<String>), String>;
+    /// Newest messages first
+    async fn session_messages(&self, id: i64, limit: i64) -> Result<Vec<Message>, String>;
+    async fn list_sessions(&self, cwd: &str) -> Result<Vec<SessionRecord>, String>;
+    /// Start a turn whose events are sent to `channel`
+    async fn start_task(
+        &self,
+        params: ExecuteTaskParams,
+        channel: Channel<TaskEvent>,
+    ) -> Result<Arc<dyn RunningTask>, String>;
+    async fn cancel_task(&self, task_id: &str) -> Result<(), String>;
+}
+
+/// A turn started by `session/prompt`
+#[async_trait]
+pub(super) trait RunningTask: Send + Sync {
+    fn task_id(&self) -> String;
+    fn tool_registry(&self) -> Arc<ToolRegistry>;
+    /// False when the confirmation is no longer pending
+    async fn resolve_confirmation(
+        &self,
+        request_id: &str,
+        decision: ToolConfirmationDecision,
+    ) -> bool;
+}
+
+#[async_trait]
+impl AgentBackend for TaskExecutor {
+    async fn create_session(&self, cwd: &str) -> Result<SessionRecord, String> {
+        WorkspaceService::new(self.database())
+            .create_session(cwd, None)
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    async fn get_session(&self, id: i64) -> Result<Option<SessionRecord>, String> {
+        WorkspaceService::new(self.database())
+            .get_session(id)
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    async fn session_defaults(&self, id: i64) -> Result<(Option<String>, Option<String>), String> {
+        let stored = self
+            .agent_persistence()
+            .sessions()
+            .get(id)
+            .await
+            .map_err(|err| err.to_string())?;
+        Ok(match stored {
+            Some(session) => (session.model_id, Some(session.agent_type)),
+            None => (None, None),
+        })
+    }
+
+    async fn session_messages(&self, id: i64, limit: i64) -> Result<Vec<Message>, String> {
+        WorkspaceService::new(self.database())
+            .get_session_messages(id, limit, None)
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    async fn list_sessions(&self, cwd: &str) -> Result<Vec<SessionRecord>, String> {
+        WorkspaceService::new(self.database())
+            .list_sessions(cwd)
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    async fn start_task(
+        &self,
+        params: ExecuteTaskParams,
+        channel: Channel<TaskEvent>,
+    ) -> Result<Arc<dyn RunningTask>, String> {
+        let ctx = self
+            .execute_task(params, channel)
+            .await
+            .map_err(|err| err.to_string())?;
+        Ok(ctx)
+    }
+
+    async fn cancel_task(&self, task_id: &str) -> Result<(), String> {
+        TaskExecutor::cancel_task(self, task_id, None)
+            .await
+            .map_err(|err| err.to_string())
+    }
+}
+
+#[async_trait]
+impl RunningTask for TaskContext {
+    fn task_id(&self) -> String {
+        self.task_id.to_string()
+    }
+
+    fn tool_registry(&self) -> Arc<ToolRegistry> {
+        TaskContext::tool_registry(self)
+    }
+
+    async fn resolve_confirmation(
+        &self,
+        request_id: &str,
+        decision: ToolConfirmationDecision,
+    ) -> bool {
+        TaskContext::tool_registry(self)
+            .resolve_confirmation(self, request_id, decision, None)
+            .await
+    }
+}
+
+struct AcpSession {
+    id: i64,
+    cwd: String,
+    model_id: Option<String>,
+    agent: Option<String>,
+    /// Set while a `session/prompt` is running
+    prompting: AtomicBool,
+    task_id: Mutex<Option<String>>,
+}
+
+pub(super) struct Connection {
+    backend: Arc<dyn AgentBackend>,
+    options: Arc<ServerOptions>,
+    authenticated: AtomicBool,
+    outgoing: mpsc::UnboundedSender<Value>,
+    next_request_id: AtomicI64,
+    pending_requests: DashMap<i64, oneshot::Sender<Result<Value, RpcError>>>,
+    sessions: DashMap<String, Arc<AcpSession>>,
+}
+
+/// Serve one client until it closes its end of the stream.
+pub(super) async fn serve_connection<R, W>(
+    executor: Arc<TaskExecutor>,
+    options: Arc<ServerOptions>,
+    reader: R,
+    writer: W,
+) -> std::io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    serve_backend(executor, options, reader, writer).await
+}
+
+async fn serve_backend<R, W>(
+    backend: Arc<dyn AgentBackend>,
+    options: Arc<ServerOptions>,
+    reader: R,
+    writer: W,
+) -> std::io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (outgoing, mut rx) = mpsc::unbounded_channel::<Value>();
+    let writer_task = tokio::spawn(async move {
+        let mut writer = writer;
+        while let Some(message) = rx.recv().await {
+            let mut line = serde_json::to_vec(&message)?;
+            line.push(b'\n');
+            writer.write_all(&line).await?;
+            writer.flush().await?;
+        }
+        Ok::<_, std::io::Error>(())
+    });
+
+    let conn = Arc::new(Connection {
+        backend,
+        authenticated: AtomicBool::new(options.token.is_none()),
+        options,
+        outgoing,
+        next_request_id: AtomicI64::new(1),
+        pending_requests: DashMap::new(),
+        sessions: DashMap::new(),
+    });
+
+    let mut lines = BufReader::new(reader).lines();
+    let result = loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break Ok(()),
+            Err(err) => break Err(err),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let message = serde_json::from_str::<Value>(&line)
+            .and_then(JsonRpcMessage::from_value)
+            .map_err(|err| err.to_string());
+        match message {
+            Ok(JsonRpcMessage::Request(request)) => {
+                let conn = Arc::clone(&conn);
+                tokio::spawn(async move { conn.handle_request(request).await });
+            }
+            Ok(JsonRpcMessage::Notification(notification)) => {
+                conn.handle_notification(notification).await
+            }
+            Ok(JsonRpcMessage::Response(response)) => conn.handle_response(response),
+            Err(err) => conn.send(json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": PARSE_ERROR, "message": format!("Parse error: {err}") },
+            })),
+        }
+    };
+
+    conn.shutdown().await;
+    writer_task.abort();
+    result
+}
+
+fn parse_params<T: DeserializeOwned>(params: Option<Value>) -> Result<T, RpcError> {
+    serde_json::from_value(params.unwrap_or(Value::Null))
+        .map_err(|err| RpcError::invalid_params(err.to_string()))
+}
+
+/// Compare without short-circuiting so the token can't be guessed byte by byte
+fn token_matches(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthenticateParams {
+    method_id: String,
+    #[serde(default)]
+    token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NewSessionParams {
+    cwd: String,
+    /// Client-provided MCP servers are not connected; the servers configured in OpenCodex
+    /// settings are used instead.
+    #[serde(default)]
+    mcp_servers: Vec<Value>,
+    #[serde(default, rename = "_meta")]
+    meta: Option<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LoadSessionParams {
+    session_id: String,
+    cwd: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListSessionsParams {
+    cwd: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptParams {
+    session_id: String,
+    prompt: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelParams {
+    session_id: String,
+}
+
+impl Connection {
+    fn send(&self, message: Value) {
+        // Fails only once the writer has stopped, i.e. the client is gone
+        let _ = self.outgoing.send(message);
+    }
+
+    fn send_serialized(&self, message: impl serde::Serialize) {
+        match serde_json::to_value(message) {
+            Ok(value) => self.send(value),
+            Err(err) => warn!("Failed to serialize agent server message: {}", err),
+        }
+    }
+
+    fn notify(&self, method: &str, params: Value) {
+        self.send_serialized(JsonRpcRequest::new_notification(method, Some(params)));
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending_requests.insert(id, tx);
+        self.send_serialized(JsonRpcRequest::new_request(id, method, Some(params)));
+        rx.await
+            .map_err(|_| RpcError::internal("connection closed"))?
+    }
+
+    fn handle_response(&self, response: JsonRpcResponse) {
+        let JsonRpcId::Number(id) = response.id else {
+            debug!("Ignoring response with non-numeric id");
+            return;
+        };
+        let Some((_, tx)) = self.pending_requests.remove(&id) else {
+            debug!("Ignoring response to unknown request {}", id);
+            return;
+        };
+        let result = match response.error {
+            Some(error) => Err(error.into()),
+            None => Ok(response.result.unwrap_or(Value::Null)),
+        };
+        let _ = tx.send(result);
+    }
+
+    async fn handle_request(self: Arc<Self>, request: JsonRpcRequest) {
+        let Some(id) = request.id else {
+            return;
+        };
+        let response = match self.dispatch(&request.method, request.params).await {
+            Ok(result) => JsonRpcResponse::success(id, result),
+            Err(err) => JsonRpcResponse::error(id, err.code, err.message),
+        };
+        self.send_serialized(response);
+    }
+
+    async fn dispatch(
+        self: &Arc<Self>,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Value, RpcError> {
+        match method {
+            INITIALIZE => return Ok(self.initialize()),
+            AUTHENTICATE => return self.authenticate(parse_params(params)?),
+            _ => {}
+        }
+        if !self.authenticated.load(Ordering::Acquire) {
+            return Err(RpcError::auth_required());
+        }
+
+        match method {
+            SESSION_NEW => self.new_session(parse_params(params)?).await,
+            SESSION_LOAD => self.load_session(parse_params(params)?).await,
+            SESSION_LIST => self.list_sessions(parse_params(params)?).await,
+            SESSION_PROMPT => self.prompt(parse_params(params)?).await,
+            other => Err(RpcError::method_not_found(other)),
+        }
+    }
+
+    async fn handle_notification(&self, notification: JsonRpcNotification) {
+        if !self.authenticated.load(Ordering::Acquire) {
+            return;
+        }
+        match notification.method.as_str() {
+            SESSION_CANCEL => match parse_params::<CancelParams>(notification.params) {
+                Ok(params) => self.cancel(&params.session_id).await,
+                Err(err) => warn!("Invalid session/cancel: {}", err.message),
+            },
+            other => debug!("Ignoring notification {}", other),
+        }
+    }
+
+    fn initialize(&self) -> Value {
+        let auth_methods = if self.options.token.is_some() {
+            json!([{
+                "id": TOKEN_AUTH_METHOD,
+                "name": "Token",
+                "description": "Pass the contents of the server's token file as `token`",
+            }])
+        } else {
+            json!([])
+        };
+        json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "agentCapabilities": {
+                "loadSession": true,
+                "promptCapabilities": { "image": true, "audio": false, "embeddedContext": true },
+            },
+            "authMethods": auth_methods,
+            "agentInfo": { "name": "opencodex", "version": env!("CARGO_PKG_VERSION") },
+        })
+    }
+
+    fn authenticate(&self, params: AuthenticateParams) -> Result<Value, RpcError> {
+        let Some(expected) = self.options.token.as_deref() else {
+            return Ok(json!({}));
+        };
+        if params.method_id != TOKEN_AUTH_METHOD {
+            return Err(RpcError::invalid_params(format!(
+                "unknown auth method '{}'",
+                params.method_id
+            )));
+        }
+        match params.token {
+            Some(token) if token_matches(expected, &token) => {
+                self.authenticated.store(true, Ordering::Release);
+                Ok(json!({}))
+            }
+            _ => Err(RpcError::auth_required()),
+        }
+    }
+
+    fn session(&self, session_id: &str) -> Result<Arc<AcpSession>, RpcError> {
+        self.sessions
+            .get(session_id)
+            .map(|entry| Arc::clone(entry.value()))
+            .ok_or_else(|| RpcError::invalid_params(format!("unknown session '{session_id}'")))
+    }
+
+    async fn new_session(&self, params: NewSessionParams) -> Result<Value, RpcError> {
+        if !params.mcp_servers.is_empty() {
+            debug!(
+                "Ignoring {} client MCP servers; using configured servers",
+                params.mcp_servers.len()
+            );
+        }
+        let record = self
+            .backend
+            .create_session(&params.cwd)
+            .await
+            .map_err(RpcError::internal)?;
+
+        let meta = params
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.get("opencodex"))
+            .cloned()
+            .unwrap_or(Value::Null);
+        let meta_str = |key: &str| meta.get(key).and_then(Value::as_str).map(str::to_string);
+
+        let session_id = record.id.to_string();
+        self.sessions.insert(
+            session_id.clone(),
+            Arc::new(AcpSession {
+                id: record.id,
+                cwd: record.workspace_path,
+                model_id: meta_str("modelId").or_else(|| self.options.model.clone()),
+                agent: meta_str("agent").or_else(|| self.options.agent.clone()),
+                prompting: AtomicBool::new(false),
+                task_id: Mutex::new(None),
+            }),
+        );
+        Ok(json!({ "sessionId": session_id }))
+    }
+
+    async fn load_session(&self, params: LoadSessionParams) -> Result<Value, RpcError> {
+        let id = params
+            .session_id
+            .parse::<i64>()
+            .map_err(|_| RpcError::invalid_params("sessionId must be numeric"))?;
+        let record = self
+            .backend
+            .get_session(id)
+            .await
+            .map_err(RpcError::internal)?
+            .ok_or_else(|| RpcError::invalid_params(format!("session {id} not found")))?;
+        if record.workspace_path != params.cwd {
+            debug!(
+                "session/load cwd '{}' differs from session workspace '{}'",
+                params.cwd, record.workspace_path
+            );
+        }
+
+        let (model_id, agent) = self
+            .backend
+            .session_defaults(id)
+            .await
+            .map_err(RpcError::internal)?;
+
+        let mut messages = self
+            .backend
+            .session_messages(id, HISTORY_REPLAY_LIMIT)
+            .await
+            .map_err(RpcError::internal)?;
+        messages.reverse();
+
+        // Stored tool calls are shown with their raw names; the task's registry isn't built yet
+        let registry = ToolRegistry::default();
+        let mut translator = SessionUpdateTranslator::new();
+        for message in messages.iter().filter(|message| !message.is_internal) {
+            for update in translator.replay(message, &registry).await {
+                self.notify(
+                    SESSION_UPDATE,
+                    json!({ "sessionId": params.session_id, "update": update }),
+                );
+            }
+        }
+
+        self.sessions.insert(
+            params.session_id,
+            Arc::new(AcpSession {
+                id,
+                cwd: record.workspace_path,
+                model_id: model_id.or_else(|| self.options.model.clone()),
+                agent: self.options.agent.clone().or(agent),
+                prompting: AtomicBool::new(false),
+                task_id: Mutex::new(None),
+            }),
+        );
+        Ok(Value::Null)
+    }
+
+    async fn list_sessions(&self, params: ListSessionsParams) -> Result<Value, RpcError> {
+        let sessions = self
+            .backend
+            .list_sessions(&params.cwd)
+            .await
+            .map_err(RpcError::internal)?;
+        let sessions: Vec<Value> = sessions
+            .into_iter()
+            .map(|session| {
+                let updated_at = Utc
+                    .timestamp_opt(session.updated_at, 0)
+                    .single()
+                    .map(|ts| ts.to_rfc3339());
+                json!({
+                    "sessionId": session.id.to_string(),
+                    "cwd": session.workspace_path,
+                    "title": session.title,
+                    "updatedAt": updated_at,
+                })
+            })
+            .collect();
+        Ok(json!({ "sessions": sessions }))
+    }
+
+    async fn prompt(self: &Arc<Self>, params: PromptParams) -> Result<Value, RpcError> {
+        let session = self.session(&params.session_id)?;
+        let (user_prompt, images) = convert_prompt(&params.prompt)?;
+        let model_id = session.model_id.clone().ok_or_else(|| {
+            RpcError::invalid_params(
+                "no model selected; pass _meta.opencodex.modelId to session/new or start the server with --model",
+            )
+        })?;
+        if session.prompting.swap(true, Ordering::AcqRel) {
+            return Err(RpcError::invalid_params(
+                "a prompt is already running in this session",
+            ));
+        }
+
+        let params = ExecuteTaskParams {
+            workspace_path: session.cwd.clone(),
+            session_id: session.id,
+            user_prompt,
+            model_id,
+            agent_type: session.agent.clone(),
+            command_id: None,
+            images: (!images.is_empty()).then_some(images),
+            system_reminders: Vec::new(),
+        };
+        let result = self.run_prompt(&session, params).await;
+
+        session.task_id.lock().take();
+        session.prompting.store(false, Ordering::Release);
+        result
+    }
+
+    async fn run_prompt(
+        self: &Arc<Self>,
+        session: &AcpSession,
+        params: ExecuteTaskParams,
+    ) -> Result<Value, RpcError> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        let channel = Channel::<TaskEvent>::new(move |body| {
+            if let InvokeResponseBody::Json(json) = body {
+                let _ = tx.send(json);
+            }
+            Ok(())
+        });
+
+        let task = self
+            .backend
+            .start_task(params, channel)
+            .await
+            .map_err(RpcError::internal)?;
+        *session.task_id.lock() = Some(task.task_id());
+
+        let session_id = session.id.to_string();
+        let registry = task.tool_registry();
+        let mut translator = SessionUpdateTranslator::new();
+        while let Some(json) = rx.recv().await {
+            let event: TaskEvent = match serde_json::from_str(&json) {
+                Ok(event) => event,
+                Err(err) => {
+                    warn!("Failed to decode task event: {}", err);
+                    continue;
+                }
+            };
+
+            for update in translator.translate(&event, &registry).await {
+                self.notify(
+                    SESSION_UPDATE,
+                    json!({ "sessionId": session_id, "update": update }),
+                );
+            }
+
+            match event {
+                TaskEvent::ToolConfirmationRequested {
+                    request_id,
+                    tool_name,
+                    summary,
+                    form,
+                    ..
+                } => {
+                    let conn = Arc::clone(self);
+                    let task = Arc::clone(&task);
+                    let session_id = session_id.clone();
+                    tokio::spawn(async move {
+                        conn.request_permission(
+                            task.as_ref(),
+                            &session_id,
+                            &request_id,
+                            &tool_name,
+                            &summary,
+                            form.is_some(),
+                        )
+                        .await
+                    });
+                }
+                TaskEvent::TaskCompleted { .. } => return Ok(json!({ "stopReason": "end_turn" })),
+                TaskEvent::TaskCancelled { .. } => return Ok(json!({ "stopReason": "cancelled" })),
+                TaskEvent::TaskBudgetExceeded { .. } => {
+                    return Ok(json!({ "stopReason": "max_tokens" }))
+                }
+                TaskEvent::TaskError { error, .. } => {
+                    return Err(RpcError::internal(error.message))
+                }
+                _ => {}
+            }
+        }
+        Err(RpcError::internal("task ended without a result"))
+    }
+
+    /// Ask the client to approve a tool call. Forms can't be expressed as ACP permission
+    /// options, so they are declined.
+    async fn request_permission(
+        &self,
+        task: &dyn RunningTask,
+        session_id: &str,
+        request_id: &str,
+        tool_name: &str,
+        summary: &str,
+        is_form: bool,
+    ) {
+        let registry = task.tool_registry();
+        let decision = if is_form {
+            ToolConfirmationDecision::Deny
+        } else {
+            let category = registry
+                .get_tool_metadata(tool_name)
+                .await
+                .map(|metadata| metadata.category);
+            let params = json!({
+                "sessionId": session_id,
+                "toolCall": {
+                    "toolCallId": request_id,
+                    "title": summary,
+                    "kind": tool_kind(category),
+                    "status": "pending",
+                },
+                "options": permission_options(),
+            });
+            match self.request(SESSION_REQUEST_PERMISSION, params).await {
+                Ok(result) => permission_decision(&result),
+                Err(err) => {
+                    warn!("Permission request failed: {}", err.message);
+                    ToolConfirmationDecision::Deny
+                }
+            }
+        };
+
+        if !task.resolve_confirmation(request_id, decision).await {
+            warn!("Tool confirmation '{}' was no longer pending", request_id);
+        }
+    }
+
+    async fn cancel(&self, session_id: &str) {
+        let Ok(session) = self.session(session_id) else {
+            return;
+        };
+        let Some(task_id) = session.task_id.lock().clone() else {
+            return;
+        };
+        if let Err(err) = self.backend.cancel_task(&task_id).await {
+            warn!("Failed to cancel task {}: {}", task_id, err);
+        }
+    }
+
+    /// Cancel running prompts and fail outstanding permission requests
+    async fn shutdown(&self) {
+        self.pending_requests.clear();
+        let session_ids: Vec<String> = self.sessions.iter().map(|e| e.key().clone()).collect();
+        for session_id in session_ids {
+            self.cancel(&session_id).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::types::{Block, TextBlock};
+    use tokio::io::{DuplexStream, ReadHalf, WriteHalf};
+
+    /// Answers every prompt with "Hello", or waits for `session/cancel` when `hold` is set
+    #[derive(Default)]
+    struct ScriptedBackend {
+        hold: bool,
+        channel: Mutex<Option<Channel<TaskEvent>>>,
+    }
+
+    struct ScriptedTask;
+
+    #[async_trait]
+    impl RunningTask for ScriptedTask {
+        fn task_id(&self) -> String {
+            "task-1".to_string()
+        }
+
+        fn tool_registry(&self) -> Arc<ToolRegistry> {
+            Arc::new(ToolRegistry::default())
+        }
+
+        async fn resolve_confirmation(&self, _: &str, _: ToolConfirmationDecision) -> bool {
+            false
+        }
+    }
+
+    fn record(id: i64, cwd: &str) -> SessionRecord {
+        SessionRecord {
+            id,
+            workspace_path: cwd.to_string(),
+            parent_id: None,
+            title: None,
+            message_count: 0,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[async_trait]
+    impl AgentBackend for ScriptedBackend {
+        async fn create_session(&self, cwd: &str) -> Result<SessionRecord, String> {
+            Ok(record(7, cwd))
+        }
+
+        async fn get_session(&self, id: i64) -> Result<Option<SessionRecord>, String> {
+            Ok(Some(record(id, "/work")))
+        }
+
+        async fn session_defaults(
+            &self,
+            _: i64,
+        ) -> Result<(Option<String>, Option<String>), String> {
+            Ok((None, None))
+        }
+
+        async fn session_messages(&self, _: i64, _: i64) -> Result<Vec<Message>, String> {
+            Ok(Vec::new())
+        }
+
+        async fn list_sessions(&self, cwd: &str) -> Result<Vec<SessionRecord>, String> {
+            Ok(vec![record(7, cwd)])
+        }
+
+        async fn start_task(
+            &self,
+            _: ExecuteTaskParams,
+            channel: Channel<TaskEvent>,
+        ) -> Result<Arc<dyn RunningTask>, String> {
+            let task_id = "task-1".to_string();
+            channel
+                .send(TaskEvent::BlockAppended {
+                    task_id: task_id.clone(),
+                    message_id: 1,
+                    block: Block::Text(TextBlock {
+                        id: "text-1".to_string(),
+                        content: "Hello".to_string(),
+                        is_streaming: false,
+                    }),
+                })
+                .map_err(|err| err.to_string())?;
+            if self.hold {
+                *self.channel.lock() = Some(channel);
+            } else {
+                channel
+                    .send(TaskEvent::TaskCompleted { task_id })
+                    .map_err(|err| err.to_string())?;
+            }
+            Ok(Arc::new(ScriptedTask))
+        }
+
+        async fn cancel_task(&self, task_id: &str) -> Result<(), String> {
+            let channel = self.channel.lock().take().ok_or("no running task")?;
+            channel
+                .send(TaskEvent::TaskCancelled {
+                    task_id: task_id.to_string(),
+                })
+                .map_err(|err| err.to_string())
+        }
+    }
+
+    struct Client {
+        reader: tokio::io::Lines<BufReader<ReadHalf<DuplexStream>>>,
+        writer: WriteHalf<DuplexStream>,
+        /// Notifications received while waiting for responses
+        notifications: Vec<Value>,
+    }
+
+    impl Client {
+        fn connect(backend: ScriptedBackend, token: Option<&str>) -> Self {
+            let (client, server) = tokio::io::duplex(64 * 1024);
+            let (server_reader, server_writer) = tokio::io::split(server);
+            let options = Arc::new(ServerOptions {
+                model: Some("test-model".to_string()),
+                agent: None,
+                token: token.map(str::to_string),
+            });
+            tokio::spawn(serve_backend(
+                Arc::new(backend),
+                options,
+                server_reader,
+                server_writer,
+            ));
+            let (reader, writer) = tokio::io::split(client);
+            Self {
+                reader: BufReader::new(reader).lines(),
+                writer,
+                notifications: Vec::new(),
+            }
+        }
+
+        async fn send(&mut self, message: Value) {
+            let mut line = serde_json::to_vec(&message).unwrap();
+            line.push(b'\n');
+            self.writer.write_all(&line).await.unwrap();
+        }
+
+        async fn call(&mut self, id: i64, method: &str, params: Value) -> Value {
+            self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+                .await;
+            loop {
+                let line = self
+                    .reader
+                    .next_line()
+                    .await
+                    .unwrap()
+                    .expect("server hung up");
+                let message: Value = serde_json::from_str(&line).unwrap();
+                if message["id"] == json!(id) {
+                    return message;
+                }
+                self.notifications.push(message);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_before_authenticate_are_rejected() {
+        let mut client = Client::connect(ScriptedBackend::default(), Some("secret"));
+
+        let init = client
+            .call(1, INITIALIZE, json!({ "protocolVersion": 1 }))
+            .await;
+        assert_eq!(init["result"]["authMethods"][0]["id"], TOKEN_AUTH_METHOD);
+
+        let denied = client.call(2, SESSION_NEW, json!({ "cwd": "/work" })).await;
+        assert_eq!(denied["error"]["code"], AUTH_REQUIRED);
+
+        let wrong = client
+            .call(
+                3,
+                AUTHENTICATE,
+                json!({ "methodId": "token", "token": "guess" }),
+            )
+            .await;
+        assert_eq!(wrong["error"]["code"], AUTH_REQUIRED);
+
+        let ok = client
+            .call(
+                4,
+                AUTHENTICATE,
+                json!({ "methodId": "token", "token": "secret" }),
+            )
+            .await;
+        assert!(ok.get("error").is_none());
+        let session = client.call(5, SESSION_NEW, json!({ "cwd": "/work" })).await;
+        assert_eq!(session["result"]["sessionId"], "7");
+    }
+
+    #[tokio::test]
+    async fn prompt_streams_session_updates() {
+        let mut client = Client::connect(ScriptedBackend::default(), None);
+        client
+            .call(1, INITIALIZE, json!({ "protocolVersion": 1 }))
+            .await;
+        let session = client.call(2, SESSION_NEW, json!({ "cwd": "/work" })).await;
+        let session_id = session["result"]["sessionId"].clone();
+
+        let prompt =
+            json!({ "sessionId": session_id, "prompt": [{ "type": "text", "text": "Hi" }] });
+        let response = client.call(3, SESSION_PROMPT, prompt).await;
+        assert_eq!(response["result"]["stopReason"], "end_turn");
+
+        let update = client
+            .notifications
+            .iter()
+            .find(|message| message["method"] == SESSION_UPDATE)
+            .expect("no session/update before the prompt finished");
+        assert_eq!(update["params"]["sessionId"], session_id);
+        assert_eq!(
+            update["params"]["update"]["sessionUpdate"],
+            "agent_message_chunk"
+        );
+        assert_eq!(update["params"]["update"]["content"]["text"], "Hello");
+    }
+
+    #[tokio::test]
+    async fn cancel_stops_a_running_prompt() {
+        let backend = ScriptedBackend {
+            hold: true,
+            ..Default::default()
+        };
+        let mut client = Client::connect(backend, None);
+        client
+            .call(1, INITIALIZE, json!({ "protocolVersion": 1 }))
+            .await;
+        let session = client.call(2, SESSION_NEW, json!({ "cwd": "/work" })).await;
+        let session_id = session["result"]["sessionId"].clone();
+
+        let prompt =
+            json!({ "sessionId": session_id, "prompt": [{ "type": "text", "text": "Hi" }] });
+        client
+            .send(json!({ "jsonrpc": "2.0", "id": 3, "method": SESSION_PROMPT, "params": prompt }))
+            .await;
+        // The first update means the task is running and can be cancelled
+        let line = client.reader.next_line().await.unwrap().unwrap();
+        let update: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(update["method"], SESSION_UPDATE);
+
+        client
+            .send(json!({ "jsonrpc": "2.0", "method": SESSION_CANCEL, "params": { "sessionId": session_id } }))
+            .await;
+        let line = client.reader.next_line().await.unwrap().unwrap();
+        let response: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(response["id"], 3);
+        assert_eq!(response["result"]["stopReason"], "cancelled");
+    }
+
+    #[test]
+    fn token_comparison() {
+        assert!(token_matches("abc123", "abc123"));
+        assert!(!token_matches("abc123", "abc124"));
+        assert!(!token_matches("abc123", "abc12"));
+    }
+}
@@ -0,0 +1,178 @@
+//! Local agent server speaking the Agent Client Protocol (ACP)
+//!
+//! Lets editors and scripts drive OpenCodex sessions without the desktop window, using the
+//! same `TaskExecutor`, tools, MCP servers and checkpoints. Two transports carry the same
+//! newline-delimited JSON-RPC:
+//!
+//! - stdio (`opencodex acp`): the editor spawns the process, so no authentication is needed.
+//! - Unix socket (`opencodex serve`): a random token is written next to the socket with
+//!   owner-only permissions and clients must `authenticate` with it before anything else.
+//!
+//! Supported methods: `initialize`, `authenticate`, `session/new`, `session/load`,
+//! `session/list`, `session/prompt` and the `session/cancel` notification. Task progress is
+//! streamed as `session/update` notifications and tool confirmations become
+//! `session/request_permission` requests to the client.
+
+mod connection;
+pub mod protocol;
+
+use std::sync::Arc;
+
+use crate::agent::core::executor::TaskExecutor;
+
+/// Per-server defaults and authentication
+#[derive(Debug, Clone, Default)]
+pub struct ServerOptions {
+    /// Model for sessions that don't pick one via `_meta.opencodex.modelId`
+    pub model: Option<String>,
+    /// Agent for sessions that don't pick one via `_meta.opencodex.agent`
+    pub agent: Option<String>,
+    /// When set, clients must `authenticate` with this token
+    pub token: Option<String>,
+}
+
+/// Serve a single client over stdin/stdout until stdin closes.
+pub async fn serve_stdio(
+    executor: Arc<TaskExecutor>,
+    options: ServerOptions,
+) -> std::io::Result<()> {
+    connection::serve_connection(
+        executor,
+        Arc::new(options),
+        tokio::io::stdin(),
+        tokio::io::stdout(),
+    )
+    .await
+}
+
+#[cfg(unix)]
+pub use unix::{serve_unix_socket, token_path};
+
+#[cfg(unix)]
+mod unix {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use tokio::net::{UnixListener, UnixStream};
+    use tracing::{info, warn};
+
+    use super::{connection, ServerOptions};
+    use crate::agent::core::executor::TaskExecutor;
+
+    /// Where the token for `socket_path` is written: `<socket>.token`
+    pub fn token_path(socket_path: &Path) -> PathBuf {
+        let mut path = socket_path.as_os_str().to_owned();
+        path.push(".token");
+        PathBuf::from(path)
+    }
+
+    /// Removes the socket and token file when the server stops
+    struct SocketFiles {
+        socket: PathBuf,
+        token: PathBuf,
+    }
+
+    impl Drop for SocketFiles {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.socket);
+            let _ = std::fs::remove_file(&self.token);
+        }
+    }
+
+    /// A socket left behind by a crashed server would make bind fail, so remove it, but
+    /// only once a connection attempt shows nothing is listening on it anymore.
+    async fn remove_stale_socket(socket_path: &Path) -> std::io::Result<()> {
+        if !socket_path.exists() {
+            return Ok(());
+        }
+        match UnixStream::connect(socket_path).await {
+            Ok(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                format!(
+                    "An agent server is already running on {}",
+                    socket_path.display()
+                ),
+            )),
+            Err(err) if err.kind() == std::io::ErrorKind::ConnectionRefused => {
+                std::fs::remove_file(socket_path)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Accept clients on `socket_path` until the returned future is dropped.
+    ///
+    /// A fresh token is generated unless `options.token` is already set.
+    pub async fn serve_unix_socket(
+        executor: Arc<TaskExecutor>,
+        mut options: ServerOptions,
+        socket_path: &Path,
+    ) -> std::io::Result<()> {
+        let token = options
+            .token
+            .get_or_insert_with(|| uuid::Uuid::new_v4().simple().to_string())
+            .clone();
+
+        if let Some(parent) = socket_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        remove_stale_socket(socket_path).await?;
+        let listener = UnixListener::bind(socket_path)?;
+
+        let files = SocketFiles {
+            socket: socket_path.to_path_buf(),
+            token: token_path(socket_path),
+        };
+        // `mode` only applies to new files, so never reuse one left behind with wider permissions
+        match std::fs::remove_file(&files.token) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+        let mut token_file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&files.token)?;
+        token_file.write_all(token.as_bytes())?;
+
+        info!("Agent server listening on {}", socket_path.display());
+        let options = Arc::new(options);
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let executor = Arc::clone(&executor);
+            let options = Arc::clone(&options);
+            tokio::spawn(async move {
+                let (reader, writer) = stream.into_split();
+                if let Err(err) =
+                    connection::serve_connection(executor, options, reader, writer).await
+                {
+                    warn!("Agent server connection failed: {}", err);
+                }
+            });
+        }
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_only_stale_sockets_are_removed() {
+            let dir = tempfile::tempdir().unwrap();
+            let socket_path = dir.path().join("agent.sock");
+
+            let listener = UnixListener::bind(&socket_path).unwrap();
+            let err = remove_stale_socket(&socket_path).await.unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+            assert!(socket_path.exists());
+
+            // Dropping a listener leaves its socket file behind, like a crashed server
+            drop(listener);
+            remove_stale_socket(&socket_path).await.unwrap();
+            assert!(!socket_path.exists());
+            remove_stale_socket(&socket_path).await.unwrap();
+        }
+    }
+}
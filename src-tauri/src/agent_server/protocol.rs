@@ -0,0 +1,395 @@
+//! Agent Client Protocol messages: prompt conversion and `TaskEvent` → `session/update`
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::agent::core::executor::ImageAttachment;
+use crate::agent::mcp::protocol::jsonrpc::{
+    JsonRpcErrorObject, INTERNAL_ERROR, INVALID_PARAMS, METHOD_NOT_FOUND,
+};
+use crate::agent::tools::registry::{summarize_tool_call, ToolConfirmationDecision};
+use crate::agent::tools::{ToolCategory, ToolRegistry};
+use crate::agent::types::{Block, Message, MessageRole, TaskEvent, ToolBlock, ToolStatus};
+
+pub const PROTOCOL_VERSION: u64 = 1;
+
+/// ACP: the client must call `authenticate` first
+pub const AUTH_REQUIRED: i64 = -32000;
+
+pub const TOKEN_AUTH_METHOD: &str = "token";
+
+pub const INITIALIZE: &str = "initialize";
+pub const AUTHENTICATE: &str = "authenticate";
+pub const SESSION_NEW: &str = "session/new";
+pub const SESSION_LOAD: &str = "session/load";
+pub const SESSION_LIST: &str = "session/list";
+pub const SESSION_PROMPT: &str = "session/prompt";
+pub const SESSION_CANCEL: &str = "session/cancel";
+pub const SESSION_UPDATE: &str = "session/update";
+pub const SESSION_REQUEST_PERMISSION: &str = "session/request_permission";
+
+pub const PARSE_ERROR: i64 = -32700;
+
+pub const PERMISSION_ALLOW_ONCE: &str = "allow_once";
+pub const PERMISSION_ALLOW_ALWAYS: &str = "allow_always";
+pub const PERMISSION_REJECT_ONCE: &str = "reject_once";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn invalid_params(message: impl Into<String>) -> Self {
+        Self {
+            code: INVALID_PARAMS,
+            message: message.into(),
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self {
+            code: INTERNAL_ERROR,
+            message: message.into(),
+        }
+    }
+
+    pub fn method_not_found(method: &str) -> Self {
+        Self {
+            code: METHOD_NOT_FOUND,
+            message: format!("Method not found: {method}"),
+        }
+    }
+
+    pub fn auth_required() -> Self {
+        Self {
+            code: AUTH_REQUIRED,
+            message: "Authentication required".to_string(),
+        }
+    }
+}
+
+impl From<JsonRpcErrorObject> for RpcError {
+    fn from(err: JsonRpcErrorObject) -> Self {
+        Self {
+            code: err.code,
+            message: err.message,
+        }
+    }
+}
+
+/// Flatten ACP prompt content blocks into a user prompt and image attachments.
+///
+/// Embedded resources are appended as `<file>` blocks, the same shape slash commands use
+/// for `@path` references; resource links are mentioned by URI.
+pub fn convert_prompt(blocks: &[Value]) -> Result<(String, Vec<ImageAttachment>), RpcError> {
+    let mut text = Vec::new();
+    let mut files = Vec::new();
+    let mut images = Vec::new();
+
+    for block in blocks {
+        match block["type"].as_str() {
+            Some("text") => text.push(block["text"].as_str().unwrap_or_default().to_string()),
+            Some("image") => {
+                let (Some(data), Some(mime_type)) =
+                    (block["data"].as_str(), block["mimeType"].as_str())
+                else {
+                    return Err(RpcError::invalid_params(
+                        "image block needs data and mimeType",
+                    ));
+                };
+                images.push(ImageAttachment {
+                    attachment_type: "image".to_string(),
+                    data_url: format!("data:{mime_type};base64,{data}"),
+                    mime_type: mime_type.to_string(),
+                });
+            }
+            Some("resource_link") => {
+                let uri = block["uri"].as_str().unwrap_or_default();
+                text.push(format!("@{}", uri.strip_prefix("file://").unwrap_or(uri)));
+            }
+            Some("resource") => {
+                let resource = &block["resource"];
+                let uri = resource["uri"].as_str().unwrap_or_default();
+                match resource["text"].as_str() {
+                    Some(content) => files.push(format!(
+                        "<file path=\"{}\">\n{}\n</file>",
+                        uri.strip_prefix("file://").unwrap_or(uri),
+                        content.trim_end()
+                    )),
+                    None => text.push(format!("@{uri}")),
+                }
+            }
+            other => {
+                return Err(RpcError::invalid_params(format!(
+                    "unsupported prompt content type: {}",
+                    other.unwrap_or("<missing>")
+                )))
+            }
+        }
+    }
+
+    let mut prompt = text.join("\n");
+    if !files.is_empty() {
+        prompt = format!("{prompt}\n\n{}", files.join("\n\n"));
+    }
+    Ok((prompt, images))
+}
+
+/// Parse the `outcome` of a `session/request_permission` response
+pub fn permission_decision(result: &Value) -> ToolConfirmationDecision {
+    let outcome = &result["outcome"];
+    if outcome["outcome"].as_str() != Some("selected") {
+        return ToolConfirmationDecision::Deny;
+    }
+    match outcome["optionId"].as_str() {
+        Some(PERMISSION_ALLOW_ONCE) => ToolConfirmationDecision::AllowOnce,
+        Some(PERMISSION_ALLOW_ALWAYS) => ToolConfirmationDecision::AllowAlways,
+        _ => ToolConfirmationDecision::Deny,
+    }
+}
+
+pub fn permission_options() -> Value {
+    json!([
+        { "optionId": PERMISSION_ALLOW_ONCE, "name": "Allow", "kind": "allow_once" },
+        { "optionId": PERMISSION_ALLOW_ALWAYS, "name": "Always allow", "kind": "allow_always" },
+        { "optionId": PERMISSION_REJECT_ONCE, "name": "Reject", "kind": "reject_once" },
+    ])
+}
+
+pub fn tool_kind(category: Option<ToolCategory>) -> &'static str {
+    match category {
+        Some(ToolCategory::FileRead) => "read",
+        Some(ToolCategory::FileWrite) => "edit",
+        Some(ToolCategory::Execution | ToolCategory::Terminal) => "execute",
+        Some(ToolCategory::CodeAnalysis | ToolCategory::FileSystem) => "search",
+        Some(ToolCategory::Network) => "fetch",
+        Some(ToolCategory::Delegation) | None => "other",
+    }
+}
+
+fn tool_status(status: &ToolStatus) -> &'static str {
+    match status {
+        ToolStatus::Pending => "pending",
+        ToolStatus::Running => "in_progress",
+        ToolStatus::Completed => "completed",
+        ToolStatus::Cancelled | ToolStatus::Error => "failed",
+    }
+}
+
+/// Turns the block snapshots carried by `TaskEvent`s into incremental `session/update`s.
+#[derive(Default)]
+pub struct SessionUpdateTranslator {
+    /// Bytes of each text/thinking block already sent
+    sent: HashMap<String, usize>,
+    /// Last status sent for each tool call
+    tools: HashMap<String, &'static str>,
+}
+
+impl SessionUpdateTranslator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn translate(&mut self, event: &TaskEvent, registry: &ToolRegistry) -> Vec<Value> {
+        match event {
+            TaskEvent::BlockAppended { block, .. } | TaskEvent::BlockUpdated { block, .. } => {
+                self.translate_block(block, registry).await
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Updates that re-create a stored message, used by `session/load`
+    pub async fn replay(&mut self, message: &Message, registry: &ToolRegistry) -> Vec<Value> {
+        let mut updates = Vec::new();
+        for block in &message.blocks {
+            match (&message.role, block) {
+                (MessageRole::User, Block::UserText(text)) => updates.push(json!({
+                    "sessionUpdate": "user_message_chunk",
+                    "content": { "type": "text", "text": text.content },
+                })),
+                (MessageRole::Assistant, block) => {
+                    updates.extend(self.translate_block(block, registry).await)
+                }
+                _ => {}
+            }
+        }
+        updates
+    }
+
+    async fn translate_block(&mut self, block: &Block, registry: &ToolRegistry) -> Vec<Value> {
+        match block {
+            Block::Text(text) => self
+                .chunk(&text.id, &text.content, "agent_message_chunk")
+                .into_iter()
+                .collect(),
+            Block::Thinking(thinking) => self
+                .chunk(&thinking.id, &thinking.content, "agent_thought_chunk")
+                .into_iter()
+                .collect(),
+            Block::Tool(tool) => self.tool_update(tool, registry).await.into_iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn chunk(&mut self, id: &str, content: &str, kind: &str) -> Option<Value> {
+        let sent = self.sent.entry(id.to_string()).or_insert(0);
+        let delta = content.get(*sent..).filter(|d| !d.is_empty())?;
+        *sent = content.len();
+        Some(json!({
+            "sessionUpdate": kind,
+            "content": { "type": "text", "text": delta },
+        }))
+    }
+
+    async fn tool_update(&mut self, tool: &ToolBlock, registry: &ToolRegistry) -> Option<Value> {
+        let status = tool_status(&tool.status);
+        let previous = self.tools.insert(tool.call_id.clone(), status);
+        if previous == Some(status) {
+            return None;
+        }
+
+        let mut update = if previous.is_none() {
+            let metadata = registry.get_tool_metadata(&tool.name).await;
+            let title = match &metadata {
+                Some(metadata) => summarize_tool_call(&tool.name, metadata, &tool.input),
+                None => tool.name.clone(),
+            };
+            json!({
+                "sessionUpdate": "tool_call",
+                "toolCallId": tool.call_id,
+                "title": title,
+                "kind": tool_kind(metadata.map(|m| m.category)),
+                "status": status,
+                "rawInput": tool.input,
+            })
+        } else {
+            json!({
+                "sessionUpdate": "tool_call_update",
+                "toolCallId": tool.call_id,
+                "status": status,
+            })
+        };
+
+        if let Some(output) = &tool.output {
+            let text = match &output.content {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            update["content"] =
+                json!([{ "type": "content", "content": { "type": "text", "text": text } }]);
+        }
+        Some(update)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::types::{TextBlock, ToolOutput};
+    use chrono::Utc;
+
+    fn updated(block: Block) -> TaskEvent {
+        TaskEvent::BlockUpdated {
+            task_id: "t".to_string(),
+            message_id: 1,
+            block_id: "b".to_string(),
+            block,
+        }
+    }
+
+    fn tool(status: ToolStatus, output: Option<&str>) -> Block {
+        Block::Tool(ToolBlock {
+            id: "block-1".to_string(),
+            call_id: "call-1".to_string(),
+            name: "read_file".to_string(),
+            status,
+            input: json!({ "path": "src/main.rs" }),
+            output: output.map(|text| ToolOutput {
+                content: json!(text),
+                title: None,
+                metadata: None,
+                cancel_reason: None,
+            }),
+            compacted_at: None,
+            started_at: Utc::now(),
+            finished_at: None,
+            duration_ms: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn translates_text_and_tool_blocks() {
+        let registry = ToolRegistry::default();
+        let mut translator = SessionUpdateTranslator::new();
+        let text = |content: &str| {
+            updated(Block::Text(TextBlock {
+                id: "text-1".to_string(),
+                content: content.to_string(),
+                is_streaming: true,
+            }))
+        };
+
+        let first = translator.translate(&text("Hel"), &registry).await;
+        let second = translator.translate(&text("Hello"), &registry).await;
+        let repeat = translator.translate(&text("Hello"), &registry).await;
+        assert_eq!(first[0]["content"]["text"], "Hel");
+        assert_eq!(second[0]["content"]["text"], "lo");
+        assert!(repeat.is_empty());
+
+        let started = translator
+            .translate(&updated(tool(ToolStatus::Running, None)), &registry)
+            .await;
+        assert_eq!(started[0]["sessionUpdate"], "tool_call");
+        assert_eq!(started[0]["toolCallId"], "call-1");
+        assert_eq!(started[0]["status"], "in_progress");
+
+        let finished = translator
+            .translate(
+                &updated(tool(ToolStatus::Completed, Some("fn main() {}"))),
+                &registry,
+            )
+            .await;
+        assert_eq!(finished[0]["sessionUpdate"], "tool_call_update");
+        assert_eq!(finished[0]["content"][0]["content"]["text"], "fn main() {}");
+    }
+
+    #[test]
+    fn converts_prompt_blocks() {
+        let (prompt, images) = convert_prompt(&[
+            json!({ "type": "text", "text": "Explain this" }),
+            json!({ "type": "resource_link", "uri": "file:///repo/a.rs", "name": "a.rs" }),
+            json!({ "type": "resource", "resource": { "uri": "file:///repo/b.rs", "text": "fn b() {}\n" } }),
+            json!({ "type": "image", "data": "AAAA", "mimeType": "image/png" }),
+        ])
+        .unwrap();
+        assert_eq!(
+            prompt,
+            "Explain this\n@/repo/a.rs\n\n<file path=\"/repo/b.rs\">\nfn b() {}\n</file>"
+        );
+        assert_eq!(images[0].data_url, "data:image/png;base64,AAAA");
+
+        assert!(convert_prompt(&[json!({ "type": "audio" })]).is_err());
+    }
+
+    #[test]
+    fn maps_permission_outcomes() {
+        let selected = |id: &str| json!({ "outcome": { "outcome": "selected", "optionId": id } });
+        assert_eq!(
+            permission_decision(&selected(PERMISSION_ALLOW_ALWAYS)),
+            ToolConfirmationDecision::AllowAlways
+        );
+        assert_eq!(
+            permission_decision(&selected(PERMISSION_REJECT_ONCE)),
+            ToolConfirmationDecision::Deny
+        );
+        assert_eq!(
+            permission_decision(&json!({ "outcome": { "outcome": "cancelled" } })),
+            ToolConfirmationDecision::Deny
+        );
+    }
+}
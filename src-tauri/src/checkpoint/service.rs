@@ -138,6 +138,28 @@ impl CheckpointService {
         Ok(())
     }
 
+    /// Record original content of a file edited outside a task in the session's latest
+    /// checkpoint, so rolling back to it restores the file too. Before the session's first
+    /// checkpoint there is nothing to roll back to and nothing is recorded.
+    pub async fn snapshot_file_for_session(
+        &self,
+        session_id: i64,
+        file_path: &Path,
+        workspace_path: &Path,
+    ) -> CheckpointResult<()> {
+        let workspace_root = canonicalize_workspace(workspace_path).await?;
+        let workspace_key = workspace_root.to_string_lossy().to_string();
+        let Some(latest) = self
+            .storage
+            .find_latest_by_session(session_id, &workspace_key)
+            .await?
+        else {
+            return Ok(());
+        };
+        self.snapshot_file_before_edit(latest.id, file_path, &workspace_root)
+            .await
+    }
+
     /// Get checkpoint
     pub async fn get(&self, id: i64) -> CheckpointResult<Option<Checkpoint>> {
         self.storage.find_by_id(id).await
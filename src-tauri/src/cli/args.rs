@@ -0,0 +1,343 @@
+//! `opencodex run` argument parsing
+
+use std::path::PathBuf;
+
+use crate::agent::tools::registry::ToolConfirmationDecision;
+use crate::agent::tools::ToolCategory;
+
+use super::error::{CliError, CliResult};
+
+pub const RUN_USAGE: &str = "\
+Usage: opencodex run [OPTIONS] --model <ID> [PROMPT]...
+
+Run an agent task in the given workspace without opening the desktop window.
+The prompt is read from stdin when none is given on the command line.
+
+Options:
+  -w, --workspace <DIR>    Workspace directory [default: .]
+  -a, --agent <NAME>       Agent to run (e.g. coder, plan)
+  -m, --model <ID>         Model id as configured in the app
+  -s, --session <ID>       Continue an existing session instead of starting a new one
+  -f, --format <FORMAT>    Output format: text | json [default: text]
+      --approve <POLICY>   Tool confirmations: never | auto-edits | all [default: never]
+  -h, --help               Print help
+
+Exit status: 0 completed, 1 failed, 2 usage error, 3 budget exceeded, 130 cancelled.";
+
+pub const ACP_USAGE: &str = "\
+Usage: opencodex acp [OPTIONS]
+
+Speak the Agent Client Protocol over stdin/stdout, for editors that launch the agent
+as a subprocess (Zed, Neovim).
+
+Options:
+  -m, --model <ID>         Default model for new sessions
+  -a, --agent <NAME>       Default agent for new sessions
+  -h, --help               Print help";
+
+pub const SERVE_USAGE: &str = "\
+Usage: opencodex serve [OPTIONS]
+
+Serve the Agent Client Protocol on a Unix socket. Clients authenticate with the token
+written to <SOCKET>.token (readable only by the current user).
+
+Options:
+      --socket <PATH>      Socket path [default: <data dir>/agent.sock]
+  -m, --model <ID>         Default model for new sessions
+  -a, --agent <NAME>       Default agent for new sessions
+  -h, --help               Print help";
+
+/// How `TaskEvent`s are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Assistant text on stdout, tool activity and status on stderr
+    Text,
+    /// One serialized `TaskEvent` per line
+    Json,
+}
+
+/// How tool confirmation requests are answered, since nobody is there to click
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalPolicy {
+    Never,
+    AutoEdits,
+    All,
+}
+
+impl ApprovalPolicy {
+    /// Settings `deny` rules are applied before this is ever asked. Forms can't be
+    /// filled in non-interactively, so they are always declined.
+    pub fn decide(self, category: Option<ToolCategory>, is_form: bool) -> ToolConfirmationDecision {
+        let allow = !is_form
+            && match self {
+                Self::Never => false,
+                Self::AutoEdits => category == Some(ToolCategory::FileWrite),
+                Self::All => true,
+            };
+        if allow {
+            ToolConfirmationDecision::AllowOnce
+        } else {
+            ToolConfirmationDecision::Deny
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunArgs {
+    pub workspace: PathBuf,
+    pub agent: Option<String>,
+    pub model: String,
+    pub session_id: Option<i64>,
+    pub format: OutputFormat,
+    pub approve: ApprovalPolicy,
+    /// `None` means read the prompt from stdin
+    pub prompt: Option<String>,
+}
+
+/// Parsed command line; `Help` is returned for `-h/--help`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunCommand {
+    Run(RunArgs),
+    Help,
+}
+
+/// Arguments shared by `acp` and `serve`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerArgs {
+    pub model: Option<String>,
+    pub agent: Option<String>,
+    /// Only accepted by `serve`
+    pub socket: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerCommand {
+    Serve(ServerArgs),
+    Help,
+}
+
+/// Parse the arguments following `acp` or `serve`
+pub fn parse_server_args<I, S>(args: I, allow_socket: bool) -> CliResult<ServerCommand>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut args = args.into_iter().map(Into::into);
+    let mut parsed = ServerArgs::default();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| CliError::Usage(format!("{flag} requires a value")))
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(ServerCommand::Help),
+            "-m" | "--model" => parsed.model = Some(value()?),
+            "-a" | "--agent" => parsed.agent = Some(value()?),
+            "--socket" if allow_socket => parsed.socket = Some(PathBuf::from(value()?)),
+            other => return Err(CliError::Usage(format!("unexpected argument '{other}'"))),
+        }
+    }
+
+    Ok(ServerCommand::Serve(parsed))
+}
+
+/// Parse the arguments following `run`
+pub fn parse_run_args<I, S>(args: I) -> CliResult<RunCommand>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut args = args.into_iter().map(Into::into);
+    let mut workspace = PathBuf::from(".");
+    let mut agent = None;
+    let mut model = None;
+    let mut session_id = None;
+    let mut format = OutputFormat::Text;
+    let mut approve = ApprovalPolicy::Never;
+    let mut prompt_parts: Vec<String> = Vec::new();
+
+    while let Some(arg) = args.next() {
+        if !prompt_parts.is_empty() || !arg.starts_with('-') || arg == "-" {
+            prompt_parts.push(arg);
+            continue;
+        }
+        if arg == "--" {
+            prompt_parts.extend(args.by_ref());
+            break;
+        }
+
+        // Accept both `--flag value` and `--flag=value`
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| CliError::Usage(format!("{flag} requires a value")))
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(RunCommand::Help),
+            "-w" | "--workspace" => workspace = PathBuf::from(value()?),
+            "-a" | "--agent" => agent = Some(value()?),
+            "-m" | "--model" => model = Some(value()?),
+            "-s" | "--session" => {
+                let raw = value()?;
+                let id = raw
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|id| *id > 0)
+                    .ok_or_else(|| CliError::Usage(format!("invalid session id '{raw}'")))?;
+                session_id = Some(id);
+            }
+            "-f" | "--format" => {
+                format = match value()?.as_str() {
+                    "text" => OutputFormat::Text,
+                    "json" => OutputFormat::Json,
+                    other => {
+                        return Err(CliError::Usage(format!(
+                            "unknown format '{other}', expected text or json"
+                        )))
+                    }
+                }
+            }
+            "--approve" => {
+                approve = match value()?.as_str() {
+                    "never" => ApprovalPolicy::Never,
+                    "auto-edits" => ApprovalPolicy::AutoEdits,
+                    "all" => ApprovalPolicy::All,
+                    other => {
+                        return Err(CliError::Usage(format!(
+                            "unknown approval policy '{other}', expected never, auto-edits or all"
+                        )))
+                    }
+                }
+            }
+            other => return Err(CliError::Usage(format!("unknown option '{other}'"))),
+        }
+    }
+
+    let model = model
+        .filter(|m| !m.trim().is_empty())
+        .ok_or_else(|| CliError::Usage("--model is required".to_string()))?;
+
+    let prompt = match prompt_parts.as_slice() {
+        [] => None,
+        [only] if only == "-" => None,
+        parts => Some(parts.join(" ")),
+    };
+
+    Ok(RunCommand::Run(RunArgs {
+        workspace,
+        agent,
+        model,
+        session_id,
+        format,
+        approve,
+        prompt,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> CliResult<RunCommand> {
+        parse_run_args(args.iter().copied())
+    }
+
+    #[test]
+    fn parses_flags_and_prompt() {
+        let RunCommand::Run(args) = parse(&[
+            "--workspace",
+            "/repo",
+            "-a",
+            "plan",
+            "--model=gpt-5",
+            "--format",
+            "json",
+            "--approve=auto-edits",
+            "fix",
+            "the",
+            "--flaky",
+            "test",
+        ])
+        .unwrap() else {
+            panic!("expected run");
+        };
+        assert_eq!(args.workspace, PathBuf::from("/repo"));
+        assert_eq!(args.agent.as_deref(), Some("plan"));
+        assert_eq!(args.model, "gpt-5");
+        assert_eq!(args.format, OutputFormat::Json);
+        assert_eq!(args.approve, ApprovalPolicy::AutoEdits);
+        assert_eq!(args.prompt.as_deref(), Some("fix the --flaky test"));
+
+        let RunCommand::Run(stdin) = parse(&["-m", "gpt-5"]).unwrap() else {
+            panic!("expected run");
+        };
+        assert_eq!(stdin.prompt, None);
+        assert_eq!(stdin.approve, ApprovalPolicy::Never);
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(matches!(parse(&["hello"]), Err(CliError::Usage(_))));
+        assert!(matches!(
+            parse(&["-m", "x", "--approve", "yes"]),
+            Err(CliError::Usage(_))
+        ));
+        assert!(matches!(
+            parse(&["-m", "x", "--session", "0"]),
+            Err(CliError::Usage(_))
+        ));
+        assert!(matches!(parse(&["-m"]), Err(CliError::Usage(_))));
+        assert_eq!(parse(&["--help"]).unwrap(), RunCommand::Help);
+    }
+
+    #[test]
+    fn parses_server_args() {
+        let ServerCommand::Serve(args) =
+            parse_server_args(["--socket=/tmp/a.sock", "-m", "gpt-5"], true).unwrap()
+        else {
+            panic!("expected serve");
+        };
+        assert_eq!(args.socket, Some(PathBuf::from("/tmp/a.sock")));
+        assert_eq!(args.model.as_deref(), Some("gpt-5"));
+
+        assert!(matches!(
+            parse_server_args(["--socket", "/tmp/a.sock"], false),
+            Err(CliError::Usage(_))
+        ));
+        assert_eq!(
+            parse_server_args(["-h"], false).unwrap(),
+            ServerCommand::Help
+        );
+    }
+
+    #[test]
+    fn approval_policy_decisions() {
+        use ToolConfirmationDecision::{AllowOnce, Deny};
+        let write = Some(ToolCategory::FileWrite);
+        let exec = Some(ToolCategory::Execution);
+
+        assert_eq!(ApprovalPolicy::Never.decide(write, false), Deny);
+        assert_eq!(ApprovalPolicy::AutoEdits.decide(write, false), AllowOnce);
+        assert_eq!(ApprovalPolicy::AutoEdits.decide(exec, false), Deny);
+        assert_eq!(ApprovalPolicy::All.decide(exec, false), AllowOnce);
+        assert_eq!(ApprovalPolicy::All.decide(None, true), Deny);
+    }
+}
@@ -0,0 +1,18 @@
+use thiserror::Error;
+
+use crate::agent::error::TaskExecutorError;
+use crate::setup::SetupError;
+
+pub type CliResult<T> = Result<T, CliError>;
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("{0}")]
+    Usage(String),
+    #[error(transparent)]
+    Setup(#[from] SetupError),
+    #[error(transparent)]
+    Task(#[from] TaskExecutorError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
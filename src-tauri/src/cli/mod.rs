@@ -0,0 +1,400 @@
+//! Headless command line entry points: `opencodex run`, `opencodex acp` and `opencodex serve`
+//!
+//! `run` executes a single agent task with the same `TaskExecutor`, tool registry, settings
+//! permissions and database as the desktop app, without creating a window. `TaskEvent`s are
+//! received through a plain `Channel` callback and written to stdout; tool confirmations are
+//! answered by the `--approve` policy.
+//!
+//! `acp` and `serve` expose the same executor to editors through `crate::agent_server`.
+//!
+//! Note: Windows release builds use the GUI subsystem, so stdout is only visible when it is
+//! redirected to a file or pipe.
+
+mod args;
+mod error;
+mod output;
+
+pub use args::{
+    parse_run_args, parse_server_args, ApprovalPolicy, OutputFormat, RunArgs, RunCommand,
+    ServerArgs, ServerCommand, ACP_USAGE, RUN_USAGE, SERVE_USAGE,
+};
+pub use error::{CliError, CliResult};
+
+use std::future::Future;
+use std::io::{IsTerminal, Read, Write};
+use std::sync::Arc;
+
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tokio::sync::mpsc;
+use tracing::warn;
+use tracing_subscriber::EnvFilter;
+
+use crate::agent::core::context::TaskContext;
+use crate::agent::core::executor::{ExecuteTaskParams, TaskExecutor, TaskExecutorServices};
+use crate::agent::tools::registry::ToolConfirmationDecision;
+use crate::agent::types::TaskEvent;
+use crate::agent_server::{self, ServerOptions};
+use crate::setup::resolve_app_data_dir;
+use output::TextRenderer;
+
+const EXIT_COMPLETED: i32 = 0;
+const EXIT_FAILED: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_BUDGET_EXCEEDED: i32 = 3;
+const EXIT_CANCELLED: i32 = 130;
+
+/// Run the subcommand `args` (without the program name) starts with and return its exit
+/// code, or `None` when the desktop app should start instead.
+pub fn dispatch(args: &[String]) -> Option<i32> {
+    let (command, rest) = args.split_first()?;
+    match command.as_str() {
+        "run" => Some(run(rest.to_vec())),
+        "acp" => Some(acp(rest.to_vec())),
+        "serve" => Some(serve(rest.to_vec())),
+        _ => None,
+    }
+}
+
+/// Run the `run` subcommand with the arguments that follow it; returns the exit code.
+pub fn run(args: Vec<String>) -> i32 {
+    let args = match parse_run_args(args) {
+        Ok(RunCommand::Run(args)) => args,
+        Ok(RunCommand::Help) => {
+            println!("{RUN_USAGE}");
+            return EXIT_COMPLETED;
+        }
+        Err(err) => {
+            eprintln!("error: {err}\n\n{RUN_USAGE}");
+            return EXIT_USAGE;
+        }
+    };
+
+    init_cli_logging();
+    block_on(run_task(args))
+}
+
+/// Serve the Agent Client Protocol over stdio; returns the exit code.
+pub fn acp(args: Vec<String>) -> i32 {
+    let args = match parse_server_args(args, false) {
+        Ok(ServerCommand::Serve(args)) => args,
+        Ok(ServerCommand::Help) => {
+            println!("{ACP_USAGE}");
+            return EXIT_COMPLETED;
+        }
+        Err(err) => {
+            eprintln!("error: {err}\n\n{ACP_USAGE}");
+            return EXIT_USAGE;
+        }
+    };
+
+    init_cli_logging();
+    block_on(async move {
+        let executor = build_executor().await?;
+        let options = ServerOptions {
+            model: args.model,
+            agent: args.agent,
+            token: None,
+        };
+        agent_server::serve_stdio(executor, options).await?;
+        Ok(EXIT_COMPLETED)
+    })
+}
+
+/// Serve the Agent Client Protocol on a Unix socket until interrupted; returns the exit code.
+pub fn serve(args: Vec<String>) -> i32 {
+    let args = match parse_server_args(args, true) {
+        Ok(ServerCommand::Serve(args)) => args,
+        Ok(ServerCommand::Help) => {
+            println!("{SERVE_USAGE}");
+            return EXIT_COMPLETED;
+        }
+        Err(err) => {
+            eprintln!("error: {err}\n\n{SERVE_USAGE}");
+            return EXIT_USAGE;
+        }
+    };
+
+    init_cli_logging();
+    block_on(serve_socket(args))
+}
+
+fn block_on(future: impl Future<Output = CliResult<i32>>) -> i32 {
+    let runtime = match tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            eprintln!("error: failed to start async runtime: {err}");
+            return EXIT_FAILED;
+        }
+    };
+
+    match runtime.block_on(future) {
+        Ok(code) => code,
+        Err(CliError::Usage(message)) => {
+            eprintln!("error: {message}");
+            EXIT_USAGE
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            EXIT_FAILED
+        }
+    }
+}
+
+/// Logs go to stderr so stdout stays clean for the task output.
+fn init_cli_logging() {
+    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn"));
+    if let Err(err) = tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .try_init()
+    {
+        eprintln!("Log system initialization failed: {err}");
+    }
+}
+
+async fn run_task(args: RunArgs) -> CliResult<i32> {
+    let workspace = args.workspace.canonicalize().map_err(|err| {
+        CliError::Usage(format!(
+            "workspace '{}' is not accessible: {err}",
+            args.workspace.display()
+        ))
+    })?;
+    let prompt = match args.prompt.clone() {
+        Some(prompt) => prompt,
+        None => read_prompt_from_stdin()?,
+    };
+    if prompt.trim().is_empty() {
+        return Err(CliError::Usage("prompt is empty".to_string()));
+    }
+
+    let executor = build_executor().await?;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    let channel = Channel::<TaskEvent>::new(move |body| {
+        if let InvokeResponseBody::Json(json) = body {
+            // The receiver only goes away once the process is exiting
+            let _ = tx.send(json);
+        }
+        Ok(())
+    });
+
+    let params = ExecuteTaskParams {
+        workspace_path: workspace.to_string_lossy().to_string(),
+        session_id: args.session_id.unwrap_or(0),
+        user_prompt: prompt,
+        model_id: args.model.clone(),
+        agent_type: args.agent.clone(),
+        command_id: None,
+        images: None,
+        system_reminders: Vec::new(),
+    };
+    let ctx = executor.execute_task(params, channel).await?;
+
+    let mut renderer = TextRenderer::new();
+    let mut cancel_requested = false;
+    let code = loop {
+        let json = tokio::select! {
+            json = rx.recv() => match json {
+                Some(json) => json,
+                None => break EXIT_FAILED,
+            },
+            _ = tokio::signal::ctrl_c() => {
+                if cancel_requested {
+                    break EXIT_CANCELLED;
+                }
+                cancel_requested = true;
+                eprintln!("cancelling... (press Ctrl-C again to exit immediately)");
+                if let Err(err) = executor.cancel_task(&ctx.task_id, None).await {
+                    warn!("Failed to cancel task: {}", err);
+                    break EXIT_CANCELLED;
+                }
+                continue;
+            }
+        };
+
+        let event: TaskEvent = match serde_json::from_str(&json) {
+            Ok(event) => event,
+            Err(err) => {
+                warn!("Failed to decode task event: {}", err);
+                continue;
+            }
+        };
+
+        match args.format {
+            OutputFormat::Json => {
+                let mut stdout = std::io::stdout().lock();
+                writeln!(stdout, "{json}")?;
+                stdout.flush()?;
+            }
+            OutputFormat::Text => {
+                renderer.render(&event, &mut std::io::stdout(), &mut std::io::stderr())?
+            }
+        }
+
+        match event {
+            TaskEvent::ToolConfirmationRequested {
+                request_id,
+                tool_name,
+                summary,
+                form,
+                ..
+            } => {
+                answer_confirmation(
+                    &ctx,
+                    &args,
+                    &request_id,
+                    &tool_name,
+                    &summary,
+                    form.is_some(),
+                )
+                .await
+            }
+            TaskEvent::TaskCompleted { .. } => break EXIT_COMPLETED,
+            TaskEvent::TaskError { .. } => break EXIT_FAILED,
+            TaskEvent::TaskCancelled { .. } => break EXIT_CANCELLED,
+            TaskEvent::TaskBudgetExceeded { .. } => break EXIT_BUDGET_EXCEEDED,
+            _ => {}
+        }
+    };
+
+    Ok(code)
+}
+
+async fn answer_confirmation(
+    ctx: &Arc<TaskContext>,
+    args: &RunArgs,
+    request_id: &str,
+    tool_name: &str,
+    summary: &str,
+    is_form: bool,
+) {
+    let registry = ctx.tool_registry();
+    let category = registry
+        .get_tool_metadata(tool_name)
+        .await
+        .map(|metadata| metadata.category);
+    let decision = args.approve.decide(category, is_form);
+
+    if args.format == OutputFormat::Text {
+        let verdict = match decision {
+            ToolConfirmationDecision::Deny => "denied",
+            _ => "approved",
+        };
+        eprintln!("{verdict}: {summary}");
+    }
+    if !registry
+        .resolve_confirmation(ctx, request_id, decision, None)
+        .await
+    {
+        warn!("Tool confirmation '{}' was no longer pending", request_id);
+    }
+}
+
+#[cfg(unix)]
+async fn serve_socket(args: ServerArgs) -> CliResult<i32> {
+    let socket = match args.socket {
+        Some(socket) => socket,
+        None => resolve_app_data_dir()?.join("agent.sock"),
+    };
+    let executor = build_executor().await?;
+    let options = ServerOptions {
+        model: args.model,
+        agent: args.agent,
+        token: None,
+    };
+
+    eprintln!(
+        "serving on {} (token in {})",
+        socket.display(),
+        agent_server::token_path(&socket).display()
+    );
+    tokio::select! {
+        result = agent_server::serve_unix_socket(executor, options, &socket) => result?,
+        _ = tokio::signal::ctrl_c() => {}
+    }
+    Ok(EXIT_COMPLETED)
+}
+
+#[cfg(not(unix))]
+async fn serve_socket(_args: ServerArgs) -> CliResult<i32> {
+    Err(CliError::Usage(
+        "serve needs Unix domain sockets; use `opencodex acp` over stdio instead".to_string(),
+    ))
+}
+
+fn read_prompt_from_stdin() -> CliResult<String> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Err(CliError::Usage(
+            "no prompt given; pass it as an argument or pipe it on stdin".to_string(),
+        ));
+    }
+    let mut prompt = String::new();
+    stdin.read_to_string(&mut prompt)?;
+    Ok(prompt)
+}
+
+/// Build the executor and the services it needs, without a Tauri app.
+async fn build_executor() -> CliResult<Arc<TaskExecutor>> {
+    use crate::agent::mcp::{oauth::McpOAuth, McpRegistry};
+    use crate::agent::persistence::AgentPersistence;
+    use crate::agent::workspace_changes::WorkspaceChangeJournal;
+    use crate::checkpoint::{BlobStore, CheckpointConfig, CheckpointService, CheckpointStorage};
+    use crate::llm::oauth::OAuthManager;
+    use crate::lsp::LspManager;
+    use crate::settings::SettingsManager;
+    use crate::setup::SetupError;
+    use crate::storage::{DatabaseManager, DatabaseOptions, StoragePaths, UnifiedCache};
+
+    let paths = StoragePaths::new(resolve_app_data_dir()?).map_err(SetupError::from)?;
+    let database = DatabaseManager::new(paths, DatabaseOptions::default())
+        .await
+        .map_err(SetupError::from)?;
+    database.initialize().await.map_err(SetupError::from)?;
+    let database = Arc::new(database);
+
+    let settings_manager = Arc::new(SettingsManager::new().map_err(SetupError::from)?);
+    let oauth_manager = Arc::new(OAuthManager::new(Arc::clone(&database)));
+    let mcp_registry = Arc::new(McpRegistry::new(Arc::new(McpOAuth::new(
+        Arc::clone(&database),
+        oauth_manager,
+    ))));
+
+    let checkpoint_service = {
+        let pool = database.pool().clone();
+        let config = CheckpointConfig::default();
+        let storage = Arc::new(CheckpointStorage::new(pool.clone()));
+        let blob_store = Arc::new(BlobStore::new(pool, config.clone()));
+        Arc::new(CheckpointService::with_config(storage, blob_store, config))
+    };
+
+    let vector_search_engine =
+        match crate::vector_db::build_search_engine(Arc::clone(&database), &settings_manager).await
+        {
+            Ok(engine) => Some(engine),
+            Err(err) => {
+                warn!("Semantic search unavailable: {}", err);
+                None
+            }
+        };
+
+    let lsp_manager = Arc::new(LspManager::with_settings(Arc::clone(&settings_manager)));
+
+    Ok(Arc::new(TaskExecutor::with_checkpoint_service(
+        TaskExecutorServices {
+            agent_persistence: Arc::new(AgentPersistence::new(Arc::clone(&database))),
+            database,
+            cache: Arc::new(UnifiedCache::new()),
+            settings_manager,
+            mcp_registry,
+            lsp_manager,
+            checkpoint_service: Some(checkpoint_service),
+            workspace_changes: Arc::new(WorkspaceChangeJournal::new()),
+            vector_search_engine,
+        },
+    )))
+}
@@ -0,0 +1,203 @@
+//! Human-readable rendering of `TaskEvent`s for `--format text`
+//!
+//! Assistant text goes to stdout so it can be redirected on its own; tool activity, errors
+//! and usage go to stderr.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use serde_json::Value;
+
+use crate::agent::common::truncate_chars;
+use crate::agent::types::{Block, TaskEvent, ToolBlock, ToolStatus};
+
+const MAX_TOOL_ARG_CHARS: usize = 80;
+
+#[derive(Default)]
+pub struct TextRenderer {
+    /// Bytes of each text block already written; blocks are re-sent whole on every update
+    printed: HashMap<String, usize>,
+    /// Last reported status of each tool block
+    tool_status: HashMap<String, &'static str>,
+    /// Whether stdout currently ends mid-line
+    open_line: bool,
+}
+
+impl TextRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn render(
+        &mut self,
+        event: &TaskEvent,
+        out: &mut dyn Write,
+        err: &mut dyn Write,
+    ) -> io::Result<()> {
+        match event {
+            TaskEvent::BlockAppended { block, .. } | TaskEvent::BlockUpdated { block, .. } => {
+                self.render_block(block, out, err)?
+            }
+            TaskEvent::MessageFinished {
+                token_usage: Some(usage),
+                ..
+            } => {
+                self.end_line(out)?;
+                writeln!(
+                    err,
+                    "tokens: {} in, {} out",
+                    usage.input_tokens, usage.output_tokens
+                )?;
+            }
+            TaskEvent::TaskRetrying {
+                attempt,
+                max_attempts,
+                error_message,
+                retry_in_ms,
+                ..
+            } => writeln!(
+                err,
+                "retrying ({attempt}/{max_attempts}) in {}s: {error_message}",
+                retry_in_ms / 1000
+            )?,
+            TaskEvent::TaskError { error, .. } => {
+                self.end_line(out)?;
+                writeln!(err, "error [{}]: {}", error.code, error.message)?;
+            }
+            TaskEvent::TaskCancelled { .. } => {
+                self.end_line(out)?;
+                writeln!(err, "cancelled")?;
+            }
+            TaskEvent::TaskBudgetExceeded {
+                used_tokens,
+                used_cost,
+                ..
+            } => {
+                self.end_line(out)?;
+                writeln!(
+                    err,
+                    "stopped: run budget exceeded ({used_tokens} tokens, ${used_cost:.2})"
+                )?;
+            }
+            TaskEvent::TaskCompleted { .. } => self.end_line(out)?,
+            _ => {}
+        }
+        out.flush()
+    }
+
+    fn render_block(
+        &mut self,
+        block: &Block,
+        out: &mut dyn Write,
+        err: &mut dyn Write,
+    ) -> io::Result<()> {
+        match block {
+            Block::Text(text) => {
+                let printed = self.printed.entry(text.id.clone()).or_insert(0);
+                if let Some(delta) = text.content.get(*printed..) {
+                    if !delta.is_empty() {
+                        out.write_all(delta.as_bytes())?;
+                        self.open_line = !delta.ends_with('\n');
+                    }
+                    *printed = text.content.len();
+                }
+                if !text.is_streaming {
+                    self.end_line(out)?;
+                }
+            }
+            Block::Tool(tool) => self.render_tool(tool, out, err)?,
+            Block::Subtask(subtask) => {
+                writeln!(
+                    err,
+                    "subtask [{}] {}: {:?}",
+                    subtask.agent_type, subtask.description, subtask.status
+                )?;
+            }
+            Block::Error(error) => {
+                self.end_line(out)?;
+                writeln!(err, "error [{}]: {}", error.code, error.message)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn render_tool(
+        &mut self,
+        tool: &ToolBlock,
+        out: &mut dyn Write,
+        err: &mut dyn Write,
+    ) -> io::Result<()> {
+        let status = match tool.status {
+            ToolStatus::Pending | ToolStatus::Running => "running",
+            ToolStatus::Completed => "done",
+            ToolStatus::Cancelled => "cancelled",
+            ToolStatus::Error => "failed",
+        };
+        if self.tool_status.insert(tool.id.clone(), status) == Some(status) {
+            return Ok(());
+        }
+
+        self.end_line(out)?;
+        let arg = tool_arg_summary(&tool.input);
+        match (status, tool.duration_ms) {
+            ("running", _) => writeln!(err, "> {} {}", tool.name, arg),
+            (_, Some(ms)) => writeln!(err, "  {} {} ({}ms)", tool.name, status, ms),
+            _ => writeln!(err, "  {} {}", tool.name, status),
+        }
+    }
+
+    fn end_line(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        if self.open_line {
+            writeln!(out)?;
+            self.open_line = false;
+        }
+        Ok(())
+    }
+}
+
+/// The argument that best identifies a tool call, e.g. the path or command
+fn tool_arg_summary(input: &Value) -> String {
+    const KEYS: &[&str] = &["path", "file_path", "command", "pattern", "query", "url"];
+    KEYS.iter()
+        .find_map(|key| input.get(*key).and_then(Value::as_str))
+        .map(|arg| truncate_chars(arg, MAX_TOOL_ARG_CHARS))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::types::TextBlock;
+
+    fn text_event(content: &str, is_streaming: bool) -> TaskEvent {
+        TaskEvent::BlockUpdated {
+            task_id: "t".to_string(),
+            message_id: 1,
+            block_id: "b".to_string(),
+            block: Block::Text(TextBlock {
+                id: "b".to_string(),
+                content: content.to_string(),
+                is_streaming,
+            }),
+        }
+    }
+
+    #[test]
+    fn streams_text_deltas_once() {
+        let mut renderer = TextRenderer::new();
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        for event in [
+            text_event("Hel", true),
+            text_event("Hello, wor", true),
+            text_event("Hello, world", false),
+            TaskEvent::TaskCompleted {
+                task_id: "t".to_string(),
+            },
+        ] {
+            renderer.render(&event, &mut out, &mut err).unwrap();
+        }
+        assert_eq!(String::from_utf8(out).unwrap(), "Hello, world\n");
+        assert!(err.is_empty());
+    }
+}
@@ -168,7 +168,17 @@ pub fn register_all_commands<R: tauri::Runtime>(builder: tauri::Builder<R>) -> t
         crate::lsp::commands::lsp_definition,
         crate::lsp::commands::lsp_references,
         crate::lsp::commands::lsp_diagnostics,
+        crate::lsp::commands::lsp_implementation,
+        crate::lsp::commands::lsp_type_definition,
+        crate::lsp::commands::lsp_signature_help,
+        crate::lsp::commands::lsp_rename,
+        crate::lsp::commands::lsp_code_actions,
+        crate::lsp::commands::lsp_format,
+        crate::lsp::commands::lsp_apply_workspace_edit,
         crate::agent::mcp::commands::reload_mcp_servers,
+        crate::agent::mcp::commands::start_mcp_oauth,
+        crate::agent::mcp::commands::finish_mcp_oauth,
+        crate::agent::mcp::commands::cancel_mcp_oauth,
         // Terminal configuration commands
         crate::config::terminal_commands::terminal_config_get,
         crate::config::terminal_commands::terminal_config_set,
@@ -229,6 +239,10 @@ pub fn register_all_commands<R: tauri::Runtime>(builder: tauri::Builder<R>) -> t
         crate::agent::core::commands::agent_list_skills,
         crate::agent::core::commands::agent_validate_skill,
         crate::agent::core::commands::agent_switch_session_agent,
+        crate::agent::core::commands::agent_get_spend,
+        crate::agent::core::commands::agent_worktree_diff,
+        crate::agent::core::commands::agent_worktree_merge,
+        crate::agent::core::commands::agent_worktree_discard,
         // Storage system commands (Runtime)
         crate::ai::tool::storage::commands::storage_get_terminals_state,
         crate::ai::tool::storage::commands::storage_get_terminal_state,
@@ -92,19 +92,20 @@ struct WatcherState {
 
 pub struct UnifiedFileWatcher {
     state: Arc<RwLock<Option<WatcherState>>>,
-    fs_sink: Option<mpsc::Sender<ObservedFsChangeBatch>>,
+    fs_sinks: Vec<mpsc::Sender<ObservedFsChangeBatch>>,
 }
 
 impl UnifiedFileWatcher {
     pub fn new() -> Self {
         Self {
             state: Arc::new(RwLock::new(None)),
-            fs_sink: None,
+            fs_sinks: Vec::new(),
         }
     }
 
+    /// Add a receiver of observed file changes; every sink gets every batch.
     pub fn with_fs_sink(mut self, sink: mpsc::Sender<ObservedFsChangeBatch>) -> Self {
-        self.fs_sink = Some(sink);
+        self.fs_sinks.push(sink);
         self
     }
 
@@ -203,7 +204,7 @@ impl UnifiedFileWatcher {
         let workspace_root_for_task = workspace_root.clone();
         let workspace_key: Arc<str> = Arc::from(workspace_root.to_string_lossy().to_string());
         let workspace_root_str = workspace_root.to_string_lossy().to_string();
-        let fs_sink = self.fs_sink.clone();
+        let fs_sinks = self.fs_sinks.clone();
         let repo_root_str = repo_root.as_ref().map(|p| p.to_string_lossy().to_string());
         let git_paths_for_task = git_paths;
         let ignore_for_task = ignore;
@@ -292,13 +293,14 @@ impl UnifiedFileWatcher {
 
                         pending_git.clear();
 
-                        if let Some(sink) = &fs_sink {
-                            if !observed.is_empty() {
-                                if let Err(err) = sink.try_send(ObservedFsChangeBatch {
-                                    workspace_key: Arc::clone(&workspace_key),
-                                    workspace_root: workspace_root_for_task.clone(),
-                                    changes: observed,
-                                }) {
+                        if !observed.is_empty() {
+                            let batch = ObservedFsChangeBatch {
+                                workspace_key: Arc::clone(&workspace_key),
+                                workspace_root: workspace_root_for_task.clone(),
+                                changes: observed,
+                            };
+                            for sink in &fs_sinks {
+                                if let Err(err) = sink.try_send(batch.clone()) {
                                     warn!("Dropping observed file changes because sink is full: {}", err);
                                 }
                             }
@@ -443,12 +443,20 @@ impl GitService {
         )
         .await?;
 
+        Self::parse_file_changes(&output, &status_output)
+    }
+
+    /// Combine `--numstat` and `--name-status` output of the same diff
+    fn parse_file_changes(
+        numstat_output: &str,
+        status_output: &str,
+    ) -> Result<Vec<CommitFileChange>, GitError> {
         let mut files: Vec<CommitFileChange> = Vec::new();
 
         // Parse numstat for additions/deletions
         let mut numstat_map: std::collections::HashMap<String, (Option<u32>, Option<u32>)> =
             std::collections::HashMap::new();
-        for line in output.lines() {
+        for line in numstat_output.lines() {
             let mut parts = line.splitn(3, '\t');
             let additions = Self::parse_numstat_field(parts.next(), "additions")?;
             let deletions = Self::parse_numstat_field(parts.next(), "deletions")?;
@@ -1278,6 +1286,147 @@ impl GitService {
 
         Ok(result)
     }
+
+    /// Prune bookkeeping of worktrees whose directories no longer exist.
+    pub async fn worktree_prune(repo_path: &str) -> Result<(), GitError> {
+        Self::execute_no_output(&["worktree", "prune"], repo_path).await
+    }
+
+    /// Force-delete a local branch.
+    pub async fn delete_branch(repo_path: &str, branch: &str) -> Result<(), GitError> {
+        Self::execute_no_output(&["branch", "-D", branch], repo_path).await
+    }
+
+    /// Commit hash of HEAD.
+    pub async fn head_commit(path: &str) -> Result<String, GitError> {
+        let text = Self::execute_text(&["rev-parse", "HEAD"], path).await?;
+        Ok(text.trim().to_string())
+    }
+
+    /// Stage and commit everything pending in `path`. Returns `false` when there was nothing
+    /// to commit.
+    pub async fn commit_all(path: &str, message: &str) -> Result<bool, GitError> {
+        let status = Self::execute_text(&["status", "--porcelain"], path).await?;
+        if status.trim().is_empty() {
+            return Ok(false);
+        }
+        Self::execute_no_output(&["add", "-A"], path).await?;
+        Self::execute_no_output(&["commit", "-m", message], path).await?;
+        Ok(true)
+    }
+
+    /// Diff of the worktree at `worktree_path` against the point where it forked from `base`.
+    ///
+    /// Uncommitted work is included: everything in the worktree is staged first and the
+    /// index is compared with the merge base.
+    pub async fn branch_diff(worktree_path: &str, base: &str) -> Result<BranchDiff, GitError> {
+        let branch = Self::execute_text(&["rev-parse", "--abbrev-ref", "HEAD"], worktree_path)
+            .await?
+            .trim()
+            .to_string();
+        let merge_base = Self::execute_text(&["merge-base", base, "HEAD"], worktree_path)
+            .await?
+            .trim()
+            .to_string();
+
+        Self::execute_no_output(&["add", "-A"], worktree_path).await?;
+        let numstat = Self::execute_text(
+            &["diff", "--cached", "--numstat", "--no-color", &merge_base],
+            worktree_path,
+        )
+        .await?;
+        let name_status = Self::execute_text(
+            &[
+                "diff",
+                "--cached",
+                "--name-status",
+                "--no-color",
+                &merge_base,
+            ],
+            worktree_path,
+        )
+        .await?;
+        let patch = Self::execute_text(
+            &["diff", "--cached", "--no-color", "--unified=3", &merge_base],
+            worktree_path,
+        )
+        .await?;
+
+        Ok(BranchDiff {
+            branch,
+            merge_base,
+            files: Self::parse_file_changes(&numstat, &name_status)?,
+            patch,
+        })
+    }
+
+    /// Bring `branch` into the branch checked out at `path`.
+    ///
+    /// On conflicts the merge or cherry-pick is aborted and the conflicting paths are
+    /// reported instead of leaving the tree half-merged.
+    pub async fn merge_branch(
+        path: &str,
+        branch: &str,
+        strategy: MergeStrategy,
+        message: &str,
+    ) -> Result<MergeOutcome, GitError> {
+        let range = format!("HEAD..{branch}");
+        let pending = Self::execute_text(&["rev-list", "--count", &range], path).await?;
+        if pending.trim() == "0" {
+            return Ok(MergeOutcome {
+                merged: true,
+                commit: None,
+                conflicts: vec![],
+            });
+        }
+
+        let result = match strategy {
+            MergeStrategy::Merge => {
+                Self::execute_no_output(&["merge", "--no-ff", "-m", message, branch], path).await
+            }
+            MergeStrategy::CherryPick => {
+                Self::execute_no_output(&["cherry-pick", &range], path).await
+            }
+        };
+
+        if let Err(err) = result {
+            let conflicts = Self::execute_text(&["diff", "--name-only", "--diff-filter=U"], path)
+                .await
+                .unwrap_or_default();
+            let conflicts: Vec<String> = conflicts
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect();
+            if conflicts.is_empty() {
+                return Err(err);
+            }
+
+            let abort = match strategy {
+                MergeStrategy::Merge => ["merge", "--abort"],
+                MergeStrategy::CherryPick => ["cherry-pick", "--abort"],
+            };
+            if let Err(abort_err) = Self::execute_no_output(&abort, path).await {
+                tracing::warn!(
+                    "Failed to abort conflicted merge in '{}': {}",
+                    path,
+                    abort_err.message
+                );
+            }
+            return Ok(MergeOutcome {
+                merged: false,
+                commit: None,
+                conflicts,
+            });
+        }
+
+        Ok(MergeOutcome {
+            merged: true,
+            commit: Some(Self::head_commit(path).await?),
+            conflicts: vec![],
+        })
+    }
 }
 
 #[derive(Default)]
@@ -1350,4 +1499,73 @@ mod tests {
         assert_eq!(parsed.modified_files[0].path, "file.txt");
         assert_eq!(parsed.untracked_files[0].path, "new.txt");
     }
+
+    fn git(dir: &std::path::Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[tokio::test]
+    async fn worktree_branch_diff_merge_and_conflicts() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let repo = temp.path().join("repo");
+        std::fs::create_dir(&repo).unwrap();
+        git(&repo, &["init", "-q", "-b", "main"]);
+        git(&repo, &["config", "user.email", "test@example.com"]);
+        git(&repo, &["config", "user.name", "Test"]);
+        std::fs::write(repo.join("a.txt"), "one\n").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-q", "-m", "init"]);
+
+        let repo_path = repo.to_str().unwrap();
+        let wt = temp.path().join("wt");
+        let wt_path = wt.to_str().unwrap();
+        GitService::worktree_add(repo_path, "task-1", wt_path)
+            .await
+            .unwrap();
+        std::fs::write(wt.join("b.txt"), "new\n").unwrap();
+
+        let diff = GitService::branch_diff(wt_path, "main").await.unwrap();
+        assert_eq!(diff.branch, "task-1");
+        assert_eq!(diff.files.len(), 1);
+        assert_eq!(diff.files[0].status, FileChangeStatus::Added);
+        assert!(diff.patch.contains("+new"));
+
+        assert!(GitService::commit_all(wt_path, "task work").await.unwrap());
+        assert!(!GitService::commit_all(wt_path, "task work").await.unwrap());
+        let outcome =
+            GitService::merge_branch(repo_path, "task-1", MergeStrategy::Merge, "merge task")
+                .await
+                .unwrap();
+        assert!(outcome.merged && outcome.commit.is_some());
+        assert!(repo.join("b.txt").exists());
+
+        // Both sides edit the same line
+        std::fs::write(wt.join("a.txt"), "theirs\n").unwrap();
+        GitService::commit_all(wt_path, "theirs").await.unwrap();
+        std::fs::write(repo.join("a.txt"), "ours\n").unwrap();
+        GitService::commit_all(repo_path, "ours").await.unwrap();
+
+        let outcome =
+            GitService::merge_branch(repo_path, "task-1", MergeStrategy::CherryPick, "merge task")
+                .await
+                .unwrap();
+        assert!(!outcome.merged);
+        assert_eq!(outcome.conflicts, vec!["a.txt".to_string()]);
+        assert_eq!(
+            std::fs::read_to_string(repo.join("a.txt")).unwrap(),
+            "ours\n"
+        );
+
+        GitService::worktree_remove(repo_path, wt_path, true)
+            .await
+            .unwrap();
+        GitService::delete_branch(repo_path, "task-1")
+            .await
+            .unwrap();
+    }
 }
@@ -174,3 +174,34 @@ pub enum GitErrorCode {
     ParseError,
     IoError,
 }
+
+/// Changes of a branch since it forked from `base`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchDiff {
+    pub branch: String,
+    pub merge_base: String,
+    pub files: Vec<CommitFileChange>,
+    pub patch: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeStrategy {
+    /// `git merge --no-ff`, keeping the branch history
+    #[default]
+    Merge,
+    /// Replay the branch commits on top of the current HEAD
+    CherryPick,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeOutcome {
+    pub merged: bool,
+    /// HEAD after the merge; `None` when there was nothing to merge or it conflicted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Conflicting paths. The merge is aborted, so the target tree is left untouched.
+    pub conflicts: Vec<String>,
+}
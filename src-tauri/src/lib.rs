@@ -1,6 +1,8 @@
 pub mod agent;
+pub mod agent_server;
 pub mod ai;
 pub mod checkpoint;
+pub mod cli;
 pub mod code_intel;
 pub mod commands;
 pub mod config;
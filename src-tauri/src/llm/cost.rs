@@ -0,0 +1,167 @@
+//! LLM call cost from token usage and per-token prices
+//!
+//! Prices come from the model's `options.cost` override when set (self-hosted or custom
+//! endpoints), otherwise from models.dev. Both use USD per million tokens.
+
+use tracing::debug;
+
+use crate::llm::anthropic_types::Usage;
+use crate::llm::models_dev::{self, ModelCost};
+use crate::storage::repositories::AIModels;
+use crate::storage::DatabaseManager;
+
+const TOKENS_PER_PRICE_UNIT: f64 = 1_000_000.0;
+
+/// Prices and provider of a configured model
+#[derive(Debug, Clone)]
+pub struct ModelPricing {
+    pub provider: String,
+    pub cost: Option<ModelCost>,
+}
+
+impl ModelPricing {
+    pub fn cost_of(&self, usage: &Usage) -> f64 {
+        self.cost
+            .as_ref()
+            .map(|cost| calculate_cost(cost, usage, input_includes_cache_reads(&self.provider)))
+            .unwrap_or(0.0)
+    }
+
+    pub fn fresh_tokens(&self, usage: &Usage) -> u64 {
+        fresh_tokens(usage, input_includes_cache_reads(&self.provider))
+    }
+}
+
+/// Anthropic reports cache reads and writes separately from `input_tokens`; the OpenAI
+/// and Gemini style APIs count cached tokens as part of the prompt.
+fn input_includes_cache_reads(provider: &str) -> bool {
+    provider != "anthropic"
+}
+
+/// Tokens a call actually processed: uncached input, cache writes and output. Cache reads
+/// are left out so a long conversation isn't charged for its whole prompt on every turn.
+pub fn fresh_tokens(usage: &Usage, input_includes_cache_reads: bool) -> u64 {
+    let cache_read = usage.cache_read_input_tokens.unwrap_or(0);
+    let uncached_input = if input_includes_cache_reads {
+        usage.input_tokens.saturating_sub(cache_read)
+    } else {
+        usage.input_tokens
+    };
+    u64::from(uncached_input)
+        + u64::from(usage.cache_creation_input_tokens.unwrap_or(0))
+        + u64::from(usage.output_tokens)
+}
+
+/// Cost in USD of a single call.
+///
+/// Cached tokens fall back to the input price when the model has no cache price.
+pub fn calculate_cost(cost: &ModelCost, usage: &Usage, input_includes_cache_reads: bool) -> f64 {
+    let cache_read = usage.cache_read_input_tokens.unwrap_or(0);
+    let cache_write = usage.cache_creation_input_tokens.unwrap_or(0);
+    let uncached_input = if input_includes_cache_reads {
+        usage.input_tokens.saturating_sub(cache_read)
+    } else {
+        usage.input_tokens
+    };
+
+    let total = f64::from(uncached_input) * cost.input
+        + f64::from(usage.output_tokens) * cost.output
+        + f64::from(cache_read) * cost.cache_read.unwrap_or(cost.input)
+        + f64::from(cache_write) * cost.cache_write.unwrap_or(cost.input);
+    total / TOKENS_PER_PRICE_UNIT
+}
+
+/// Look up prices for a model id from models.json. `None` when the model is unknown.
+pub async fn model_pricing(database: &DatabaseManager, model_id: &str) -> Option<ModelPricing> {
+    let model = match AIModels::new(database).find_by_id(model_id).await {
+        Ok(Some(model)) => model,
+        Ok(None) => return None,
+        Err(err) => {
+            debug!("Model lookup for pricing failed: {}", err);
+            return None;
+        }
+    };
+
+    let override_cost = model
+        .options
+        .as_ref()
+        .and_then(|options| options.get("cost"))
+        .and_then(|cost| serde_json::from_value::<ModelCost>(cost.clone()).ok());
+    let cost = match override_cost {
+        Some(cost) => Some(cost),
+        None => match models_dev::get_model(&model.provider, &model.model).await {
+            Ok(def) => def.and_then(|def| def.cost),
+            Err(err) => {
+                debug!("models.dev pricing unavailable: {}", err);
+                None
+            }
+        },
+    };
+
+    Some(ModelPricing {
+        provider: model.provider,
+        cost,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input: u32, output: u32, cache_read: Option<u32>, cache_write: Option<u32>) -> Usage {
+        Usage {
+            input_tokens: input,
+            output_tokens: output,
+            cache_creation_input_tokens: cache_write,
+            cache_read_input_tokens: cache_read,
+        }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn prices_cached_tokens_by_provider_semantics() {
+        let cost = ModelCost {
+            input: 3.0,
+            output: 15.0,
+            cache_read: Some(0.3),
+            cache_write: Some(3.75),
+            reasoning: None,
+        };
+
+        // Anthropic: 1000 fresh input + 10_000 cache reads + 2000 cache writes
+        let anthropic = calculate_cost(&cost, &usage(1000, 500, Some(10_000), Some(2000)), false);
+        assert!(close(anthropic, 0.003 + 0.0075 + 0.003 + 0.0075));
+
+        // OpenAI style: 11_000 prompt tokens of which 10_000 were cached
+        let openai = calculate_cost(&cost, &usage(11_000, 500, Some(10_000), None), true);
+        assert!(close(openai, 0.003 + 0.0075 + 0.003));
+    }
+
+    #[test]
+    fn fresh_tokens_match_across_providers() {
+        // The same call: 1000 fresh prompt tokens, 10_000 read from cache, 500 output
+        let anthropic = fresh_tokens(&usage(1000, 500, Some(10_000), None), false);
+        let openai = fresh_tokens(&usage(11_000, 500, Some(10_000), None), true);
+        assert_eq!(anthropic, 1500);
+        assert_eq!(openai, 1500);
+
+        assert_eq!(
+            fresh_tokens(&usage(1000, 500, None, Some(2000)), false),
+            3500
+        );
+    }
+
+    #[test]
+    fn missing_cache_price_falls_back_to_input() {
+        let cost = ModelCost {
+            input: 1.0,
+            output: 2.0,
+            ..Default::default()
+        };
+        let total = calculate_cost(&cost, &usage(0, 0, Some(1_000_000), None), false);
+        assert!(close(total, 1.0));
+    }
+}
@@ -1,5 +1,6 @@
 pub mod anthropic_types;
 pub mod commands;
+pub mod cost;
 pub mod error;
 pub mod models_dev;
 pub mod oauth;
@@ -58,6 +58,25 @@ impl OAuthManager {
             .get(provider_type)
             .ok_or_else(|| OAuthError::InvalidProvider(provider_type.to_string()))?;
 
+        let flow = self
+            .start_pkce_flow(provider_type, |pkce, state| {
+                provider.generate_authorize_url(pkce, state)
+            })
+            .await?;
+
+        info!("OAuth flow started for provider: {}", provider_type);
+        Ok(flow)
+    }
+
+    /// Start an authorization code flow on the shared callback server for an
+    /// authorization server that is not a registered provider (e.g. discovered
+    /// from an MCP server). `build_url` receives the PKCE codes and state; the
+    /// redirect URI is `OAuthCallbackServer::callback_url()`.
+    pub async fn start_pkce_flow(
+        &self,
+        provider: &str,
+        build_url: impl FnOnce(&PkceCodes, &str) -> OAuthResult<String>,
+    ) -> OAuthResult<OAuthFlowInfo> {
         // Ensure callback server is started
         OAuthCallbackServer::ensure_started(self.callback_server.clone()).await;
 
@@ -66,7 +85,7 @@ impl OAuthManager {
         let state = generate_state()?;
 
         // Generate authorization URL
-        let authorize_url = provider.generate_authorize_url(&pkce, &state)?;
+        let authorize_url = build_url(&pkce, &state)?;
 
         // Register callback wait and get receiver
         let mut server = self.callback_server.lock().await;
@@ -80,26 +99,15 @@ impl OAuthManager {
             .await
             .insert(state.clone(), pending);
 
-        info!("OAuth flow started for provider: {}", provider_type);
-
         Ok(OAuthFlowInfo {
             flow_id: state,
             authorize_url,
-            provider: provider_type.to_string(),
+            provider: provider.to_string(),
         })
     }
 
-    /// Wait for OAuth callback
-    pub async fn wait_for_callback(
-        &self,
-        flow_id: &str,
-        provider_type: &str,
-    ) -> OAuthResult<StorageOAuthConfig> {
-        let provider = self
-            .providers
-            .get(provider_type)
-            .ok_or_else(|| OAuthError::InvalidProvider(provider_type.to_string()))?;
-
+    /// Wait for the authorization code of a flow started with `start_pkce_flow`
+    pub async fn wait_for_code(&self, flow_id: &str) -> OAuthResult<(String, PkceCodes)> {
         // Retrieve pending flow
         let pending = self
             .pending_flows
@@ -111,12 +119,24 @@ impl OAuthManager {
         // Wait for callback (with timeout)
         let timeout = tokio::time::Duration::from_secs(5 * 60); // 5 minute timeout
 
-        let result = tokio::time::timeout(timeout, pending.receiver)
+        tokio::time::timeout(timeout, pending.receiver)
             .await
             .map_err(|_| OAuthError::Timeout)?
-            .map_err(|_| OAuthError::Other("Channel closed".to_string()))??;
+            .map_err(|_| OAuthError::Other("Channel closed".to_string()))?
+    }
+
+    /// Wait for OAuth callback
+    pub async fn wait_for_callback(
+        &self,
+        flow_id: &str,
+        provider_type: &str,
+    ) -> OAuthResult<StorageOAuthConfig> {
+        let provider = self
+            .providers
+            .get(provider_type)
+            .ok_or_else(|| OAuthError::InvalidProvider(provider_type.to_string()))?;
 
-        let (code, pkce) = result;
+        let (code, pkce) = self.wait_for_code(flow_id).await?;
 
         // Exchange authorization code for tokens
         debug!("Exchanging code for tokens");
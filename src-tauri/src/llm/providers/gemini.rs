@@ -1,53 +1,846 @@
+//! Gemini Provider - Google Generative Language API (`generateContent`)
+//!
+//! Requests are converted with `transform::gemini`; responses and SSE chunks are translated
+//! back into Anthropic `Message` / `StreamEvent`s. Gemini sends each function call whole in a
+//! single chunk, so tool blocks are opened, filled and closed in one go.
+
 use async_trait::async_trait;
+use eventsource_stream::Eventsource;
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use reqwest::{Client, StatusCode};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
 use std::pin::Pin;
+use std::time::Duration;
 use tokio_stream::Stream;
 
+use crate::llm::anthropic_types::{
+    ContentBlock, ContentBlockStart, ContentDelta, CreateMessageRequest, Message, MessageDeltaData,
+    MessageRole, MessageStartData, ReasoningBlockMetadata, StopReason, StreamEvent, Usage,
+};
 use crate::llm::{
-    error::{LlmProviderError, LlmProviderResult},
+    error::{GeminiError, LlmProviderError, LlmProviderResult},
     providers::base::LLMProvider,
-    types::LLMProviderConfig,
+    transform::gemini::{
+        build_gemini_system_instruction, convert_to_gemini_contents, convert_to_gemini_tools,
+        GEMINI_PROVIDER_ID,
+    },
+    types::{EmbeddingData, EmbeddingRequest, EmbeddingResponse, LLMProviderConfig},
 };
 
-/// Gemini Provider (messages unsupported in zero-abstraction mode)
+/// Global shared HTTP client for optimized connection reuse
+static SHARED_HTTP_CLIENT: Lazy<Client> = Lazy::new(|| {
+    Client::builder()
+        .pool_max_idle_per_host(20)
+        .pool_idle_timeout(Duration::from_secs(90))
+        .timeout(Duration::from_secs(120))
+        .build()
+        .expect("Failed to create shared HTTP client")
+});
+
+type GeminiResult<T> = Result<T, GeminiError>;
+const GEMINI_API_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+/// `batchEmbedContents` accepts at most 100 requests per call
+const MAX_EMBEDDING_BATCH: usize = 100;
+
+/// Gemini Provider
 #[derive(Clone)]
-pub struct GeminiProvider;
+pub struct GeminiProvider {
+    config: LLMProviderConfig,
+}
+
+/// Build `generateContent` request body
+fn build_gemini_body(req: &CreateMessageRequest) -> Value {
+    let mut body = json!({ "contents": convert_to_gemini_contents(&req.messages) });
+
+    if let Some(system) = build_gemini_system_instruction(req) {
+        body["systemInstruction"] = system;
+    }
+    if let Some(tools) = req.tools.as_ref().filter(|t| !t.is_empty()) {
+        body["tools"] = convert_to_gemini_tools(tools);
+    }
+
+    let mut generation = json!({ "maxOutputTokens": req.max_tokens });
+    if let Some(temperature) = req.temperature {
+        generation["temperature"] = json!(temperature);
+    }
+    if let Some(top_p) = req.top_p {
+        generation["topP"] = json!(top_p);
+    }
+    if let Some(top_k) = req.top_k {
+        generation["topK"] = json!(top_k);
+    }
+    if let Some(stop) = req.stop_sequences.as_ref().filter(|s| !s.is_empty()) {
+        generation["stopSequences"] = json!(stop);
+    }
+    if let Some(thinking) = &req.thinking {
+        generation["thinkingConfig"] = json!({
+            "thinkingBudget": thinking.budget_tokens,
+            "includeThoughts": true,
+        });
+    }
+    body["generationConfig"] = generation;
+
+    body
+}
+
+fn empty_usage() -> Usage {
+    Usage {
+        input_tokens: 0,
+        output_tokens: 0,
+        cache_creation_input_tokens: None,
+        cache_read_input_tokens: None,
+    }
+}
+
+/// Parse `usageMetadata`; thought tokens are billed as output
+fn parse_usage(response: &Value) -> Usage {
+    let meta = &response["usageMetadata"];
+    let count = |key: &str| meta[key].as_u64().unwrap_or(0) as u32;
+    let cached = count("cachedContentTokenCount");
+    Usage {
+        input_tokens: count("promptTokenCount"),
+        output_tokens: count("candidatesTokenCount") + count("thoughtsTokenCount"),
+        cache_creation_input_tokens: None,
+        cache_read_input_tokens: (cached > 0).then_some(cached),
+    }
+}
+
+fn map_finish_reason(reason: &str, has_tool_calls: bool) -> StopReason {
+    match reason {
+        "MAX_TOKENS" => StopReason::MaxTokens,
+        _ if has_tool_calls => StopReason::ToolUse,
+        _ => StopReason::EndTurn,
+    }
+}
+
+fn new_tool_call_id(call: &Value) -> String {
+    match call["id"].as_str().filter(|id| !id.is_empty()) {
+        Some(id) => id.to_string(),
+        None => format!("call_{}", uuid::Uuid::new_v4().simple()),
+    }
+}
+
+fn signature_metadata(signature: Option<&str>) -> ReasoningBlockMetadata {
+    ReasoningBlockMetadata {
+        signature: signature.map(str::to_string),
+        provider: Some(GEMINI_PROVIDER_ID.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Candidate parts → Anthropic content blocks (non-streaming)
+fn parse_candidate_content(response: &Value) -> Vec<ContentBlock> {
+    let mut blocks = Vec::new();
+    let parts = response["candidates"][0]["content"]["parts"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+
+    for part in &parts {
+        let signature = part["thoughtSignature"].as_str();
+        let is_thought = part["thought"].as_bool().unwrap_or(false);
+
+        if is_thought {
+            blocks.push(ContentBlock::Thinking {
+                thinking: part["text"].as_str().unwrap_or_default().to_string(),
+                signature: signature.map(str::to_string),
+                reasoning_metadata: Some(signature_metadata(signature)),
+            });
+            continue;
+        }
+
+        // The signature belongs before the part it arrived on, see transform::gemini
+        if let Some(signature) = signature {
+            blocks.push(ContentBlock::Thinking {
+                thinking: String::new(),
+                signature: Some(signature.to_string()),
+                reasoning_metadata: Some(signature_metadata(Some(signature))),
+            });
+        }
+
+        if let Some(call) = part.get("functionCall") {
+            blocks.push(ContentBlock::ToolUse {
+                id: new_tool_call_id(call),
+                name: call["name"].as_str().unwrap_or_default().to_string(),
+                input: call.get("args").cloned().unwrap_or_else(|| json!({})),
+            });
+        } else if let Some(text) = part["text"].as_str().filter(|t| !t.is_empty()) {
+            blocks.push(ContentBlock::Text {
+                text: text.to_string(),
+                cache_control: None,
+            });
+        }
+    }
+
+    blocks
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpenBlock {
+    Text(usize),
+    Thinking(usize),
+}
+
+/// Translates `streamGenerateContent` chunks into Anthropic stream events
+struct GeminiStreamState {
+    model: String,
+    message_started: bool,
+    next_index: usize,
+    open: Option<OpenBlock>,
+    has_tool_calls: bool,
+    finish_reason: Option<String>,
+    usage: Usage,
+}
+
+impl GeminiStreamState {
+    fn new(model: String) -> Self {
+        Self {
+            model,
+            message_started: false,
+            next_index: 0,
+            open: None,
+            has_tool_calls: false,
+            finish_reason: None,
+            usage: empty_usage(),
+        }
+    }
+
+    fn close_open(&mut self, events: &mut Vec<StreamEvent>) {
+        if let Some(OpenBlock::Text(index) | OpenBlock::Thinking(index)) = self.open.take() {
+            events.push(StreamEvent::ContentBlockStop { index });
+        }
+    }
+
+    fn start_block(
+        &mut self,
+        content_block: ContentBlockStart,
+        events: &mut Vec<StreamEvent>,
+    ) -> usize {
+        self.close_open(events);
+        let index = self.next_index;
+        self.next_index += 1;
+        events.push(StreamEvent::ContentBlockStart {
+            index,
+            content_block,
+        });
+        index
+    }
+
+    fn process_chunk(&mut self, chunk: &Value) -> GeminiResult<Vec<StreamEvent>> {
+        if let Some(error) = chunk.get("error") {
+            return Err(GeminiError::Stream {
+                message: error["message"]
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| error.to_string()),
+            });
+        }
+
+        let mut events = Vec::new();
+        if !self.message_started {
+            self.message_started = true;
+            let id = chunk["responseId"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("msg_{}", uuid::Uuid::new_v4().simple()));
+            events.push(StreamEvent::MessageStart {
+                message: MessageStartData {
+                    id,
+                    message_type: "message".to_string(),
+                    role: MessageRole::Assistant,
+                    model: self.model.clone(),
+                    usage: empty_usage(),
+                },
+            });
+        }
+
+        if chunk.get("usageMetadata").is_some() {
+            self.usage = parse_usage(chunk);
+        }
+
+        let candidate = &chunk["candidates"][0];
+        let parts = candidate["content"]["parts"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        for part in &parts {
+            self.process_part(part, &mut events);
+        }
+
+        if let Some(reason) = candidate["finishReason"].as_str() {
+            self.finish_reason = Some(reason.to_string());
+        }
+
+        Ok(events)
+    }
+
+    fn process_part(&mut self, part: &Value, events: &mut Vec<StreamEvent>) {
+        let signature = part["thoughtSignature"].as_str();
+        let text = part["text"].as_str().unwrap_or_default();
+
+        if part["thought"].as_bool().unwrap_or(false) {
+            let index = match self.open {
+                Some(OpenBlock::Thinking(index)) => index,
+                _ => {
+                    let index = self.start_block(
+                        ContentBlockStart::Thinking {
+                            thinking: String::new(),
+                            metadata: Some(signature_metadata(None)),
+                        },
+                        events,
+                    );
+                    self.open = Some(OpenBlock::Thinking(index));
+                    index
+                }
+            };
+            if !text.is_empty() {
+                events.push(StreamEvent::ContentBlockDelta {
+                    index,
+                    delta: ContentDelta::Thinking {
+                        thinking: text.to_string(),
+                    },
+                });
+            }
+            if let Some(signature) = signature {
+                events.push(StreamEvent::ContentBlockDelta {
+                    index,
+                    delta: ContentDelta::Signature {
+                        signature: signature.to_string(),
+                    },
+                });
+            }
+            return;
+        }
+
+        // Record the signature on a thinking block ahead of the part it came with
+        if let Some(signature) = signature {
+            match self.open {
+                Some(OpenBlock::Thinking(index)) => events.push(StreamEvent::ContentBlockDelta {
+                    index,
+                    delta: ContentDelta::Signature {
+                        signature: signature.to_string(),
+                    },
+                }),
+                _ => {
+                    let index = self.start_block(
+                        ContentBlockStart::Thinking {
+                            thinking: String::new(),
+                            metadata: Some(signature_metadata(Some(signature))),
+                        },
+                        events,
+                    );
+                    self.open = Some(OpenBlock::Thinking(index));
+                }
+            }
+            self.close_open(events);
+        }
+
+        if let Some(call) = part.get("functionCall") {
+            self.has_tool_calls = true;
+            let index = self.start_block(
+                ContentBlockStart::ToolUse {
+                    id: new_tool_call_id(call),
+                    name: call["name"].as_str().unwrap_or_default().to_string(),
+                },
+                events,
+            );
+            let args = call.get("args").cloned().unwrap_or_else(|| json!({}));
+            events.push(StreamEvent::ContentBlockDelta {
+                index,
+                delta: ContentDelta::InputJson {
+                    partial_json: args.to_string(),
+                },
+            });
+            events.push(StreamEvent::ContentBlockStop { index });
+            return;
+        }
+
+        if text.is_empty() {
+            return;
+        }
+        let index = match self.open {
+            Some(OpenBlock::Text(index)) => index,
+            _ => {
+                let index = self.start_block(
+                    ContentBlockStart::Text {
+                        text: String::new(),
+                    },
+                    events,
+                );
+                self.open = Some(OpenBlock::Text(index));
+                index
+            }
+        };
+        events.push(StreamEvent::ContentBlockDelta {
+            index,
+            delta: ContentDelta::Text {
+                text: text.to_string(),
+            },
+        });
+    }
+
+    /// Close any open block and emit the final `MessageDelta` / `MessageStop`
+    fn finish(&mut self) -> Vec<StreamEvent> {
+        let mut events = Vec::new();
+        self.close_open(&mut events);
+        let stop_reason = self
+            .finish_reason
+            .as_deref()
+            .map(|reason| map_finish_reason(reason, self.has_tool_calls));
+        events.push(StreamEvent::MessageDelta {
+            delta: MessageDeltaData {
+                stop_reason,
+                stop_sequence: None,
+            },
+            usage: self.usage,
+        });
+        events.push(StreamEvent::MessageStop);
+        events
+    }
+}
 
 impl GeminiProvider {
-    pub fn new(_config: LLMProviderConfig) -> Self {
-        Self
+    pub fn new(config: LLMProviderConfig) -> Self {
+        Self { config }
+    }
+
+    /// Get shared HTTP client
+    fn client(&self) -> &'static Client {
+        &SHARED_HTTP_CLIENT
+    }
+
+    fn base_url(&self) -> &str {
+        match self.config.api_url.as_deref() {
+            Some(api_url) => api_url.trim_end_matches('/'),
+            None => GEMINI_API_BASE_URL,
+        }
+    }
+
+    /// `models/{model}`; accepts model names with or without the prefix
+    fn model_path(model: &str) -> String {
+        if model.starts_with("models/") {
+            model.to_string()
+        } else {
+            format!("models/{model}")
+        }
+    }
+
+    fn get_endpoint(&self, model: &str, method: &str) -> String {
+        format!("{}/{}:{}", self.base_url(), Self::model_path(model), method)
+    }
+
+    /// Send a request and fail on non-success status
+    async fn send(&self, url: &str, body: &Value) -> GeminiResult<reqwest::Response> {
+        let resp = self
+            .client()
+            .post(url)
+            .header("x-goog-api-key", &self.config.api_key)
+            .json(body)
+            .send()
+            .await
+            .map_err(|source| GeminiError::Http { source })?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let txt = match resp.text().await {
+                Ok(text) => text,
+                Err(err) => {
+                    tracing::warn!("Failed to read Gemini error response body: {}", err);
+                    format!("<failed to read error response body: {err}>")
+                }
+            };
+            return Err(Self::handle_error_response(status, &txt));
+        }
+        Ok(resp)
+    }
+
+    /// Handle API error response (`{"error": {"code", "message", "status"}}`)
+    fn handle_error_response(status: StatusCode, body: &str) -> GeminiError {
+        match serde_json::from_str::<Value>(body) {
+            Ok(error_json) => {
+                if let Some(message) = error_json["error"]["message"]
+                    .as_str()
+                    .filter(|m| !m.trim().is_empty())
+                {
+                    let message = match error_json["error"]["status"].as_str() {
+                        Some("RESOURCE_EXHAUSTED") => format!("Quota exceeded: {message}"),
+                        Some("PERMISSION_DENIED") | Some("UNAUTHENTICATED") => {
+                            format!("Authentication failed: {message}")
+                        }
+                        _ => message.to_string(),
+                    };
+                    return GeminiError::Api { status, message };
+                }
+            }
+            Err(err) => {
+                tracing::debug!(
+                    status = %status,
+                    error = %err,
+                    "Failed to parse Gemini error response as JSON"
+                );
+            }
+        }
+        GeminiError::Api {
+            status,
+            message: body.to_string(),
+        }
+    }
+
+    async fn call_internal(&self, request: CreateMessageRequest) -> GeminiResult<Message> {
+        let url = self.get_endpoint(&request.model, "generateContent");
+        let body = build_gemini_body(&request);
+
+        let json: Value = self
+            .send(&url, &body)
+            .await?
+            .json()
+            .await
+            .map_err(|source| GeminiError::Http { source })?;
+
+        if json["candidates"].as_array().is_none_or(|c| c.is_empty()) {
+            let reason = json["promptFeedback"]["blockReason"].as_str();
+            return match reason {
+                Some(reason) => Err(GeminiError::Api {
+                    status: StatusCode::BAD_REQUEST,
+                    message: format!("Prompt blocked: {reason}"),
+                }),
+                None => Err(GeminiError::MissingField {
+                    field: "candidates",
+                }),
+            };
+        }
+
+        let content = parse_candidate_content(&json);
+        let has_tool_calls = content
+            .iter()
+            .any(|b| matches!(b, ContentBlock::ToolUse { .. }));
+        let stop_reason = json["candidates"][0]["finishReason"]
+            .as_str()
+            .map(|reason| map_finish_reason(reason, has_tool_calls));
+
+        Ok(Message {
+            id: json["responseId"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("msg_{}", uuid::Uuid::new_v4().simple())),
+            message_type: "message".to_string(),
+            role: MessageRole::Assistant,
+            content,
+            model: request.model,
+            stop_reason,
+            stop_sequence: None,
+            usage: parse_usage(&json),
+        })
+    }
+
+    async fn call_stream_internal(
+        &self,
+        request: CreateMessageRequest,
+    ) -> GeminiResult<Pin<Box<dyn Stream<Item = GeminiResult<StreamEvent>> + Send>>> {
+        let url = format!(
+            "{}?alt=sse",
+            self.get_endpoint(&request.model, "streamGenerateContent")
+        );
+        let body = build_gemini_body(&request);
+        let resp = self.send(&url, &body).await?;
+
+        let raw_stream = resp.bytes_stream().eventsource();
+        let state = GeminiStreamState::new(request.model);
+
+        // Use unfold to maintain state; `None` state means the stream is finished
+        let event_stream = futures::stream::unfold(
+            Some((raw_stream, state, VecDeque::<StreamEvent>::new(), false)),
+            |current| async move {
+                let (mut stream, mut state, mut pending, mut finished) = current?;
+                loop {
+                    if let Some(evt) = pending.pop_front() {
+                        return Some((Ok(evt), Some((stream, state, pending, finished))));
+                    }
+                    if finished {
+                        return None;
+                    }
+                    match stream.next().await {
+                        Some(Ok(event)) => {
+                            if event.data.trim().is_empty() {
+                                continue;
+                            }
+                            let chunk: Value = match serde_json::from_str(&event.data) {
+                                Ok(v) => v,
+                                Err(source) => {
+                                    tracing::warn!(
+                                        "Failed to parse Gemini stream chunk: {} | raw: {}",
+                                        source,
+                                        event.data.chars().take(500).collect::<String>()
+                                    );
+                                    return Some((Err(GeminiError::Json { source }), None));
+                                }
+                            };
+                            match state.process_chunk(&chunk) {
+                                Ok(events) => pending.extend(events),
+                                Err(err) => return Some((Err(err), None)),
+                            }
+                        }
+                        Some(Err(e)) => {
+                            tracing::error!("Gemini SSE stream error: {:?}", e);
+                            return Some((
+                                Err(GeminiError::Stream {
+                                    message: format!("Network error: {e}"),
+                                }),
+                                None,
+                            ));
+                        }
+                        None => {
+                            if !state.message_started {
+                                return Some((
+                                    Err(GeminiError::Stream {
+                                        message: "Stream ended without any response".to_string(),
+                                    }),
+                                    None,
+                                ));
+                            }
+                            pending.extend(state.finish());
+                            finished = true;
+                        }
+                    }
+                }
+            },
+        );
+
+        Ok(Box::pin(event_stream))
+    }
+
+    async fn create_embeddings_internal(
+        &self,
+        request: EmbeddingRequest,
+    ) -> GeminiResult<EmbeddingResponse> {
+        let url = self.get_endpoint(&request.model, "batchEmbedContents");
+        let model_path = Self::model_path(&request.model);
+        let mut data = Vec::with_capacity(request.input.len());
+
+        for batch in request.input.chunks(MAX_EMBEDDING_BATCH) {
+            let body = build_embedding_body(&model_path, batch, request.dimensions);
+            let json: Value = self
+                .send(&url, &body)
+                .await?
+                .json()
+                .await
+                .map_err(|source| GeminiError::Http { source })?;
+
+            let offset = data.len();
+            data.extend(parse_embedding_batch(&json, offset)?);
+        }
+
+        Ok(EmbeddingResponse {
+            data,
+            model: request.model,
+            usage: None,
+        })
     }
 }
 
+fn build_embedding_body(model_path: &str, inputs: &[String], dimensions: Option<usize>) -> Value {
+    let requests: Vec<Value> = inputs
+        .iter()
+        .map(|text| {
+            let mut req = json!({
+                "model": model_path,
+                "content": { "parts": [{ "text": text }] },
+            });
+            if let Some(dimensions) = dimensions {
+                req["outputDimensionality"] = json!(dimensions);
+            }
+            req
+        })
+        .collect();
+    json!({ "requests": requests })
+}
+
+/// `{"embeddings": [{"values": [...]}, ...]}` → `EmbeddingData`, indexed from `offset`
+fn parse_embedding_batch(json: &Value, offset: usize) -> GeminiResult<Vec<EmbeddingData>> {
+    let embeddings = json["embeddings"]
+        .as_array()
+        .ok_or(GeminiError::MissingField {
+            field: "embeddings",
+        })?;
+
+    embeddings
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let values = item["values"].as_array().ok_or(GeminiError::MissingField {
+                field: "embeddings.values",
+            })?;
+            Ok(EmbeddingData {
+                embedding: values
+                    .iter()
+                    .filter_map(|v| v.as_f64())
+                    .map(|v| v as f32)
+                    .collect(),
+                index: offset + i,
+                object: "embedding".to_string(),
+            })
+        })
+        .collect()
+}
+
+// ============================================================
+// LLMProvider Trait Implementation
+// ============================================================
+
 #[async_trait]
 impl LLMProvider for GeminiProvider {
     fn provider_name(&self) -> &'static str {
         "gemini"
     }
 
-    async fn call(
-        &self,
-        _request: crate::llm::anthropic_types::CreateMessageRequest,
-    ) -> LlmProviderResult<crate::llm::anthropic_types::Message> {
-        Err(LlmProviderError::UnsupportedOperation {
-            provider: "gemini",
-            operation: "messages",
-        })
+    async fn call(&self, request: CreateMessageRequest) -> LlmProviderResult<Message> {
+        self.call_internal(request)
+            .await
+            .map_err(LlmProviderError::from)
     }
 
     async fn call_stream(
         &self,
-        _request: crate::llm::anthropic_types::CreateMessageRequest,
-    ) -> LlmProviderResult<
-        Pin<
-            Box<
-                dyn Stream<Item = LlmProviderResult<crate::llm::anthropic_types::StreamEvent>>
-                    + Send,
-            >,
-        >,
-    > {
-        Err(LlmProviderError::UnsupportedOperation {
-            provider: "gemini",
-            operation: "messages_stream",
-        })
+        request: CreateMessageRequest,
+    ) -> LlmProviderResult<Pin<Box<dyn Stream<Item = LlmProviderResult<StreamEvent>> + Send>>> {
+        let stream = self
+            .call_stream_internal(request)
+            .await
+            .map_err(LlmProviderError::from)?;
+
+        // Convert error type: GeminiError -> LlmProviderError
+        let converted_stream = stream.map(|result| result.map_err(LlmProviderError::from));
+
+        Ok(Box::pin(converted_stream))
+    }
+
+    async fn create_embeddings(
+        &self,
+        request: EmbeddingRequest,
+    ) -> LlmProviderResult<EmbeddingResponse> {
+        self.create_embeddings_internal(request)
+            .await
+            .map_err(LlmProviderError::from)
+    }
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::anthropic_types::{MessageParam, ThinkingConfig};
+
+    fn request() -> CreateMessageRequest {
+        CreateMessageRequest {
+            model: "gemini-2.5-pro".to_string(),
+            messages: vec![MessageParam::user("hello")],
+            max_tokens: 1024,
+            system: None,
+            developer_context: None,
+            tools: None,
+            temperature: Some(1.0),
+            stop_sequences: Some(vec!["END".to_string()]),
+            stream: true,
+            top_p: None,
+            top_k: Some(40),
+            metadata: None,
+            thinking: Some(ThinkingConfig::enabled(2048)),
+        }
+    }
+
+    #[test]
+    fn builds_generation_and_thinking_config() {
+        let body = build_gemini_body(&request());
+        let generation = &body["generationConfig"];
+        assert_eq!(generation["maxOutputTokens"], 1024);
+        assert_eq!(generation["topK"], 40);
+        assert_eq!(generation["stopSequences"], json!(["END"]));
+        assert_eq!(generation["thinkingConfig"]["thinkingBudget"], 2048);
+        assert_eq!(generation["thinkingConfig"]["includeThoughts"], true);
+        assert_eq!(body["contents"][0]["role"], "user");
+        assert!(body.get("tools").is_none());
+    }
+
+    #[test]
+    fn translates_stream_chunks() {
+        let mut state = GeminiStreamState::new("gemini-2.5-pro".to_string());
+        let chunks = [
+            json!({"candidates": [{"content": {"role": "model", "parts": [
+                {"text": "Let me look", "thought": true}
+            ]}}]}),
+            json!({"candidates": [{"content": {"role": "model", "parts": [
+                {"text": "Reading it."},
+                {"functionCall": {"name": "read_file", "args": {"path": "a.rs"}}, "thoughtSignature": "sig"}
+            ]}, "finishReason": "STOP"}],
+             "usageMetadata": {"promptTokenCount": 10, "candidatesTokenCount": 5, "thoughtsTokenCount": 3}}),
+        ];
+
+        let mut events = Vec::new();
+        for chunk in &chunks {
+            events.extend(state.process_chunk(chunk).unwrap());
+        }
+        events.extend(state.finish());
+
+        assert!(matches!(events[0], StreamEvent::MessageStart { .. }));
+        assert!(matches!(
+            events[1],
+            StreamEvent::ContentBlockStart {
+                index: 0,
+                content_block: ContentBlockStart::Thinking { .. }
+            }
+        ));
+        let signature_block = events.iter().find_map(|e| match e {
+            StreamEvent::ContentBlockStart {
+                index,
+                content_block: ContentBlockStart::Thinking { metadata, .. },
+            } if *index > 0 => metadata.as_ref().and_then(|m| m.signature.clone()),
+            _ => None,
+        });
+        assert_eq!(signature_block.as_deref(), Some("sig"));
+        let tool_args = events.iter().find_map(|e| match e {
+            StreamEvent::ContentBlockDelta {
+                delta: ContentDelta::InputJson { partial_json },
+                ..
+            } => Some(partial_json.clone()),
+            _ => None,
+        });
+        assert_eq!(tool_args.as_deref(), Some(r#"{"path":"a.rs"}"#));
+
+        match &events[events.len() - 2] {
+            StreamEvent::MessageDelta { delta, usage } => {
+                assert_eq!(delta.stop_reason, Some(StopReason::ToolUse));
+                assert_eq!(usage.input_tokens, 10);
+                assert_eq!(usage.output_tokens, 8);
+            }
+            other => panic!("expected message delta, got {other:?}"),
+        }
+        assert!(matches!(events.last(), Some(StreamEvent::MessageStop)));
+    }
+
+    #[test]
+    fn stream_error_payload_is_reported() {
+        let mut state = GeminiStreamState::new("gemini-2.5-pro".to_string());
+        let err = state
+            .process_chunk(&json!({"error": {"code": 429, "message": "quota"}}))
+            .unwrap_err();
+        assert!(matches!(err, GeminiError::Stream { message } if message == "quota"));
+    }
+
+    #[test]
+    fn parses_embedding_batches() {
+        let body = build_embedding_body("models/text-embedding-004", &["a".to_string()], Some(256));
+        assert_eq!(body["requests"][0]["model"], "models/text-embedding-004");
+        assert_eq!(body["requests"][0]["outputDimensionality"], 256);
+
+        let json = json!({"embeddings": [{"values": [0.5, 1.0]}, {"values": [0.25]}]});
+        let data = parse_embedding_batch(&json, 100).unwrap();
+        assert_eq!(data[1].index, 101);
+        assert_eq!(data[0].embedding, vec![0.5, 1.0]);
+        assert!(parse_embedding_batch(&json!({}), 0).is_err());
     }
 }
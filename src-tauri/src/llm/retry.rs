@@ -109,6 +109,23 @@ fn is_status_retryable(status: reqwest::StatusCode) -> bool {
     status.is_server_error() || matches!(status.as_u16(), 401 | 403 | 408 | 425 | 429 | 499)
 }
 
+/// Whether the provider rejected the credentials (HTTP 401)
+pub fn is_unauthorized(e: &LlmProviderError) -> bool {
+    match e {
+        LlmProviderError::OpenAi(OpenAiError::Api { status, .. })
+        | LlmProviderError::Anthropic(AnthropicError::Api { status, .. })
+        | LlmProviderError::Gemini(GeminiError::Api { status, .. }) => {
+            *status == reqwest::StatusCode::UNAUTHORIZED
+        }
+        LlmProviderError::OpenAi(OpenAiError::Http { source })
+        | LlmProviderError::Anthropic(AnthropicError::Http { source })
+        | LlmProviderError::Gemini(GeminiError::Http { source }) => {
+            source.status() == Some(reqwest::StatusCode::UNAUTHORIZED)
+        }
+        _ => false,
+    }
+}
+
 /// Get retry reason for logging
 pub fn error_retry_reason(e: &LlmProviderError) -> &'static str {
     match e {
@@ -216,4 +233,20 @@ mod tests {
         assert!(is_status_retryable(StatusCode::UNAUTHORIZED));
         assert!(is_status_retryable(StatusCode::FORBIDDEN));
     }
+
+    #[test]
+    fn test_unauthorized_detection() {
+        use reqwest::StatusCode;
+        let api = |status| {
+            LlmProviderError::Anthropic(AnthropicError::Api {
+                status,
+                message: String::new(),
+            })
+        };
+        assert!(is_unauthorized(&api(StatusCode::UNAUTHORIZED)));
+        assert!(!is_unauthorized(&api(StatusCode::FORBIDDEN)));
+        assert!(!is_unauthorized(&LlmProviderError::UnsupportedProvider {
+            provider: "x".to_string()
+        }));
+    }
 }
@@ -1,5 +1,10 @@
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use tokio::sync::Mutex;
 use tokio::time::{interval, MissedTickBehavior};
 use tokio_stream::StreamExt;
 use tokio_util::sync::CancellationToken;
@@ -7,13 +12,21 @@ use tokio_util::sync::CancellationToken;
 use crate::llm::{
     anthropic_types::{CreateMessageRequest, Message, MessageContent, MessageParam, StreamEvent},
     error::{LlmError, LlmProviderResult, LlmResult},
+    oauth::OAuthManager,
     provider_registry::ProviderRegistry,
-    retry::{error_retry_reason, is_retryable_error, retry_async, RetryConfig},
+    retry::{error_retry_reason, is_retryable_error, is_unauthorized, retry_async, RetryConfig},
     types::{EmbeddingRequest, EmbeddingResponse, LLMProviderConfig, OAuthRuntimeConfig},
 };
-use crate::storage::repositories::{AIModels, AuthType};
+use crate::storage::repositories::{AIModelConfig, AIModels, AuthType};
 use crate::storage::DatabaseManager;
 
+type ProviderStream =
+    Pin<Box<dyn tokio_stream::Stream<Item = LlmProviderResult<StreamEvent>> + Send>>;
+
+/// One OAuth refresh at a time per model, so parallel subtasks don't each spend the
+/// (single-use) refresh token
+static OAUTH_REFRESH_LOCKS: Lazy<DashMap<String, Arc<Mutex<()>>>> = Lazy::new(DashMap::new);
+
 pub struct LLMService {
     database: Arc<DatabaseManager>,
 }
@@ -28,13 +41,32 @@ impl LLMService {
         &self,
         model_id: &str,
     ) -> LlmResult<(LLMProviderConfig, String)> {
-        let model = AIModels::new(&self.database)
+        let mut model = AIModels::new(&self.database)
             .find_by_id(model_id)
             .await?
             .ok_or_else(|| LlmError::ModelNotFound {
                 model_id: model_id.to_string(),
             })?;
 
+        if model.auth_type == AuthType::OAuth {
+            if let Some(oauth) = &model.oauth_config {
+                if OAuthManager::new(Arc::clone(&self.database)).should_refresh_token(oauth) {
+                    let stale_token = oauth.access_token.clone();
+                    match self
+                        .refresh_oauth_token(model_id, stale_token.as_deref())
+                        .await
+                    {
+                        Ok(refreshed) => model = refreshed,
+                        // The token may still be usable for a while; the expiry check below
+                        // decides whether the call goes ahead.
+                        Err(err) => {
+                            tracing::warn!("OAuth token refresh for {} failed: {}", model_id, err)
+                        }
+                    }
+                }
+            }
+        }
+
         let provider_type = model.provider.as_str().to_string();
 
         if !ProviderRegistry::global().supports(&provider_type) {
@@ -70,9 +102,8 @@ impl LLMService {
                     let now = chrono::Utc::now().timestamp();
                     if now >= expires_at {
                         return Err(LlmError::Configuration {
-                            message:
-                                "OAuth access token has expired. Please refresh or re-authorize."
-                                    .to_string(),
+                            message: "OAuth access token has expired and could not be refreshed. Please re-authorize."
+                                .to_string(),
                         });
                     }
                 }
@@ -106,18 +137,101 @@ impl LLMService {
         Ok((config, model.model))
     }
 
+    /// Refresh and persist the OAuth tokens of `model_id`, returning the updated model.
+    ///
+    /// `stale_access_token` is the token the caller found expiring or saw rejected. The stored
+    /// config is re-read under the per-model lock, so if another caller rotated the token in
+    /// the meantime it is reused instead of refreshing again.
+    async fn refresh_oauth_token(
+        &self,
+        model_id: &str,
+        stale_access_token: Option<&str>,
+    ) -> LlmResult<AIModelConfig> {
+        let lock = Arc::clone(&OAUTH_REFRESH_LOCKS.entry(model_id.to_string()).or_default());
+        let _guard = lock.lock().await;
+
+        let ai_models = AIModels::new(&self.database);
+        let mut model =
+            ai_models
+                .find_by_id(model_id)
+                .await?
+                .ok_or_else(|| LlmError::ModelNotFound {
+                    model_id: model_id.to_string(),
+                })?;
+        let manager = OAuthManager::new(Arc::clone(&self.database));
+        let oauth = model
+            .oauth_config
+            .as_mut()
+            .ok_or_else(|| LlmError::Configuration {
+                message: "OAuth configuration is required for OAuth models".to_string(),
+            })?;
+
+        if oauth.access_token.as_deref() != stale_access_token
+            && !manager.should_refresh_token(oauth)
+        {
+            return Ok(model);
+        }
+
+        manager
+            .refresh_token(oauth)
+            .await
+            .map_err(|err| LlmError::Configuration {
+                message: format!("OAuth token refresh failed: {err}. Please re-authorize."),
+            })?;
+        ai_models.update_oauth_config(model_id, oauth).await?;
+        tracing::info!("🔑 Refreshed OAuth access token for model {}", model_id);
+
+        Ok(model)
+    }
+
+    /// Refresh the token a provider rejected with 401 and rebuild the config with it.
+    async fn refresh_rejected_token(
+        &self,
+        model_id: &str,
+        config: &LLMProviderConfig,
+    ) -> LlmResult<(LLMProviderConfig, String)> {
+        tracing::warn!(
+            "OAuth access token for {} was rejected, refreshing",
+            model_id
+        );
+        let rejected = config
+            .oauth_config
+            .as_ref()
+            .map(|oauth| oauth.access_token.as_str());
+        self.refresh_oauth_token(model_id, rejected).await?;
+        self.get_provider_config_and_model(model_id).await
+    }
+
     /// Non-streaming call with automatic retry
     pub async fn call(&self, request: CreateMessageRequest) -> LlmResult<Message> {
         self.validate_request(&request)?;
 
         let (config, model_name) = self.get_provider_config_and_model(&request.model).await?;
+        let model_id = request.model.clone();
+        let retry_request = config.oauth_config.is_some().then(|| request.clone());
+
+        match self.call_with_config(&config, model_name, request).await {
+            Err(err) if is_oauth_rejection(&config, &err) => {
+                let (config, model_name) = self.refresh_rejected_token(&model_id, &config).await?;
+                let request = retry_request.expect("OAuth requests keep a copy for retry");
+                self.call_with_config(&config, model_name, request).await
+            }
+            result => result,
+        }
+    }
 
+    async fn call_with_config(
+        &self,
+        config: &LLMProviderConfig,
+        model_name: String,
+        request: CreateMessageRequest,
+    ) -> LlmResult<Message> {
         let provider = ProviderRegistry::global()
             .create(config.clone())
             .map_err(LlmError::from)?;
 
         let mut actual_request = request;
-        actual_request.model = model_name.clone();
+        actual_request.model = model_name;
 
         // Anthropic provider automatically applies prompt cache optimization
         if config.provider_type == "anthropic" {
@@ -125,6 +239,7 @@ impl LLMService {
         }
 
         let retry_config = RetryConfig::default();
+        let uses_oauth = config.oauth_config.is_some();
 
         let result = retry_async(
             retry_config,
@@ -134,6 +249,10 @@ impl LLMService {
                 async move { provider.call(req).await }
             },
             |e| {
+                // A rejected OAuth token won't recover by itself; `call` refreshes it instead
+                if uses_oauth && is_unauthorized(e) {
+                    return false;
+                }
                 let retryable = is_retryable_error(e);
                 if retryable {
                     tracing::debug!(
@@ -162,25 +281,20 @@ impl LLMService {
         self.validate_request(&request)?;
 
         let (config, model_name) = self.get_provider_config_and_model(&request.model).await?;
+        let model_id = request.model.clone();
+        let retry_request = config.oauth_config.is_some().then(|| request.clone());
 
         tracing::info!("🚀 Starting LLM stream call: model={}", model_name);
 
-        let provider = ProviderRegistry::global()
-            .create(config.clone())
-            .map_err(LlmError::from)?;
-
         let model_for_logs = model_name.clone();
-        let mut actual_request = request;
-        actual_request.model = model_name;
-
-        if config.provider_type == "anthropic" {
-            actual_request = crate::llm::providers::anthropic::apply_prompt_caching(actual_request);
-        }
-
-        let stream = provider
-            .call_stream(actual_request)
-            .await
-            .map_err(LlmError::from)?;
+        let stream = match self.open_stream(&config, model_name, request).await {
+            Err(err) if is_oauth_rejection(&config, &err) => {
+                let (config, model_name) = self.refresh_rejected_token(&model_id, &config).await?;
+                let request = retry_request.expect("OAuth requests keep a copy for retry");
+                self.open_stream(&config, model_name, request).await?
+            }
+            result => result?,
+        };
 
         let stream_with_cancel = tokio_stream::wrappers::ReceiverStream::new({
             let (tx, rx) = tokio::sync::mpsc::channel(10);
@@ -266,21 +380,67 @@ impl LLMService {
         Ok(stream_with_cancel)
     }
 
+    async fn open_stream(
+        &self,
+        config: &LLMProviderConfig,
+        model_name: String,
+        request: CreateMessageRequest,
+    ) -> LlmResult<ProviderStream> {
+        let provider = ProviderRegistry::global()
+            .create(config.clone())
+            .map_err(LlmError::from)?;
+
+        let mut actual_request = request;
+        actual_request.model = model_name;
+
+        if config.provider_type == "anthropic" {
+            actual_request = crate::llm::providers::anthropic::apply_prompt_caching(actual_request);
+        }
+
+        provider
+            .call_stream(actual_request)
+            .await
+            .map_err(LlmError::from)
+    }
+
     /// Embedding call with automatic retry
     pub async fn create_embeddings(
         &self,
         request: EmbeddingRequest,
     ) -> LlmResult<EmbeddingResponse> {
         let (config, model_name) = self.get_provider_config_and_model(&request.model).await?;
+        let model_id = request.model.clone();
+        let retry_request = config.oauth_config.is_some().then(|| request.clone());
+
+        match self
+            .create_embeddings_with_config(&config, model_name, request)
+            .await
+        {
+            Err(err) if is_oauth_rejection(&config, &err) => {
+                let (config, model_name) = self.refresh_rejected_token(&model_id, &config).await?;
+                let request = retry_request.expect("OAuth requests keep a copy for retry");
+                self.create_embeddings_with_config(&config, model_name, request)
+                    .await
+            }
+            result => result,
+        }
+    }
 
+    async fn create_embeddings_with_config(
+        &self,
+        config: &LLMProviderConfig,
+        model_name: String,
+        request: EmbeddingRequest,
+    ) -> LlmResult<EmbeddingResponse> {
         let provider = ProviderRegistry::global()
-            .create(config)
+            .create(config.clone())
             .map_err(LlmError::from)?;
 
         let mut actual_request = request;
         actual_request.model = model_name;
 
         let retry_config = RetryConfig::default();
+        let uses_oauth = config.oauth_config.is_some();
 
         let result = retry_async(
             retry_config,
@@ -290,6 +450,9 @@ impl LLMService {
                 async move { provider.create_embeddings(req).await }
             },
             |e| {
+                if uses_oauth && is_unauthorized(e) {
+                    return false;
+                }
                 let retryable = is_retryable_error(e);
                 if retryable {
                     tracing::debug!(
@@ -377,6 +540,10 @@ impl LLMService {
     }
 }
 
+fn is_oauth_rejection(config: &LLMProviderConfig, err: &LlmError) -> bool {
+    config.oauth_config.is_some() && err.as_provider().is_some_and(is_unauthorized)
+}
+
 fn stream_event_kind(event: &StreamEvent) -> &'static str {
     use crate::llm::anthropic_types::{ContentBlockStart, ContentDelta, StreamEvent};
 
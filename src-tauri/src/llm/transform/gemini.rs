@@ -0,0 +1,446 @@
+//! Gemini Format Converter
+//!
+//!
+//! ## Core Conversion Logic
+//!
+//! ### Message Role Mapping
+//! - Anthropic `user` → Gemini `user`
+//! - Anthropic `assistant` → Gemini `model`
+//!
+//! ### Tool Call Mapping
+//! - Anthropic `tool_use` (sent by assistant) → Gemini `functionCall` part
+//! - Anthropic `tool_result` (returned by user) → Gemini `functionResponse` part
+//!
+//! ### Special Handling
+//! 1. `functionResponse` is matched by function name, so tool names are looked up from the
+//!    preceding `tool_use` blocks
+//! 2. Gemini thought signatures are stored on `thinking` blocks (provider `gemini`) and must be
+//!    sent back on the part that followed them, otherwise multi-turn function calling fails
+//! 3. Consecutive contents with the same role are merged; Gemini expects alternating turns
+//! 4. Tool schemas are reduced to the OpenAPI subset Gemini accepts
+
+use std::collections::HashMap;
+
+use crate::llm::anthropic_types::*;
+use serde_json::{json, Map, Value as JsonValue};
+
+/// Provider identifier stored in `ReasoningBlockMetadata::provider`
+pub const GEMINI_PROVIDER_ID: &str = "gemini";
+
+// ============================================================
+// Main Conversion Functions
+// ============================================================
+
+/// Convert Anthropic messages to Gemini `contents`
+pub fn convert_to_gemini_contents(messages: &[MessageParam]) -> Vec<JsonValue> {
+    let mut tool_names: HashMap<&str, &str> = HashMap::new();
+    let mut contents: Vec<JsonValue> = Vec::new();
+
+    for msg in messages {
+        let parts = match &msg.content {
+            MessageContent::Text(text) => vec![json!({ "text": text })],
+            MessageContent::Blocks(blocks) => match msg.role {
+                MessageRole::User => user_parts(blocks, &tool_names),
+                MessageRole::Assistant => {
+                    for block in blocks {
+                        if let ContentBlock::ToolUse { id, name, .. } = block {
+                            tool_names.insert(id.as_str(), name.as_str());
+                        }
+                    }
+                    model_parts(blocks)
+                }
+            },
+        };
+        if parts.is_empty() {
+            continue;
+        }
+
+        let role = role_to_string(msg.role);
+        match contents.last_mut() {
+            Some(last) if last["role"] == role => {
+                if let Some(existing) = last["parts"].as_array_mut() {
+                    existing.extend(parts);
+                }
+            }
+            _ => contents.push(json!({ "role": role, "parts": parts })),
+        }
+    }
+
+    contents
+}
+
+/// Build Gemini `systemInstruction` from the system prompt and developer context
+pub fn build_gemini_system_instruction(req: &CreateMessageRequest) -> Option<JsonValue> {
+    let mut sections = Vec::new();
+    match &req.system {
+        Some(SystemPrompt::Text(text)) => sections.push(text.trim().to_string()),
+        Some(SystemPrompt::Blocks(blocks)) => {
+            sections.extend(blocks.iter().map(|b| b.text.trim().to_string()))
+        }
+        None => {}
+    }
+    if let Some(developer_context) = &req.developer_context {
+        sections.extend(developer_context.iter().map(|c| c.trim().to_string()));
+    }
+    sections.retain(|s| !s.is_empty());
+
+    (!sections.is_empty()).then(|| json!({ "parts": [{ "text": sections.join("\n\n") }] }))
+}
+
+/// Convert Anthropic tools to a Gemini `tools` array with one `functionDeclarations` entry
+pub fn convert_to_gemini_tools(tools: &[Tool]) -> JsonValue {
+    let declarations: Vec<JsonValue> = tools
+        .iter()
+        .map(|tool| {
+            let mut decl = json!({ "name": tool.name, "description": tool.description });
+            let has_properties = tool
+                .input_schema
+                .get("properties")
+                .and_then(|p| p.as_object())
+                .is_some_and(|p| !p.is_empty());
+            // Gemini rejects an object schema without properties
+            if has_properties {
+                decl["parameters"] = sanitize_gemini_schema(&tool.input_schema);
+            }
+            decl
+        })
+        .collect();
+    json!([{ "functionDeclarations": declarations }])
+}
+
+// ============================================================
+// User / Model Part Handling
+// ============================================================
+
+fn user_parts(blocks: &[ContentBlock], tool_names: &HashMap<&str, &str>) -> Vec<JsonValue> {
+    let mut responses = Vec::new();
+    let mut others = Vec::new();
+
+    for block in blocks {
+        match block {
+            ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } => {
+                let mut text_parts = Vec::new();
+                match content {
+                    Some(ToolResultContent::Text(text)) => text_parts.push(text.as_str()),
+                    Some(ToolResultContent::Blocks(blocks)) => {
+                        for b in blocks {
+                            match b {
+                                ToolResultBlock::Text { text } => text_parts.push(text.as_str()),
+                                // Images can't go inside functionResponse; send them alongside
+                                ToolResultBlock::Image { source } => {
+                                    others.push(convert_image_to_gemini(source))
+                                }
+                            }
+                        }
+                    }
+                    None => {}
+                }
+
+                let output = text_parts.join("\n");
+                let response = if is_error.unwrap_or(false) {
+                    json!({ "error": output })
+                } else {
+                    json!({ "content": output })
+                };
+                let name = tool_names
+                    .get(tool_use_id.as_str())
+                    .copied()
+                    .unwrap_or(tool_use_id.as_str());
+                responses.push(json!({
+                    "functionResponse": { "name": name, "response": response }
+                }));
+            }
+            ContentBlock::Text { text, .. } => others.push(json!({ "text": text })),
+            ContentBlock::Image { source, .. } => others.push(convert_image_to_gemini(source)),
+            ContentBlock::ToolUse { .. } | ContentBlock::Thinking { .. } => {}
+        }
+    }
+
+    // Function responses must come first, right after the model's function calls
+    responses.extend(others);
+    responses
+}
+
+fn model_parts(blocks: &[ContentBlock]) -> Vec<JsonValue> {
+    let mut parts = Vec::new();
+    let mut pending_signature: Option<&str> = None;
+
+    for block in blocks {
+        let mut part = match block {
+            ContentBlock::Thinking {
+                reasoning_metadata, ..
+            } => {
+                // Thought text is not replayed; only the signature matters to Gemini
+                if let Some(meta) = reasoning_metadata
+                    .as_ref()
+                    .filter(|m| m.provider.as_deref() == Some(GEMINI_PROVIDER_ID))
+                {
+                    pending_signature = meta.signature.as_deref().or(pending_signature);
+                }
+                continue;
+            }
+            ContentBlock::Text { text, .. } => {
+                if text.is_empty() {
+                    continue;
+                }
+                json!({ "text": text })
+            }
+            ContentBlock::ToolUse { name, input, .. } => {
+                let args = if input.is_object() {
+                    input.clone()
+                } else {
+                    json!({})
+                };
+                json!({ "functionCall": { "name": name, "args": args } })
+            }
+            ContentBlock::Image { .. } | ContentBlock::ToolResult { .. } => continue,
+        };
+
+        if let Some(signature) = pending_signature.take() {
+            part["thoughtSignature"] = json!(signature);
+        }
+        parts.push(part);
+    }
+
+    // A signature with nothing after it still has to be returned
+    if let Some(signature) = pending_signature {
+        parts.push(json!({ "text": "", "thoughtSignature": signature }));
+    }
+
+    parts
+}
+
+// ============================================================
+// Helper Conversion Functions
+// ============================================================
+
+/// Convert image to Gemini part
+fn convert_image_to_gemini(source: &ImageSource) -> JsonValue {
+    match source {
+        ImageSource::Base64 { media_type, data } => json!({
+            "inlineData": { "mimeType": media_type, "data": data }
+        }),
+        // Gemini only fetches gs:// and uploaded file URIs, so pass web URLs as text
+        ImageSource::Url { url } => json!({ "text": format!("(Image: {url})") }),
+        ImageSource::FileId { .. } => json!({
+            "text": "(File content not supported in Gemini format)"
+        }),
+    }
+}
+
+/// Convert role enum to string
+fn role_to_string(role: MessageRole) -> &'static str {
+    match role {
+        MessageRole::User => "user",
+        MessageRole::Assistant => "model",
+    }
+}
+
+/// Keys of the OpenAPI schema subset accepted by Gemini function declarations
+const GEMINI_SCHEMA_KEYS: &[&str] = &[
+    "type",
+    "format",
+    "title",
+    "description",
+    "nullable",
+    "enum",
+    "items",
+    "properties",
+    "required",
+    "anyOf",
+    "minItems",
+    "maxItems",
+    "minLength",
+    "maxLength",
+    "minimum",
+    "maximum",
+    "minProperties",
+    "maxProperties",
+    "pattern",
+    "propertyOrdering",
+];
+
+/// Reduce a JSON Schema to what Gemini accepts: unknown keywords are dropped,
+/// `type: [T, "null"]` becomes `type: T, nullable: true` and `const` becomes a one-value enum.
+pub fn sanitize_gemini_schema(schema: &JsonValue) -> JsonValue {
+    let Some(obj) = schema.as_object() else {
+        return schema.clone();
+    };
+
+    let mut out = Map::new();
+    for (key, value) in obj {
+        match key.as_str() {
+            "type" => match value {
+                JsonValue::Array(types) => {
+                    let non_null: Vec<&JsonValue> = types
+                        .iter()
+                        .filter(|t| t.as_str() != Some("null"))
+                        .collect();
+                    if non_null.len() < types.len() {
+                        out.insert("nullable".to_string(), json!(true));
+                    }
+                    if let Some(first) = non_null.first() {
+                        out.insert("type".to_string(), (*first).clone());
+                    }
+                }
+                _ => {
+                    out.insert(key.clone(), value.clone());
+                }
+            },
+            "const" => {
+                out.insert("enum".to_string(), json!([value]));
+            }
+            "properties" => {
+                let props = value
+                    .as_object()
+                    .map(|props| {
+                        props
+                            .iter()
+                            .map(|(k, v)| (k.clone(), sanitize_gemini_schema(v)))
+                            .collect::<Map<_, _>>()
+                    })
+                    .unwrap_or_default();
+                out.insert(key.clone(), JsonValue::Object(props));
+            }
+            "items" => {
+                out.insert(key.clone(), sanitize_gemini_schema(value));
+            }
+            "anyOf" | "oneOf" => {
+                let variants = value
+                    .as_array()
+                    .map(|v| v.iter().map(sanitize_gemini_schema).collect::<Vec<_>>())
+                    .unwrap_or_default();
+                out.insert("anyOf".to_string(), JsonValue::Array(variants));
+            }
+            // Gemini only supports string enums
+            "enum" => {
+                let values = value
+                    .as_array()
+                    .map(|v| {
+                        v.iter()
+                            .filter(|e| !e.is_null())
+                            .map(|e| match e {
+                                JsonValue::String(_) => e.clone(),
+                                other => json!(other.to_string()),
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                out.insert(key.clone(), JsonValue::Array(values));
+            }
+            k if GEMINI_SCHEMA_KEYS.contains(&k) => {
+                out.insert(key.clone(), value.clone());
+            }
+            _ => {}
+        }
+    }
+
+    // `required` may only name declared properties
+    if let (Some(required), Some(props)) = (
+        out.get("required").and_then(|r| r.as_array()).cloned(),
+        out.get("properties").and_then(|p| p.as_object()),
+    ) {
+        let required: Vec<JsonValue> = required
+            .into_iter()
+            .filter(|r| r.as_str().is_some_and(|name| props.contains_key(name)))
+            .collect();
+        out.insert("required".to_string(), JsonValue::Array(required));
+    }
+
+    JsonValue::Object(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gemini_thinking(signature: &str) -> ContentBlock {
+        ContentBlock::Thinking {
+            thinking: "considering".to_string(),
+            signature: Some(signature.to_string()),
+            reasoning_metadata: Some(ReasoningBlockMetadata {
+                signature: Some(signature.to_string()),
+                provider: Some(GEMINI_PROVIDER_ID.to_string()),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_tool_round_trip_with_thought_signature() {
+        let messages = vec![
+            MessageParam::user("List files"),
+            MessageParam::assistant_blocks(vec![
+                gemini_thinking("sig-1"),
+                ContentBlock::ToolUse {
+                    id: "call_1".to_string(),
+                    name: "list_files".to_string(),
+                    input: json!({ "path": "." }),
+                },
+            ]),
+            MessageParam::user_blocks(vec![
+                ContentBlock::ToolResult {
+                    tool_use_id: "call_1".to_string(),
+                    content: Some(ToolResultContent::Text("a.rs".to_string())),
+                    is_error: None,
+                },
+                ContentBlock::text("Now read it"),
+            ]),
+        ];
+
+        let contents = convert_to_gemini_contents(&messages);
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[1]["role"], "model");
+        let call = &contents[1]["parts"][0];
+        assert_eq!(call["functionCall"]["name"], "list_files");
+        assert_eq!(call["thoughtSignature"], "sig-1");
+        assert_eq!(contents[1]["parts"].as_array().unwrap().len(), 1);
+
+        let parts = contents[2]["parts"].as_array().unwrap();
+        assert_eq!(parts[0]["functionResponse"]["name"], "list_files");
+        assert_eq!(parts[0]["functionResponse"]["response"]["content"], "a.rs");
+        assert_eq!(parts[1]["text"], "Now read it");
+    }
+
+    #[test]
+    fn test_consecutive_roles_are_merged() {
+        let messages = vec![
+            MessageParam::user("one"),
+            MessageParam::user("two"),
+            MessageParam::assistant("three"),
+        ];
+        let contents = convert_to_gemini_contents(&messages);
+        assert_eq!(contents.len(), 2);
+        assert_eq!(contents[0]["parts"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_sanitize_schema() {
+        let schema = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "path": { "type": ["string", "null"], "default": "." },
+                "mode": { "const": "fast" },
+                "limit": { "type": "integer", "enum": [1, 2] }
+            },
+            "required": ["path", "missing"]
+        });
+
+        let out = sanitize_gemini_schema(&schema);
+        assert!(out.get("$schema").is_none());
+        assert!(out.get("additionalProperties").is_none());
+        assert_eq!(
+            out["properties"]["path"],
+            json!({ "type": "string", "nullable": true })
+        );
+        assert_eq!(out["properties"]["mode"], json!({ "enum": ["fast"] }));
+        assert_eq!(out["properties"]["limit"]["enum"], json!(["1", "2"]));
+        assert_eq!(out["required"], json!(["path"]));
+    }
+}
@@ -14,9 +14,10 @@
 //! | `gemini` | Google Gemini API | Cline: transform/gemini-format.ts |
 //! | `reasoning` | Reasoning/Thinking context | opencode-dev: transform.ts |
 
+pub mod gemini;
 pub mod openai;
 pub mod reasoning;
-// pub mod gemini;  // TODO: Phase 2
 
+pub use gemini::*;
 pub use openai::*;
 pub use reasoning::*;
@@ -1,20 +1,24 @@
 use crate::lsp::language::language_id_for_path;
 use crate::lsp::types::{
-    LspDocumentSymbol, LspFileDiagnostics, LspHoverResult, LspLocation, LspServerStatus,
-    ResolvedServerConfig,
+    LspCodeAction, LspDocumentChange, LspDocumentSymbol, LspFileDiagnostics, LspHoverResult,
+    LspLocation, LspServerStatus, LspSignatureHelp, LspWorkspaceEdit, ResolvedServerConfig,
 };
 use dashmap::DashMap;
 use lsp_types::{
     request::{
-        DocumentSymbolRequest, GotoDefinition, HoverRequest, References, Request,
-        WorkspaceSymbolRequest,
+        CodeActionRequest, CodeActionResolveRequest, DocumentSymbolRequest, Formatting,
+        GotoDefinition, GotoImplementation, GotoTypeDefinition, HoverRequest, RangeFormatting,
+        References, Rename, Request, SignatureHelpRequest, WorkspaceSymbolRequest,
     },
-    ClientCapabilities, Diagnostic, DidChangeConfigurationParams, DidChangeTextDocumentParams,
-    DidOpenTextDocumentParams, DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams,
-    HoverParams, InitializeParams, PartialResultParams, Position, PublishDiagnosticsParams,
-    ReferenceContext, ReferenceParams, TextDocumentContentChangeEvent, TextDocumentIdentifier,
-    TextDocumentItem, TextDocumentPositionParams, Uri, VersionedTextDocumentIdentifier,
-    WorkDoneProgressParams, WorkspaceFolder, WorkspaceSymbolParams,
+    ClientCapabilities, CodeActionContext, CodeActionOrCommand, CodeActionParams,
+    CodeActionTriggerKind, Diagnostic, DidChangeConfigurationParams, DidChangeTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentFormattingParams, DocumentRangeFormattingParams,
+    DocumentSymbolParams, DocumentSymbolResponse, FormattingOptions, GotoDefinitionParams,
+    GotoDefinitionResponse, HoverParams, InitializeParams, PartialResultParams, Position,
+    PublishDiagnosticsParams, Range, ReferenceContext, ReferenceParams, RenameParams,
+    SignatureHelpParams, TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem,
+    TextDocumentPositionParams, TextEdit, Uri, VersionedTextDocumentIdentifier,
+    WorkDoneProgressParams, WorkspaceEdit, WorkspaceFolder, WorkspaceSymbolParams,
 };
 use serde::de::DeserializeOwned;
 use serde_json::{json, Value};
@@ -24,7 +28,7 @@ use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 use std::sync::Arc;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::{oneshot, Mutex};
+use tokio::sync::{oneshot, Mutex, Notify};
 
 #[derive(Debug, thiserror::Error)]
 pub enum LspClientError {
@@ -55,12 +59,16 @@ type Result<T> = std::result::Result<T, LspClientError>;
 pub struct LspClient {
     config: ResolvedServerConfig,
     child: Mutex<Option<Child>>,
-    stdin: Mutex<Option<tokio::process::ChildStdin>>,
+    stdin: Arc<Mutex<Option<tokio::process::ChildStdin>>>,
     pending: Arc<DashMap<i64, oneshot::Sender<Result<Value>>>>,
     next_id: AtomicI64,
     connected: Arc<AtomicBool>,
     initialized: Arc<AtomicBool>,
     diagnostics: Arc<DashMap<String, Vec<Diagnostic>>>,
+    /// Number of `publishDiagnostics` received per path, and the document version the
+    /// latest one was computed for (when the server says)
+    diagnostics_versions: Arc<DashMap<String, (u64, Option<i32>)>>,
+    diagnostics_published: Arc<Notify>,
     open_documents: Arc<Mutex<HashMap<String, i32>>>,
     last_error: Arc<Mutex<Option<String>>>,
 }
@@ -69,6 +77,7 @@ impl LspClient {
     pub async fn spawn(config: ResolvedServerConfig) -> Result<Self> {
         let mut cmd = Command::new(&config.command);
         cmd.args(&config.args)
+            .envs(&config.env)
             .current_dir(&config.root)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
@@ -94,12 +103,14 @@ impl LspClient {
         let client = Self {
             config,
             child: Mutex::new(Some(child)),
-            stdin: Mutex::new(Some(stdin)),
+            stdin: Arc::new(Mutex::new(Some(stdin))),
             pending: Arc::new(DashMap::new()),
             next_id: AtomicI64::new(1),
             connected: Arc::new(AtomicBool::new(true)),
             initialized: Arc::new(AtomicBool::new(false)),
             diagnostics: Arc::new(DashMap::new()),
+            diagnostics_versions: Arc::new(DashMap::new()),
+            diagnostics_published: Arc::new(Notify::new()),
             open_documents: Arc::new(Mutex::new(HashMap::new())),
             last_error: Arc::new(Mutex::new(None)),
         };
@@ -110,6 +121,14 @@ impl LspClient {
         Ok(client)
     }
 
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    pub fn config(&self) -> &ResolvedServerConfig {
+        &self.config
+    }
+
     pub fn status(&self) -> LspServerStatus {
         let open_documents = match self.open_documents.try_lock() {
             Ok(docs) => docs.len(),
@@ -126,7 +145,7 @@ impl LspClient {
             }
         };
         LspServerStatus {
-            server_id: self.config.server_id.as_str().to_string(),
+            server_id: self.config.server_id.clone(),
             root: self.config.root.to_string_lossy().to_string(),
             command: format!("{} {}", self.config.command, self.config.args.join(" "))
                 .trim()
@@ -221,30 +240,47 @@ impl LspClient {
         path: &std::path::Path,
         position: Position,
     ) -> Result<Vec<LspLocation>> {
+        let params = self.goto_params(path, position).await?;
+        let response: Option<GotoDefinitionResponse> =
+            self.request(GotoDefinition::METHOD, params).await?;
+        Ok(goto_response_locations(response))
+    }
+
+    pub async fn implementation(
+        &self,
+        path: &std::path::Path,
+        position: Position,
+    ) -> Result<Vec<LspLocation>> {
+        let params = self.goto_params(path, position).await?;
+        let response: Option<GotoDefinitionResponse> =
+            self.request(GotoImplementation::METHOD, params).await?;
+        Ok(goto_response_locations(response))
+    }
+
+    pub async fn type_definition(
+        &self,
+        path: &std::path::Path,
+        position: Position,
+    ) -> Result<Vec<LspLocation>> {
+        let params = self.goto_params(path, position).await?;
+        let response: Option<GotoDefinitionResponse> =
+            self.request(GotoTypeDefinition::METHOD, params).await?;
+        Ok(goto_response_locations(response))
+    }
+
+    async fn goto_params(
+        &self,
+        path: &std::path::Path,
+        position: Position,
+    ) -> Result<GotoDefinitionParams> {
         let uri = self.ensure_document(path).await?;
-        let params = GotoDefinitionParams {
+        Ok(GotoDefinitionParams {
             text_document_position_params: TextDocumentPositionParams {
                 text_document: TextDocumentIdentifier { uri },
                 position,
             },
             work_done_progress_params: WorkDoneProgressParams::default(),
             partial_result_params: PartialResultParams::default(),
-        };
-        let response: Option<lsp_types::GotoDefinitionResponse> =
-            self.request(GotoDefinition::METHOD, params).await?;
-        Ok(match response {
-            Some(lsp_types::GotoDefinitionResponse::Scalar(location)) => vec![location.into()],
-            Some(lsp_types::GotoDefinitionResponse::Array(items)) => {
-                items.into_iter().map(Into::into).collect()
-            }
-            Some(lsp_types::GotoDefinitionResponse::Link(items)) => items
-                .into_iter()
-                .map(|item| LspLocation {
-                    path: uri_to_path_string(item.target_uri.as_str()),
-                    range: item.target_selection_range.into(),
-                })
-                .collect(),
-            None => Vec::new(),
         })
     }
 
@@ -271,6 +307,158 @@ impl LspClient {
         Ok(locations.into_iter().map(Into::into).collect())
     }
 
+    pub async fn rename(
+        &self,
+        path: &std::path::Path,
+        position: Position,
+        new_name: String,
+    ) -> Result<LspWorkspaceEdit> {
+        let uri = self.ensure_document(path).await?;
+        let params = RenameParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            new_name,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        let response: Option<WorkspaceEdit> = self.request(Rename::METHOD, params).await?;
+        Ok(response.map(Into::into).unwrap_or_default())
+    }
+
+    /// Code actions for `range`, offering fixes for the diagnostics that overlap it.
+    pub async fn code_actions(
+        &self,
+        path: &std::path::Path,
+        range: Range,
+    ) -> Result<Vec<LspCodeAction>> {
+        let actions = self.raw_code_actions(path, range).await?;
+        Ok(actions.into_iter().map(code_action_from).collect())
+    }
+
+    /// The code action titled `title`, with its edit resolved when the server defers it.
+    pub async fn code_action(
+        &self,
+        path: &std::path::Path,
+        range: Range,
+        title: &str,
+    ) -> Result<Option<LspCodeAction>> {
+        let Some(item) = self
+            .raw_code_actions(path, range)
+            .await?
+            .into_iter()
+            .find(|item| code_action_title(item) == title)
+        else {
+            return Ok(None);
+        };
+        let item = match item {
+            CodeActionOrCommand::CodeAction(action)
+                if action.edit.is_none() && action.data.is_some() =>
+            {
+                let resolved = self
+                    .request(CodeActionResolveRequest::METHOD, action)
+                    .await?;
+                CodeActionOrCommand::CodeAction(resolved)
+            }
+            other => other,
+        };
+        Ok(Some(code_action_from(item)))
+    }
+
+    async fn raw_code_actions(
+        &self,
+        path: &std::path::Path,
+        range: Range,
+    ) -> Result<Vec<CodeActionOrCommand>> {
+        let uri = self.ensure_document(path).await?;
+        let diagnostics = self
+            .diagnostics_for_path(path)
+            .into_iter()
+            .filter(|diagnostic| {
+                diagnostic.range.start <= range.end && range.start <= diagnostic.range.end
+            })
+            .collect();
+        let params = CodeActionParams {
+            text_document: TextDocumentIdentifier { uri },
+            range,
+            context: CodeActionContext {
+                diagnostics,
+                only: None,
+                trigger_kind: Some(CodeActionTriggerKind::INVOKED),
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let response: Option<Vec<CodeActionOrCommand>> =
+            self.request(CodeActionRequest::METHOD, params).await?;
+        Ok(response.unwrap_or_default())
+    }
+
+    /// Formatting edits for the whole document, or only `range` when given.
+    pub async fn formatting(
+        &self,
+        path: &std::path::Path,
+        range: Option<Range>,
+    ) -> Result<LspWorkspaceEdit> {
+        let uri = self.ensure_document(path).await?;
+        let text_document = TextDocumentIdentifier { uri };
+        let options = FormattingOptions {
+            tab_size: 4,
+            insert_spaces: true,
+            ..Default::default()
+        };
+        let response: Option<Vec<TextEdit>> = match range {
+            Some(range) => {
+                let params = DocumentRangeFormattingParams {
+                    text_document,
+                    range,
+                    options,
+                    work_done_progress_params: WorkDoneProgressParams::default(),
+                };
+                self.request(RangeFormatting::METHOD, params).await?
+            }
+            None => {
+                let params = DocumentFormattingParams {
+                    text_document,
+                    options,
+                    work_done_progress_params: WorkDoneProgressParams::default(),
+                };
+                self.request(Formatting::METHOD, params).await?
+            }
+        };
+        let edits = response.unwrap_or_default();
+        if edits.is_empty() {
+            return Ok(LspWorkspaceEdit::default());
+        }
+        Ok(LspWorkspaceEdit {
+            changes: vec![LspDocumentChange::Edit {
+                path: normalize_path_string(path),
+                edits: edits.into_iter().map(Into::into).collect(),
+            }],
+        })
+    }
+
+    pub async fn signature_help(
+        &self,
+        path: &std::path::Path,
+        position: Position,
+    ) -> Result<Option<LspSignatureHelp>> {
+        let uri = self.ensure_document(path).await?;
+        let params = SignatureHelpParams {
+            context: None,
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        let response: Option<lsp_types::SignatureHelp> =
+            self.request(SignatureHelpRequest::METHOD, params).await?;
+        Ok(response
+            .filter(|help| !help.signatures.is_empty())
+            .map(Into::into))
+    }
+
     pub fn diagnostics_for_path(&self, path: &std::path::Path) -> Vec<Diagnostic> {
         let key = normalize_path_string(path);
         self.diagnostics
@@ -279,6 +467,45 @@ impl LspClient {
             .unwrap_or_default()
     }
 
+    /// Whether the server has published diagnostics for `path` since it was opened.
+    pub fn has_diagnostics(&self, path: &std::path::Path) -> bool {
+        self.diagnostics.contains_key(&normalize_path_string(path))
+    }
+
+    /// Send the file's current content and wait for the server to publish diagnostics
+    /// for it. `None` when nothing arrives within `timeout`.
+    ///
+    /// Publishes for an older version of the document are skipped; unversioned ones
+    /// are taken as current, since the server gives nothing to compare.
+    pub async fn sync_diagnostics(
+        &self,
+        path: &std::path::Path,
+        timeout: std::time::Duration,
+    ) -> Result<Option<Vec<Diagnostic>>> {
+        let key = normalize_path_string(path);
+        let latest = |key: &str| {
+            self.diagnostics_versions
+                .get(key)
+                .map(|entry| *entry.value())
+                .unwrap_or((0, None))
+        };
+        let (before, _) = latest(&key);
+        let (_, version) = self.sync_document(path).await?;
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // Register before checking so a publish in between is not missed
+            let published = self.diagnostics_published.notified();
+            let (count, published_version) = latest(&key);
+            if count > before && published_version.is_none_or(|v| v == version) {
+                return Ok(Some(self.diagnostics_for_path(path)));
+            }
+            if tokio::time::timeout_at(deadline, published).await.is_err() {
+                return Ok(None);
+            }
+        }
+    }
+
     pub fn all_diagnostics(&self) -> Vec<LspFileDiagnostics> {
         self.diagnostics
             .iter()
@@ -316,7 +543,7 @@ impl LspClient {
         let params = InitializeParams {
             process_id: Some(std::process::id()),
             initialization_options: self.config.initialization_options.clone(),
-            capabilities: ClientCapabilities::default(),
+            capabilities: client_capabilities(),
             workspace_folders: Some(vec![WorkspaceFolder {
                 uri: root_uri,
                 name: self.config.root.to_string_lossy().to_string(),
@@ -340,14 +567,23 @@ impl LspClient {
     }
 
     async fn ensure_document(&self, path: &std::path::Path) -> Result<Uri> {
+        self.sync_document(path).await.map(|(uri, _)| uri)
+    }
+
+    /// Open the file, or send its current content if already open. Returns the
+    /// document version the server now has.
+    async fn sync_document(&self, path: &std::path::Path) -> Result<(Uri, i32)> {
         let path = normalize_path(path);
         let path_string = normalize_path_string(&path);
         let text = tokio::fs::read_to_string(&path).await?;
         let uri = file_uri(&path)?;
-        let language_id = language_id_for_path(&path).to_string();
+        let language_id = match &self.config.language_id {
+            Some(language_id) => language_id.clone(),
+            None => language_id_for_path(&path).to_string(),
+        };
 
         let mut docs = self.open_documents.lock().await;
-        if let Some(version) = docs.get_mut(&path_string) {
+        let version = if let Some(version) = docs.get_mut(&path_string) {
             *version += 1;
             self.notify(
                 "textDocument/didChange",
@@ -364,6 +600,7 @@ impl LspClient {
                 })?,
             )
             .await?;
+            *version
         } else {
             docs.insert(path_string, 0);
             self.notify(
@@ -378,8 +615,9 @@ impl LspClient {
                 })?,
             )
             .await?;
-        }
-        Ok(uri)
+            0
+        };
+        Ok((uri, version))
     }
 
     async fn request<T: serde::Serialize, R: DeserializeOwned>(
@@ -423,28 +661,43 @@ impl LspClient {
     }
 
     async fn write_message(&self, value: &Value) -> Result<()> {
-        let bytes = serde_json::to_vec(value)?;
-        let mut stdin = self.stdin.lock().await;
-        let Some(stdin) = stdin.as_mut() else {
-            return Err(LspClientError::NotConnected);
-        };
-        let header = format!("Content-Length: {}\r\n\r\n", bytes.len());
-        stdin.write_all(header.as_bytes()).await?;
-        stdin.write_all(&bytes).await?;
-        stdin.flush().await?;
-        Ok(())
+        write_to_stdin(&self.stdin, value).await
     }
 
     fn start_stdout_loop(&self, stdout: tokio::process::ChildStdout) {
+        let stdin = Arc::clone(&self.stdin);
         let pending = Arc::clone(&self.pending);
         let connected = Arc::clone(&self.connected);
         let diagnostics = Arc::clone(&self.diagnostics);
+        let diagnostics_versions = Arc::clone(&self.diagnostics_versions);
+        let diagnostics_published = Arc::clone(&self.diagnostics_published);
         let last_error = Arc::clone(&self.last_error);
         tokio::spawn(async move {
             let mut reader = BufReader::new(stdout);
             loop {
                 match read_message(&mut reader).await {
                     Ok(Some(value)) => {
+                        // Requests from the server carry an id too; their ids are unrelated
+                        // to ours and must be answered rather than matched.
+                        if let (Some(id), Some(method)) =
+                            (value.get("id"), value.get("method").and_then(Value::as_str))
+                        {
+                            let response = json!({
+                                "jsonrpc": "2.0",
+                                "id": id,
+                                "result": server_request_result(method, value.get("params")),
+                            });
+                            if let Err(err) = write_to_stdin(&stdin, &response).await {
+                                tracing::warn!(
+                                    target: "lsp",
+                                    "Failed to answer LSP server request {}: {}",
+                                    method,
+                                    err
+                                );
+                            }
+                            continue;
+                        }
+
                         if let Some(id) = value.get("id").and_then(|id| id.as_i64()) {
                             if let Some((_, tx)) = pending.remove(&id) {
                                 if let Some(error) = value.get("error") {
@@ -484,7 +737,12 @@ impl LspClient {
                                 ) {
                                     Ok(payload) => {
                                         let key = uri_to_path_string(payload.uri.as_str());
-                                        diagnostics.insert(key, payload.diagnostics);
+                                        diagnostics.insert(key.clone(), payload.diagnostics);
+                                        let mut latest =
+                                            diagnostics_versions.entry(key).or_insert((0, None));
+                                        latest.0 += 1;
+                                        latest.1 = payload.version;
+                                        diagnostics_published.notify_waiters();
                                     }
                                     Err(err) => {
                                         *last_error.lock().await = Some(err.to_string());
@@ -538,6 +796,135 @@ impl LspClient {
     }
 }
 
+async fn write_to_stdin(
+    stdin: &Mutex<Option<tokio::process::ChildStdin>>,
+    value: &Value,
+) -> Result<()> {
+    let bytes = serde_json::to_vec(value)?;
+    let mut stdin = stdin.lock().await;
+    let Some(stdin) = stdin.as_mut() else {
+        return Err(LspClientError::NotConnected);
+    };
+    let header = format!("Content-Length: {}\r\n\r\n", bytes.len());
+    stdin.write_all(header.as_bytes()).await?;
+    stdin.write_all(&bytes).await?;
+    stdin.flush().await?;
+    Ok(())
+}
+
+/// Reply to a server-initiated request. We have no settings to offer and apply edits
+/// ourselves, so everything gets an empty answer of the expected shape.
+fn server_request_result(method: &str, params: Option<&Value>) -> Value {
+    match method {
+        "workspace/configuration" => {
+            let items = params
+                .and_then(|params| params.get("items"))
+                .and_then(Value::as_array)
+                .map(Vec::len)
+                .unwrap_or(0);
+            Value::Array(vec![Value::Null; items])
+        }
+        "workspace/applyEdit" => json!({
+            "applied": false,
+            "failureReason": "edits are applied by the client on request",
+        }),
+        _ => Value::Null,
+    }
+}
+
+/// Advertise the editing features we handle so servers return literal code actions and
+/// workspace edits with resource operations.
+fn client_capabilities() -> ClientCapabilities {
+    let capabilities = json!({
+        "workspace": {
+            "applyEdit": false,
+            "workspaceEdit": {
+                "documentChanges": true,
+                "resourceOperations": ["create", "rename", "delete"],
+                "failureHandling": "abort"
+            }
+        },
+        "textDocument": {
+            "synchronization": { "didSave": false },
+            "publishDiagnostics": { "relatedInformation": true },
+            "hover": { "contentFormat": ["markdown", "plaintext"] },
+            "definition": { "linkSupport": true },
+            "typeDefinition": { "linkSupport": true },
+            "implementation": { "linkSupport": true },
+            "references": {},
+            "documentSymbol": { "hierarchicalDocumentSymbolSupport": true },
+            "rename": { "prepareSupport": false },
+            "formatting": {},
+            "rangeFormatting": {},
+            "signatureHelp": {
+                "signatureInformation": {
+                    "documentationFormat": ["markdown", "plaintext"],
+                    "parameterInformation": { "labelOffsetSupport": true },
+                    "activeParameterSupport": true
+                }
+            },
+            "codeAction": {
+                "isPreferredSupport": true,
+                "disabledSupport": true,
+                "dataSupport": true,
+                "resolveSupport": { "properties": ["edit"] },
+                "codeActionLiteralSupport": {
+                    "codeActionKind": {
+                        "valueSet": [
+                            "", "quickfix", "refactor", "refactor.extract", "refactor.inline",
+                            "refactor.rewrite", "source", "source.organizeImports", "source.fixAll"
+                        ]
+                    }
+                }
+            }
+        }
+    });
+    match serde_json::from_value(capabilities) {
+        Ok(capabilities) => capabilities,
+        Err(err) => {
+            tracing::warn!("invalid LSP client capabilities: {}", err);
+            ClientCapabilities::default()
+        }
+    }
+}
+
+fn code_action_title(item: &CodeActionOrCommand) -> &str {
+    match item {
+        CodeActionOrCommand::Command(command) => &command.title,
+        CodeActionOrCommand::CodeAction(action) => &action.title,
+    }
+}
+
+fn code_action_from(item: CodeActionOrCommand) -> LspCodeAction {
+    match item {
+        CodeActionOrCommand::Command(command) => LspCodeAction {
+            title: command.title,
+            kind: None,
+            is_preferred: false,
+            diagnostics: Vec::new(),
+            edit: None,
+            command: Some(command.command),
+            disabled_reason: None,
+        },
+        CodeActionOrCommand::CodeAction(action) => action.into(),
+    }
+}
+
+fn goto_response_locations(response: Option<GotoDefinitionResponse>) -> Vec<LspLocation> {
+    match response {
+        Some(GotoDefinitionResponse::Scalar(location)) => vec![location.into()],
+        Some(GotoDefinitionResponse::Array(items)) => items.into_iter().map(Into::into).collect(),
+        Some(GotoDefinitionResponse::Link(items)) => items
+            .into_iter()
+            .map(|item| LspLocation {
+                path: uri_to_path_string(item.target_uri.as_str()),
+                range: item.target_selection_range.into(),
+            })
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
 async fn read_message<R: AsyncRead + Unpin>(reader: &mut BufReader<R>) -> Result<Option<Value>> {
     let mut content_length = None::<usize>;
     loop {
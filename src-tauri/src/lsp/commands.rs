@@ -1,11 +1,14 @@
+use crate::agent::core::commands::TaskExecutorState;
+use crate::lsp::edit::apply_workspace_edit;
 use crate::lsp::manager::LspManager;
 use crate::lsp::types::{
-    LspDocumentSymbol, LspFileDiagnostics, LspHoverResult, LspLocation, LspServerStatus,
-    LspWorkspaceSymbol,
+    LspCodeAction, LspDocumentSymbol, LspFileDiagnostics, LspHoverResult, LspLocation, LspRange,
+    LspServerStatus, LspSignatureHelp, LspWorkspaceEdit, LspWorkspaceSymbol,
 };
 use crate::utils::TauriApiResult;
 use crate::{api_error, api_success};
 use serde::Deserialize;
+use std::path::Path;
 use std::sync::Arc;
 use tauri::State;
 
@@ -34,6 +37,43 @@ pub struct LspPositionParams {
     pub character: u32,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspRenameParams {
+    pub workspace: String,
+    pub path: String,
+    pub line: u32,
+    pub character: u32,
+    pub new_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspRangeParams {
+    pub workspace: String,
+    pub path: String,
+    pub range: LspRange,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspFormatParams {
+    pub workspace: String,
+    pub path: String,
+    #[serde(default)]
+    pub range: Option<LspRange>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspApplyEditParams {
+    pub workspace: String,
+    pub edit: LspWorkspaceEdit,
+    /// Session whose checkpoints should be able to roll the edit back
+    #[serde(default)]
+    pub session_id: Option<i64>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LspDiagnosticsParams {
@@ -145,3 +185,144 @@ pub async fn lsp_diagnostics(
         Err(err) => Ok(api_error!(&err.to_string())),
     }
 }
+
+#[tauri::command]
+pub async fn lsp_implementation(
+    state: State<'_, Arc<LspManager>>,
+    params: LspPositionParams,
+) -> TauriApiResult<Vec<LspLocation>> {
+    match state
+        .implementation(
+            &params.workspace,
+            &params.path,
+            params.line,
+            params.character,
+        )
+        .await
+    {
+        Ok(result) => Ok(api_success!(result)),
+        Err(err) => Ok(api_error!(&err.to_string())),
+    }
+}
+
+#[tauri::command]
+pub async fn lsp_type_definition(
+    state: State<'_, Arc<LspManager>>,
+    params: LspPositionParams,
+) -> TauriApiResult<Vec<LspLocation>> {
+    match state
+        .type_definition(
+            &params.workspace,
+            &params.path,
+            params.line,
+            params.character,
+        )
+        .await
+    {
+        Ok(result) => Ok(api_success!(result)),
+        Err(err) => Ok(api_error!(&err.to_string())),
+    }
+}
+
+#[tauri::command]
+pub async fn lsp_signature_help(
+    state: State<'_, Arc<LspManager>>,
+    params: LspPositionParams,
+) -> TauriApiResult<Option<LspSignatureHelp>> {
+    match state
+        .signature_help(
+            &params.workspace,
+            &params.path,
+            params.line,
+            params.character,
+        )
+        .await
+    {
+        Ok(result) => Ok(api_success!(result)),
+        Err(err) => Ok(api_error!(&err.to_string())),
+    }
+}
+
+/// Returns the edit without applying it; pass it to `lsp_apply_workspace_edit`.
+#[tauri::command]
+pub async fn lsp_rename(
+    state: State<'_, Arc<LspManager>>,
+    params: LspRenameParams,
+) -> TauriApiResult<LspWorkspaceEdit> {
+    match state
+        .rename(
+            &params.workspace,
+            &params.path,
+            params.line,
+            params.character,
+            params.new_name,
+        )
+        .await
+    {
+        Ok(result) => Ok(api_success!(result)),
+        Err(err) => Ok(api_error!(&err.to_string())),
+    }
+}
+
+#[tauri::command]
+pub async fn lsp_code_actions(
+    state: State<'_, Arc<LspManager>>,
+    params: LspRangeParams,
+) -> TauriApiResult<Vec<LspCodeAction>> {
+    match state
+        .code_actions(&params.workspace, &params.path, params.range)
+        .await
+    {
+        Ok(result) => Ok(api_success!(result)),
+        Err(err) => Ok(api_error!(&err.to_string())),
+    }
+}
+
+#[tauri::command]
+pub async fn lsp_format(
+    state: State<'_, Arc<LspManager>>,
+    params: LspFormatParams,
+) -> TauriApiResult<LspWorkspaceEdit> {
+    match state
+        .formatting(&params.workspace, &params.path, params.range)
+        .await
+    {
+        Ok(result) => Ok(api_success!(result)),
+        Err(err) => Ok(api_error!(&err.to_string())),
+    }
+}
+
+/// Apply an edit returned by rename, code actions or formatting. Returns the changed paths.
+///
+/// With a `session_id`, the files are snapshotted into the session's latest checkpoint first,
+/// like agent edits, so rolling the session back also undoes this edit.
+#[tauri::command]
+pub async fn lsp_apply_workspace_edit(
+    state: State<'_, TaskExecutorState>,
+    params: LspApplyEditParams,
+) -> TauriApiResult<Vec<String>> {
+    let workspace = Path::new(&params.workspace);
+    if let Err(err) = params.edit.ensure_within(workspace).await {
+        return Ok(api_error!(&err.to_string()));
+    }
+    if let (Some(session_id), Some(checkpoints)) =
+        (params.session_id, state.executor.checkpoint_service())
+    {
+        for path in params.edit.touched_paths() {
+            let path = Path::new(&path);
+            if path.is_dir() {
+                continue;
+            }
+            if let Err(err) = checkpoints
+                .snapshot_file_for_session(session_id, path, workspace)
+                .await
+            {
+                return Ok(api_error!(&err.to_string()));
+            }
+        }
+    }
+    match apply_workspace_edit(&params.edit).await {
+        Ok(changed) => Ok(api_success!(changed)),
+        Err(err) => Ok(api_error!(&err.to_string())),
+    }
+}
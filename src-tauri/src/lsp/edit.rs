@@ -0,0 +1,335 @@
+//! Applying LSP text and workspace edits to files on disk
+//!
+//! Positions use the protocol's default UTF-16 encoding. Characters past the end of
+//! a line clamp to the line end, as the spec requires.
+
+use crate::agent::tools::builtin::file_utils::normalize_path;
+use crate::lsp::types::{LspDocumentChange, LspPosition, LspTextEdit, LspWorkspaceEdit};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LspEditError {
+    #[error("overlapping text edits in {0}")]
+    OverlappingEdits(String),
+    #[error("failed to update {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("edit touches a path outside the workspace: {0}")]
+    OutsideWorkspace(String),
+    #[error("cannot rename onto existing file: {0}")]
+    TargetExists(String),
+}
+
+type Result<T> = std::result::Result<T, LspEditError>;
+
+impl LspWorkspaceEdit {
+    /// Every path the edit reads, writes, creates, renames or deletes, in order of first use
+    pub fn touched_paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = Vec::new();
+        let mut push = |path: &String| {
+            if !paths.contains(path) {
+                paths.push(path.clone());
+            }
+        };
+        for change in &self.changes {
+            match change {
+                LspDocumentChange::Edit { path, .. }
+                | LspDocumentChange::Create { path, .. }
+                | LspDocumentChange::Delete { path, .. } => push(path),
+                LspDocumentChange::Rename {
+                    old_path, new_path, ..
+                } => {
+                    push(old_path);
+                    push(new_path);
+                }
+            }
+        }
+        paths
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.iter().all(
+            |change| matches!(change, LspDocumentChange::Edit { edits, .. } if edits.is_empty()),
+        )
+    }
+
+    /// Reject edits that reach outside `root`, including through symlinks.
+    pub async fn ensure_within(&self, root: &Path) -> Result<()> {
+        let root = resolve_for_check(root).await;
+        for path in self.touched_paths() {
+            if !resolve_for_check(Path::new(&path)).await.starts_with(&root) {
+                return Err(LspEditError::OutsideWorkspace(path));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Apply a workspace edit in order and return the paths that changed.
+///
+/// Creating an existing file without `overwrite` and deleting a missing one are no-ops.
+pub async fn apply_workspace_edit(edit: &LspWorkspaceEdit) -> Result<Vec<String>> {
+    let mut changed: Vec<String> = Vec::new();
+    let mut mark = |path: &str| {
+        if !changed.iter().any(|existing| existing == path) {
+            changed.push(path.to_string());
+        }
+    };
+
+    for change in &edit.changes {
+        match change {
+            LspDocumentChange::Edit { path, edits } => {
+                if edits.is_empty() {
+                    continue;
+                }
+                let text = tokio::fs::read_to_string(path)
+                    .await
+                    .map_err(|source| io_error(path, source))?;
+                let updated = apply_text_edits(&text, edits)
+                    .ok_or_else(|| LspEditError::OverlappingEdits(path.clone()))?;
+                if updated != text {
+                    tokio::fs::write(path, updated)
+                        .await
+                        .map_err(|source| io_error(path, source))?;
+                    mark(path);
+                }
+            }
+            LspDocumentChange::Create { path, overwrite } => {
+                if !*overwrite && tokio::fs::try_exists(path).await.unwrap_or(false) {
+                    continue;
+                }
+                create_parent(path).await?;
+                tokio::fs::write(path, "")
+                    .await
+                    .map_err(|source| io_error(path, source))?;
+                mark(path);
+            }
+            LspDocumentChange::Rename {
+                old_path,
+                new_path,
+                overwrite,
+            } => {
+                if !*overwrite && tokio::fs::try_exists(new_path).await.unwrap_or(false) {
+                    return Err(LspEditError::TargetExists(new_path.clone()));
+                }
+                create_parent(new_path).await?;
+                tokio::fs::rename(old_path, new_path)
+                    .await
+                    .map_err(|source| io_error(old_path, source))?;
+                mark(old_path);
+                mark(new_path);
+            }
+            LspDocumentChange::Delete { path, recursive } => {
+                let metadata = match tokio::fs::metadata(path).await {
+                    Ok(metadata) => metadata,
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                    Err(source) => return Err(io_error(path, source)),
+                };
+                let result = if !metadata.is_dir() {
+                    tokio::fs::remove_file(path).await
+                } else if *recursive {
+                    tokio::fs::remove_dir_all(path).await
+                } else {
+                    tokio::fs::remove_dir(path).await
+                };
+                result.map_err(|source| io_error(path, source))?;
+                mark(path);
+            }
+        }
+    }
+    Ok(changed)
+}
+
+/// Apply text edits that all refer to the original `text`.
+///
+/// Edits starting at the same position are applied in the order given. Returns `None`
+/// when edits overlap.
+pub fn apply_text_edits(text: &str, edits: &[LspTextEdit]) -> Option<String> {
+    let line_starts = line_starts(text);
+    let mut spans: Vec<(usize, usize, &str)> = edits
+        .iter()
+        .map(|edit| {
+            let start = offset_at(text, &line_starts, &edit.range.start);
+            let end = offset_at(text, &line_starts, &edit.range.end).max(start);
+            (start, end, edit.new_text.as_str())
+        })
+        .collect();
+    spans.sort_by_key(|(start, _, _)| *start);
+
+    let mut out = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for (start, end, new_text) in spans {
+        if start < cursor {
+            return None;
+        }
+        out.push_str(&text[cursor..start]);
+        out.push_str(new_text);
+        cursor = end;
+    }
+    out.push_str(&text[cursor..]);
+    Some(out)
+}
+
+fn line_starts(text: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+        .collect()
+}
+
+fn offset_at(text: &str, line_starts: &[usize], position: &LspPosition) -> usize {
+    let Some(&start) = line_starts.get(position.line as usize) else {
+        return text.len();
+    };
+    let end = line_starts
+        .get(position.line as usize + 1)
+        .copied()
+        .unwrap_or(text.len());
+    let line = text[start..end].trim_end_matches(['\n', '\r']);
+
+    let mut units = 0u32;
+    for (idx, ch) in line.char_indices() {
+        if units >= position.character {
+            return start + idx;
+        }
+        units += ch.len_utf16() as u32;
+    }
+    start + line.len()
+}
+
+fn io_error(path: &str, source: std::io::Error) -> LspEditError {
+    LspEditError::Io {
+        path: path.to_string(),
+        source,
+    }
+}
+
+async fn create_parent(path: &str) -> Result<()> {
+    if let Some(parent) = Path::new(path).parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|source| io_error(path, source))?;
+    }
+    Ok(())
+}
+
+/// Canonicalize the longest existing prefix so paths that don't exist yet still resolve
+/// symlinks in their parents.
+async fn resolve_for_check(path: &Path) -> PathBuf {
+    let path = normalize_path(path);
+    let mut existing = path.as_path();
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = tokio::fs::canonicalize(existing).await {
+            return rest
+                .iter()
+                .rev()
+                .fold(canonical, |acc: PathBuf, part| acc.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return path,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::types::LspRange;
+
+    fn edit(start: (u32, u32), end: (u32, u32), new_text: &str) -> LspTextEdit {
+        LspTextEdit {
+            range: LspRange {
+                start: LspPosition {
+                    line: start.0,
+                    character: start.1,
+                },
+                end: LspPosition {
+                    line: end.0,
+                    character: end.1,
+                },
+            },
+            new_text: new_text.to_string(),
+        }
+    }
+
+    #[test]
+    fn applies_edits_against_original_positions() {
+        let text = "let a = 1;\nlet b = a;\n";
+        let edits = vec![
+            edit((1, 8), (1, 9), "count"),
+            edit((0, 4), (0, 5), "count"),
+            edit((1, 4), (1, 5), "total"),
+        ];
+        assert_eq!(
+            apply_text_edits(text, &edits).unwrap(),
+            "let count = 1;\nlet total = count;\n"
+        );
+    }
+
+    #[test]
+    fn positions_count_utf16_units_and_clamp_to_line_end() {
+        // "😀" is two UTF-16 code units
+        let text = "s = \"😀x\"\r\nnext";
+        let edits = vec![edit((0, 7), (0, 8), "y"), edit((0, 99), (0, 99), ";")];
+        assert_eq!(
+            apply_text_edits(text, &edits).unwrap(),
+            "s = \"😀y\";\r\nnext"
+        );
+    }
+
+    #[test]
+    fn rejects_overlapping_edits() {
+        let edits = vec![edit((0, 0), (0, 4), "a"), edit((0, 2), (0, 6), "b")];
+        assert!(apply_text_edits("abcdefgh", &edits).is_none());
+    }
+
+    #[tokio::test]
+    async fn applies_resource_operations_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let old_path = root.join("old.rs").to_string_lossy().to_string();
+        let new_path = root.join("sub/new.rs").to_string_lossy().to_string();
+        std::fs::write(&old_path, "fn old() {}\n").unwrap();
+
+        let workspace_edit = LspWorkspaceEdit {
+            changes: vec![
+                LspDocumentChange::Edit {
+                    path: old_path.clone(),
+                    edits: vec![edit((0, 3), (0, 6), "renamed")],
+                },
+                LspDocumentChange::Rename {
+                    old_path: old_path.clone(),
+                    new_path: new_path.clone(),
+                    overwrite: false,
+                },
+            ],
+        };
+        workspace_edit.ensure_within(root).await.unwrap();
+        let changed = apply_workspace_edit(&workspace_edit).await.unwrap();
+
+        assert_eq!(changed, vec![old_path.clone(), new_path.clone()]);
+        assert!(!Path::new(&old_path).exists());
+        assert_eq!(
+            std::fs::read_to_string(&new_path).unwrap(),
+            "fn renamed() {}\n"
+        );
+
+        let outside = LspWorkspaceEdit {
+            changes: vec![LspDocumentChange::Create {
+                path: root.join("../escape.rs").to_string_lossy().to_string(),
+                overwrite: false,
+            }],
+        };
+        assert!(matches!(
+            outside.ensure_within(root).await,
+            Err(LspEditError::OutsideWorkspace(_))
+        ));
+    }
+}
@@ -12,6 +12,15 @@ pub fn language_id_for_path(path: &Path) -> &'static str {
         "rs" => "rust",
         "py" => "python",
         "go" => "go",
+        "c" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx" => "cpp",
+        "h" => "c",
+        "java" => "java",
+        "rb" => "ruby",
+        "lua" => "lua",
+        "yaml" | "yml" => "yaml",
+        "sh" | "bash" => "shellscript",
+        "tf" | "tfvars" => "terraform",
         _ => "plaintext",
     }
 }
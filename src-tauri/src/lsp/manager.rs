@@ -1,15 +1,17 @@
 use crate::agent::tools::builtin::file_utils::ensure_absolute;
 use crate::lsp::client::{LspClient, LspClientError};
-use crate::lsp::language::candidate_servers_for_path;
-use crate::lsp::server::resolve_server_for_file;
+use crate::lsp::server::{candidate_servers, resolve_server_for_file, ServerCandidate};
 use crate::lsp::types::{
-    LspDocumentSymbol, LspFileDiagnostics, LspHoverResult, LspLocation, LspServerId,
-    LspServerStatus, LspWorkspaceSymbol,
+    LspCodeAction, LspDocumentSymbol, LspFileDiagnostics, LspHoverResult, LspLocation, LspRange,
+    LspServerId, LspServerStatus, LspSignatureHelp, LspWorkspaceEdit, LspWorkspaceSymbol,
 };
+use crate::settings::types::LspSettings;
+use crate::settings::SettingsManager;
 use dashmap::DashMap;
-use lsp_types::Position;
+use lsp_types::{Diagnostic, Position, Range};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Debug, thiserror::Error)]
 pub enum LspManagerError {
@@ -25,9 +27,15 @@ pub enum LspManagerError {
 
 type Result<T> = std::result::Result<T, LspManagerError>;
 
+/// How long to wait for diagnostics of a file the server has not seen yet
+const BASELINE_DIAGNOSTICS_TIMEOUT: Duration = Duration::from_millis(1500);
+/// How long to wait for diagnostics after an edit
+const EDIT_DIAGNOSTICS_TIMEOUT: Duration = Duration::from_millis(3000);
+
 #[derive(Default)]
 pub struct LspManager {
     clients: DashMap<String, Arc<LspClient>>,
+    settings: Option<Arc<SettingsManager>>,
 }
 
 impl LspManager {
@@ -35,6 +43,14 @@ impl LspManager {
         Self::default()
     }
 
+    /// Read server definitions and overrides from the `lsp` settings section.
+    pub fn with_settings(settings: Arc<SettingsManager>) -> Self {
+        Self {
+            clients: DashMap::new(),
+            settings: Some(settings),
+        }
+    }
+
     pub async fn status(&self) -> Vec<LspServerStatus> {
         self.clients
             .iter()
@@ -122,6 +138,110 @@ impl LspManager {
             .map_err(Into::into)
     }
 
+    pub async fn implementation(
+        &self,
+        workspace: &str,
+        path: &str,
+        line: u32,
+        character: u32,
+    ) -> Result<Vec<LspLocation>> {
+        let path = resolve_path(workspace, path)?;
+        let client = self.client_for_path(&path, Path::new(workspace)).await?;
+        client
+            .implementation(&path, Position { line, character })
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn type_definition(
+        &self,
+        workspace: &str,
+        path: &str,
+        line: u32,
+        character: u32,
+    ) -> Result<Vec<LspLocation>> {
+        let path = resolve_path(workspace, path)?;
+        let client = self.client_for_path(&path, Path::new(workspace)).await?;
+        client
+            .type_definition(&path, Position { line, character })
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn rename(
+        &self,
+        workspace: &str,
+        path: &str,
+        line: u32,
+        character: u32,
+        new_name: String,
+    ) -> Result<LspWorkspaceEdit> {
+        let path = resolve_path(workspace, path)?;
+        let client = self.client_for_path(&path, Path::new(workspace)).await?;
+        client
+            .rename(&path, Position { line, character }, new_name)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn code_actions(
+        &self,
+        workspace: &str,
+        path: &str,
+        range: LspRange,
+    ) -> Result<Vec<LspCodeAction>> {
+        let path = resolve_path(workspace, path)?;
+        let client = self.client_for_path(&path, Path::new(workspace)).await?;
+        client
+            .code_actions(&path, to_lsp_range(range))
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn code_action(
+        &self,
+        workspace: &str,
+        path: &str,
+        range: LspRange,
+        title: &str,
+    ) -> Result<Option<LspCodeAction>> {
+        let path = resolve_path(workspace, path)?;
+        let client = self.client_for_path(&path, Path::new(workspace)).await?;
+        client
+            .code_action(&path, to_lsp_range(range), title)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn formatting(
+        &self,
+        workspace: &str,
+        path: &str,
+        range: Option<LspRange>,
+    ) -> Result<LspWorkspaceEdit> {
+        let path = resolve_path(workspace, path)?;
+        let client = self.client_for_path(&path, Path::new(workspace)).await?;
+        client
+            .formatting(&path, range.map(to_lsp_range))
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn signature_help(
+        &self,
+        workspace: &str,
+        path: &str,
+        line: u32,
+        character: u32,
+    ) -> Result<Option<LspSignatureHelp>> {
+        let path = resolve_path(workspace, path)?;
+        let client = self.client_for_path(&path, Path::new(workspace)).await?;
+        client
+            .signature_help(&path, Position { line, character })
+            .await
+            .map_err(Into::into)
+    }
+
     pub async fn diagnostics(
         &self,
         workspace: &str,
@@ -146,36 +266,162 @@ impl LspManager {
         Ok(out)
     }
 
+    /// Diagnostics of `path` as it is on disk, from a server that is already running for
+    /// it. Files that don't exist yet have none. `None` when no server is running.
+    pub async fn baseline_diagnostics(
+        &self,
+        workspace: &str,
+        path: &Path,
+    ) -> Option<Vec<Diagnostic>> {
+        let client = self.running_client(workspace, path).await?;
+        if !path.exists() {
+            return Some(Vec::new());
+        }
+        if client.has_diagnostics(path) {
+            return Some(client.diagnostics_for_path(path));
+        }
+        match client
+            .sync_diagnostics(path, BASELINE_DIAGNOSTICS_TIMEOUT)
+            .await
+        {
+            Ok(diagnostics) => Some(diagnostics.unwrap_or_default()),
+            Err(err) => {
+                tracing::debug!(
+                    "LSP baseline diagnostics failed for {}: {}",
+                    path.display(),
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    /// Push the edited file to its running server and wait briefly for fresh diagnostics.
+    pub async fn diagnostics_after_edit(
+        &self,
+        workspace: &str,
+        path: &Path,
+    ) -> Option<Vec<Diagnostic>> {
+        let client = self.running_client(workspace, path).await?;
+        match client
+            .sync_diagnostics(path, EDIT_DIAGNOSTICS_TIMEOUT)
+            .await
+        {
+            Ok(diagnostics) => diagnostics,
+            Err(err) => {
+                tracing::debug!(
+                    "LSP diagnostics after edit failed for {}: {}",
+                    path.display(),
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    /// A connected client that would serve `path`, without starting a new server.
+    async fn running_client(&self, workspace: &str, path: &Path) -> Option<Arc<LspClient>> {
+        if self.clients.is_empty() {
+            return None;
+        }
+        let workspace_root = Path::new(workspace);
+        let settings = self.lsp_settings(workspace_root).await;
+        let canonical = path
+            .parent()
+            .and_then(|parent| parent.canonicalize().ok())
+            .zip(path.file_name())
+            .map(|(parent, name)| parent.join(name));
+
+        for candidate in candidate_servers(path, workspace_root, &settings) {
+            let found = self.clients.iter().find_map(|entry| {
+                let client = entry.value();
+                let config = client.config();
+                let under_root = path.starts_with(&config.root)
+                    || canonical
+                        .as_ref()
+                        .is_some_and(|canonical| canonical.starts_with(&config.root));
+                (config.server_id == candidate.name() && under_root && client.is_connected())
+                    .then(|| client.clone())
+            });
+            if found.is_some() {
+                return found;
+            }
+        }
+        None
+    }
+
     async fn client_for_path(&self, path: &Path, workspace_root: &Path) -> Result<Arc<LspClient>> {
-        let candidates = candidate_servers_for_path(path, workspace_root);
+        let settings = self.lsp_settings(workspace_root).await;
+        let candidates = candidate_servers(path, workspace_root, &settings);
         if candidates.is_empty() {
             return Err(LspManagerError::UnsupportedFile(
                 path.to_string_lossy().to_string(),
             ));
         }
 
-        let config = resolve_server_for_file(path, workspace_root).ok_or_else(|| {
-            LspManagerError::MissingDependency(missing_dependency_message(candidates[0]))
+        let config = resolve_server_for_file(path, workspace_root, &settings).ok_or_else(|| {
+            LspManagerError::MissingDependency(missing_dependency_message(&candidates[0]))
         })?;
-        let key = format!(
-            "{}::{}",
-            config.server_id.as_str(),
-            config.root.to_string_lossy()
-        );
-        if let Some(existing) = self.clients.get(&key) {
-            return Ok(existing.clone());
+        let key = format!("{}::{}", config.server_id, config.root.to_string_lossy());
+        if let Some(existing) = self.clients.get(&key).map(|entry| entry.value().clone()) {
+            if existing.config() == &config {
+                return Ok(existing);
+            }
+            // Settings changed since the server started; restart it with the new config
+            self.clients.remove(&key);
+            existing.shutdown().await;
         }
         let client = Arc::new(LspClient::spawn(config).await?);
         self.clients.insert(key, client.clone());
         Ok(client)
     }
+
+    async fn lsp_settings(&self, workspace_root: &Path) -> LspSettings {
+        let Some(settings) = &self.settings else {
+            return LspSettings::default();
+        };
+        match settings
+            .get_effective_settings(Some(workspace_root.to_path_buf()))
+            .await
+        {
+            Ok(effective) => effective.lsp,
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to load LSP settings, using built-in servers: {}",
+                    err
+                );
+                LspSettings::default()
+            }
+        }
+    }
 }
 
 fn resolve_path(workspace: &str, path: &str) -> Result<PathBuf> {
     ensure_absolute(path, workspace).map_err(|err| LspManagerError::InvalidPath(err.to_string()))
 }
 
-fn missing_dependency_message(server_id: LspServerId) -> String {
+fn to_lsp_range(range: LspRange) -> Range {
+    Range {
+        start: Position {
+            line: range.start.line,
+            character: range.start.character,
+        },
+        end: Position {
+            line: range.end.line,
+            character: range.end.character,
+        },
+    }
+}
+
+fn missing_dependency_message(candidate: &ServerCandidate) -> String {
+    let server_id = match candidate {
+        ServerCandidate::Builtin(server_id) => *server_id,
+        ServerCandidate::Custom(name) => {
+            return format!(
+                "LSP unavailable: the command for language server `{name}` was not found. Check `lsp.servers.{name}` in settings."
+            );
+        }
+    };
     match server_id {
         LspServerId::Typescript => {
             "LSP unavailable: missing `typescript-language-server`. Install it with `npm i -g typescript typescript-language-server`.".to_string()
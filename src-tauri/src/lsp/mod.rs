@@ -1,5 +1,6 @@
 pub mod client;
 pub mod commands;
+pub mod edit;
 pub mod language;
 pub mod manager;
 pub mod server;
@@ -1,13 +1,158 @@
 use crate::lsp::language::{candidate_servers_for_path, nearest_root};
 use crate::lsp::types::{LspServerId, ResolvedServerConfig};
+use crate::settings::types::{LspServerSettings, LspSettings};
 use serde_json::json;
 use std::env;
 use std::path::{Path, PathBuf};
 
-pub fn resolve_server_for_file(path: &Path, workspace_root: &Path) -> Option<ResolvedServerConfig> {
-    for server_id in candidate_servers_for_path(path, workspace_root) {
-        if let Some(config) = resolve_server(server_id, path, workspace_root) {
-            return Some(config);
+const NODE_ROOT_MARKERS: &[&str] = &[
+    "package-lock.json",
+    "bun.lockb",
+    "bun.lock",
+    "pnpm-lock.yaml",
+    "yarn.lock",
+    "package.json",
+];
+
+/// A server that may handle a file, before its command is resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerCandidate {
+    Builtin(LspServerId),
+    /// Key of a server defined in the `lsp.servers` settings
+    Custom(String),
+}
+
+impl ServerCandidate {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Builtin(id) => id.as_str(),
+            Self::Custom(name) => name,
+        }
+    }
+}
+
+/// Defaults of a built-in server that settings may override
+struct BuiltinServer {
+    binary: &'static str,
+    args: &'static [&'static str],
+    root_markers: &'static [&'static str],
+    /// Look in `node_modules/.bin` before `PATH`
+    node_package: bool,
+    /// Only start when a root marker is found instead of falling back to the workspace
+    requires_root: bool,
+}
+
+fn builtin_server(server_id: LspServerId) -> BuiltinServer {
+    match server_id {
+        LspServerId::Deno => BuiltinServer {
+            binary: "deno",
+            args: &["lsp"],
+            root_markers: &["deno.json", "deno.jsonc"],
+            node_package: false,
+            requires_root: true,
+        },
+        LspServerId::Typescript => BuiltinServer {
+            binary: "typescript-language-server",
+            args: &["--stdio"],
+            root_markers: NODE_ROOT_MARKERS,
+            node_package: true,
+            requires_root: false,
+        },
+        LspServerId::Vue => BuiltinServer {
+            binary: "vue-language-server",
+            args: &["--stdio"],
+            root_markers: NODE_ROOT_MARKERS,
+            node_package: true,
+            requires_root: false,
+        },
+        LspServerId::RustAnalyzer => BuiltinServer {
+            binary: "rust-analyzer",
+            args: &[],
+            root_markers: &["Cargo.toml"],
+            node_package: false,
+            requires_root: false,
+        },
+        LspServerId::Pyright => BuiltinServer {
+            binary: "pyright-langserver",
+            args: &["--stdio"],
+            root_markers: &["pyproject.toml", "requirements.txt", ".git"],
+            node_package: true,
+            requires_root: false,
+        },
+        LspServerId::Gopls => BuiltinServer {
+            binary: "gopls",
+            args: &[],
+            root_markers: &["go.work", "go.mod"],
+            node_package: false,
+            requires_root: false,
+        },
+    }
+}
+
+/// Servers that may handle `path`, most preferred first: custom servers by name, then
+/// the enabled built-ins.
+pub fn candidate_servers(
+    path: &Path,
+    workspace_root: &Path,
+    settings: &LspSettings,
+) -> Vec<ServerCandidate> {
+    let mut custom: Vec<&String> = settings
+        .servers
+        .iter()
+        .filter(|(name, server)| {
+            LspServerId::from_name(name).is_none()
+                && server.disabled != Some(true)
+                && server
+                    .file_patterns
+                    .as_deref()
+                    .is_some_and(|patterns| matches_file_patterns(patterns, path, workspace_root))
+        })
+        .map(|(name, _)| name)
+        .collect();
+    custom.sort();
+
+    let mut candidates: Vec<ServerCandidate> = custom
+        .into_iter()
+        .map(|name| ServerCandidate::Custom(name.clone()))
+        .collect();
+
+    let defaults = candidate_servers_for_path(path, workspace_root);
+    for server_id in defaults.iter().copied().chain(LspServerId::ALL) {
+        let overrides = settings.servers.get(server_id.as_str());
+        if overrides.and_then(|server| server.disabled) == Some(true) {
+            continue;
+        }
+        let handles = match overrides.and_then(|server| server.file_patterns.as_deref()) {
+            Some(patterns) => matches_file_patterns(patterns, path, workspace_root),
+            None => defaults.contains(&server_id),
+        };
+        let candidate = ServerCandidate::Builtin(server_id);
+        if handles && !candidates.contains(&candidate) {
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+pub fn resolve_server_for_file(
+    path: &Path,
+    workspace_root: &Path,
+    settings: &LspSettings,
+) -> Option<ResolvedServerConfig> {
+    for candidate in candidate_servers(path, workspace_root, settings) {
+        let config = match &candidate {
+            ServerCandidate::Builtin(server_id) => resolve_builtin(
+                *server_id,
+                settings.servers.get(server_id.as_str()),
+                path,
+                workspace_root,
+            ),
+            ServerCandidate::Custom(name) => {
+                resolve_custom(name, &settings.servers[name], path, workspace_root)
+            }
+        };
+        if config.is_some() {
+            return config;
         }
     }
     None
@@ -49,111 +194,118 @@ fn resolve_server_command(root: &Path, binary_name: &str) -> Option<String> {
     None
 }
 
-fn resolve_server(
+/// Resolve a command from settings: absolute paths as-is, paths with a separator relative
+/// to the server root, bare names through `node_modules/.bin` and `PATH`.
+fn resolve_configured_command(root: &Path, command: &str) -> Option<String> {
+    let candidate = Path::new(command);
+    if candidate.is_absolute() || candidate.components().count() > 1 {
+        let path = root.join(candidate);
+        if path.exists() {
+            return Some(path.to_string_lossy().to_string());
+        }
+        tracing::warn!("Configured language server command '{}' not found", command);
+        return None;
+    }
+    resolve_server_command(root, command)
+}
+
+fn resolve_builtin(
     server_id: LspServerId,
+    overrides: Option<&LspServerSettings>,
     path: &Path,
     workspace_root: &Path,
 ) -> Option<ResolvedServerConfig> {
-    match server_id {
-        LspServerId::Deno => {
-            let root = nearest_root(path, workspace_root, &["deno.json", "deno.jsonc"])?;
-            Some(ResolvedServerConfig {
-                server_id,
-                root,
-                command: which("deno")?,
-                args: vec!["lsp".to_string()],
-                initialization_options: None,
-            })
-        }
-        LspServerId::Typescript => {
-            let root = resolved_root_or_workspace(
-                path,
-                workspace_root,
-                &[
-                    "package-lock.json",
-                    "bun.lockb",
-                    "bun.lock",
-                    "pnpm-lock.yaml",
-                    "yarn.lock",
-                    "package.json",
-                ],
-                "typescript-language-server",
-            );
+    let builtin = builtin_server(server_id);
+    let overrides = overrides.cloned().unwrap_or_default();
 
-            let command = resolve_server_command(&root, "typescript-language-server")?;
-            let tsserver = resolve_typescript_tsserver(&root);
-
-            Some(ResolvedServerConfig {
-                server_id,
-                root,
-                command,
-                args: vec!["--stdio".to_string()],
-                initialization_options: tsserver.map(|path| {
-                    json!({
-                        "tsserver": {
-                            "path": path.to_string_lossy().to_string()
-                        }
-                    })
-                }),
+    let markers: Vec<&str> = match &overrides.root_markers {
+        Some(markers) => markers.iter().map(String::as_str).collect(),
+        None => builtin.root_markers.to_vec(),
+    };
+    let root = if builtin.requires_root {
+        nearest_root(path, workspace_root, &markers)?
+    } else {
+        resolved_root_or_workspace(path, workspace_root, &markers, builtin.binary)
+    };
+
+    let command = match &overrides.command {
+        Some(command) => resolve_configured_command(&root, command)?,
+        None if builtin.node_package => resolve_server_command(&root, builtin.binary)?,
+        None => which(builtin.binary)?,
+    };
+    let initialization_options = match overrides.initialization_options {
+        Some(options) => Some(options),
+        None if server_id == LspServerId::Typescript => {
+            resolve_typescript_tsserver(&root).map(|path| {
+                json!({
+                    "tsserver": {
+                        "path": path.to_string_lossy().to_string()
+                    }
+                })
             })
         }
-        LspServerId::Vue => {
-            let root = resolved_root_or_workspace(
-                path,
-                workspace_root,
-                &[
-                    "package-lock.json",
-                    "bun.lockb",
-                    "bun.lock",
-                    "pnpm-lock.yaml",
-                    "yarn.lock",
-                    "package.json",
-                ],
-                "vue-language-server",
-            );
+        None => None,
+    };
 
-            let command = resolve_server_command(&root, "vue-language-server")?;
+    Some(ResolvedServerConfig {
+        server_id: server_id.as_str().to_string(),
+        root,
+        command,
+        args: overrides
+            .args
+            .unwrap_or_else(|| builtin.args.iter().map(|arg| arg.to_string()).collect()),
+        initialization_options,
+        env: overrides.env.unwrap_or_default(),
+        language_id: overrides.language_id,
+    })
+}
 
-            Some(ResolvedServerConfig {
-                server_id,
-                root,
-                command,
-                args: vec!["--stdio".to_string()],
-                initialization_options: None,
-            })
-        }
-        LspServerId::RustAnalyzer => Some(ResolvedServerConfig {
-            server_id,
-            root: resolved_root_or_workspace(
-                path,
-                workspace_root,
-                &["Cargo.toml"],
-                "rust-analyzer",
-            ),
-            command: which("rust-analyzer")?,
-            args: Vec::new(),
-            initialization_options: None,
-        }),
-        LspServerId::Pyright => Some(ResolvedServerConfig {
-            server_id,
-            root: resolved_root_or_workspace(
-                path,
-                workspace_root,
-                &["pyproject.toml", "requirements.txt", ".git"],
-                "pyright-langserver",
-            ),
-            command: resolve_server_command(workspace_root, "pyright-langserver")?,
-            args: vec!["--stdio".to_string()],
-            initialization_options: None,
-        }),
-        LspServerId::Gopls => Some(ResolvedServerConfig {
-            server_id,
-            root: resolved_root_or_workspace(path, workspace_root, &["go.work", "go.mod"], "gopls"),
-            command: which("gopls")?,
-            args: Vec::new(),
-            initialization_options: None,
-        }),
-    }
+fn resolve_custom(
+    name: &str,
+    server: &LspServerSettings,
+    path: &Path,
+    workspace_root: &Path,
+) -> Option<ResolvedServerConfig> {
+    let Some(command) = server.command.as_deref() else {
+        tracing::warn!("Language server '{}' has no command configured", name);
+        return None;
+    };
+    let markers: Vec<&str> = server
+        .root_markers
+        .iter()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    let root = resolved_root_or_workspace(path, workspace_root, &markers, name);
+
+    Some(ResolvedServerConfig {
+        server_id: name.to_string(),
+        command: resolve_configured_command(&root, command)?,
+        root,
+        args: server.args.clone().unwrap_or_default(),
+        initialization_options: server.initialization_options.clone(),
+        env: server.env.clone().unwrap_or_default(),
+        language_id: server.language_id.clone(),
+    })
+}
+
+/// Globs match either the file name (`*.cpp`) or the workspace-relative path
+/// (`infra/**/*.tf`).
+fn matches_file_patterns(patterns: &[String], path: &Path, workspace_root: &Path) -> bool {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let relative = path.strip_prefix(workspace_root).unwrap_or(path);
+    patterns
+        .iter()
+        .any(|pattern| match glob::Pattern::new(pattern) {
+            Ok(pattern) => pattern.matches(&file_name) || pattern.matches_path(relative),
+            Err(err) => {
+                tracing::warn!("Invalid LSP file pattern '{}': {}", pattern, err);
+                false
+            }
+        })
 }
 
 fn resolve_typescript_tsserver(root: &Path) -> Option<PathBuf> {
@@ -226,3 +378,86 @@ fn which(command: &str) -> Option<String> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(json: &str) -> LspSettings {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn custom_servers_come_before_builtins() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let settings = settings(
+            r#"{"servers":{
+                "clangd":{"command":"clangd","filePatterns":["*.cpp","*.h"]},
+                "tsgo":{"command":"tsgo","filePatterns":["src/**/*.ts"]},
+                "off":{"command":"off","filePatterns":["*.ts"],"disabled":true}
+            }}"#,
+        );
+
+        assert_eq!(
+            candidate_servers(&root.join("a/b.cpp"), root, &settings),
+            vec![ServerCandidate::Custom("clangd".into())]
+        );
+        assert_eq!(
+            candidate_servers(&root.join("src/app/main.ts"), root, &settings),
+            vec![
+                ServerCandidate::Custom("tsgo".into()),
+                ServerCandidate::Builtin(LspServerId::Typescript)
+            ]
+        );
+        assert!(candidate_servers(&root.join("main.rb"), root, &settings).is_empty());
+    }
+
+    #[test]
+    fn builtins_can_be_disabled_or_retargeted() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let settings = settings(
+            r#"{"servers":{
+                "rust_analyzer":{"disabled":true},
+                "pyright":{"filePatterns":["*.py","*.pyi"]}
+            }}"#,
+        );
+
+        assert!(candidate_servers(&root.join("lib.rs"), root, &settings).is_empty());
+        assert_eq!(
+            candidate_servers(&root.join("stubs.pyi"), root, &settings),
+            vec![ServerCandidate::Builtin(LspServerId::Pyright)]
+        );
+    }
+
+    #[test]
+    fn resolves_custom_server_from_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("svc/bin")).unwrap();
+        std::fs::create_dir_all(root.join("svc/src")).unwrap();
+        std::fs::write(root.join("svc/compile_commands.json"), "[]").unwrap();
+        std::fs::write(root.join("svc/bin/clangd"), "").unwrap();
+        let settings = settings(
+            r#"{"servers":{"clangd":{
+                "command":"bin/clangd",
+                "args":["--background-index"],
+                "filePatterns":["*.cpp"],
+                "rootMarkers":["compile_commands.json"],
+                "env":{"CLANGD_FLAGS":"-j=2"},
+                "languageId":"cpp"
+            }}}"#,
+        );
+
+        let config = resolve_server_for_file(&root.join("svc/src/main.cpp"), root, &settings)
+            .expect("clangd resolves");
+        let svc = root.join("svc").canonicalize().unwrap();
+        assert_eq!(config.server_id, "clangd");
+        assert_eq!(config.root, svc);
+        assert_eq!(Path::new(&config.command), svc.join("bin/clangd"));
+        assert_eq!(config.args, vec!["--background-index".to_string()]);
+        assert_eq!(config.env["CLANGD_FLAGS"], "-j=2");
+        assert_eq!(config.language_id.as_deref(), Some("cpp"));
+    }
+}
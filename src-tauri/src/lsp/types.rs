@@ -1,7 +1,10 @@
 use lsp_types::{
-    Diagnostic, DocumentSymbol, Hover, Location, MarkedString, SymbolInformation, SymbolKind,
+    CodeAction, Diagnostic, DocumentChangeOperation, DocumentChanges, DocumentSymbol,
+    Documentation, Hover, Location, MarkedString, OneOf, ParameterLabel, ResourceOp, SignatureHelp,
+    SignatureInformation, SymbolInformation, SymbolKind, TextEdit, WorkspaceEdit,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -16,6 +19,19 @@ pub enum LspServerId {
 }
 
 impl LspServerId {
+    pub const ALL: [LspServerId; 6] = [
+        Self::Deno,
+        Self::Typescript,
+        Self::Vue,
+        Self::RustAnalyzer,
+        Self::Pyright,
+        Self::Gopls,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|id| id.as_str() == name)
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Deno => "deno",
@@ -96,13 +112,84 @@ pub struct LspFileDiagnostics {
     pub diagnostics: Vec<Diagnostic>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspTextEdit {
+    pub range: LspRange,
+    pub new_text: String,
+}
+
+/// One step of a workspace edit. Steps are applied in order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum LspDocumentChange {
+    Edit {
+        path: String,
+        edits: Vec<LspTextEdit>,
+    },
+    Create {
+        path: String,
+        overwrite: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    Rename {
+        old_path: String,
+        new_path: String,
+        overwrite: bool,
+    },
+    Delete {
+        path: String,
+        recursive: bool,
+    },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspWorkspaceEdit {
+    pub changes: Vec<LspDocumentChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspCodeAction {
+    pub title: String,
+    pub kind: Option<String>,
+    pub is_preferred: bool,
+    /// Messages of the diagnostics this action fixes
+    pub diagnostics: Vec<String>,
+    pub edit: Option<LspWorkspaceEdit>,
+    /// Server command the action runs; such actions can't be applied from here
+    pub command: Option<String>,
+    pub disabled_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspSignature {
+    pub label: String,
+    pub documentation: Option<String>,
+    pub parameters: Vec<String>,
+    pub active_parameter: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspSignatureHelp {
+    pub signatures: Vec<LspSignature>,
+    pub active_signature: Option<u32>,
+    pub active_parameter: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct ResolvedServerConfig {
-    pub server_id: LspServerId,
+    /// Built-in id (see [`LspServerId::as_str`]) or the settings key of a custom server
+    pub server_id: String,
     pub root: PathBuf,
     pub command: String,
     pub args: Vec<String>,
     pub initialization_options: Option<serde_json::Value>,
+    pub env: HashMap<String, String>,
+    pub language_id: Option<String>,
 }
 
 impl From<lsp_types::Position> for LspPosition {
@@ -183,6 +270,155 @@ impl From<Hover> for LspHoverResult {
     }
 }
 
+impl From<TextEdit> for LspTextEdit {
+    fn from(value: TextEdit) -> Self {
+        Self {
+            range: value.range.into(),
+            new_text: value.new_text,
+        }
+    }
+}
+
+impl From<WorkspaceEdit> for LspWorkspaceEdit {
+    fn from(value: WorkspaceEdit) -> Self {
+        let mut changes = Vec::new();
+        match value.document_changes {
+            Some(DocumentChanges::Edits(edits)) => {
+                changes.extend(edits.into_iter().map(|edit| LspDocumentChange::Edit {
+                    path: uri_to_path_string(&edit.text_document.uri),
+                    edits: edit.edits.into_iter().map(one_of_text_edit).collect(),
+                }));
+            }
+            Some(DocumentChanges::Operations(operations)) => {
+                changes.extend(operations.into_iter().map(|operation| {
+                    match operation {
+                        DocumentChangeOperation::Edit(edit) => LspDocumentChange::Edit {
+                            path: uri_to_path_string(&edit.text_document.uri),
+                            edits: edit.edits.into_iter().map(one_of_text_edit).collect(),
+                        },
+                        DocumentChangeOperation::Op(ResourceOp::Create(create)) => {
+                            LspDocumentChange::Create {
+                                path: uri_to_path_string(&create.uri),
+                                overwrite: create
+                                    .options
+                                    .and_then(|options| options.overwrite)
+                                    .unwrap_or(false),
+                            }
+                        }
+                        DocumentChangeOperation::Op(ResourceOp::Rename(rename)) => {
+                            LspDocumentChange::Rename {
+                                old_path: uri_to_path_string(&rename.old_uri),
+                                new_path: uri_to_path_string(&rename.new_uri),
+                                overwrite: rename
+                                    .options
+                                    .and_then(|options| options.overwrite)
+                                    .unwrap_or(false),
+                            }
+                        }
+                        DocumentChangeOperation::Op(ResourceOp::Delete(delete)) => {
+                            LspDocumentChange::Delete {
+                                path: uri_to_path_string(&delete.uri),
+                                recursive: delete
+                                    .options
+                                    .and_then(|options| options.recursive)
+                                    .unwrap_or(false),
+                            }
+                        }
+                    }
+                }));
+            }
+            // `changes` is only meaningful when `documentChanges` is absent
+            None => {
+                let mut by_path: Vec<_> = value
+                    .changes
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(uri, edits)| (uri_to_path_string(&uri), edits))
+                    .collect();
+                by_path.sort_by(|a, b| a.0.cmp(&b.0));
+                changes.extend(
+                    by_path
+                        .into_iter()
+                        .map(|(path, edits)| LspDocumentChange::Edit {
+                            path,
+                            edits: edits.into_iter().map(Into::into).collect(),
+                        }),
+                );
+            }
+        }
+        Self { changes }
+    }
+}
+
+impl From<CodeAction> for LspCodeAction {
+    fn from(value: CodeAction) -> Self {
+        Self {
+            title: value.title,
+            kind: value.kind.map(|kind| kind.as_str().to_string()),
+            is_preferred: value.is_preferred.unwrap_or(false),
+            diagnostics: value
+                .diagnostics
+                .unwrap_or_default()
+                .into_iter()
+                .map(|diagnostic| diagnostic.message)
+                .collect(),
+            edit: value.edit.map(Into::into),
+            command: value.command.map(|command| command.command),
+            disabled_reason: value.disabled.map(|disabled| disabled.reason),
+        }
+    }
+}
+
+impl From<SignatureHelp> for LspSignatureHelp {
+    fn from(value: SignatureHelp) -> Self {
+        Self {
+            signatures: value.signatures.into_iter().map(Into::into).collect(),
+            active_signature: value.active_signature,
+            active_parameter: value.active_parameter,
+        }
+    }
+}
+
+impl From<SignatureInformation> for LspSignature {
+    fn from(value: SignatureInformation) -> Self {
+        let parameters = value
+            .parameters
+            .unwrap_or_default()
+            .into_iter()
+            .map(|parameter| match parameter.label {
+                ParameterLabel::Simple(label) => label,
+                // Offsets are UTF-16 code units into the signature label
+                ParameterLabel::LabelOffsets([start, end]) => {
+                    let units: Vec<u16> = value.label.encode_utf16().collect();
+                    let start = (start as usize).min(units.len());
+                    let end = (end as usize).clamp(start, units.len());
+                    String::from_utf16_lossy(&units[start..end])
+                }
+            })
+            .collect();
+        Self {
+            documentation: value.documentation.map(documentation_to_text),
+            label: value.label,
+            parameters,
+            active_parameter: value.active_parameter,
+        }
+    }
+}
+
+fn one_of_text_edit(edit: OneOf<TextEdit, lsp_types::AnnotatedTextEdit>) -> LspTextEdit {
+    match edit {
+        OneOf::Left(edit) => edit.into(),
+        OneOf::Right(annotated) => annotated.text_edit.into(),
+    }
+}
+
+fn documentation_to_text(documentation: Documentation) -> String {
+    match documentation {
+        Documentation::String(text) => text,
+        Documentation::MarkupContent(markup) => markup.value,
+    }
+}
+
 fn marked_string_to_text(marked: MarkedString) -> String {
     match marked {
         MarkedString::String(text) => text,
@@ -2,5 +2,10 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = terminal_lib::cli::dispatch(&args) {
+        std::process::exit(code);
+    }
+
     terminal_lib::run()
 }
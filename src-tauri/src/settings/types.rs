@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Component, Path};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PermissionRules {
@@ -60,18 +61,51 @@ pub struct AgentConfigPatch {
     #[serde(default)]
     pub max_token_budget: Option<u64>,
     #[serde(default)]
+    pub max_cost_budget: Option<f64>,
+    #[serde(default)]
     pub thinking_enabled: Option<bool>,
     #[serde(default)]
     pub auto_summary_threshold: Option<f32>,
+    #[serde(default)]
+    pub sandbox: Option<SandboxConfigPatch>,
+}
+
+/// In a workspace file these can only tighten the global sandbox; see `tighten_sandbox`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxConfigPatch {
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub allow_network: Option<bool>,
+    #[serde(default)]
+    pub writable_paths: Option<Vec<String>>,
+}
+
+/// OS-level sandbox for the shell tool (Linux only). Writes are limited to the workspace,
+/// temp dirs and `writable_paths`; network is off unless `allow_network` is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxConfig {
+    pub enabled: bool,
+    pub allow_network: bool,
+    pub writable_paths: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AgentConfig {
     pub max_iterations: u32,
+    /// Tokens one run may spend across the root task and its subtasks; 0 disables the limit.
+    /// Counts fresh tokens only (uncached input, cache writes and output): cache reads are
+    /// left out because a long cached session re-reads its whole prompt every iteration.
     pub max_token_budget: u64,
+    /// USD one run may spend; `None` disables the limit
+    #[serde(default)]
+    pub max_cost_budget: Option<f64>,
     pub thinking_enabled: bool,
     pub auto_summary_threshold: f32,
+    pub sandbox: SandboxConfig,
 }
 
 impl Default for AgentConfig {
@@ -79,12 +113,161 @@ impl Default for AgentConfig {
         Self {
             max_iterations: 50,
             max_token_budget: 200_000,
+            max_cost_budget: None,
             thinking_enabled: true,
             auto_summary_threshold: 0.7,
+            sandbox: SandboxConfig::default(),
         }
     }
 }
 
+/// A shell command run around an agent lifecycle event. The event payload is written to the
+/// command's stdin as JSON.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookConfig {
+    /// Tool name patterns separated by `|` (globs allowed). Only used by tool events;
+    /// an empty matcher applies the hook to every tool.
+    #[serde(default)]
+    pub matcher: Option<String>,
+    pub command: String,
+    /// Timeout in seconds
+    #[serde(default)]
+    pub timeout: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HooksConfig {
+    #[serde(default)]
+    pub pre_tool_use: Vec<HookConfig>,
+    #[serde(default)]
+    pub post_tool_use: Vec<HookConfig>,
+    #[serde(default)]
+    pub user_prompt_submit: Vec<HookConfig>,
+    #[serde(default)]
+    pub session_start: Vec<HookConfig>,
+    #[serde(default)]
+    pub stop: Vec<HookConfig>,
+}
+
+impl HooksConfig {
+    pub fn is_empty(&self) -> bool {
+        self.pre_tool_use.is_empty()
+            && self.post_tool_use.is_empty()
+            && self.user_prompt_submit.is_empty()
+            && self.session_start.is_empty()
+            && self.stop.is_empty()
+    }
+}
+
+/// A language server definition, or an override of a built-in one when the key is a
+/// built-in id (`typescript`, `deno`, `vue`, `rust_analyzer`, `pyright`, `gopls`).
+/// Fields left unset keep the built-in behaviour.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspServerSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+    /// Globs matched against the file name and the workspace-relative path, e.g. `*.cpp`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_patterns: Option<Vec<String>>,
+    /// Files or directories marking the server root; the nearest match wins
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root_markers: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initialization_options: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+    /// `languageId` sent when opening documents; derived from the extension when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspSettings {
+    #[serde(default)]
+    pub servers: HashMap<String, LspServerSettings>,
+}
+
+impl LspSettings {
+    pub fn is_empty(&self) -> bool {
+        self.servers.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LocalEmbeddingBackend {
+    /// Ollama's native API
+    Ollama,
+    /// A text-embeddings-inference server
+    Tei,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RerankerType {
+    /// A rerank endpoint scoring query-document pairs
+    CrossEncoder,
+    /// A chat model asked to score relevance
+    Llm,
+}
+
+/// Second stage that reorders semantic search results
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RerankerSettings {
+    #[serde(rename = "type")]
+    pub kind: RerankerType,
+    /// Configured model whose provider and credentials serve the reranker; defaults to
+    /// the embedding model of the model list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<String>,
+    /// Model name sent to the provider; defaults to the name of the `modelId` model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// Embedding model served by a local process. When a backend is set, it replaces the
+/// embedding model of the model list. Only read from the global settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddingSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<LocalEmbeddingBackend>,
+    /// Server address; defaults to the backend's usual local port
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Required for Ollama; a TEI server reports the model it serves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Asked from the server on first use when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimension: Option<usize>,
+    /// Texts per request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<usize>,
+    /// Requests in flight at once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+    /// Applies with or without a local backend and replaces the embedding model's
+    /// `reranker` option
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reranker: Option<RerankerSettings>,
+}
+
+impl EmbeddingSettings {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 /// AI settings (shared structure for global and workspace)
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -103,6 +286,15 @@ pub struct Settings {
 
     #[serde(default)]
     pub agent: AgentConfigPatch,
+
+    #[serde(default, skip_serializing_if = "HooksConfig::is_empty")]
+    pub hooks: HooksConfig,
+
+    #[serde(default, skip_serializing_if = "LspSettings::is_empty")]
+    pub lsp: LspSettings,
+
+    #[serde(default, skip_serializing_if = "EmbeddingSettings::is_empty")]
+    pub embedding: EmbeddingSettings,
 }
 
 /// Merged effective settings
@@ -113,6 +305,8 @@ pub struct EffectiveSettings {
     pub mcp_servers: HashMap<String, McpServerConfig>,
     pub rules_content: String,
     pub agent: AgentConfig,
+    pub hooks: HooksConfig,
+    pub lsp: LspSettings,
 }
 
 impl EffectiveSettings {
@@ -132,11 +326,17 @@ impl EffectiveSettings {
 
         let agent = merge_agent(&global.agent, &workspace.agent);
 
+        let hooks = merge_hooks(&global.hooks, &workspace.hooks);
+
+        let lsp = merge_lsp(&global.lsp, &workspace.lsp);
+
         Self {
             permissions,
             mcp_servers,
             rules_content,
             agent,
+            hooks,
+            lsp,
         }
     }
 }
@@ -148,7 +348,7 @@ fn default_rules_files() -> Vec<String> {
         .collect()
 }
 
-fn merge_vec(a: &[String], b: &[String]) -> Vec<String> {
+fn merge_vec<T: Clone>(a: &[T], b: &[T]) -> Vec<T> {
     let mut out = Vec::with_capacity(a.len() + b.len());
     out.extend(a.iter().cloned());
     out.extend(b.iter().cloned());
@@ -178,12 +378,63 @@ fn merge_rules_content(global: &str, workspace: &str) -> String {
     }
 }
 
+/// Hooks accumulate: global hooks run first, then the workspace's.
+fn merge_hooks(global: &HooksConfig, workspace: &HooksConfig) -> HooksConfig {
+    HooksConfig {
+        pre_tool_use: merge_vec(&global.pre_tool_use, &workspace.pre_tool_use),
+        post_tool_use: merge_vec(&global.post_tool_use, &workspace.post_tool_use),
+        user_prompt_submit: merge_vec(&global.user_prompt_submit, &workspace.user_prompt_submit),
+        session_start: merge_vec(&global.session_start, &workspace.session_start),
+        stop: merge_vec(&global.stop, &workspace.stop),
+    }
+}
+
+/// Servers merge per field, so a workspace can change one setting of a global definition.
+fn merge_lsp(global: &LspSettings, workspace: &LspSettings) -> LspSettings {
+    let mut servers = global.servers.clone();
+    for (name, patch) in &workspace.servers {
+        let merged = servers.entry(name.clone()).or_default();
+        if patch.command.is_some() {
+            merged.command = patch.command.clone();
+        }
+        if patch.args.is_some() {
+            merged.args = patch.args.clone();
+        }
+        if patch.file_patterns.is_some() {
+            merged.file_patterns = patch.file_patterns.clone();
+        }
+        if patch.root_markers.is_some() {
+            merged.root_markers = patch.root_markers.clone();
+        }
+        if patch.initialization_options.is_some() {
+            merged.initialization_options = patch.initialization_options.clone();
+        }
+        if patch.env.is_some() {
+            merged.env = patch.env.clone();
+        }
+        if patch.language_id.is_some() {
+            merged.language_id = patch.language_id.clone();
+        }
+        if patch.disabled.is_some() {
+            merged.disabled = patch.disabled;
+        }
+    }
+    LspSettings { servers }
+}
+
 fn merge_agent(global: &AgentConfigPatch, workspace: &AgentConfigPatch) -> AgentConfig {
     let mut merged = AgentConfig::default();
 
     apply_agent_patch(&mut merged, global);
     apply_agent_patch(&mut merged, workspace);
 
+    if let Some(sandbox) = &global.sandbox {
+        apply_sandbox_patch(&mut merged.sandbox, sandbox);
+    }
+    if let Some(sandbox) = &workspace.sandbox {
+        tighten_sandbox(&mut merged.sandbox, sandbox);
+    }
+
     merged
 }
 
@@ -194,6 +445,9 @@ fn apply_agent_patch(target: &mut AgentConfig, patch: &AgentConfigPatch) {
     if let Some(v) = patch.max_token_budget {
         target.max_token_budget = v;
     }
+    if let Some(v) = patch.max_cost_budget {
+        target.max_cost_budget = Some(v);
+    }
     if let Some(v) = patch.thinking_enabled {
         target.thinking_enabled = v;
     }
@@ -202,6 +456,46 @@ fn apply_agent_patch(target: &mut AgentConfig, patch: &AgentConfigPatch) {
     }
 }
 
+fn apply_sandbox_patch(target: &mut SandboxConfig, patch: &SandboxConfigPatch) {
+    if let Some(v) = patch.enabled {
+        target.enabled = v;
+    }
+    if let Some(v) = patch.allow_network {
+        target.allow_network = v;
+    }
+    if let Some(v) = &patch.writable_paths {
+        target.writable_paths = v.clone();
+    }
+}
+
+/// A workspace file comes with the repository, so it may only make the sandbox stricter:
+/// it can't turn the sandbox off, open the network, or allow writes outside the workspace.
+fn tighten_sandbox(target: &mut SandboxConfig, patch: &SandboxConfigPatch) {
+    if patch.enabled == Some(true) {
+        target.enabled = true;
+    }
+    if patch.allow_network == Some(false) {
+        target.allow_network = false;
+    }
+    if let Some(paths) = &patch.writable_paths {
+        for path in paths {
+            if is_inside_workspace(path) && !target.writable_paths.contains(path) {
+                target.writable_paths.push(path.clone());
+            }
+        }
+    }
+}
+
+/// Relative paths resolve against the workspace; `..` could climb out of it
+fn is_inside_workspace(path: &str) -> bool {
+    let path = Path::new(path.trim());
+    !path.as_os_str().is_empty()
+        && path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        && !path.starts_with("~")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -231,4 +525,139 @@ mod tests {
         let serialized = serde_json::to_string(&config).unwrap();
         assert!(serialized.contains(r#""type":"streamable_http""#));
     }
+
+    #[test]
+    fn test_hooks_merge_global_then_workspace() {
+        let global: Settings = serde_json::from_str(
+            r#"{"hooks":{"preToolUse":[{"matcher":"write_file","command":"./check.sh"}]}}"#,
+        )
+        .unwrap();
+        let workspace: Settings = serde_json::from_str(
+            r#"{"hooks":{"preToolUse":[{"command":"./audit.sh","timeout":5}],"postToolUse":[{"matcher":"edit_file|write_file","command":"cargo fmt"}]}}"#,
+        )
+        .unwrap();
+
+        let merged = EffectiveSettings::merge(&global, Some(&workspace));
+        let pre: Vec<_> = merged
+            .hooks
+            .pre_tool_use
+            .iter()
+            .map(|h| h.command.as_str())
+            .collect();
+        assert_eq!(pre, vec!["./check.sh", "./audit.sh"]);
+        assert_eq!(merged.hooks.pre_tool_use[1].timeout, Some(5));
+        assert_eq!(merged.hooks.post_tool_use.len(), 1);
+        assert!(merged.hooks.stop.is_empty());
+    }
+
+    #[test]
+    fn test_empty_hooks_not_serialized() {
+        let serialized = serde_json::to_string(&Settings::default()).unwrap();
+        assert!(!serialized.contains("hooks"));
+    }
+
+    #[test]
+    fn test_workspace_sandbox_patch_only_tightens() {
+        let global: Settings = serde_json::from_str(
+            r#"{"agent":{"sandbox":{"enabled":true,"writablePaths":["~/.cargo"]}}}"#,
+        )
+        .unwrap();
+        let workspace: Settings = serde_json::from_str(
+            r#"{"agent":{"sandbox":{"enabled":false,"allowNetwork":true,"writablePaths":["target","../other","/etc","~/.ssh","./out"]}}}"#,
+        )
+        .unwrap();
+
+        let merged = EffectiveSettings::merge(&global, Some(&workspace));
+        assert!(merged.agent.sandbox.enabled);
+        assert!(!merged.agent.sandbox.allow_network);
+        assert_eq!(
+            merged.agent.sandbox.writable_paths,
+            vec!["~/.cargo", "target", "./out"]
+        );
+
+        let online: Settings =
+            serde_json::from_str(r#"{"agent":{"sandbox":{"enabled":true,"allowNetwork":true}}}"#)
+                .unwrap();
+        let offline: Settings =
+            serde_json::from_str(r#"{"agent":{"sandbox":{"allowNetwork":false}}}"#).unwrap();
+        let merged = EffectiveSettings::merge(&online, Some(&offline));
+        assert!(!merged.agent.sandbox.allow_network);
+
+        let defaults = EffectiveSettings::merge(&Settings::default(), None);
+        assert!(!defaults.agent.sandbox.enabled);
+    }
+
+    #[test]
+    fn test_budget_patch_merges() {
+        let global: Settings =
+            serde_json::from_str(r#"{"agent":{"maxTokenBudget":2000000,"maxCostBudget":5.0}}"#)
+                .unwrap();
+        let workspace: Settings =
+            serde_json::from_str(r#"{"agent":{"maxCostBudget":1.5}}"#).unwrap();
+
+        let merged = EffectiveSettings::merge(&global, Some(&workspace));
+        assert_eq!(merged.agent.max_token_budget, 2_000_000);
+        assert_eq!(merged.agent.max_cost_budget, Some(1.5));
+
+        let defaults = EffectiveSettings::merge(&Settings::default(), None);
+        assert_eq!(defaults.agent.max_token_budget, 200_000);
+        assert_eq!(defaults.agent.max_cost_budget, None);
+    }
+
+    #[test]
+    fn test_lsp_servers_merge_per_field() {
+        let global: Settings = serde_json::from_str(
+            r#"{"lsp":{"servers":{"clangd":{"command":"clangd","args":["--background-index"],"filePatterns":["*.cpp","*.h"]}}}}"#,
+        )
+        .unwrap();
+        let workspace: Settings = serde_json::from_str(
+            r#"{"lsp":{"servers":{"clangd":{"args":[]},"rust_analyzer":{"disabled":true}}}}"#,
+        )
+        .unwrap();
+
+        let merged = EffectiveSettings::merge(&global, Some(&workspace));
+        let clangd = &merged.lsp.servers["clangd"];
+        assert_eq!(clangd.command.as_deref(), Some("clangd"));
+        assert_eq!(clangd.args, Some(Vec::new()));
+        assert_eq!(clangd.file_patterns.as_ref().map(Vec::len), Some(2));
+        assert_eq!(merged.lsp.servers["rust_analyzer"].disabled, Some(true));
+
+        let serialized = serde_json::to_string(&Settings::default()).unwrap();
+        assert!(!serialized.contains("lsp"));
+    }
+
+    #[test]
+    fn test_embedding_settings_select_a_local_backend() {
+        let settings: Settings = serde_json::from_str(
+            r#"{"embedding":{"backend":"ollama","model":"nomic-embed-text","batchSize":16}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            settings.embedding.backend,
+            Some(LocalEmbeddingBackend::Ollama)
+        );
+        assert_eq!(settings.embedding.batch_size, Some(16));
+        assert_eq!(settings.embedding.dimension, None);
+
+        let serialized = serde_json::to_string(&Settings::default()).unwrap();
+        assert!(!serialized.contains("embedding"));
+    }
+
+    #[test]
+    fn test_embedding_settings_reranker() {
+        let settings: Settings = serde_json::from_str(
+            r#"{"embedding":{"reranker":{"type":"crossEncoder","modelId":"cohere"}}}"#,
+        )
+        .unwrap();
+        let reranker = settings.embedding.reranker.unwrap();
+        assert_eq!(reranker.kind, RerankerType::CrossEncoder);
+        assert_eq!(reranker.model_id.as_deref(), Some("cohere"));
+        assert_eq!(reranker.model, None);
+        assert_eq!(settings.embedding.backend, None);
+
+        assert!(
+            serde_json::from_str::<Settings>(r#"{"embedding":{"reranker":{"type":"bm25"}}}"#)
+                .is_err()
+        );
+    }
 }
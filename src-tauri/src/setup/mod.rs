@@ -20,7 +20,7 @@ use tauri::{Emitter, Manager};
 use tracing::warn;
 use tracing_subscriber::{self, EnvFilter};
 
-fn resolve_app_data_dir() -> SetupResult<std::path::PathBuf> {
+pub(crate) fn resolve_app_data_dir() -> SetupResult<std::path::PathBuf> {
     use std::env;
 
     match env::var("OPENCODEX_DATA_DIR") {
@@ -100,10 +100,11 @@ pub fn initialize_app_states<R: tauri::Runtime>(app: &tauri::App<R>) -> SetupRes
     app.manage(shortcut_state);
 
     // Initialize SettingsManager (settings.json / workspace .opencodex/settings.json)
-    app.manage(Arc::new(SettingsManager::new()?));
-    // Initialize MCP Registry (cache MCP clients by workspace)
-    app.manage(Arc::new(crate::agent::mcp::McpRegistry::default()));
-    app.manage(Arc::new(crate::lsp::LspManager::new()));
+    let settings_manager = Arc::new(SettingsManager::new()?);
+    app.manage(Arc::clone(&settings_manager));
+    app.manage(Arc::new(crate::lsp::LspManager::with_settings(
+        settings_manager,
+    )));
 
     // Initialize DatabaseManager
     let database_manager = {
@@ -219,7 +220,31 @@ pub fn initialize_app_states<R: tauri::Runtime>(app: &tauri::App<R>) -> SetupRes
             .clone();
         Arc::new(crate::llm::oauth::OAuthManager::new(database))
     };
-    app.manage(oauth_manager);
+    app.manage(oauth_manager.clone());
+
+    // Initialize MCP Registry (cache MCP clients by workspace, OAuth for remote servers)
+    let mcp_oauth = Arc::new(crate::agent::mcp::oauth::McpOAuth::new(
+        database_manager.clone(),
+        oauth_manager,
+    ));
+    let mcp_registry = Arc::new(crate::agent::mcp::McpRegistry::new(mcp_oauth));
+    let mut mcp_status_rx = mcp_registry.subscribe_status();
+    let app_handle = app.handle().clone();
+    tauri::async_runtime::spawn(async move {
+        use tokio::sync::broadcast::error::RecvError;
+        loop {
+            match mcp_status_rx.recv().await {
+                Ok(event) => {
+                    if let Err(err) = app_handle.emit("mcp:server-status", &event) {
+                        warn!("Failed to emit MCP server status: {}", err);
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+    app.manage(mcp_registry);
 
     // Initialize Checkpoint service (create early for TaskExecutor use)
     let checkpoint_service = {
@@ -244,12 +269,8 @@ pub fn initialize_app_states<R: tauri::Runtime>(app: &tauri::App<R>) -> SetupRes
         std::sync::Arc::new(crate::agent::workspace_changes::WorkspaceChangeJournal::new());
     app.manage(std::sync::Arc::clone(&workspace_changes));
 
-    let watcher = std::sync::Arc::new(
-        crate::file_watcher::UnifiedFileWatcher::new().with_fs_sink(workspace_changes.fs_sender()),
-    );
-    app.manage(std::sync::Arc::clone(&watcher));
-
     // Initialize vector database state (and inject search_engine into TaskExecutor for agent's semantic_search tool)
+    // and the file watcher, which also feeds the background indexer
     let vector_search_engine = {
         use crate::vector_db::commands::VectorDbState;
         use std::sync::Arc;
@@ -258,20 +279,33 @@ pub fn initialize_app_states<R: tauri::Runtime>(app: &tauri::App<R>) -> SetupRes
             .state::<Arc<crate::storage::DatabaseManager>>()
             .inner()
             .clone();
-        match tauri::async_runtime::block_on(crate::vector_db::build_search_engine_from_database(
-            database,
-        )) {
-            Ok(search_engine) => {
-                let state = VectorDbState::new(Arc::clone(&search_engine));
-                app.manage(state);
-                Some(search_engine)
-            }
+        let settings_manager = app
+            .state::<Arc<crate::settings::SettingsManager>>()
+            .inner()
+            .clone();
+        let (state, search_engine) = match tauri::async_runtime::block_on(
+            crate::vector_db::build_search_engine(database, &settings_manager),
+        ) {
+            Ok(search_engine) => (
+                VectorDbState::new(Arc::clone(&search_engine)),
+                Some(search_engine),
+            ),
             Err(e) => {
                 warn!("Failed to initialize vector DB: {}", e);
-                app.manage(VectorDbState::empty());
-                None
+                (VectorDbState::empty(), None)
             }
-        }
+        };
+        // Keeps built indexes in sync with the files the watcher reports
+        let indexer = crate::vector_db::BackgroundIndexer::new(state.clone());
+        app.manage(state);
+
+        app.manage(Arc::new(
+            crate::file_watcher::UnifiedFileWatcher::new()
+                .with_fs_sink(workspace_changes.fs_sender())
+                .with_fs_sink(indexer.fs_sender()),
+        ));
+
+        search_engine
     };
 
     // Initialize TaskExecutor state (with Checkpoint service)
@@ -183,6 +183,7 @@ impl DatabaseManager {
         self.ensure_messages_schema().await?;
         self.ensure_workspaces_schema().await?;
         self.ensure_sessions_schema().await?;
+        self.ensure_cost_schema().await?;
         self.insert_default_data().await?;
         Ok(())
     }
@@ -542,6 +543,114 @@ impl DatabaseManager {
         Ok(())
     }
 
+    async fn table_has_column(&self, table: &str, column: &str) -> DatabaseResult<bool> {
+        let rows = sqlx::query(&format!("PRAGMA table_info({table})"))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|err| {
+                DatabaseError::internal(format!("Failed to inspect {table} schema: {err}"))
+            })?;
+        for row in &rows {
+            if pragma_text_column(row, "name", "table_info")? == column {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Cost accounting columns, and `ai_model_usage_stats` without its foreign key to the
+    /// legacy `ai_models` table (models live in models.json, so every insert would fail).
+    async fn ensure_cost_schema(&self) -> DatabaseResult<()> {
+        let migrations = [
+            (
+                "messages",
+                "cost",
+                "ALTER TABLE messages ADD COLUMN cost REAL NOT NULL DEFAULT 0",
+            ),
+            (
+                "runs",
+                "total_cost",
+                "ALTER TABLE runs ADD COLUMN total_cost REAL NOT NULL DEFAULT 0",
+            ),
+        ];
+        for (table, column, statement) in migrations {
+            if self.table_has_column(table, column).await? {
+                continue;
+            }
+            sqlx::query(statement)
+                .execute(&self.pool)
+                .await
+                .map_err(|err| {
+                    DatabaseError::internal(format!(
+                        "Failed to migrate {table} schema (add {column}): {err}"
+                    ))
+                })?;
+        }
+
+        let foreign_keys = sqlx::query("PRAGMA foreign_key_list(ai_model_usage_stats)")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|err| {
+                DatabaseError::internal(format!(
+                    "Failed to inspect ai_model_usage_stats schema: {err}"
+                ))
+            })?;
+        if !foreign_keys.is_empty() {
+            // The old table allowed several rows per model; they are folded into one
+            let mut tx = self.pool.begin().await.map_err(|err| {
+                DatabaseError::internal(format!("Failed to begin transaction: {err}"))
+            })?;
+            for (step, statement) in [
+                (
+                    "create ai_model_usage_stats_new",
+                    "CREATE TABLE ai_model_usage_stats_new (
+                        id INTEGER PRIMARY KEY AUTOINCREMENT,
+                        model_id TEXT NOT NULL UNIQUE,
+                        request_count INTEGER DEFAULT 0,
+                        total_tokens INTEGER DEFAULT 0,
+                        total_cost REAL DEFAULT 0.0,
+                        last_used_at DATETIME,
+                        created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                        updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                    )",
+                ),
+                (
+                    "copy data",
+                    "INSERT INTO ai_model_usage_stats_new (
+                        model_id, request_count, total_tokens, total_cost,
+                        last_used_at, created_at, updated_at
+                    )
+                    SELECT
+                        model_id, SUM(COALESCE(request_count, 0)), SUM(COALESCE(total_tokens, 0)),
+                        SUM(COALESCE(total_cost, 0.0)), MAX(last_used_at),
+                        COALESCE(MIN(created_at), CURRENT_TIMESTAMP),
+                        COALESCE(MAX(updated_at), CURRENT_TIMESTAMP)
+                    FROM ai_model_usage_stats
+                    GROUP BY model_id",
+                ),
+                ("drop old table", "DROP TABLE ai_model_usage_stats"),
+                (
+                    "rename table",
+                    "ALTER TABLE ai_model_usage_stats_new RENAME TO ai_model_usage_stats",
+                ),
+            ] {
+                sqlx::query(statement)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|err| {
+                        DatabaseError::internal(format!(
+                            "Failed to migrate ai_model_usage_stats schema ({step}): {err}"
+                        ))
+                    })?;
+            }
+            tx.commit().await.map_err(|err| {
+                DatabaseError::internal(format!("Failed to commit transaction: {err}"))
+            })?;
+        }
+
+        Ok(())
+    }
+
     async fn insert_default_data(&self) -> DatabaseResult<()> {
         let features = [
             ("chat", true, r#"{"max_history":100,"auto_save":true}"#),
@@ -780,4 +889,61 @@ mod tests {
         let decrypted = manager.decrypt_data(&encrypted).await.unwrap();
         assert_eq!(decrypted, "hello world");
     }
+
+    #[tokio::test]
+    async fn usage_stats_migration_keeps_rows() {
+        let temp_dir = TempDir::new().unwrap();
+        let paths = crate::storage::paths::StoragePathsBuilder::new()
+            .app_dir(temp_dir.path().to_path_buf())
+            .build()
+            .unwrap();
+        paths.ensure_directories().unwrap();
+        let manager = DatabaseManager::new(paths.clone(), DatabaseOptions::default())
+            .await
+            .unwrap();
+        manager.initialize().await.unwrap();
+
+        // Recreate the table as older versions defined it
+        for statement in [
+            "DROP TABLE ai_model_usage_stats",
+            "CREATE TABLE ai_model_usage_stats (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                model_id TEXT NOT NULL,
+                request_count INTEGER DEFAULT 0,
+                total_tokens INTEGER DEFAULT 0,
+                total_cost REAL DEFAULT 0.0,
+                last_used_at DATETIME,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (model_id) REFERENCES ai_models(id) ON DELETE CASCADE
+            )",
+            "INSERT INTO ai_models (id, provider, model_name) VALUES ('a', 'openai', 'gpt'), ('b', 'anthropic', 'claude')",
+            "INSERT INTO ai_model_usage_stats (model_id, request_count, total_tokens, total_cost)
+             VALUES ('a', 2, 100, 0.5), ('a', 1, 50, 0.25), ('b', 4, 400, 1.0)",
+        ] {
+            sqlx::query(statement).execute(manager.pool()).await.unwrap();
+        }
+
+        manager.ensure_cost_schema().await.unwrap();
+
+        let foreign_keys = sqlx::query("PRAGMA foreign_key_list(ai_model_usage_stats)")
+            .fetch_all(manager.pool())
+            .await
+            .unwrap();
+        assert!(foreign_keys.is_empty());
+        let rows: Vec<(String, i64, i64, f64)> = sqlx::query_as(
+            "SELECT model_id, request_count, total_tokens, total_cost
+             FROM ai_model_usage_stats ORDER BY model_id",
+        )
+        .fetch_all(manager.pool())
+        .await
+        .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                ("a".to_string(), 3, 150, 0.75),
+                ("b".to_string(), 4, 400, 1.0),
+            ]
+        );
+    }
 }
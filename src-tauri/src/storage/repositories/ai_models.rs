@@ -382,6 +382,29 @@ impl<'a> AIModels<'a> {
         self.write_config_file_locked(&file).await
     }
 
+    /// Replace only the OAuth tokens of a model, leaving edits made meanwhile intact.
+    pub async fn update_oauth_config(
+        &self,
+        id: &str,
+        oauth_config: &OAuthConfig,
+    ) -> RepositoryResult<()> {
+        let _guard = MODELS_FILE_LOCK.lock().await;
+        let file = self.load_config_file_locked().await?;
+        let defaults = file.defaults.clone();
+        let agents = file.agents.clone();
+        let mut models = flatten_models(file);
+
+        let model = models
+            .iter_mut()
+            .find(|model| model.id == id)
+            .ok_or_else(|| RepositoryError::AiModelNotFound { id: id.to_string() })?;
+        model.oauth_config = Some(oauth_config.clone());
+        model.updated_at = Utc::now();
+
+        let file = build_models_file(models, defaults, agents);
+        self.write_config_file_locked(&file).await
+    }
+
     pub async fn find_by_id(&self, id: &str) -> RepositoryResult<Option<AIModelConfig>> {
         let models = self.find_all().await?;
         Ok(models.into_iter().find(|model| model.id == id))
@@ -3,7 +3,7 @@ use crate::vector_db::commands::VectorDbState;
 use crate::{api_error, api_success};
 use parking_lot::Mutex;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, OnceLock};
 use tauri::{ipc::Channel, State};
 use tokio::sync::broadcast;
@@ -106,6 +106,17 @@ fn build_tasks() -> &'static Mutex<HashMap<String, BuildEntry>> {
     BUILD_TASKS.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
+/// Whether a manual build is running for `root`. Builds are keyed by the path the UI
+/// passed in, so paths are compared canonicalized.
+pub(crate) fn build_running(root: &Path) -> bool {
+    let store = build_tasks().lock();
+    store.iter().any(|(path, entry)| {
+        !entry.state.snapshot().is_done
+            && (Path::new(path) == root
+                || std::fs::canonicalize(path).is_ok_and(|path| path == root))
+    })
+}
+
 fn send_progress(channel: &Channel<VectorBuildProgress>, p: VectorBuildProgress) -> bool {
     if let Err(e) = channel.send(p) {
         warn!("Failed to send vector build progress: {}", e);
@@ -114,10 +125,11 @@ fn send_progress(channel: &Channel<VectorBuildProgress>, p: VectorBuildProgress)
     true
 }
 
+/// Without a search engine only the keyword index is built.
 fn start_build_locked(
     store: &mut HashMap<String, BuildEntry>,
     path: String,
-    state: Arc<crate::vector_db::SemanticSearchEngine>,
+    state: Option<Arc<crate::vector_db::SemanticSearchEngine>>,
 ) {
     if let Some(existing) = store.remove(&path) {
         existing.token.cancel();
@@ -128,13 +140,14 @@ fn start_build_locked(
     let task_state = Arc::new(BuildState::new(path.clone()));
 
     let root = PathBuf::from(&path);
-    let config = state.config().clone();
-    let embedder = state.embedder();
+    let embedder = state.as_ref().map(|engine| engine.embedder());
     let token_for_task = token.clone();
     let task_state_for_task = Arc::clone(&task_state);
 
     let handle = tokio::spawn(async move {
-        state.invalidate_workspace_index(&root);
+        if let Some(engine) = &state {
+            engine.invalidate_workspace_index(&root);
+        }
 
         task_state_for_task.update(|p| {
             p.phase = VectorBuildPhase::CollectingFiles;
@@ -142,6 +155,23 @@ fn start_build_locked(
             p.is_done = false;
         });
 
+        // A local embedding server is first reached here
+        let config = match &state {
+            Some(engine) => match engine.connect().await {
+                Ok(()) => engine.config(),
+                Err(e) => {
+                    error!("Failed to reach the embedding server: {}", e);
+                    task_state_for_task.update(|p| {
+                        p.phase = VectorBuildPhase::Failed;
+                        p.is_done = true;
+                        p.error = Some(e.to_string());
+                    });
+                    return;
+                }
+            },
+            None => Default::default(),
+        };
+
         let manager = match crate::vector_db::storage::IndexManager::new(&root, config.clone()) {
             Ok(m) => Arc::new(m),
             Err(e) => {
@@ -187,6 +217,9 @@ fn start_build_locked(
 
         for file_path in files {
             if token_for_task.is_cancelled() {
+                if let Err(e) = manager.save_lexical_index() {
+                    warn!("Failed to save keyword index: {}", e);
+                }
                 task_state_for_task.update(|p| {
                     p.phase = VectorBuildPhase::Cancelled;
                     p.is_done = true;
@@ -206,7 +239,7 @@ fn start_build_locked(
             });
 
             let res = manager
-                .index_file_with_progress(&file_path, &*embedder, |done, total| {
+                .index_file_with_progress(&file_path, embedder.as_deref(), |done, total| {
                     task_state_for_task.update(|p| {
                         p.phase = VectorBuildPhase::Embedding;
                         p.current_file_chunks_total = total;
@@ -234,8 +267,16 @@ fn start_build_locked(
             }
         }
 
+        let keyword_error = manager.save_lexical_index().err();
+        if let Some(e) = &keyword_error {
+            error!("Failed to save keyword index: {}", e);
+        }
+
         task_state_for_task.update(|p| {
-            p.phase = if p.files_failed > 0 {
+            if let Some(e) = keyword_error {
+                p.error = Some(e.to_string());
+            }
+            p.phase = if p.files_failed > 0 || p.error.is_some() {
                 VectorBuildPhase::Failed
             } else {
                 VectorBuildPhase::Completed
@@ -262,22 +303,23 @@ pub async fn vector_build_index_start(
     path: String,
     state: State<'_, VectorDbState>,
     database: State<'_, Arc<crate::storage::DatabaseManager>>,
+    settings_manager: State<'_, Arc<crate::settings::SettingsManager>>,
 ) -> TauriApiResult<EmptyData> {
     let search_engine =
-        match crate::vector_db::build_search_engine_from_database(database.inner().clone()).await {
+        match crate::vector_db::build_search_engine(database.inner().clone(), &settings_manager)
+            .await
+        {
             Ok(engine) => {
                 state.replace_search_engine(Arc::clone(&engine));
-                engine
+                Some(engine)
             }
             Err(e) => {
                 warn!(
                     error = %e,
                     "Failed to refresh embedding config before build; using existing vector engine"
                 );
-                match state.current_search_engine() {
-                    Some(engine) => engine,
-                    None => return Ok(api_error!("vector_db.engine_not_configured")),
-                }
+                // With no embedding model at all, the keyword index is still built
+                state.current_search_engine()
             }
         };
 
@@ -19,15 +19,17 @@ pub async fn get_index_status(
             total_chunks: 0,
             embedding_model: String::new(),
             vector_dimension: 0,
+            keyword_files: 0,
+            keyword_chunks: 0,
             size_bytes: 0,
         }));
     }
 
-    let engine = match state.current_search_engine() {
-        Some(e) => e,
-        None => return Ok(api_error!("vector_db.engine_not_configured")),
-    };
-    let config = engine.config().clone();
+    // Without an embedding model the workspace may still have a keyword index
+    let config = state
+        .current_search_engine()
+        .map(|engine| engine.config())
+        .unwrap_or_default();
     match crate::vector_db::storage::IndexManager::new(&workspace_path, config) {
         Ok(manager) => Ok(api_success!(manager.get_status_with_size_bytes())),
         Err(e) => {
@@ -70,8 +72,9 @@ pub async fn delete_workspace_index(
 pub async fn vector_reload_embedding_config(
     state: State<'_, VectorDbState>,
     database: State<'_, Arc<crate::storage::DatabaseManager>>,
+    settings_manager: State<'_, Arc<crate::settings::SettingsManager>>,
 ) -> TauriApiResult<EmptyData> {
-    match crate::vector_db::build_search_engine_from_database(database.inner().clone()).await {
+    match crate::vector_db::build_search_engine(database.inner().clone(), &settings_manager).await {
         Ok(search_engine) => {
             state.replace_search_engine(search_engine);
             Ok(api_success!())
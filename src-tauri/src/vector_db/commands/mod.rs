@@ -8,22 +8,23 @@ use crate::vector_db::SemanticSearchEngine;
 use std::sync::Arc;
 use std::sync::RwLock;
 
-/// Vector database global state (managed by Tauri)
+/// Vector database global state (managed by Tauri). Clones share the same engine slot.
+#[derive(Clone)]
 pub struct VectorDbState {
-    search_engine: RwLock<Option<Arc<SemanticSearchEngine>>>,
+    search_engine: Arc<RwLock<Option<Arc<SemanticSearchEngine>>>>,
 }
 
 impl VectorDbState {
     pub fn new(search_engine: Arc<SemanticSearchEngine>) -> Self {
         Self {
-            search_engine: RwLock::new(Some(search_engine)),
+            search_engine: Arc::new(RwLock::new(Some(search_engine))),
         }
     }
 
     /// Create a state with no search engine (embedding model not configured).
     pub fn empty() -> Self {
         Self {
-            search_engine: RwLock::new(None),
+            search_engine: Arc::new(RwLock::new(None)),
         }
     }
 
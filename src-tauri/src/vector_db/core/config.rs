@@ -38,12 +38,81 @@ impl Default for RemoteEmbeddingConfig {
     }
 }
 
+/// Where embeddings come from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum EmbeddingBackend {
+    /// The LLM provider in [`RemoteEmbeddingConfig::provider_config`]
+    #[default]
+    Provider,
+    /// Ollama's native `/api/embed`
+    Ollama { url: String },
+    /// A text-embeddings-inference server (`/embed`)
+    Tei { url: String },
+}
+
+impl EmbeddingBackend {
+    /// Local servers need no API key, and report the dimension when it is left at 0
+    pub fn is_local(&self) -> bool {
+        !matches!(self, Self::Provider)
+    }
+}
+
+/// Request shaping for local embedding servers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingBatchConfig {
+    /// Texts per request
+    pub batch_size: usize,
+
+    /// Requests in flight at once, over all searches and index builds
+    pub max_concurrency: usize,
+}
+
+impl Default for EmbeddingBatchConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 32,
+            max_concurrency: 2,
+        }
+    }
+}
+
+/// Second-stage model that rescores search candidates against the query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RerankerKind {
+    /// A rerank endpoint (Cohere, Jina and Voyage style) scoring query/passage pairs
+    CrossEncoder,
+    /// A chat model asked to grade each passage
+    Llm,
+}
+
+/// Reranker configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RerankerConfig {
+    pub kind: RerankerKind,
+
+    /// LLM Provider configuration (API Key, URL, etc.)
+    pub provider_config: LLMProviderConfig,
+
+    /// Model name (e.g., "rerank-v3.5")
+    pub model_name: String,
+}
+
 /// Vector database configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VectorDbConfig {
     /// Remote embedding model configuration
     pub embedding: RemoteEmbeddingConfig,
 
+    /// Service that computes the embeddings
+    #[serde(default)]
+    pub backend: EmbeddingBackend,
+
+    /// Batching for local backends
+    #[serde(default)]
+    pub batching: EmbeddingBatchConfig,
+
     /// Maximum number of results to return when searching
     pub max_results: usize,
 
@@ -58,34 +127,44 @@ pub struct VectorDbConfig {
 
     /// Keyword search weight (0.0-1.0)
     pub keyword_weight: f32,
+
+    /// Optional reranker for the best search candidates
+    #[serde(default)]
+    pub reranker: Option<RerankerConfig>,
 }
 
 impl Default for VectorDbConfig {
     fn default() -> Self {
         Self {
             embedding: RemoteEmbeddingConfig::default(),
+            backend: EmbeddingBackend::default(),
+            batching: EmbeddingBatchConfig::default(),
             max_results: 20,
             similarity_threshold: 0.3,
             max_file_size: 10 * 1024 * 1024,
             semantic_weight: 0.7,
             keyword_weight: 0.3,
+            reranker: None,
         }
     }
 }
 
 impl VectorDbConfig {
     pub fn validate(&self) -> crate::vector_db::core::Result<()> {
-        if self.embedding.model_name.is_empty() {
+        // A TEI server serves a single model and reports its name
+        if self.embedding.model_name.is_empty()
+            && !matches!(self.backend, EmbeddingBackend::Tei { .. })
+        {
             return Err(crate::vector_db::core::VectorDbError::Config(
                 "Embedding model name is required".to_string(),
             ));
         }
-        if self.embedding.provider_config.api_key.is_empty() {
+        if self.embedding.provider_config.api_key.is_empty() && !self.backend.is_local() {
             return Err(crate::vector_db::core::VectorDbError::Config(
                 "API key is required".to_string(),
             ));
         }
-        if self.embedding.dimension == 0 {
+        if self.embedding.dimension == 0 && !self.backend.is_local() {
             return Err(crate::vector_db::core::VectorDbError::Config(
                 "Dimension must be > 0".to_string(),
             ));
@@ -100,6 +179,11 @@ impl VectorDbConfig {
                 "Chunk overlap must be < chunk size".to_string(),
             ));
         }
+        if self.batching.batch_size == 0 || self.batching.max_concurrency == 0 {
+            return Err(crate::vector_db::core::VectorDbError::Config(
+                "Batch size and concurrency must be > 0".to_string(),
+            ));
+        }
         if self.similarity_threshold < 0.0 || self.similarity_threshold > 1.0 {
             return Err(crate::vector_db::core::VectorDbError::Config(
                 "Similarity threshold must be in [0, 1]".to_string(),
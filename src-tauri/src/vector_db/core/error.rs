@@ -20,6 +20,12 @@ pub enum VectorDbError {
     #[error("Embedding error: {0}")]
     Embedding(String),
 
+    #[error("Embedding provider rate limit: {0}")]
+    RateLimited(String),
+
+    #[error("Rerank error: {0}")]
+    Rerank(String),
+
     #[error("Search error: {0}")]
     Search(String),
 
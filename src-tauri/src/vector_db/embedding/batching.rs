@@ -0,0 +1,99 @@
+use crate::vector_db::core::{EmbeddingBatchConfig, Result, VectorDbError};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Splits embedding calls into requests of bounded size and caps the requests in flight.
+/// The cap is shared by every caller of one embedder, so a running build and a search
+/// don't overload a local server together.
+pub struct EmbedBatcher {
+    batch_size: usize,
+    permits: Arc<Semaphore>,
+}
+
+impl EmbedBatcher {
+    pub fn new(config: &EmbeddingBatchConfig) -> Self {
+        Self {
+            batch_size: config.batch_size.max(1),
+            permits: Arc::new(Semaphore::new(config.max_concurrency.max(1))),
+        }
+    }
+
+    /// Lower the batch size to what the server accepts
+    pub fn limit_batch_size(&mut self, max: usize) {
+        self.batch_size = self.batch_size.min(max.max(1));
+    }
+
+    /// Embed `texts` with one `request` per batch. Vectors come back in input order.
+    pub async fn embed<F, Fut>(&self, texts: &[&str], request: F) -> Result<Vec<Vec<f32>>>
+    where
+        F: Fn(Vec<String>) -> Fut,
+        Fut: Future<Output = Result<Vec<Vec<f32>>>>,
+    {
+        let batches = texts.chunks(self.batch_size).map(|batch| {
+            let request = &request;
+            async move {
+                let _permit = self
+                    .permits
+                    .acquire()
+                    .await
+                    .map_err(|e| VectorDbError::Embedding(e.to_string()))?;
+                let vectors = request(batch.iter().map(|text| text.to_string()).collect()).await?;
+                if vectors.len() != batch.len() {
+                    return Err(VectorDbError::Embedding(format!(
+                        "Expected {} embeddings, got {}",
+                        batch.len(),
+                        vectors.len()
+                    )));
+                }
+                Ok(vectors)
+            }
+        });
+
+        let batches = futures::future::try_join_all(batches).await?;
+        Ok(batches.into_iter().flatten().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn batches_keep_order_and_respect_the_concurrency_cap() {
+        let batcher = EmbedBatcher::new(&EmbeddingBatchConfig {
+            batch_size: 2,
+            max_concurrency: 2,
+        });
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let texts = ["a", "bb", "ccc", "dddd", "eeeee"];
+
+        let vectors = batcher
+            .embed(&texts, |batch| {
+                let in_flight = &in_flight;
+                let peak = &peak;
+                async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    assert!(batch.len() <= 2);
+                    Ok(batch.iter().map(|text| vec![text.len() as f32]).collect())
+                }
+            })
+            .await
+            .unwrap();
+
+        let lengths: Vec<f32> = vectors.iter().map(|v| v[0]).collect();
+        assert_eq!(lengths, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+
+        let short = batcher
+            .embed(&texts, |_| async { Ok(vec![vec![0.0]]) })
+            .await;
+        assert!(short.is_err());
+    }
+}
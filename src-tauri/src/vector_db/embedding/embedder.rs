@@ -1,4 +1,4 @@
-use crate::vector_db::core::Result;
+use crate::vector_db::core::{EmbeddingBackend, Result, VectorDbConfig};
 use async_trait::async_trait;
 use std::sync::Arc;
 
@@ -8,15 +8,53 @@ pub trait Embedder: Send + Sync {
     fn dim(&self) -> usize;
     fn model_name(&self) -> &str;
     async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>>;
+
+    /// Reach the server so `dim` and `model_name` report what it serves. Embedders
+    /// configured up front have nothing to ask.
+    async fn connect(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Create embedder (single entry point). Local backends don't contact their server until
+/// first used; call [`Embedder::connect`] before reading a dimension or model name that
+/// was left unset.
+pub fn create_embedder(config: &VectorDbConfig) -> Result<Arc<dyn Embedder>> {
+    let embedding = &config.embedding;
+    Ok(match &config.backend {
+        EmbeddingBackend::Provider => Arc::new(super::remote::RemoteEmbedder::new(
+            embedding.provider_config.clone(),
+            embedding.model_name.clone(),
+            embedding.dimension,
+        )?),
+        EmbeddingBackend::Ollama { .. } | EmbeddingBackend::Tei { .. } => {
+            Arc::new(super::local::LazyLocalEmbedder::new(config.clone()))
+        }
+    })
 }
 
-/// Create embedder (single entry point)
-pub fn create_embedder(
-    config: &crate::vector_db::core::RemoteEmbeddingConfig,
-) -> Result<Arc<dyn Embedder>> {
-    Ok(Arc::new(super::remote::RemoteEmbedder::new(
-        config.provider_config.clone(),
-        config.model_name.clone(),
-        config.dimension,
-    )?))
+/// Connect the local backend `config` selects
+pub(super) async fn connect_local(config: &VectorDbConfig) -> Result<Arc<dyn Embedder>> {
+    let embedding = &config.embedding;
+    Ok(match &config.backend {
+        EmbeddingBackend::Provider => create_embedder(config)?,
+        EmbeddingBackend::Ollama { url } => Arc::new(
+            super::ollama::OllamaEmbedder::connect(
+                url,
+                embedding.model_name.clone(),
+                embedding.dimension,
+                &config.batching,
+            )
+            .await?,
+        ),
+        EmbeddingBackend::Tei { url } => Arc::new(
+            super::tei::TeiEmbedder::connect(
+                url,
+                embedding.model_name.clone(),
+                embedding.dimension,
+                &config.batching,
+            )
+            .await?,
+        ),
+    })
 }
@@ -0,0 +1,194 @@
+//! HTTP plumbing shared by the embedding backends served by a local process
+
+use super::Embedder;
+use crate::vector_db::core::{EmbeddingBackend, Result, VectorDbConfig, VectorDbError};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+/// Models on CPU can take a while per batch
+static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(120))
+        .no_proxy()
+        .build()
+        .expect("failed to build local embedding HTTP client")
+});
+
+/// Connects to the server on first use, so building the search engine never waits on it.
+/// A failed connection isn't kept; the next call tries again.
+pub(super) struct LazyLocalEmbedder {
+    config: VectorDbConfig,
+    connected: OnceCell<Arc<dyn Embedder>>,
+}
+
+impl LazyLocalEmbedder {
+    pub(super) fn new(config: VectorDbConfig) -> Self {
+        Self {
+            config,
+            connected: OnceCell::new(),
+        }
+    }
+
+    async fn embedder(&self) -> Result<&Arc<dyn Embedder>> {
+        self.connected
+            .get_or_try_init(|| super::embedder::connect_local(&self.config))
+            .await
+    }
+}
+
+#[async_trait]
+impl Embedder for LazyLocalEmbedder {
+    fn id(&self) -> &str {
+        match self.config.backend {
+            EmbeddingBackend::Provider => "remote",
+            EmbeddingBackend::Ollama { .. } => "ollama",
+            EmbeddingBackend::Tei { .. } => "tei",
+        }
+    }
+
+    /// 0 until connected when the settings leave it unset
+    fn dim(&self) -> usize {
+        self.connected
+            .get()
+            .map_or(self.config.embedding.dimension, |embedder| embedder.dim())
+    }
+
+    fn model_name(&self) -> &str {
+        self.connected
+            .get()
+            .map_or(&self.config.embedding.model_name, |embedder| {
+                embedder.model_name()
+            })
+    }
+
+    async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        self.embedder().await?.embed(texts).await
+    }
+
+    async fn connect(&self) -> Result<()> {
+        self.embedder().await.map(|_| ())
+    }
+}
+
+/// Server address without the trailing slash
+pub(super) fn base_url(url: &str) -> Result<String> {
+    let url = url.trim().trim_end_matches('/');
+    if url.is_empty() {
+        return Err(VectorDbError::Config(
+            "Embedding server URL is required".to_string(),
+        ));
+    }
+    Ok(url.to_string())
+}
+
+pub(super) async fn get_json<T: DeserializeOwned>(url: &str) -> Result<T> {
+    let response = HTTP_CLIENT
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| unreachable_error(url, e))?;
+    read_json(url, response).await
+}
+
+pub(super) async fn post_json<B: Serialize, T: DeserializeOwned>(url: &str, body: &B) -> Result<T> {
+    let response = HTTP_CLIENT
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| unreachable_error(url, e))?;
+    read_json(url, response).await
+}
+
+/// Dimension of the vectors the server produces
+pub(super) async fn detect_dimension(embedder: &dyn Embedder) -> Result<usize> {
+    let vectors = embedder.embed(&["dimension probe"]).await?;
+    match vectors.first().map(Vec::len) {
+        Some(dim) if dim > 0 => Ok(dim),
+        _ => Err(VectorDbError::Embedding(format!(
+            "{} returned an empty embedding",
+            embedder.model_name()
+        ))),
+    }
+}
+
+fn unreachable_error(url: &str, err: reqwest::Error) -> VectorDbError {
+    VectorDbError::Embedding(format!("Embedding server at {url} is unreachable: {err}"))
+}
+
+async fn read_json<T: DeserializeOwned>(url: &str, response: reqwest::Response) -> Result<T> {
+    let status = response.status();
+    // Both servers answer 429 or 503 while their queue is full
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+    {
+        return Err(VectorDbError::RateLimited(format!(
+            "{url} returned {status}"
+        )));
+    }
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(VectorDbError::Embedding(format!(
+            "{url} returned {status}: {body}"
+        )));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| VectorDbError::Embedding(format!("Invalid response from {url}: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_db::core::RemoteEmbeddingConfig;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answer one request with a single three-dimensional embedding
+    async fn serve_one_embedding(listener: TcpListener) {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = vec![0; 4096];
+        let _ = socket.read(&mut request).await.unwrap();
+        let body = r#"{"embeddings":[[0.1,0.2,0.3]]}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        socket.write_all(response.as_bytes()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn local_embedder_connects_on_first_use_and_retries() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let embedder = super::super::create_embedder(&VectorDbConfig {
+            embedding: RemoteEmbeddingConfig {
+                model_name: "nomic-embed-text".to_string(),
+                dimension: 0,
+                ..RemoteEmbeddingConfig::default()
+            },
+            backend: EmbeddingBackend::Ollama {
+                url: format!("http://{addr}"),
+            },
+            ..VectorDbConfig::default()
+        })
+        .unwrap();
+        assert_eq!(embedder.dim(), 0);
+        assert!(embedder.connect().await.is_err());
+
+        let listener = TcpListener::bind(addr).await.unwrap();
+        let server = tokio::spawn(serve_one_embedding(listener));
+        embedder.connect().await.unwrap();
+        assert_eq!(embedder.dim(), 3);
+        assert_eq!(embedder.model_name(), "nomic-embed-text");
+        server.await.unwrap();
+    }
+}
@@ -1,5 +1,12 @@
+pub mod batching;
 pub mod embedder;
+mod local;
+pub mod ollama;
 pub mod remote;
+pub mod tei;
 
+pub use batching::*;
 pub use embedder::*;
+pub use ollama::*;
 pub use remote::*;
+pub use tei::*;
@@ -0,0 +1,75 @@
+use super::local::{base_url, detect_dimension, post_json};
+use super::{EmbedBatcher, Embedder};
+use crate::vector_db::core::{EmbeddingBatchConfig, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+struct EmbedRequest<'a> {
+    model: &'a str,
+    input: Vec<String>,
+    truncate: bool,
+}
+
+#[derive(Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+/// Embeddings from Ollama's native `/api/embed`
+pub struct OllamaEmbedder {
+    endpoint: String,
+    model_name: String,
+    dim: usize,
+    batcher: EmbedBatcher,
+}
+
+impl OllamaEmbedder {
+    /// Connect to the server at `url`. A `dim` of 0 is detected with a probe request.
+    pub async fn connect(
+        url: &str,
+        model_name: String,
+        dim: usize,
+        batching: &EmbeddingBatchConfig,
+    ) -> Result<Self> {
+        let mut embedder = Self {
+            endpoint: format!("{}/api/embed", base_url(url)?),
+            model_name,
+            dim,
+            batcher: EmbedBatcher::new(batching),
+        };
+        if embedder.dim == 0 {
+            embedder.dim = detect_dimension(&embedder).await?;
+        }
+        Ok(embedder)
+    }
+}
+
+#[async_trait]
+impl Embedder for OllamaEmbedder {
+    fn id(&self) -> &str {
+        "ollama"
+    }
+
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        self.batcher
+            .embed(texts, |input| async move {
+                let request = EmbedRequest {
+                    model: &self.model_name,
+                    input,
+                    truncate: true,
+                };
+                let response: EmbedResponse = post_json(&self.endpoint, &request).await?;
+                Ok(response.embeddings)
+            })
+            .await
+    }
+}
@@ -1,22 +1,25 @@
 use super::Embedder;
 use crate::llm::{
-    providers::base::LLMProvider,
-    providers::openai::OpenAIProvider,
+    provider_registry::ProviderRegistry,
+    providers::Provider,
     types::{EmbeddingRequest, LLMProviderConfig},
 };
 use crate::vector_db::core::{Result, VectorDbError};
 use async_trait::async_trait;
 
 pub struct RemoteEmbedder {
-    provider: OpenAIProvider,
+    provider: Provider,
     model_name: String,
     dim: usize,
 }
 
 impl RemoteEmbedder {
     pub fn new(config: LLMProviderConfig, model_name: String, dim: usize) -> Result<Self> {
+        let provider = ProviderRegistry::global()
+            .create(config)
+            .map_err(|e| VectorDbError::Config(e.to_string()))?;
         Ok(Self {
-            provider: OpenAIProvider::new(config),
+            provider,
             model_name,
             dim,
         })
@@ -49,6 +52,12 @@ impl Embedder for RemoteEmbedder {
             .create_embeddings(request)
             .await
             .map(|resp| resp.data.into_iter().map(|d| d.embedding).collect())
-            .map_err(|e| VectorDbError::Embedding(e.to_string()))
+            .map_err(|e| {
+                if crate::llm::retry::error_retry_reason(&e) == "rate_limit" {
+                    VectorDbError::RateLimited(e.to_string())
+                } else {
+                    VectorDbError::Embedding(e.to_string())
+                }
+            })
     }
 }
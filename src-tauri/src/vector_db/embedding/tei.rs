@@ -0,0 +1,98 @@
+use super::local::{base_url, detect_dimension, get_json, post_json};
+use super::{EmbedBatcher, Embedder};
+use crate::vector_db::core::{EmbeddingBatchConfig, Result, VectorDbError};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+struct EmbedRequest {
+    inputs: Vec<String>,
+    truncate: bool,
+}
+
+#[derive(Deserialize)]
+struct ServerInfo {
+    model_id: String,
+    max_client_batch_size: Option<usize>,
+}
+
+/// Embeddings from a text-embeddings-inference style server (`/embed`, `/info`)
+pub struct TeiEmbedder {
+    endpoint: String,
+    model_name: String,
+    dim: usize,
+    batcher: EmbedBatcher,
+}
+
+impl TeiEmbedder {
+    /// Connect to the server at `url`. The server serves one model; its name is asked
+    /// for when `model_name` is empty, and a `dim` of 0 is detected with a probe request.
+    pub async fn connect(
+        url: &str,
+        model_name: String,
+        dim: usize,
+        batching: &EmbeddingBatchConfig,
+    ) -> Result<Self> {
+        let base = base_url(url)?;
+        let mut batcher = EmbedBatcher::new(batching);
+        // Servers without `/info` are fine as long as the model is named
+        let info = match get_json::<ServerInfo>(&format!("{base}/info")).await {
+            Ok(info) => Some(info),
+            Err(err) if !model_name.is_empty() => {
+                tracing::debug!("No server info from {base}: {err}");
+                None
+            }
+            Err(err) => return Err(err),
+        };
+        if let Some(max) = info.as_ref().and_then(|info| info.max_client_batch_size) {
+            batcher.limit_batch_size(max);
+        }
+        let model_name = match info {
+            Some(info) if model_name.is_empty() => info.model_id,
+            _ => model_name,
+        };
+        if model_name.is_empty() {
+            return Err(VectorDbError::Config(format!(
+                "{base} did not report its model name"
+            )));
+        }
+
+        let mut embedder = Self {
+            endpoint: format!("{base}/embed"),
+            model_name,
+            dim,
+            batcher,
+        };
+        if embedder.dim == 0 {
+            embedder.dim = detect_dimension(&embedder).await?;
+        }
+        Ok(embedder)
+    }
+}
+
+#[async_trait]
+impl Embedder for TeiEmbedder {
+    fn id(&self) -> &str {
+        "tei"
+    }
+
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        self.batcher
+            .embed(texts, |inputs| async move {
+                let request = EmbedRequest {
+                    inputs,
+                    truncate: true,
+                };
+                post_json(&self.endpoint, &request).await
+            })
+            .await
+    }
+}
@@ -0,0 +1,324 @@
+//! Keeps built indexes current by re-indexing the files the watcher reports.
+//!
+//! Only workspaces that already have an index are touched; the first build stays a manual
+//! action. Vectors are updated when the index was built with the configured embedding
+//! model, the keyword index whenever it exists.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::file_watcher::{FsEventType, ObservedFsChange, ObservedFsChangeBatch};
+use crate::vector_db::commands::{build_running, VectorDbState};
+use crate::vector_db::core::{Result, VectorDbError};
+use crate::vector_db::lexical::has_keyword_index;
+use crate::vector_db::search::SemanticSearchEngine;
+use crate::vector_db::storage::{FileStore, IndexManager};
+use crate::vector_db::utils::{collect_source_files, SourceFileFilter};
+
+/// Quiet period after the last change in a workspace before it is re-indexed
+const SETTLE_DELAY: Duration = Duration::from_secs(3);
+/// Files re-indexed per round; the rest wait for the next one
+const MAX_FILES_PER_ROUND: usize = 64;
+/// Wait after a failed round, doubled for every further failure
+const INITIAL_BACKOFF: Duration = Duration::from_secs(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(600);
+
+pub struct BackgroundIndexer {
+    fs_tx: mpsc::Sender<ObservedFsChangeBatch>,
+}
+
+impl BackgroundIndexer {
+    pub fn new(state: VectorDbState) -> Self {
+        let (fs_tx, fs_rx) = mpsc::channel::<ObservedFsChangeBatch>(2048);
+        tauri::async_runtime::spawn(indexer_loop(state, fs_rx));
+        Self { fs_tx }
+    }
+
+    pub fn fs_sender(&self) -> mpsc::Sender<ObservedFsChangeBatch> {
+        self.fs_tx.clone()
+    }
+}
+
+/// Changes in one workspace waiting to be indexed
+struct PendingWorkspace {
+    changed: HashSet<PathBuf>,
+    removed: HashSet<PathBuf>,
+    due: Instant,
+    failures: u32,
+}
+
+impl PendingWorkspace {
+    fn new() -> Self {
+        Self {
+            changed: HashSet::new(),
+            removed: HashSet::new(),
+            due: Instant::now(),
+            failures: 0,
+        }
+    }
+
+    fn record(&mut self, changes: Vec<ObservedFsChange>) {
+        for change in changes {
+            if let Some(old_path) = change.old_abs_path {
+                let old_path = PathBuf::from(old_path);
+                self.changed.remove(&old_path);
+                self.removed.insert(old_path);
+            }
+            let path = PathBuf::from(change.abs_path);
+            match change.event_type {
+                FsEventType::Deleted => {
+                    self.changed.remove(&path);
+                    self.removed.insert(path);
+                }
+                // Renames without a source report the old and the new name separately;
+                // whichever no longer exists is dropped when the round runs.
+                FsEventType::Created | FsEventType::Modified | FsEventType::Renamed => {
+                    self.removed.remove(&path);
+                    self.changed.insert(path);
+                }
+            }
+        }
+        self.due = self.due.max(Instant::now() + SETTLE_DELAY);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+
+    fn clear(&mut self) {
+        self.changed.clear();
+        self.removed.clear();
+    }
+
+    fn backoff(&self) -> Duration {
+        let exponent = self.failures.saturating_sub(1).min(16);
+        INITIAL_BACKOFF
+            .saturating_mul(1 << exponent)
+            .min(MAX_BACKOFF)
+    }
+}
+
+async fn indexer_loop(state: VectorDbState, mut fs_rx: mpsc::Receiver<ObservedFsChangeBatch>) {
+    let mut pending: HashMap<PathBuf, PendingWorkspace> = HashMap::new();
+
+    loop {
+        let next_due = pending.values().map(|work| work.due).min();
+        tokio::select! {
+            batch = fs_rx.recv() => {
+                let Some(batch) = batch else { break };
+                // Writes to the index itself must not schedule another round
+                let Ok(store) = FileStore::new(&batch.workspace_root) else { continue };
+                let changes: Vec<ObservedFsChange> = batch
+                    .changes
+                    .into_iter()
+                    .filter(|change| !Path::new(&change.abs_path).starts_with(store.root_path()))
+                    .collect();
+                if !changes.is_empty() {
+                    pending
+                        .entry(batch.workspace_root)
+                        .or_insert_with(PendingWorkspace::new)
+                        .record(changes);
+                }
+            }
+            _ = tokio::time::sleep_until(next_due.unwrap_or_else(Instant::now)), if next_due.is_some() => {
+                let now = Instant::now();
+                let due: Vec<PathBuf> = pending
+                    .iter()
+                    .filter(|(_, work)| work.due <= now)
+                    .map(|(root, _)| root.clone())
+                    .collect();
+
+                for root in due {
+                    let Some(mut work) = pending.remove(&root) else {
+                        continue;
+                    };
+                    if build_running(&root) {
+                        work.due = Instant::now() + SETTLE_DELAY;
+                        pending.insert(root, work);
+                        continue;
+                    }
+
+                    let engine = state.current_search_engine();
+                    match index_round(engine.as_deref(), &root, &mut work).await {
+                        Ok(()) => {
+                            work.failures = 0;
+                            work.due = Instant::now();
+                        }
+                        Err(err) => {
+                            work.failures += 1;
+                            work.due = Instant::now() + work.backoff();
+                            let reason = match err {
+                                VectorDbError::RateLimited(_) => "rate limited",
+                                _ => "failed",
+                            };
+                            tracing::warn!(
+                                "Background re-index of {} {}, retrying in {:?}: {}",
+                                root.display(),
+                                reason,
+                                work.backoff(),
+                                err
+                            );
+                        }
+                    }
+                    if !work.is_empty() {
+                        pending.insert(root, work);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Apply one round of pending changes. Paths that were not processed stay in `work`.
+async fn index_round(
+    engine: Option<&SemanticSearchEngine>,
+    root: &Path,
+    work: &mut PendingWorkspace,
+) -> Result<()> {
+    let store = FileStore::new(root)?;
+    let has_vectors = store.root_path().join("manifest.json").exists();
+    let has_keywords = has_keyword_index(root);
+    if !has_vectors && !has_keywords {
+        work.clear();
+        return Ok(());
+    }
+    // A local embedding server that is still down fails the round, which is retried
+    if let Some(engine) = engine.filter(|_| has_vectors) {
+        engine.connect().await?;
+    }
+    let config = engine.map(|engine| engine.config()).unwrap_or_default();
+    let manager = IndexManager::new(root, config.clone())?;
+    let status = manager.get_status();
+    // Vectors of another model can't share the graph; that index needs a manual rebuild
+    let embedder = engine
+        .filter(|_| {
+            has_vectors
+                && status.total_files > 0
+                && status.embedding_model == config.embedding.model_name
+                && status.vector_dimension == config.embedding.dimension
+        })
+        .map(|engine| engine.embedder());
+    if embedder.is_none() && !has_keywords {
+        work.clear();
+        return Ok(());
+    }
+
+    let mut removed: Vec<PathBuf> = Vec::new();
+    for path in std::mem::take(&mut work.removed) {
+        if path.exists() {
+            work.changed.insert(path);
+        } else {
+            removed.push(path);
+        }
+    }
+
+    let filter = SourceFileFilter::new(root);
+    let mut files: Vec<PathBuf> = Vec::new();
+    while files.len() < MAX_FILES_PER_ROUND {
+        let Some(path) = work.changed.iter().next().cloned() else {
+            break;
+        };
+        work.changed.remove(&path);
+        if path.is_file() {
+            if filter.matches(&path) {
+                files.push(path);
+            }
+        } else if path.is_dir() {
+            if filter.matches(&path) {
+                let max_size = config.max_file_size;
+                let dir_files =
+                    tokio::task::spawn_blocking(move || collect_source_files(&path, max_size))
+                        .await
+                        .map_err(|e| VectorDbError::Index(format!("file walk failed: {e}")))?;
+                files.extend(dir_files.into_iter().filter(|file| filter.matches(file)));
+            }
+        } else {
+            removed.push(path);
+        }
+    }
+    if files.len() > MAX_FILES_PER_ROUND {
+        work.changed.extend(files.drain(MAX_FILES_PER_ROUND..));
+    }
+
+    let removed_files = match manager.remove_paths(&removed) {
+        Ok(count) => count,
+        Err(err) => {
+            work.removed.extend(removed);
+            work.changed.extend(files);
+            return Err(err);
+        }
+    };
+    let outcome = match manager.reindex_files(&files, embedder.as_deref()).await {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            work.changed.extend(files);
+            return Err(err);
+        }
+    };
+
+    if removed_files > 0 || outcome.indexed_files > 0 || outcome.removed_files > 0 {
+        tracing::debug!(
+            "Re-indexed {} file(s) ({} chunks) and removed {} in {}",
+            outcome.indexed_files,
+            outcome.indexed_chunks,
+            removed_files + outcome.removed_files,
+            root.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(event_type: FsEventType, path: &str, old_path: Option<&str>) -> ObservedFsChange {
+        ObservedFsChange {
+            abs_path: path.to_string(),
+            old_abs_path: old_path.map(str::to_string),
+            event_type,
+            observed_at_ms: 0,
+        }
+    }
+
+    #[test]
+    fn record_keeps_the_latest_state_of_each_path() {
+        let mut work = PendingWorkspace::new();
+        work.record(vec![
+            change(FsEventType::Modified, "/ws/a.rs", None),
+            change(FsEventType::Deleted, "/ws/a.rs", None),
+            change(FsEventType::Deleted, "/ws/b.rs", None),
+            change(FsEventType::Created, "/ws/b.rs", None),
+            change(FsEventType::Renamed, "/ws/d.rs", Some("/ws/c.rs")),
+        ]);
+
+        let mut changed: Vec<_> = work.changed.iter().cloned().collect();
+        changed.sort();
+        let mut removed: Vec<_> = work.removed.iter().cloned().collect();
+        removed.sort();
+        assert_eq!(
+            changed,
+            vec![PathBuf::from("/ws/b.rs"), PathBuf::from("/ws/d.rs")]
+        );
+        assert_eq!(
+            removed,
+            vec![PathBuf::from("/ws/a.rs"), PathBuf::from("/ws/c.rs")]
+        );
+        assert!(work.due > Instant::now());
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let mut work = PendingWorkspace::new();
+        work.failures = 1;
+        assert_eq!(work.backoff(), INITIAL_BACKOFF);
+        work.failures = 3;
+        assert_eq!(work.backoff(), INITIAL_BACKOFF * 4);
+        work.failures = 40;
+        assert_eq!(work.backoff(), MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,296 @@
+//! BM25 inverted index over the chunks of a workspace
+//!
+//! Chunks are scored with BM25F: the identifier and comment fields are length-normalized
+//! separately and weighted before term-frequency saturation, so a term in a name counts
+//! for more than the same term in prose.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::tokenizer::{tokenize_query, tokenize_source};
+use crate::vector_db::core::{Chunk, ChunkType, Language, SearchResult, Span};
+
+pub(crate) const LEXICAL_FORMAT_VERSION: u32 = 1;
+
+const IDENTIFIER: usize = 0;
+const COMMENT: usize = 1;
+const FIELD_WEIGHTS: [f32; 2] = [1.0, 0.5];
+/// Length normalization per field
+const FIELD_B: [f32; 2] = [0.75, 0.75];
+/// Term-frequency saturation
+const K1: f32 = 1.2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedFile {
+    hash: String,
+    docs: Vec<u32>,
+}
+
+/// An indexed chunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Doc {
+    file_path: PathBuf,
+    span: Span,
+    chunk_type: ChunkType,
+    /// Token count per field
+    lengths: [u32; 2],
+    /// Distinct terms, to find the postings to drop on removal
+    terms: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Posting {
+    doc: u32,
+    /// Term frequency per field
+    tf: [u32; 2],
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LexicalIndex {
+    pub(crate) format_version: u32,
+    files: HashMap<PathBuf, IndexedFile>,
+    docs: HashMap<u32, Doc>,
+    postings: HashMap<String, Vec<Posting>>,
+    /// Token count per field over all docs, for the average lengths
+    total_lengths: [u64; 2],
+    next_doc: u32,
+}
+
+impl Default for LexicalIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LexicalIndex {
+    pub fn new() -> Self {
+        Self {
+            format_version: LEXICAL_FORMAT_VERSION,
+            files: HashMap::new(),
+            docs: HashMap::new(),
+            postings: HashMap::new(),
+            total_lengths: [0; 2],
+            next_doc: 0,
+        }
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.docs.len()
+    }
+
+    /// Content hash the file was indexed with
+    pub fn file_hash(&self, file_path: &Path) -> Option<&str> {
+        self.files.get(file_path).map(|file| file.hash.as_str())
+    }
+
+    pub fn contains_file(&self, file_path: &Path) -> bool {
+        self.files.contains_key(file_path)
+    }
+
+    pub fn file_paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.keys()
+    }
+
+    /// Replace the indexed chunks of a file.
+    pub fn replace_file(&mut self, file_path: &Path, hash: &str, chunks: &[Chunk]) {
+        self.remove_file(file_path);
+
+        let language = Language::from_path(file_path);
+        let mut doc_ids = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            let tokens = tokenize_source(&chunk.content, language);
+            let mut counts: HashMap<String, [u32; 2]> = HashMap::new();
+            for (field, terms) in [(IDENTIFIER, tokens.identifiers), (COMMENT, tokens.comments)] {
+                for term in terms {
+                    counts.entry(term).or_default()[field] += 1;
+                }
+            }
+            let lengths = counts.values().fold([0u32; 2], |acc, tf| {
+                [acc[0] + tf[IDENTIFIER], acc[1] + tf[COMMENT]]
+            });
+
+            let doc = self.next_doc;
+            self.next_doc = self.next_doc.wrapping_add(1);
+            for (term, tf) in &counts {
+                self.postings
+                    .entry(term.clone())
+                    .or_default()
+                    .push(Posting { doc, tf: *tf });
+            }
+            self.total_lengths[IDENTIFIER] += u64::from(lengths[IDENTIFIER]);
+            self.total_lengths[COMMENT] += u64::from(lengths[COMMENT]);
+            self.docs.insert(
+                doc,
+                Doc {
+                    file_path: file_path.to_path_buf(),
+                    span: chunk.span.clone(),
+                    chunk_type: chunk.chunk_type.clone(),
+                    lengths,
+                    terms: counts.into_keys().collect(),
+                },
+            );
+            doc_ids.push(doc);
+        }
+
+        self.files.insert(
+            file_path.to_path_buf(),
+            IndexedFile {
+                hash: hash.to_string(),
+                docs: doc_ids,
+            },
+        );
+    }
+
+    /// Drop a file's chunks. Returns whether the file was indexed.
+    pub fn remove_file(&mut self, file_path: &Path) -> bool {
+        let Some(file) = self.files.remove(file_path) else {
+            return false;
+        };
+        for doc_id in file.docs {
+            let Some(doc) = self.docs.remove(&doc_id) else {
+                continue;
+            };
+            for term in &doc.terms {
+                if let Some(postings) = self.postings.get_mut(term) {
+                    postings.retain(|posting| posting.doc != doc_id);
+                    if postings.is_empty() {
+                        self.postings.remove(term);
+                    }
+                }
+            }
+            self.total_lengths[IDENTIFIER] -= u64::from(doc.lengths[IDENTIFIER]);
+            self.total_lengths[COMMENT] -= u64::from(doc.lengths[COMMENT]);
+        }
+        true
+    }
+
+    /// Best matching chunks for `query`, highest score first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        let doc_count = self.docs.len();
+        if doc_count == 0 || limit == 0 {
+            return Vec::new();
+        }
+        let avg_lengths = [IDENTIFIER, COMMENT]
+            .map(|field| (self.total_lengths[field] as f32 / doc_count as f32).max(1.0));
+
+        let mut scores: HashMap<u32, f32> = HashMap::new();
+        for term in tokenize_query(query) {
+            let Some(postings) = self.postings.get(&term) else {
+                continue;
+            };
+            let df = postings.len() as f32;
+            let idf = (1.0 + (doc_count as f32 - df + 0.5) / (df + 0.5)).ln();
+            for posting in postings {
+                let Some(doc) = self.docs.get(&posting.doc) else {
+                    continue;
+                };
+                let tf: f32 = [IDENTIFIER, COMMENT]
+                    .into_iter()
+                    .map(|field| {
+                        let norm = 1.0 - FIELD_B[field]
+                            + FIELD_B[field] * doc.lengths[field] as f32 / avg_lengths[field];
+                        FIELD_WEIGHTS[field] * posting.tf[field] as f32 / norm
+                    })
+                    .sum();
+                *scores.entry(posting.doc).or_default() += idf * tf / (K1 + tf);
+            }
+        }
+
+        let mut ranked: Vec<(u32, f32)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked
+            .into_iter()
+            .take(limit)
+            .filter_map(|(doc_id, score)| {
+                let doc = self.docs.get(&doc_id)?;
+                Some(SearchResult::new(
+                    doc.file_path.clone(),
+                    doc.span.clone(),
+                    score,
+                    format!("Chunk {:?}", doc.chunk_type),
+                    None,
+                    Some(doc.chunk_type.clone()),
+                ))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn chunk(path: &str, line: usize, content: &str) -> Chunk {
+        Chunk {
+            id: Uuid::new_v4(),
+            file_path: PathBuf::from(path),
+            span: Span::new(0, content.len(), line, line),
+            content: content.to_string(),
+            chunk_type: ChunkType::Function,
+            stride_info: None,
+        }
+    }
+
+    #[test]
+    fn ranks_identifier_matches_over_comment_mentions() {
+        let mut index = LexicalIndex::new();
+        index.replace_file(
+            Path::new("/ws/auth.rs"),
+            "a",
+            &[chunk(
+                "/ws/auth.rs",
+                1,
+                "fn refresh_token(session: &Session) {}",
+            )],
+        );
+        index.replace_file(
+            Path::new("/ws/notes.rs"),
+            "b",
+            &[chunk(
+                "/ws/notes.rs",
+                1,
+                "// Callers refresh the token elsewhere\nfn unrelated() {}",
+            )],
+        );
+        index.replace_file(
+            Path::new("/ws/other.rs"),
+            "c",
+            &[chunk("/ws/other.rs", 1, "fn render(frame: Frame) {}")],
+        );
+
+        let results = index.search("refreshToken", 10);
+        let files: Vec<&Path> = results.iter().map(|r| r.file_path.as_path()).collect();
+        assert_eq!(
+            files,
+            vec![Path::new("/ws/auth.rs"), Path::new("/ws/notes.rs")]
+        );
+    }
+
+    #[test]
+    fn replacing_and_removing_files_updates_postings() {
+        let mut index = LexicalIndex::new();
+        let path = Path::new("/ws/lib.rs");
+        index.replace_file(
+            path,
+            "v1",
+            &[chunk("/ws/lib.rs", 1, "fn parse_header() {}")],
+        );
+        index.replace_file(path, "v2", &[chunk("/ws/lib.rs", 1, "fn parse_body() {}")]);
+
+        assert_eq!(index.file_hash(path), Some("v2"));
+        assert!(index.search("header", 10).is_empty());
+        assert_eq!(index.search("body", 10).len(), 1);
+
+        assert!(index.remove_file(path));
+        assert!(index.search("parse", 10).is_empty());
+        assert!(index.postings.is_empty());
+        assert_eq!(index.total_lengths, [0, 0]);
+    }
+}
@@ -0,0 +1,63 @@
+//! Keyword (BM25) index, kept next to the vector store and usable without an embedding
+//! model
+
+mod index;
+mod tokenizer;
+
+pub use index::*;
+pub use tokenizer::*;
+
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::SystemTime;
+
+use lru::LruCache;
+use parking_lot::Mutex;
+
+use crate::vector_db::core::Result;
+use crate::vector_db::storage::FileStore;
+
+/// Workspaces whose loaded keyword index is kept in memory
+const CACHED_INDEXES: usize = 3;
+
+/// Size and modification time of the index file a cached copy was loaded from
+type FileStamp = (u64, Option<SystemTime>);
+type IndexCache = LruCache<PathBuf, (FileStamp, Arc<LexicalIndex>)>;
+
+static INDEX_CACHE: OnceLock<Mutex<IndexCache>> = OnceLock::new();
+
+pub fn has_keyword_index(workspace_root: &Path) -> bool {
+    FileStore::new(workspace_root).is_ok_and(|store| store.lexical_index_path().is_file())
+}
+
+/// Load the keyword index of a workspace, reusing the copy loaded before while the file
+/// on disk is unchanged. `None` when the workspace has no usable index.
+pub fn load_keyword_index(workspace_root: &Path) -> Result<Option<Arc<LexicalIndex>>> {
+    let store = FileStore::new(workspace_root)?;
+    let stamp = match std::fs::metadata(store.lexical_index_path()) {
+        Ok(meta) => (meta.len(), meta.modified().ok()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    let cache = INDEX_CACHE.get_or_init(|| {
+        Mutex::new(LruCache::new(
+            NonZeroUsize::new(CACHED_INDEXES).unwrap_or(NonZeroUsize::MIN),
+        ))
+    });
+    if let Some((cached_stamp, index)) = cache.lock().get(workspace_root) {
+        if *cached_stamp == stamp {
+            return Ok(Some(Arc::clone(index)));
+        }
+    }
+
+    let Some(index) = store.load_lexical_index()? else {
+        return Ok(None);
+    };
+    let index = Arc::new(index);
+    cache
+        .lock()
+        .put(workspace_root.to_path_buf(), (stamp, Arc::clone(&index)));
+    Ok(Some(index))
+}
@@ -0,0 +1,249 @@
+//! Code-aware tokenization for the keyword index
+//!
+//! Words are split at camelCase, snake_case and path boundaries and indexed both whole
+//! and in parts, so `getUserById` matches a query for `user` as well as the exact name.
+//! Comment text is collected apart from code.
+
+use crate::vector_db::core::Language;
+
+/// Parts shorter than this carry no signal
+const MIN_TOKEN_LEN: usize = 2;
+/// Longer words are hashes, encoded data and the like
+const MAX_WORD_LEN: usize = 64;
+
+/// Tokens of a piece of source, by field
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldTokens {
+    /// Identifiers, keywords and string contents
+    pub identifiers: Vec<String>,
+    /// Words in comments and docstrings
+    pub comments: Vec<String>,
+}
+
+struct CommentSyntax {
+    line: &'static [&'static str],
+    block: &'static [(&'static str, &'static str)],
+    /// String delimiters; comment markers inside strings are not comments
+    quotes: &'static [char],
+}
+
+impl CommentSyntax {
+    fn for_language(language: Option<Language>) -> Self {
+        const C_BLOCK: &[(&str, &str)] = &[("/*", "*/")];
+        match language {
+            Some(
+                Language::Rust
+                | Language::Go
+                | Language::Java
+                | Language::C
+                | Language::Cpp
+                | Language::CSharp
+                | Language::Swift
+                | Language::Kotlin,
+            ) => Self {
+                line: &["//"],
+                block: C_BLOCK,
+                quotes: &['"'],
+            },
+            Some(Language::TypeScript | Language::JavaScript) => Self {
+                line: &["//"],
+                block: C_BLOCK,
+                quotes: &['"', '\'', '`'],
+            },
+            Some(Language::Php) => Self {
+                line: &["//", "#"],
+                block: C_BLOCK,
+                quotes: &['"', '\''],
+            },
+            // Docstrings document code the way comments do
+            Some(Language::Python) => Self {
+                line: &["#"],
+                block: &[("\"\"\"", "\"\"\""), ("'''", "'''")],
+                quotes: &['"', '\''],
+            },
+            Some(Language::Ruby) => Self {
+                line: &["#"],
+                block: &[("=begin", "=end")],
+                quotes: &['"', '\''],
+            },
+            None => Self {
+                line: &[],
+                block: &[],
+                quotes: &[],
+            },
+        }
+    }
+}
+
+/// Tokenize source text. Without a known language everything counts as code.
+pub fn tokenize_source(content: &str, language: Option<Language>) -> FieldTokens {
+    let syntax = CommentSyntax::for_language(language);
+    let mut tokens = FieldTokens::default();
+    let mut code_start = 0;
+    let mut idx = 0;
+
+    while idx < content.len() {
+        let rest = &content[idx..];
+        if let Some(marker) = syntax.line.iter().find(|marker| rest.starts_with(**marker)) {
+            push_words(&content[code_start..idx], &mut tokens.identifiers);
+            let end = rest.find('\n').map_or(content.len(), |offset| idx + offset);
+            push_words(&content[idx + marker.len()..end], &mut tokens.comments);
+            idx = end;
+            code_start = end;
+            continue;
+        }
+        if let Some((open, close)) = syntax.block.iter().find(|(open, _)| rest.starts_with(open)) {
+            push_words(&content[code_start..idx], &mut tokens.identifiers);
+            let body_start = idx + open.len();
+            let body_end = content[body_start..]
+                .find(close)
+                .map_or(content.len(), |offset| body_start + offset);
+            push_words(&content[body_start..body_end], &mut tokens.comments);
+            idx = (body_end + close.len()).min(content.len());
+            code_start = idx;
+            continue;
+        }
+
+        let Some(ch) = rest.chars().next() else {
+            break;
+        };
+        idx += ch.len_utf8();
+        if syntax.quotes.contains(&ch) {
+            idx = string_end(content, idx, ch);
+        }
+    }
+    push_words(&content[code_start..], &mut tokens.identifiers);
+    tokens
+}
+
+/// Tokenize a search query. Terms are deduplicated so repeating a word doesn't weigh it
+/// more.
+pub fn tokenize_query(query: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    push_words(query, &mut terms);
+    let mut seen = std::collections::HashSet::new();
+    terms.retain(|term| seen.insert(term.clone()));
+    terms
+}
+
+/// Offset just past the string literal whose body starts at `start`. Only backtick
+/// strings span lines.
+fn string_end(content: &str, start: usize, quote: char) -> usize {
+    let mut escaped = false;
+    for (offset, ch) in content[start..].char_indices() {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == quote {
+            return start + offset + ch.len_utf8();
+        } else if ch == '\n' && quote != '`' {
+            return start + offset;
+        }
+    }
+    content.len()
+}
+
+fn push_words(text: &str, out: &mut Vec<String>) {
+    for word in text.split(|ch: char| !(ch.is_alphanumeric() || ch == '_')) {
+        push_word(word, out);
+    }
+}
+
+/// Push a word lowercased, followed by its parts when it is compound.
+fn push_word(word: &str, out: &mut Vec<String>) {
+    let word = word.trim_matches('_');
+    if word.chars().count() < MIN_TOKEN_LEN || word.chars().count() > MAX_WORD_LEN {
+        return;
+    }
+    out.push(word.to_lowercase());
+
+    let parts: Vec<&str> = word
+        .split('_')
+        .flat_map(split_camel_case)
+        .filter(|part| part.chars().count() >= MIN_TOKEN_LEN)
+        .collect();
+    if parts.len() > 1 {
+        out.extend(parts.into_iter().map(str::to_lowercase));
+    }
+}
+
+/// Split at lower-to-upper transitions and before the last capital of an acronym, so
+/// `HTTPServerError` becomes `HTTP`, `Server`, `Error`. Digits stay with what precedes them.
+fn split_camel_case(word: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = word.char_indices().collect();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for window in 1..chars.len() {
+        let (idx, ch) = chars[window];
+        let prev = chars[window - 1].1;
+        let next_is_lower = chars
+            .get(window + 1)
+            .is_some_and(|(_, next)| next.is_lowercase());
+        let boundary = ch.is_uppercase()
+            && (prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower));
+        if boundary {
+            parts.push(&word[start..idx]);
+            start = idx;
+        }
+    }
+    if start < word.len() {
+        parts.push(&word[start..]);
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_compound_identifiers_and_paths() {
+        let terms = tokenize_query("getUserById HTTPServer max_file_size src/vector_db/mod.rs");
+        assert_eq!(
+            terms,
+            vec![
+                "getuserbyid",
+                "get",
+                "user",
+                "by",
+                "id",
+                "httpserver",
+                "http",
+                "server",
+                "max_file_size",
+                "max",
+                "file",
+                "size",
+                "src",
+                "vector_db",
+                "vector",
+                "db",
+                "mod",
+                "rs",
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_comments_apart_from_code() {
+        let source = "/* Cache of parsed files */\nfn load_cache() {\n    let marker = \"// not a comment\"; // Reload on change\n}\n";
+        let tokens = tokenize_source(source, Some(Language::Rust));
+        assert_eq!(
+            tokens.comments,
+            vec!["cache", "of", "parsed", "files", "reload", "on", "change"]
+        );
+        assert!(tokens.identifiers.contains(&"load_cache".to_string()));
+        assert!(tokens.identifiers.contains(&"comment".to_string()));
+        assert!(!tokens.identifiers.contains(&"reload".to_string()));
+
+        let python = tokenize_source(
+            "def area(r):\n    \"\"\"Circle area\"\"\"\n    return PI * r  # radius\n",
+            Some(Language::Python),
+        );
+        assert_eq!(python.comments, vec!["circle", "area", "radius"]);
+        assert_eq!(python.identifiers, vec!["def", "area", "return", "pi"]);
+    }
+}
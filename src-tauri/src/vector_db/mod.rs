@@ -2,6 +2,9 @@ pub mod chunking;
 pub mod commands;
 pub mod core;
 pub mod embedding;
+pub mod indexer;
+pub mod lexical;
+pub mod rerank;
 pub mod search;
 pub mod storage;
 pub mod utils;
@@ -9,32 +12,99 @@ pub mod utils;
 use std::sync::Arc;
 
 use crate::llm::types::LLMProviderConfig;
-use crate::storage::repositories::{AIModels, ModelType};
+use crate::settings::types::{
+    EmbeddingSettings, LocalEmbeddingBackend, RerankerSettings, RerankerType,
+};
+use crate::settings::SettingsManager;
+use crate::storage::repositories::{AIModelConfig, AIModels, ModelType};
 
 pub use chunking::*;
 pub use commands::*;
 pub use core::*;
 pub use embedding::*;
+pub use indexer::*;
+pub use lexical::*;
+pub use rerank::*;
 pub use search::*;
 pub use storage::*;
 
-pub async fn build_search_engine_from_database(
+/// Ollama's default address
+const OLLAMA_DEFAULT_URL: &str = "http://localhost:11434";
+/// text-embeddings-inference's default address
+const TEI_DEFAULT_URL: &str = "http://localhost:8080";
+
+/// Build the search engine from the local embedding backend in the global settings, or
+/// from the embedding model of the model list when none is set.
+pub async fn build_search_engine(
     database: Arc<crate::storage::DatabaseManager>,
+    settings_manager: &SettingsManager,
 ) -> crate::vector_db::core::Result<Arc<SemanticSearchEngine>> {
+    let settings = settings_manager
+        .get_global_settings()
+        .await
+        .map_err(|e| crate::vector_db::core::VectorDbError::Config(e.to_string()))?;
     let models = AIModels::new(&database)
         .find_all()
         .await
         .map_err(|e| crate::vector_db::core::VectorDbError::Config(e.to_string()))?;
+    let embedding_model = models.iter().find(|m| m.model_type == ModelType::Embedding);
 
-    let model = models
-        .into_iter()
-        .find(|m| m.model_type == ModelType::Embedding)
-        .ok_or_else(|| {
+    let mut config = match local_embedding_config(&settings.embedding) {
+        Some(config) => config,
+        None => config_from_model(embedding_model.ok_or_else(|| {
             crate::vector_db::core::VectorDbError::Config(
                 "Embedding model configuration not found".to_string(),
             )
-        })?;
+        })?)?,
+    };
+    // A local backend leaves the model list's embedding model, and its option, unused
+    let reranker = match &settings.embedding.reranker {
+        Some(reranker) => Some(reranker.clone()),
+        None if config.backend == EmbeddingBackend::Provider => {
+            embedding_model.and_then(reranker_option)
+        }
+        None => None,
+    };
+    config.reranker = reranker.and_then(|reranker| {
+        reranker_config(&reranker, embedding_model, &models)
+            .map_err(|e| tracing::warn!("Searching without a reranker: {}", e))
+            .ok()
+    });
+
+    config.validate()?;
+    create_search_engine(config)
+}
 
+/// Embedding config for a local backend; `None` when the settings don't select one
+fn local_embedding_config(settings: &EmbeddingSettings) -> Option<VectorDbConfig> {
+    let url = |default: &str| settings.url.clone().unwrap_or_else(|| default.to_string());
+    let backend = match settings.backend? {
+        LocalEmbeddingBackend::Ollama => EmbeddingBackend::Ollama {
+            url: url(OLLAMA_DEFAULT_URL),
+        },
+        LocalEmbeddingBackend::Tei => EmbeddingBackend::Tei {
+            url: url(TEI_DEFAULT_URL),
+        },
+    };
+    let defaults = VectorDbConfig::default();
+    Some(VectorDbConfig {
+        embedding: RemoteEmbeddingConfig {
+            model_name: settings.model.clone().unwrap_or_default(),
+            dimension: settings.dimension.unwrap_or(0),
+            ..RemoteEmbeddingConfig::default()
+        },
+        backend,
+        batching: EmbeddingBatchConfig {
+            batch_size: settings.batch_size.unwrap_or(defaults.batching.batch_size),
+            max_concurrency: settings
+                .max_concurrency
+                .unwrap_or(defaults.batching.max_concurrency),
+        },
+        ..defaults
+    })
+}
+
+fn config_from_model(model: &AIModelConfig) -> crate::vector_db::core::Result<VectorDbConfig> {
     let dimension = model
         .options
         .as_ref()
@@ -43,36 +113,185 @@ pub async fn build_search_engine_from_database(
         .map(|v| v as usize)
         .unwrap_or(1024);
 
-    let api_key = model.api_key.ok_or_else(|| {
-        crate::vector_db::core::VectorDbError::Config(format!(
-            "Embedding model '{}' is missing API key",
-            model.id
-        ))
-    })?;
-
-    let config = VectorDbConfig {
+    Ok(VectorDbConfig {
         embedding: RemoteEmbeddingConfig {
-            provider_config: LLMProviderConfig {
-                provider_type: model.provider.as_str().to_string(),
-                api_key,
-                api_url: model.api_url,
-                options: model
-                    .options
-                    .as_ref()
-                    .and_then(|v| v.as_object())
-                    .map(|obj| obj.iter().map(|(k, v)| (k.clone(), v.clone())).collect()),
-                oauth_config: None,
-            },
-            model_name: model.model,
+            provider_config: provider_config(model)?,
+            model_name: model.model.clone(),
             dimension,
             chunk_size: 512,
             chunk_overlap: 100,
         },
         ..VectorDbConfig::default()
+    })
+}
+
+/// Doesn't contact a local embedding server; the engine connects on first use.
+fn create_search_engine(
+    config: VectorDbConfig,
+) -> crate::vector_db::core::Result<Arc<SemanticSearchEngine>> {
+    let embedder = crate::vector_db::embedding::create_embedder(&config)?;
+
+    let reranker = config.reranker.as_ref().and_then(|reranker| {
+        crate::vector_db::rerank::create_reranker(reranker)
+            .map_err(|e| tracing::warn!("Searching without a reranker: {}", e))
+            .ok()
+    });
+    let mut engine = SemanticSearchEngine::new(embedder, config);
+    if let Some(reranker) = reranker {
+        engine = engine.with_reranker(reranker);
+    }
+    Ok(Arc::new(engine))
+}
+
+/// Connection settings of a configured model
+fn provider_config(model: &AIModelConfig) -> crate::vector_db::core::Result<LLMProviderConfig> {
+    let api_key = model.api_key.clone().ok_or_else(|| {
+        crate::vector_db::core::VectorDbError::Config(format!(
+            "Model '{}' is missing API key",
+            model.id
+        ))
+    })?;
+
+    Ok(LLMProviderConfig {
+        provider_type: model.provider.as_str().to_string(),
+        api_key,
+        api_url: model.api_url.clone(),
+        options: model
+            .options
+            .as_ref()
+            .and_then(|v| v.as_object())
+            .map(|obj| obj.iter().map(|(k, v)| (k.clone(), v.clone())).collect()),
+        oauth_config: None,
+    })
+}
+
+/// Reranker set in the embedding model's `reranker` option, the older spelling of the
+/// `embedding.reranker` setting with the same fields. A malformed option is ignored.
+fn reranker_option(embedding_model: &AIModelConfig) -> Option<RerankerSettings> {
+    let option = embedding_model
+        .options
+        .as_ref()
+        .and_then(|opts| opts.get("reranker"))
+        .filter(|v| !v.is_null())?;
+    serde_json::from_value(option.clone())
+        .map_err(|e| {
+            tracing::warn!(
+                "Ignoring the reranker option of model '{}': {}",
+                embedding_model.id,
+                e
+            )
+        })
+        .ok()
+}
+
+/// `modelId` names the configured model whose provider and credentials are used; without
+/// it the embedding model's provider serves the reranker too.
+fn reranker_config(
+    reranker: &RerankerSettings,
+    embedding_model: Option<&AIModelConfig>,
+    models: &[AIModelConfig],
+) -> crate::vector_db::core::Result<RerankerConfig> {
+    let source = match reranker.model_id.as_deref() {
+        Some(id) => models.iter().find(|m| m.id == id).ok_or_else(|| {
+            crate::vector_db::core::VectorDbError::Config(format!(
+                "Reranker model '{id}' not found"
+            ))
+        })?,
+        None => embedding_model.ok_or_else(|| {
+            crate::vector_db::core::VectorDbError::Config(
+                "Reranker modelId is required without an embedding model".to_string(),
+            )
+        })?,
+    };
+    let serves_embeddings = embedding_model.is_some_and(|m| m.id == source.id);
+    let model_name = match &reranker.model {
+        Some(name) => name.clone(),
+        None if !serves_embeddings => source.model.clone(),
+        None => {
+            return Err(crate::vector_db::core::VectorDbError::Config(
+                "Reranker model name is required".to_string(),
+            ))
+        }
     };
 
-    config.validate()?;
+    Ok(RerankerConfig {
+        kind: match reranker.kind {
+            RerankerType::CrossEncoder => RerankerKind::CrossEncoder,
+            RerankerType::Llm => RerankerKind::Llm,
+        },
+        provider_config: provider_config(source)?,
+        model_name,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(id: &str, model_type: ModelType, options: serde_json::Value) -> AIModelConfig {
+        AIModelConfig {
+            id: id.to_string(),
+            model_type,
+            options: Some(options),
+            ..AIModelConfig::new(
+                "openai".to_string(),
+                "https://api.example.com".to_string(),
+                "key".to_string(),
+                id.to_string(),
+            )
+        }
+    }
+
+    #[test]
+    fn reranker_comes_from_settings_or_the_model_option() {
+        let embedding = model(
+            "embed",
+            ModelType::Embedding,
+            serde_json::json!({"reranker": {"type": "llm", "modelId": "chat"}}),
+        );
+        let chat = model("chat", ModelType::Chat, serde_json::json!({}));
+        let models = vec![embedding.clone(), chat];
+
+        let option = reranker_option(&embedding).unwrap();
+        let config = reranker_config(&option, Some(&embedding), &models).unwrap();
+        assert_eq!(config.kind, RerankerKind::Llm);
+        assert_eq!(config.model_name, "chat");
+
+        // A local backend has no embedding model to fall back on
+        let settings = RerankerSettings {
+            kind: RerankerType::CrossEncoder,
+            model_id: Some("chat".to_string()),
+            model: Some("rerank-v3".to_string()),
+        };
+        let config = reranker_config(&settings, None, &models).unwrap();
+        assert_eq!(config.kind, RerankerKind::CrossEncoder);
+        assert_eq!(config.model_name, "rerank-v3");
+        assert!(reranker_config(
+            &RerankerSettings {
+                model_id: None,
+                ..settings
+            },
+            None,
+            &models
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn malformed_reranker_option_is_ignored() {
+        let embedding = model(
+            "embed",
+            ModelType::Embedding,
+            serde_json::json!({"reranker": {"type": "bm25"}}),
+        );
+        assert!(reranker_option(&embedding).is_none());
 
-    let embedder = crate::vector_db::embedding::create_embedder(&config.embedding)?;
-    Ok(Arc::new(SemanticSearchEngine::new(embedder, config)))
+        let unknown = model(
+            "embed",
+            ModelType::Embedding,
+            serde_json::json!({"reranker": {"type": "llm", "modelId": "missing"}}),
+        );
+        let option = reranker_option(&unknown).unwrap();
+        assert!(reranker_config(&option, Some(&unknown), std::slice::from_ref(&unknown)).is_err());
+    }
 }
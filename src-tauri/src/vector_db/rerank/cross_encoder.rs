@@ -0,0 +1,121 @@
+use super::Reranker;
+use crate::llm::types::LLMProviderConfig;
+use crate::vector_db::core::{Result, VectorDbError};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .expect("failed to build rerank HTTP client")
+});
+
+#[derive(Serialize)]
+struct RerankRequest<'a> {
+    model: &'a str,
+    query: &'a str,
+    documents: &'a [&'a str],
+}
+
+#[derive(Deserialize)]
+struct RerankResponse {
+    /// Cohere and Jina answer with `results`, Voyage with `data`
+    #[serde(alias = "data")]
+    results: Vec<RerankHit>,
+}
+
+#[derive(Deserialize)]
+struct RerankHit {
+    index: usize,
+    relevance_score: f32,
+}
+
+/// Cross-encoder behind a `/rerank` endpoint, as served by Cohere, Jina, Voyage and
+/// compatible self-hosted servers
+pub struct CrossEncoderReranker {
+    endpoint: String,
+    api_key: String,
+    model_name: String,
+}
+
+impl CrossEncoderReranker {
+    pub fn new(config: LLMProviderConfig, model_name: String) -> Result<Self> {
+        let base = config
+            .api_url
+            .as_deref()
+            .map(|url| url.trim().trim_end_matches('/'))
+            .filter(|url| !url.is_empty())
+            .ok_or_else(|| {
+                VectorDbError::Config(
+                    "Cross-encoder reranker requires the API URL of a rerank endpoint".to_string(),
+                )
+            })?;
+        let endpoint = if base.ends_with("/rerank") {
+            base.to_string()
+        } else {
+            format!("{base}/rerank")
+        };
+        Ok(Self {
+            endpoint,
+            api_key: config.api_key,
+            model_name,
+        })
+    }
+}
+
+#[async_trait]
+impl Reranker for CrossEncoderReranker {
+    fn id(&self) -> &str {
+        "cross_encoder"
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    async fn rerank(&self, query: &str, documents: &[&str]) -> Result<Vec<f32>> {
+        if documents.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let response = HTTP_CLIENT
+            .post(&self.endpoint)
+            .bearer_auth(&self.api_key)
+            .json(&RerankRequest {
+                model: &self.model_name,
+                query,
+                documents,
+            })
+            .send()
+            .await
+            .map_err(|e| VectorDbError::Rerank(e.to_string()))?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(VectorDbError::RateLimited(format!(
+                "rerank endpoint returned {status}"
+            )));
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(VectorDbError::Rerank(format!(
+                "rerank endpoint returned {status}: {body}"
+            )));
+        }
+
+        let body: RerankResponse = response
+            .json()
+            .await
+            .map_err(|e| VectorDbError::Rerank(format!("invalid rerank response: {e}")))?;
+        let mut scores = vec![0.0; documents.len()];
+        for hit in body.results {
+            if let Some(score) = scores.get_mut(hit.index) {
+                *score = hit.relevance_score.clamp(0.0, 1.0);
+            }
+        }
+        Ok(scores)
+    }
+}
@@ -0,0 +1,133 @@
+use super::Reranker;
+use crate::llm::{
+    anthropic_types::{
+        ContentBlock, CreateMessageRequest, MessageContent, MessageParam, MessageRole,
+    },
+    provider_registry::ProviderRegistry,
+    providers::Provider,
+    types::LLMProviderConfig,
+};
+use crate::vector_db::core::{Result, VectorDbError};
+use async_trait::async_trait;
+
+/// Grades the model is asked for run from 0 to this
+const MAX_GRADE: f32 = 10.0;
+
+/// Chat model asked to grade every passage in a single request
+pub struct LlmReranker {
+    provider: Provider,
+    model_name: String,
+}
+
+impl LlmReranker {
+    pub fn new(config: LLMProviderConfig, model_name: String) -> Result<Self> {
+        let provider = ProviderRegistry::global()
+            .create(config)
+            .map_err(|e| VectorDbError::Config(e.to_string()))?;
+        Ok(Self {
+            provider,
+            model_name,
+        })
+    }
+}
+
+#[async_trait]
+impl Reranker for LlmReranker {
+    fn id(&self) -> &str {
+        "llm"
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    async fn rerank(&self, query: &str, documents: &[&str]) -> Result<Vec<f32>> {
+        if documents.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut prompt = format!("Query: {query}\n\n");
+        for (idx, document) in documents.iter().enumerate() {
+            prompt.push_str(&format!("[{}]\n```\n{document}\n```\n\n", idx + 1));
+        }
+        prompt.push_str(&format!(
+            "Grade how well each of the {} code passages above answers the query, from 0 \
+             (unrelated) to 10 (exactly what the query asks for). Reply with only a JSON \
+             array of {} integers, one per passage, in order.",
+            documents.len(),
+            documents.len()
+        ));
+
+        let request = CreateMessageRequest {
+            model: self.model_name.clone(),
+            max_tokens: 64 + 4 * documents.len() as u32,
+            system: None,
+            developer_context: None,
+            messages: vec![MessageParam {
+                role: MessageRole::User,
+                content: MessageContent::Text(prompt),
+            }],
+            tools: None,
+            stream: false,
+            temperature: Some(0.0),
+            top_p: None,
+            top_k: None,
+            metadata: None,
+            stop_sequences: None,
+            thinking: None,
+        };
+
+        let message = self.provider.call(request).await.map_err(|e| {
+            if crate::llm::retry::error_retry_reason(&e) == "rate_limit" {
+                VectorDbError::RateLimited(e.to_string())
+            } else {
+                VectorDbError::Rerank(e.to_string())
+            }
+        })?;
+        let reply: String = message
+            .content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        parse_grades(&reply, documents.len())
+    }
+}
+
+/// Read the JSON array of grades out of the reply and scale it to 0..=1
+fn parse_grades(reply: &str, count: usize) -> Result<Vec<f32>> {
+    let array = reply
+        .find('[')
+        .zip(reply.rfind(']'))
+        .filter(|(start, end)| start < end)
+        .map(|(start, end)| &reply[start..=end])
+        .ok_or_else(|| VectorDbError::Rerank(format!("no grades in reply: {reply}")))?;
+    let grades: Vec<f32> = serde_json::from_str(array)
+        .map_err(|e| VectorDbError::Rerank(format!("invalid grades '{array}': {e}")))?;
+    if grades.len() != count {
+        return Err(VectorDbError::Rerank(format!(
+            "expected {count} grades, got {}",
+            grades.len()
+        )));
+    }
+    Ok(grades
+        .into_iter()
+        .map(|grade| (grade / MAX_GRADE).clamp(0.0, 1.0))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_grades_wrapped_in_prose() {
+        let grades = parse_grades("Here you go:\n```json\n[10, 0, 7.5, 12]\n```", 4).unwrap();
+        assert_eq!(grades, vec![1.0, 0.0, 0.75, 1.0]);
+
+        assert!(parse_grades("[3, 4]", 3).is_err());
+        assert!(parse_grades("none of these match", 1).is_err());
+    }
+}
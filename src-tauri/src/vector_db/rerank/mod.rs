@@ -0,0 +1,7 @@
+pub mod cross_encoder;
+pub mod llm_reranker;
+pub mod reranker;
+
+pub use cross_encoder::*;
+pub use llm_reranker::*;
+pub use reranker::*;
@@ -0,0 +1,25 @@
+use crate::vector_db::core::{RerankerConfig, RerankerKind, Result};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    fn id(&self) -> &str;
+    fn model_name(&self) -> &str;
+    /// Relevance of each document to the query in 0..=1, in input order
+    async fn rerank(&self, query: &str, documents: &[&str]) -> Result<Vec<f32>>;
+}
+
+/// Create reranker (single entry point)
+pub fn create_reranker(config: &RerankerConfig) -> Result<Arc<dyn Reranker>> {
+    Ok(match config.kind {
+        RerankerKind::CrossEncoder => Arc::new(super::cross_encoder::CrossEncoderReranker::new(
+            config.provider_config.clone(),
+            config.model_name.clone(),
+        )?),
+        RerankerKind::Llm => Arc::new(super::llm_reranker::LlmReranker::new(
+            config.provider_config.clone(),
+            config.model_name.clone(),
+        )?),
+    })
+}
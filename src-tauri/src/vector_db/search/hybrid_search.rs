@@ -1,8 +1,14 @@
+use super::{refine_results, SearchOptions};
 use crate::vector_db::core::{Result, SearchResult};
+use crate::vector_db::lexical::load_keyword_index;
 use std::collections::HashMap;
+use std::path::Path;
+
+/// RRF constant; larger values flatten the difference between ranks
+pub const RRF_K: usize = 60;
 
 /// Hybrid search engine
-/// Combines semantic search and keyword matching, uses Reciprocal Rank Fusion (RRF) algorithm to merge results
+/// Combines semantic search and BM25 keyword search, uses Reciprocal Rank Fusion (RRF) algorithm to merge results
 pub struct HybridSearchEngine;
 
 impl HybridSearchEngine {
@@ -61,61 +67,71 @@ impl HybridSearchEngine {
         Ok(final_results)
     }
 
-    /// Execute simple keyword search
-    /// Search for content containing query keywords in all chunks
-    pub fn keyword_search(query: &str, all_results: &[SearchResult]) -> Result<Vec<SearchResult>> {
-        // Tokenize query
-        let query_lower = query.to_lowercase();
-        let keywords: Vec<&str> = query_lower.split_whitespace().collect();
+    /// BM25 search over the workspace's keyword index, highest score first. Empty when
+    /// the workspace has no keyword index.
+    pub fn keyword_search(
+        workspace_root: &Path,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        Ok(match load_keyword_index(workspace_root)? {
+            Some(index) => index.search(query, limit),
+            None => Vec::new(),
+        })
+    }
 
-        if keywords.is_empty() {
+    /// Fuse both result lists with [`Self::hybrid_search`] and keep the best `limit`.
+    ///
+    /// Scores are scaled to 0..=1, where 1 means ranked first by every retriever that
+    /// returned anything, so they stay comparable when one side is empty.
+    pub fn fuse(
+        semantic_results: Vec<SearchResult>,
+        keyword_results: Vec<SearchResult>,
+        semantic_weight: f32,
+        keyword_weight: f32,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let semantic_weight = if semantic_results.is_empty() {
+            0.0
+        } else {
+            semantic_weight
+        };
+        let keyword_weight = if keyword_results.is_empty() {
+            0.0
+        } else {
+            keyword_weight
+        };
+        let best_score = (semantic_weight + keyword_weight) / (RRF_K as f32 + 1.0);
+        if best_score <= 0.0 {
             return Ok(Vec::new());
         }
 
-        let mut scored_results = Vec::with_capacity(all_results.len());
-
-        for result in all_results {
-            let content_lower = result.preview.to_lowercase();
-            let mut match_count = 0;
-            let mut total_positions = 0;
-
-            // Count matches for each keyword
-            for keyword in &keywords {
-                if keyword.len() < 2 {
-                    continue; // Skip words that are too short
-                }
-
-                let matches: Vec<_> = content_lower.match_indices(keyword).collect();
-                match_count += matches.len();
-
-                // Record match positions to calculate relevance
-                for (pos, _) in matches {
-                    total_positions += pos;
-                }
-            }
-
-            if match_count > 0 {
-                // Calculate keyword match score
-                // Consider: match count, matched keyword count, match position (earlier is better)
-                let keyword_coverage = match_count as f32 / keywords.len() as f32;
-                let position_score = if total_positions > 0 {
-                    1.0 / (1.0 + (total_positions as f32 / content_lower.len() as f32))
-                } else {
-                    1.0
-                };
-
-                let score = keyword_coverage * 0.7 + position_score * 0.3;
-
-                let mut result = result.clone();
-                result.score = score;
-                scored_results.push(result);
-            }
+        let mut results = Self::hybrid_search(
+            "",
+            semantic_results,
+            keyword_results,
+            semantic_weight,
+            keyword_weight,
+            RRF_K,
+        )?;
+        results.truncate(limit);
+        for result in &mut results {
+            result.score /= best_score;
         }
+        Ok(results)
+    }
 
-        // Sort by score
-        scored_results.sort_by(|a, b| b.score.total_cmp(&a.score));
-
-        Ok(scored_results)
+    /// Keyword-only search, for when no embedding model is configured. Scores are on
+    /// the same scale as [`Self::fuse`]; there is no reranker to apply.
+    pub async fn search_without_embeddings(
+        workspace_root: &Path,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let candidates = options.candidate_count();
+        let keyword_results = Self::keyword_search(workspace_root, query, candidates)?;
+        let fused = Self::fuse(Vec::new(), keyword_results, 0.0, 1.0, candidates)?;
+        Ok(refine_results(query, fused, options, None).await)
     }
 }
 
@@ -134,7 +150,9 @@ impl SearchResult {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::vector_db::core::{ChunkType, Span};
+    use crate::vector_db::core::{Chunk, ChunkType, Span};
+    use crate::vector_db::lexical::LexicalIndex;
+    use crate::vector_db::storage::FileStore;
     use std::path::PathBuf;
 
     fn create_test_result(
@@ -181,17 +199,48 @@ mod tests {
         assert_eq!(results[0].file_path, PathBuf::from("file1.rs"));
     }
 
-    #[test]
-    fn test_keyword_search() {
-        let all_results = vec![
-            create_test_result("file1.rs", 1, 10, "This is a test function", 0.0),
-            create_test_result("file2.rs", 5, 15, "Another test here", 0.0),
-            create_test_result("file3.rs", 20, 30, "No match", 0.0),
-        ];
+    #[tokio::test]
+    async fn test_keyword_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let mut index = LexicalIndex::new();
+        for (name, content) in [
+            ("file1.rs", "// This is a test function"),
+            ("file2.rs", "fn another_test() {}"),
+            ("file3.rs", "// No match"),
+        ] {
+            let path = root.join(name);
+            let chunk = Chunk {
+                id: uuid::Uuid::new_v4(),
+                file_path: path.clone(),
+                span: Span::new(0, content.len(), 1, 1),
+                content: content.to_string(),
+                chunk_type: ChunkType::Function,
+                stride_info: None,
+            };
+            index.replace_file(&path, name, &[chunk]);
+        }
+        FileStore::new(root)
+            .unwrap()
+            .save_lexical_index(&index)
+            .unwrap();
 
-        let results = HybridSearchEngine::keyword_search("test", &all_results).unwrap();
+        let results = HybridSearchEngine::keyword_search(root, "test", 10).unwrap();
 
         assert_eq!(results.len(), 2);
         assert!(results[0].score > 0.0);
+        // The identifier match outranks the comment mention
+        assert_eq!(results[0].file_path, root.join("file2.rs"));
+
+        let scaled =
+            HybridSearchEngine::search_without_embeddings(root, "test", &SearchOptions::default())
+                .await
+                .unwrap();
+        assert_eq!(scaled[0].score, 1.0);
+        assert!(
+            HybridSearchEngine::keyword_search(&root.join("missing"), "test", 10)
+                .unwrap()
+                .is_empty()
+        );
     }
 }
@@ -1,15 +1,30 @@
 pub mod hybrid_search;
+mod refine;
 pub mod semantic_search;
 mod workspace_index;
 
+use std::path::PathBuf;
+
 use crate::vector_db::core::Language;
 
+/// Candidates each retriever contributes per requested result
+const CANDIDATE_FACTOR: usize = 3;
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct SearchOptions {
     pub top_k: usize,
     pub threshold: f32,
     pub include_snippet: bool,
     pub filter_languages: Vec<Language>,
+    /// Merge overlapping and adjacent chunks of a file into one result
+    pub merge_adjacent: bool,
+    /// MMR trade-off between relevance (0.0) and spreading results across files (1.0)
+    pub diversity: f32,
+    /// Rescore the best candidates with the configured reranker, if there is one
+    pub rerank: bool,
+    /// Files recently read or edited; their results rank higher
+    pub boost_files: Vec<PathBuf>,
 }
 
 impl Default for SearchOptions {
@@ -19,10 +34,22 @@ impl Default for SearchOptions {
             threshold: 0.3,
             include_snippet: true,
             filter_languages: vec![],
+            merge_adjacent: true,
+            diversity: 0.3,
+            rerank: true,
+            boost_files: vec![],
         }
     }
 }
 
+impl SearchOptions {
+    /// First-stage results to fetch, leaving the second stage room to merge and diversify
+    pub(crate) fn candidate_count(&self) -> usize {
+        self.top_k.saturating_mul(CANDIDATE_FACTOR)
+    }
+}
+
 pub use hybrid_search::*;
+pub use refine::*;
 pub use semantic_search::*;
 pub(crate) use workspace_index::*;
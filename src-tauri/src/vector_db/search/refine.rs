@@ -0,0 +1,358 @@
+//! Second stage of a search, run over the fused candidates
+//!
+//! Neighbouring chunks of a file are merged into one span, the best candidates are
+//! optionally rescored by a reranker, files the user is working on are boosted, and the
+//! final list is picked with maximal marginal relevance (MMR) so one file can't fill it.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::SearchOptions;
+use crate::vector_db::core::{Result, SearchResult, Span};
+use crate::vector_db::rerank::Reranker;
+
+/// Merged spans stop growing past this many lines
+const MAX_MERGED_LINES: usize = 200;
+/// Candidates sent to the reranker per requested result
+const RERANK_FACTOR: usize = 2;
+/// Text of a candidate sent to the reranker, in chars
+const RERANK_MAX_CHARS: usize = 2000;
+/// Share of the gap to a perfect score closed for results in recently used files
+const RECENT_FILE_BOOST: f32 = 0.2;
+/// MMR similarity of results in the same directory; results in the same file count as 1
+const SAME_DIR_SIMILARITY: f32 = 0.3;
+
+/// Run the second stage and keep the best `options.top_k`. A failing reranker is logged
+/// and the first-stage order kept.
+pub async fn refine_results(
+    query: &str,
+    candidates: Vec<SearchResult>,
+    options: &SearchOptions,
+    reranker: Option<&dyn Reranker>,
+) -> Vec<SearchResult> {
+    let mut results = if options.merge_adjacent {
+        merge_adjacent_chunks(candidates)
+    } else {
+        candidates
+    };
+
+    if let Some(reranker) = reranker.filter(|_| options.rerank) {
+        let window = options.top_k.saturating_mul(RERANK_FACTOR);
+        match rerank_results(reranker, query, &results, window).await {
+            Ok(reranked) => results = reranked,
+            Err(err) => tracing::warn!(
+                "Reranking with {} failed, keeping the fused order: {}",
+                reranker.model_name(),
+                err
+            ),
+        }
+    }
+
+    boost_files(&mut results, &options.boost_files);
+    diversify(results, options.diversity, options.top_k)
+}
+
+/// Merge results of the same file whose line ranges overlap or touch. A merged result
+/// keeps the best score and the metadata of the chunk that had it.
+pub fn merge_adjacent_chunks(results: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut by_position = results;
+    by_position.sort_by(|a, b| {
+        a.file_path
+            .cmp(&b.file_path)
+            .then(a.span.line_start.cmp(&b.span.line_start))
+            .then(a.span.line_end.cmp(&b.span.line_end))
+    });
+
+    let mut merged: Vec<SearchResult> = Vec::with_capacity(by_position.len());
+    for result in by_position {
+        if let Some(last) = merged.last_mut() {
+            let touches = last.file_path == result.file_path
+                && result.span.line_start <= last.span.line_end + 1;
+            let line_end = last.span.line_end.max(result.span.line_end);
+            if touches && line_end + 1 - last.span.line_start <= MAX_MERGED_LINES {
+                let span = Span::new(
+                    last.span.byte_start.min(result.span.byte_start),
+                    last.span.byte_end.max(result.span.byte_end),
+                    last.span.line_start,
+                    line_end,
+                );
+                if result.score > last.score {
+                    *last = result;
+                }
+                last.span = span;
+                continue;
+            }
+        }
+        merged.push(result);
+    }
+
+    sort_by_score(&mut merged);
+    merged
+}
+
+/// Rescore the first `window` results with `reranker`, best first. Results whose file
+/// can no longer be read are dropped.
+async fn rerank_results(
+    reranker: &dyn Reranker,
+    query: &str,
+    results: &[SearchResult],
+    window: usize,
+) -> Result<Vec<SearchResult>> {
+    let mut files: HashMap<&Path, Option<String>> = HashMap::new();
+    let mut candidates: Vec<(SearchResult, String)> = Vec::new();
+    for result in results.iter().take(window) {
+        if !files.contains_key(result.file_path.as_path()) {
+            let content = tokio::fs::read_to_string(&result.file_path).await.ok();
+            files.insert(result.file_path.as_path(), content);
+        }
+        let Some(Some(content)) = files.get(result.file_path.as_path()) else {
+            continue;
+        };
+        candidates.push((result.clone(), span_text(content, &result.span)));
+    }
+
+    let documents: Vec<&str> = candidates.iter().map(|(_, text)| text.as_str()).collect();
+    let scores = reranker.rerank(query, &documents).await?;
+    let mut reranked: Vec<SearchResult> = candidates
+        .into_iter()
+        .zip(scores)
+        .map(|((mut result, _), score)| {
+            result.score = score;
+            result
+        })
+        .collect();
+    sort_by_score(&mut reranked);
+    Ok(reranked)
+}
+
+/// Lines of `span` (1-based, inclusive), cut to what a reranker is sent
+fn span_text(content: &str, span: &Span) -> String {
+    let start = span.line_start.max(1) - 1;
+    let count = span.line_end.saturating_sub(start).max(1);
+    let text = content
+        .lines()
+        .skip(start)
+        .take(count)
+        .collect::<Vec<_>>()
+        .join("\n");
+    match text.char_indices().nth(RERANK_MAX_CHARS) {
+        Some((cut, _)) => text[..cut].to_string(),
+        None => text,
+    }
+}
+
+/// Move results in `files` up, keeping scores within 0..=1
+pub fn boost_files(results: &mut [SearchResult], files: &[PathBuf]) {
+    if files.is_empty() {
+        return;
+    }
+    let files: HashSet<&Path> = files.iter().map(PathBuf::as_path).collect();
+    for result in results.iter_mut() {
+        if files.contains(result.file_path.as_path()) {
+            result.score += (1.0 - result.score).max(0.0) * RECENT_FILE_BOOST;
+        }
+    }
+    sort_by_score(results);
+}
+
+/// Pick `limit` results by maximal marginal relevance. `diversity` 0 keeps the score
+/// order; higher values increasingly prefer files and directories not picked yet.
+pub fn diversify(results: Vec<SearchResult>, diversity: f32, limit: usize) -> Vec<SearchResult> {
+    let diversity = diversity.clamp(0.0, 1.0);
+    let mut remaining = results;
+    if diversity == 0.0 || remaining.len() <= 1 {
+        remaining.truncate(limit);
+        return remaining;
+    }
+
+    let relevance_weight = 1.0 - diversity;
+    let mut selected: Vec<SearchResult> = Vec::with_capacity(limit.min(remaining.len()));
+    while selected.len() < limit && !remaining.is_empty() {
+        let mut best = 0;
+        let mut best_value = f32::NEG_INFINITY;
+        for (idx, candidate) in remaining.iter().enumerate() {
+            let redundancy = selected
+                .iter()
+                .map(|picked| similarity(candidate, picked))
+                .fold(0.0, f32::max);
+            let value = relevance_weight * candidate.score - diversity * redundancy;
+            if value > best_value {
+                best = idx;
+                best_value = value;
+            }
+        }
+        selected.push(remaining.remove(best));
+    }
+    selected
+}
+
+fn similarity(a: &SearchResult, b: &SearchResult) -> f32 {
+    if a.file_path == b.file_path {
+        1.0
+    } else if a.file_path.parent() == b.file_path.parent() {
+        SAME_DIR_SIMILARITY
+    } else {
+        0.0
+    }
+}
+
+fn sort_by_score(results: &mut [SearchResult]) {
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_db::core::ChunkType;
+
+    fn result(file: &str, line_start: usize, line_end: usize, score: f32) -> SearchResult {
+        SearchResult::new(
+            PathBuf::from(file),
+            Span::new(line_start * 10, line_end * 10, line_start, line_end),
+            score,
+            String::new(),
+            None,
+            Some(ChunkType::Function),
+        )
+    }
+
+    fn positions(results: &[SearchResult]) -> Vec<(&str, usize, usize)> {
+        results
+            .iter()
+            .map(|r| {
+                (
+                    r.file_path.to_str().unwrap(),
+                    r.span.line_start,
+                    r.span.line_end,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn merges_overlapping_and_adjacent_chunks_of_a_file() {
+        let merged = merge_adjacent_chunks(vec![
+            result("/ws/a.rs", 21, 40, 0.6),
+            result("/ws/a.rs", 1, 20, 0.9),
+            result("/ws/a.rs", 35, 50, 0.4),
+            result("/ws/a.rs", 60, 70, 0.5),
+            result("/ws/b.rs", 41, 60, 0.7),
+            result("/ws/a.rs", 100, 400, 0.3),
+        ]);
+
+        assert_eq!(
+            positions(&merged),
+            vec![
+                ("/ws/a.rs", 1, 50),
+                ("/ws/b.rs", 41, 60),
+                ("/ws/a.rs", 60, 70),
+                ("/ws/a.rs", 100, 400),
+            ]
+        );
+        assert_eq!(merged[0].score, 0.9);
+        assert_eq!(
+            (merged[0].span.byte_start, merged[0].span.byte_end),
+            (10, 500)
+        );
+
+        // Spans stop growing at the cap
+        let capped = merge_adjacent_chunks(vec![
+            result("/ws/c.rs", 1, 150, 0.5),
+            result("/ws/c.rs", 151, 300, 0.5),
+        ]);
+        assert_eq!(capped.len(), 2);
+    }
+
+    #[test]
+    fn diversify_spreads_results_across_files() {
+        let results = vec![
+            result("/ws/src/a.rs", 1, 10, 0.95),
+            result("/ws/src/a.rs", 30, 40, 0.9),
+            result("/ws/src/a.rs", 60, 70, 0.85),
+            result("/ws/lib/b.rs", 1, 10, 0.7),
+            result("/ws/src/c.rs", 1, 10, 0.65),
+        ];
+
+        let plain = diversify(results.clone(), 0.0, 3);
+        assert_eq!(
+            positions(&plain),
+            vec![
+                ("/ws/src/a.rs", 1, 10),
+                ("/ws/src/a.rs", 30, 40),
+                ("/ws/src/a.rs", 60, 70),
+            ]
+        );
+
+        let spread = diversify(results, 0.3, 3);
+        let files: Vec<&str> = positions(&spread).into_iter().map(|p| p.0).collect();
+        assert_eq!(files, vec!["/ws/src/a.rs", "/ws/lib/b.rs", "/ws/src/c.rs"]);
+    }
+
+    struct ContainsReranker;
+
+    #[async_trait::async_trait]
+    impl Reranker for ContainsReranker {
+        fn id(&self) -> &str {
+            "test"
+        }
+
+        fn model_name(&self) -> &str {
+            "contains"
+        }
+
+        async fn rerank(&self, query: &str, documents: &[&str]) -> Result<Vec<f32>> {
+            Ok(documents
+                .iter()
+                .map(|doc| if doc.contains(query) { 1.0 } else { 0.1 })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn reranks_the_text_of_each_span() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        std::fs::write(&path, "fn first() {}\n\nfn needle() {}\n").unwrap();
+        let file = path.to_str().unwrap();
+        let missing = dir.path().join("gone.rs");
+
+        let reranked = rerank_results(
+            &ContainsReranker,
+            "needle",
+            &[
+                result(file, 1, 1, 0.9),
+                result(missing.to_str().unwrap(), 1, 1, 0.8),
+                result(file, 3, 3, 0.5),
+            ],
+            10,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(positions(&reranked), vec![(file, 3, 3), (file, 1, 1)]);
+        assert_eq!(reranked[0].score, 1.0);
+    }
+
+    #[test]
+    fn boosts_recent_files_without_leaving_the_score_range() {
+        let mut results = vec![
+            result("/ws/a.rs", 1, 10, 0.75),
+            result("/ws/b.rs", 1, 10, 0.7),
+            result("/ws/c.rs", 1, 10, 1.0),
+        ];
+        boost_files(
+            &mut results,
+            &[PathBuf::from("/ws/b.rs"), PathBuf::from("/ws/c.rs")],
+        );
+
+        assert_eq!(
+            positions(&results)
+                .into_iter()
+                .map(|p| p.0)
+                .collect::<Vec<_>>(),
+            vec!["/ws/c.rs", "/ws/b.rs", "/ws/a.rs"]
+        );
+        assert_eq!(results[0].score, 1.0);
+        assert!((results[1].score - 0.76).abs() < 1e-6);
+    }
+}
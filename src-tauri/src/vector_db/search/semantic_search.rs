@@ -1,13 +1,15 @@
-use super::SearchOptions;
+use super::{refine_results, SearchOptions};
 use crate::vector_db::core::{Result, SearchResult, VectorDbConfig};
 use crate::vector_db::embedding::Embedder;
-use crate::vector_db::search::WorkspaceIndexCache;
+use crate::vector_db::rerank::Reranker;
+use crate::vector_db::search::{HybridSearchEngine, WorkspaceIndexCache};
 use crate::vector_db::storage::IndexManager;
 use std::path::Path;
 use std::sync::Arc;
 
 pub struct SemanticSearchEngine {
     embedder: Arc<dyn Embedder>,
+    reranker: Option<Arc<dyn Reranker>>,
     config: VectorDbConfig,
     index_cache: WorkspaceIndexCache,
 }
@@ -18,44 +20,87 @@ impl SemanticSearchEngine {
         let index_cache = WorkspaceIndexCache::new(3, 256 * 1024 * 1024);
         Self {
             embedder,
+            reranker: None,
             config,
             index_cache,
         }
     }
 
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        self.reranker = Some(reranker);
+        self
+    }
+
     pub fn embedder(&self) -> Arc<dyn Embedder> {
         self.embedder.clone()
     }
 
-    pub fn config(&self) -> &VectorDbConfig {
-        &self.config
+    /// Settings with the dimension and model name the embedder reports. Those of a local
+    /// backend may be unknown until [`Self::connect`] succeeds.
+    pub fn config(&self) -> VectorDbConfig {
+        let mut config = self.config.clone();
+        config.embedding.model_name = self.embedder.model_name().to_string();
+        config.embedding.dimension = self.embedder.dim();
+        config
+    }
+
+    /// Reach the embedding server; see [`Embedder::connect`]
+    pub async fn connect(&self) -> Result<()> {
+        self.embedder.connect().await
     }
 
     pub fn invalidate_workspace_index(&self, workspace_root: &Path) {
         self.index_cache.invalidate(workspace_root);
     }
 
+    /// Hybrid search: BM25 and HNSW candidates fused with RRF, then refined by
+    /// [`refine_results`]. Works with either index alone.
     pub async fn search_in_workspace(
         &self,
         workspace_root: &Path,
         query: &str,
         options: SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let candidates = options.candidate_count();
+        let keyword_results =
+            HybridSearchEngine::keyword_search(workspace_root, query, candidates)?;
+        let threshold = self.config.similarity_threshold.max(options.threshold);
+        let semantic_results = self
+            .vector_search(workspace_root, query, candidates, threshold)
+            .await?;
+
+        let fused = HybridSearchEngine::fuse(
+            semantic_results,
+            keyword_results,
+            self.config.semantic_weight,
+            self.config.keyword_weight,
+            candidates,
+        )?;
+        Ok(refine_results(query, fused, &options, self.reranker.as_deref()).await)
+    }
+
+    async fn vector_search(
+        &self,
+        workspace_root: &Path,
+        query: &str,
+        top_k: usize,
+        threshold: f32,
     ) -> Result<Vec<SearchResult>> {
         let index_manager = IndexManager::new(workspace_root, self.config.clone())?;
         if index_manager.get_status().total_chunks == 0 {
             return Ok(Vec::new());
         }
 
+        self.connect().await?;
         let cached = self
             .index_cache
-            .get_or_build(workspace_root, &self.config)
+            .get_or_build(workspace_root, &self.config())
             .await?;
 
         let query_embedding = self.embedder.embed(&[query]).await?;
         let query_vec = &query_embedding[0];
 
-        let threshold = self.config.similarity_threshold.max(options.threshold);
-        let hits = cached.search(query_vec, options.top_k, threshold)?;
+        let hits = cached.search(query_vec, top_k, threshold)?;
 
         let mut search_results = Vec::with_capacity(hits.len());
         for (internal_idx, score) in hits {
@@ -1,16 +1,24 @@
 use crate::vector_db::core::{ChunkId, Result, VectorDbConfig, VectorDbError};
-use crate::vector_db::storage::{ChunkMetadata, IndexManager};
+use crate::vector_db::storage::{ChunkMetadata, FileStore, GraphMeta, IndexManager, StoredGraph};
 use hnsw_rs::prelude::*;
 use lru::LruCache;
 use parking_lot::Mutex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+// HNSW params: good default trade-off.
+const HNSW_M: usize = 16;
+const HNSW_MAX_LAYER: usize = 16;
+const HNSW_EF_CONSTRUCTION: usize = 200;
+/// Rebuild instead of patching once this share of a graph's points is deleted
+const MAX_DELETED_RATIO: f64 = 0.25;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct IndexSignature {
-    updated_at: u64,
+    /// Changes whenever any file is re-indexed or removed
+    chunks: String,
     embedding_model: String,
     vector_dimension: usize,
     total_chunks: usize,
@@ -21,7 +29,7 @@ impl IndexSignature {
         let status = manager.get_status();
         let manifest = manager.manifest.read();
         Self {
-            updated_at: manifest.updated_at,
+            chunks: manifest.chunk_signature(),
             embedding_model: status.embedding_model,
             vector_dimension: status.vector_dimension,
             total_chunks: status.total_chunks,
@@ -91,10 +99,12 @@ impl WorkspaceIndexCache {
             return Ok(entry);
         }
 
+        // A stale entry no search holds any more is patched in place instead of reloaded.
+        let stale = self.take_unshared(&workspace_root);
         let config = config.clone();
         let workspace_root_for_build = workspace_root.clone();
         let built = tokio::task::spawn_blocking(move || {
-            build_workspace_index(&workspace_root_for_build, &config)
+            build_workspace_index(&workspace_root_for_build, &config, stale)
         })
         .await
         .map_err(|e| VectorDbError::Index(format!("index build join failed: {e}")))??;
@@ -129,6 +139,13 @@ impl WorkspaceIndexCache {
         Ok(None)
     }
 
+    fn take_unshared(&self, workspace_root: &Path) -> Option<CachedWorkspaceIndex> {
+        let mut inner = self.inner.lock();
+        let entry = inner.lru.pop(workspace_root)?;
+        inner.bytes = inner.bytes.saturating_sub(entry.approx_bytes);
+        Arc::try_unwrap(entry).ok()
+    }
+
     fn insert(&self, workspace_root: PathBuf, entry: Arc<CachedWorkspaceIndex>) {
         let mut inner = self.inner.lock();
 
@@ -152,9 +169,7 @@ impl WorkspaceIndexCache {
 pub struct CachedWorkspaceIndex {
     signature: IndexSignature,
     dimension: usize,
-    ids: Vec<ChunkId>,
-    metas: Vec<ChunkMetadata>,
-    hnsw: Option<Hnsw<'static, f32, DistCosine>>,
+    graph: Option<StoredGraph>,
     approx_bytes: usize,
 }
 
@@ -163,13 +178,26 @@ impl CachedWorkspaceIndex {
         Self {
             signature,
             dimension,
-            ids: Vec::new(),
-            metas: Vec::new(),
-            hnsw: None,
+            graph: None,
             approx_bytes: 0,
         }
     }
 
+    fn new(signature: IndexSignature, dimension: usize, graph: StoredGraph) -> Self {
+        let approx_bytes = graph
+            .meta
+            .ids
+            .len()
+            .saturating_mul(dimension)
+            .saturating_mul(std::mem::size_of::<f32>());
+        Self {
+            signature,
+            dimension,
+            graph: Some(graph),
+            approx_bytes,
+        }
+    }
+
     pub fn search(&self, query: &[f32], top_k: usize, threshold: f32) -> Result<Vec<(usize, f32)>> {
         if query.len() != self.dimension {
             return Err(VectorDbError::InvalidDimension {
@@ -177,28 +205,31 @@ impl CachedWorkspaceIndex {
                 actual: query.len(),
             });
         }
-        if self.ids.is_empty() || top_k == 0 {
+        let graph = match &self.graph {
+            Some(graph) if !graph.meta.ids.is_empty() => graph,
+            _ => return Ok(Vec::new()),
+        };
+        if top_k == 0 {
             return Ok(Vec::new());
         }
 
         let query_norm = normalize_l2(query);
         let max_dist = 1.0f32 - threshold;
 
-        let hnsw = match &self.hnsw {
-            Some(h) => h,
-            None => return Ok(Vec::new()),
-        };
-
         // ef_search: trade recall vs latency. Keep it modest to control CPU.
         let ef_search = (top_k * 8).clamp(32, 256);
-        let neighbors = hnsw.search(&query_norm, top_k, ef_search);
+        let deleted = &graph.meta.deleted;
+        let live = |idx: &usize| !deleted.get(*idx).copied().unwrap_or(true);
+        let neighbors = graph
+            .hnsw
+            .search_filter(&query_norm, top_k, ef_search, Some(&live));
 
         let mut results: Vec<(usize, f32)> = neighbors
             .into_iter()
             .filter_map(|n| {
                 let idx = n.d_id;
                 let dist = n.distance;
-                if dist <= max_dist && idx < self.ids.len() {
+                if dist <= max_dist && idx < graph.meta.ids.len() {
                     Some((idx, 1.0f32 - dist))
                 } else {
                     None
@@ -214,58 +245,133 @@ impl CachedWorkspaceIndex {
     }
 
     pub fn chunk_meta_by_internal(&self, idx: usize) -> Option<(&ChunkId, &ChunkMetadata)> {
-        self.ids.get(idx).zip(self.metas.get(idx))
+        let meta = &self.graph.as_ref()?.meta;
+        meta.ids.get(idx).zip(meta.metas.get(idx))
     }
 }
 
+/// Load the workspace graph, reusing `stale` or the persisted graph when they were
+/// built with the same embedding model, and patch it up to the current manifest.
 fn build_workspace_index(
     workspace_root: &Path,
     config: &VectorDbConfig,
+    stale: Option<CachedWorkspaceIndex>,
 ) -> Result<CachedWorkspaceIndex> {
     let manager = IndexManager::new(workspace_root, config.clone())?;
     let signature = IndexSignature::from_manager(&manager);
     let status = manager.get_status();
+    let dimension = config.embedding.dimension;
     if status.total_chunks == 0 {
-        return Ok(CachedWorkspaceIndex::empty(
-            signature,
-            config.embedding.dimension,
-        ));
+        return Ok(CachedWorkspaceIndex::empty(signature, dimension));
     }
 
-    if status.vector_dimension != config.embedding.dimension {
+    if status.vector_dimension != dimension {
         return Err(VectorDbError::InvalidDimension {
-            expected: config.embedding.dimension,
+            expected: dimension,
             actual: status.vector_dimension,
         });
     }
 
     let store = manager.store();
+    let manifest_signature = signature.chunks.clone();
+
+    let previous = match stale.and_then(|entry| entry.graph) {
+        Some(graph) => Some(graph),
+        None => store.load_graph().unwrap_or_else(|err| {
+            tracing::warn!("Failed to load persisted vector graph: {}", err);
+            None
+        }),
+    };
+    let reusable = previous.filter(|graph| {
+        graph.meta.embedding_model == status.embedding_model && graph.meta.dimension == dimension
+    });
+
+    let graph = match reusable {
+        Some(graph) if graph.meta.manifest_signature == manifest_signature => graph,
+        reusable => {
+            let patched = match reusable {
+                Some(mut graph) => patch_graph(&mut graph, &manager, dimension)?.then_some(graph),
+                None => None,
+            };
+            let mut graph = match patched {
+                Some(graph) => graph,
+                None => build_graph(&manager, dimension)?,
+            };
+            graph.meta.manifest_signature = manifest_signature;
+            if let Err(err) = store.save_graph(&graph) {
+                tracing::warn!("Failed to persist vector graph: {}", err);
+            }
+            graph
+        }
+    };
 
-    let chunk_metadata_vec = manager.get_all_chunk_metadata();
+    Ok(CachedWorkspaceIndex::new(signature, dimension, graph))
+}
+
+fn build_graph(manager: &IndexManager, dimension: usize) -> Result<StoredGraph> {
+    let chunks = manager.get_all_chunk_metadata();
+    let hnsw = Hnsw::new(
+        HNSW_M,
+        chunks.len().max(1),
+        HNSW_MAX_LAYER,
+        HNSW_EF_CONSTRUCTION,
+        DistCosine {},
+    );
+    let mut graph = StoredGraph {
+        hnsw,
+        meta: GraphMeta::new(manager.get_status().embedding_model, dimension),
+    };
+    insert_chunks(&mut graph, manager.store(), chunks, dimension)?;
+    Ok(graph)
+}
+
+/// Delete chunks that left the manifest and insert the ones that joined it. Returns
+/// false when too many points are deleted and the graph should be rebuilt instead.
+fn patch_graph(graph: &mut StoredGraph, manager: &IndexManager, dimension: usize) -> Result<bool> {
+    let chunks: HashMap<ChunkId, ChunkMetadata> =
+        manager.get_all_chunk_metadata().into_iter().collect();
+
+    let meta = &mut graph.meta;
+    let mut present: HashSet<ChunkId> = HashSet::with_capacity(meta.ids.len());
+    for (id, deleted) in meta.ids.iter().zip(meta.deleted.iter_mut()) {
+        if *deleted {
+            continue;
+        }
+        if chunks.contains_key(id) {
+            present.insert(*id);
+        } else {
+            *deleted = true;
+        }
+    }
+
+    let added: Vec<(ChunkId, ChunkMetadata)> = chunks
+        .into_iter()
+        .filter(|(id, _)| !present.contains(id))
+        .collect();
+    let total = meta.ids.len() + added.len();
+    if meta.deleted_count() as f64 > total as f64 * MAX_DELETED_RATIO {
+        return Ok(false);
+    }
+
+    insert_chunks(graph, manager.store(), added, dimension)?;
+    Ok(true)
+}
+
+fn insert_chunks(
+    graph: &mut StoredGraph,
+    store: &FileStore,
+    chunks: Vec<(ChunkId, ChunkMetadata)>,
+    dimension: usize,
+) -> Result<()> {
     let mut by_file: HashMap<PathBuf, Vec<(ChunkId, ChunkMetadata)>> = HashMap::new();
-    by_file.reserve(chunk_metadata_vec.len().max(1));
-    for (id, meta) in chunk_metadata_vec {
+    by_file.reserve(chunks.len().max(1));
+    for (id, meta) in chunks {
         by_file
             .entry(meta.file_path.clone())
             .or_default()
             .push((id, meta));
     }
 
-    // HNSW params: good default trade-off.
-    let m = 16;
-    let ef_construction = 200;
-    let expected = signature.total_chunks.max(1);
-    let hnsw: Hnsw<'static, f32, DistCosine> = Hnsw::new(
-        m,
-        expected,
-        config.embedding.dimension,
-        ef_construction,
-        DistCosine {},
-    );
-
-    let mut ids: Vec<ChunkId> = Vec::with_capacity(signature.total_chunks);
-    let mut metas: Vec<ChunkMetadata> = Vec::with_capacity(signature.total_chunks);
-
     for (file_path, chunks) in by_file {
         let file_vectors = match store.load_file_vectors(&file_path) {
             Ok(v) => v,
@@ -276,34 +382,22 @@ fn build_workspace_index(
             let Some(vecf) = file_vectors.chunks.get(&chunk_id) else {
                 continue;
             };
-            if vecf.len() != config.embedding.dimension {
+            if vecf.len() != dimension {
                 return Err(VectorDbError::InvalidDimension {
-                    expected: config.embedding.dimension,
+                    expected: dimension,
                     actual: vecf.len(),
                 });
             }
 
             let v = normalize_l2(vecf);
-            let internal_id = ids.len();
-            hnsw.insert((&v, internal_id));
-            ids.push(chunk_id);
-            metas.push(meta);
+            let internal_id = graph.meta.ids.len();
+            graph.hnsw.insert((&v, internal_id));
+            graph.meta.ids.push(chunk_id);
+            graph.meta.metas.push(meta);
+            graph.meta.deleted.push(false);
         }
     }
-
-    let approx_bytes = ids
-        .len()
-        .saturating_mul(config.embedding.dimension)
-        .saturating_mul(std::mem::size_of::<f32>());
-
-    Ok(CachedWorkspaceIndex {
-        signature,
-        dimension: config.embedding.dimension,
-        ids,
-        metas,
-        hnsw: Some(hnsw),
-        approx_bytes,
-    })
+    Ok(())
 }
 
 #[inline]
@@ -320,3 +414,129 @@ fn normalize_l2(vector: &[f32]) -> Vec<f32> {
         vector.to_vec()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_db::core::{ChunkType, Span};
+
+    fn test_config() -> VectorDbConfig {
+        let mut config = VectorDbConfig::default();
+        config.embedding.model_name = "test-model".to_string();
+        config.embedding.dimension = 4;
+        config
+    }
+
+    /// Index two chunks for `name` the way `IndexManager` stores them
+    fn index_file(root: &Path, config: &VectorDbConfig, name: &str, seed: f32) -> Vec<ChunkId> {
+        let manager = IndexManager::new(root, config.clone()).unwrap();
+        let file_path = root.join(name);
+        let chunks: Vec<(ChunkId, Vec<f32>)> = (0..2)
+            .map(|i| (ChunkId::new_v4(), vec![seed, i as f32, 1.0, 0.5]))
+            .collect();
+        manager
+            .store()
+            .save_file_vectors(&file_path, &chunks)
+            .unwrap();
+
+        let mut manifest = manager.manifest.write();
+        manifest.add_file(file_path.clone(), name.to_string());
+        for (id, _) in &chunks {
+            manifest.add_chunk(
+                *id,
+                ChunkMetadata {
+                    file_path: file_path.clone(),
+                    span: Span::new(0, 1, 1, 1),
+                    chunk_type: ChunkType::Generic,
+                    hash: String::new(),
+                },
+            );
+        }
+        manifest
+            .save(&manager.store().root_path().join("manifest.json"))
+            .unwrap();
+        chunks.into_iter().map(|(id, _)| id).collect()
+    }
+
+    #[test]
+    fn persisted_graph_is_patched_instead_of_rebuilt() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let config = test_config();
+        let mut indexed: Vec<Vec<ChunkId>> = (0..8)
+            .map(|i| index_file(root, &config, &format!("f{i}.rs"), i as f32))
+            .collect();
+        let removed = indexed.swap_remove(0);
+
+        let built = build_workspace_index(root, &config, None).unwrap();
+        assert_eq!(built.graph.as_ref().unwrap().meta.ids.len(), 16);
+
+        IndexManager::new(root, config.clone())
+            .unwrap()
+            .remove_file(&root.join("f0.rs"))
+            .unwrap();
+        let added = index_file(root, &config, "new.rs", 3.0);
+
+        // Loaded from disk, not the in-memory entry
+        let patched = build_workspace_index(root, &config, None).unwrap();
+        let meta = &patched.graph.as_ref().unwrap().meta;
+        assert_eq!(meta.ids.len(), 18);
+        assert_eq!(meta.deleted_count(), 2);
+        assert!(added.iter().all(|id| meta.ids.contains(id)));
+
+        // Every live chunk scores above 0 against the query, so brute force returns all 16;
+        // HNSW is approximate but must find nearly all of them and never a removed chunk
+        let live: Vec<ChunkId> = indexed.iter().flatten().chain(&added).copied().collect();
+        let hits = patched.search(&[0.0, 0.0, 1.0, 0.5], 18, 0.0).unwrap();
+        let hit_ids: Vec<ChunkId> = hits
+            .iter()
+            .map(|(idx, _)| *patched.chunk_meta_by_internal(*idx).unwrap().0)
+            .collect();
+        assert!(hit_ids.iter().all(|id| !removed.contains(id)));
+        let recalled = live.iter().filter(|id| hit_ids.contains(id)).count();
+        assert!(
+            recalled >= 14,
+            "recalled {recalled} of {} chunks",
+            live.len()
+        );
+
+        let store = FileStore::new(root).unwrap();
+        let persisted = store.load_graph().unwrap().unwrap();
+        assert_eq!(persisted.meta.deleted_count(), 2);
+        assert_eq!(
+            persisted.meta.manifest_signature,
+            IndexManager::new(root, config)
+                .unwrap()
+                .manifest
+                .read()
+                .chunk_signature()
+        );
+    }
+
+    #[test]
+    fn damaged_graph_dump_is_rebuilt() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let config = test_config();
+        for i in 0..4 {
+            index_file(root, &config, &format!("f{i}.rs"), i as f32);
+        }
+        build_workspace_index(root, &config, None).unwrap();
+
+        let store = FileStore::new(root).unwrap();
+        assert!(store.load_graph().unwrap().is_some());
+
+        let data = store.root_path().join("graph.hnsw.data");
+        let len = std::fs::metadata(&data).unwrap().len();
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&data)
+            .unwrap()
+            .set_len(len / 2)
+            .unwrap();
+        assert!(store.load_graph().unwrap().is_none());
+
+        let rebuilt = build_workspace_index(root, &config, None).unwrap();
+        assert_eq!(rebuilt.graph.as_ref().unwrap().meta.ids.len(), 8);
+    }
+}
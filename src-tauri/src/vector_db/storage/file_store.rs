@@ -1,8 +1,21 @@
+use super::ChunkMetadata;
 use crate::vector_db::core::{ChunkId, FileMetadata, Result, VectorDbError};
+use crate::vector_db::lexical::{LexicalIndex, LEXICAL_FORMAT_VERSION};
+use hnsw_rs::prelude::*;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// Basename of the HNSW dump (`graph.hnsw.graph` and `graph.hnsw.data`)
+const GRAPH_BASENAME: &str = "graph";
+const GRAPH_SUFFIXES: [&str; 2] = ["hnsw.graph", "hnsw.data"];
+/// [`GraphMeta`] and a digest of the dump files. Written last; a graph without it is
+/// incomplete and ignored
+const GRAPH_META_FILE: &str = "graph.meta";
+const GRAPH_FORMAT_VERSION: u32 = 2;
+/// BM25 keyword index
+const LEXICAL_INDEX_FILE: &str = "lexical.bin";
+
 /// Vector data for a single file
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct FileVectors {
@@ -10,6 +23,47 @@ pub struct FileVectors {
     pub chunks: HashMap<ChunkId, Vec<f32>>,
 }
 
+/// What the points of a persisted HNSW graph are and what they were built from.
+/// `ids`, `metas` and `deleted` are indexed by HNSW data id.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct GraphMeta {
+    pub format_version: u32,
+    pub embedding_model: String,
+    pub dimension: usize,
+    /// [`IndexManifest::chunk_signature`](super::IndexManifest::chunk_signature) the graph
+    /// was last synced with
+    pub manifest_signature: String,
+    pub ids: Vec<ChunkId>,
+    pub metas: Vec<ChunkMetadata>,
+    /// Points whose chunk is gone from the manifest. HNSW can't remove points, so these
+    /// stay in the graph and are filtered out of search results.
+    pub deleted: Vec<bool>,
+}
+
+impl GraphMeta {
+    pub fn new(embedding_model: String, dimension: usize) -> Self {
+        Self {
+            format_version: GRAPH_FORMAT_VERSION,
+            embedding_model,
+            dimension,
+            manifest_signature: String::new(),
+            ids: Vec::new(),
+            metas: Vec::new(),
+            deleted: Vec::new(),
+        }
+    }
+
+    pub fn deleted_count(&self) -> usize {
+        self.deleted.iter().filter(|deleted| **deleted).count()
+    }
+}
+
+/// HNSW graph over a workspace's chunk vectors, as stored next to the manifest
+pub struct StoredGraph {
+    pub hnsw: Hnsw<'static, f32, DistCosine>,
+    pub meta: GraphMeta,
+}
+
 /// File system storage manager
 pub struct FileStore {
     /// Index root directory
@@ -155,6 +209,157 @@ impl FileStore {
         Ok(metadata)
     }
 
+    /// Persist an HNSW graph, replacing the previous one.
+    pub fn save_graph(&self, graph: &StoredGraph) -> Result<()> {
+        // Drop the marker first so a crash mid-save leaves no graph rather than a mismatched one
+        let meta_path = self.root_path.join(GRAPH_META_FILE);
+        if meta_path.exists() {
+            fs::remove_file(&meta_path)?;
+        }
+
+        // hnsw_rs never overwrites a dump of a reloaded graph and picks a fresh basename
+        // instead, so dump under a scratch name and move the files into place.
+        let dumped = graph
+            .hnsw
+            .file_dump(&self.root_path, "graph-pending")
+            .map_err(|e| VectorDbError::Index(format!("failed to dump HNSW graph: {e}")))?;
+        for suffix in GRAPH_SUFFIXES {
+            fs::rename(
+                self.root_path.join(format!("{dumped}.{suffix}")),
+                self.root_path.join(format!("{GRAPH_BASENAME}.{suffix}")),
+            )?;
+        }
+
+        let digest = self.graph_digest()?;
+        let pending_meta = self.root_path.join(format!("{GRAPH_META_FILE}.tmp"));
+        fs::write(&pending_meta, bincode::serialize(&(&graph.meta, &digest))?)?;
+        fs::rename(pending_meta, meta_path)?;
+        Ok(())
+    }
+
+    /// Load the persisted HNSW graph. `None` when there is none or it can't be used.
+    pub fn load_graph(&self) -> Result<Option<StoredGraph>> {
+        let meta_path = self.root_path.join(GRAPH_META_FILE);
+        if !meta_path.exists() {
+            return Ok(None);
+        }
+        let (meta, digest): (GraphMeta, String) = match bincode::deserialize(&fs::read(&meta_path)?)
+        {
+            Ok(stored) => stored,
+            Err(err) => {
+                tracing::warn!("Ignoring unreadable HNSW graph metadata: {}", err);
+                return Ok(None);
+            }
+        };
+        if meta.format_version != GRAPH_FORMAT_VERSION
+            || meta.metas.len() != meta.ids.len()
+            || meta.deleted.len() != meta.ids.len()
+        {
+            return Ok(None);
+        }
+
+        // hnsw_rs asserts on malformed dumps instead of returning an error, and release builds
+        // abort on panic, so only hand it the exact files `save_graph` wrote
+        if !self.graph_matches(&meta, &digest)? {
+            tracing::warn!("Ignoring HNSW graph that doesn't match its metadata");
+            return Ok(None);
+        }
+
+        let options = ReloadOptions::default();
+        if options.use_mmap().0 {
+            return Err(VectorDbError::Index(
+                "HNSW graph reload must not memory-map its data".to_string(),
+            ));
+        }
+        let mut io = HnswIo::new_with_options(&self.root_path, GRAPH_BASENAME, options);
+        let hnsw = match io.load_hnsw::<f32, DistCosine>() {
+            // SAFETY: in hnsw_rs 0.3.3 (pinned in Cargo.toml for this) a reloaded graph only
+            // borrows from `io` when its data is memory-mapped. `options` has mmap off, so
+            // every point is read into an owned vector and the graph holds no reference
+            // into `io`.
+            Ok(hnsw) => unsafe {
+                std::mem::transmute::<Hnsw<'_, f32, DistCosine>, Hnsw<'static, f32, DistCosine>>(
+                    hnsw,
+                )
+            },
+            Err(err) => {
+                tracing::warn!("Ignoring unreadable HNSW graph: {}", err);
+                return Ok(None);
+            }
+        };
+        if hnsw.get_nb_point() != meta.ids.len() {
+            tracing::warn!(
+                "Ignoring HNSW graph with {} points for {} recorded chunks",
+                hnsw.get_nb_point(),
+                meta.ids.len()
+            );
+            return Ok(None);
+        }
+        Ok(Some(StoredGraph { hnsw, meta }))
+    }
+
+    /// blake3 of the dump files, in [`GRAPH_SUFFIXES`] order
+    fn graph_digest(&self) -> Result<String> {
+        let mut hasher = blake3::Hasher::new();
+        for suffix in GRAPH_SUFFIXES {
+            let path = self.root_path.join(format!("{GRAPH_BASENAME}.{suffix}"));
+            std::io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+        }
+        Ok(hasher.finalize().to_hex().to_string())
+    }
+
+    /// Whether the dump on disk is the one `meta` was saved with
+    fn graph_matches(&self, meta: &GraphMeta, digest: &str) -> Result<bool> {
+        let graph_path = self
+            .root_path
+            .join(format!("{GRAPH_BASENAME}.{}", GRAPH_SUFFIXES[0]));
+        let Ok(file) = fs::File::open(graph_path) else {
+            return Ok(false);
+        };
+        let dimension = match load_description(&mut std::io::BufReader::new(file)) {
+            Ok(description) => description.get_dimension(),
+            Err(_) => return Ok(false),
+        };
+        if dimension != meta.dimension {
+            return Ok(false);
+        }
+        match self.graph_digest() {
+            Ok(actual) => Ok(actual == digest),
+            Err(VectorDbError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn lexical_index_path(&self) -> PathBuf {
+        self.root_path.join(LEXICAL_INDEX_FILE)
+    }
+
+    /// Persist the keyword index, replacing the previous one.
+    pub fn save_lexical_index(&self, index: &LexicalIndex) -> Result<()> {
+        fs::create_dir_all(&self.root_path)?;
+        let pending = self.root_path.join(format!("{LEXICAL_INDEX_FILE}.tmp"));
+        fs::write(&pending, bincode::serialize(index)?)?;
+        fs::rename(pending, self.lexical_index_path())?;
+        Ok(())
+    }
+
+    /// Load the keyword index. `None` when there is none or it was written by another
+    /// format version.
+    pub fn load_lexical_index(&self) -> Result<Option<LexicalIndex>> {
+        let path = self.lexical_index_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        match bincode::deserialize::<LexicalIndex>(&fs::read(&path)?) {
+            Ok(index) if index.format_version == LEXICAL_FORMAT_VERSION => Ok(Some(index)),
+            Ok(_) => Ok(None),
+            Err(err) => {
+                tracing::warn!("Ignoring unreadable keyword index: {}", err);
+                Ok(None)
+            }
+        }
+    }
+
     /// Clean up expired data
     pub fn cleanup(&self) -> Result<()> {
         // Implement cleanup logic
@@ -1,20 +1,46 @@
 use super::{ChunkMetadata, FileStore, IndexManifest};
 use crate::vector_db::chunking::TextChunker;
-use crate::vector_db::core::{Chunk, Result, VectorDbConfig, VectorDbError};
+use crate::vector_db::core::{Chunk, ChunkId, Result, VectorDbConfig, VectorDbError};
 use crate::vector_db::embedding::Embedder;
+use crate::vector_db::lexical::LexicalIndex;
 use crate::vector_db::utils::blake3_hash_bytes;
 use parking_lot::RwLock;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+
+/// Chunks sent to the embedder per request
+const EMBED_BATCH_SIZE: usize = 64;
 
 #[derive(Debug, Clone, Copy)]
 pub struct IndexFileOutcome {
     pub indexed_chunks: usize,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReindexOutcome {
+    pub indexed_files: usize,
+    pub indexed_chunks: usize,
+    pub unchanged_files: usize,
+    pub removed_files: usize,
+    pub failed_files: usize,
+}
+
+/// A file read for indexing
+struct SourceFile {
+    path: PathBuf,
+    content: String,
+    hash: String,
+    last_modified: u64,
+    size: u64,
+}
+
+/// Maintains a workspace's vector store and its keyword index. Without an embedder only
+/// the keyword index is updated.
 pub struct IndexManager {
     pub(crate) store: Arc<FileStore>,
     pub(crate) manifest: Arc<RwLock<IndexManifest>>,
+    /// Loaded on first use; searches and status queries don't need it
+    lexical: OnceLock<RwLock<LexicalIndex>>,
     pub(crate) config: VectorDbConfig,
 }
 
@@ -36,6 +62,7 @@ impl IndexManager {
         Ok(Self {
             store,
             manifest: Arc::new(RwLock::new(manifest)),
+            lexical: OnceLock::new(),
             config,
         })
     }
@@ -49,31 +76,261 @@ impl IndexManager {
         manifest.save(&self.manifest_path())
     }
 
+    /// Persist the keyword index. [`Self::index_file_with_progress`] leaves this to the
+    /// caller so a build writes it once.
+    pub fn save_lexical_index(&self) -> Result<()> {
+        self.store.save_lexical_index(&self.lexical()?.read())
+    }
+
+    fn lexical(&self) -> Result<&RwLock<LexicalIndex>> {
+        if let Some(lexical) = self.lexical.get() {
+            return Ok(lexical);
+        }
+        let loaded = match self.store.load_lexical_index()? {
+            Some(index) => index,
+            None => self.build_lexical_from_manifest(),
+        };
+        Ok(self.lexical.get_or_init(|| RwLock::new(loaded)))
+    }
+
+    /// Keyword index of every file with vectors, for workspaces indexed before keyword
+    /// indexes existed or whose keyword index is gone. Starting those from an empty index
+    /// would save one holding only the files changed since.
+    fn build_lexical_from_manifest(&self) -> LexicalIndex {
+        let files: Vec<PathBuf> = self.manifest.read().files.keys().cloned().collect();
+        let mut index = LexicalIndex::default();
+        for file_path in files {
+            let source = match self.read_source(&file_path) {
+                Ok(Some(source)) => source,
+                Ok(None) => continue,
+                Err(err) => {
+                    tracing::debug!(
+                        "Skipping '{}' in the keyword index: {}",
+                        file_path.display(),
+                        err
+                    );
+                    continue;
+                }
+            };
+            match self.chunk(&source) {
+                Ok(chunks) => index.replace_file(&source.path, &source.hash, &chunks),
+                Err(err) => tracing::warn!("Failed to chunk '{}': {}", file_path.display(), err),
+            }
+        }
+        index
+    }
+
     pub async fn index_file_with_progress<F>(
         &self,
         file_path: &Path,
-        embedder: &dyn Embedder,
+        embedder: Option<&dyn Embedder>,
         mut on_progress: F,
     ) -> Result<IndexFileOutcome>
     where
         F: FnMut(usize, usize) + Send,
     {
-        // 0. Limit: size
+        // 1. Read content; oversized and non-UTF-8 files are skipped
+        let Some(source) = self.read_source(file_path)? else {
+            return Ok(IndexFileOutcome { indexed_chunks: 0 });
+        };
+
+        // 2. Chunking
+        let chunks = self.chunk(&source)?;
+        if chunks.is_empty() {
+            if self.forget_file(file_path)? {
+                self.save_manifest()?;
+            }
+            return Ok(IndexFileOutcome { indexed_chunks: 0 });
+        }
+
+        // 3. Generate embeddings (batch + progress)
+        let embeddings = match embedder {
+            Some(embedder) => {
+                let texts: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
+                Some(self.embed_texts(&texts, embedder, &mut on_progress).await?)
+            }
+            None => None,
+        };
+
+        // 4. Write index and manifest
+        self.lexical()?
+            .write()
+            .replace_file(&source.path, &source.hash, &chunks);
+        if let Some(embeddings) = embeddings {
+            self.store_file(&source, &chunks, embeddings)?;
+            self.save_manifest()?;
+        }
+
+        Ok(IndexFileOutcome {
+            indexed_chunks: chunks.len(),
+        })
+    }
+
+    /// Re-index files whose content changed since they were indexed.
+    ///
+    /// Chunks of all changed files are embedded together, so many small edits cost a few
+    /// embedding requests instead of one per file. Nothing is written when embedding
+    /// fails. Files that can no longer be indexed are removed from the index. Without an
+    /// embedder only the keyword index is updated.
+    pub async fn reindex_files(
+        &self,
+        files: &[PathBuf],
+        embedder: Option<&dyn Embedder>,
+    ) -> Result<ReindexOutcome> {
+        let mut outcome = ReindexOutcome::default();
+        // Source, chunks and whether its vectors need updating
+        let mut pending: Vec<(SourceFile, Vec<Chunk>, bool)> = Vec::new();
+        let mut dropped: Vec<&Path> = Vec::new();
+
+        for file_path in files {
+            let source = match self.read_source(file_path) {
+                Ok(Some(source)) => source,
+                Ok(None) => {
+                    dropped.push(file_path);
+                    continue;
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to read '{}' for indexing: {}",
+                        file_path.display(),
+                        err
+                    );
+                    outcome.failed_files += 1;
+                    continue;
+                }
+            };
+            let vectors_stale = embedder.is_some()
+                && self.manifest.read().files.get(file_path) != Some(&source.hash);
+            let keywords_stale =
+                self.lexical()?.read().file_hash(file_path) != Some(source.hash.as_str());
+            if !vectors_stale && !keywords_stale {
+                outcome.unchanged_files += 1;
+                continue;
+            }
+            match self.chunk(&source) {
+                Ok(chunks) if chunks.is_empty() => dropped.push(file_path),
+                Ok(chunks) => pending.push((source, chunks, vectors_stale)),
+                Err(err) => {
+                    tracing::warn!("Failed to chunk '{}': {}", file_path.display(), err);
+                    outcome.failed_files += 1;
+                }
+            }
+        }
+
+        let mut embeddings = match embedder {
+            Some(embedder) => {
+                let texts: Vec<&str> = pending
+                    .iter()
+                    .filter(|(_, _, vectors_stale)| *vectors_stale)
+                    .flat_map(|(_, chunks, _)| chunks.iter().map(|c| c.content.as_str()))
+                    .collect();
+                self.embed_texts(&texts, embedder, |_, _| {}).await?
+            }
+            None => Vec::new(),
+        }
+        .into_iter();
+
+        let mut manifest_changed = false;
+        for (source, chunks, vectors_stale) in &pending {
+            self.lexical()?
+                .write()
+                .replace_file(&source.path, &source.hash, chunks);
+            outcome.indexed_files += 1;
+            outcome.indexed_chunks += chunks.len();
+            if !vectors_stale {
+                continue;
+            }
+
+            manifest_changed = true;
+            let file_embeddings: Vec<Vec<f32>> = embeddings.by_ref().take(chunks.len()).collect();
+            if let Err(err) = self.store_file(source, chunks, file_embeddings) {
+                tracing::warn!(
+                    "Failed to store vectors for '{}': {}",
+                    source.path.display(),
+                    err
+                );
+                self.forget_vectors(&source.path);
+                outcome.indexed_files -= 1;
+                outcome.indexed_chunks -= chunks.len();
+                outcome.failed_files += 1;
+            }
+        }
+        for file_path in dropped {
+            let indexed = self.manifest.read().files.contains_key(file_path)
+                || self.lexical()?.read().contains_file(file_path);
+            if indexed {
+                manifest_changed |= self.forget_file(file_path)?;
+                outcome.removed_files += 1;
+            }
+        }
+
+        if manifest_changed {
+            self.save_manifest()?;
+        }
+        if outcome.indexed_files > 0 || outcome.removed_files > 0 || outcome.failed_files > 0 {
+            self.save_lexical_index()?;
+        }
+        Ok(outcome)
+    }
+
+    pub fn remove_file(&self, file_path: &Path) -> Result<()> {
+        if self.forget_file(file_path)? {
+            self.save_manifest()?;
+        }
+        self.save_lexical_index()
+    }
+
+    /// Remove indexed files at or below each of `paths`, so deleting a directory drops
+    /// everything that was indexed inside it. Returns the number of files removed.
+    pub fn remove_paths(&self, paths: &[PathBuf]) -> Result<usize> {
+        let under_removed = |file: &&PathBuf| paths.iter().any(|path| file.starts_with(path));
+        let mut indexed: Vec<PathBuf> = self
+            .manifest
+            .read()
+            .files
+            .keys()
+            .filter(under_removed)
+            .cloned()
+            .collect();
+        indexed.extend(
+            self.lexical()?
+                .read()
+                .file_paths()
+                .filter(under_removed)
+                .cloned(),
+        );
+        indexed.sort();
+        indexed.dedup();
+        if indexed.is_empty() {
+            return Ok(0);
+        }
+
+        let mut manifest_changed = false;
+        for file_path in &indexed {
+            manifest_changed |= self.forget_file(file_path)?;
+        }
+        if manifest_changed {
+            self.save_manifest()?;
+        }
+        self.save_lexical_index()?;
+        Ok(indexed.len())
+    }
+
+    fn read_source(&self, file_path: &Path) -> Result<Option<SourceFile>> {
+        // Limit: size
         let meta = std::fs::metadata(file_path).map_err(VectorDbError::Io)?;
         if meta.len() > self.config.max_file_size {
-            return Ok(IndexFileOutcome { indexed_chunks: 0 }); // Skip oversized files
+            return Ok(None); // Skip oversized files
         }
 
-        // 1. Read content
         let content = match std::fs::read(file_path) {
             Ok(bytes) => match String::from_utf8(bytes) {
                 Ok(s) => s,
-                Err(_) => return Ok(IndexFileOutcome { indexed_chunks: 0 }), // Skip non-UTF-8 files
+                Err(_) => return Ok(None), // Skip non-UTF-8 files
             },
             Err(e) => return Err(VectorDbError::Io(e)),
         };
-        let file_hash = blake3_hash_bytes(content.as_bytes());
-        let _language = crate::vector_db::core::Language::from_path(file_path);
+        let hash = blake3_hash_bytes(content.as_bytes());
         let last_modified = match meta.modified() {
             Ok(modified) => match modified.duration_since(std::time::UNIX_EPOCH) {
                 Ok(duration) => duration.as_secs(),
@@ -96,57 +353,41 @@ impl IndexManager {
             }
         };
 
-        // 2. Clean old chunks (if they exist)
-        {
-            let guard = self.manifest.read();
-            let existing_ids: Vec<_> = guard
-                .get_file_chunks(file_path)
-                .into_iter()
-                .map(|(id, _)| id)
-                .collect();
-            drop(guard);
-            if !existing_ids.is_empty() {
-                // Delete vector file for this file
-                if let Err(err) = self.store.delete_file_vectors(file_path) {
-                    tracing::warn!(
-                        "Failed to delete stale vectors for '{}': {}",
-                        file_path.display(),
-                        err
-                    );
-                }
-                let mut manifest = self.manifest.write();
-                for chunk_id in existing_ids {
-                    manifest.remove_chunk(&chunk_id);
-                }
-                manifest.remove_file(file_path);
-            }
-        }
+        Ok(Some(SourceFile {
+            path: file_path.to_path_buf(),
+            content,
+            hash,
+            last_modified,
+            size: meta.len(),
+        }))
+    }
 
-        // 3. Chunking
+    fn chunk(&self, source: &SourceFile) -> Result<Vec<Chunk>> {
         let chunker = TextChunker::new(self.config.embedding.chunk_size);
-        let chunks: Vec<Chunk> = chunker.chunk(&content, file_path)?;
-
-        if chunks.is_empty() {
-            return Ok(IndexFileOutcome { indexed_chunks: 0 });
-        }
-
-        // 4. Generate embeddings (batch + progress)
-        const EMBED_BATCH_SIZE: usize = 64;
-        let total_chunks = chunks.len();
-        let mut embeddings: Vec<Vec<f32>> = Vec::with_capacity(total_chunks);
-        let mut done_chunks = 0usize;
-        on_progress(0, total_chunks);
+        chunker.chunk(&source.content, &source.path)
+    }
 
-        while done_chunks < total_chunks {
-            let end = (done_chunks + EMBED_BATCH_SIZE).min(total_chunks);
-            let texts: Vec<&str> = chunks[done_chunks..end]
-                .iter()
-                .map(|c| c.content.as_str())
-                .collect();
+    async fn embed_texts<F>(
+        &self,
+        texts: &[&str],
+        embedder: &dyn Embedder,
+        mut on_progress: F,
+    ) -> Result<Vec<Vec<f32>>>
+    where
+        F: FnMut(usize, usize) + Send,
+    {
+        let total = texts.len();
+        let mut embeddings: Vec<Vec<f32>> = Vec::with_capacity(total);
+        on_progress(0, total);
 
-            let mut batch = embedder.embed(&texts).await?;
-            if batch.is_empty() {
-                return Err(VectorDbError::Embedding("No embeddings returned".into()));
+        for batch_texts in texts.chunks(EMBED_BATCH_SIZE) {
+            let mut batch = embedder.embed(batch_texts).await?;
+            if batch.len() != batch_texts.len() {
+                return Err(VectorDbError::Embedding(format!(
+                    "Expected {} embeddings, got {}",
+                    batch_texts.len(),
+                    batch.len()
+                )));
             }
 
             let actual_dim = batch[0].len();
@@ -163,53 +404,63 @@ impl IndexManager {
             }
 
             embeddings.append(&mut batch);
-            done_chunks = embeddings.len();
-            on_progress(done_chunks, total_chunks);
+            on_progress(embeddings.len(), total);
         }
+        Ok(embeddings)
+    }
+
+    /// Replace the indexed chunks of a file. The caller saves the manifest.
+    fn store_file(
+        &self,
+        source: &SourceFile,
+        chunks: &[Chunk],
+        embeddings: Vec<Vec<f32>>,
+    ) -> Result<()> {
+        self.forget_vectors(&source.path);
 
-        // 5. Write index and manifest
-        let mut file_vectors: Vec<(crate::vector_db::core::ChunkId, Vec<f32>)> =
-            Vec::with_capacity(total_chunks);
+        let mut file_vectors: Vec<(ChunkId, Vec<f32>)> = Vec::with_capacity(chunks.len());
         {
             let mut manifest = self.manifest.write();
-            manifest.add_file(file_path.to_path_buf(), file_hash);
-            for (chunk, vecf) in chunks.iter().zip(embeddings.into_iter()) {
-                let chunk_hash = blake3_hash_bytes(chunk.content.as_bytes());
+            manifest.add_file(source.path.clone(), source.hash.clone());
+            for (chunk, vecf) in chunks.iter().zip(embeddings) {
                 let metadata = ChunkMetadata {
-                    file_path: file_path.to_path_buf(),
+                    file_path: source.path.clone(),
                     span: chunk.span.clone(),
                     chunk_type: chunk.chunk_type.clone(),
-                    hash: chunk_hash,
+                    hash: blake3_hash_bytes(chunk.content.as_bytes()),
                 };
-                // Collect vector data
                 file_vectors.push((chunk.id, vecf));
-                // add to manifest
                 manifest.add_chunk(chunk.id, metadata);
             }
         }
 
         // Save all vectors for this file at once
-        self.store.save_file_vectors(file_path, &file_vectors)?;
+        self.store.save_file_vectors(&source.path, &file_vectors)?;
 
-        // 6. Save file metadata
         let file_meta = crate::vector_db::core::FileMetadata::new(
-            file_path.to_path_buf(),
-            blake3_hash_bytes(content.as_bytes()),
-            last_modified,
-            meta.len(),
+            source.path.clone(),
+            source.hash.clone(),
+            source.last_modified,
+            source.size,
         );
         self.store.save_file_metadata(&file_meta)?;
+        Ok(())
+    }
 
-        // 7. Save manifest
-        self.save_manifest()?;
-
-        Ok(IndexFileOutcome {
-            indexed_chunks: total_chunks,
-        })
+    /// Drop a file's vectors, manifest entries and keywords. Returns whether the manifest
+    /// changed; the caller saves it and the keyword index.
+    fn forget_file(&self, file_path: &Path) -> Result<bool> {
+        self.lexical()?.write().remove_file(file_path);
+        Ok(self.forget_vectors(file_path))
     }
 
-    pub fn remove_file(&self, file_path: &Path) -> Result<()> {
-        // Delete vector file for this file
+    /// Drop a file's vectors and manifest entries. Returns whether the manifest changed.
+    fn forget_vectors(&self, file_path: &Path) -> bool {
+        let mut manifest = self.manifest.write();
+        if !manifest.files.contains_key(file_path) && manifest.get_file_chunks(file_path).is_empty()
+        {
+            return false;
+        }
         if let Err(err) = self.store.delete_file_vectors(file_path) {
             tracing::warn!(
                 "Failed to delete vectors for '{}' while removing index entry: {}",
@@ -217,20 +468,8 @@ impl IndexManager {
                 err
             );
         }
-
-        let mut manifest = self.manifest.write();
-        let chunk_ids: Vec<_> = manifest
-            .get_file_chunks(file_path)
-            .into_iter()
-            .map(|(id, _)| id)
-            .collect();
-        for id in chunk_ids {
-            manifest.remove_chunk(&id);
-        }
         manifest.remove_file(file_path);
-        drop(manifest);
-        self.save_manifest()?;
-        Ok(())
+        true
     }
 
     pub fn get_status(&self) -> IndexStatus {
@@ -240,12 +479,26 @@ impl IndexManager {
             total_chunks: manifest.chunks.len(),
             embedding_model: manifest.embedding_model.clone(),
             vector_dimension: manifest.vector_dimension,
+            keyword_files: 0,
+            keyword_chunks: 0,
             size_bytes: 0,
         }
     }
 
+    /// Status including disk usage and keyword index counts, which need the keyword
+    /// index loaded
     pub fn get_status_with_size_bytes(&self) -> IndexStatus {
         let mut status = self.get_status();
+        match self.lexical() {
+            Ok(lexical) => {
+                let lexical = lexical.read();
+                status.keyword_files = lexical.file_count();
+                status.keyword_chunks = lexical.chunk_count();
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load keyword index: {}", e);
+            }
+        }
         match self.store.disk_usage_bytes() {
             Ok(bytes) => status.size_bytes = bytes,
             Err(e) => {
@@ -278,5 +531,154 @@ pub struct IndexStatus {
     pub total_chunks: usize,
     pub embedding_model: String,
     pub vector_dimension: usize,
+    pub keyword_files: usize,
+    pub keyword_chunks: usize,
     pub size_bytes: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    struct FakeEmbedder;
+
+    #[async_trait]
+    impl Embedder for FakeEmbedder {
+        fn id(&self) -> &str {
+            "fake"
+        }
+
+        fn dim(&self) -> usize {
+            4
+        }
+
+        fn model_name(&self) -> &str {
+            "test-model"
+        }
+
+        async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+            Ok(texts
+                .iter()
+                .map(|text| vec![text.len() as f32, 1.0, 0.0, 0.5])
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn reindex_and_remove_keep_vectors_and_keywords_in_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let mut config = VectorDbConfig::default();
+        config.embedding.model_name = "test-model".to_string();
+        config.embedding.dimension = 4;
+
+        let kept = root.join("kept.rs");
+        let edited = root.join("edited.rs");
+        let deleted = root.join("deleted.rs");
+        for (path, name) in [(&kept, "kept"), (&edited, "edited"), (&deleted, "deleted")] {
+            std::fs::write(path, format!("fn {name}() {{}}\n")).unwrap();
+        }
+
+        let manager = IndexManager::new(root, config.clone()).unwrap();
+        let files = [kept.clone(), edited.clone(), deleted.clone()];
+        let outcome = manager
+            .reindex_files(&files, Some(&FakeEmbedder))
+            .await
+            .unwrap();
+        assert_eq!(outcome.indexed_files, 3);
+        let old_hash = manager.manifest.read().files[&edited].clone();
+        let old_chunks: Vec<ChunkId> = manager
+            .manifest
+            .read()
+            .get_file_chunks(&edited)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+
+        std::fs::write(&edited, "fn edited() {\n    let renamed = 1;\n}\n").unwrap();
+        std::fs::remove_file(&deleted).unwrap();
+        assert_eq!(
+            manager
+                .remove_paths(std::slice::from_ref(&deleted))
+                .unwrap(),
+            1
+        );
+        let outcome = manager
+            .reindex_files(&[kept.clone(), edited.clone()], Some(&FakeEmbedder))
+            .await
+            .unwrap();
+        assert_eq!(outcome.indexed_files, 1);
+        assert_eq!(outcome.unchanged_files, 1);
+
+        // Everything was persisted
+        let reloaded = IndexManager::new(root, config).unwrap();
+        let manifest = reloaded.manifest.read();
+        assert_eq!(manifest.files.len(), 2);
+        assert!(!manifest.files.contains_key(&deleted));
+        assert!(manifest.get_file_chunks(&deleted).is_empty());
+        assert_ne!(manifest.files[&edited], old_hash);
+
+        let new_chunks: Vec<ChunkId> = manifest
+            .get_file_chunks(&edited)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert!(!new_chunks.is_empty());
+        assert!(new_chunks.iter().all(|id| !old_chunks.contains(id)));
+        let vectors = reloaded.store.load_file_vectors(&edited).unwrap();
+        assert_eq!(vectors.chunks.len(), new_chunks.len());
+        assert!(new_chunks.iter().all(|id| vectors.chunks.contains_key(id)));
+        assert!(reloaded.store.load_file_vectors(&deleted).is_err());
+
+        let lexical = reloaded.lexical().unwrap().read();
+        assert!(!lexical.contains_file(&deleted));
+        assert!(lexical.contains_file(&kept));
+        assert_eq!(
+            lexical.file_hash(&edited),
+            Some(manifest.files[&edited].as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn vector_only_index_gets_a_complete_keyword_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let mut config = VectorDbConfig::default();
+        config.embedding.model_name = "test-model".to_string();
+        config.embedding.dimension = 4;
+
+        let files: Vec<PathBuf> = ["a", "b", "c"]
+            .iter()
+            .map(|name| {
+                let path = root.join(format!("{name}.rs"));
+                std::fs::write(&path, format!("fn {name}() {{}}\n")).unwrap();
+                path
+            })
+            .collect();
+        let manager = IndexManager::new(root, config.clone()).unwrap();
+        manager
+            .reindex_files(&files, Some(&FakeEmbedder))
+            .await
+            .unwrap();
+        // An index built before keyword indexes existed has vectors only
+        std::fs::remove_file(manager.store.lexical_index_path()).unwrap();
+        drop(manager);
+
+        std::fs::write(&files[0], "fn a() {\n    let edited = 1;\n}\n").unwrap();
+        let manager = IndexManager::new(root, config).unwrap();
+        let outcome = manager
+            .reindex_files(&files[..1], Some(&FakeEmbedder))
+            .await
+            .unwrap();
+        assert_eq!(outcome.indexed_files, 1);
+
+        let saved = manager.store.load_lexical_index().unwrap().unwrap();
+        assert_eq!(saved.file_count(), 3);
+        assert!(files.iter().all(|file| saved.contains_file(file)));
+        assert_eq!(
+            saved.file_hash(&files[0]),
+            Some(manager.manifest.read().files[&files[0]].as_str())
+        );
+    }
+}
@@ -112,6 +112,18 @@ impl IndexManifest {
         self.updated_at = Self::current_timestamp();
     }
 
+    /// Identifies the set of indexed chunks. Chunk ids are fresh on every indexing run,
+    /// so re-indexing or removing any file changes it.
+    pub fn chunk_signature(&self) -> String {
+        let mut ids: Vec<&ChunkId> = self.chunks.keys().collect();
+        ids.sort_unstable();
+        let mut hasher = blake3::Hasher::new();
+        for id in ids {
+            hasher.update(id.as_bytes());
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+
     /// Get all chunks for a file
     pub fn get_file_chunks(&self, file_path: &Path) -> Vec<(ChunkId, &ChunkMetadata)> {
         self.chunks
@@ -1,6 +1,20 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use ignore::{DirEntry, WalkBuilder};
 use std::path::{Path, PathBuf};
 
+// Common excludes
+const EXCLUDED_DIRS: &[&str] = &[
+    ".git",
+    ".svn",
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    ".idea",
+    ".vscode",
+    ".DS_Store",
+];
+
 pub fn collect_source_files(root: &Path, max_size: u64) -> Vec<PathBuf> {
     let mut files = Vec::new();
     let mut builder = WalkBuilder::new(root);
@@ -38,19 +52,61 @@ fn filter_dirs(e: &DirEntry) -> bool {
         return true;
     }
     if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
-        // Common excludes
-        const EXCLUDES: &[&str] = &[
-            ".git",
-            ".svn",
-            "node_modules",
-            "target",
-            "dist",
-            "build",
-            ".idea",
-            ".vscode",
-            ".DS_Store",
-        ];
-        return !EXCLUDES.contains(&name);
+        return !EXCLUDED_DIRS.contains(&name);
     }
     true
 }
+
+/// Decides for single paths whether [`collect_source_files`] would pick them up. Only the
+/// workspace's top-level `.gitignore` and `.git/info/exclude` are consulted.
+/// Applies the rules of [`collect_source_files`] to single paths, e.g. from watcher events.
+/// Only the workspace's root ignore files are read.
+pub struct SourceFileFilter {
+    root: PathBuf,
+    gitignore: Gitignore,
+}
+
+impl SourceFileFilter {
+    pub fn new(root: &Path) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        for ignore_file in [root.join(".gitignore"), root.join(".git/info/exclude")] {
+            if ignore_file.is_file() {
+                if let Some(err) = builder.add(&ignore_file) {
+                    tracing::warn!("Failed to read '{}': {}", ignore_file.display(), err);
+                }
+            }
+        }
+        let gitignore = builder.build().unwrap_or_else(|err| {
+            tracing::warn!(
+                "Failed to build ignore rules for '{}': {}",
+                root.display(),
+                err
+            );
+            Gitignore::empty()
+        });
+        Self {
+            root: root.to_path_buf(),
+            gitignore,
+        }
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let is_dir = path.is_dir();
+        let mut components = relative.components().peekable();
+        while let Some(component) = components.next() {
+            let name = component.as_os_str().to_string_lossy();
+            let dir = is_dir || components.peek().is_some();
+            // Hidden entries are skipped by the walker as well
+            if name.starts_with('.') || (dir && EXCLUDED_DIRS.contains(&name.as_ref())) {
+                return false;
+            }
+        }
+        !self
+            .gitignore
+            .matched_path_or_any_parents(path, is_dir)
+            .is_ignore()
+    }
+}
@@ -680,6 +680,9 @@ async fn delete_session_cascade(
     }
 
     for id in delete_order {
+        if let Ok(Some(session)) = persistence.sessions().get(id).await {
+            crate::agent::worktree::remove_for_deleted_session(&session).await;
+        }
         persistence
             .sessions()
             .delete(id)
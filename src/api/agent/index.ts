@@ -46,9 +46,13 @@ export class AgentApi {
     await invoke('agent_cancel_task', { taskId, reason })
   }
 
-  confirmTool = async (requestId: string, decision: 'allow_once' | 'allow_always' | 'deny'): Promise<void> => {
+  confirmTool = async (
+    requestId: string,
+    decision: 'allow_once' | 'allow_always' | 'deny',
+    content?: Record<string, unknown>
+  ): Promise<void> => {
     await invoke('agent_tool_confirm', {
-      params: { requestId, decision },
+      params: { requestId, decision, content },
     })
   }
 
@@ -194,7 +194,12 @@ export const isTaskProgressEvent = (event: unknown): event is TaskProgressPayloa
  * Determine if it is a terminal event
  */
 export const isTerminalEvent = (event: TaskProgressPayload): boolean => {
-  return event.type === 'task_completed' || event.type === 'task_cancelled' || event.type === 'task_error'
+  return (
+    event.type === 'task_completed' ||
+    event.type === 'task_cancelled' ||
+    event.type === 'task_error' ||
+    event.type === 'task_budget_exceeded'
+  )
 }
 
 /**
@@ -23,7 +23,12 @@ class AgentChannelApi {
             return false
           }
           if (!rootTaskId) return false
-          if (event.type === 'task_completed' || event.type === 'task_cancelled' || event.type === 'task_error') {
+          if (
+            event.type === 'task_completed' ||
+            event.type === 'task_cancelled' ||
+            event.type === 'task_error' ||
+            event.type === 'task_budget_exceeded'
+          ) {
             return event.taskId === rootTaskId
           }
           return false
@@ -1,6 +1,8 @@
 import { invoke } from '@/utils/request'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 import type { McpServerConfig } from '@/api/settings'
-import type { McpServerStatus, McpTestResult } from './types'
+import type { OAuthFlowInfo } from '@/types/oauth'
+import type { McpServerStatus, McpServerStatusEvent, McpTestResult } from './types'
 
 export class McpApi {
   listServers = async (workspace?: string): Promise<McpServerStatus[]> => {
@@ -14,6 +16,23 @@ export class McpApi {
   reloadServers = async (workspace?: string): Promise<McpServerStatus[]> => {
     return await invoke<McpServerStatus[]>('reload_mcp_servers', { workspace: workspace ?? null })
   }
+
+  startOAuth = async (name: string, workspace?: string): Promise<OAuthFlowInfo> => {
+    return await invoke<OAuthFlowInfo>('start_mcp_oauth', { name, workspace: workspace ?? null })
+  }
+
+  finishOAuth = async (flowId: string, workspace?: string): Promise<McpServerStatus[]> => {
+    return await invoke<McpServerStatus[]>('finish_mcp_oauth', { flowId, workspace: workspace ?? null })
+  }
+
+  cancelOAuth = async (flowId: string): Promise<void> => {
+    await invoke('cancel_mcp_oauth', { flowId })
+  }
+
+  // Status changes from the backend health supervisor (crash, restart, give up)
+  onStatusChanged = async (callback: (payload: McpServerStatusEvent) => void): Promise<UnlistenFn> => {
+    return listen<McpServerStatusEvent>('mcp:server-status', event => callback(event.payload))
+  }
 }
 
 export const mcpApi = new McpApi()
@@ -3,10 +3,12 @@ export interface McpToolInfo {
   description?: string
 }
 
+export type McpConnectionStatus = 'connected' | 'disconnected' | 'error' | 'needs_auth' | 'reconnecting'
+
 export interface McpServerStatus {
   name: string
   source: 'global' | 'workspace'
-  status: 'connected' | 'disconnected' | 'error'
+  status: McpConnectionStatus
   tools: McpToolInfo[]
   error?: string | null
 }
@@ -16,3 +18,10 @@ export interface McpTestResult {
   toolsCount: number
   error?: string | null
 }
+
+export interface McpServerStatusEvent {
+  workspace: string
+  name: string
+  status: McpConnectionStatus
+  error?: string | null
+}
@@ -31,11 +31,64 @@ export interface RulesConfig {
   rulesFiles?: string[]
 }
 
+export interface SandboxConfig {
+  enabled: boolean
+  allowNetwork: boolean
+  writablePaths: string[]
+}
+
 export interface AgentConfigPatch {
   maxIterations?: number | null
   maxTokenBudget?: number | null
+  maxCostBudget?: number | null
   thinkingEnabled?: boolean | null
   autoSummaryThreshold?: number | null
+  sandbox?: Partial<SandboxConfig> | null
+}
+
+export interface HookConfig {
+  matcher?: string | null
+  command: string
+  timeout?: number | null
+}
+
+export interface HooksConfig {
+  preToolUse?: HookConfig[]
+  postToolUse?: HookConfig[]
+  userPromptSubmit?: HookConfig[]
+  sessionStart?: HookConfig[]
+  stop?: HookConfig[]
+}
+
+export interface LspServerSettings {
+  command?: string | null
+  args?: string[] | null
+  filePatterns?: string[] | null
+  rootMarkers?: string[] | null
+  initializationOptions?: unknown
+  env?: Record<string, string> | null
+  languageId?: string | null
+  disabled?: boolean | null
+}
+
+export interface LspSettings {
+  servers: Record<string, LspServerSettings>
+}
+
+export interface RerankerSettings {
+  type: 'crossEncoder' | 'llm'
+  modelId?: string | null
+  model?: string | null
+}
+
+export interface EmbeddingSettings {
+  backend?: 'ollama' | 'tei' | null
+  url?: string | null
+  model?: string | null
+  dimension?: number | null
+  batchSize?: number | null
+  maxConcurrency?: number | null
+  reranker?: RerankerSettings | null
 }
 
 export interface Settings {
@@ -44,6 +97,9 @@ export interface Settings {
   mcpServers: Record<string, McpServerConfig>
   rules: RulesConfig
   agent: AgentConfigPatch
+  hooks?: HooksConfig
+  lsp?: LspSettings
+  embedding?: EmbeddingSettings
 }
 
 export interface EffectiveSettings {
@@ -53,7 +109,11 @@ export interface EffectiveSettings {
   agent: {
     maxIterations: number
     maxTokenBudget: number
+    maxCostBudget?: number | null
     thinkingEnabled: boolean
     autoSummaryThreshold: number
+    sandbox: SandboxConfig
   }
+  hooks: HooksConfig
+  lsp: LspSettings
 }
@@ -9,6 +9,8 @@ export interface VectorIndexStatus {
   sizeBytes?: number
   totalFiles: number
   totalChunks: number
+  keywordFiles: number
+  keywordChunks: number
   model: string
   dim: number
 }
@@ -46,15 +48,19 @@ export class VectorDbApi {
       totalChunks: number
       embeddingModel: string
       vectorDimension: number
+      keywordFiles: number
+      keywordChunks: number
       sizeBytes: number
     }>('get_index_status', { path: params.path })
     return {
-      isReady: raw.totalChunks > 0,
+      isReady: raw.totalChunks > 0 || raw.keywordChunks > 0,
       path: params.path,
       sizeBytes: raw.sizeBytes,
       size: formatBytes(raw.sizeBytes),
       totalFiles: raw.totalFiles,
       totalChunks: raw.totalChunks,
+      keywordFiles: raw.keywordFiles,
+      keywordChunks: raw.keywordChunks,
       model: raw.embeddingModel,
       dim: raw.vectorDimension,
     }
@@ -4,7 +4,7 @@ import type { ExecutionNodeRecord } from '@/api/workspace'
 import { useAISettingsStore } from '@/components/settings/components/AI'
 import type { ImageAttachment } from '@/stores/imageLightbox'
 import { useLayoutStore } from '@/stores/layout'
-import { useToolConfirmationDialogStore } from '@/stores/toolConfirmationDialog'
+import { useToolConfirmationDialogStore, type ToolConfirmationFormSchema } from '@/stores/toolConfirmationDialog'
 import { useWorkspaceStore } from '@/stores/workspace'
 import type { RetryStatus } from '@/types'
 import type { Block } from '@/types/domain/aiMessage'
@@ -298,6 +298,7 @@ export const useAIChatStore = defineStore('ai-chat', () => {
           workspacePath: event.workspacePath,
           toolName: event.toolName,
           summary: event.summary,
+          form: event.form as ToolConfirmationFormSchema | undefined,
         })
         break
       case 'task_completed':
@@ -312,6 +313,7 @@ export const useAIChatStore = defineStore('ai-chat', () => {
         }
         break
       case 'task_cancelled':
+      case 'task_budget_exceeded':
       case 'task_error':
         retryStatus.value = null
         if (currentWorkspacePath.value) {
@@ -1,11 +1,26 @@
 import { defineStore } from 'pinia'
 import { ref } from 'vue'
 
+export interface ToolConfirmationFormField {
+  type?: string
+  title?: string
+  description?: string
+  enum?: string[]
+  default?: unknown
+}
+
+/** Flat JSON schema an MCP server sends with `elicitation/create` */
+export interface ToolConfirmationFormSchema {
+  properties?: Record<string, ToolConfirmationFormField>
+  required?: string[]
+}
+
 export interface ToolConfirmationDialogState {
   requestId: string
   workspacePath: string
   toolName: string
   summary: string
+  form?: ToolConfirmationFormSchema
 }
 
 export type ToolConfirmationDecision = 'allow_once' | 'allow_always' | 'deny'
@@ -15,9 +30,16 @@ export const useToolConfirmationDialogStore = defineStore('toolConfirmationDialo
   const submitting = ref(false)
   const remember = ref(false)
   const state = ref<ToolConfirmationDialogState | null>(null)
+  const formValues = ref<Record<string, unknown>>({})
 
   const open = (data: ToolConfirmationDialogState) => {
     state.value = data
+    formValues.value = Object.fromEntries(
+      Object.entries(data.form?.properties ?? {}).map(([key, field]) => [
+        key,
+        field.default ?? (field.type === 'boolean' ? false : ''),
+      ])
+    )
     remember.value = false
     submitting.value = false
     visible.value = true
@@ -28,6 +50,7 @@ export const useToolConfirmationDialogStore = defineStore('toolConfirmationDialo
     submitting.value = false
     remember.value = false
     state.value = null
+    formValues.value = {}
   }
 
   return {
@@ -35,6 +58,7 @@ export const useToolConfirmationDialogStore = defineStore('toolConfirmationDialo
     submitting,
     remember,
     state,
+    formValues,
     open,
     close,
   }
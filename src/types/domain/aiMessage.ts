@@ -89,6 +89,8 @@ export type TaskEvent =
       workspacePath: string
       toolName: string
       summary: string
+      /** JSON schema of the form to fill in, when an MCP server asks for input */
+      form?: Record<string, unknown>
     }
   | {
       type: 'message_finished'
@@ -103,6 +105,15 @@ export type TaskEvent =
   | { type: 'task_completed'; taskId: string }
   | { type: 'task_error'; taskId: string; error: { code: string; message: string; details?: string } }
   | { type: 'task_cancelled'; taskId: string }
+  | {
+      type: 'task_budget_exceeded'
+      taskId: string
+      usedTokens: number
+      maxTokens?: number
+      /** USD */
+      usedCost: number
+      maxCost?: number
+    }
   | {
       type: 'task_retrying'
       taskId: string
@@ -112,3 +123,21 @@ export type TaskEvent =
       errorMessage: string
       retryInMs: number
     }
+  | {
+      type: 'mcp_progress'
+      taskId: string
+      toolName: string
+      server: string
+      progress: number
+      total?: number
+      message?: string
+    }
+  | {
+      type: 'mcp_log'
+      taskId: string
+      toolName: string
+      server: string
+      level: string
+      logger?: string
+      data: unknown
+    }